@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// gRPC service definitions, compiled with the `grpc` feature only
+const CONTROL_PROTO: &str = "proto/recorder_control.proto";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Compile protobuf definitions if proto files exist
     let proto_files = std::fs::read_dir("proto");
@@ -20,6 +23,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .filter_map(|e| e.ok())
             .filter(|e| e.path().extension().is_some_and(|ext| ext == "proto"))
             .map(|e| e.path())
+            .filter(|path| path != std::path::Path::new(CONTROL_PROTO))
             .collect();
 
         if !proto_files.is_empty() {
@@ -27,5 +31,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    #[cfg(feature = "grpc")]
+    // Maps encode in key order, so signed messages encode the same on both ends
+    tonic_prost_build::configure()
+        .build_client(true)
+        .btree_map(".")
+        .compile_protos(&[CONTROL_PROTO], &["proto"])?;
+
     Ok(())
 }
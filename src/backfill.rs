@@ -0,0 +1,430 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Import of existing logs as a recording (`zenoh-recorder import`)
+//
+// MCAP files, and rosbag2 directories stored as MCAP, are read message by
+// message and written through the storage backend like a live recording of a
+// new recording_id: the messages of each topic are batched up to
+// `batch_bytes` of payload, serialized and compressed in the recorder's format
+// and written under the topic's entry, labeled with the recording, the topic,
+// their checksum and the file they were read from. The metadata record is
+// written last, with the checksum of every record, so an import cut short
+// leaves no recording behind.
+//
+// A message keeps its log time as record timestamp and its publish time as
+// source timestamp; a batch is stored at the log time of its first message,
+// moved past the previous record of its entry on collisions. Channel topics
+// become key expressions without their leading `/`, and the channel's
+// message encoding and schema are stored as the message's schema metadata.
+// rosbag2 directories in the sqlite3 format have to be converted first
+// (`ros2 bag convert`).
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, info};
+
+use crate::config::{RecorderConfig, SchemaConfig};
+use crate::mcap_writer::McapSerializer;
+use crate::proto::{RecordedMessage, SampleInfo, SchemaInfo};
+use crate::protocol::{
+    compression_ratio, CompressionLevel, CompressionType, RecordChecksum, RecordingMetadata,
+};
+use crate::record_timestamps::{BumpStrategy, RecordTimestamps};
+use crate::recorder::RecorderManager;
+use crate::runtime::fs;
+use crate::storage::{
+    checksum, chunk_checksums, topic_to_entry_name, StorageBackend, CHECKSUM_CHUNK_BYTES,
+    CHECKSUM_LABEL,
+};
+
+/// Metadata file of a rosbag2 directory
+const ROSBAG2_METADATA: &str = "metadata.yaml";
+
+/// Priority stored for imported messages (Zenoh's default, `data`)
+const DEFAULT_PRIORITY: u32 = 5;
+
+/// How files are imported
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// ID of the new recording (a new UUID when None)
+    pub recording_id: Option<String>,
+    pub device_id: String,
+    pub scene: Option<String>,
+    /// Labels of every record, like the labels of a Start request
+    pub labels: HashMap<String, String>,
+    pub compression_type: CompressionType,
+    pub compression_level: CompressionLevel,
+    /// Payload bytes of a topic stored per record
+    pub batch_bytes: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            recording_id: None,
+            device_id: "recorder-001".to_string(),
+            scene: None,
+            labels: HashMap::new(),
+            compression_type: CompressionType::Zstd,
+            compression_level: CompressionLevel::Default,
+            batch_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Device, default compression and flush size of the recorder
+    pub fn from_config(config: &RecorderConfig) -> Self {
+        let compression = &config.recorder.compression;
+        Self {
+            device_id: config.recorder.device_id.clone(),
+            compression_type: CompressionType::from_name(&compression.default_type)
+                .unwrap_or_default(),
+            compression_level: CompressionLevel::from_index(compression.default_level)
+                .unwrap_or_default(),
+            batch_bytes: config.recorder.flush_policy.max_buffer_size_bytes,
+            ..Default::default()
+        }
+    }
+}
+
+/// What an import wrote
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub recording_id: String,
+    /// MCAP files read
+    pub files: usize,
+    pub messages: u64,
+    pub records: usize,
+    /// Bytes of the records written
+    pub bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct Rosbag2Metadata {
+    rosbag2_bagfile_information: Rosbag2Info,
+}
+
+#[derive(Deserialize)]
+struct Rosbag2Info {
+    storage_identifier: String,
+    #[serde(default)]
+    relative_file_paths: Vec<String>,
+}
+
+/// MCAP files of `path`: the file itself, or the files of a rosbag2
+/// directory
+pub fn source_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let metadata_path = path.join(ROSBAG2_METADATA);
+    let metadata = std::fs::read_to_string(&metadata_path).with_context(|| {
+        format!(
+            "'{}' is a directory without a rosbag2 {}",
+            path.display(),
+            ROSBAG2_METADATA
+        )
+    })?;
+    let info = serde_yaml::from_str::<Rosbag2Metadata>(&metadata)
+        .with_context(|| format!("Failed to parse {}", metadata_path.display()))?
+        .rosbag2_bagfile_information;
+    if info.storage_identifier != "mcap" {
+        bail!(
+            "'{}' is stored as {}, only MCAP bags can be imported (convert it with `ros2 bag convert`)",
+            path.display(),
+            info.storage_identifier
+        );
+    }
+    Ok(info
+        .relative_file_paths
+        .iter()
+        .map(|file| path.join(file))
+        .collect())
+}
+
+/// Messages of a topic waiting for their record
+#[derive(Default)]
+struct PendingBatch {
+    messages: Vec<RecordedMessage>,
+    payload_bytes: usize,
+}
+
+/// Samples and bytes of a topic, as in the metadata's `per_topic_stats`
+#[derive(Default)]
+struct TopicTotals {
+    samples: u64,
+    raw_bytes: u64,
+    stored_bytes: u64,
+}
+
+/// Import state of a recording
+struct Importer<'a> {
+    storage: &'a dyn StorageBackend,
+    options: &'a ImportOptions,
+    recording_id: String,
+    serializer: McapSerializer,
+    timestamps: RecordTimestamps,
+    pending: BTreeMap<String, PendingBatch>,
+    totals: BTreeMap<String, TopicTotals>,
+    records: Vec<RecordChecksum>,
+    /// Log times of the first and last messages (ns)
+    time_range: Option<(u64, u64)>,
+    summary: ImportSummary,
+}
+
+impl Importer<'_> {
+    fn push(&mut self, message: &mcap::Message) -> Option<String> {
+        let topic = message.channel.topic.trim_start_matches('/').to_string();
+        let schema = message.channel.schema.as_ref();
+        let recorded = RecordedMessage {
+            topic: topic.clone(),
+            timestamp_ns: message.log_time as i64,
+            payload: message.data.to_vec(),
+            schema: Some(SchemaInfo {
+                format: message.channel.message_encoding.clone(),
+                schema_name: schema.map(|s| s.name.clone()).unwrap_or_default(),
+                schema_hash: String::new(),
+                schema_data: schema.map(|s| s.data.to_vec()).unwrap_or_default(),
+            }),
+            sample: Some(SampleInfo {
+                encoding: message.channel.message_encoding.clone(),
+                kind: "put".to_string(),
+                source_timestamp_ns: message.publish_time as i64,
+                source_id: String::new(),
+                received_ns: message.log_time as i64,
+                congestion_control: "drop".to_string(),
+                priority: DEFAULT_PRIORITY,
+                express: false,
+                key_expr: topic.clone(),
+                liveliness: false,
+            }),
+            transform: String::new(),
+            keyframe: false,
+            attachment: Vec::new(),
+        };
+
+        self.time_range = Some(match self.time_range {
+            Some((first, last)) => (first.min(message.log_time), last.max(message.log_time)),
+            None => (message.log_time, message.log_time),
+        });
+        self.summary.messages += 1;
+        let totals = self.totals.entry(topic.clone()).or_default();
+        totals.samples += 1;
+        totals.raw_bytes += recorded.payload.len() as u64;
+
+        let batch = self.pending.entry(topic.clone()).or_default();
+        batch.payload_bytes += recorded.payload.len();
+        batch.messages.push(recorded);
+        (batch.payload_bytes >= self.options.batch_bytes).then_some(topic)
+    }
+
+    /// Write the pending messages of `topic` as a record
+    async fn write_batch(&mut self, topic: &str, source: &str) -> Result<()> {
+        let Some(batch) = self.pending.remove(topic) else {
+            return Ok(());
+        };
+        let Some(first_ns) = batch.messages.iter().map(|m| m.timestamp_ns).min() else {
+            return Ok(());
+        };
+        let data = self
+            .serializer
+            .serialize_messages(topic, batch.messages, &self.recording_id)?;
+
+        let entry = topic_to_entry_name(topic);
+        let allocation = self.timestamps.allocate(&entry, first_ns as u64 / 1000);
+        let mut labels = self.options.labels.clone();
+        labels.extend(allocation.label);
+        labels.insert("recording_id".to_string(), self.recording_id.clone());
+        labels.insert("topic".to_string(), topic.to_string());
+        labels.insert("format".to_string(), "mcap".to_string());
+        labels.insert("imported_from".to_string(), source.to_string());
+        let crc32c = checksum(&data);
+        labels.insert(CHECKSUM_LABEL.to_string(), crc32c.clone());
+        let chunks = Some(chunk_checksums(&data, CHECKSUM_CHUNK_BYTES));
+
+        let bytes = data.len();
+        self.storage
+            .write_with_retry(&entry, allocation.timestamp_us, data, labels, 3)
+            .await
+            .with_context(|| format!("Failed to write a record of '{}'", topic))?;
+        debug!(
+            "Imported a record of '{}' at {} ({} bytes)",
+            topic, allocation.timestamp_us, bytes
+        );
+
+        self.totals
+            .entry(topic.to_string())
+            .or_default()
+            .stored_bytes += bytes as u64;
+        self.records.push(RecordChecksum {
+            topic: topic.to_string(),
+            entry,
+            timestamp_us: allocation.timestamp_us,
+            bytes,
+            crc32c,
+            coalesced: None,
+            chunks,
+        });
+        self.summary.records += 1;
+        self.summary.bytes += bytes as u64;
+        Ok(())
+    }
+
+    /// Write the pending messages of every topic
+    async fn write_all(&mut self, source: &str) -> Result<()> {
+        let topics: Vec<String> = self.pending.keys().cloned().collect();
+        for topic in topics {
+            self.write_batch(&topic, source).await?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self, first_ns: u64, last_ns: u64) -> RecordingMetadata {
+        let per_topic_stats = self
+            .totals
+            .iter()
+            .map(|(topic, totals)| {
+                (
+                    topic.clone(),
+                    serde_json::json!({
+                        "samples": totals.samples,
+                        "raw_bytes": totals.raw_bytes,
+                        "stored_bytes": totals.stored_bytes,
+                        "compression_ratio": compression_ratio(totals.raw_bytes, totals.stored_bytes),
+                    }),
+                )
+            })
+            .collect();
+        let payload_bytes = self.totals.values().map(|totals| totals.raw_bytes).sum();
+        let time = |ns: u64| chrono::DateTime::from_timestamp_nanos(ns as i64).to_rfc3339();
+        RecordingMetadata {
+            recording_id: self.recording_id.clone(),
+            scene: self.options.scene.clone(),
+            skills: vec![],
+            organization: None,
+            task_id: None,
+            device_id: self.options.device_id.clone(),
+            data_collector_id: None,
+            topics: self.totals.keys().cloned().collect(),
+            compression_type: format!("{:?}", self.options.compression_type),
+            compression_level: self.options.compression_level as i32,
+            start_time: time(first_ns),
+            end_time: Some(time(last_ns)),
+            total_bytes: self.summary.bytes as i64,
+            total_samples: self.summary.messages as i64,
+            payload_bytes,
+            compression_ratio: compression_ratio(payload_bytes, self.summary.bytes),
+            per_topic_stats: serde_json::Value::Object(per_topic_stats),
+            interrupted: false,
+            schema_drift: vec![],
+            handoff: None,
+            previous_recording_id: None,
+            group_id: None,
+            topic_changes: vec![],
+            session_gaps: vec![],
+            records: self.records.clone(),
+            verify_failures: 0,
+            records_verified: 0,
+            bucket: self.storage.bucket().map(str::to_string),
+            controller_liveliness: None,
+            priority: None,
+            topic_aliases: BTreeMap::new(),
+            previews: BTreeMap::new(),
+            exclude_topics: vec![],
+            buffer_strategy: None,
+            retention_seconds: None,
+            expires_at: None,
+            formats: BTreeMap::new(),
+            topology: None,
+            environment: BTreeMap::new(),
+            labels: self.options.labels.clone(),
+        }
+    }
+}
+
+/// Import the MCAP files and rosbag2 directories of `paths` as one
+/// recording, in order
+pub async fn import(
+    storage: &dyn StorageBackend,
+    paths: &[PathBuf],
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    let recording_id = options
+        .recording_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let mut importer = Importer {
+        storage,
+        options,
+        recording_id: recording_id.clone(),
+        serializer: McapSerializer::with_schema_config(
+            options.compression_type,
+            options.compression_level,
+            SchemaConfig::default(),
+        ),
+        timestamps: RecordTimestamps::new(Box::new(BumpStrategy)),
+        pending: BTreeMap::new(),
+        totals: BTreeMap::new(),
+        records: Vec::new(),
+        time_range: None,
+        summary: ImportSummary {
+            recording_id,
+            ..Default::default()
+        },
+    };
+
+    for path in paths {
+        for file in source_files(path)? {
+            let source = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let data = fs::read(&file)
+                .await
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let stream = mcap::MessageStream::new(&data)
+                .with_context(|| format!("{} is not an MCAP file", file.display()))?;
+            for message in stream {
+                let message =
+                    message.with_context(|| format!("Failed to read {}", file.display()))?;
+                if let Some(topic) = importer.push(&message) {
+                    importer.write_batch(&topic, &source).await?;
+                }
+            }
+            // Records don't span files, so each names the file it came from
+            importer.write_all(&source).await?;
+            importer.summary.files += 1;
+            info!("Imported {}", file.display());
+        }
+    }
+
+    let Some((first_ns, last_ns)) = importer.time_range else {
+        bail!("No messages to import");
+    };
+    let metadata = importer.metadata(first_ns, last_ns);
+    RecorderManager::write_metadata_record(
+        storage,
+        &importer.timestamps,
+        &metadata,
+        SystemTime::now(),
+    )
+    .await
+    .context("Failed to write the recording metadata")?;
+    Ok(importer.summary)
+}
@@ -0,0 +1,141 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Sparse index records of stored batches
+//
+// With `index.enabled`, every batch written to an entry gets a small JSON
+// record at the same timestamp in `{entry}__index`: the first and last message
+// timestamp, the message count, and the offset of every message (of its length
+// prefix) in the decompressed batch. Uncompressed batches can be read at these
+// offsets directly; seekable Zstd batches also list their frames, so a reader
+// fetches and decompresses only the frames it needs. A reader looking for a
+// time range reads the index records first and skips the batches outside of
+// it without reading or decompressing them. The index is written once its
+// batch is stored, so it never points to a missing record.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::seekable::Frame;
+
+/// Suffix of the entry holding the index records of an entry
+pub const INDEX_ENTRY_SUFFIX: &str = "__index";
+
+/// Entry holding the index records of `entry`
+pub fn index_entry(entry: &str) -> String {
+    format!("{}{}", entry, INDEX_ENTRY_SUFFIX)
+}
+
+/// Index of one stored batch
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BatchIndex {
+    /// First and last message timestamp (ns)
+    pub first_ns: i64,
+    pub last_ns: i64,
+    pub samples: usize,
+    /// Offset of every message in the decompressed batch, in batch order
+    pub offsets: Vec<u64>,
+    /// Frames of seekable Zstd batches, the header frame first (empty for
+    /// other batches)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<IndexFrame>,
+}
+
+/// Compressed frame of a seekable Zstd batch
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexFrame {
+    /// Offset in the stored batch
+    pub offset: u64,
+    pub compressed_bytes: u64,
+    pub decompressed_bytes: u64,
+    /// (first, last) message timestamp in ns; None for the header frame
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_range: Option<(i64, i64)>,
+}
+
+impl From<&Frame> for IndexFrame {
+    fn from(frame: &Frame) -> Self {
+        Self {
+            offset: frame.offset as u64,
+            compressed_bytes: frame.compressed_size as u64,
+            decompressed_bytes: frame.decompressed_size as u64,
+            time_range: frame.time_range,
+        }
+    }
+}
+
+impl BatchIndex {
+    /// Index of the messages `(timestamp, encoded length)` written after a
+    /// header of `header_len` bytes, each with a 4 byte length prefix
+    pub fn new(header_len: usize, messages: impl IntoIterator<Item = (i64, usize)>) -> Self {
+        let mut index = Self::default();
+        let mut offset = header_len as u64;
+        for (timestamp_ns, len) in messages {
+            if index.offsets.is_empty() {
+                index.first_ns = timestamp_ns;
+                index.last_ns = timestamp_ns;
+            }
+            index.first_ns = index.first_ns.min(timestamp_ns);
+            index.last_ns = index.last_ns.max(timestamp_ns);
+            index.offsets.push(offset);
+            offset += 4 + len as u64;
+        }
+        index.samples = index.offsets.len();
+        index
+    }
+
+    /// Whether a message of the batch may have a timestamp in
+    /// `start_ns..=end_ns`
+    pub fn overlaps(&self, start_ns: i64, end_ns: i64) -> bool {
+        self.samples > 0 && self.first_ns <= end_ns && self.last_ns >= start_ns
+    }
+
+    /// Labels of the index record, for queries by time range
+    pub fn labels(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("first_ns".to_string(), self.first_ns.to_string()),
+            ("last_ns".to_string(), self.last_ns.to_string()),
+            ("samples".to_string(), self.samples.to_string()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_follow_length_prefixes() {
+        let index = BatchIndex::new(10, [(300, 5), (100, 2), (200, 0)]);
+        assert_eq!(index.offsets, vec![10, 19, 25]);
+        assert_eq!((index.first_ns, index.last_ns), (100, 300));
+        assert_eq!(index.samples, 3);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let index = BatchIndex::new(0, [(100, 1), (200, 1)]);
+        assert!(index.overlaps(0, 100));
+        assert!(index.overlaps(150, 160));
+        assert!(index.overlaps(200, 300));
+        assert!(!index.overlaps(0, 99));
+        assert!(!index.overlaps(201, 300));
+        assert!(!BatchIndex::default().overlaps(i64::MIN, i64::MAX));
+    }
+
+    #[test]
+    fn test_index_entry() {
+        assert_eq!(index_entry("camera_front"), "camera_front__index");
+    }
+}
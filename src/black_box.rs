@@ -0,0 +1,159 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Continuous in-memory capture of the `black_box` topics (`dump` command)
+//
+// Every configured topic joins the subscription hub at startup and keeps its
+// latest samples in a ring bounded by `max_bytes_per_topic` and
+// `max_age_seconds`. Nothing reaches storage until a `dump` request, which
+// copies the rings into a new recording; the rings keep filling meanwhile.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+use zenoh::sample::Sample;
+
+use crate::config::BlackBoxConfig;
+use crate::protocol::TaskStage;
+use crate::subscription_hub::SubscriptionHub;
+use crate::task_registry::TaskRegistry;
+
+/// Latest samples of one topic, with their receive times (ns since the epoch)
+#[derive(Default)]
+struct Ring {
+    samples: VecDeque<(Sample, u64)>,
+    bytes: usize,
+}
+
+impl Ring {
+    /// Drop the samples received before `cutoff_ns`
+    fn expire(&mut self, cutoff_ns: u64) {
+        while self
+            .samples
+            .front()
+            .is_some_and(|(_, received_ns)| *received_ns < cutoff_ns)
+        {
+            self.pop();
+        }
+    }
+
+    fn pop(&mut self) {
+        if let Some((sample, _)) = self.samples.pop_front() {
+            self.bytes -= sample.payload().len();
+        }
+    }
+}
+
+/// Rings of the black box topics
+pub struct BlackBox {
+    /// By configured topic, in the configured order
+    rings: Vec<(String, Arc<Mutex<Ring>>)>,
+    max_age: Option<Duration>,
+}
+
+impl BlackBox {
+    /// Subscribe to the configured topics and fill their rings until the
+    /// tasks are aborted
+    pub fn start(config: &BlackBoxConfig, hub: &SubscriptionHub, tasks: &TaskRegistry) -> Self {
+        let max_bytes = config.max_bytes_per_topic;
+        let max_age = config.max_age();
+        let mut rings = Vec::new();
+        for topic in &config.topics {
+            let ring = Arc::new(Mutex::new(Ring::default()));
+            rings.push((topic.clone(), ring.clone()));
+            let sink = match hub.subscribe(topic) {
+                Ok(sink) => sink,
+                Err(e) => {
+                    error!(
+                        "Failed to subscribe to '{}' for the black box: {:#}",
+                        topic, e
+                    );
+                    continue;
+                }
+            };
+            let topic = topic.clone();
+            tasks.spawn_service(
+                TaskStage::Ingest,
+                format!("black box {}", topic),
+                async move {
+                    loop {
+                        match sink.recv_async().await {
+                            Ok(sample) => {
+                                let received_ns = now_ns();
+                                let mut ring = ring.lock().unwrap();
+                                ring.bytes += sample.payload().len();
+                                ring.samples.push_back((sample, received_ns));
+                                // The latest sample stays even when it's over the limit
+                                while ring.bytes > max_bytes && ring.samples.len() > 1 {
+                                    ring.pop();
+                                }
+                                if let Some(max_age) = max_age {
+                                    ring.expire(
+                                        received_ns.saturating_sub(max_age.as_nanos() as u64),
+                                    );
+                                }
+                            }
+                            Err(e) => {
+                                warn!("Black box stopped receiving '{}': {}", topic, e);
+                                break;
+                            }
+                        }
+                    }
+                },
+            );
+        }
+        if !rings.is_empty() {
+            info!(
+                "Black box keeping the last {} bytes{} of {} topic(s)",
+                max_bytes,
+                max_age
+                    .map(|age| format!(" and {:?}", age))
+                    .unwrap_or_default(),
+                rings.len()
+            );
+        }
+        Self { rings, max_age }
+    }
+
+    /// Configured topics
+    pub fn topics(&self) -> Vec<String> {
+        self.rings.iter().map(|(topic, _)| topic.clone()).collect()
+    }
+
+    /// Copy of the samples of every ring, oldest first, with their receive
+    /// times; the rings are left as they are
+    pub fn contents(&self) -> Vec<(String, Vec<(Sample, u64)>)> {
+        let cutoff_ns = self
+            .max_age
+            .map(|max_age| now_ns().saturating_sub(max_age.as_nanos() as u64));
+        self.rings
+            .iter()
+            .map(|(topic, ring)| {
+                let mut ring = ring.lock().unwrap();
+                if let Some(cutoff_ns) = cutoff_ns {
+                    ring.expire(cutoff_ns);
+                }
+                (topic.clone(), ring.samples.iter().cloned().collect())
+            })
+            .collect()
+    }
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
@@ -12,14 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::Result;
-use crossbeam::queue::ArrayQueue;
+use crate::error::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
-use zenoh::sample::Sample;
+use zenoh::sample::{Sample, SampleKind};
+
+use crate::clock::{system_clock, Clock};
+use crate::config::{
+    DedupePolicy, FlushPriority, MissingTimestampPolicy, TimestampPolicy, TimestampSource,
+};
+use crate::flush_pool::FlushPool;
+use crate::protocol::{FlushCounts, FlushReason};
 
 /// Message to flush buffer
 #[derive(Clone)]
@@ -27,6 +36,112 @@ pub struct FlushTask {
     pub topic: String,
     pub samples: Vec<Sample>,
     pub recording_id: String,
+    /// Resolved record timestamp (ns since epoch) per sample; empty when the
+    /// serializer should derive timestamps from the samples themselves
+    pub timestamps_ns: Vec<u64>,
+    /// Time the recorder received each sample (ns since epoch); empty when
+    /// unknown
+    pub received_ns: Vec<u64>,
+}
+
+impl FlushTask {
+    /// Split into tasks of at most `max_bytes` of payload each (0 = no limit)
+    ///
+    /// Sample order is kept; a single sample above the limit gets a task of
+    /// its own.
+    pub fn split(self, max_bytes: usize) -> Vec<FlushTask> {
+        let total: usize = self.samples.iter().map(|s| s.payload().len()).sum();
+        if max_bytes == 0 || total <= max_bytes {
+            return vec![self];
+        }
+
+        let FlushTask {
+            topic,
+            samples,
+            recording_id,
+            timestamps_ns,
+            received_ns,
+        } = self;
+        let mut timestamps = timestamps_ns.into_iter();
+        let mut received = received_ns.into_iter();
+        let mut parts = Vec::new();
+        let mut current = Vec::new();
+        let mut current_timestamps = Vec::new();
+        let mut current_received = Vec::new();
+        let mut current_bytes = 0;
+
+        for sample in samples {
+            let len = sample.payload().len();
+            if !current.is_empty() && current_bytes + len > max_bytes {
+                parts.push(FlushTask {
+                    topic: topic.clone(),
+                    samples: std::mem::take(&mut current),
+                    recording_id: recording_id.clone(),
+                    timestamps_ns: std::mem::take(&mut current_timestamps),
+                    received_ns: std::mem::take(&mut current_received),
+                });
+                current_bytes = 0;
+            }
+            current_bytes += len;
+            current.push(sample);
+            current_timestamps.extend(timestamps.next());
+            current_received.extend(received.next());
+        }
+        parts.push(FlushTask {
+            topic,
+            samples: current,
+            recording_id,
+            timestamps_ns: current_timestamps,
+            received_ns: current_received,
+        });
+        parts
+    }
+}
+
+/// Skips the samples repeating the last one recorded on their key
+struct Dedupe {
+    /// Repeats are still recorded this long after the last sample recorded
+    heartbeat_ns: Option<u64>,
+    /// Payload hash and receive time of the last sample recorded, by key
+    last: Mutex<HashMap<String, (u64, u64)>>,
+    suppressed: AtomicU64,
+}
+
+impl Dedupe {
+    fn new(policy: &DedupePolicy) -> Self {
+        Self {
+            heartbeat_ns: policy
+                .heartbeat()
+                .map(|interval| interval.as_nanos() as u64),
+            last: Mutex::new(HashMap::new()),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether `sample` repeats the last sample recorded on its key within
+    /// the heartbeat interval; records it as the last one otherwise
+    fn is_repeat(&self, sample: &Sample, received_ns: u64) -> bool {
+        let mut hasher = DefaultHasher::new();
+        matches!(sample.kind(), SampleKind::Delete).hash(&mut hasher);
+        for slice in sample.payload().slices() {
+            hasher.write(slice);
+        }
+        let hash = hasher.finish();
+
+        let mut last = self.last.lock().unwrap();
+        let key = sample.key_expr().as_str();
+        if let Some((last_hash, last_ns)) = last.get(key) {
+            let heartbeat_due = self
+                .heartbeat_ns
+                .is_some_and(|interval| received_ns.saturating_sub(*last_ns) >= interval);
+            if *last_hash == hash && !heartbeat_due {
+                self.suppressed.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+        last.insert(key.to_string(), (hash, received_ns));
+        false
+    }
 }
 
 /// Double-buffered topic buffer with flush policies
@@ -34,22 +149,53 @@ pub struct TopicBuffer {
     topic_name: String,
     recording_id: String,
 
-    // Double buffer
-    front_buffer: Arc<RwLock<Vec<Sample>>>,
-    back_buffer: Arc<RwLock<Vec<Sample>>>,
+    // Double buffer (sample, resolved timestamp, receive time; both in ns)
+    front_buffer: Arc<RwLock<Vec<(Sample, u64, u64)>>>,
+    back_buffer: Arc<RwLock<Vec<(Sample, u64, u64)>>>,
     active_is_front: AtomicBool, // true = front is active, false = back is active
+    capacity: usize,             // pre-allocated sample slots per buffer
 
-    // Flush triggers
-    max_buffer_size: usize,
-    max_buffer_duration: Duration,
-    last_flush_time: AtomicU64,
+    // Flush triggers and idle trimming (changeable by config reloads)
+    max_buffer_size: AtomicUsize,
+    max_buffer_duration_ms: AtomicU64,
+    last_flush_ms: AtomicU64,  // ms since the epoch
+    idle_trim_secs: AtomicU64, // 0 = never trim
 
     // Statistics
     total_samples: AtomicUsize,
     total_bytes: AtomicUsize,
 
-    // Flush queue
-    flush_queue: Arc<ArrayQueue<FlushTask>>,
+    // Samples without publisher timestamp (cumulative)
+    timestamp_policy: TimestampPolicy,
+    missing_timestamps: AtomicU64,
+    rejected_samples: AtomicU64,
+
+    // Samples received, dropped with a full flush queue, and receive time of
+    // the last one in ns (0 = none yet); cumulative
+    received_samples: AtomicU64,
+    dropped_samples: AtomicU64,
+    last_sample_ns: AtomicU64,
+
+    // Samples received as shared-memory payloads, held without a copy
+    shm_samples: AtomicU64,
+
+    // Repeated samples skipped (rate-of-change topics only)
+    dedupe: Option<Dedupe>,
+
+    // Most bytes buffered at once, flushes by reason and the last reason
+    high_watermark_bytes: AtomicU64,
+    flushes: [AtomicU64; 4],
+    last_flush_reason: Mutex<Option<FlushReason>>,
+
+    // Payload bytes accepted and not written yet (shared with the recording)
+    pending_bytes: Option<Arc<AtomicU64>>,
+
+    // Flush queue, and the priority class of the topic's tasks in it
+    flush_queue: Arc<FlushPool>,
+    flush_priority: FlushPriority,
+
+    // Time source of the flush timing and receive times
+    clock: Arc<dyn Clock>,
 }
 
 impl TopicBuffer {
@@ -58,30 +204,225 @@ impl TopicBuffer {
         recording_id: String,
         max_buffer_size: usize,
         max_buffer_duration: Duration,
-        flush_queue: Arc<ArrayQueue<FlushTask>>,
+        flush_queue: Arc<FlushPool>,
     ) -> Self {
+        let clock = system_clock();
         Self {
             topic_name,
             recording_id,
             front_buffer: Arc::new(RwLock::new(Vec::new())),
             back_buffer: Arc::new(RwLock::new(Vec::new())),
             active_is_front: AtomicBool::new(true),
-            max_buffer_size,
-            max_buffer_duration,
-            last_flush_time: AtomicU64::new(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-            ),
+            capacity: 0,
+            max_buffer_size: AtomicUsize::new(max_buffer_size),
+            max_buffer_duration_ms: AtomicU64::new(max_buffer_duration.as_millis() as u64),
+            last_flush_ms: AtomicU64::new(clock.since_epoch().as_millis() as u64),
+            idle_trim_secs: AtomicU64::new(0),
             total_samples: AtomicUsize::new(0),
             total_bytes: AtomicUsize::new(0),
+            timestamp_policy: TimestampPolicy::default(),
+            missing_timestamps: AtomicU64::new(0),
+            rejected_samples: AtomicU64::new(0),
+            received_samples: AtomicU64::new(0),
+            dropped_samples: AtomicU64::new(0),
+            last_sample_ns: AtomicU64::new(0),
+            shm_samples: AtomicU64::new(0),
+            dedupe: None,
+            high_watermark_bytes: AtomicU64::new(0),
+            flushes: Default::default(),
+            last_flush_reason: Mutex::new(None),
+            pending_bytes: None,
             flush_queue,
+            flush_priority: FlushPriority::default(),
+            clock,
         }
     }
 
+    /// Pre-allocate room for `capacity` samples in each buffer
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self.front_buffer = Arc::new(RwLock::new(Vec::with_capacity(capacity)));
+        self.back_buffer = Arc::new(RwLock::new(Vec::with_capacity(capacity)));
+        self
+    }
+
+    /// Set the timestamp source and the policy applied to samples without a
+    /// publisher timestamp
+    pub fn with_timestamp_policy(mut self, policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = policy;
+        self
+    }
+
+    /// Record only the samples that differ from the last one recorded on
+    /// their key, and a repeat every heartbeat interval
+    pub fn with_dedupe(mut self, policy: &DedupePolicy) -> Self {
+        self.dedupe = Some(Dedupe::new(policy));
+        self
+    }
+
+    /// Count accepted payload bytes into `counter`; the flush worker takes
+    /// them off once written
+    pub fn with_pending_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.pending_bytes = Some(counter);
+        self
+    }
+
+    /// Queue the flush tasks in priority class `priority`
+    pub fn with_flush_priority(mut self, priority: FlushPriority) -> Self {
+        self.flush_priority = priority;
+        self
+    }
+
+    /// Read the time from `clock` (the flush timer restarts on it)
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.last_flush_ms = AtomicU64::new(clock.since_epoch().as_millis() as u64);
+        self.clock = clock;
+        self
+    }
+
+    /// Change the flush triggers; samples already buffered count towards them
+    pub fn set_flush_limits(&self, max_buffer_size: usize, max_buffer_duration: Duration) {
+        self.max_buffer_size
+            .store(max_buffer_size, Ordering::Relaxed);
+        self.max_buffer_duration_ms
+            .store(max_buffer_duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Trim the buffers after `idle_after` without samples (zero = never)
+    pub fn set_idle_trim(&self, idle_after: Duration) {
+        self.idle_trim_secs
+            .store(idle_after.as_secs(), Ordering::Relaxed);
+    }
+
+    /// Time without samples after which the buffers are trimmed, if set
+    pub fn idle_trim(&self) -> Option<Duration> {
+        let secs = self.idle_trim_secs.load(Ordering::Relaxed);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+
+    /// Release the capacity the buffers hold beyond their samples
+    ///
+    /// Returns the number of sample slots released. Buffers get their
+    /// pre-allocated capacity back at the next flush after samples return.
+    pub async fn trim(&self) -> usize {
+        let mut released = 0;
+        for buffer in [&self.front_buffer, &self.back_buffer] {
+            let mut buf = buffer.write().await;
+            let before = buf.capacity();
+            buf.shrink_to_fit();
+            released += before - buf.capacity();
+        }
+        if released > 0 {
+            debug!(
+                "Trimmed idle buffers of topic '{}', released {} sample slots",
+                self.topic_name, released
+            );
+        }
+        released
+    }
+
+    /// Sample slots allocated by both buffers (for monitoring and tests)
+    #[allow(dead_code)]
+    pub async fn allocated_slots(&self) -> usize {
+        self.front_buffer.read().await.capacity() + self.back_buffer.read().await.capacity()
+    }
+
+    /// Resolve the record timestamp of a sample
+    ///
+    /// Returns `None` when the sample must be rejected.
+    fn resolve_timestamp(&self, sample: &Sample, receive_ns: u64) -> Option<u64> {
+        match self.timestamp_policy.source {
+            TimestampSource::Sample => {}
+            TimestampSource::ReceiveTime => return Some(receive_ns),
+            TimestampSource::PayloadField => {
+                if let Some(ts) = self.payload_timestamp(sample) {
+                    return Some(ts);
+                }
+            }
+        }
+
+        if let Some(ts) = sample.timestamp() {
+            return Some(ts.get_time().to_duration().as_nanos() as u64);
+        }
+
+        self.missing_timestamps.fetch_add(1, Ordering::Relaxed);
+
+        match self.timestamp_policy.missing {
+            MissingTimestampPolicy::ReceiveTime => Some(receive_ns),
+            MissingTimestampPolicy::Reject => {
+                self.rejected_samples.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            MissingTimestampPolicy::PayloadField => {
+                Some(self.payload_timestamp(sample).unwrap_or_else(|| {
+                    debug!(
+                        "No timestamp field '{}' in payload on topic '{}', using receive time",
+                        self.timestamp_policy.payload_field.as_deref().unwrap_or(""),
+                        self.topic_name
+                    );
+                    receive_ns
+                }))
+            }
+        }
+    }
+
+    /// Timestamp in the payload field of the policy, if the payload has it
+    fn payload_timestamp(&self, sample: &Sample) -> Option<u64> {
+        let field = self.timestamp_policy.payload_field.as_deref().unwrap_or("");
+        payload_timestamp_ns(
+            &sample.payload().to_bytes(),
+            field,
+            self.timestamp_policy.payload_field_unit,
+        )
+    }
+
     /// Push a sample to the active buffer
     pub async fn push_sample(&self, sample: Sample) -> Result<()> {
+        self.push_sample_at(sample, None).await
+    }
+
+    /// Push a sample recorded at `timestamp_ns`, or at the time the
+    /// timestamp policy resolves when None
+    pub async fn push_sample_at(&self, sample: Sample, timestamp_ns: Option<u64>) -> Result<()> {
+        let received_ns = self.clock.since_epoch().as_nanos() as u64;
+        self.push(sample, timestamp_ns, received_ns).await
+    }
+
+    /// Push a sample that was received earlier, at `received_ns`, as if it
+    /// had been pushed then
+    pub async fn push_sample_received(&self, sample: Sample, received_ns: u64) -> Result<()> {
+        self.push(sample, None, received_ns).await
+    }
+
+    async fn push(
+        &self,
+        sample: Sample,
+        timestamp_ns: Option<u64>,
+        received_ns: u64,
+    ) -> Result<()> {
+        self.received_samples.fetch_add(1, Ordering::Relaxed);
+        self.last_sample_ns.store(received_ns, Ordering::Relaxed);
+        #[cfg(feature = "shared-memory")]
+        if sample.payload().as_shm().is_some() {
+            self.shm_samples.fetch_add(1, Ordering::Relaxed);
+        }
+        let Some(timestamp_ns) =
+            timestamp_ns.or_else(|| self.resolve_timestamp(&sample, received_ns))
+        else {
+            debug!(
+                "Rejected sample without timestamp on topic '{}'",
+                self.topic_name
+            );
+            return Ok(());
+        };
+        if self
+            .dedupe
+            .as_ref()
+            .is_some_and(|dedupe| dedupe.is_repeat(&sample, received_ns))
+        {
+            return Ok(());
+        }
+
         let active_is_front = self.active_is_front.load(Ordering::Acquire);
         let buffer = if active_is_front {
             &self.front_buffer
@@ -93,51 +434,86 @@ impl TopicBuffer {
 
         {
             let mut buf = buffer.write().await;
-            buf.push(sample);
+            buf.push((sample, timestamp_ns, received_ns));
         }
 
         self.total_samples.fetch_add(1, Ordering::Relaxed);
-        self.total_bytes.fetch_add(sample_size, Ordering::Relaxed);
+        let buffered = self.total_bytes.fetch_add(sample_size, Ordering::Relaxed) + sample_size;
+        self.high_watermark_bytes
+            .fetch_max(buffered as u64, Ordering::Relaxed);
+        if let Some(pending) = &self.pending_bytes {
+            pending.fetch_add(sample_size as u64, Ordering::Relaxed);
+        }
 
         // Check if we need to flush
-        if self.should_flush() {
-            self.trigger_flush().await;
+        if let Some(reason) = self.should_flush() {
+            self.trigger_flush(reason).await;
         }
 
         Ok(())
     }
 
-    /// Check if buffer should be flushed
-    fn should_flush(&self) -> bool {
+    /// Time of `clock` in ms since the epoch
+    fn epoch_ms(&self) -> u64 {
+        self.clock.since_epoch().as_millis() as u64
+    }
+
+    /// Check if buffer should be flushed, and why
+    fn should_flush(&self) -> Option<FlushReason> {
         let bytes = self.total_bytes.load(Ordering::Relaxed);
-        if bytes >= self.max_buffer_size {
+        if bytes >= self.max_buffer_size.load(Ordering::Relaxed) {
             debug!(
                 "Buffer size threshold reached for topic '{}': {} bytes",
                 self.topic_name, bytes
             );
-            return true;
+            return Some(FlushReason::Size);
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let last_flush = self.last_flush_time.load(Ordering::Relaxed);
-
-        if now - last_flush >= self.max_buffer_duration.as_secs() {
+        let elapsed_ms = self
+            .epoch_ms()
+            .saturating_sub(self.last_flush_ms.load(Ordering::Relaxed));
+        if elapsed_ms >= self.max_buffer_duration_ms.load(Ordering::Relaxed) {
             debug!(
-                "Time threshold reached for topic '{}': {} seconds",
-                self.topic_name,
-                now - last_flush
+                "Time threshold reached for topic '{}': {} ms",
+                self.topic_name, elapsed_ms
             );
-            return true;
+            return Some(FlushReason::Time);
         }
 
-        false
+        None
+    }
+
+    /// Time until the buffered samples are due for a time-based flush
+    /// (None while the buffer is empty)
+    pub fn flush_due_in(&self) -> Option<Duration> {
+        if self.total_samples.load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+        let due_ms = self.last_flush_ms.load(Ordering::Relaxed)
+            + self.max_buffer_duration_ms.load(Ordering::Relaxed);
+        Some(Duration::from_millis(
+            due_ms.saturating_sub(self.epoch_ms()),
+        ))
+    }
+
+    /// Flush the buffered samples if they are due, without waiting for the
+    /// next sample (topics that stopped publishing)
+    ///
+    /// Returns whether a flush was triggered.
+    pub async fn flush_if_due(&self) -> bool {
+        if self.flush_due_in() != Some(Duration::ZERO) {
+            return false;
+        }
+        debug!(
+            "Flushing idle buffer of topic '{}' on its time threshold",
+            self.topic_name
+        );
+        self.trigger_flush(FlushReason::Time).await;
+        true
     }
 
     /// Trigger buffer flush
-    async fn trigger_flush(&self) {
+    async fn trigger_flush(&self, reason: FlushReason) {
         // Swap buffers atomically
         let was_front = self.active_is_front.fetch_xor(true, Ordering::AcqRel);
 
@@ -149,10 +525,18 @@ impl TopicBuffer {
         };
 
         // Extract samples
-        let samples = {
+        let entries = {
             let mut buf = buffer_to_flush.write().await;
-            std::mem::take(&mut *buf)
+            std::mem::replace(&mut *buf, Vec::with_capacity(self.capacity))
         };
+        let mut samples = Vec::with_capacity(entries.len());
+        let mut timestamps_ns = Vec::with_capacity(entries.len());
+        let mut received_ns = Vec::with_capacity(entries.len());
+        for (sample, timestamp, received) in entries {
+            samples.push(sample);
+            timestamps_ns.push(timestamp);
+            received_ns.push(received);
+        }
 
         let sample_count = samples.len();
         let bytes = samples.iter().map(|s| s.payload().len()).sum::<usize>();
@@ -160,17 +544,15 @@ impl TopicBuffer {
         // Reset counters
         self.total_samples.store(0, Ordering::Relaxed);
         self.total_bytes.store(0, Ordering::Relaxed);
-        self.last_flush_time.store(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            Ordering::Relaxed,
-        );
+        self.last_flush_ms.store(self.epoch_ms(), Ordering::Relaxed);
 
+        if sample_count > 0 {
+            self.flushes[reason as usize].fetch_add(1, Ordering::Relaxed);
+            *self.last_flush_reason.lock().unwrap() = Some(reason);
+        }
         debug!(
-            "Flushing {} samples ({} bytes) from topic '{}'",
-            sample_count, bytes, self.topic_name
+            "Flushing {} samples ({} bytes) from topic '{}' ({:?})",
+            sample_count, bytes, self.topic_name, reason
         );
 
         // Send to flush queue
@@ -178,19 +560,32 @@ impl TopicBuffer {
             topic: self.topic_name.clone(),
             samples,
             recording_id: self.recording_id.clone(),
+            timestamps_ns,
+            received_ns,
         };
 
-        if self.flush_queue.push(task).is_err() {
+        if self.flush_queue.push(task, self.flush_priority).is_err() {
             warn!(
                 "Flush queue full for topic '{}', dropping flush task",
                 self.topic_name
             );
+            self.count_dropped(sample_count);
+            if let Some(pending) = &self.pending_bytes {
+                let _ = pending.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |p| {
+                    Some(p.saturating_sub(bytes as u64))
+                });
+            }
         }
     }
 
     /// Force flush remaining data
     pub async fn force_flush(&self) -> Result<()> {
-        self.trigger_flush().await;
+        self.flush(FlushReason::Forced).await
+    }
+
+    /// Flush remaining data, accounted to `reason`
+    pub async fn flush(&self, reason: FlushReason) -> Result<()> {
+        self.trigger_flush(reason).await;
         Ok(())
     }
 
@@ -201,4 +596,123 @@ impl TopicBuffer {
             self.total_bytes.load(Ordering::Relaxed),
         )
     }
+
+    /// Get cumulative (samples without publisher timestamp, rejected samples)
+    pub fn timestamp_stats(&self) -> (u64, u64) {
+        (
+            self.missing_timestamps.load(Ordering::Relaxed),
+            self.rejected_samples.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Get cumulative (samples received, samples dropped with a full flush
+    /// queue, receive time of the last sample in ns)
+    pub fn ingest_stats(&self) -> (u64, u64, Option<u64>) {
+        let last_sample_ns = self.last_sample_ns.load(Ordering::Relaxed);
+        (
+            self.received_samples.load(Ordering::Relaxed),
+            self.dropped_samples.load(Ordering::Relaxed),
+            (last_sample_ns > 0).then_some(last_sample_ns),
+        )
+    }
+
+    /// Count samples dropped after they were flushed (the task was evicted
+    /// from the flush queue)
+    pub fn count_dropped(&self, samples: usize) {
+        self.dropped_samples
+            .fetch_add(samples as u64, Ordering::Relaxed);
+    }
+
+    /// Get cumulative samples received as shared-memory payloads
+    pub fn shm_samples(&self) -> u64 {
+        self.shm_samples.load(Ordering::Relaxed)
+    }
+
+    /// Get the most bytes buffered at once
+    pub fn high_watermark_bytes(&self) -> u64 {
+        self.high_watermark_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Get cumulative flushes by reason, and the reason of the last one
+    pub fn flush_stats(&self) -> (FlushCounts, Option<FlushReason>) {
+        let count = |reason: FlushReason| self.flushes[reason as usize].load(Ordering::Relaxed);
+        (
+            FlushCounts {
+                size: count(FlushReason::Size),
+                time: count(FlushReason::Time),
+                forced: count(FlushReason::Forced),
+                shutdown: count(FlushReason::Shutdown),
+            },
+            *self.last_flush_reason.lock().unwrap(),
+        )
+    }
+
+    /// Get cumulative repeated samples skipped
+    pub fn duplicates_suppressed(&self) -> u64 {
+        self.dedupe
+            .as_ref()
+            .map_or(0, |dedupe| dedupe.suppressed.load(Ordering::Relaxed))
+    }
+
+    /// Name the topic is recorded under
+    pub fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+}
+
+/// Extract a timestamp from a dot-separated JSON payload field
+///
+/// Accepts numeric values and numeric strings expressed in `unit`.
+fn payload_timestamp_ns(
+    payload: &[u8],
+    field: &str,
+    unit: crate::config::TimestampUnit,
+) -> Option<u64> {
+    if field.is_empty() {
+        return None;
+    }
+
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    let value = field
+        .split('.')
+        .try_fold(&value, |current, key| current.get(key))?;
+
+    // Integers are converted exactly; fractional values go through f64
+    let (integer, number) = match value {
+        serde_json::Value::Number(n) => (n.as_u64(), n.as_f64()?),
+        serde_json::Value::String(s) => (s.parse::<u64>().ok(), s.parse::<f64>().ok()?),
+        _ => return None,
+    };
+    match integer {
+        Some(v) => v.checked_mul(unit.nanos_per_unit()),
+        None => (number >= 0.0).then(|| unit.to_nanos(number)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimestampUnit;
+
+    #[test]
+    fn test_payload_timestamp_ns() {
+        let payload = br#"{"header": {"stamp": 1.5}, "ms": "1700000000500"}"#;
+        assert_eq!(
+            payload_timestamp_ns(payload, "header.stamp", TimestampUnit::S),
+            Some(1_500_000_000)
+        );
+        assert_eq!(
+            payload_timestamp_ns(payload, "ms", TimestampUnit::Ms),
+            Some(1_700_000_000_500_000_000)
+        );
+        assert_eq!(
+            payload_timestamp_ns(payload, "missing", TimestampUnit::Ns),
+            None
+        );
+        assert_eq!(
+            payload_timestamp_ns(payload, "header", TimestampUnit::Ns),
+            None
+        );
+        assert_eq!(payload_timestamp_ns(b"raw", "ts", TimestampUnit::Ns), None);
+    }
 }
@@ -0,0 +1,144 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Wall clock of the recorder
+//
+// Flush timing, recording start/end times and retention read the time
+// through a `Clock`, the system clock unless another one is set
+// (`RecorderManager::with_clock`). The manager hands its components a
+// `SharedClock`, so those started before the clock was set follow it too.
+// `MockClock` only moves when told to, so time-based behavior can be tested
+// without sleeping.
+
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    /// Time elapsed since the Unix epoch (zero before it)
+    fn since_epoch(&self) -> Duration {
+        self.now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// The system wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Shared system clock, the default of the components reading the time
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// `time` as an RFC 3339 UTC timestamp
+pub fn rfc3339(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+/// Clock reading another one, which can be replaced while it's shared
+pub struct SharedClock {
+    clock: RwLock<Arc<dyn Clock>>,
+}
+
+impl SharedClock {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock: RwLock::new(clock),
+        }
+    }
+
+    /// Read `clock` from now on
+    pub fn set(&self, clock: Arc<dyn Clock>) {
+        *self.clock.write().unwrap() = clock;
+    }
+}
+
+impl Clock for SharedClock {
+    fn now(&self) -> SystemTime {
+        self.clock.read().unwrap().now()
+    }
+}
+
+/// Clock set and advanced by hand
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<SystemTime>,
+}
+
+#[allow(dead_code)]
+impl MockClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Mutex::new(start),
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_moves_when_told() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = MockClock::new(start);
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(
+            clock.since_epoch(),
+            Duration::from_millis(1_700_000_001_500)
+        );
+
+        clock.set(UNIX_EPOCH - Duration::from_secs(1));
+        assert_eq!(clock.since_epoch(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_shared_clock_follows_replacement() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let shared = SharedClock::new(system_clock());
+        assert!(shared.now() > start);
+
+        let mock = Arc::new(MockClock::new(start));
+        shared.set(mock.clone());
+        assert_eq!(shared.now(), start);
+        mock.advance(Duration::from_secs(1));
+        assert_eq!(shared.now(), start + Duration::from_secs(1));
+        assert_eq!(rfc3339(shared.now()), "2023-11-14T22:13:21+00:00");
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Coalescing of small batches across topics
+//
+// With `coalesce.enabled`, batches smaller than `max_batch_bytes` don't become
+// records of their own. They are collected per recording and stored together
+// as one record of the `__coalesced` entry once `max_record_bytes` are
+// collected, or `interval_ms` after the first of them. The record holds the
+// batches one after another, as they would have been stored, followed by an
+// index of them (topic, entry, timestamp, offset and length of each) and a
+// footer. The recording metadata lists every batch with its place in the
+// coalesced record, so replays, downloads and data queries read it through
+// `read_range` as if it was a record of its own. Coalesced batches get no
+// index record (see `crate::batch_index`); they are small enough to be read
+// whole.
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{RecorderError, Result};
+use crate::protocol::RecordChecksum;
+use crate::storage::StorageBackend;
+
+/// Entry holding the coalesced records
+pub const COALESCED_ENTRY: &str = "__coalesced";
+
+/// Last bytes of a coalesced record, after the index length
+const FOOTER_MAGIC: &[u8; 8] = b"ZRCOALSC";
+
+/// Batch stored in a coalesced record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoalescedPart {
+    pub topic: String,
+    /// Entry and timestamp the batch would have been stored at
+    pub entry: String,
+    pub timestamp_us: u64,
+    /// Range of the batch in the coalesced record
+    pub offset: u64,
+    pub length: u64,
+    pub crc32c: String,
+    /// Payload bytes and samples the batch holds
+    pub raw_bytes: usize,
+    pub samples: usize,
+    pub compressed: bool,
+}
+
+/// Index at the end of a coalesced record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoalescedIndex {
+    pub recording_id: String,
+    pub parts: Vec<CoalescedPart>,
+}
+
+/// Store `batches` one after another, followed by their index
+///
+/// The offset and length of each part are set from its batch.
+pub fn encode(
+    recording_id: &str,
+    batches: impl IntoIterator<Item = (CoalescedPart, Vec<u8>)>,
+) -> Result<(Vec<u8>, Vec<CoalescedPart>)> {
+    let mut data = Vec::new();
+    let mut parts = Vec::new();
+    for (mut part, batch) in batches {
+        part.offset = data.len() as u64;
+        part.length = batch.len() as u64;
+        data.extend_from_slice(&batch);
+        parts.push(part);
+    }
+    let index = CoalescedIndex {
+        recording_id: recording_id.to_string(),
+        parts,
+    };
+    let json = serde_json::to_vec(&index).map_err(RecorderError::serialization)?;
+    data.extend_from_slice(&json);
+    data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    data.extend_from_slice(FOOTER_MAGIC);
+    Ok((data, index.parts))
+}
+
+/// Index of a coalesced record
+pub fn decode(data: &[u8]) -> Result<CoalescedIndex> {
+    decode_index(data).map_err(RecorderError::serialization)
+}
+
+fn decode_index(data: &[u8]) -> anyhow::Result<CoalescedIndex> {
+    let Some(rest) = data.strip_suffix(FOOTER_MAGIC) else {
+        bail!("Not a coalesced record");
+    };
+    let Some(len_at) = rest.len().checked_sub(4) else {
+        bail!("Truncated coalesced record footer");
+    };
+    let len = u32::from_le_bytes(rest[len_at..].try_into().unwrap()) as usize;
+    let Some(index_at) = len_at.checked_sub(len) else {
+        bail!("Truncated coalesced record index");
+    };
+    let index: CoalescedIndex = serde_json::from_slice(&rest[index_at..len_at])
+        .context("Failed to parse the coalesced record index")?;
+    if let Some(part) = index
+        .parts
+        .iter()
+        .find(|part| part.offset + part.length > index_at as u64)
+    {
+        bail!(
+            "Batch {} of '{}' is outside of the coalesced record",
+            part.timestamp_us,
+            part.topic
+        );
+    }
+    Ok(index)
+}
+
+/// Bytes of `part` in the coalesced record `data`
+pub fn part_data<'a>(data: &'a [u8], part: &CoalescedPart) -> &'a [u8] {
+    crate::storage::backend::slice_range(data, part.offset, part.length)
+}
+
+/// Metadata entry of a coalesced batch
+pub fn record_checksum(part: &CoalescedPart, timestamp_us: u64) -> RecordChecksum {
+    RecordChecksum {
+        topic: part.topic.clone(),
+        entry: part.entry.clone(),
+        timestamp_us: part.timestamp_us,
+        bytes: part.length as usize,
+        crc32c: part.crc32c.clone(),
+        coalesced: Some(crate::protocol::CoalescedLocation {
+            timestamp_us,
+            offset: part.offset,
+        }),
+        chunks: None,
+    }
+}
+
+/// Read `length` bytes from `offset` of a recorded batch, wherever it is
+/// stored
+pub async fn read_range(
+    storage: &dyn StorageBackend,
+    record: &RecordChecksum,
+    offset: u64,
+    length: u64,
+) -> Result<Vec<u8>> {
+    match &record.coalesced {
+        Some(location) => {
+            let length = length.min((record.bytes as u64).saturating_sub(offset));
+            storage
+                .read_record_range(
+                    COALESCED_ENTRY,
+                    location.timestamp_us,
+                    location.offset + offset,
+                    length,
+                )
+                .await
+        }
+        None => {
+            storage
+                .read_record_range(&record.entry, record.timestamp_us, offset, length)
+                .await
+        }
+    }
+}
+
+/// Read a recorded batch, wherever it is stored
+pub async fn read(storage: &dyn StorageBackend, record: &RecordChecksum) -> Result<Vec<u8>> {
+    match &record.coalesced {
+        Some(_) => read_range(storage, record, 0, record.bytes as u64).await,
+        None => {
+            storage
+                .read_record(&record.entry, record.timestamp_us)
+                .await
+        }
+    }
+}
+
+/// Batches collected per recording until they are stored together
+pub struct Coalescer<T> {
+    max_batch_bytes: usize,
+    max_record_bytes: usize,
+    interval: Duration,
+    pending: Mutex<HashMap<String, Pending<T>>>,
+}
+
+struct Pending<T> {
+    since: Instant,
+    bytes: usize,
+    batches: Vec<T>,
+}
+
+impl<T> Coalescer<T> {
+    pub fn new(max_batch_bytes: usize, max_record_bytes: usize, interval: Duration) -> Self {
+        Self {
+            max_batch_bytes,
+            max_record_bytes,
+            interval,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a batch of `bytes` is small enough to be coalesced
+    pub fn accepts(&self, bytes: usize) -> bool {
+        bytes < self.max_batch_bytes
+    }
+
+    /// Add a batch of `bytes` to the batches of `recording_id`
+    ///
+    /// Returns them all once they reach `max_record_bytes`.
+    pub fn push(&self, recording_id: &str, bytes: usize, batch: T) -> Option<Vec<T>> {
+        let mut pending = self.pending.lock().unwrap();
+        let collected = pending
+            .entry(recording_id.to_string())
+            .or_insert_with(|| Pending {
+                since: Instant::now(),
+                bytes: 0,
+                batches: Vec::new(),
+            });
+        collected.bytes += bytes;
+        collected.batches.push(batch);
+        if collected.bytes < self.max_record_bytes {
+            return None;
+        }
+        pending.remove(recording_id).map(|p| p.batches)
+    }
+
+    /// Recordings whose first batch waits for `interval` or longer
+    pub fn due(&self) -> Vec<String> {
+        self.pending
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, p)| p.since.elapsed() >= self.interval)
+            .map(|(recording_id, _)| recording_id.clone())
+            .collect()
+    }
+
+    /// Take the batches of `recording_id`
+    pub fn take(&self, recording_id: &str) -> Vec<T> {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(recording_id)
+            .map(|p| p.batches)
+            .unwrap_or_default()
+    }
+
+    /// How long the first batch of a recording waits
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(topic: &str, timestamp_us: u64) -> CoalescedPart {
+        CoalescedPart {
+            topic: topic.to_string(),
+            entry: topic.replace('/', "_"),
+            timestamp_us,
+            offset: 0,
+            length: 0,
+            crc32c: String::new(),
+            raw_bytes: 0,
+            samples: 1,
+            compressed: false,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let (data, parts) = encode(
+            "rec-1",
+            vec![
+                (part("a/one", 10), b"first".to_vec()),
+                (part("b/two", 11), b"second batch".to_vec()),
+            ],
+        )
+        .unwrap();
+        assert_eq!((parts[1].offset, parts[1].length), (5, 12));
+
+        let index = decode(&data).unwrap();
+        assert_eq!(index.recording_id, "rec-1");
+        assert_eq!(index.parts, parts);
+        assert_eq!(part_data(&data, &index.parts[0]), b"first");
+        assert_eq!(part_data(&data, &index.parts[1]), b"second batch");
+    }
+
+    #[test]
+    fn test_decode_rejects_other_records() {
+        assert!(decode(b"ZENOH_MCAP|batch").is_err());
+        let (data, _) = encode("rec-1", vec![(part("a", 1), b"batch".to_vec())]).unwrap();
+        assert!(decode(&data[3..]).is_err());
+    }
+
+    #[test]
+    fn test_coalescer_limits() {
+        let coalescer = Coalescer::new(10, 20, Duration::ZERO);
+        assert!(coalescer.accepts(9));
+        assert!(!coalescer.accepts(10));
+
+        assert_eq!(coalescer.push("rec", 8, 1), None);
+        assert_eq!(coalescer.push("rec", 8, 2), None);
+        assert_eq!(coalescer.push("rec", 8, 3), Some(vec![1, 2, 3]));
+        assert!(coalescer.take("rec").is_empty());
+
+        coalescer.push("rec", 1, 4);
+        coalescer.push("other", 1, 5);
+        let mut due = coalescer.due();
+        due.sort();
+        assert_eq!(due, vec!["other", "rec"]);
+        assert_eq!(coalescer.take("rec"), vec![4]);
+        assert!(coalescer.take("rec").is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// CPU pool for batch serialization and compression
+//
+// Serializing a flush batch into MCAP and compressing it (Zstd at high levels
+// on large batches in particular) can take seconds of CPU time, which would
+// stall the async executor if it ran in a flush worker task. The flush workers
+// hand it to this pool instead: jobs run on the runtime's blocking thread pool
+// (`runtime::unblock`), at most `workers.compression_threads` at a time; the
+// others wait for a slot. Every job carries the `CancelToken` of its
+// recording. A job still waiting when the recording is cancelled is dropped
+// without running, and the result of a job that was running is discarded.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio::sync::{Notify, Semaphore};
+
+use crate::protocol::CompressionPoolStatus;
+use crate::runtime;
+
+/// Cancellation flag of a recording's pending work
+#[derive(Debug, Default)]
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Bounded pool running CPU-heavy jobs off the async executor
+pub struct CompressionPool {
+    slots: Semaphore,
+    threads: usize,
+    queued: AtomicUsize,
+    max_queued: AtomicUsize,
+    running: AtomicUsize,
+    jobs: AtomicU64,
+    cancelled: AtomicU64,
+    busy_us: AtomicU64,
+    started: Instant,
+}
+
+impl CompressionPool {
+    /// Pool running up to `threads` jobs at once (at least one)
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        Self {
+            slots: Semaphore::new(threads),
+            threads,
+            queued: AtomicUsize::new(0),
+            max_queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+            jobs: AtomicU64::new(0),
+            cancelled: AtomicU64::new(0),
+            busy_us: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    /// Run `job` on the pool; None when `cancel` fired before it finished
+    pub async fn run<F, T>(&self, cancel: &CancelToken, job: F) -> Option<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (queued, depth) = Counted::enter(&self.queued);
+        self.max_queued.fetch_max(depth, Ordering::Relaxed);
+        let slot = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            slot = self.slots.acquire() => slot.ok(),
+        };
+        drop(queued);
+        let Some(_slot) = slot else {
+            self.cancelled.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        let (running, _) = Counted::enter(&self.running);
+        let started = Instant::now();
+        // Spans opened by the job nest under the caller's
+        let span = tracing::Span::current();
+        let result = runtime::unblock(move || span.in_scope(job)).await;
+        self.busy_us
+            .fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        drop(running);
+        self.jobs.fetch_add(1, Ordering::Relaxed);
+
+        if cancel.is_cancelled() {
+            self.cancelled.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(result)
+    }
+
+    /// Queue depth and load since the pool was created
+    pub fn stats(&self) -> CompressionPoolStatus {
+        let busy_us = self.busy_us.load(Ordering::Relaxed);
+        let capacity_us = self.started.elapsed().as_micros().max(1) as f64 * self.threads as f64;
+        CompressionPoolStatus {
+            threads: self.threads,
+            running: self.running.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            max_queued: self.max_queued.load(Ordering::Relaxed),
+            jobs: self.jobs.load(Ordering::Relaxed),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+            busy_seconds: busy_us as f64 / 1e6,
+            utilization: (busy_us as f64 / capacity_us).min(1.0),
+        }
+    }
+}
+
+/// Count of queued or running jobs, decremented on drop so a caller dropping
+/// its future while waiting doesn't leave the job counted
+struct Counted<'a>(&'a AtomicUsize);
+
+impl<'a> Counted<'a> {
+    /// Count a job in; returns the count including it
+    fn enter(counter: &'a AtomicUsize) -> (Self, usize) {
+        let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
+        (Self(counter), count)
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_bounded_and_cancelled_while_queued() {
+        let pool = Arc::new(CompressionPool::new(1));
+        let cancel = Arc::new(CancelToken::default());
+        let (release, blocked) = mpsc::channel::<()>();
+
+        // Holds the only slot until released
+        let running = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                pool.run(&CancelToken::default(), move || blocked.recv().is_ok())
+                    .await
+            }
+        });
+        while pool.stats().running == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let waiting = tokio::spawn({
+            let (pool, cancel) = (pool.clone(), cancel.clone());
+            async move { pool.run(&cancel, || 42).await }
+        });
+        while pool.stats().queued == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        cancel.cancel();
+        assert_eq!(waiting.await.unwrap(), None);
+
+        release.send(()).unwrap();
+        assert_eq!(running.await.unwrap(), Some(true));
+
+        let stats = pool.stats();
+        assert_eq!(stats.jobs, 1);
+        assert_eq!(stats.cancelled, 1);
+        assert_eq!(stats.max_queued, 1);
+        assert_eq!((stats.queued, stats.running), (0, 0));
+
+        // Cancelled tokens stay cancelled
+        assert_eq!(pool.run(&cancel, || 1).await, None);
+        assert_eq!(pool.run(&CancelToken::default(), || 1).await, Some(1));
+    }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_dropped_while_queued() {
+        let pool = Arc::new(CompressionPool::new(1));
+        let (release, blocked) = mpsc::channel::<()>();
+        let running = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                pool.run(&CancelToken::default(), move || blocked.recv().is_ok())
+                    .await
+            }
+        });
+        while pool.stats().running == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // The caller gives up on a job still waiting for a slot
+        let waiting = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(&CancelToken::default(), || 42).await }
+        });
+        while pool.stats().queued == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        waiting.abort();
+        assert!(waiting.await.unwrap_err().is_cancelled());
+        assert_eq!(pool.stats().queued, 0);
+
+        release.send(()).unwrap();
+        assert_eq!(running.await.unwrap(), Some(true));
+        assert_eq!((pool.stats().queued, pool.stats().running), (0, 0));
+    }
+}
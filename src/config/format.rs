@@ -0,0 +1,163 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Configuration file formats
+//
+// TOML is the standard format, YAML is accepted as well. The format comes from
+// the file extension (`.toml`, `.yaml`/`.yml`); for other names it is detected
+// from the content.
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// Format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Format named by the extension of `path`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Format of a document, judged by which parser accepts it as a table
+    pub fn detect(content: &str) -> Option<Self> {
+        if toml::from_str::<toml::Table>(content).is_ok() {
+            return Some(Self::Toml);
+        }
+        match serde_yaml::from_str::<serde_yaml::Value>(content) {
+            Ok(serde_yaml::Value::Mapping(_)) => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// The other format
+    pub fn other(self) -> Self {
+        match self {
+            Self::Toml => Self::Yaml,
+            Self::Yaml => Self::Toml,
+        }
+    }
+
+    /// Extensions of files in this format, preferred first
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Toml => &["toml"],
+            Self::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        match self {
+            Self::Toml => toml::from_str(content).context("Failed to parse TOML configuration"),
+            Self::Yaml => {
+                serde_yaml::from_str(content).context("Failed to parse YAML configuration")
+            }
+        }
+    }
+
+    /// Rewrite a configuration document of format `from` in this format
+    ///
+    /// Keys end up in alphabetical order and comments are dropped. `${VAR}`
+    /// placeholders are kept as long as they are inside strings.
+    pub fn convert(self, content: &str, from: ConfigFormat) -> Result<String> {
+        let mut document: serde_json::Value = from.parse(content)?;
+        match self {
+            Self::Toml => {
+                // TOML has no null; unset values are left out instead
+                remove_nulls(&mut document);
+                if !document.is_object() {
+                    bail!("Configuration must be a table");
+                }
+                toml::to_string(&document).context("Failed to write TOML configuration")
+            }
+            Self::Yaml => {
+                serde_yaml::to_string(&document).context("Failed to write YAML configuration")
+            }
+        }
+    }
+}
+
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path_and_content() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config/default.toml")),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("recorder.yml")),
+            Some(ConfigFormat::Yaml)
+        );
+        assert_eq!(ConfigFormat::from_path(Path::new("recorder.conf")), None);
+
+        assert_eq!(
+            ConfigFormat::detect("[zenoh]\nmode = \"peer\"\n"),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(
+            ConfigFormat::detect("zenoh:\n  mode: peer\n"),
+            Some(ConfigFormat::Yaml)
+        );
+        assert_eq!(ConfigFormat::detect("just text"), None);
+    }
+
+    #[test]
+    fn test_convert_round_trip() {
+        let toml = "[recorder]\ndevice_id = \"${DEVICE_ID:-robot-01}\"\n\n[recorder.flush_policy]\nmax_buffer_size_bytes = 1024\n";
+        let yaml = ConfigFormat::Yaml
+            .convert(toml, ConfigFormat::Toml)
+            .unwrap();
+        assert!(yaml.contains("device_id: ${DEVICE_ID:-robot-01}"));
+
+        let back = ConfigFormat::Toml
+            .convert(&yaml, ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(
+            toml::from_str::<toml::Table>(&back).unwrap(),
+            toml::from_str::<toml::Table>(toml).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_convert_drops_nulls_for_toml() {
+        let yaml = "logging:\n  level: info\n  file: null\n";
+        let toml = ConfigFormat::Toml
+            .convert(yaml, ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(toml.trim(), "[logging]\nlevel = \"info\"");
+    }
+}
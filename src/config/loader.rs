@@ -14,25 +14,73 @@
 
 // Configuration loader with environment variable substitution
 
+use super::format::ConfigFormat;
 use super::types::*;
-use anyhow::{bail, Context, Result};
+use crate::error::{RecorderError, Result};
+use crate::protocol::{CompressionLevel, CompressionType, MAX_LZ4_LEVEL, MAX_ZSTD_LEVEL};
+use crate::sample_filter::Expression;
+use crate::upload_gate::TimeWindow;
+use crate::validation::{compression_limit_problem, MAX_LABEL_KEY_LEN, RESERVED_LABELS};
+use anyhow::{bail, Context};
 use regex::Regex;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::warn;
 
 pub struct ConfigLoader;
 
 impl ConfigLoader {
     /// Load configuration from file with environment variable substitution
     pub fn load<P: AsRef<Path>>(path: P) -> Result<RecorderConfig> {
-        let content =
-            std::fs::read_to_string(path.as_ref()).context("Failed to read config file")?;
+        let (config, warnings) = Self::load_with_warnings(path)?;
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+        Ok(config)
+    }
+
+    /// Load configuration, returning deprecation warnings instead of logging them
+    ///
+    /// For callers that load the configuration before logging is set up.
+    pub fn load_with_warnings<P: AsRef<Path>>(path: P) -> Result<(RecorderConfig, Vec<String>)> {
+        let mut warnings = Vec::new();
+        let config =
+            Self::load_file(path.as_ref(), &mut warnings).map_err(RecorderError::config)?;
+        Ok((config, warnings))
+    }
+
+    /// Convert a configuration file to another format
+    ///
+    /// `to` defaults to the format the file isn't in. The result is checked to
+    /// load as a valid configuration.
+    pub fn convert<P: AsRef<Path>>(path: P, to: Option<ConfigFormat>) -> Result<String> {
+        Self::convert_file(path.as_ref(), to).map_err(RecorderError::config)
+    }
+
+    fn convert_file(path: &Path, to: Option<ConfigFormat>) -> anyhow::Result<String> {
+        let content = std::fs::read_to_string(path).context("Failed to read config file")?;
+        let from = Self::format_of(path, &content, &mut Vec::new())?;
+        let to = to.unwrap_or(from.other());
+
+        let converted = to.convert(&content, from)?;
+        let config = to
+            .parse(&Self::substitute_env_vars(&converted))
+            .context("Converted configuration doesn't load")?;
+        Self::validate(&config)?;
+        Ok(converted)
+    }
+
+    fn load_file(path: &Path, warnings: &mut Vec<String>) -> anyhow::Result<RecorderConfig> {
+        let path = Self::locate(path, warnings);
+        let content = std::fs::read_to_string(&path).context("Failed to read config file")?;
 
         // Substitute environment variables
         let content = Self::substitute_env_vars(&content);
 
-        // Parse TOML
-        let config: RecorderConfig =
-            toml::from_str(&content).context("Failed to parse TOML configuration")?;
+        // Parse TOML or YAML
+        let format = Self::format_of(&path, &content, warnings)?;
+        let mut config: RecorderConfig = format.parse(&content)?;
+        config.recorder.apply_low_power();
 
         // Validate configuration
         Self::validate(&config)?;
@@ -40,6 +88,62 @@ impl ConfigLoader {
         Ok(config)
     }
 
+    /// `path`, or the file next to it with the same name in the other format
+    ///
+    /// Keeps deployments working whose file was converted while their
+    /// `--config` still names the old one.
+    fn locate(path: &Path, warnings: &mut Vec<String>) -> PathBuf {
+        if path.exists() {
+            return path.to_path_buf();
+        }
+        let Some(format) = ConfigFormat::from_path(path) else {
+            return path.to_path_buf();
+        };
+        for extension in format.other().extensions() {
+            let candidate = path.with_extension(extension);
+            if candidate.exists() {
+                warnings.push(format!(
+                    "Config file {} not found, loading {} instead; this fallback is deprecated, pass --config {}",
+                    path.display(),
+                    candidate.display(),
+                    candidate.display()
+                ));
+                return candidate;
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Format of a configuration file: its extension, else its content
+    fn format_of(
+        path: &Path,
+        content: &str,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<ConfigFormat> {
+        match (ConfigFormat::from_path(path), ConfigFormat::detect(content)) {
+            (Some(named), Some(detected)) if named != detected => {
+                warnings.push(format!(
+                    "Config file {} contains {:?} despite its extension; this is deprecated, rename it to *.{}",
+                    path.display(),
+                    detected,
+                    detected.extensions()[0]
+                ));
+                Ok(detected)
+            }
+            (Some(named), _) => Ok(named),
+            (None, Some(detected)) => {
+                warnings.push(format!(
+                    "Config file {} has no .toml/.yaml extension, detected {:?}; this is deprecated, rename it to *.{}",
+                    path.display(),
+                    detected,
+                    detected.extensions()[0]
+                ));
+                Ok(detected)
+            }
+            (None, None) => bail!("Config file {} is neither TOML nor YAML", path.display()),
+        }
+    }
+
     /// Substitute ${VAR} and ${VAR:-default} patterns with environment variables
     ///
     /// Examples:
@@ -68,7 +172,7 @@ impl ConfigLoader {
     }
 
     /// Validate configuration
-    fn validate(config: &RecorderConfig) -> Result<()> {
+    fn validate(config: &RecorderConfig) -> anyhow::Result<()> {
         // Validate flush policy
         if config.recorder.flush_policy.max_buffer_size_bytes == 0 {
             bail!("flush_policy.max_buffer_size_bytes must be > 0");
@@ -78,29 +182,113 @@ impl ConfigLoader {
             bail!("flush_policy.max_buffer_duration_seconds must be > 0");
         }
 
+        if config.zenoh.reconnect.enabled && config.zenoh.reconnect.check_interval_ms == 0 {
+            bail!("zenoh.reconnect.check_interval_ms must be > 0");
+        }
+
         // Validate compression level
         if config.recorder.compression.default_level > 4 {
             bail!("compression.default_level must be 0-4");
         }
+        if !matches!(
+            config.recorder.compression.default_type.as_str(),
+            "none" | "lz4" | "zstd"
+        ) {
+            bail!("compression.default_type must be none, lz4 or zstd");
+        }
+        let compression = &config.recorder.compression;
+        if let Some(max) = compression.max_lz4_level {
+            if !(1..=MAX_LZ4_LEVEL).contains(&max) {
+                bail!("compression.max_lz4_level must be 1-{}", MAX_LZ4_LEVEL);
+            }
+        }
+        if let Some(max) = compression.max_zstd_level {
+            if !(1..=MAX_ZSTD_LEVEL).contains(&max) {
+                bail!("compression.max_zstd_level must be 1-{}", MAX_ZSTD_LEVEL);
+            }
+        }
+        // Starts without compression settings get the defaults
+        if let (Some(compression_type), Some(level)) = (
+            CompressionType::from_name(&compression.default_type),
+            CompressionLevel::from_index(compression.default_level),
+        ) {
+            if let Some(problem) = compression_limit_problem(compression_type, level, compression) {
+                bail!("compression.default_level: {}", problem);
+            }
+        }
+        for (topic, topic_compression) in &config.recorder.compression.per_topic {
+            let Some((compression_type, level)) = topic_compression.settings() else {
+                bail!(
+                    "compression.per_topic.\"{}\" needs a type of none, lz4 or zstd and a level of 0-4, got {} level {}",
+                    topic,
+                    topic_compression.r#type,
+                    topic_compression.level
+                );
+            };
+            if let Some(problem) = compression_limit_problem(compression_type, level, compression) {
+                bail!("compression.per_topic.\"{}\": {}", topic, problem);
+            }
+            if let Some(TransformConfig::Delta {
+                keyframe_interval: 0,
+            }) = topic_compression.transform
+            {
+                bail!(
+                    "compression.per_topic.\"{}\".transform.keyframe_interval must be > 0",
+                    topic
+                );
+            }
+        }
 
         // Validate backend
         match config.storage.backend.as_str() {
             "reductstore" => {
-                if config.storage.backend_config.as_reductstore().is_none() {
+                let Some(reductstore) = config.storage.backend_config.as_reductstore() else {
                     bail!("reductstore backend selected but reductstore config missing");
+                };
+                let tls = &reductstore.tls;
+                if tls.client_cert_file.is_some() != tls.client_key_file.is_some() {
+                    bail!(
+                        "reductstore.tls.client_cert_file and client_key_file must be set together"
+                    );
+                }
+                let settings = &reductstore.bucket_settings;
+                if matches!(
+                    settings.quota_type,
+                    Some(ReductStoreQuotaType::Fifo | ReductStoreQuotaType::Hard)
+                ) && settings.quota_size.unwrap_or(0) == 0
+                {
+                    bail!("reductstore.bucket_settings.quota_size must be > 0 with a FIFO or HARD quota_type");
                 }
             }
             "filesystem" => {
-                if config.storage.backend_config.as_filesystem().is_none() {
+                let Some(filesystem) = config.storage.backend_config.as_filesystem() else {
                     bail!("filesystem backend selected but filesystem config missing");
+                };
+                if filesystem.retention.prunes() && filesystem.retention.check_interval_seconds == 0
+                {
+                    bail!("filesystem.retention.check_interval_seconds must be > 0");
+                }
+            }
+            "mock" => {
+                let Some(mock) = config.storage.backend_config.as_mock() else {
+                    bail!("mock backend selected but mock config missing");
+                };
+                if !(0.0..=1.0).contains(&mock.error_rate) {
+                    bail!("mock.error_rate must be between 0 and 1");
                 }
             }
             unknown => bail!(
-                "Unknown backend: '{}'. Supported: reductstore, filesystem",
+                "Unknown backend: '{}'. Supported: reductstore, filesystem, mock",
                 unknown
             ),
         }
 
+        // Validate read-back sampling
+        let verify_percent = config.recorder.integrity.verify_percent;
+        if !(0.0..=100.0).contains(&verify_percent) {
+            bail!("integrity.verify_percent must be between 0 and 100");
+        }
+
         // Validate worker count
         if config.recorder.workers.flush_workers == 0 {
             bail!("workers.flush_workers must be > 0");
@@ -110,6 +298,355 @@ impl ConfigLoader {
             bail!("workers.queue_capacity must be > 0");
         }
 
+        let power_events = &config.recorder.power_events;
+        if !(0.0..=100.0).contains(&power_events.low_battery_percent) {
+            bail!("power_events.low_battery_percent must be 0-100");
+        }
+
+        let data_bridge = &config.recorder.data_bridge;
+        if data_bridge.enabled {
+            if data_bridge.page_bytes == 0 {
+                bail!("data_bridge.page_bytes must be > 0");
+            }
+            if let Err(e) = zenoh::key_expr::KeyExpr::try_from(data_bridge.key_prefix.as_str()) {
+                bail!(
+                    "data_bridge.key_prefix is not a valid key expression: {}",
+                    e
+                );
+            }
+        }
+
+        for (pattern, transforms) in &config.recorder.transforms.per_topic {
+            for transform in transforms {
+                if let Some(problem) = transform_problem(transform) {
+                    bail!("transforms.per_topic.\"{}\": {}", pattern, problem);
+                }
+                if let SampleTransformConfig::Filter { expression } = transform {
+                    if let Err(e) = Expression::parse(expression) {
+                        bail!(
+                            "transforms.per_topic.\"{}\": invalid filter expression: {}",
+                            pattern,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        for topic in &config.recorder.snapshot.topics {
+            if let Err(e) = zenoh::key_expr::KeyExpr::try_from(topic.as_str()) {
+                bail!(
+                    "snapshot.topics: '{}' is not a valid key expression: {}",
+                    topic,
+                    e
+                );
+            }
+        }
+
+        for topic in &config.recorder.black_box.topics {
+            if let Err(e) = zenoh::key_expr::KeyExpr::try_from(topic.as_str()) {
+                bail!(
+                    "black_box.topics: '{}' is not a valid key expression: {}",
+                    topic,
+                    e
+                );
+            }
+        }
+        if config.recorder.black_box.max_bytes_per_topic == 0 {
+            bail!("black_box.max_bytes_per_topic must be greater than 0");
+        }
+
+        for (i, hook) in config.recorder.hooks.iter().enumerate() {
+            match &hook.action {
+                HookAction::Webhook { url, headers } => {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        bail!("hooks[{}].url must be an http(s) URL, got '{}'", i, url);
+                    }
+                    if headers.keys().any(String::is_empty) {
+                        bail!("hooks[{}].headers must not have an empty name", i);
+                    }
+                }
+                HookAction::Exec { command } => {
+                    if command.first().is_none_or(String::is_empty) {
+                        bail!("hooks[{}].command must name a program", i);
+                    }
+                }
+            }
+            if hook.timeout_ms == 0 {
+                bail!("hooks[{}].timeout_ms must be greater than 0", i);
+            }
+        }
+
+        let ingestion = &config.recorder.ingestion;
+        if ingestion.enabled {
+            let endpoint = &ingestion.endpoint;
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                bail!(
+                    "ingestion.endpoint must be an http(s) URL, got '{}'",
+                    endpoint
+                );
+            }
+            match &ingestion.token_url {
+                Some(_) if ingestion.token.is_some() => {
+                    bail!("ingestion takes a token or a token_url, not both");
+                }
+                Some(token_url) => {
+                    if !token_url.starts_with("http://") && !token_url.starts_with("https://") {
+                        bail!(
+                            "ingestion.token_url must be an http(s) URL, got '{}'",
+                            token_url
+                        );
+                    }
+                    if ingestion.client_id.is_none() || ingestion.client_secret.is_none() {
+                        bail!("ingestion.token_url needs a client_id and a client_secret");
+                    }
+                }
+                None if ingestion.token.is_none() => {
+                    bail!("ingestion needs a token or a token_url");
+                }
+                None => {}
+            }
+            if ingestion.chunk_size_bytes == 0 {
+                bail!("ingestion.chunk_size_bytes must be greater than 0");
+            }
+            if ingestion.timeout_ms == 0 {
+                bail!("ingestion.timeout_ms must be greater than 0");
+            }
+        }
+
+        let quotas = &config.recorder.quotas;
+        if quotas.organizations.keys().any(String::is_empty) {
+            bail!("quotas.organizations must not have an empty organization");
+        }
+        if quotas.tasks.keys().any(String::is_empty) {
+            bail!("quotas.tasks must not have an empty task ID");
+        }
+
+        if config.recorder.workers.compression_threads == 0 {
+            bail!("workers.compression_threads must be > 0");
+        }
+
+        if config.recorder.workers.shutdown_deadline_seconds > 0
+            && !config.recorder.work_dir.enabled
+        {
+            bail!("workers.shutdown_deadline_seconds needs work_dir.enabled to spill recordings");
+        }
+
+        let smoothing = config.recorder.topic_stats.smoothing;
+        if !(smoothing > 0.0 && smoothing <= 1.0) {
+            bail!("topic_stats.smoothing must be in (0, 1]");
+        }
+
+        if config.recorder.status_events.max_per_sec < 0.0 {
+            bail!("status_events.max_per_sec must be >= 0");
+        }
+
+        // The liveliness token must name this recorder only
+        let health = &config.recorder.health;
+        if health.enabled {
+            let alive_key = format!("{}/alive/{}", health.key_prefix, config.recorder.device_id);
+            if alive_key.contains('*') {
+                bail!("health.key_prefix and recorder.device_id must not contain wildcards");
+            }
+            if let Err(e) = zenoh::key_expr::KeyExpr::try_from(alive_key.as_str()) {
+                bail!("health.key_prefix is not a valid key expression: {}", e);
+            }
+        }
+
+        // Validate the live tap
+        let tap = &config.recorder.tap;
+        if tap.channel_capacity == 0 {
+            bail!("tap.channel_capacity must be > 0");
+        }
+        if tap.publish && (tap.key_prefix.is_empty() || tap.key_prefix.contains('*')) {
+            bail!("tap.key_prefix must be a non-empty key without wildcards");
+        }
+
+        // Validate upload deferral
+        let deferral = &config.recorder.upload_deferral;
+        if deferral.enabled {
+            if !config.recorder.work_dir.enabled {
+                bail!("upload_deferral needs work_dir.enabled to keep deferred records");
+            }
+            if deferral.check_interval_seconds == 0 {
+                bail!("upload_deferral.check_interval_seconds must be > 0");
+            }
+            for window in &deferral.windows {
+                if let Err(e) = TimeWindow::parse(window) {
+                    bail!("upload_deferral.windows: {}", e);
+                }
+            }
+            for probe in &deferral.probes {
+                match probe {
+                    ConnectivityProbe::Interface { name } if name.is_empty() => {
+                        bail!("upload_deferral.probes: interface name cannot be empty")
+                    }
+                    ConnectivityProbe::Tcp { address, .. } if address.is_empty() => {
+                        bail!("upload_deferral.probes: tcp address cannot be empty")
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let auth = &config.recorder.control.auth;
+        let mut names = HashSet::new();
+        for principal in &auth.principals {
+            if principal.name.is_empty() {
+                bail!("control.auth.principals: name cannot be empty");
+            }
+            if !names.insert(principal.name.as_str()) {
+                bail!(
+                    "control.auth.principals: duplicate name '{}'",
+                    principal.name
+                );
+            }
+            if principal.token.as_deref().unwrap_or_default().is_empty()
+                && principal.secret.as_deref().unwrap_or_default().is_empty()
+            {
+                bail!(
+                    "control.auth.principals: '{}' needs a token or a secret",
+                    principal.name
+                );
+            }
+        }
+        if auth.enabled && auth.principals.is_empty() {
+            bail!("control.auth.enabled needs at least one principal");
+        }
+        if let Some(name) = &auth.handoff_principal {
+            match auth.principals.iter().find(|p| &p.name == name) {
+                Some(principal) if principal.role == ControlRole::Admin => {}
+                Some(_) => bail!("control.auth.handoff_principal '{}' must be an admin", name),
+                None => bail!(
+                    "control.auth.handoff_principal '{}' is not a principal",
+                    name
+                ),
+            }
+        }
+
+        // Group names are a single key expression chunk
+        for group in &config.recorder.control.groups {
+            if group.is_empty() || group.contains(['/', '*', '$', '?', '#']) {
+                bail!(
+                    "control.groups: '{}' must be a name without '/' or wildcards",
+                    group
+                );
+            }
+        }
+
+        let low_power = &config.recorder.low_power;
+        if low_power.enabled && low_power.flush_interval_seconds == 0 {
+            bail!("low_power.flush_interval_seconds must be > 0");
+        }
+
+        // Validate topic remapping
+        let mut recorded_names = HashSet::new();
+        for (topic, name) in &config.recorder.topic_remap {
+            if name.is_empty() || name.contains('*') {
+                bail!(
+                    "topic_remap.'{}' must be a topic name without wildcards",
+                    topic
+                );
+            }
+            if !recorded_names.insert(name) {
+                bail!("topic_remap maps several topics to '{}'", name);
+            }
+        }
+
+        for pattern in &config.recorder.exclude_topics {
+            if pattern.is_empty() || zenoh::key_expr::KeyExpr::try_from(pattern.as_str()).is_err() {
+                bail!(
+                    "exclude_topics: '{}' is not a valid key expression",
+                    pattern
+                );
+            }
+        }
+
+        let initial_query = &config.recorder.initial_query;
+        for pattern in &initial_query.topics {
+            if pattern.is_empty() || zenoh::key_expr::KeyExpr::try_from(pattern.as_str()).is_err() {
+                bail!(
+                    "initial_query.topics: '{}' is not a valid key expression",
+                    pattern
+                );
+            }
+        }
+        if !initial_query.topics.is_empty() && initial_query.timeout_ms == 0 {
+            bail!("initial_query.timeout_ms must be > 0");
+        }
+
+        let coalesce = &config.recorder.coalesce;
+        if coalesce.enabled {
+            if coalesce.max_batch_bytes == 0 || coalesce.interval_ms == 0 {
+                bail!("coalesce.max_batch_bytes and coalesce.interval_ms must be > 0");
+            }
+            if coalesce.max_record_bytes < coalesce.max_batch_bytes {
+                bail!("coalesce.max_record_bytes must be at least coalesce.max_batch_bytes");
+            }
+        }
+
+        let expiry = &config.recorder.expiry;
+        if expiry.max_ttl_seconds > 0 && expiry.default_ttl_seconds > expiry.max_ttl_seconds {
+            bail!("expiry.default_ttl_seconds must not exceed expiry.max_ttl_seconds");
+        }
+
+        for key in &config.recorder.attachment_labels {
+            if key.is_empty()
+                || key.len() > MAX_LABEL_KEY_LEN
+                || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                bail!(
+                    "attachment_labels: '{}' must be 1-{} letters, digits or '_'",
+                    key,
+                    MAX_LABEL_KEY_LEN
+                );
+            }
+            if RESERVED_LABELS.contains(&key.as_str()) || key.starts_with("env_") {
+                bail!("attachment_labels: '{}' is set by the recorder", key);
+            }
+        }
+
+        // Validate timestamp policies
+        let timestamps = &config.recorder.timestamps;
+        let policies = std::iter::once(("default".to_string(), &timestamps.default)).chain(
+            timestamps
+                .per_topic
+                .iter()
+                .map(|(topic, policy)| (format!("per_topic.\"{}\"", topic), policy)),
+        );
+        for (name, policy) in policies {
+            let from_payload = policy.source == TimestampSource::PayloadField
+                || policy.missing == MissingTimestampPolicy::PayloadField;
+            if from_payload && policy.payload_field.as_deref().unwrap_or("").is_empty() {
+                bail!(
+                    "recorder.timestamps.{}.payload_field must be set to read timestamps from the payload",
+                    name
+                );
+            }
+        }
+
+        // Validate logging
+        let logging = &config.logging;
+        if !matches!(logging.format.as_str(), "text" | "json") {
+            bail!("logging.format must be 'text' or 'json'");
+        }
+        if let Some(file) = &logging.file {
+            if !matches!(
+                file.rotation.as_str(),
+                "minutely" | "hourly" | "daily" | "never"
+            ) {
+                bail!("logging.file.rotation must be minutely, hourly, daily or never");
+            }
+        }
+        if let Some(otlp) = &logging.otlp {
+            if !otlp.endpoint.starts_with("http://") && !otlp.endpoint.starts_with("https://") {
+                bail!("logging.otlp.endpoint must be an http:// or https:// URL");
+            }
+            if otlp.service_name.is_empty() {
+                bail!("logging.otlp.service_name cannot be empty");
+            }
+        }
+
         // Validate device_id is not empty
         if config.recorder.device_id.is_empty() {
             bail!("recorder.device_id cannot be empty");
@@ -119,6 +656,41 @@ impl ConfigLoader {
     }
 }
 
+/// What is wrong with a sample transform, if anything
+fn transform_problem(transform: &SampleTransformConfig) -> Option<&'static str> {
+    match transform {
+        SampleTransformConfig::Drop {
+            min_bytes: None,
+            json_field: None,
+            ..
+        } => Some("drop needs min_bytes or json_field"),
+        SampleTransformConfig::Drop {
+            json_field: None,
+            equals: Some(_),
+            ..
+        } => Some("drop with equals needs json_field"),
+        SampleTransformConfig::Drop {
+            json_field: Some(field),
+            ..
+        } if field.is_empty() => Some("drop json_field must not be empty"),
+        SampleTransformConfig::Truncate { max_bytes: 0 } => Some("truncate max_bytes must be > 0"),
+        SampleTransformConfig::RedactBytes { ranges, .. } if ranges.is_empty() => {
+            Some("redact_bytes needs ranges")
+        }
+        SampleTransformConfig::RedactBytes { ranges, .. }
+            if ranges.iter().any(|[start, end]| start >= end) =>
+        {
+            Some("redact_bytes ranges must be [start, end) with start < end")
+        }
+        SampleTransformConfig::RedactJson { fields, .. }
+            if fields.is_empty() || fields.iter().any(String::is_empty) =>
+        {
+            Some("redact_json needs non-empty fields")
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,4 +739,205 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("compression"));
     }
+
+    #[test]
+    fn test_validation_transform_keyframe_interval() {
+        let mut config = RecorderConfig::default();
+        let compression: TopicCompression = toml::from_str(
+            r#"
+            type = "zstd"
+            level = 3
+            transform = { kind = "delta" }
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            compression.transform,
+            Some(TransformConfig::Delta {
+                keyframe_interval: 10
+            })
+        ));
+        config
+            .recorder
+            .compression
+            .per_topic
+            .insert("/lidar/**".to_string(), compression);
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config
+            .recorder
+            .compression
+            .per_topic
+            .get_mut("/lidar/**")
+            .unwrap()
+            .transform = Some(TransformConfig::Delta {
+            keyframe_interval: 0,
+        });
+        let error = ConfigLoader::validate(&config).unwrap_err().to_string();
+        assert!(error.contains("keyframe_interval"), "{}", error);
+    }
+
+    #[test]
+    fn test_validation_retention_interval() {
+        let mut config = RecorderConfig::default();
+        config.storage.backend = "filesystem".to_string();
+        config.storage.backend_config = BackendConfig::Filesystem {
+            filesystem: FilesystemConfig::default(),
+        };
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        if let BackendConfig::Filesystem { filesystem } = &mut config.storage.backend_config {
+            filesystem.retention.max_usage_bytes = 1024;
+            filesystem.retention.check_interval_seconds = 0;
+        }
+        let result = ConfigLoader::validate(&config);
+        assert!(result.unwrap_err().to_string().contains("check_interval"));
+    }
+
+    #[test]
+    fn test_validation_topic_remap() {
+        let mut config = RecorderConfig::default();
+        config
+            .recorder
+            .topic_remap
+            .insert("/robot1/camera".to_string(), "/camera".to_string());
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config
+            .recorder
+            .topic_remap
+            .insert("/robot2/camera".to_string(), "/camera".to_string());
+        let result = ConfigLoader::validate(&config);
+        assert!(result.unwrap_err().to_string().contains("several topics"));
+
+        config.recorder.topic_remap.clear();
+        config
+            .recorder
+            .topic_remap
+            .insert("/robot1/**".to_string(), "/robot/**".to_string());
+        assert!(ConfigLoader::validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_validation_exclude_topics() {
+        let mut config = RecorderConfig::default();
+        config.recorder.exclude_topics = vec!["robot/**/debug/**".to_string()];
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config
+            .recorder
+            .exclude_topics
+            .push("robot//log".to_string());
+        let result = ConfigLoader::validate(&config);
+        assert!(result.unwrap_err().to_string().contains("exclude_topics"));
+    }
+
+    #[test]
+    fn test_validation_attachment_labels() {
+        let mut config = RecorderConfig::default();
+        config.recorder.attachment_labels = vec!["mission_id".to_string()];
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        for key in ["mission-id", "topic", "env_site"] {
+            config.recorder.attachment_labels = vec![key.to_string()];
+            let result = ConfigLoader::validate(&config);
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("attachment_labels"));
+        }
+    }
+
+    #[test]
+    fn test_validation_health_keys() {
+        let mut config = RecorderConfig::default();
+        config.recorder.health.enabled = true;
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        for prefix in ["fleet/*", "fleet//recorders", ""] {
+            config.recorder.health.key_prefix = prefix.to_string();
+            let result = ConfigLoader::validate(&config);
+            assert!(
+                result.unwrap_err().to_string().contains("health"),
+                "{}",
+                prefix
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation_expiry() {
+        let mut config = RecorderConfig::default();
+        config.recorder.expiry.enabled = true;
+        config.recorder.expiry.default_ttl_seconds = 3600;
+        assert!(ConfigLoader::validate(&config).is_ok());
+        config.recorder.expiry.max_ttl_seconds = 60;
+        assert!(ConfigLoader::validate(&config).is_err());
+
+        let mut config = RecorderConfig::default();
+        let reductstore = config.storage.backend_config.as_reductstore_mut().unwrap();
+        reductstore.bucket_settings.quota_type = Some(ReductStoreQuotaType::Fifo);
+        assert!(ConfigLoader::validate(&config).is_err());
+        let reductstore = config.storage.backend_config.as_reductstore_mut().unwrap();
+        reductstore.bucket_settings.quota_size = Some(1_000_000_000);
+        assert!(ConfigLoader::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn test_validation_coalesce() {
+        let mut config = RecorderConfig::default();
+        config.recorder.coalesce.enabled = true;
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config.recorder.coalesce.max_record_bytes = 1024;
+        assert!(ConfigLoader::validate(&config).is_err());
+        config.recorder.coalesce.max_record_bytes = 1024 * 1024;
+        config.recorder.coalesce.interval_ms = 0;
+        assert!(ConfigLoader::validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_validation_filter_expression() {
+        let mut config = RecorderConfig::default();
+        let filter = |expression: &str| {
+            vec![SampleTransformConfig::Filter {
+                expression: expression.to_string(),
+            }]
+        };
+        config
+            .recorder
+            .transforms
+            .per_topic
+            .insert("robot/odom".to_string(), filter("payload.speed > 2.0"));
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config
+            .recorder
+            .transforms
+            .per_topic
+            .insert("robot/odom".to_string(), filter("speed > 2.0"));
+        let err = ConfigLoader::validate(&config).unwrap_err().to_string();
+        assert!(err.contains("invalid filter expression"), "{}", err);
+    }
+
+    #[test]
+    fn test_validation_initial_query() {
+        let mut config = RecorderConfig::default();
+        config.recorder.initial_query.topics = vec!["robot/map".to_string()];
+        assert!(ConfigLoader::validate(&config).is_ok());
+
+        config.recorder.initial_query.timeout_ms = 0;
+        assert!(ConfigLoader::validate(&config).is_err());
+        config.recorder.initial_query.timeout_ms = 2000;
+
+        for pattern in ["", "robot//map"] {
+            config.recorder.initial_query.topics = vec![pattern.to_string()];
+            let result = ConfigLoader::validate(&config);
+            assert!(
+                result.unwrap_err().to_string().contains("initial_query"),
+                "{}",
+                pattern
+            );
+        }
+    }
 }
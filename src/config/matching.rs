@@ -0,0 +1,101 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Topic pattern matching for per-topic configuration sections
+//
+// Per-topic keys may be exact topics or key-expression style patterns:
+// - `*` matches exactly one path chunk
+// - `**` matches zero or more path chunks
+
+use std::collections::HashMap;
+
+/// Check whether `topic` matches the per-topic `pattern`
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+    let topic: Vec<&str> = topic.trim_start_matches('/').split('/').collect();
+    chunks_match(&pattern, &topic)
+}
+
+fn chunks_match(pattern: &[&str], topic: &[&str]) -> bool {
+    match (pattern.first(), topic.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            chunks_match(&pattern[1..], topic)
+                || (!topic.is_empty() && chunks_match(pattern, &topic[1..]))
+        }
+        (Some(&"*"), Some(_)) => chunks_match(&pattern[1..], &topic[1..]),
+        (Some(p), Some(t)) if p == t => chunks_match(&pattern[1..], &topic[1..]),
+        _ => false,
+    }
+}
+
+/// Find the per-topic entry for `topic`
+///
+/// An exact key wins; otherwise the longest (most specific) matching pattern is used.
+/// Whether `topic` matches one of `patterns`
+pub fn matches_any(patterns: &[String], topic: &str) -> bool {
+    patterns.iter().any(|pattern| topic_matches(pattern, topic))
+}
+
+pub fn find_per_topic<'a, T>(per_topic: &'a HashMap<String, T>, topic: &str) -> Option<&'a T> {
+    if let Some(entry) = per_topic.get(topic) {
+        return Some(entry);
+    }
+
+    per_topic
+        .iter()
+        .filter(|(pattern, _)| topic_matches(pattern, topic))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(|(_, entry)| entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        assert!(topic_matches("/camera/front", "/camera/front"));
+        assert!(topic_matches("/camera/*", "/camera/front"));
+        assert!(!topic_matches("/camera/*", "/camera/front/raw"));
+        assert!(topic_matches("/camera/**", "/camera/front/raw"));
+        assert!(topic_matches("/camera/**", "/camera"));
+        assert!(topic_matches("**/debug/**", "robot/arm/debug/log"));
+        assert!(!topic_matches("/lidar/**", "/camera/front"));
+        // Leading slashes are not significant
+        assert!(topic_matches("camera/**", "/camera/front"));
+    }
+
+    #[test]
+    fn test_matches_any() {
+        let patterns = vec!["robot/**/debug/**".to_string(), "robot/log".to_string()];
+        assert!(matches_any(&patterns, "robot/arm/debug/trace"));
+        assert!(matches_any(&patterns, "robot/log"));
+        assert!(!matches_any(&patterns, "robot/arm/joints"));
+        assert!(!matches_any(&[], "robot/log"));
+    }
+
+    #[test]
+    fn test_find_per_topic_prefers_exact_then_specific() {
+        let mut per_topic = HashMap::new();
+        per_topic.insert("/camera/**".to_string(), 1);
+        per_topic.insert("/camera/front/**".to_string(), 2);
+        per_topic.insert("/camera/front/raw".to_string(), 3);
+
+        assert_eq!(find_per_topic(&per_topic, "/camera/front/raw"), Some(&3));
+        assert_eq!(find_per_topic(&per_topic, "/camera/front/info"), Some(&2));
+        assert_eq!(find_per_topic(&per_topic, "/camera/rear"), Some(&1));
+        assert_eq!(find_per_topic(&per_topic, "/lidar/points"), None);
+    }
+}
@@ -15,30 +15,41 @@
 // Configuration module for zenoh-recorder
 //
 // Provides:
-// - TOML configuration file loading
+// - TOML (standard) or YAML configuration file loading, and conversion
 // - Environment variable substitution
 // - Configuration validation
 // - Default values
 
+mod format;
 mod loader;
+pub mod matching;
 pub mod types;
 
+pub use format::ConfigFormat;
 pub use loader::ConfigLoader;
+#[allow(unused_imports)]
+pub use matching::{find_per_topic, matches_any, topic_matches};
 pub use types::*;
 
-use anyhow::{Context, Result};
-use std::path::Path;
+use crate::error::Result;
+use std::path::{Path, PathBuf};
 
-/// Load configuration from a TOML file
+/// Load configuration from a TOML or YAML file
+#[allow(dead_code)]
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<RecorderConfig> {
-    ConfigLoader::load(path).context("Failed to load configuration")
+    ConfigLoader::load(path)
 }
 
 /// Load configuration with environment variable overrides
+#[allow(dead_code)]
 pub fn load_config_with_env<P: AsRef<Path>>(path: P) -> Result<RecorderConfig> {
     let mut config = load_config(path)?;
+    apply_env_overrides(&mut config);
+    Ok(config)
+}
 
-    // Allow environment variables to override config values
+/// Allow environment variables to override config values
+fn apply_env_overrides(config: &mut RecorderConfig) {
     if let Ok(device_id) = std::env::var("DEVICE_ID") {
         config.recorder.device_id = device_id;
     }
@@ -54,6 +65,33 @@ pub fn load_config_with_env<P: AsRef<Path>>(path: P) -> Result<RecorderConfig> {
             reduct_config.api_token = Some(api_token);
         }
     }
+}
 
-    Ok(config)
+/// Configuration file and command-line overrides, kept for reloads
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub path: PathBuf,
+    /// Device ID given on the command line
+    pub device_id: Option<String>,
+}
+
+impl ConfigSource {
+    /// Load the file with environment and command-line overrides applied
+    pub fn load(&self) -> Result<RecorderConfig> {
+        let (config, warnings) = self.load_with_warnings()?;
+        for warning in &warnings {
+            tracing::warn!("{}", warning);
+        }
+        Ok(config)
+    }
+
+    /// Like `load`, returning deprecation warnings instead of logging them
+    pub fn load_with_warnings(&self) -> Result<(RecorderConfig, Vec<String>)> {
+        let (mut config, warnings) = ConfigLoader::load_with_warnings(&self.path)?;
+        apply_env_overrides(&mut config);
+        if let Some(device_id) = &self.device_id {
+            config.recorder.device_id = device_id.clone();
+        }
+        Ok((config, warnings))
+    }
 }
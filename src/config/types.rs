@@ -18,6 +18,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 
+use super::matching::find_per_topic;
+use crate::protocol::{
+    BufferStrategy, CompressionLevel, CompressionType, HandoffMode, RecordingEventKind,
+};
+
 /// Main configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct RecorderConfig {
@@ -42,6 +47,14 @@ pub struct ZenohConfig {
 
     #[serde(default)]
     pub listen: Option<ListenConfig>,
+
+    /// Receive payloads of local publishers through shared memory, without
+    /// copying them before serialization (needs the `shared-memory` feature)
+    #[serde(default)]
+    pub shared_memory: bool,
+
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
 }
 
 impl Default for ZenohConfig {
@@ -52,10 +65,53 @@ impl Default for ZenohConfig {
                 endpoints: vec!["tcp/localhost:7447".to_string()],
             }),
             listen: None,
+            shared_memory: false,
+            reconnect: ReconnectConfig::default(),
+        }
+    }
+}
+
+/// Supervision of the Zenoh session
+///
+/// Once connected, the session is checked every `check_interval_ms`. Losing
+/// every router and peer opens a gap in the metadata of the active
+/// recordings; when the connection comes back the subscribers and queryables
+/// are declared again and the gap is closed. A session closed, or in client
+/// mode without a router for `reopen_after_seconds`, is replaced by a new one.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReconnectConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    #[serde(default = "default_reconnect_check_interval")]
+    pub check_interval_ms: u64,
+
+    /// 0 = only reopen a closed session
+    #[serde(default = "default_reopen_after")]
+    pub reopen_after_seconds: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_ms: default_reconnect_check_interval(),
+            reopen_after_seconds: default_reopen_after(),
         }
     }
 }
 
+impl ReconnectConfig {
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_millis(self.check_interval_ms)
+    }
+
+    /// How long a disconnected session is kept (None = until closed)
+    pub fn reopen_after(&self) -> Option<Duration> {
+        (self.reopen_after_seconds > 0).then(|| Duration::from_secs(self.reopen_after_seconds))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConnectConfig {
     pub endpoints: Vec<String>,
@@ -69,7 +125,7 @@ pub struct ListenConfig {
 /// Storage configuration with backend selection
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
-    /// Backend type: "reductstore", "filesystem", "influxdb", "s3"
+    /// Backend type: "reductstore", "filesystem", "mock", "influxdb", "s3"
     pub backend: String,
 
     /// Backend-specific configuration
@@ -99,6 +155,10 @@ pub enum BackendConfig {
         #[serde(rename = "filesystem")]
         filesystem: FilesystemConfig,
     },
+    Mock {
+        #[serde(rename = "mock")]
+        mock: MockConfig,
+    },
 }
 
 // Manual implementation to handle the nested structure
@@ -123,6 +183,13 @@ impl BackendConfig {
             _ => None,
         }
     }
+
+    pub fn as_mock(&self) -> Option<&MockConfig> {
+        match self {
+            BackendConfig::Mock { mock } => Some(mock),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -137,6 +204,25 @@ pub struct ReductStoreConfig {
 
     #[serde(default = "default_retries")]
     pub max_retries: u32,
+
+    /// Cap on the label header names and values of a record; labels over
+    /// it are dropped and the record marked `labels_truncated` (0 = no cap)
+    #[serde(default = "default_max_label_bytes")]
+    pub max_label_bytes: usize,
+
+    /// Records of an entry sent per batch request (0 or 1 = a request per
+    /// record)
+    #[serde(default = "default_batch_max_records")]
+    pub batch_max_records: usize,
+
+    /// Trust and client certificate settings of `https://` URLs
+    #[serde(default)]
+    pub tls: ReductStoreTlsConfig,
+
+    /// Quota and block settings applied to the bucket when the backend
+    /// creates or finds it
+    #[serde(default)]
+    pub bucket_settings: ReductStoreBucketSettings,
 }
 
 impl Default for ReductStoreConfig {
@@ -147,15 +233,107 @@ impl Default for ReductStoreConfig {
             api_token: None,
             timeout_seconds: default_timeout(),
             max_retries: default_retries(),
+            max_label_bytes: default_max_label_bytes(),
+            batch_max_records: default_batch_max_records(),
+            tls: ReductStoreTlsConfig::default(),
+            bucket_settings: ReductStoreBucketSettings::default(),
+        }
+    }
+}
+
+/// Bucket settings pushed to ReductStore (unset = the server's defaults, or
+/// the bucket's current settings)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReductStoreBucketSettings {
+    /// What the bucket does once it holds `quota_size` bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_type: Option<ReductStoreQuotaType>,
+    /// Size limit of the bucket for `quota_type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_size: Option<u64>,
+    /// Largest block the server writes records to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_size: Option<u64>,
+    /// Most records per block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_records: Option<u64>,
+}
+
+impl ReductStoreBucketSettings {
+    /// Whether nothing is set, so existing buckets are left as they are
+    pub fn is_empty(&self) -> bool {
+        self.quota_type.is_none()
+            && self.quota_size.is_none()
+            && self.max_block_size.is_none()
+            && self.max_block_records.is_none()
+    }
+}
+
+/// Quota of a ReductStore bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReductStoreQuotaType {
+    /// No limit
+    None,
+    /// Delete the oldest blocks to make room
+    Fifo,
+    /// Refuse writes
+    Hard,
+}
+
+impl ReductStoreQuotaType {
+    /// Name in the ReductStore API
+    pub fn as_api_str(&self) -> &'static str {
+        match self {
+            Self::None => "NONE",
+            Self::Fifo => "FIFO",
+            Self::Hard => "HARD",
         }
     }
 }
 
+/// TLS settings of the connection to ReductStore
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReductStoreTlsConfig {
+    /// PEM bundle of CAs trusted next to the system roots (private CAs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+    /// PEM certificate (chain) presented to servers requiring client auth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_file: Option<String>,
+    /// PKCS#8 PEM private key of `client_cert_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_file: Option<String>,
+    /// Accept any server certificate; for lab setups only
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FilesystemConfig {
     pub base_path: String,
     #[serde(default = "default_file_format")]
     pub file_format: String, // "mcap"
+    /// Keep an append-only index of the records of each entry, so recordings
+    /// can be repaired after a power loss (`zenoh-recorder repair`)
+    #[serde(default = "default_true")]
+    pub record_index: bool,
+    /// One file per flush, or one file per recording and topic
+    #[serde(default)]
+    pub layout: FileLayout,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+/// How the filesystem backend lays out the records of a recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileLayout {
+    /// `{entry}/{timestamp_us}.{format}` per flush
+    #[default]
+    PerFlush,
+    /// Batches appended to `{recording_id}/{entry}.{format}`, completed on finish
+    Consolidated,
 }
 
 impl Default for FilesystemConfig {
@@ -163,10 +341,79 @@ impl Default for FilesystemConfig {
         Self {
             base_path: "/data/recordings".to_string(),
             file_format: default_file_format(),
+            record_index: true,
+            layout: FileLayout::default(),
+            retention: RetentionConfig::default(),
+        }
+    }
+}
+
+/// In-memory backend with injected faults (`backend = "mock"`), for
+/// integration and chaos tests
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MockConfig {
+    /// Delay of every write in milliseconds
+    #[serde(default)]
+    pub latency_ms: u64,
+
+    /// Share of write attempts that fail (0.0-1.0), spread evenly
+    #[serde(default)]
+    pub error_rate: f64,
+
+    /// Writes fail once the stored records would exceed this size, and
+    /// recordings are refused when it is reached (0 = no limit)
+    #[serde(default)]
+    pub capacity_bytes: u64,
+}
+
+/// Disk usage limits of the filesystem backend
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    /// Delete the oldest records once the stored records exceed this size
+    /// (0 = no limit)
+    #[serde(default)]
+    pub max_usage_bytes: u64,
+
+    /// Delete records older than this (0 = keep them)
+    #[serde(default)]
+    pub max_age_hours: u64,
+
+    /// Refuse to start recordings when the disk has less free space than
+    /// this (0 = no check)
+    #[serde(default)]
+    pub min_free_bytes: u64,
+
+    /// Interval of the background retention pass
+    #[serde(default = "default_retention_interval")]
+    pub check_interval_seconds: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_usage_bytes: 0,
+            max_age_hours: 0,
+            min_free_bytes: 0,
+            check_interval_seconds: default_retention_interval(),
         }
     }
 }
 
+impl RetentionConfig {
+    /// Whether records are ever deleted
+    pub fn prunes(&self) -> bool {
+        self.max_usage_bytes > 0 || self.max_age_hours > 0
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        (self.max_age_hours > 0).then(|| Duration::from_secs(self.max_age_hours * 3600))
+    }
+
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_seconds)
+    }
+}
+
 /// Recorder-specific settings
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RecorderSettings {
@@ -179,6 +426,83 @@ pub struct RecorderSettings {
     pub control: ControlConfig,
     #[serde(default)]
     pub schema: SchemaConfig,
+    #[serde(default)]
+    pub recovery: RecoveryConfig,
+    #[serde(default)]
+    pub timestamps: TimestampConfig,
+    #[serde(default)]
+    pub upload_limit: UploadLimitConfig,
+    #[serde(default)]
+    pub handoff: HandoffConfig,
+    #[serde(default)]
+    pub topic_stats: TopicStatsConfig,
+    #[serde(default)]
+    pub status_events: StatusEventsConfig,
+    /// Liveliness token and heartbeats for fleet monitoring
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
+    /// Index records of the stored batches, for time-range reads
+    #[serde(default)]
+    pub index: IndexConfig,
+    /// Small batches of many topics stored together as one record
+    #[serde(default)]
+    pub coalesce: CoalesceConfig,
+    #[serde(default)]
+    pub controller_liveliness: ControllerLivelinessConfig,
+    #[serde(default)]
+    pub work_dir: WorkDirConfig,
+    #[serde(default)]
+    pub preview: PreviewConfig,
+    /// Live, down-sampled copies of recorded topics for operators
+    #[serde(default)]
+    pub tap: TapConfig,
+    #[serde(default)]
+    pub dedupe: DedupeConfig,
+    #[serde(default)]
+    pub topology: TopologyConfig,
+    /// Latest values of slow-changing topics fetched when they're subscribed
+    #[serde(default)]
+    pub initial_query: InitialQueryConfig,
+    #[serde(default)]
+    pub upload_deferral: UploadDeferralConfig,
+    #[serde(default)]
+    pub low_power: LowPowerConfig,
+    #[serde(default)]
+    pub power_events: PowerEventsConfig,
+    #[serde(default)]
+    pub data_bridge: DataBridgeConfig,
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    /// Time to live of recordings, pushed to the backend as labels
+    #[serde(default)]
+    pub expiry: ExpiryConfig,
+    #[serde(default)]
+    pub transforms: TransformsConfig,
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    #[serde(default)]
+    pub black_box: BlackBoxConfig,
+    /// Webhooks and commands run on recording events
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+    /// Upload of finished recordings to a remote ingestion API
+    #[serde(default)]
+    pub ingestion: IngestionConfig,
+    /// Keys never recorded, even when a recorded topic matches them
+    /// (`robot/**/debug/**`); Starts may add their own `exclude_topics`
+    #[serde(default)]
+    pub exclude_topics: Vec<String>,
+    /// Keys of JSON-object sample attachments copied to the labels of the
+    /// records (`mission_id`), to query records by them
+    #[serde(default)]
+    pub attachment_labels: Vec<String>,
+    /// Recorded topic names keyed by the requested topic (remapped topics
+    /// are stored, labeled and matched against per-topic sections by the
+    /// recorded name)
+    #[serde(default)]
+    pub topic_remap: HashMap<String, String>,
 }
 
 impl Default for RecorderSettings {
@@ -190,10 +514,73 @@ impl Default for RecorderSettings {
             workers: WorkerConfig::default(),
             control: ControlConfig::default(),
             schema: SchemaConfig::default(),
+            recovery: RecoveryConfig::default(),
+            timestamps: TimestampConfig::default(),
+            upload_limit: UploadLimitConfig::default(),
+            handoff: HandoffConfig::default(),
+            topic_stats: TopicStatsConfig::default(),
+            status_events: StatusEventsConfig::default(),
+            health: HealthConfig::default(),
+            integrity: IntegrityConfig::default(),
+            index: IndexConfig::default(),
+            coalesce: CoalesceConfig::default(),
+            controller_liveliness: ControllerLivelinessConfig::default(),
+            work_dir: WorkDirConfig::default(),
+            preview: PreviewConfig::default(),
+            tap: TapConfig::default(),
+            dedupe: DedupeConfig::default(),
+            topology: TopologyConfig::default(),
+            initial_query: InitialQueryConfig::default(),
+            upload_deferral: UploadDeferralConfig::default(),
+            low_power: LowPowerConfig::default(),
+            power_events: PowerEventsConfig::default(),
+            data_bridge: DataBridgeConfig::default(),
+            quotas: QuotaConfig::default(),
+            expiry: ExpiryConfig::default(),
+            transforms: TransformsConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            black_box: BlackBoxConfig::default(),
+            hooks: Vec::new(),
+            ingestion: IngestionConfig::default(),
+            exclude_topics: Vec::new(),
+            attachment_labels: Vec::new(),
+            topic_remap: HashMap::new(),
         }
     }
 }
 
+impl RecorderSettings {
+    /// Override the settings that keep the CPU awake when `low_power` is
+    /// enabled (applied on load; applying it again changes nothing)
+    pub fn apply_low_power(&mut self) {
+        let low_power = &self.low_power;
+        if !low_power.enabled {
+            return;
+        }
+        let flush_policy = &mut self.flush_policy;
+        flush_policy.max_buffer_duration_seconds = flush_policy
+            .max_buffer_duration_seconds
+            .max(low_power.flush_interval_seconds);
+        flush_policy.idle_trim_seconds = 0;
+        self.status_events.progress_interval_ms = 0;
+        self.schema.detect_drift = false;
+        self.preview.per_topic.clear();
+    }
+
+    /// Flush policy of a recording started with `requested` (None = the
+    /// configured `flush_policy.strategy`), low-power floor included
+    pub fn effective_flush_policy(&self, requested: Option<BufferStrategy>) -> FlushPolicy {
+        let mut flush_policy = self.flush_policy.with_strategy(requested);
+        if self.low_power.enabled {
+            flush_policy.max_buffer_duration_seconds = flush_policy
+                .max_buffer_duration_seconds
+                .max(self.low_power.flush_interval_seconds);
+            flush_policy.idle_trim_seconds = 0;
+        }
+        flush_policy
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FlushPolicy {
     /// Maximum buffer size in bytes before flush
@@ -205,6 +592,26 @@ pub struct FlushPolicy {
     /// Minimum samples before flush (avoid tiny flushes)
     #[serde(default = "default_min_samples")]
     pub min_samples_per_flush: usize,
+
+    /// Maximum payload bytes per stored record; larger flush batches are
+    /// split into several records (0 = no limit)
+    #[serde(default)]
+    pub max_record_size_bytes: usize,
+
+    /// Seconds without samples after which a topic's buffers release their
+    /// spare capacity (0 = never)
+    #[serde(default = "default_idle_trim_seconds")]
+    pub idle_trim_seconds: u64,
+
+    /// Pre-allocate the buffers for the samples expected per flush (learned
+    /// by `topic_stats`)
+    #[serde(default = "default_true")]
+    pub preallocate: bool,
+
+    /// Named strategy replacing the thresholds above ("low_latency",
+    /// "high_throughput" or "low_memory"); a Start may pick another one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<BufferStrategy>,
 }
 
 impl Default for FlushPolicy {
@@ -213,6 +620,10 @@ impl Default for FlushPolicy {
             max_buffer_size_bytes: 10485760, // 10 MB
             max_buffer_duration_seconds: 10, // 10 seconds
             min_samples_per_flush: default_min_samples(),
+            max_record_size_bytes: 0,
+            idle_trim_seconds: default_idle_trim_seconds(),
+            preallocate: true,
+            strategy: None,
         }
     }
 }
@@ -221,6 +632,30 @@ impl FlushPolicy {
     pub fn max_duration(&self) -> Duration {
         Duration::from_secs(self.max_buffer_duration_seconds)
     }
+
+    pub fn idle_trim(&self) -> Duration {
+        Duration::from_secs(self.idle_trim_seconds)
+    }
+
+    /// Thresholds of `strategy`, or of the configured strategy when None
+    /// (the policy as it is without either)
+    pub fn with_strategy(&self, strategy: Option<BufferStrategy>) -> FlushPolicy {
+        let mut policy = self.clone();
+        let Some(strategy) = strategy.or(self.strategy) else {
+            return policy;
+        };
+        let (size_bytes, duration_seconds, idle_trim_seconds, preallocate) = match strategy {
+            BufferStrategy::LowLatency => (512 * 1024, 1, 60, true),
+            BufferStrategy::HighThroughput => (64 * 1024 * 1024, 60, 0, true),
+            BufferStrategy::LowMemory => (1024 * 1024, 5, 10, false),
+        };
+        policy.max_buffer_size_bytes = size_bytes;
+        policy.max_buffer_duration_seconds = duration_seconds;
+        policy.idle_trim_seconds = idle_trim_seconds;
+        policy.preallocate = preallocate;
+        policy.strategy = Some(strategy);
+        policy
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -230,6 +665,25 @@ pub struct CompressionConfig {
 
     #[serde(default)]
     pub per_topic: HashMap<String, TopicCompression>,
+
+    /// Uncompressed bytes per frame of Zstd batches, which are written in the
+    /// seekable format so time ranges can be read without decompressing the
+    /// whole batch (0 = a single frame)
+    #[serde(default = "default_seekable_frame_bytes")]
+    pub seekable_frame_bytes: usize,
+
+    /// Highest Zstd level a recording may compress with, e.g. 10 to forbid
+    /// the 19 of `Slowest` on a low-power device (none by default)
+    #[serde(default)]
+    pub max_zstd_level: Option<i32>,
+
+    /// Highest LZ4 level a recording may compress with (none by default)
+    #[serde(default)]
+    pub max_lz4_level: Option<u32>,
+
+    /// What a Start asking for a level above these limits gets
+    #[serde(default)]
+    pub over_limit: CompressionLimitAction,
 }
 
 impl Default for CompressionConfig {
@@ -238,21 +692,110 @@ impl Default for CompressionConfig {
             default_type: "zstd".to_string(),
             default_level: 2,
             per_topic: HashMap::new(),
+            seekable_frame_bytes: default_seekable_frame_bytes(),
+            max_zstd_level: None,
+            max_lz4_level: None,
+            over_limit: CompressionLimitAction::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionLimitAction {
+    /// Reject the Start
+    #[default]
+    Reject,
+    /// Start with the slowest level within the limits
+    Clamp,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TopicCompression {
     pub r#type: String,
     pub level: u8,
+
+    /// Payload transform applied before compression
+    #[serde(default)]
+    pub transform: Option<TransformConfig>,
+}
+
+impl TopicCompression {
+    /// Compression of the topic's batches, in place of the recording's
+    /// (None when `type` or `level` is invalid)
+    pub fn settings(&self) -> Option<(CompressionType, CompressionLevel)> {
+        Some((
+            CompressionType::from_name(&self.r#type)?,
+            CompressionLevel::from_index(self.level)?,
+        ))
+    }
+}
+
+/// Pre-compression payload transform of a topic
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransformConfig {
+    /// Every `keyframe_interval`-th sample of a batch as published, the
+    /// others XORed with the previous sample
+    Delta {
+        #[serde(default = "default_keyframe_interval")]
+        keyframe_interval: usize,
+    },
+}
+
+/// Sample filters and redactions applied before storage
+///
+/// Unlike `compression.per_topic.*.transform`, these change what is recorded:
+/// dropped samples and redacted bytes can't be recovered.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TransformsConfig {
+    /// Transforms applied in order, keyed by topic pattern (the most specific
+    /// pattern matching a topic wins)
+    #[serde(default)]
+    pub per_topic: HashMap<String, Vec<SampleTransformConfig>>,
+}
+
+/// Built-in sample transform
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SampleTransformConfig {
+    /// Drop the samples matching every condition given
+    Drop {
+        /// Payloads of at least this many bytes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_bytes: Option<usize>,
+        /// JSON payloads with this dot-separated field...
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        json_field: Option<String>,
+        /// ...set to this value (any value when unset)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        equals: Option<serde_json::Value>,
+    },
+    /// Keep only the JSON samples for which `expression` holds, e.g.
+    /// `payload.speed > 2.0` (see `crate::sample_filter`)
+    Filter { expression: String },
+    /// Cut payloads to their first `max_bytes`
+    Truncate { max_bytes: usize },
+    /// Overwrite the byte ranges `[start, end)` of payloads with `fill`
+    RedactBytes {
+        ranges: Vec<[usize; 2]>,
+        #[serde(default)]
+        fill: u8,
+    },
+    /// Replace the dot-separated fields of JSON payloads with `replacement`
+    /// (null when unset)
+    RedactJson {
+        fields: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        replacement: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SchemaConfig {
     /// Default format for messages without explicit schema
     #[serde(default = "default_schema_format")]
-    pub default_format: String, // "raw", "protobuf", "json", etc.
+    pub default_format: String, // "raw", "protobuf", "json", etc., or "auto" to detect it
 
     /// Whether to include schema metadata in recordings
     #[serde(default)]
@@ -261,6 +804,32 @@ pub struct SchemaConfig {
     /// Per-topic schema information
     #[serde(default)]
     pub per_topic: HashMap<String, TopicSchemaInfo>,
+
+    /// Directory holding ROS 2 `.msg` definitions (`{pkg}/msg/{Type}.msg`),
+    /// embedded for topics with `format = "cdr"`
+    #[serde(default)]
+    pub ros2_msg_dir: Option<String>,
+
+    /// Detect payload structure changes between flushed segments
+    #[serde(default)]
+    pub detect_drift: bool,
+
+    /// Number of samples per segment inspected for drift detection
+    #[serde(default = "default_drift_samples")]
+    pub drift_samples_per_batch: usize,
+
+    /// Protobuf `FileDescriptorSet` files (or directories of them) embedded
+    /// for topics with `format = "protobuf"`
+    #[serde(default)]
+    pub descriptor_sets: Vec<String>,
+
+    /// Zenoh key expression queried at startup for `FileDescriptorSet` blobs
+    #[serde(default)]
+    pub descriptor_key: Option<String>,
+
+    /// Timeout of the descriptor query in milliseconds
+    #[serde(default = "default_descriptor_timeout_ms")]
+    pub descriptor_timeout_ms: u64,
 }
 
 impl Default for SchemaConfig {
@@ -269,15 +838,21 @@ impl Default for SchemaConfig {
             default_format: default_schema_format(),
             include_metadata: false,
             per_topic: HashMap::new(),
+            ros2_msg_dir: None,
+            detect_drift: false,
+            drift_samples_per_batch: default_drift_samples(),
+            descriptor_sets: vec![],
+            descriptor_key: None,
+            descriptor_timeout_ms: default_descriptor_timeout_ms(),
         }
     }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TopicSchemaInfo {
-    pub format: String, // "protobuf", "json", "msgpack", "raw"
+    pub format: String, // "protobuf", "json", "msgpack", "cdr", "raw"
     #[serde(default)]
-    pub schema_name: Option<String>, // e.g., "sensor_msgs/Image"
+    pub schema_name: Option<String>, // e.g., "sensor_msgs/Image" (ROS 2 type name for "cdr", full message name for "protobuf")
     #[serde(default)]
     pub schema_hash: Option<String>, // Optional version hash
 }
@@ -287,8 +862,43 @@ pub struct WorkerConfig {
     #[serde(default = "default_flush_workers")]
     pub flush_workers: usize,
 
+    /// Pending flush tasks per worker
     #[serde(default = "default_queue_capacity")]
     pub queue_capacity: usize,
+
+    /// Batches serialized and compressed at once, off the async executor
+    #[serde(default = "default_compression_threads")]
+    pub compression_threads: usize,
+
+    /// Time each shutdown stage gives its background tasks to end before
+    /// they're aborted
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+
+    /// Time a shutdown gives the recordings to finish uploading before what's
+    /// left of them is spilled to the work directory (0 = wait until uploaded)
+    #[serde(default)]
+    pub shutdown_deadline_seconds: u64,
+
+    /// Priority class of the flush tasks of each topic pattern (others are
+    /// "normal"); with a worker's queue full, higher classes are written
+    /// first and lower ones dropped first
+    #[serde(default)]
+    pub flush_priority: HashMap<String, FlushPriority>,
+}
+
+/// Priority class of the flush tasks of a topic
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FlushPriority {
+    /// Bulk data (video, point clouds), dropped first when the queue is full
+    Low,
+    #[default]
+    Normal,
+    /// Safety logs and events, written before everything else
+    High,
 }
 
 impl Default for WorkerConfig {
@@ -296,87 +906,1471 @@ impl Default for WorkerConfig {
         Self {
             flush_workers: default_flush_workers(),
             queue_capacity: default_queue_capacity(),
+            compression_threads: default_compression_threads(),
+            shutdown_timeout_ms: default_shutdown_timeout_ms(),
+            shutdown_deadline_seconds: 0,
+            flush_priority: HashMap::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ControlConfig {
-    #[serde(default = "default_control_prefix")]
-    pub key_prefix: String,
+impl WorkerConfig {
+    /// Priority class of the flush tasks of `topic`
+    pub fn flush_priority(&self, topic: &str) -> FlushPriority {
+        find_per_topic(&self.flush_priority, topic)
+            .copied()
+            .unwrap_or_default()
+    }
 
-    #[serde(default = "default_status_key")]
-    pub status_key: String,
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_millis(self.shutdown_timeout_ms)
+    }
 
-    #[serde(default = "default_control_timeout")]
+    /// Deadline of a shutdown (None = wait until uploaded)
+    pub fn shutdown_deadline(&self) -> Option<Duration> {
+        (self.shutdown_deadline_seconds > 0)
+            .then(|| Duration::from_secs(self.shutdown_deadline_seconds))
+    }
+}
+
+/// Backend upload limits (0 = unlimited), adjustable at runtime
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct UploadLimitConfig {
+    /// Maximum upload bandwidth in bytes per second
+    #[serde(default)]
+    pub max_bytes_per_sec: u64,
+
+    /// Maximum number of concurrent backend uploads
+    #[serde(default)]
+    pub max_concurrent_uploads: usize,
+}
+
+/// Handoff of active recordings between two recorder processes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HandoffConfig {
+    /// Timeout of each handoff step (offer, subscriptions ready, ack)
+    #[serde(default = "default_handoff_timeout")]
     pub timeout_seconds: u64,
+
+    /// Shut down once the active recordings were handed off to a successor
+    #[serde(default = "default_true")]
+    pub exit_after_handoff: bool,
+
+    /// Taking over as successor: restart the recordings under new ids
+    /// (`split`) or continue them under their ids (`continue`)
+    #[serde(default)]
+    pub mode: HandoffMode,
 }
 
-impl Default for ControlConfig {
+impl Default for HandoffConfig {
     fn default() -> Self {
         Self {
-            key_prefix: default_control_prefix(),
-            status_key: default_status_key(),
-            timeout_seconds: default_control_timeout(),
+            timeout_seconds: default_handoff_timeout(),
+            exit_after_handoff: true,
+            mode: HandoffMode::default(),
         }
     }
 }
 
+/// Per-topic statistics kept across recordings
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct LoggingConfig {
-    #[serde(default = "default_log_level")]
-    pub level: String, // "trace", "debug", "info", "warn", "error"
+pub struct TopicStatsConfig {
+    /// Learn per-topic rates, sample sizes and compression ratios
+    #[serde(default)]
+    pub enabled: bool,
 
-    #[serde(default = "default_log_format")]
-    pub format: String, // "text", "json"
+    /// JSON file holding the statistics
+    #[serde(default = "default_topic_stats_path")]
+    pub path: String,
+
+    /// Weight of the latest recording in the rolling averages (0-1]
+    #[serde(default = "default_topic_stats_smoothing")]
+    pub smoothing: f64,
+
+    /// Store topics uncompressed once their learned compression ratio
+    /// (stored / raw bytes) is at least this value (0 = never)
+    #[serde(default = "default_incompressible_ratio")]
+    pub incompressible_ratio: f64,
 }
 
-impl Default for LoggingConfig {
+impl Default for TopicStatsConfig {
     fn default() -> Self {
         Self {
-            level: default_log_level(),
-            format: default_log_format(),
+            enabled: false,
+            path: default_topic_stats_path(),
+            smoothing: default_topic_stats_smoothing(),
+            incompressible_ratio: default_incompressible_ratio(),
         }
     }
 }
 
-// Default value functions
-fn default_mode() -> String {
-    "peer".to_string()
-}
-fn default_timeout() -> u64 {
-    300
+/// Publication of recording status changes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatusEventsConfig {
+    /// Publish a status event on every state change of a recording
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Events go to `{key_prefix}/{device_id}/{recording_id}`
+    #[serde(default = "default_status_events_prefix")]
+    pub key_prefix: String,
+
+    /// Maximum publications per second and recording; updates in between are
+    /// coalesced and only the latest is published (0 = unlimited)
+    #[serde(default = "default_status_events_rate")]
+    pub max_per_sec: f64,
+
+    /// Publish upload progress this often while a record upload is running
+    /// (0 = only on state changes)
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
 }
-fn default_retries() -> u32 {
-    3
+
+impl StatusEventsConfig {
+    /// Interval of progress events, `None` when disabled
+    pub fn progress_interval(&self) -> Option<Duration> {
+        (self.enabled && self.progress_interval_ms > 0)
+            .then(|| Duration::from_millis(self.progress_interval_ms))
+    }
 }
-fn default_min_samples() -> usize {
-    10
+
+impl Default for StatusEventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_prefix: default_status_events_prefix(),
+            max_per_sec: default_status_events_rate(),
+            progress_interval_ms: default_progress_interval_ms(),
+        }
+    }
 }
-fn default_flush_workers() -> usize {
-    4
+
+/// Index records of the stored batches
+///
+/// Every batch gets a record at the same timestamp in `{entry}__index` with
+/// its time range, message count and message offsets (see
+/// `crate::batch_index`).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IndexConfig {
+    /// Write an index record after every stored batch
+    #[serde(default)]
+    pub enabled: bool,
 }
-fn default_queue_capacity() -> usize {
-    1000
+
+/// Coalescing of small batches across topics
+///
+/// Batches below `max_batch_bytes` are collected per recording and stored as
+/// one record of the `__coalesced` entry (see `crate::coalesce`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CoalesceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Batches smaller than this are coalesced
+    #[serde(default = "default_coalesce_max_batch_bytes")]
+    pub max_batch_bytes: usize,
+
+    /// A coalesced record is stored once its batches reach this size
+    #[serde(default = "default_coalesce_max_record_bytes")]
+    pub max_record_bytes: usize,
+
+    /// ... or this long after its first batch
+    #[serde(default = "default_coalesce_interval_ms")]
+    pub interval_ms: u64,
 }
-fn default_control_prefix() -> String {
-    "recorder/control".to_string()
+
+impl CoalesceConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
 }
-fn default_status_key() -> String {
-    "recorder/status/**".to_string()
+
+impl Default for CoalesceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_batch_bytes: default_coalesce_max_batch_bytes(),
+            max_record_bytes: default_coalesce_max_record_bytes(),
+            interval_ms: default_coalesce_interval_ms(),
+        }
+    }
 }
-fn default_control_timeout() -> u64 {
+
+/// Zenoh queries for the latest value of topics when a recording subscribes
+/// to them
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InitialQueryConfig {
+    /// Topics (or patterns) whose latest value is queried; replies from
+    /// storages and queryables are recorded like samples (empty = none)
+    #[serde(default)]
+    pub topics: Vec<String>,
+
+    /// How long to wait for the replies
+    #[serde(default = "default_initial_query_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl InitialQueryConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for InitialQueryConfig {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            timeout_ms: default_initial_query_timeout_ms(),
+        }
+    }
+}
+
+/// Recorder health announcements
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthConfig {
+    /// Declare the liveliness token `{key_prefix}/alive/{device_id}` and
+    /// publish heartbeats
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Heartbeats go to `{key_prefix}/health/{device_id}`
+    #[serde(default = "default_health_prefix")]
+    pub key_prefix: String,
+
+    /// Publish a heartbeat this often (0 = only the liveliness token)
+    #[serde(default = "default_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+}
+
+impl HealthConfig {
+    /// Interval of the heartbeats, `None` when disabled
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        (self.enabled && self.heartbeat_interval_ms > 0)
+            .then(|| Duration::from_millis(self.heartbeat_interval_ms))
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_prefix: default_health_prefix(),
+            heartbeat_interval_ms: default_heartbeat_interval_ms(),
+        }
+    }
+}
+
+/// Time to live of recordings
+///
+/// The records of a recording with a TTL carry an `expires_at` label (Unix
+/// time in seconds), and a background sweep removes the expired records.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExpiryConfig {
+    /// Accept `retention_seconds` on Start and sweep expired records
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// TTL of recordings whose Start has no `retention_seconds` (0 = kept)
+    #[serde(default)]
+    pub default_ttl_seconds: u64,
+
+    /// Longest TTL a Start may ask for (0 = no limit)
+    #[serde(default)]
+    pub max_ttl_seconds: u64,
+
+    /// Interval of the sweep (0 = records are only labeled, for an external
+    /// cleanup)
+    #[serde(default = "default_expiry_sweep_interval")]
+    pub sweep_interval_seconds: u64,
+}
+
+impl ExpiryConfig {
+    /// Interval of the sweep, `None` when disabled
+    pub fn sweep_interval(&self) -> Option<Duration> {
+        (self.enabled && self.sweep_interval_seconds > 0)
+            .then(|| Duration::from_secs(self.sweep_interval_seconds))
+    }
+}
+
+impl Default for ExpiryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_ttl_seconds: 0,
+            max_ttl_seconds: 0,
+            sweep_interval_seconds: default_expiry_sweep_interval(),
+        }
+    }
+}
+
+/// Read-back verification of uploaded records
+///
+/// Every record carries a CRC32C of its bytes in the `crc32c` label and in the
+/// recording metadata; verification reads a sample of them back after upload.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IntegrityConfig {
+    /// Read back every Nth uploaded record of a recording (0 = never)
+    #[serde(default)]
+    pub verify_every: u64,
+
+    /// Share of the uploaded records to read back, in percent (0-100)
+    #[serde(default)]
+    pub verify_percent: f64,
+}
+
+impl IntegrityConfig {
+    /// Whether the `batch`th record of a recording (1-based) is read back
+    ///
+    /// `verify_percent` picks records spread evenly over the recording, so
+    /// the extra reads stay within the configured share.
+    pub fn should_verify(&self, batch: u64) -> bool {
+        if self.verify_every > 0 && batch.is_multiple_of(self.verify_every) {
+            return true;
+        }
+        let share = self.verify_percent / 100.0;
+        share > 0.0
+            && (batch as f64 * share).floor() > (batch.saturating_sub(1) as f64 * share).floor()
+    }
+}
+
+/// Dead-man switch for recordings started with a controller liveliness key
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControllerLivelinessConfig {
+    /// How long the controller's liveliness token may be gone before acting
+    #[serde(default = "default_controller_grace_period")]
+    pub grace_period_seconds: u64,
+
+    /// What happens to the recording once the grace period is over
+    #[serde(default)]
+    pub on_lost: ControllerLostAction,
+}
+
+impl Default for ControllerLivelinessConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_seconds: default_controller_grace_period(),
+            on_lost: ControllerLostAction::default(),
+        }
+    }
+}
+
+impl ControllerLivelinessConfig {
+    pub fn grace_period(&self) -> Duration {
+        Duration::from_secs(self.grace_period_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControllerLostAction {
+    /// Finish the recording
+    #[default]
+    Finish,
+    /// Pause the recording and resume it when the controller is back
+    Pause,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControlConfig {
+    #[serde(default = "default_control_prefix")]
+    pub key_prefix: String,
+
+    #[serde(default = "default_status_key")]
+    pub status_key: String,
+
+    #[serde(default = "default_control_timeout")]
+    pub timeout_seconds: u64,
+
+    /// Listen address of the gRPC control server, e.g. "0.0.0.0:50051"
+    /// (needs the `grpc` feature; off when unset)
+    #[serde(default)]
+    pub grpc_listen: Option<String>,
+
+    /// Listen address of the HTTP status dashboard, e.g. "0.0.0.0:8080"
+    /// (needs the `dashboard` feature; off when unset)
+    #[serde(default)]
+    pub http_listen: Option<String>,
+
+    /// Credentials and permissions required of control requests
+    #[serde(default)]
+    pub auth: ControlAuthConfig,
+
+    /// Groups whose Start and Finish commands this recorder follows, sent to
+    /// `recorder/control/group/{group}`
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    /// Furthest ahead a group command may be scheduled with `execute_at_ns`,
+    /// in milliseconds
+    #[serde(default = "default_group_max_delay_ms")]
+    pub group_max_delay_ms: u64,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            key_prefix: default_control_prefix(),
+            status_key: default_status_key(),
+            timeout_seconds: default_control_timeout(),
+            grpc_listen: None,
+            http_listen: None,
+            auth: ControlAuthConfig::default(),
+            groups: vec![],
+            group_max_delay_ms: default_group_max_delay_ms(),
+        }
+    }
+}
+
+/// Authentication of control, status and handoff requests
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControlAuthConfig {
+    /// Reject requests without valid credentials (off: everyone may do anything)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How far the timestamp of an HMAC-signed request may be off the clock
+    #[serde(default = "default_max_clock_skew")]
+    pub max_clock_skew_seconds: u64,
+
+    /// Principal whose credentials a successor recorder signs its handoff
+    /// queries with (needs the admin role; handoffs fail when unset)
+    #[serde(default)]
+    pub handoff_principal: Option<String>,
+
+    #[serde(default)]
+    pub principals: Vec<ControlPrincipal>,
+}
+
+impl Default for ControlAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clock_skew_seconds: default_max_clock_skew(),
+            handoff_principal: None,
+            principals: Vec::new(),
+        }
+    }
+}
+
+/// Client allowed to send control requests
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ControlPrincipal {
+    pub name: String,
+
+    /// Bearer token sent with every request
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// HMAC-SHA256 key of signed requests (the secret itself is never sent)
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    #[serde(default)]
+    pub role: ControlRole,
+}
+
+/// What a principal may do; each role includes the ones before it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlRole {
+    /// Status, drift reports, estimates and `wait_for_completion`
+    #[default]
+    Observer,
+    /// Start, pause, resume and finish recordings and their topics
+    Operator,
+    /// Cancel recordings, change upload limits, reload and hand off
+    Admin,
+}
+
+/// Low-rate preview channels recorded next to heavy topics
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct PreviewConfig {
+    /// Topics that get a preview (exact topics or `*`/`**` patterns)
+    #[serde(default)]
+    pub per_topic: HashMap<String, PreviewPolicy>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PreviewPolicy {
+    /// At most one sample per interval goes into the preview
+    #[serde(default = "default_preview_interval_ms")]
+    pub interval_ms: u64,
+}
+
+impl PreviewPolicy {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+/// Live tap of recorded topics
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapConfig {
+    /// Re-publish tapped samples on `{key_prefix}/{recording_id}/{key}`
+    /// (library receivers get them either way)
+    #[serde(default)]
+    pub publish: bool,
+
+    #[serde(default = "default_tap_prefix")]
+    pub key_prefix: String,
+
+    /// Tapped samples held for each receiver; receivers that fall further
+    /// behind miss the oldest
+    #[serde(default = "default_tap_channel_capacity")]
+    pub channel_capacity: usize,
+
+    /// Topics that are tapped (exact topics or `*`/`**` patterns)
+    #[serde(default)]
+    pub per_topic: HashMap<String, TapPolicy>,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        Self {
+            publish: false,
+            key_prefix: default_tap_prefix(),
+            channel_capacity: default_tap_channel_capacity(),
+            per_topic: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TapPolicy {
+    /// At most one sample per interval is tapped
+    #[serde(default = "default_tap_interval_ms")]
+    pub interval_ms: u64,
+}
+
+impl TapPolicy {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+/// Topics recorded only when their payload changes
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct DedupeConfig {
+    /// Topics that skip repeated samples (exact topics or `*`/`**` patterns)
+    #[serde(default)]
+    pub per_topic: HashMap<String, DedupePolicy>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DedupePolicy {
+    /// A repeated sample is still recorded once this long after the last one
+    /// recorded, so a quiet topic shows it is alive (0 = never)
+    #[serde(default = "default_dedupe_heartbeat_ms")]
+    pub heartbeat_ms: u64,
+}
+
+impl Default for DedupePolicy {
+    fn default() -> Self {
+        Self {
+            heartbeat_ms: default_dedupe_heartbeat_ms(),
+        }
+    }
+}
+
+impl DedupePolicy {
+    /// Interval of the heartbeat samples (None = no heartbeat)
+    pub fn heartbeat(&self) -> Option<Duration> {
+        (self.heartbeat_ms > 0).then(|| Duration::from_millis(self.heartbeat_ms))
+    }
+}
+
+/// Snapshot of the Zenoh topology stored with each recording
+///
+/// Taken from the Zenoh admin space, which is enabled on the recorder's
+/// session when `snapshot` is set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TopologyConfig {
+    /// Store the publishers and connected nodes seen at Start in the metadata
+    #[serde(default)]
+    pub snapshot: bool,
+
+    /// How long Start waits for the admin space to answer
+    #[serde(default = "default_topology_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for TopologyConfig {
+    fn default() -> Self {
+        Self {
+            snapshot: false,
+            timeout_ms: default_topology_timeout_ms(),
+        }
+    }
+}
+
+impl TopologyConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// One-off captures of the latest sample of each topic (`snapshot` command)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnapshotConfig {
+    /// Topics captured when a `snapshot` request lists none
+    #[serde(default)]
+    pub topics: Vec<String>,
+
+    /// How long a snapshot waits for the next sample of topics no recording
+    /// is subscribed to (or that haven't received one yet)
+    #[serde(default = "default_snapshot_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            timeout_ms: default_snapshot_timeout_ms(),
+        }
+    }
+}
+
+impl SnapshotConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// Topics kept in memory at all times, stored as a recording on `dump`
+///
+/// Each topic has a ring of its latest samples, bounded by size and age;
+/// nothing is written to storage until a `dump` request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlackBoxConfig {
+    /// Topics with a ring (none = black box off)
+    #[serde(default)]
+    pub topics: Vec<String>,
+
+    /// Payload bytes kept per topic; the oldest samples are dropped first
+    #[serde(default = "default_black_box_max_bytes")]
+    pub max_bytes_per_topic: usize,
+
+    /// Samples older than this are dropped (0 = kept until the ring is full)
+    #[serde(default = "default_black_box_max_age_seconds")]
+    pub max_age_seconds: u64,
+}
+
+impl Default for BlackBoxConfig {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            max_bytes_per_topic: default_black_box_max_bytes(),
+            max_age_seconds: default_black_box_max_age_seconds(),
+        }
+    }
+}
+
+impl BlackBoxConfig {
+    /// Age limit of the samples (None = no limit)
+    pub fn max_age(&self) -> Option<Duration> {
+        (self.max_age_seconds > 0).then(|| Duration::from_secs(self.max_age_seconds))
+    }
+}
+
+/// Hook run on recording events
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HookConfig {
+    /// Events running the hook (all when empty)
+    #[serde(default)]
+    pub events: Vec<RecordingEventKind>,
+
+    #[serde(flatten)]
+    pub action: HookAction,
+
+    /// Time a webhook request or command may take before it is abandoned
+    #[serde(default = "default_hook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl HookConfig {
+    pub fn fires_on(&self, event: RecordingEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+/// What a hook does with the `RecordingEvent` JSON
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HookAction {
+    /// POST it to `url`
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Run `command` (program and arguments) with it on stdin
+    Exec { command: Vec<String> },
+}
+
+/// Upload of finished recordings to a remote ingestion API
+///
+/// The manifest (the recording metadata) is pushed to `endpoint` once the
+/// recording is finished, after its records with `include_data`; the API
+/// accepting it marks the recording `Archived`. Requests carry the static
+/// `token`, or a token obtained from `token_url` with the OAuth 2.0 client
+/// credentials grant.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IngestionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Base URL of the API
+    #[serde(default)]
+    pub endpoint: String,
+
+    /// Upload the records too, not only the manifest
+    #[serde(default)]
+    pub include_data: bool,
+
+    /// Static bearer token
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// OAuth 2.0 token endpoint, used with `client_id` and `client_secret`
+    #[serde(default)]
+    pub token_url: Option<String>,
+
+    #[serde(default)]
+    pub client_id: Option<String>,
+
+    #[serde(default)]
+    pub client_secret: Option<String>,
+
+    /// Scope of the requested tokens
+    #[serde(default)]
+    pub scope: Option<String>,
+
+    /// Record bytes sent per request
+    #[serde(default = "default_ingestion_chunk_size_bytes")]
+    pub chunk_size_bytes: u64,
+
+    /// Retries of a failed request, with a doubling delay
+    #[serde(default = "default_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry
+    #[serde(default = "default_ingestion_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+
+    /// Time a request may take before it is abandoned
+    #[serde(default = "default_ingestion_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for IngestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            include_data: false,
+            token: None,
+            token_url: None,
+            client_id: None,
+            client_secret: None,
+            scope: None,
+            chunk_size_bytes: default_ingestion_chunk_size_bytes(),
+            max_retries: default_retries(),
+            retry_delay_ms: default_ingestion_retry_delay_ms(),
+            timeout_ms: default_ingestion_timeout_ms(),
+        }
+    }
+}
+
+impl IngestionConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    pub fn retry_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_delay_ms)
+    }
+}
+
+/// Profile for battery-powered loggers
+///
+/// Flushes less often, turns off progress events, previews, drift detection
+/// and idle trimming, and aligns the recorder's timers to a common tick so
+/// they wake the CPU together.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LowPowerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Buffers are flushed at most this often (raises
+    /// `flush_policy.max_buffer_duration_seconds`)
+    #[serde(default = "default_low_power_flush_interval")]
+    pub flush_interval_seconds: u64,
+
+    /// Timers fire on multiples of this interval of the wall clock (0 = as
+    /// scheduled)
+    #[serde(default = "default_wakeup_granularity_ms")]
+    pub wakeup_granularity_ms: u64,
+}
+
+impl Default for LowPowerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            flush_interval_seconds: default_low_power_flush_interval(),
+            wakeup_granularity_ms: default_wakeup_granularity_ms(),
+        }
+    }
+}
+
+impl LowPowerConfig {
+    /// Tick timers are aligned to, `None` when disabled
+    pub fn wakeup_granularity(&self) -> Option<Duration> {
+        (self.enabled && self.wakeup_granularity_ms > 0)
+            .then(|| Duration::from_millis(self.wakeup_granularity_ms))
+    }
+}
+
+/// Emergency flush when power is about to be lost
+///
+/// Listens for systemd-logind shutdown and UPower battery signals on the
+/// system D-Bus (Linux, `power-events` feature).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PowerEventsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Flush on logind's `PrepareForShutdown`, delaying the shutdown with an
+    /// inhibitor lock until done
+    #[serde(default = "default_true")]
+    pub on_shutdown: bool,
+
+    /// Flush when the battery discharges to this percentage (0 = never)
+    #[serde(default = "default_low_battery_percent")]
+    pub low_battery_percent: f64,
+
+    /// Longest wait for the flushed records to be written
+    #[serde(default = "default_emergency_flush_timeout")]
+    pub flush_timeout_seconds: u64,
+}
+
+impl Default for PowerEventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            on_shutdown: true,
+            low_battery_percent: default_low_battery_percent(),
+            flush_timeout_seconds: default_emergency_flush_timeout(),
+        }
+    }
+}
+
+/// Stored data served back over Zenoh
+///
+/// Answers queries on `{key_prefix}/{recording_id}/{topic}?from=..;to=..`
+/// with the recorded messages read from the storage backend.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DataBridgeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_data_bridge_prefix")]
+    pub key_prefix: String,
+
+    /// Serialized messages per reply, at most (a larger message gets a reply
+    /// of its own)
+    #[serde(default = "default_data_bridge_page_bytes")]
+    pub page_bytes: usize,
+}
+
+impl Default for DataBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_prefix: default_data_bridge_prefix(),
+            page_bytes: default_data_bridge_page_bytes(),
+        }
+    }
+}
+
+/// Recording quotas per organization and task
+///
+/// Recordings are counted against the `organization` and `task_id` of their
+/// Start request. A Start over a limit is refused, and recordings of a scope
+/// that used up its bytes for the (UTC) day are finished.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct QuotaConfig {
+    /// Limits of organizations without an entry in `organizations`
+    #[serde(default)]
+    pub default_organization: QuotaLimits,
+
+    /// Limits keyed by organization
+    #[serde(default)]
+    pub organizations: HashMap<String, QuotaLimits>,
+
+    /// Limits keyed by task ID
+    #[serde(default)]
+    pub tasks: HashMap<String, QuotaLimits>,
+}
+
+impl QuotaConfig {
+    /// Limits of an organization
+    pub fn organization(&self, organization: &str) -> QuotaLimits {
+        self.organizations
+            .get(organization)
+            .copied()
+            .unwrap_or(self.default_organization)
+    }
+
+    /// Limits of a task
+    pub fn task(&self, task_id: &str) -> QuotaLimits {
+        self.tasks.get(task_id).copied().unwrap_or_default()
+    }
+
+    /// Whether any limit is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.default_organization.is_unlimited()
+            || self.organizations.values().any(|l| !l.is_unlimited())
+            || self.tasks.values().any(|l| !l.is_unlimited())
+    }
+}
+
+/// Limits of one organization or task (0 = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QuotaLimits {
+    /// Recordings recording or paused at the same time
+    #[serde(default)]
+    pub max_concurrent_recordings: usize,
+
+    /// Bytes stored per UTC day
+    #[serde(default)]
+    pub max_bytes_per_day: u64,
+}
+
+impl QuotaLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_concurrent_recordings == 0 && self.max_bytes_per_day == 0
+    }
+}
+
+/// Upload deferral to off-peak hours or good connectivity
+///
+/// While uploads are deferred, records are kept in the work directory
+/// (`work_dir.enabled` must be set) and uploaded once the conditions are met.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UploadDeferralConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Local-time windows uploads are allowed in, e.g. "22:00-06:00"
+    /// (empty = any time)
+    #[serde(default)]
+    pub windows: Vec<String>,
+
+    /// Connectivity checks; uploads need one of them to pass (empty = none)
+    #[serde(default)]
+    pub probes: Vec<ConnectivityProbe>,
+
+    /// How often the windows and probes are checked
+    #[serde(default = "default_deferral_check_interval")]
+    pub check_interval_seconds: u64,
+}
+
+impl Default for UploadDeferralConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            windows: vec![],
+            probes: vec![],
+            check_interval_seconds: default_deferral_check_interval(),
+        }
+    }
+}
+
+impl UploadDeferralConfig {
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_seconds)
+    }
+}
+
+/// Check for a good link to the storage backend
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConnectivityProbe {
+    /// A network interface (e.g. "wlan0", "eth0") is up
+    Interface { name: String },
+    /// A TCP connection to `address` ("host:port") opens within the timeout
+    Tcp {
+        address: String,
+        #[serde(default = "default_probe_timeout_ms")]
+        timeout_ms: u64,
+    },
+}
+
+/// Where record timestamps come from, and handling of samples published
+/// without a Zenoh timestamp
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct TimestampConfig {
+    /// Policy for topics without a per-topic override
+    #[serde(default)]
+    pub default: TimestampPolicy,
+
+    /// Per-topic overrides (exact topics or `*`/`**` patterns)
+    #[serde(default)]
+    pub per_topic: HashMap<String, TimestampPolicy>,
+
+    /// How records of an entry flushed within the same microsecond are kept
+    /// apart
+    #[serde(default)]
+    pub collision_strategy: CollisionStrategy,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionStrategy {
+    /// Move colliding records forward a microsecond at a time
+    #[default]
+    Bump,
+    /// Stamp records with the millisecond and number them within it
+    Sequence,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct TimestampPolicy {
+    /// Clock the record timestamps are taken from
+    #[serde(default)]
+    pub source: TimestampSource,
+
+    /// What to do with samples that carry no publisher timestamp
+    #[serde(default)]
+    pub missing: MissingTimestampPolicy,
+
+    /// Dot-separated JSON field holding the timestamp (for `payload_field`)
+    #[serde(default)]
+    pub payload_field: Option<String>,
+
+    /// Unit of the payload timestamp field
+    #[serde(default)]
+    pub payload_field_unit: TimestampUnit,
+
+    /// Added to the record timestamps of the topic when serializing (ns),
+    /// e.g. the NTP/PTP offset measured for the publisher's clock
+    #[serde(default)]
+    pub clock_offset_ns: i64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSource {
+    /// The publisher's Zenoh timestamp; samples without one follow `missing`
+    #[default]
+    Sample,
+    /// The recorder's reception time
+    ReceiveTime,
+    /// The JSON payload field `payload_field`; samples without it fall back
+    /// to the Zenoh timestamp
+    PayloadField,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingTimestampPolicy {
+    /// Stamp the sample with the recorder's reception time
+    #[default]
+    ReceiveTime,
+    /// Drop the sample
+    Reject,
+    /// Read the timestamp from a JSON payload field, falling back to reception time
+    PayloadField,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampUnit {
+    #[default]
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl TimestampUnit {
+    /// Number of nanoseconds in one unit
+    pub fn nanos_per_unit(self) -> u64 {
+        match self {
+            TimestampUnit::Ns => 1,
+            TimestampUnit::Us => 1_000,
+            TimestampUnit::Ms => 1_000_000,
+            TimestampUnit::S => 1_000_000_000,
+        }
+    }
+
+    /// Convert a value expressed in this unit to nanoseconds
+    pub fn to_nanos(self, value: f64) -> u64 {
+        (value * self.nanos_per_unit() as f64) as u64
+    }
+}
+
+/// Crash recovery of in-flight recordings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecoveryConfig {
+    /// Persist session state so interrupted recordings can be recovered on restart
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Directory holding one state file per active recording
+    #[serde(default = "default_state_dir")]
+    pub state_dir: String,
+
+    /// Resume subscriptions of interrupted recordings under the same recording_id
+    /// instead of finalizing them
+    #[serde(default)]
+    pub resume: bool,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state_dir: default_state_dir(),
+            resume: false,
+        }
+    }
+}
+
+/// Per-recording scratch directories
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkDirConfig {
+    /// Give every recording a scratch directory; records whose upload fails
+    /// after all retries are spilled to it instead of being dropped
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Directory holding one subdirectory per recording
+    #[serde(default = "default_work_dir")]
+    pub path: String,
+
+    /// Keep the directory of a recording with spilled records this long after
+    /// it ended (0 = remove it with the recording)
+    #[serde(default = "default_retain_failed_hours")]
+    pub retain_failed_hours: u64,
+}
+
+impl Default for WorkDirConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_work_dir(),
+            retain_failed_hours: default_retain_failed_hours(),
+        }
+    }
+}
+
+impl WorkDirConfig {
+    pub fn retain_failed(&self) -> Duration {
+        Duration::from_secs(self.retain_failed_hours * 3600)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoggingConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String, // "trace", "debug", "info", "warn", "error"
+
+    #[serde(default = "default_log_format")]
+    pub format: String, // "text", "json"
+
+    /// Write logs to rotating files instead of stdout
+    #[serde(default)]
+    pub file: Option<LogFileConfig>,
+
+    /// Export tracing spans to an OTLP collector (needs the `otlp` feature)
+    #[serde(default)]
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            format: default_log_format(),
+            file: None,
+            otlp: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OtlpConfig {
+    /// OTLP/HTTP traces endpoint, e.g. `http://localhost:4318/v1/traces`
+    pub endpoint: String,
+
+    /// `service.name` of the exported spans
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+
+    /// Export timeout per batch
+    #[serde(default = "default_otlp_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl OtlpConfig {
+    #[cfg_attr(not(feature = "otlp"), allow(dead_code))]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogFileConfig {
+    pub directory: String,
+
+    /// File name prefix; the rotation date is appended
+    #[serde(default = "default_log_file_prefix")]
+    pub prefix: String,
+
+    #[serde(default = "default_log_rotation")]
+    pub rotation: String, // "minutely", "hourly", "daily", "never"
+
+    /// Number of rotated files to keep (0 = keep all)
+    #[serde(default)]
+    pub max_files: usize,
+}
+
+// Default value functions
+fn default_mode() -> String {
+    "peer".to_string()
+}
+
+fn default_reconnect_check_interval() -> u64 {
+    1000
+}
+
+fn default_reopen_after() -> u64 {
+    30
+}
+fn default_timeout() -> u64 {
+    300
+}
+fn default_retries() -> u32 {
+    3
+}
+fn default_min_samples() -> usize {
+    10
+}
+fn default_flush_workers() -> usize {
+    4
+}
+
+fn default_max_label_bytes() -> usize {
+    8 * 1024
+}
+fn default_batch_max_records() -> usize {
+    32
+}
+
+fn default_low_battery_percent() -> f64 {
+    10.0
+}
+
+fn default_emergency_flush_timeout() -> u64 {
+    10
+}
+
+fn default_data_bridge_prefix() -> String {
+    "recorder/data".to_string()
+}
+
+fn default_data_bridge_page_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_keyframe_interval() -> usize {
+    10
+}
+
+fn default_seekable_frame_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_compression_threads() -> usize {
+    std::thread::available_parallelism().map_or(2, |n| n.get())
+}
+fn default_queue_capacity() -> usize {
+    1000
+}
+fn default_shutdown_timeout_ms() -> u64 {
+    5000
+}
+fn default_control_prefix() -> String {
+    "recorder/control".to_string()
+}
+fn default_status_key() -> String {
+    "recorder/status/**".to_string()
+}
+fn default_control_timeout() -> u64 {
     30
 }
+fn default_group_max_delay_ms() -> u64 {
+    60_000
+}
+fn default_max_clock_skew() -> u64 {
+    300
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
 fn default_log_format() -> String {
     "text".to_string()
 }
+fn default_otlp_service_name() -> String {
+    "zenoh-recorder".to_string()
+}
+fn default_otlp_timeout_ms() -> u64 {
+    10_000
+}
+fn default_log_file_prefix() -> String {
+    "zenoh-recorder.log".to_string()
+}
+fn default_log_rotation() -> String {
+    "daily".to_string()
+}
 fn default_file_format() -> String {
     "mcap".to_string()
 }
+fn default_retention_interval() -> u64 {
+    60
+}
 fn default_schema_format() -> String {
     "raw".to_string()
 }
+fn default_drift_samples() -> usize {
+    16
+}
+fn default_descriptor_timeout_ms() -> u64 {
+    3000
+}
+fn default_handoff_timeout() -> u64 {
+    10
+}
+fn default_true() -> bool {
+    true
+}
+fn default_state_dir() -> String {
+    "/var/lib/zenoh-recorder/state".to_string()
+}
+fn default_work_dir() -> String {
+    "/var/lib/zenoh-recorder/work".to_string()
+}
+fn default_retain_failed_hours() -> u64 {
+    24
+}
+fn default_controller_grace_period() -> u64 {
+    10
+}
+fn default_status_events_prefix() -> String {
+    "recorder/events".to_string()
+}
+
+fn default_initial_query_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_coalesce_max_batch_bytes() -> usize {
+    16 * 1024
+}
+
+fn default_coalesce_max_record_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_coalesce_interval_ms() -> u64 {
+    1000
+}
+
+fn default_health_prefix() -> String {
+    "recorder".to_string()
+}
+
+fn default_heartbeat_interval_ms() -> u64 {
+    5000
+}
+
+fn default_expiry_sweep_interval() -> u64 {
+    3600
+}
+fn default_status_events_rate() -> f64 {
+    2.0
+}
+fn default_preview_interval_ms() -> u64 {
+    1000
+}
+fn default_tap_prefix() -> String {
+    "recorder/tap".to_string()
+}
+fn default_tap_channel_capacity() -> usize {
+    64
+}
+fn default_tap_interval_ms() -> u64 {
+    100
+}
+fn default_dedupe_heartbeat_ms() -> u64 {
+    10_000
+}
+fn default_hook_timeout_ms() -> u64 {
+    10_000
+}
+fn default_snapshot_timeout_ms() -> u64 {
+    1000
+}
+fn default_ingestion_chunk_size_bytes() -> u64 {
+    8 * 1024 * 1024
+}
+fn default_ingestion_retry_delay_ms() -> u64 {
+    1000
+}
+fn default_ingestion_timeout_ms() -> u64 {
+    60_000
+}
+fn default_black_box_max_bytes() -> usize {
+    16 * 1024 * 1024
+}
+fn default_black_box_max_age_seconds() -> u64 {
+    60
+}
+fn default_topology_timeout_ms() -> u64 {
+    500
+}
+fn default_deferral_check_interval() -> u64 {
+    30
+}
+fn default_probe_timeout_ms() -> u64 {
+    1000
+}
+fn default_low_power_flush_interval() -> u64 {
+    60
+}
+fn default_wakeup_granularity_ms() -> u64 {
+    1000
+}
+
+fn default_idle_trim_seconds() -> u64 {
+    60
+}
+
+fn default_progress_interval_ms() -> u64 {
+    1000
+}
+fn default_topic_stats_path() -> String {
+    "/var/lib/zenoh-recorder/topic_stats.json".to_string()
+}
+fn default_topic_stats_smoothing() -> f64 {
+    0.3
+}
+fn default_incompressible_ratio() -> f64 {
+    0.95
+}
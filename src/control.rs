@@ -14,19 +14,30 @@
 
 use anyhow::Result;
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Duration;
+use tracing::{error, info, warn};
 use zenoh::query::Query;
 use zenoh::Session;
 use zenoh::Wait;
 
-use crate::protocol::{RecorderCommand, RecorderRequest, RecorderResponse, StatusResponse};
+use crate::control_auth::{request_body, ControlAction, ControlAuth};
+use crate::protocol::{
+    ErrorCode, HandoffAck, HandoffReady, RecorderCommand, RecorderRequest, RecorderResponse,
+    RequestAuth, StatusResponse, TaskStage, GROUP_KEY_PREFIX, HANDOFF_KEY_PREFIX,
+};
 use crate::recorder::RecorderManager;
+use crate::runtime;
+use crate::session_supervisor::{current_session, session_changed, SessionUpdates};
 
 /// Control interface for handling recorder commands via Zenoh queryable
 pub struct ControlInterface {
     session: Arc<Session>,
     recorder_manager: Arc<RecorderManager>,
     device_id: String,
+    auth: Arc<ControlAuth>,
+    session_updates: Option<SessionUpdates>,
+    groups: Vec<String>,
+    group_max_delay: Duration,
 }
 
 impl ControlInterface {
@@ -39,49 +50,135 @@ impl ControlInterface {
             session,
             recorder_manager,
             device_id,
+            auth: Arc::new(ControlAuth::default()),
+            session_updates: None,
+            groups: Vec::new(),
+            group_max_delay: Duration::ZERO,
         }
     }
 
+    /// Follow the Start and Finish commands sent to the keys of `groups`,
+    /// scheduled at most `max_delay` ahead
+    pub fn with_groups(mut self, groups: Vec<String>, max_delay: Duration) -> Self {
+        self.groups = groups;
+        self.group_max_delay = max_delay;
+        self
+    }
+
+    /// Require credentials with every request (open to everyone by default)
+    pub fn with_auth(mut self, auth: ControlAuth) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    /// Declare the queryables on the sessions handed out by the session
+    /// supervisor, again after every reconnect
+    pub fn with_session_updates(mut self, updates: SessionUpdates) -> Self {
+        self.session_updates = Some(updates);
+        self
+    }
+
     /// Run the control interface (blocks until stopped)
     pub async fn run(&self) -> Result<()> {
-        // Declare queryable for control commands
-        let control_key = format!("recorder/control/{}", self.device_id);
-        let queryable = self
-            .session
-            .declare_queryable(&control_key)
-            .wait()
-            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut updates = self.session_updates.clone();
+        loop {
+            let session = current_session(&mut updates, &self.session);
 
-        info!("Control interface listening on '{}'", control_key);
+            // Declare queryable for control commands
+            let control_key = format!("recorder/control/{}", self.device_id);
+            let queryable = session
+                .declare_queryable(&control_key)
+                .wait()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-        // Declare queryable for status queries
-        let status_key = "recorder/status/**";
-        let status_queryable = self
-            .session
-            .declare_queryable(status_key)
-            .wait()
-            .map_err(|e| anyhow::anyhow!("{}", e))?;
+            info!("Control interface listening on '{}'", control_key);
 
-        info!("Status interface listening on '{}'", status_key);
+            // Declare queryable for status queries
+            let status_key = "recorder/status/**";
+            let status_queryable = session
+                .declare_queryable(status_key)
+                .wait()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-        // Handle queries in parallel
-        loop {
-            tokio::select! {
-                Ok(query) = queryable.recv_async() => {
-                    let recorder_manager = self.recorder_manager.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::handle_control_query(query, recorder_manager).await {
-                            error!("Error handling control query: {}", e);
-                        }
-                    });
-                }
-                Ok(query) = status_queryable.recv_async() => {
-                    let recorder_manager = self.recorder_manager.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::handle_status_query(query, recorder_manager).await {
-                            error!("Error handling status query: {}", e);
-                        }
-                    });
+            info!("Status interface listening on '{}'", status_key);
+
+            // Declare queryable for handoffs to a successor recorder
+            let handoff_key = format!("{}/{}/*", HANDOFF_KEY_PREFIX, self.device_id);
+            let handoff_queryable = session
+                .declare_queryable(&handoff_key)
+                .wait()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            info!("Handoff interface listening on '{}'", handoff_key);
+
+            // Declare queryables for the commands of the recorder's groups
+            let (group_tx, mut group_queries) = tokio::sync::mpsc::unbounded_channel();
+            let mut group_queryables = Vec::new();
+            for group_id in &self.groups {
+                let group_key = format!("{}/{}", GROUP_KEY_PREFIX, group_id);
+                let (group_tx, group_id) = (group_tx.clone(), group_id.clone());
+                let group_queryable = session
+                    .declare_queryable(&group_key)
+                    .callback(move |query| {
+                        let _ = group_tx.send((group_id.clone(), query));
+                    })
+                    .wait()
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+                info!("Group interface listening on '{}'", group_key);
+                group_queryables.push(group_queryable);
+            }
+
+            // Handle queries in parallel
+            loop {
+                tokio::select! {
+                    Ok(query) = queryable.recv_async() => {
+                        let recorder_manager = self.recorder_manager.clone();
+                        let auth = self.auth.clone();
+                        let device_id = self.device_id.clone();
+                        let tasks = recorder_manager.tasks().clone();
+                        tasks.spawn(TaskStage::Control, "control query", async move {
+                            if let Err(e) = Self::handle_control_query(query, recorder_manager, &auth, &device_id).await {
+                                error!("Error handling control query: {}", e);
+                            }
+                        });
+                    }
+                    Ok(query) = status_queryable.recv_async() => {
+                        let recorder_manager = self.recorder_manager.clone();
+                        let auth = self.auth.clone();
+                        let device_id = self.device_id.clone();
+                        let tasks = recorder_manager.tasks().clone();
+                        tasks.spawn(TaskStage::Control, "status query", async move {
+                            if let Err(e) = Self::handle_status_query(query, recorder_manager, &auth, &device_id).await {
+                                error!("Error handling status query: {}", e);
+                            }
+                        });
+                    }
+                    Ok(query) = handoff_queryable.recv_async() => {
+                        let recorder_manager = self.recorder_manager.clone();
+                        let auth = self.auth.clone();
+                        let device_id = self.device_id.clone();
+                        let tasks = recorder_manager.tasks().clone();
+                        tasks.spawn(TaskStage::Control, "handoff query", async move {
+                            if let Err(e) = Self::handle_handoff_query(query, recorder_manager, &auth, &device_id).await {
+                                error!("Error handling handoff query: {}", e);
+                            }
+                        });
+                    }
+                    Some((group_id, query)) = group_queries.recv() => {
+                        let recorder_manager = self.recorder_manager.clone();
+                        let auth = self.auth.clone();
+                        let device_id = self.device_id.clone();
+                        let session = session.clone();
+                        let max_delay = self.group_max_delay;
+                        let tasks = recorder_manager.tasks().clone();
+                        tasks.spawn(TaskStage::Control, "group query", async move {
+                            if let Err(e) = Self::handle_group_query(query, recorder_manager, &auth, &device_id, &group_id, &session, max_delay).await {
+                                error!("Error handling group query: {}", e);
+                            }
+                        });
+                    }
+                    // Undeclared before they're declared again
+                    _ = session_changed(&mut updates) => break,
                 }
             }
         }
@@ -90,24 +187,65 @@ impl ControlInterface {
     async fn handle_control_query(
         query: Query,
         recorder_manager: Arc<RecorderManager>,
+        auth: &ControlAuth,
+        device_id: &str,
     ) -> Result<()> {
-        info!("Received control query on '{}'", query.selector());
-
-        // Parse request from query payload
-        let request: RecorderRequest = if let Some(payload) = query.payload() {
-            serde_json::from_slice(&payload.to_bytes())?
-        } else {
-            let response = RecorderResponse::error("Missing request payload".to_string());
-            let response_bytes = serde_json::to_vec(&response)?;
+        let Some(request) =
+            Self::accept_request(&query, &recorder_manager, auth, device_id).await?
+        else {
+            return Ok(());
+        };
+        if request.execute_at_ns.is_some() {
+            let response = RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                "execute_at_ns is only accepted on group keys".to_string(),
+            );
             query
-                .reply(query.key_expr().clone(), response_bytes)
+                .reply(query.key_expr().clone(), serde_json::to_vec(&response)?)
                 .await
                 .map_err(|e| anyhow::anyhow!("{}", e))?;
             return Ok(());
-        };
+        }
 
         info!("Processing command: {:?}", request.command);
 
+        // Drift reports, estimates, reloads, task lists, quota usage and
+        // snapshots carry their own response types
+        let response_bytes = match request.command {
+            RecorderCommand::DriftReport => Some(serde_json::to_vec(
+                &recorder_manager
+                    .get_drift_report(request.recording_id.as_deref().unwrap_or_default())
+                    .await,
+            )?),
+            RecorderCommand::Estimate => Some(serde_json::to_vec(
+                &recorder_manager.estimate(&request.topics),
+            )?),
+            RecorderCommand::Reload => Some(serde_json::to_vec(&recorder_manager.reload_config())?),
+            RecorderCommand::Tasks => Some(serde_json::to_vec(&recorder_manager.list_tasks())?),
+            RecorderCommand::Quotas => {
+                Some(serde_json::to_vec(&recorder_manager.quota_usage().await)?)
+            }
+            RecorderCommand::Snapshot => Some(serde_json::to_vec(
+                &recorder_manager.snapshot(request.clone()).await,
+            )?),
+            RecorderCommand::WaitForCompletion => Some(serde_json::to_vec(
+                &recorder_manager
+                    .wait_for_completion(
+                        request.recording_id.as_deref().unwrap_or_default(),
+                        Duration::from_millis(request.wait_timeout_ms.unwrap_or_default()),
+                    )
+                    .await,
+            )?),
+            _ => None,
+        };
+        if let Some(response_bytes) = response_bytes {
+            query
+                .reply(query.key_expr().clone(), response_bytes)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            return Ok(());
+        }
+
         // Handle the command
         let response = match request.command {
             RecorderCommand::Start => recorder_manager.start_recording(request).await,
@@ -127,10 +265,64 @@ impl ControlInterface {
                     .await
             }
             RecorderCommand::Finish => {
+                let recording_id = request.recording_id.unwrap_or_default();
+                let response = recorder_manager.begin_finish(&recording_id).await;
+                if response.success {
+                    Self::spawn_complete_finish(&recorder_manager, recording_id);
+                }
+                response
+            }
+            RecorderCommand::Rollover => {
+                let recording_id = request.recording_id.unwrap_or_default();
+                let response = recorder_manager.rollover(&recording_id).await;
+                if response.success {
+                    Self::spawn_complete_finish(&recorder_manager, recording_id);
+                }
+                response
+            }
+            RecorderCommand::Dump => {
+                let response = recorder_manager.dump(request).await;
+                if let Some(recording_id) =
+                    response.recording_id.clone().filter(|_| response.success)
+                {
+                    Self::spawn_complete_finish(&recorder_manager, recording_id);
+                }
+                response
+            }
+            RecorderCommand::PauseTopics => {
+                recorder_manager
+                    .pause_topics(&request.recording_id.unwrap_or_default(), &request.topics)
+                    .await
+            }
+            RecorderCommand::ResumeTopics => {
+                recorder_manager
+                    .resume_topics(&request.recording_id.unwrap_or_default(), &request.topics)
+                    .await
+            }
+            RecorderCommand::AddTopics => {
+                recorder_manager
+                    .add_topics(&request.recording_id.unwrap_or_default(), &request.topics)
+                    .await
+            }
+            RecorderCommand::RemoveTopics => {
                 recorder_manager
-                    .finish_recording(&request.recording_id.unwrap_or_default())
+                    .remove_topics(&request.recording_id.unwrap_or_default(), &request.topics)
                     .await
             }
+            RecorderCommand::SetUploadLimit => match request.upload_limit {
+                Some(limit) => recorder_manager.set_upload_limit(limit),
+                None => RecorderResponse::error(
+                    ErrorCode::InvalidRequest,
+                    "Missing upload_limit".to_string(),
+                ),
+            },
+            RecorderCommand::DriftReport
+            | RecorderCommand::Estimate
+            | RecorderCommand::Reload
+            | RecorderCommand::WaitForCompletion
+            | RecorderCommand::Tasks
+            | RecorderCommand::Quotas
+            | RecorderCommand::Snapshot => unreachable!("handled above"),
         };
 
         // Send response
@@ -143,9 +335,245 @@ impl ControlInterface {
         Ok(())
     }
 
+    /// Parse and authorize the request of a control query, replying with
+    /// the problem when it is rejected
+    async fn accept_request(
+        query: &Query,
+        recorder_manager: &RecorderManager,
+        auth: &ControlAuth,
+        device_id: &str,
+    ) -> Result<Option<RecorderRequest>> {
+        info!("Received control query on '{}'", query.selector());
+
+        // Parse request from query payload
+        let parsed = match query.payload() {
+            Some(payload) => {
+                let (compression_type, compression_level) = recorder_manager.compression_defaults();
+                RecorderRequest::from_json_with_defaults(
+                    &payload.to_bytes(),
+                    compression_type,
+                    compression_level,
+                )
+                .map_err(|e| format!("Invalid request payload: {}", e))
+            }
+            None => Err("Missing request payload".to_string()),
+        };
+        let mut request = match parsed {
+            Ok(request) => request,
+            Err(message) => {
+                warn!("Rejected control query: {}", message);
+                let response = RecorderResponse::error(ErrorCode::InvalidRequest, message);
+                let response_bytes = serde_json::to_vec(&response)?;
+                query
+                    .reply(query.key_expr().clone(), response_bytes)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+                return Ok(None);
+            }
+        };
+
+        // Credentials never reach the recorder (or the stored metadata)
+        let credentials = request.auth.take().or_else(|| attached_credentials(query));
+        let body = query
+            .payload()
+            .map(|payload| request_body(&payload.to_bytes()))
+            .unwrap_or_default();
+        if let Err(e) = auth.authorize(
+            credentials.as_ref(),
+            ControlAction::Command(&request.command),
+            device_id,
+            request.recording_id.as_deref().unwrap_or_default(),
+            &body,
+        ) {
+            warn!("Rejected {:?} command: {}", request.command, e);
+            let response_bytes =
+                serde_json::to_vec(&RecorderResponse::error(e.code(), e.to_string()))?;
+            query
+                .reply(query.key_expr().clone(), response_bytes)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            return Ok(None);
+        }
+
+        Ok(Some(request))
+    }
+
+    /// Run a Start or Finish sent to the key of `group_id` at its
+    /// `execute_at_ns`
+    ///
+    /// Every recorder of the group replies: to a Start with the recording it
+    /// started, to a Finish once per recording of the group it finished.
+    async fn handle_group_query(
+        query: Query,
+        recorder_manager: Arc<RecorderManager>,
+        auth: &ControlAuth,
+        device_id: &str,
+        group_id: &str,
+        session: &Session,
+        max_delay: Duration,
+    ) -> Result<()> {
+        let Some(mut request) =
+            Self::accept_request(&query, &recorder_manager, auth, device_id).await?
+        else {
+            return Ok(());
+        };
+
+        let responses = match request.command {
+            RecorderCommand::Start | RecorderCommand::Finish => {
+                match Self::wait_until(session, request.execute_at_ns, max_delay).await {
+                    Err(response) => vec![response],
+                    Ok(()) if matches!(request.command, RecorderCommand::Start) => {
+                        // The controller addresses the group, not the device
+                        request.device_id = device_id.to_string();
+                        vec![
+                            recorder_manager
+                                .start_group_recording(request, group_id)
+                                .await,
+                        ]
+                    }
+                    Ok(()) => {
+                        let recording_ids = recorder_manager.group_recordings(group_id).await;
+                        if recording_ids.is_empty() {
+                            vec![RecorderResponse::error(
+                                ErrorCode::NotFound,
+                                format!("No recording of group '{}'", group_id),
+                            )]
+                        } else {
+                            let mut responses = Vec::new();
+                            for recording_id in recording_ids {
+                                let response = recorder_manager.begin_finish(&recording_id).await;
+                                if response.success {
+                                    Self::spawn_complete_finish(&recorder_manager, recording_id);
+                                }
+                                responses.push(response);
+                            }
+                            responses
+                        }
+                    }
+                }
+            }
+            _ => vec![RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "{:?} can't be sent to a group, only start and finish",
+                    request.command
+                ),
+            )],
+        };
+
+        for response in responses {
+            query
+                .reply(query.key_expr().clone(), serde_json::to_vec(&response)?)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Sleep until the Zenoh time `execute_at_ns` (ns since the epoch),
+    /// rejecting times more than `max_delay` ahead; past times don't wait
+    async fn wait_until(
+        session: &Session,
+        execute_at_ns: Option<u64>,
+        max_delay: Duration,
+    ) -> std::result::Result<(), RecorderResponse> {
+        let Some(execute_at_ns) = execute_at_ns else {
+            return Ok(());
+        };
+        let now_ns = session.new_timestamp().get_time().to_duration().as_nanos() as u64;
+        let delay = Duration::from_nanos(execute_at_ns.saturating_sub(now_ns));
+        if delay > max_delay {
+            return Err(RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "execute_at_ns is {:?} ahead, more than the {:?} allowed",
+                    delay, max_delay
+                ),
+            ));
+        }
+        runtime::sleep(delay).await;
+        Ok(())
+    }
+
+    /// Upload the rest of a recording in the background, since the upload
+    /// can outlast the query; completion shows in the status and
+    /// `wait_for_completion`
+    fn spawn_complete_finish(recorder_manager: &Arc<RecorderManager>, recording_id: String) {
+        let manager = recorder_manager.clone();
+        let name = format!("finish {}", recording_id);
+        recorder_manager
+            .tasks()
+            .spawn(TaskStage::Flush, name, async move {
+                manager.complete_finish(&recording_id).await;
+            });
+    }
+
+    async fn handle_handoff_query(
+        query: Query,
+        recorder_manager: Arc<RecorderManager>,
+        auth: &ControlAuth,
+        device_id: &str,
+    ) -> Result<()> {
+        info!("Received handoff query on '{}'", query.selector());
+
+        // The signature covers the payload the predecessor acts on
+        let payload = query
+            .payload()
+            .map(|payload| payload.to_bytes().to_vec())
+            .unwrap_or_default();
+        if let Err(e) = auth.authorize(
+            attached_credentials(&query).as_ref(),
+            ControlAction::Handoff,
+            device_id,
+            "",
+            &payload,
+        ) {
+            warn!("Rejected handoff query: {}", e);
+            query
+                .reply(
+                    query.key_expr().clone(),
+                    serde_json::to_vec(&RecorderResponse::error(e.code(), e.to_string()))?,
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            return Ok(());
+        }
+
+        let response_bytes = match query.key_expr().as_str().rsplit('/').next() {
+            Some("offer") => serde_json::to_vec(&recorder_manager.handoff_offer().await)?,
+            Some("ready") => {
+                let ready: Option<HandoffReady> = serde_json::from_slice(&payload).ok();
+                let ack = match ready {
+                    Some(ready) => recorder_manager.complete_handoff(ready).await,
+                    None => HandoffAck {
+                        success: false,
+                        message: "Missing or invalid handoff payload".to_string(),
+                        finished: vec![],
+                        finished_at: chrono::Utc::now().to_rfc3339(),
+                        states: vec![],
+                    },
+                };
+                serde_json::to_vec(&ack)?
+            }
+            _ => serde_json::to_vec(&RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                "Unknown handoff query".to_string(),
+            ))?,
+        };
+
+        query
+            .reply(query.key_expr().clone(), response_bytes)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        Ok(())
+    }
+
     async fn handle_status_query(
         query: Query,
         recorder_manager: Arc<RecorderManager>,
+        auth: &ControlAuth,
+        device_id: &str,
     ) -> Result<()> {
         info!("Received status query on '{}'", query.selector());
 
@@ -153,20 +581,10 @@ impl ControlInterface {
         // Pattern: recorder/status/{recording_id}
         let key_parts: Vec<&str> = query.key_expr().as_str().split('/').collect();
         if key_parts.len() < 3 {
-            let response = StatusResponse {
-                success: false,
-                message: "Invalid status query format".to_string(),
-                status: crate::protocol::RecordingStatus::Idle,
-                scene: None,
-                skills: vec![],
-                organization: None,
-                task_id: None,
-                device_id: String::new(),
-                data_collector_id: None,
-                active_topics: vec![],
-                buffer_size_bytes: 0,
-                total_recorded_bytes: 0,
-            };
+            let response = StatusResponse::error(
+                ErrorCode::InvalidRequest,
+                "Invalid status query format".to_string(),
+            );
             let response_bytes = serde_json::to_vec(&response)?;
             query
                 .reply(query.key_expr().clone(), response_bytes)
@@ -176,6 +594,22 @@ impl ControlInterface {
         }
 
         let recording_id = key_parts[2];
+        if let Err(e) = auth.authorize(
+            attached_credentials(&query).as_ref(),
+            ControlAction::Status,
+            device_id,
+            recording_id,
+            &[],
+        ) {
+            warn!("Rejected status query: {}", e);
+            let response_bytes =
+                serde_json::to_vec(&StatusResponse::error(e.code(), e.to_string()))?;
+            query
+                .reply(query.key_expr().clone(), response_bytes)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            return Ok(());
+        }
 
         // Get status
         let response = recorder_manager.get_status(recording_id).await;
@@ -190,3 +624,10 @@ impl ControlInterface {
         Ok(())
     }
 }
+
+/// Credentials sent as the JSON attachment of a query
+pub(crate) fn attached_credentials(query: &Query) -> Option<RequestAuth> {
+    query
+        .attachment()
+        .and_then(|attachment| serde_json::from_slice(&attachment.to_bytes()).ok())
+}
@@ -0,0 +1,471 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Authentication and authorization of control requests (`control.auth`)
+//
+// Every principal has a role, and every action needs one: observers may read
+// status, drift reports, estimates and upload progress, operators may also
+// start, pause, resume and finish recordings and read recorded data back,
+// admins may also cancel them, change upload limits, reload the configuration
+// and hand off. A request proves its principal with the principal's token or
+// with an HMAC-SHA256 signature made with its secret. Signatures cover the
+// action, the device, the recording, a timestamp, a nonce and a digest of the
+// request body, and expire after `max_clock_skew_seconds`. Each is accepted
+// once, so a captured request can't be replayed, changed, or sent against
+// another recording or device. Tokens travel in the clear; use them over
+// encrypted transports only.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{ControlAuthConfig, ControlPrincipal, ControlRole};
+use crate::protocol::{ErrorCode, RecorderCommand, RequestAuth};
+
+/// Why a request was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// No credentials were sent
+    Missing,
+    /// Unknown token, bad or expired signature
+    Invalid,
+    /// The principal's role doesn't allow the action
+    Forbidden {
+        principal: String,
+        role: ControlRole,
+        action: String,
+    },
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "Unauthorized: missing credentials"),
+            Self::Invalid => write!(f, "Unauthorized: invalid credentials"),
+            Self::Forbidden {
+                principal,
+                role,
+                action,
+            } => write!(
+                f,
+                "Forbidden: '{}' ({}) may not {}",
+                principal,
+                role_name(*role),
+                action
+            ),
+        }
+    }
+}
+
+impl AuthError {
+    /// Error code reported to controllers for this rejection
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Missing | Self::Invalid => ErrorCode::Unauthorized,
+            Self::Forbidden { .. } => ErrorCode::Forbidden,
+        }
+    }
+}
+
+/// What a request asks to do
+#[derive(Debug, Clone, Copy)]
+pub enum ControlAction<'a> {
+    Command(&'a RecorderCommand),
+    Status,
+    Handoff,
+    /// Recorded data served by the data bridge
+    ReadData,
+}
+
+impl ControlAction<'_> {
+    /// Name of the action in signatures and messages
+    pub fn name(&self) -> String {
+        match self {
+            Self::Command(command) => serde_json::to_value(command)
+                .ok()
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            Self::Status => "status".to_string(),
+            Self::Handoff => "handoff".to_string(),
+            Self::ReadData => "read_data".to_string(),
+        }
+    }
+
+    /// Least role allowed to do it
+    pub fn required_role(&self) -> ControlRole {
+        match self {
+            Self::Status => ControlRole::Observer,
+            Self::Handoff => ControlRole::Admin,
+            Self::ReadData => ControlRole::Operator,
+            Self::Command(command) => match command {
+                RecorderCommand::DriftReport
+                | RecorderCommand::Estimate
+                | RecorderCommand::WaitForCompletion
+                | RecorderCommand::Tasks
+                | RecorderCommand::Quotas => ControlRole::Observer,
+                RecorderCommand::Start
+                | RecorderCommand::Pause
+                | RecorderCommand::Resume
+                | RecorderCommand::Finish
+                | RecorderCommand::Rollover
+                | RecorderCommand::PauseTopics
+                | RecorderCommand::ResumeTopics
+                | RecorderCommand::AddTopics
+                | RecorderCommand::RemoveTopics
+                | RecorderCommand::Snapshot
+                | RecorderCommand::Dump => ControlRole::Operator,
+                RecorderCommand::Cancel
+                | RecorderCommand::SetUploadLimit
+                | RecorderCommand::Reload => ControlRole::Admin,
+            },
+        }
+    }
+}
+
+/// Checks the credentials of requests against the configured principals
+#[derive(Debug, Clone, Default)]
+pub struct ControlAuth {
+    config: ControlAuthConfig,
+    /// Signatures accepted within the clock skew, with their timestamp
+    /// (shared by the clones serving the other interfaces)
+    used: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl ControlAuth {
+    pub fn new(config: &ControlAuthConfig) -> Self {
+        Self {
+            config: config.clone(),
+            used: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Name of the principal allowed to do `action`, or why it isn't
+    ///
+    /// `body` is what the request asks for beyond the action and recording
+    /// (see `request_body`), empty for requests without one. Always allowed
+    /// (without a principal) when authentication is off.
+    pub fn authorize(
+        &self,
+        credentials: Option<&RequestAuth>,
+        action: ControlAction,
+        device_id: &str,
+        recording_id: &str,
+        body: &[u8],
+    ) -> Result<Option<String>, AuthError> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let credentials = credentials.ok_or(AuthError::Missing)?;
+        let principal = self
+            .authenticate(credentials, action, device_id, recording_id, body)
+            .ok_or(AuthError::Invalid)?;
+        if principal.role < action.required_role() {
+            return Err(AuthError::Forbidden {
+                principal: principal.name.clone(),
+                role: principal.role,
+                action: action.name(),
+            });
+        }
+        Ok(Some(principal.name.clone()))
+    }
+
+    fn authenticate(
+        &self,
+        credentials: &RequestAuth,
+        action: ControlAction,
+        device_id: &str,
+        recording_id: &str,
+        body: &[u8],
+    ) -> Option<&ControlPrincipal> {
+        match credentials {
+            RequestAuth::Token { token } => self.config.principals.iter().find(|principal| {
+                principal
+                    .token
+                    .as_deref()
+                    .is_some_and(|expected| !expected.is_empty() && equal(expected, token))
+            }),
+            RequestAuth::Hmac {
+                key_id,
+                timestamp,
+                nonce,
+                signature,
+            } => {
+                let principal = self.config.principals.iter().find(|p| &p.name == key_id)?;
+                let secret = principal.secret.as_deref().filter(|s| !s.is_empty())?;
+                let now = chrono::Utc::now().timestamp();
+                let max_skew = self.config.max_clock_skew_seconds;
+                if now.abs_diff(*timestamp) > max_skew {
+                    return None;
+                }
+                let message = SignedMessage {
+                    action: &action.name(),
+                    device_id,
+                    recording_id,
+                    timestamp: *timestamp,
+                    nonce,
+                    body,
+                };
+                if !equal(&sign(secret, &message), signature) {
+                    return None;
+                }
+                // Older signatures are rejected as expired already
+                let mut used = self.used.lock().unwrap();
+                used.retain(|_, used_at| now.abs_diff(*used_at) <= max_skew);
+                used.insert(signature.clone(), *timestamp)
+                    .is_none()
+                    .then_some(principal)
+            }
+        }
+    }
+
+    /// Credentials of the handoff principal for a query to a predecessor
+    /// carrying `payload`
+    pub fn handoff_credentials(&self, device_id: &str, payload: &[u8]) -> Option<RequestAuth> {
+        if !self.config.enabled {
+            return None;
+        }
+        let name = self.config.handoff_principal.as_ref()?;
+        let principal = self.config.principals.iter().find(|p| &p.name == name)?;
+        credentials_for(principal, ControlAction::Handoff, device_id, "", payload)
+    }
+}
+
+/// Credentials of `principal` for one request with `body`, signed when it
+/// has a secret
+pub fn credentials_for(
+    principal: &ControlPrincipal,
+    action: ControlAction,
+    device_id: &str,
+    recording_id: &str,
+    body: &[u8],
+) -> Option<RequestAuth> {
+    if let Some(secret) = principal.secret.as_deref().filter(|s| !s.is_empty()) {
+        let timestamp = chrono::Utc::now().timestamp();
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let message = SignedMessage {
+            action: &action.name(),
+            device_id,
+            recording_id,
+            timestamp,
+            nonce: &nonce,
+            body,
+        };
+        return Some(RequestAuth::Hmac {
+            key_id: principal.name.clone(),
+            timestamp,
+            signature: sign(secret, &message),
+            nonce,
+        });
+    }
+    principal
+        .token
+        .clone()
+        .map(|token| RequestAuth::Token { token })
+}
+
+/// Body of a JSON control request that its signature covers: the request
+/// without its `auth` field, compact and with sorted keys (empty when it
+/// isn't a JSON object)
+pub fn request_body(payload: &[u8]) -> Vec<u8> {
+    let Ok(serde_json::Value::Object(mut request)) = serde_json::from_slice(payload) else {
+        return Vec::new();
+    };
+    request.remove("auth");
+    serde_json::to_vec(&request).unwrap_or_default()
+}
+
+/// What the signature of a request covers
+#[derive(Debug, Clone, Copy)]
+pub struct SignedMessage<'a> {
+    pub action: &'a str,
+    pub device_id: &'a str,
+    pub recording_id: &'a str,
+    /// Unix time in seconds
+    pub timestamp: i64,
+    pub nonce: &'a str,
+    pub body: &'a [u8],
+}
+
+/// HMAC-SHA256 of a request as lowercase hex
+pub fn sign(secret: &str, message: &SignedMessage) -> String {
+    let message = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        message.action,
+        message.device_id,
+        message.recording_id,
+        message.timestamp,
+        message.nonce,
+        hex(&hmac_sha256::Hash::hash(message.body)),
+    );
+    hex(&hmac_sha256::HMAC::mac(
+        message.as_bytes(),
+        secret.as_bytes(),
+    ))
+}
+
+/// `bytes` as lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn role_name(role: ControlRole) -> &'static str {
+    match role {
+        ControlRole::Observer => "observer",
+        ControlRole::Operator => "operator",
+        ControlRole::Admin => "admin",
+    }
+}
+
+/// Compare secrets in time independent of where they differ
+fn equal(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> ControlAuth {
+        ControlAuth::new(&ControlAuthConfig {
+            enabled: true,
+            principals: vec![
+                ControlPrincipal {
+                    name: "dashboard".to_string(),
+                    token: Some("view-token".to_string()),
+                    secret: None,
+                    role: ControlRole::Observer,
+                },
+                ControlPrincipal {
+                    name: "fleet".to_string(),
+                    token: None,
+                    secret: Some("fleet-secret".to_string()),
+                    role: ControlRole::Operator,
+                },
+            ],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_roles_and_tokens() {
+        let auth = auth();
+        let view = RequestAuth::Token {
+            token: "view-token".to_string(),
+        };
+        let status = ControlAction::Status;
+        let cancel = ControlAction::Command(&RecorderCommand::Cancel);
+
+        assert_eq!(
+            auth.authorize(Some(&view), status, "robot", "rec-1", &[]),
+            Ok(Some("dashboard".to_string()))
+        );
+        let denied = auth
+            .authorize(Some(&view), cancel, "robot", "rec-1", &[])
+            .unwrap_err();
+        assert_eq!(
+            denied.to_string(),
+            "Forbidden: 'dashboard' (observer) may not cancel"
+        );
+        assert_eq!(denied.code(), ErrorCode::Forbidden);
+        assert_eq!(AuthError::Invalid.code(), ErrorCode::Unauthorized);
+        assert_eq!(
+            auth.authorize(None, status, "robot", "rec-1", &[]),
+            Err(AuthError::Missing)
+        );
+        let wrong = RequestAuth::Token {
+            token: "view-tokem".to_string(),
+        };
+        assert_eq!(
+            auth.authorize(Some(&wrong), status, "robot", "rec-1", &[]),
+            Err(AuthError::Invalid)
+        );
+
+        // Off: everything goes
+        let open = ControlAuth::default();
+        assert_eq!(
+            open.authorize(None, cancel, "robot", "rec-1", &[]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_signatures() {
+        let auth = auth();
+        let fleet = &auth.config.principals[1];
+        let finish = ControlAction::Command(&RecorderCommand::Finish);
+        let signed = credentials_for(fleet, finish, "robot", "rec-1", &[]).unwrap();
+        assert_eq!(
+            auth.authorize(Some(&signed), finish, "robot", "rec-1", &[]),
+            Ok(Some("fleet".to_string()))
+        );
+        // Accepted once
+        assert_eq!(
+            auth.clone()
+                .authorize(Some(&signed), finish, "robot", "rec-1", &[]),
+            Err(AuthError::Invalid)
+        );
+
+        // Bound to the action, device, recording and body
+        let signed = credentials_for(fleet, finish, "robot", "rec-1", b"{}").unwrap();
+        let pause = ControlAction::Command(&RecorderCommand::Pause);
+        for (action, device_id, recording_id, body) in [
+            (pause, "robot", "rec-1", &b"{}"[..]),
+            (finish, "other", "rec-1", b"{}"),
+            (finish, "robot", "rec-2", b"{}"),
+            (finish, "robot", "rec-1", b"{\"bucket\":\"x\"}"),
+        ] {
+            assert!(auth
+                .authorize(Some(&signed), action, device_id, recording_id, body)
+                .is_err());
+        }
+
+        // Expired
+        let timestamp = chrono::Utc::now().timestamp() - 600;
+        let message = SignedMessage {
+            action: "finish",
+            device_id: "robot",
+            recording_id: "rec-1",
+            timestamp,
+            nonce: "n-1",
+            body: &[],
+        };
+        let stale = RequestAuth::Hmac {
+            key_id: "fleet".to_string(),
+            timestamp,
+            nonce: "n-1".to_string(),
+            signature: sign("fleet-secret", &message),
+        };
+        assert!(auth
+            .authorize(Some(&stale), finish, "robot", "rec-1", &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_request_body_ignores_auth_and_key_order() {
+        let signed = br#"{"topics": ["a"], "command": "start", "auth": {"token": "t"}}"#;
+        assert_eq!(
+            request_body(signed),
+            br#"{"command":"start","topics":["a"]}"#.to_vec()
+        );
+        assert!(request_body(b"not json").is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Liveliness of the application controlling a recording
+//
+// A Start request may name a liveliness key expression. The recording's
+// `ControllerWatch` follows the matching tokens and tracks since when none has
+// been alive; `RecorderManager::watch_controllers` acts on recordings whose
+// controller stayed away longer than the grace period.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+use zenoh::sample::SampleKind;
+use zenoh::Session;
+
+use crate::clock::Clock;
+use crate::protocol::TaskStage;
+use crate::task_registry::TaskRegistry;
+
+#[derive(Default)]
+struct WatchState {
+    /// Matching tokens currently alive
+    alive: HashSet<String>,
+    /// Since when no token has been alive (None while the controller is up)
+    lost_since: Option<SystemTime>,
+    /// The recording was paused because the controller was lost
+    paused: bool,
+}
+
+/// Dead-man switch state of one recording
+pub struct ControllerWatch {
+    pub key_expr: String,
+    state: Mutex<WatchState>,
+    stop: Notify,
+    clock: Arc<dyn Clock>,
+}
+
+impl ControllerWatch {
+    /// The controller counts as lost until its token is seen
+    pub fn new(key_expr: String, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            key_expr,
+            state: Mutex::new(WatchState {
+                lost_since: Some(clock.now()),
+                ..Default::default()
+            }),
+            stop: Notify::new(),
+            clock,
+        }
+    }
+
+    /// Follow the liveliness tokens matching `key_expr` until stopped, as a
+    /// task of `tasks`
+    pub fn spawn(self: &Arc<Self>, session: Arc<Session>, tasks: &TaskRegistry) {
+        let watch = self.clone();
+        let name = format!("controller watch {}", self.key_expr);
+        tasks.spawn_service(TaskStage::Background, name, async move {
+            let subscriber = match session
+                .liveliness()
+                .declare_subscriber(&watch.key_expr)
+                .history(true)
+                .await
+            {
+                Ok(subscriber) => subscriber,
+                Err(e) => {
+                    error!(
+                        "Failed to watch controller liveliness '{}': {}",
+                        watch.key_expr, e
+                    );
+                    return;
+                }
+            };
+
+            loop {
+                tokio::select! {
+                    result = subscriber.recv_async() => match result {
+                        Ok(sample) => watch.update(sample.key_expr().as_str(), sample.kind()),
+                        Err(e) => {
+                            warn!("Controller liveliness subscriber closed: {}", e);
+                            break;
+                        }
+                    },
+                    _ = watch.stop.notified() => break,
+                }
+            }
+        });
+    }
+
+    fn update(&self, token: &str, kind: SampleKind) {
+        let mut state = self.state.lock().unwrap();
+        match kind {
+            SampleKind::Put => {
+                if state.alive.is_empty() {
+                    info!("Controller '{}' is alive", token);
+                }
+                state.alive.insert(token.to_string());
+                state.lost_since = None;
+            }
+            SampleKind::Delete => {
+                state.alive.remove(token);
+                if state.alive.is_empty() && state.lost_since.is_none() {
+                    warn!("Controller '{}' dropped off", token);
+                    state.lost_since = Some(self.clock.now());
+                }
+            }
+        }
+    }
+
+    /// How long no controller token has been alive (None while one is)
+    pub fn lost_for(&self) -> Option<Duration> {
+        self.state
+            .lock()
+            .unwrap()
+            .lost_since
+            .map(|since| self.clock.now().duration_since(since).unwrap_or_default())
+    }
+
+    /// Remember whether the recording is paused because of a lost controller
+    ///
+    /// Returns the previous value.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        std::mem::replace(&mut self.state.lock().unwrap().paused, paused)
+    }
+
+    /// Stop following the tokens (recording ended)
+    pub fn stop(&self) {
+        self.stop.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_lost_for_follows_tokens() {
+        let clock = Arc::new(MockClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let watch = ControllerWatch::new("app/**".to_string(), clock.clone());
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(watch.lost_for(), Some(Duration::from_secs(2)));
+
+        watch.update("app/a", SampleKind::Put);
+        watch.update("app/b", SampleKind::Put);
+        assert_eq!(watch.lost_for(), None);
+        watch.update("app/a", SampleKind::Delete);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(watch.lost_for(), None);
+
+        watch.update("app/b", SampleKind::Delete);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(watch.lost_for(), Some(Duration::from_secs(3)));
+    }
+}
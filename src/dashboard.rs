@@ -0,0 +1,322 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// HTTP status dashboard (`dashboard` feature)
+//
+// Lets field technicians open the recorder's address in a browser instead of
+// issuing Zenoh queries. `GET /recordings` and `GET /recordings/{id}` return
+// the state of the recordings as JSON, the `/events` WebSocket streams every
+// change, and `/` is a small page showing the stream as a table. The dashboard
+// is read-only: commands still go through the Zenoh or gRPC control
+// interfaces. With `control.auth` enabled, requests need a principal allowed
+// to query status, with its token as `Authorization: Bearer <token>` or, since
+// browsers can't set headers on WebSockets, as `?token=<token>`.
+
+use anyhow::Result;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::control_auth::{ControlAction, ControlAuth};
+use crate::protocol::{ErrorCode, RequestAuth, StatusResponse};
+use crate::recorder::RecorderManager;
+
+/// How often the event stream looks for changed recordings
+const EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// State of one recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingView {
+    pub recording_id: String,
+    #[serde(flatten)]
+    pub status: StatusResponse,
+}
+
+/// Response of `GET /recordings`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingsView {
+    pub recordings: Vec<RecordingView>,
+}
+
+/// Message of the `/events` stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DashboardEvent {
+    /// A recording started or its state changed
+    Recording(Box<RecordingView>),
+    /// A recording ended and was dropped by the recorder
+    Removed { recording_id: String },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// HTTP server of the status dashboard
+pub struct Dashboard {
+    recorder_manager: Arc<RecorderManager>,
+    auth: ControlAuth,
+    /// Device that signed requests must name
+    device_id: String,
+}
+
+impl Dashboard {
+    pub fn new(recorder_manager: Arc<RecorderManager>) -> Self {
+        Self {
+            recorder_manager,
+            auth: ControlAuth::default(),
+            device_id: String::new(),
+        }
+    }
+
+    /// Require credentials with every request (open to everyone by default)
+    pub fn with_auth(mut self, auth: ControlAuth, device_id: String) -> Self {
+        self.auth = auth;
+        self.device_id = device_id;
+        self
+    }
+
+    /// Routes of the dashboard
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/", get(index))
+            .route("/recordings", get(list_recordings))
+            .route("/recordings/{id}", get(get_recording))
+            .route("/events", get(events))
+            .with_state(Arc::new(self))
+    }
+
+    /// Serve on `addr` (blocks until stopped)
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!("Status dashboard listening on http://{}", addr);
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+
+    /// Check the credentials of a request
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        query: &TokenQuery,
+        recording_id: &str,
+    ) -> std::result::Result<(), Box<Response>> {
+        let credentials = credentials(headers, query);
+        match self.auth.authorize(
+            credentials.as_ref(),
+            ControlAction::Status,
+            &self.device_id,
+            recording_id,
+            &[],
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!("Rejected dashboard request: {}", e);
+                let status = match e.code() {
+                    ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+                    _ => StatusCode::UNAUTHORIZED,
+                };
+                Err(Box::new(error_response(status, e.code(), e.to_string())))
+            }
+        }
+    }
+
+    /// Send the state of the recordings, then every change, until the
+    /// client goes away
+    async fn stream_events(self: Arc<Self>, mut socket: WebSocket) {
+        // Last message sent per recording
+        let mut sent: HashMap<String, String> = HashMap::new();
+        loop {
+            let statuses = self.recorder_manager.recording_statuses().await;
+            let mut events = Vec::new();
+            let removed: Vec<String> = sent
+                .keys()
+                .filter(|id| !statuses.iter().any(|(recording_id, _)| recording_id == *id))
+                .cloned()
+                .collect();
+            for recording_id in removed {
+                sent.remove(&recording_id);
+                events.push(event_json(&DashboardEvent::Removed { recording_id }));
+            }
+            for (recording_id, status) in statuses {
+                let event = event_json(&DashboardEvent::Recording(Box::new(RecordingView {
+                    recording_id: recording_id.clone(),
+                    status,
+                })));
+                if sent.get(&recording_id) != Some(&event) {
+                    sent.insert(recording_id, event.clone());
+                    events.push(event);
+                }
+            }
+            for event in events {
+                if socket.send(Message::Text(event.into())).await.is_err() {
+                    return;
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(EVENT_INTERVAL) => {}
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
+
+/// Credentials of a request: a bearer token, or the `token` query parameter
+fn credentials(headers: &HeaderMap, query: &TokenQuery) -> Option<RequestAuth> {
+    let header = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = header.or(query.token.as_deref())?;
+    Some(RequestAuth::Token {
+        token: token.to_string(),
+    })
+}
+
+fn error_response(status: StatusCode, code: ErrorCode, message: String) -> Response {
+    (status, Json(StatusResponse::error(code, message))).into_response()
+}
+
+fn event_json(event: &DashboardEvent) -> String {
+    serde_json::to_string(event).unwrap_or_default()
+}
+
+async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn list_recordings(
+    State(dashboard): State<Arc<Dashboard>>,
+    headers: HeaderMap,
+    Query(query): Query<TokenQuery>,
+) -> Response {
+    if let Err(response) = dashboard.authorize(&headers, &query, "") {
+        return *response;
+    }
+    let recordings = dashboard
+        .recorder_manager
+        .recording_statuses()
+        .await
+        .into_iter()
+        .map(|(recording_id, status)| RecordingView {
+            recording_id,
+            status,
+        })
+        .collect();
+    Json(RecordingsView { recordings }).into_response()
+}
+
+async fn get_recording(
+    State(dashboard): State<Arc<Dashboard>>,
+    Path(recording_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<TokenQuery>,
+) -> Response {
+    if let Err(response) = dashboard.authorize(&headers, &query, &recording_id) {
+        return *response;
+    }
+    let status = dashboard.recorder_manager.get_status(&recording_id).await;
+    if status.error_code == Some(ErrorCode::NotFound) {
+        return (StatusCode::NOT_FOUND, Json(status)).into_response();
+    }
+    Json(RecordingView {
+        recording_id,
+        status,
+    })
+    .into_response()
+}
+
+async fn events(
+    State(dashboard): State<Arc<Dashboard>>,
+    headers: HeaderMap,
+    Query(query): Query<TokenQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if let Err(response) = dashboard.authorize(&headers, &query, "") {
+        return *response;
+    }
+    ws.on_upgrade(move |socket| dashboard.stream_events(socket))
+}
+
+/// Table of the recordings, fed by the event stream
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Zenoh Recorder</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+</style>
+</head>
+<body>
+<h1>Recordings</h1>
+<p id="state">Connecting...</p>
+<table>
+<thead><tr><th>Recording</th><th>Status</th><th>Topics</th><th>Bytes</th><th>Ratio</th><th>Buffered</th></tr></thead>
+<tbody id="recordings"></tbody>
+</table>
+<script>
+const rows = new Map();
+const token = new URLSearchParams(location.search).get("token");
+const scheme = location.protocol === "https:" ? "wss" : "ws";
+const url = `${scheme}://${location.host}/events` + (token ? `?token=${encodeURIComponent(token)}` : "");
+function connect() {
+  const socket = new WebSocket(url);
+  socket.onopen = () => { document.getElementById("state").textContent = "Live"; };
+  socket.onclose = () => {
+    document.getElementById("state").textContent = "Disconnected, retrying...";
+    setTimeout(connect, 2000);
+  };
+  socket.onmessage = (message) => {
+    const event = JSON.parse(message.data);
+    if (event.type === "removed") {
+      rows.get(event.recording_id)?.remove();
+      rows.delete(event.recording_id);
+      return;
+    }
+    let row = rows.get(event.recording_id);
+    if (!row) {
+      row = document.getElementById("recordings").insertRow();
+      rows.set(event.recording_id, row);
+    }
+    const cells = [event.recording_id, event.status, event.active_topics.join(", "),
+      event.total_recorded_bytes, event.compression_ratio?.toFixed(2) ?? "", event.buffer_size_bytes];
+    row.replaceChildren(...cells.map((value) => {
+      const cell = document.createElement("td");
+      cell.textContent = value;
+      return cell;
+    }));
+  };
+}
+connect();
+</script>
+</body>
+</html>
+"#;
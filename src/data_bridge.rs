@@ -0,0 +1,415 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Recorded data served back over Zenoh (`recorder.data_bridge`)
+//
+// A query on `{key_prefix}/{recording_id}/{topic}?from=..;to=..` is answered
+// with the messages of the topic's stored batches, read from the storage
+// backend, so clients can fetch segments without access to the backend. The
+// topic may be a pattern (`camera/**`); `from` and `to` are message timestamps
+// in ns since the epoch and both optional. Records are read one at a time and
+// their messages sent in pages: each reply carries length-delimited
+// `RecordedMessage` protobufs, up to `page_bytes` of them, in stored order.
+// Every page is sent on a key of its own, `{key_prefix}/{recording_id}/{key
+// its first message was published on}/{page index}`, so clients consolidating
+// replies by key (the default) still get all of them. That key lies below
+// topic patterns; queries on a single topic get their pages there when they
+// accept replies on any key (`_anyke`) and on the queried key otherwise.
+// Queries need the `read_data` permission when control auth is enabled.
+
+use anyhow::{bail, Context, Result};
+use prost::Message;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use zenoh::key_expr::KeyExpr;
+use zenoh::query::{Parameters, Query, ReplyKeyExpr};
+use zenoh::Session;
+use zenoh::Wait;
+
+use crate::coalesce;
+use crate::config::matching::topic_matches;
+use crate::config::DataBridgeConfig;
+use crate::control::attached_credentials;
+use crate::control_auth::{ControlAction, ControlAuth};
+use crate::inspect::parse_batch_range;
+use crate::proto::RecordedMessage;
+use crate::protocol::{RecordChecksum, RecordingMetadata, TaskStage};
+use crate::replay::find_recording;
+use crate::session_supervisor::{current_session, session_changed, SessionUpdates};
+use crate::storage::StorageBackend;
+use crate::task_registry::{TaskRegistry, PROCESS_SCOPE};
+
+/// Recorded data asked for by a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataQuery {
+    pub recording_id: String,
+    /// Recorded topic name or pattern
+    pub topic: String,
+    pub from_ns: i64,
+    pub to_ns: i64,
+}
+
+impl DataQuery {
+    /// Parse the key (below `key_prefix`) and the `from`/`to` parameters of a
+    /// query
+    pub fn parse(key_prefix: &str, key: &str, parameters: &Parameters) -> Result<Self> {
+        let Some(rest) = key
+            .strip_prefix(key_prefix.trim_end_matches('/'))
+            .and_then(|rest| rest.strip_prefix('/'))
+        else {
+            bail!("'{}' is not below '{}'", key, key_prefix);
+        };
+        let Some((recording_id, topic)) = rest.split_once('/') else {
+            bail!("Expected {}/{{recording_id}}/{{topic}}", key_prefix);
+        };
+        if recording_id.contains('*') {
+            bail!("The recording ID can't be a pattern");
+        }
+        let bound = |name: &str| -> Result<Option<i64>> {
+            parameters
+                .get(name)
+                .map(|value| {
+                    value.parse().context(format!(
+                        "'{}' must be a timestamp in ns, got '{}'",
+                        name, value
+                    ))
+                })
+                .transpose()
+        };
+        let from_ns = bound("from")?.unwrap_or(i64::MIN);
+        let to_ns = bound("to")?.unwrap_or(i64::MAX);
+        if from_ns > to_ns {
+            bail!("'from' is after 'to'");
+        }
+        Ok(Self {
+            recording_id: recording_id.to_string(),
+            topic: topic.to_string(),
+            from_ns,
+            to_ns,
+        })
+    }
+
+    /// Stored records of the queried topic, oldest first
+    ///
+    /// Preview channels are only served when asked for by name.
+    pub fn records<'a>(&self, metadata: &'a RecordingMetadata) -> Vec<&'a RecordChecksum> {
+        let originals: HashMap<&str, &str> = metadata
+            .topic_aliases
+            .iter()
+            .map(|(original, recorded)| (recorded.as_str(), original.as_str()))
+            .collect();
+        let topic = self.topic.trim_start_matches('/');
+        let mut records: Vec<&RecordChecksum> = metadata
+            .records
+            .iter()
+            .filter(|record| {
+                let original = originals
+                    .get(record.topic.as_str())
+                    .copied()
+                    .unwrap_or(&record.topic);
+                let named = record.topic.trim_start_matches('/') == topic
+                    || original.trim_start_matches('/') == topic;
+                let preview = metadata.previews.values().any(|p| p == &record.topic);
+                named
+                    || (!preview
+                        && (topic_matches(topic, &record.topic) || topic_matches(topic, original)))
+            })
+            .collect();
+        records.sort_by_key(|record| record.timestamp_us);
+        records.dedup_by(|a, b| a.entry == b.entry && a.timestamp_us == b.timestamp_us);
+        records
+    }
+}
+
+/// Messages collected into replies of at most `page_bytes` (a larger message
+/// gets a reply of its own)
+#[derive(Debug)]
+pub struct Pager {
+    page_bytes: usize,
+    page: Vec<u8>,
+}
+
+impl Pager {
+    pub fn new(page_bytes: usize) -> Self {
+        Self {
+            page_bytes,
+            page: Vec::new(),
+        }
+    }
+
+    /// Add a message; returns the page it doesn't fit in anymore
+    pub fn push(&mut self, message: &RecordedMessage) -> Option<Vec<u8>> {
+        let len = message.encoded_len();
+        let size = prost::length_delimiter_len(len) + len;
+        let full = (!self.page.is_empty() && self.page.len() + size > self.page_bytes)
+            .then(|| std::mem::take(&mut self.page));
+        message
+            .encode_length_delimited(&mut self.page)
+            .expect("a Vec grows as needed");
+        full
+    }
+
+    /// Whether no message was added since the last page
+    pub fn is_empty(&self) -> bool {
+        self.page.is_empty()
+    }
+
+    /// The last page, if it has messages
+    pub fn finish(self) -> Option<Vec<u8>> {
+        (!self.page.is_empty()).then_some(self.page)
+    }
+}
+
+/// Messages of a page
+#[allow(dead_code)]
+pub fn decode_page(mut page: &[u8]) -> Result<Vec<RecordedMessage>> {
+    let mut messages = Vec::new();
+    while !page.is_empty() {
+        messages.push(
+            RecordedMessage::decode_length_delimited(&mut page)
+                .context("Invalid message in page")?,
+        );
+    }
+    Ok(messages)
+}
+
+/// Key a message was published on (its recorded topic for older recordings)
+fn published_key(message: &RecordedMessage) -> &str {
+    message
+        .sample
+        .as_ref()
+        .map(|sample| sample.key_expr.as_str())
+        .filter(|key| !key.is_empty())
+        .unwrap_or(&message.topic)
+}
+
+/// Read the queried messages and hand every full page to `reply`, with the
+/// key its first message was published on and its index
+///
+/// Returns the number of messages read.
+pub async fn read_pages<F, Fut>(
+    storage: &dyn StorageBackend,
+    query: &DataQuery,
+    page_bytes: usize,
+    mut reply: F,
+) -> Result<usize>
+where
+    F: FnMut(&str, usize, Vec<u8>) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let metadata = find_recording(storage, &query.recording_id).await?;
+    let mut pager = Pager::new(page_bytes);
+    let mut messages = 0;
+    let mut pages = 0;
+    let mut page_topic = String::new();
+    for record in query.records(&metadata) {
+        // The final flush of a topic without new samples
+        if record.bytes == 0 {
+            continue;
+        }
+        let data = coalesce::read(storage, record).await.context(format!(
+            "Failed to read record {} of '{}'",
+            record.timestamp_us, record.topic
+        ))?;
+        let batch = parse_batch_range(&data, query.from_ns, query.to_ns)?;
+        for message in &batch.messages {
+            messages += 1;
+            let first = pager.is_empty();
+            if let Some(page) = pager.push(message) {
+                reply(&page_topic, pages, page).await?;
+                pages += 1;
+                page_topic = published_key(message).to_string();
+            } else if first {
+                page_topic = published_key(message).to_string();
+            }
+        }
+    }
+    if let Some(page) = pager.finish() {
+        reply(&page_topic, pages, page).await?;
+    }
+    Ok(messages)
+}
+
+/// Key a page is sent on: `{key_prefix}/{recording_id}/{topic}/{index}`
+pub fn page_key(
+    key_prefix: &str,
+    recording_id: &str,
+    topic: &str,
+    index: usize,
+) -> Result<KeyExpr<'static>> {
+    KeyExpr::try_from(format!(
+        "{}/{}/{}/{}",
+        key_prefix.trim_end_matches('/'),
+        recording_id,
+        topic.trim_start_matches('/'),
+        index
+    ))
+    .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Queryable serving recorded data from the storage backend
+pub struct DataBridge {
+    session: Arc<Session>,
+    storage: Arc<dyn StorageBackend>,
+    config: DataBridgeConfig,
+    device_id: String,
+    auth: Arc<ControlAuth>,
+    session_updates: Option<SessionUpdates>,
+    tasks: TaskRegistry,
+}
+
+impl DataBridge {
+    pub fn new(
+        session: Arc<Session>,
+        storage: Arc<dyn StorageBackend>,
+        config: DataBridgeConfig,
+        device_id: String,
+    ) -> Self {
+        Self {
+            session,
+            storage,
+            config,
+            device_id,
+            auth: Arc::new(ControlAuth::default()),
+            session_updates: None,
+            tasks: TaskRegistry::new(PROCESS_SCOPE),
+        }
+    }
+
+    /// Require credentials with every query (open to everyone by default)
+    pub fn with_auth(mut self, auth: ControlAuth) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    /// Declare the queryable on the sessions handed out by the session
+    /// supervisor, again after every reconnect
+    pub fn with_session_updates(mut self, updates: SessionUpdates) -> Self {
+        self.session_updates = Some(updates);
+        self
+    }
+
+    /// Track the query handlers in `tasks` (the recorder's process registry)
+    pub fn with_tasks(mut self, tasks: TaskRegistry) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Answer data queries (blocks until stopped)
+    pub async fn run(&self) -> Result<()> {
+        let key = format!("{}/**", self.config.key_prefix.trim_end_matches('/'));
+        let mut updates = self.session_updates.clone();
+        loop {
+            let session = current_session(&mut updates, &self.session);
+            let queryable = session
+                .declare_queryable(&key)
+                .wait()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            info!("Data bridge listening on '{}'", key);
+
+            loop {
+                tokio::select! {
+                    Ok(query) = queryable.recv_async() => {
+                        let storage = self.storage.clone();
+                        let auth = self.auth.clone();
+                        let device_id = self.device_id.clone();
+                        let config = self.config.clone();
+                        self.tasks.spawn(TaskStage::Control, "data query", async move {
+                            if let Err(e) =
+                                Self::handle_query(query, storage, &config, &auth, &device_id).await
+                            {
+                                error!("Error handling data query: {}", e);
+                            }
+                        });
+                    }
+                    _ = session_changed(&mut updates) => break,
+                }
+            }
+        }
+    }
+
+    async fn handle_query(
+        query: Query,
+        storage: Arc<dyn StorageBackend>,
+        config: &DataBridgeConfig,
+        auth: &ControlAuth,
+        device_id: &str,
+    ) -> Result<()> {
+        info!("Received data query on '{}'", query.selector());
+
+        let request = match DataQuery::parse(
+            &config.key_prefix,
+            query.key_expr().as_str(),
+            query.parameters(),
+        ) {
+            Ok(request) => request,
+            Err(e) => {
+                return query
+                    .reply_err(format!("{:#}", e))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e));
+            }
+        };
+
+        if let Err(e) = auth.authorize(
+            attached_credentials(&query).as_ref(),
+            ControlAction::ReadData,
+            device_id,
+            &request.recording_id,
+            query.parameters().as_str().as_bytes(),
+        ) {
+            warn!("Rejected data query: {}", e);
+            return query
+                .reply_err(e.to_string())
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e));
+        }
+
+        let any_key = query.accepts_replies() == ReplyKeyExpr::Any;
+        let result = read_pages(
+            storage.as_ref(),
+            &request,
+            config.page_bytes,
+            |topic, index, page| {
+                let key = page_key(&config.key_prefix, &request.recording_id, topic, index);
+                let reply = match key {
+                    Ok(key) if any_key || query.key_expr().intersects(&key) => {
+                        query.reply(key, page)
+                    }
+                    _ => query.reply(query.key_expr().clone(), page),
+                };
+                async move { reply.await.map_err(|e| anyhow::anyhow!("{}", e)) }
+            },
+        )
+        .await;
+        match result {
+            Ok(messages) => {
+                info!(
+                    "Served {} messages of '{}' from recording {}",
+                    messages, request.topic, request.recording_id
+                );
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Data query on '{}' failed: {:#}", query.selector(), e);
+                query
+                    .reply_err(format!("{:#}", e))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Resumable download of stored recordings (`zenoh-recorder download`)
+//
+// The records listed in the recording metadata are copied from the storage
+// backend into a local directory laid out like the filesystem backend:
+// `{entry}/{timestamp_us}.mcap` per record and `metadata.json` for the
+// recording. Records are fetched in ranges of `chunk_size` bytes and appended
+// to `{timestamp_us}.mcap.part`, each range retried on its own, so a dropped
+// connection or an interrupted run continues from the bytes already on disk.
+// The metadata lists a CRC32C per `CHECKSUM_CHUNK_BYTES` chunk of a record,
+// and each chunk is checked as soon as it's complete; a failing chunk is cut off the part file and fetched
+// again on its own, and a resumed part file keeps its chunks that pass. Records
+// listed without chunk checksums are checked as one chunk. Records already
+// downloaded and intact are skipped, and `metadata.json` is written last, so
+// its presence marks a complete download.
+
+use anyhow::{bail, Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::coalesce;
+use crate::protocol::{RecordChecksum, RecordingMetadata};
+use crate::runtime::{self, fs};
+use crate::storage::{checksum, StorageBackend};
+
+/// Suffix of records that are still being downloaded
+const PART_SUFFIX: &str = "part";
+
+/// How a recording is downloaded
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Bytes fetched per request
+    pub chunk_size: u64,
+    /// Retries of a failed request, and of a chunk failing its checksum
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled up to 30 seconds
+    pub retry_delay: Duration,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 8 * 1024 * 1024,
+            max_retries: 5,
+            retry_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+/// What a download did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadSummary {
+    /// Records fetched by this run
+    pub downloaded: usize,
+    /// Records already downloaded and intact
+    pub skipped: usize,
+    /// Bytes fetched by this run
+    pub bytes: u64,
+    /// Bytes of partial records kept from an earlier run
+    pub resumed_bytes: u64,
+    /// Chunks fetched again after failing their checksum
+    pub refetched: usize,
+}
+
+/// Local file of a downloaded record
+pub fn record_path(dir: &Path, record: &RecordChecksum) -> PathBuf {
+    dir.join(&record.entry)
+        .join(format!("{}.mcap", record.timestamp_us))
+}
+
+/// Download every record of a recording into `dir`
+pub async fn download(
+    storage: &dyn StorageBackend,
+    metadata: &RecordingMetadata,
+    dir: &Path,
+    options: &DownloadOptions,
+) -> Result<DownloadSummary> {
+    if options.chunk_size == 0 {
+        bail!("Chunk size must be greater than 0");
+    }
+    fs::create_dir_all(dir)
+        .await
+        .context(format!("Failed to create {}", dir.display()))?;
+
+    let mut summary = DownloadSummary::default();
+    for record in &metadata.records {
+        download_record(storage, record, dir, options, &mut summary)
+            .await
+            .context(format!(
+                "Failed to download record {} of '{}'",
+                record.timestamp_us, record.topic
+            ))?;
+    }
+
+    fs::write(
+        dir.join("metadata.json"),
+        serde_json::to_vec_pretty(metadata)?,
+    )
+    .await
+    .context("Failed to write the recording metadata")?;
+    info!(
+        "Downloaded recording {} to {}: {} records fetched, {} already present",
+        metadata.recording_id,
+        dir.display(),
+        summary.downloaded,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+async fn download_record(
+    storage: &dyn StorageBackend,
+    record: &RecordChecksum,
+    dir: &Path,
+    options: &DownloadOptions,
+    summary: &mut DownloadSummary,
+) -> Result<()> {
+    let path = record_path(dir, record);
+    let part = path.with_extension(format!("mcap.{}", PART_SUFFIX));
+    match fs::read(&path).await {
+        Ok(data) if checksum(&data) == record.crc32c => {
+            summary.skipped += 1;
+            return Ok(());
+        }
+        // Its intact chunks are kept like those of a part file
+        Ok(_) => {
+            warn!("{} is corrupt, downloading it again", path.display());
+            fs::rename(&path, &part)
+                .await
+                .context(format!("Failed to rename {}", path.display()))?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context(format!("Failed to read {}", path.display())),
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    fetch(storage, record, &part, options, summary).await?;
+    fs::rename(&part, &path)
+        .await
+        .context(format!("Failed to rename {}", part.display()))?;
+    summary.downloaded += 1;
+    Ok(())
+}
+
+/// Chunk size and CRC32C of the chunks a record is checked in
+fn checked_chunks(record: &RecordChecksum) -> Result<(u64, Vec<String>)> {
+    let size = record.bytes as u64;
+    let Some(chunks) = &record.chunks else {
+        return Ok((size.max(1), vec![record.crc32c.clone()]));
+    };
+    if chunks.chunk_bytes == 0 || chunks.crc32c.len() as u64 != size.div_ceil(chunks.chunk_bytes) {
+        bail!(
+            "{} checksums of {} byte chunks don't cover a record of {} bytes",
+            chunks.crc32c.len(),
+            chunks.chunk_bytes,
+            size
+        );
+    }
+    Ok((chunks.chunk_bytes, chunks.crc32c.clone()))
+}
+
+/// Start of the first chunk of `data` left to fetch, and the bytes of `data`
+/// worth keeping: the chunks passing their checksums and an unfinished chunk
+/// after them, which is checked once complete
+fn kept_prefix(data: &[u8], size: u64, chunk_bytes: u64, crcs: &[String]) -> (u64, u64) {
+    let len = data.len() as u64;
+    // Longer than the record: not a prefix of it
+    if len > size {
+        return (0, 0);
+    }
+    let mut start = 0;
+    for crc in crcs {
+        let end = (start + chunk_bytes).min(size);
+        if len < end {
+            return (start, len);
+        }
+        if checksum(&data[start as usize..end as usize]) != *crc {
+            return (start, start);
+        }
+        start = end;
+    }
+    (start, start)
+}
+
+/// Fill the part file of a record up to its size, checking each chunk once
+/// it's complete and fetching a failing chunk again on its own
+async fn fetch(
+    storage: &dyn StorageBackend,
+    record: &RecordChecksum,
+    part: &Path,
+    options: &DownloadOptions,
+    summary: &mut DownloadSummary,
+) -> Result<()> {
+    let size = record.bytes as u64;
+    let (chunk_bytes, crcs) = checked_chunks(record)?;
+    let existing = match fs::read(part).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e).context(format!("Failed to read {}", part.display())),
+    };
+    let (mut start, mut offset) = kept_prefix(&existing, size, chunk_bytes, &crcs);
+    let mut crc = crc32c::crc32c(&existing[start as usize..offset as usize]);
+    if offset < existing.len() as u64 || existing.is_empty() {
+        fs::write(part, &existing[..offset as usize]).await?;
+    }
+    drop(existing);
+    if offset > 0 {
+        info!("Resuming {} at byte {}", part.display(), offset);
+        summary.resumed_bytes += offset;
+    }
+
+    let mut attempt = 0;
+    while start < size {
+        let end = (start + chunk_bytes).min(size);
+        while offset < end {
+            let length = options.chunk_size.min(end - offset);
+            let range = read_range_with_retry(storage, record, offset, length, options).await?;
+            if range.is_empty() {
+                bail!("Record ended at byte {} of {}", offset, size);
+            }
+            crc = crc32c::crc32c_append(crc, &range);
+            offset += range.len() as u64;
+            summary.bytes += range.len() as u64;
+            append(part, range).await?;
+        }
+
+        let expected = &crcs[(start / chunk_bytes) as usize];
+        let actual = format!("{:08x}", crc);
+        if actual == *expected {
+            start = end;
+            crc = crc32c::crc32c(&[]);
+            attempt = 0;
+            continue;
+        }
+        if attempt >= options.max_retries {
+            bail!(
+                "Checksum mismatch of bytes {}..{} after {} attempts: expected {}, got {}",
+                start,
+                end,
+                attempt + 1,
+                expected,
+                actual
+            );
+        }
+        warn!(
+            "Bytes {}..{} of record {} of '{}' failed their checksum (expected {}, got {}), downloading them again",
+            start, end, record.timestamp_us, record.topic, expected, actual
+        );
+        truncate(part, start).await?;
+        offset = start;
+        crc = crc32c::crc32c(&[]);
+        attempt += 1;
+        summary.refetched += 1;
+    }
+    Ok(())
+}
+
+async fn read_range_with_retry(
+    storage: &dyn StorageBackend,
+    record: &RecordChecksum,
+    offset: u64,
+    length: u64,
+    options: &DownloadOptions,
+) -> Result<Vec<u8>> {
+    let mut attempt = 0;
+    let mut delay = options.retry_delay;
+    loop {
+        match coalesce::read_range(storage, record, offset, length).await {
+            Ok(chunk) => return Ok(chunk),
+            Err(e) if attempt < options.max_retries => {
+                warn!(
+                    "Reading bytes {}..{} of record {} failed (attempt {}/{}): {}. Retrying in {:?}",
+                    offset,
+                    offset + length,
+                    record.timestamp_us,
+                    attempt + 1,
+                    options.max_retries,
+                    e,
+                    delay
+                );
+                runtime::sleep(delay).await;
+                delay = (delay * 2).min(Duration::from_secs(30));
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Append a chunk to a part file and flush it to disk
+async fn append(path: &Path, chunk: Vec<u8>) -> Result<()> {
+    let path = path.to_path_buf();
+    runtime::unblock(move || {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .context(format!("Failed to open {}", path.display()))?;
+        file.write_all(&chunk)
+            .context(format!("Failed to write {}", path.display()))?;
+        file.sync_data()?;
+        Ok(())
+    })
+    .await
+}
+
+/// Cut a part file back to `len` bytes
+async fn truncate(path: &Path, len: u64) -> Result<()> {
+    let path = path.to_path_buf();
+    runtime::unblock(move || {
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_len(len))
+            .context(format!("Failed to truncate {}", path.display()))
+    })
+    .await
+}
@@ -0,0 +1,335 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Payload schema drift detection
+//
+// Each flushed batch (segment) gets a structural signature built from the
+// payloads it contains: the set of JSON key paths, or the set of protobuf
+// field tags seen at the top level of the wire format. When the signature of
+// a topic changes between segments a drift event is recorded, which catches
+// publishers that silently change their message layout mid-campaign.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use dashmap::DashMap;
+
+use crate::protocol::{DriftEvent, TopicSignature};
+
+/// Structural signature of one or more payloads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadSignature {
+    /// Detected payload kind: "json" or "protobuf"
+    pub kind: &'static str,
+    /// JSON key paths or protobuf `field:wire_type` tags
+    pub fields: BTreeSet<String>,
+}
+
+impl PayloadSignature {
+    /// Stable 64-bit FNV-1a hash of the signature, hex encoded
+    pub fn hash(&self) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+        feed(self.kind.as_bytes());
+        for field in &self.fields {
+            feed(b"\0");
+            feed(field.as_bytes());
+        }
+        format!("{:016x}", hash)
+    }
+}
+
+/// Compute the structural signature of a single payload
+///
+/// Returns `None` for payloads that are neither a JSON object/array nor
+/// well-formed protobuf wire data.
+pub fn payload_signature(payload: &[u8]) -> Option<PayloadSignature> {
+    json_signature(payload).or_else(|| protobuf_signature(payload))
+}
+
+fn json_signature(payload: &[u8]) -> Option<PayloadSignature> {
+    let first = payload.iter().find(|b| !b.is_ascii_whitespace())?;
+    if *first != b'{' && *first != b'[' {
+        return None;
+    }
+
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    let mut fields = BTreeSet::new();
+    collect_json_paths(&value, "", &mut fields);
+
+    Some(PayloadSignature {
+        kind: "json",
+        fields,
+    })
+}
+
+fn collect_json_paths(value: &serde_json::Value, prefix: &str, fields: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                fields.insert(path.clone());
+                collect_json_paths(child, &path, fields);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            let path = format!("{}[]", prefix);
+            for item in items {
+                collect_json_paths(item, &path, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn protobuf_signature(payload: &[u8]) -> Option<PayloadSignature> {
+    if payload.is_empty() {
+        return None;
+    }
+
+    let mut fields = BTreeSet::new();
+    let mut pos = 0;
+    while pos < payload.len() {
+        let tag = read_varint(payload, &mut pos)?;
+        let field_number = tag >> 3;
+        let wire_type = tag & 0x7;
+        if field_number == 0 {
+            return None;
+        }
+
+        let skip = match wire_type {
+            0 => {
+                read_varint(payload, &mut pos)?;
+                0
+            }
+            1 => 8,
+            2 => read_varint(payload, &mut pos)? as usize,
+            5 => 4,
+            // Groups (3/4) are deprecated; treat anything else as non-protobuf
+            _ => return None,
+        };
+        pos = pos.checked_add(skip)?;
+        if pos > payload.len() {
+            return None;
+        }
+
+        fields.insert(format!("{}:{}", field_number, wire_type));
+    }
+
+    Some(PayloadSignature {
+        kind: "protobuf",
+        fields,
+    })
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Per-topic signature state tracked across segments
+struct TopicDriftState {
+    signature: PayloadSignature,
+    segments: u64,
+}
+
+/// Tracks segment signatures of every topic in a recording
+#[derive(Default)]
+pub struct DriftTracker {
+    topics: DashMap<String, TopicDriftState>,
+    events: Mutex<Vec<DriftEvent>>,
+}
+
+impl DriftTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe one segment of a topic
+    ///
+    /// The segment signature is the union of the signatures of its payloads.
+    /// Returns the drift event if the signature differs from the previous segment.
+    pub fn observe<'a, I>(&self, topic: &str, payloads: I) -> Option<DriftEvent>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let mut segment: Option<PayloadSignature> = None;
+        for payload in payloads {
+            let Some(signature) = payload_signature(payload) else {
+                continue;
+            };
+            match &mut segment {
+                Some(current) if current.kind == signature.kind => {
+                    current.fields.extend(signature.fields)
+                }
+                // Mixed kinds in a segment: keep the first, the switch shows up
+                // as drift in the next segment anyway
+                Some(_) => {}
+                None => segment = Some(signature),
+            }
+        }
+        let segment = segment?;
+
+        let mut entry = match self.topics.get_mut(topic) {
+            Some(entry) => entry,
+            None => {
+                self.topics.insert(
+                    topic.to_string(),
+                    TopicDriftState {
+                        signature: segment,
+                        segments: 1,
+                    },
+                );
+                return None;
+            }
+        };
+
+        entry.segments += 1;
+        if entry.signature == segment {
+            return None;
+        }
+
+        let event = DriftEvent {
+            topic: topic.to_string(),
+            detected_at: chrono::Utc::now().to_rfc3339(),
+            segment_index: entry.segments - 1,
+            previous_kind: entry.signature.kind.to_string(),
+            previous_hash: entry.signature.hash(),
+            new_kind: segment.kind.to_string(),
+            new_hash: segment.hash(),
+            added_fields: segment
+                .fields
+                .difference(&entry.signature.fields)
+                .cloned()
+                .collect(),
+            removed_fields: entry
+                .signature
+                .fields
+                .difference(&segment.fields)
+                .cloned()
+                .collect(),
+        };
+        entry.signature = segment;
+        drop(entry);
+
+        self.events.lock().unwrap().push(event.clone());
+        Some(event)
+    }
+
+    /// All drift events detected so far, in detection order
+    pub fn events(&self) -> Vec<DriftEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Current signature of every observed topic
+    pub fn signatures(&self) -> Vec<TopicSignature> {
+        let mut signatures: Vec<TopicSignature> = self
+            .topics
+            .iter()
+            .map(|entry| TopicSignature {
+                topic: entry.key().clone(),
+                kind: entry.signature.kind.to_string(),
+                hash: entry.signature.hash(),
+                fields: entry.signature.fields.iter().cloned().collect(),
+                segments: entry.segments,
+            })
+            .collect();
+        signatures.sort_by(|a, b| a.topic.cmp(&b.topic));
+        signatures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_signature_nested_keys() {
+        let sig = payload_signature(br#"{"a": 1, "b": {"c": [{"d": true}]}}"#).unwrap();
+        assert_eq!(sig.kind, "json");
+        let fields: Vec<&str> = sig.fields.iter().map(String::as_str).collect();
+        assert_eq!(fields, vec!["a", "b", "b.c", "b.c[].d"]);
+    }
+
+    #[test]
+    fn test_json_signature_ignores_values() {
+        let a = payload_signature(br#"{"speed": 1.0}"#).unwrap();
+        let b = payload_signature(br#"{"speed": 42.5}"#).unwrap();
+        assert_eq!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn test_protobuf_signature() {
+        // field 1 varint 150, field 2 length-delimited "hi"
+        let payload = [0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i'];
+        let sig = payload_signature(&payload).unwrap();
+        assert_eq!(sig.kind, "protobuf");
+        let fields: Vec<&str> = sig.fields.iter().map(String::as_str).collect();
+        assert_eq!(fields, vec!["1:0", "2:2"]);
+    }
+
+    #[test]
+    fn test_unrecognized_payload() {
+        // Truncated length-delimited field
+        assert!(payload_signature(&[0x12, 0x05, b'x']).is_none());
+        assert!(payload_signature(b"").is_none());
+    }
+
+    #[test]
+    fn test_tracker_detects_drift() {
+        let tracker = DriftTracker::new();
+
+        let baseline: Vec<&[u8]> = vec![br#"{"x": 1, "y": 2}"#, br#"{"x": 3, "y": 4}"#];
+        assert!(tracker.observe("/pose", baseline.clone()).is_none());
+        assert!(tracker.observe("/pose", baseline).is_none());
+
+        let changed: Vec<&[u8]> = vec![br#"{"x": 1, "z": 2}"#];
+        let event = tracker.observe("/pose", changed).unwrap();
+        assert_eq!(event.segment_index, 2);
+        assert_eq!(event.added_fields, vec!["z".to_string()]);
+        assert_eq!(event.removed_fields, vec!["y".to_string()]);
+        assert_ne!(event.previous_hash, event.new_hash);
+
+        assert_eq!(tracker.events().len(), 1);
+        let signatures = tracker.signatures();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].segments, 3);
+        assert_eq!(signatures[0].fields, vec!["x".to_string(), "z".to_string()]);
+    }
+
+    #[test]
+    fn test_tracker_skips_raw_segments() {
+        let tracker = DriftTracker::new();
+        let raw: Vec<&[u8]> = vec![&[0xff, 0xff, 0xff]];
+        assert!(tracker.observe("/raw", raw).is_none());
+        assert!(tracker.signatures().is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Error type of the public API
+//
+// Embedders match on the category of a failure; the underlying error (with
+// its context chain) stays available through `source()`.
+
+use crate::protocol::ErrorCode;
+use std::error::Error as StdError;
+
+/// Boxed underlying error of a `RecorderError`
+pub type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Result type of the public API
+pub type Result<T, E = RecorderError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderError {
+    /// Storage backend failure (I/O, HTTP, rejected write, failed read-back)
+    #[error("storage backend error: {0}")]
+    Backend(#[source] BoxError),
+
+    /// Serializing or compressing recorded data failed
+    #[error("serialization error: {0}")]
+    Serialization(#[source] BoxError),
+
+    /// Zenoh session, subscriber or query failure
+    #[error("zenoh error: {0}")]
+    Zenoh(#[source] BoxError),
+
+    /// The operation doesn't apply to the current state
+    #[error("invalid state: {0}")]
+    InvalidState(String),
+
+    /// Invalid or unreadable configuration
+    #[error("configuration error: {0}")]
+    Config(#[source] BoxError),
+
+    /// Not enough free disk space to start a recording
+    #[error("insufficient disk space: {0}")]
+    InsufficientSpace(String),
+}
+
+impl RecorderError {
+    pub fn backend(error: impl Into<BoxError>) -> Self {
+        Self::Backend(error.into())
+    }
+
+    pub fn serialization(error: impl Into<BoxError>) -> Self {
+        Self::Serialization(error.into())
+    }
+
+    pub fn zenoh(error: impl Into<BoxError>) -> Self {
+        Self::Zenoh(error.into())
+    }
+
+    pub fn config(error: impl Into<BoxError>) -> Self {
+        Self::Config(error.into())
+    }
+
+    /// Error code reported to controllers for this failure
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Backend(_) => ErrorCode::BackendUnavailable,
+            Self::InvalidState(_) => ErrorCode::InvalidState,
+            Self::InsufficientSpace(_) => ErrorCode::QuotaExceeded,
+            Self::Serialization(_) | Self::Zenoh(_) | Self::Config(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_source_chain_is_kept() {
+        let inner: anyhow::Result<()> =
+            Err(std::io::Error::other("disk full")).context("Failed to write file: /data/1.mcap");
+        let error = RecorderError::backend(inner.unwrap_err());
+
+        assert!(matches!(error, RecorderError::Backend(_)));
+        assert_eq!(
+            error.to_string(),
+            "storage backend error: Failed to write file: /data/1.mcap"
+        );
+
+        // Display shows the outermost context, the causes follow via source()
+        let chain = format!("{:#}", anyhow::Error::from(error));
+        assert!(chain.contains("disk full"), "{}", chain);
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        let error = RecorderError::InvalidState("recording 'x' not found".to_string());
+        assert_eq!(error.to_string(), "invalid state: recording 'x' not found");
+        assert!(error.source().is_none());
+        assert_eq!(error.code(), ErrorCode::InvalidState);
+    }
+}
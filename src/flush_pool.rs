@@ -0,0 +1,484 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Partitioned, priority-aware flush queue of the flush workers
+//
+// `TopicBuffer`s push `FlushTask`s into the pool, which queues each for a
+// worker chosen by a hash of (recording_id, topic). Each worker processes one
+// task at a time, the tasks of the highest priority class first (see
+// `WorkerConfig::flush_priority`) and in push order within a class. A topic
+// has a single class, so the records of a topic are written in the order they
+// were flushed, while different topics are written in parallel. When a
+// worker's queue is full, a task pushes out the newest task of a lower class,
+// which the worker then only accounts as dropped, or is handed back if there
+// is none. The pool also counts the tasks of every recording that are queued
+// or being processed, so Finish knows when the last record is written.
+
+use dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::buffer::FlushTask;
+use crate::config::FlushPriority;
+use crate::protocol::FlushWorkerStatus;
+
+/// Flush task waiting in a worker's queue
+struct Queued {
+    task: FlushTask,
+    enqueued: Instant,
+}
+
+/// Flush task taken by a worker
+pub struct Dequeued {
+    pub task: FlushTask,
+    /// Time the task waited in the queue
+    pub queued: Duration,
+    /// Pushed out of the full queue by a task of a higher class; its samples
+    /// are dropped, not written
+    pub evicted: bool,
+}
+
+/// Tasks of a worker, by priority class (`FlushPriority as usize`)
+#[derive(Default)]
+struct Queues {
+    classes: [VecDeque<Queued>; 3],
+    evicted: VecDeque<Queued>,
+}
+
+impl Queues {
+    fn len(&self) -> usize {
+        self.classes.iter().map(VecDeque::len).sum()
+    }
+
+    /// Evicted tasks first (they are only accounted), then the oldest task
+    /// of the highest class
+    fn pop(&mut self) -> Option<(Queued, bool)> {
+        if let Some(queued) = self.evicted.pop_front() {
+            return Some((queued, true));
+        }
+        self.classes
+            .iter_mut()
+            .rev()
+            .find_map(VecDeque::pop_front)
+            .map(|queued| (queued, false))
+    }
+}
+
+struct Partition {
+    capacity: usize,
+    queues: Mutex<Queues>,
+    /// Wakes the worker waiting for a task
+    ready: Notify,
+    busy_us: AtomicU64,
+    tasks: AtomicU64,
+    /// Tasks rejected or evicted because the queue was full
+    dropped: AtomicU64,
+    /// Most tasks waiting at once
+    max_queued: AtomicUsize,
+    /// Time from enqueue to dequeue, summed over the dequeued tasks
+    wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+    dequeued: AtomicU64,
+}
+
+impl Partition {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            queues: Mutex::new(Queues::default()),
+            ready: Notify::new(),
+            busy_us: AtomicU64::new(0),
+            tasks: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            max_queued: AtomicUsize::new(0),
+            wait_us: AtomicU64::new(0),
+            max_wait_us: AtomicU64::new(0),
+            dequeued: AtomicU64::new(0),
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.queues.lock().unwrap().len()
+    }
+
+    /// Next task, with the time it waited in the queue
+    fn pop(&self) -> Option<Dequeued> {
+        let (queued, evicted) = self.queues.lock().unwrap().pop()?;
+        let wait = queued.enqueued.elapsed();
+        let wait_us = wait.as_micros() as u64;
+        self.wait_us.fetch_add(wait_us, Ordering::Relaxed);
+        self.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+        self.dequeued.fetch_add(1, Ordering::Relaxed);
+        Some(Dequeued {
+            task: queued.task,
+            queued: wait,
+            evicted,
+        })
+    }
+}
+
+/// Flush queue shared by the topic buffers and the flush workers
+pub struct FlushPool {
+    partitions: Vec<Partition>,
+    /// Queued and running tasks, by recording
+    pending: DashMap<String, usize>,
+    started: Instant,
+}
+
+impl FlushPool {
+    /// `workers` partitions (at least one) of `capacity` tasks each
+    pub fn new(workers: usize, capacity: usize) -> Self {
+        Self {
+            partitions: (0..workers.max(1))
+                .map(|_| Partition::new(capacity.max(1)))
+                .collect(),
+            pending: DashMap::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Worker processing the flush tasks of a topic of a recording
+    pub fn partition_of(&self, recording_id: &str, topic: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (recording_id, topic).hash(&mut hasher);
+        (hasher.finish() % self.partitions.len() as u64) as usize
+    }
+
+    /// Queue a task of class `priority` for its worker
+    ///
+    /// With the worker's queue full, the newest task of the lowest class
+    /// below `priority` is evicted to make room; the task is handed back
+    /// when there is none.
+    pub fn push(&self, task: FlushTask, priority: FlushPriority) -> Result<(), FlushTask> {
+        let partition = &self.partitions[self.partition_of(&task.recording_id, &task.topic)];
+        let recording_id = task.recording_id.clone();
+        // Counted before queueing so a fast worker can't finish it first
+        *self.pending.entry(recording_id.clone()).or_default() += 1;
+        let queued = Queued {
+            task,
+            enqueued: Instant::now(),
+        };
+
+        let mut queues = partition.queues.lock().unwrap();
+        if queues.len() >= partition.capacity {
+            let victim = queues.classes[..priority as usize]
+                .iter_mut()
+                .find_map(VecDeque::pop_back);
+            partition.dropped.fetch_add(1, Ordering::Relaxed);
+            match victim {
+                // Still pending until its worker accounted it
+                Some(victim) => queues.evicted.push_back(victim),
+                None => {
+                    drop(queues);
+                    self.task_done(&recording_id);
+                    return Err(queued.task);
+                }
+            }
+        }
+        queues.classes[priority as usize].push_back(queued);
+        partition
+            .max_queued
+            .fetch_max(queues.len(), Ordering::Relaxed);
+        drop(queues);
+        partition.ready.notify_one();
+        Ok(())
+    }
+
+    /// A task of `recording_id` was processed (or given up on)
+    pub fn task_done(&self, recording_id: &str) {
+        self.pending.remove_if_mut(recording_id, |_, count| {
+            *count = count.saturating_sub(1);
+            *count == 0
+        });
+    }
+
+    /// Tasks of `recording_id` that are queued or being processed
+    pub fn pending_tasks(&self, recording_id: &str) -> usize {
+        self.pending.get(recording_id).map_or(0, |count| *count)
+    }
+
+    /// Wait for the next task of a worker
+    pub async fn recv(&self, worker: usize) -> Dequeued {
+        let partition = &self.partitions[worker];
+        loop {
+            if let Some(dequeued) = partition.pop() {
+                return dequeued;
+            }
+            // A push in between leaves a permit, so this returns at once
+            partition.ready.notified().await;
+        }
+    }
+
+    /// Next task of a worker, if one is waiting (for monitoring and tests)
+    #[allow(dead_code)]
+    pub fn try_pop(&self, worker: usize) -> Option<FlushTask> {
+        self.partitions[worker].pop().map(|dequeued| dequeued.task)
+    }
+
+    /// Tasks waiting in all queues, evicted ones aside (for monitoring and
+    /// tests)
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.partitions.iter().map(Partition::queued).sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Account a processed task to a worker
+    pub fn record_busy(&self, worker: usize, busy: Duration) {
+        let partition = &self.partitions[worker];
+        partition
+            .busy_us
+            .fetch_add(busy.as_micros() as u64, Ordering::Relaxed);
+        partition.tasks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Utilization and queue metrics of every worker since the pool was created
+    pub fn stats(&self) -> Vec<FlushWorkerStatus> {
+        let elapsed_us = self.started.elapsed().as_micros().max(1) as f64;
+        self.partitions
+            .iter()
+            .enumerate()
+            .map(|(worker, partition)| {
+                let busy_us = partition.busy_us.load(Ordering::Relaxed);
+                let dequeued = partition.dequeued.load(Ordering::Relaxed).max(1);
+                FlushWorkerStatus {
+                    worker,
+                    queued: partition.queued(),
+                    tasks: partition.tasks.load(Ordering::Relaxed),
+                    busy_seconds: busy_us as f64 / 1e6,
+                    utilization: (busy_us as f64 / elapsed_us).min(1.0),
+                    max_queued: partition.max_queued.load(Ordering::Relaxed),
+                    dropped: partition.dropped.load(Ordering::Relaxed),
+                    avg_wait_ms: partition.wait_us.load(Ordering::Relaxed) as f64
+                        / dequeued as f64
+                        / 1e3,
+                    max_wait_ms: partition.max_wait_us.load(Ordering::Relaxed) as f64 / 1e3,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(recording_id: &str, topic: &str, sequence: u64) -> FlushTask {
+        FlushTask {
+            topic: topic.to_string(),
+            samples: vec![],
+            recording_id: recording_id.to_string(),
+            timestamps_ns: vec![sequence],
+            received_ns: vec![],
+        }
+    }
+
+    #[test]
+    fn test_topic_order_kept_within_partition() {
+        let pool = FlushPool::new(4, 100);
+        let topics = ["/camera", "/lidar", "/imu", "/gps", "/odom"];
+        for sequence in 0..10 {
+            for topic in topics {
+                assert!(pool
+                    .push(task("rec", topic, sequence), FlushPriority::Normal)
+                    .is_ok());
+            }
+        }
+        assert_eq!(pool.len(), 50);
+
+        let mut seen: std::collections::HashMap<String, Vec<u64>> = Default::default();
+        for worker in 0..pool.workers() {
+            while let Some(task) = pool.try_pop(worker) {
+                assert_eq!(pool.partition_of(&task.recording_id, &task.topic), worker);
+                seen.entry(task.topic)
+                    .or_default()
+                    .push(task.timestamps_ns[0]);
+            }
+        }
+        for topic in topics {
+            assert_eq!(seen[topic], (0..10).collect::<Vec<_>>(), "{}", topic);
+        }
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_queues_are_bounded() {
+        let pool = FlushPool::new(2, 3);
+        let worker = pool.partition_of("rec", "/a");
+        for sequence in 0..3 {
+            assert!(pool
+                .push(task("rec", "/a", sequence), FlushPriority::Normal)
+                .is_ok());
+        }
+        // The worker's queue is full; the task is handed back
+        let rejected = pool
+            .push(task("rec", "/a", 3), FlushPriority::Normal)
+            .unwrap_err();
+        assert_eq!(rejected.timestamps_ns, vec![3]);
+
+        assert_eq!(pool.try_pop(worker).unwrap().timestamps_ns, vec![0]);
+        assert!(pool
+            .push(task("rec", "/a", 3), FlushPriority::Normal)
+            .is_ok());
+        let stats = &pool.stats()[worker];
+        assert_eq!(stats.queued, 3);
+        assert_eq!(stats.max_queued, 3);
+        assert_eq!(stats.dropped, 1);
+        let order: Vec<u64> = std::iter::from_fn(|| pool.try_pop(worker))
+            .map(|t| t.timestamps_ns[0])
+            .collect();
+        assert_eq!(order, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_higher_classes_first() {
+        let pool = FlushPool::new(1, 10);
+        assert!(pool
+            .push(task("rec", "/video", 0), FlushPriority::Low)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/odom", 1), FlushPriority::Normal)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/video", 2), FlushPriority::Low)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/safety", 3), FlushPriority::High)
+            .is_ok());
+        let order: Vec<u64> = std::iter::from_fn(|| pool.try_pop(0))
+            .map(|t| t.timestamps_ns[0])
+            .collect();
+        assert_eq!(order, vec![3, 1, 0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_evicts_lower_classes() {
+        let pool = FlushPool::new(1, 3);
+        assert!(pool
+            .push(task("rec", "/video", 0), FlushPriority::Low)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/video", 1), FlushPriority::Low)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/odom", 2), FlushPriority::Normal)
+            .is_ok());
+
+        // The newest low-priority task makes room
+        assert!(pool
+            .push(task("rec", "/safety", 3), FlushPriority::High)
+            .is_ok());
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.pending_tasks("rec"), 4);
+        // Nothing below normal is left for a normal task
+        assert!(pool
+            .push(task("rec", "/video", 4), FlushPriority::Low)
+            .is_err());
+        assert!(pool
+            .push(task("rec", "/odom", 5), FlushPriority::Normal)
+            .is_ok());
+        assert!(pool
+            .push(task("rec", "/odom", 6), FlushPriority::Normal)
+            .is_err());
+        assert_eq!(pool.stats()[0].dropped, 4);
+
+        let mut taken = Vec::new();
+        for _ in 0..5 {
+            let dequeued = pool.recv(0).await;
+            taken.push((dequeued.task.timestamps_ns[0], dequeued.evicted));
+            pool.task_done("rec");
+        }
+        assert_eq!(
+            taken,
+            vec![(1, true), (0, true), (3, false), (2, false), (5, false)]
+        );
+        assert_eq!(pool.pending_tasks("rec"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_recv_wakes_on_push() {
+        let pool = std::sync::Arc::new(FlushPool::new(1, 10));
+        let waiter = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.recv(0).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(pool
+            .push(task("rec", "/a", 7), FlushPriority::Normal)
+            .is_ok());
+        let dequeued = tokio::time::timeout(Duration::from_millis(500), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dequeued.task.timestamps_ns, vec![7]);
+        assert!(dequeued.queued < Duration::from_millis(500));
+        assert!(!dequeued.evicted);
+    }
+
+    #[test]
+    fn test_stats() {
+        let pool = FlushPool::new(2, 10);
+        pool.record_busy(1, Duration::from_millis(5));
+        let stats = pool.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].tasks, 0);
+        assert_eq!(stats[1].tasks, 1);
+        assert!((stats[1].busy_seconds - 0.005).abs() < 1e-9);
+        assert!(stats[1].utilization > 0.0 && stats[1].utilization <= 1.0);
+    }
+
+    #[test]
+    fn test_pending_tasks_per_recording() {
+        let pool = FlushPool::new(1, 2);
+        assert!(pool.push(task("a", "/x", 0), FlushPriority::Normal).is_ok());
+        assert!(pool.push(task("b", "/x", 0), FlushPriority::Normal).is_ok());
+        assert!(pool
+            .push(task("a", "/x", 1), FlushPriority::Normal)
+            .is_err());
+        assert_eq!(pool.pending_tasks("a"), 1);
+
+        // Still pending while the worker processes it
+        let task = pool.try_pop(0).unwrap();
+        assert_eq!(pool.pending_tasks("a"), 1);
+        pool.task_done(&task.recording_id);
+        assert_eq!(pool.pending_tasks("a"), 0);
+        assert_eq!(pool.pending_tasks("b"), 1);
+    }
+
+    #[test]
+    fn test_wait_time() {
+        let pool = FlushPool::new(1, 10);
+        assert!(pool
+            .push(task("rec", "/a", 0), FlushPriority::Normal)
+            .is_ok());
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(pool.try_pop(0).is_some());
+        let stats = &pool.stats()[0];
+        assert!(stats.max_wait_ms >= 10.0);
+        assert_eq!(stats.avg_wait_ms, stats.max_wait_ms);
+    }
+}
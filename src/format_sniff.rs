@@ -0,0 +1,113 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Payload format detection (`schema.default_format = "auto"`)
+//
+// The first samples of a topic without a per-topic schema are matched
+// against a few well-known signatures: JPEG and PNG magic numbers, the CDR
+// encapsulation header of ROS 2 / DDS payloads, JSON documents and
+// well-formed protobuf wire data. The format is only tagged when every
+// inspected sample agrees; anything else is recorded as "raw".
+
+use crate::config::{find_per_topic, SchemaConfig};
+use crate::drift::payload_signature;
+
+/// `default_format` value enabling detection
+pub const AUTO: &str = "auto";
+
+/// Format recorded when detection is inconclusive
+pub const RAW: &str = "raw";
+
+/// Number of samples of a topic inspected
+pub const SNIFF_SAMPLES: usize = 8;
+
+/// Whether the format of `topic` is left to detection
+pub fn enabled_for(config: &SchemaConfig, topic: &str) -> bool {
+    config.default_format == AUTO && find_per_topic(&config.per_topic, topic).is_none()
+}
+
+/// Format of a single payload, if it has a recognizable signature
+pub fn sniff(payload: &[u8]) -> Option<&'static str> {
+    if payload.starts_with(&[0xff, 0xd8, 0xff]) {
+        return Some("jpeg");
+    }
+    if payload.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("png");
+    }
+    if is_cdr(payload) {
+        return Some("cdr");
+    }
+    payload_signature(payload).map(|signature| signature.kind)
+}
+
+/// Format shared by `payloads`, ignoring empty ones
+///
+/// Returns `None` when there is nothing to inspect yet, and "raw" when a
+/// payload is unrecognized or the payloads disagree.
+pub fn detect<'a>(payloads: impl IntoIterator<Item = &'a [u8]>) -> Option<&'static str> {
+    let mut detected = None;
+    for payload in payloads.into_iter().filter(|p| !p.is_empty()) {
+        match (sniff(payload), detected) {
+            (None, _) => return Some(RAW),
+            (Some(format), Some(previous)) if format != previous => return Some(RAW),
+            (format, _) => detected = format,
+        }
+    }
+    detected
+}
+
+/// Whether the payload starts with an XCDR1/XCDR2 encapsulation header:
+/// a big-endian representation identifier and options holding only the
+/// padding bits
+fn is_cdr(payload: &[u8]) -> bool {
+    // Identifiers 0x0004/0x0005 are unassigned
+    matches!(
+        payload,
+        [0x00, 0x00..=0x03 | 0x06..=0x0b, 0x00, options, _, ..] if options & !0x03 == 0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_signatures() {
+        assert_eq!(sniff(&[0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10]), Some("jpeg"));
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"), Some("png"));
+        // std_msgs/String "hi", little endian
+        assert_eq!(
+            sniff(&[0x00, 0x01, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, b'h', b'i', 0x00]),
+            Some("cdr")
+        );
+        assert_eq!(sniff(br#"{"speed": 1.5}"#), Some("json"));
+        assert_eq!(
+            sniff(&[0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i']),
+            Some("protobuf")
+        );
+        assert_eq!(sniff(b"idle"), None);
+        // A header alone is not a CDR payload
+        assert_eq!(sniff(&[0x00, 0x01, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn test_detect_needs_agreement() {
+        let json: &[u8] = br#"{"a": 1}"#;
+        let jpeg: &[u8] = &[0xff, 0xd8, 0xff, 0xdb];
+        assert_eq!(detect([json, b"", json]), Some("json"));
+        assert_eq!(detect([json, jpeg]), Some(RAW));
+        assert_eq!(detect([json, b"not json"]), Some(RAW));
+        assert_eq!(detect([b"" as &[u8]]), None);
+    }
+}
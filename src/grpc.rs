@@ -0,0 +1,516 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// gRPC control interface (`grpc` feature)
+//
+// Serves Start/Pause/Resume/Cancel/Finish/Status/WaitForCompletion of the
+// Zenoh control queryable over gRPC, so backends without Zenoh can drive the
+// recorder through a gateway. Both interfaces share one RecorderManager.
+// With `control.auth` enabled, calls carry a token as `authorization: Bearer
+// <token>` metadata, or any credentials as JSON in `recorder-auth` metadata.
+// Signatures cover the call's message as the body, encoded with map entries in
+// key order.
+
+use anyhow::Result;
+use prost::Message;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::control_auth::{AuthError, ControlAction, ControlAuth};
+use crate::protocol::{
+    BufferStrategy, CompletionResponse, CompressionLevel, CompressionType, ErrorCode, FlushReason,
+    IfExists, RecorderCommand, RecorderRequest, RecorderResponse, RecordingStatus, RequestAuth,
+    StatusResponse, TaskStage, ValidationError,
+};
+use crate::recorder::RecorderManager;
+use crate::validation::validate_start;
+
+pub mod proto {
+    tonic::include_proto!("recorder_control");
+}
+
+use proto::recorder_control_server::{RecorderControl, RecorderControlServer};
+
+/// gRPC server for recorder commands
+pub struct GrpcControl {
+    recorder_manager: Arc<RecorderManager>,
+    auth: ControlAuth,
+    /// Device that signed requests must name
+    device_id: String,
+}
+
+impl GrpcControl {
+    pub fn new(recorder_manager: Arc<RecorderManager>) -> Self {
+        Self {
+            recorder_manager,
+            auth: ControlAuth::default(),
+            device_id: String::new(),
+        }
+    }
+
+    /// Require credentials with every call (open to everyone by default)
+    pub fn with_auth(mut self, auth: ControlAuth, device_id: String) -> Self {
+        self.auth = auth;
+        self.device_id = device_id;
+        self
+    }
+
+    /// Check the credentials of a call, signed over the protobuf encoding
+    /// of its message
+    fn authorize<T: Message>(
+        &self,
+        request: &Request<T>,
+        action: ControlAction,
+        recording_id: &str,
+    ) -> std::result::Result<(), Status> {
+        let credentials = credentials(request);
+        match self.auth.authorize(
+            credentials.as_ref(),
+            action,
+            &self.device_id,
+            recording_id,
+            &request.get_ref().encode_to_vec(),
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!("Rejected gRPC {} call: {}", action.name(), e);
+                Err(match e {
+                    AuthError::Forbidden { .. } => Status::permission_denied(e.to_string()),
+                    AuthError::Missing | AuthError::Invalid => {
+                        Status::unauthenticated(e.to_string())
+                    }
+                })
+            }
+        }
+    }
+
+    /// Serve on `addr` (blocks until stopped)
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        info!("gRPC control interface listening on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(RecorderControlServer::new(self))
+            .serve(addr)
+            .await?;
+        Ok(())
+    }
+
+    /// Recorder request of a gRPC Start, or every problem with it
+    fn start_request(
+        &self,
+        start: proto::StartRequest,
+    ) -> std::result::Result<RecorderRequest, Vec<ValidationError>> {
+        let (default_type, default_level) = self.recorder_manager.compression_defaults();
+        let mut errors = Vec::new();
+
+        let compression_type = match start.compression_type.as_deref() {
+            None => default_type,
+            Some(name) => CompressionType::from_name(name).unwrap_or_else(|| {
+                errors.push(ValidationError::new(
+                    "compression_type",
+                    format!("unknown type '{}'; use none, lz4 or zstd", name),
+                ));
+                default_type
+            }),
+        };
+        let compression_level = match start.compression_level {
+            None => default_level,
+            Some(index) => u8::try_from(index)
+                .ok()
+                .and_then(CompressionLevel::from_index)
+                .unwrap_or_else(|| {
+                    errors.push(ValidationError::new(
+                        "compression_level",
+                        format!("must be between 0 and 4, got {}", index),
+                    ));
+                    default_level
+                }),
+        };
+        let if_exists = start.if_exists.as_deref().and_then(|name| {
+            let policy = if_exists_from_name(name);
+            if policy.is_none() {
+                errors.push(ValidationError::new(
+                    "if_exists",
+                    format!(
+                        "unknown policy '{}'; use error, return_existing or restart",
+                        name
+                    ),
+                ));
+            }
+            policy
+        });
+        let buffer_strategy = start.buffer_strategy.as_deref().and_then(|name| {
+            let strategy = buffer_strategy_from_name(name);
+            if strategy.is_none() {
+                errors.push(ValidationError::new(
+                    "buffer_strategy",
+                    format!(
+                        "unknown strategy '{}'; use low_latency, high_throughput or low_memory",
+                        name
+                    ),
+                ));
+            }
+            strategy
+        });
+
+        let request = RecorderRequest {
+            command: RecorderCommand::Start,
+            recording_id: start.recording_id,
+            scene: start.scene,
+            skills: start.skills,
+            organization: start.organization,
+            task_id: start.task_id,
+            device_id: start.device_id,
+            data_collector_id: start.data_collector_id,
+            topics: start.topics,
+            compression_level,
+            compression_type,
+            upload_limit: None,
+            bucket: start.bucket,
+            controller_liveliness: start.controller_liveliness,
+            priority: start.priority,
+            if_exists,
+            wait_timeout_ms: None,
+            auth: None,
+            environment: start.environment.into_iter().collect(),
+            labels: start.labels.into_iter().collect(),
+            execute_at_ns: None,
+            exclude_topics: start.exclude_topics,
+            buffer_strategy,
+            retention_seconds: start.retention_seconds,
+        };
+        if errors.is_empty() {
+            return Ok(request);
+        }
+        // Report the other problems of the request in the same round trip
+        errors.extend(validate_start(&request, default_level));
+        Err(errors)
+    }
+}
+
+#[tonic::async_trait]
+impl RecorderControl for GrpcControl {
+    async fn start(
+        &self,
+        request: Request<proto::StartRequest>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        let recording_id = request.get_ref().recording_id.clone().unwrap_or_default();
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Start),
+            &recording_id,
+        )?;
+        let response = match self.start_request(request.into_inner()) {
+            Ok(request) => self.recorder_manager.start_recording(request).await,
+            Err(errors) => RecorderResponse::invalid(errors),
+        };
+        Ok(Response::new(response.into()))
+    }
+
+    async fn pause(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Pause),
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.pause_recording(&recording_id).await;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn resume(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Resume),
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.resume_recording(&recording_id).await;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn cancel(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Cancel),
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.cancel_recording(&recording_id).await;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn finish(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Finish),
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.begin_finish(&recording_id).await;
+        if response.success {
+            // Like the Zenoh Finish: the upload completes in the background
+            let recorder_manager = self.recorder_manager.clone();
+            let name = format!("finish {}", recording_id);
+            self.recorder_manager
+                .tasks()
+                .spawn(TaskStage::Flush, name, async move {
+                    recorder_manager.complete_finish(&recording_id).await;
+                });
+        }
+        Ok(Response::new(response.into()))
+    }
+
+    async fn rollover(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::ControlResponse>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::Rollover),
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.rollover(&recording_id).await;
+        if response.success {
+            // The finished recording uploads in the background, like on Finish
+            let recorder_manager = self.recorder_manager.clone();
+            let name = format!("finish {}", recording_id);
+            self.recorder_manager
+                .tasks()
+                .spawn(TaskStage::Flush, name, async move {
+                    recorder_manager.complete_finish(&recording_id).await;
+                });
+        }
+        Ok(Response::new(response.into()))
+    }
+
+    async fn status(
+        &self,
+        request: Request<proto::RecordingRef>,
+    ) -> std::result::Result<Response<proto::StatusReply>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Status,
+            &request.get_ref().recording_id,
+        )?;
+        let recording_id = request.into_inner().recording_id;
+        let response = self.recorder_manager.get_status(&recording_id).await;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn wait_for_completion(
+        &self,
+        request: Request<proto::CompletionRequest>,
+    ) -> std::result::Result<Response<proto::CompletionReply>, Status> {
+        self.authorize(
+            &request,
+            ControlAction::Command(&RecorderCommand::WaitForCompletion),
+            &request.get_ref().recording_id,
+        )?;
+        let request = request.into_inner();
+        let response = self
+            .recorder_manager
+            .wait_for_completion(
+                &request.recording_id,
+                Duration::from_millis(request.timeout_ms),
+            )
+            .await;
+        Ok(Response::new(response.into()))
+    }
+}
+
+/// Credentials in the metadata of a call
+fn credentials<T>(request: &Request<T>) -> Option<RequestAuth> {
+    let metadata = request.metadata();
+    if let Some(auth) = metadata.get("recorder-auth") {
+        return serde_json::from_slice(auth.as_bytes()).ok();
+    }
+    let token = metadata
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+    Some(RequestAuth::Token {
+        token: token.to_string(),
+    })
+}
+
+fn if_exists_from_name(name: &str) -> Option<IfExists> {
+    match name {
+        "error" => Some(IfExists::Error),
+        "return_existing" => Some(IfExists::ReturnExisting),
+        "restart" => Some(IfExists::Restart),
+        _ => None,
+    }
+}
+
+fn buffer_strategy_from_name(name: &str) -> Option<BufferStrategy> {
+    match name {
+        "low_latency" => Some(BufferStrategy::LowLatency),
+        "high_throughput" => Some(BufferStrategy::HighThroughput),
+        "low_memory" => Some(BufferStrategy::LowMemory),
+        _ => None,
+    }
+}
+
+/// Name of a status as in JSON responses
+fn status_name(status: RecordingStatus) -> String {
+    match status {
+        RecordingStatus::Idle => "idle",
+        RecordingStatus::Recording => "recording",
+        RecordingStatus::Paused => "paused",
+        RecordingStatus::Uploading => "uploading",
+        RecordingStatus::Finished => "finished",
+        RecordingStatus::Archived => "archived",
+        RecordingStatus::Cancelled => "cancelled",
+    }
+    .to_string()
+}
+
+/// Name of a flush reason as in JSON responses
+fn flush_reason_name(reason: FlushReason) -> String {
+    match reason {
+        FlushReason::Size => "size",
+        FlushReason::Time => "time",
+        FlushReason::Forced => "forced",
+        FlushReason::Shutdown => "shutdown",
+    }
+    .to_string()
+}
+
+/// Name of an error code as in JSON responses
+fn error_code_name(code: ErrorCode) -> String {
+    match code {
+        ErrorCode::InvalidRequest => "invalid_request",
+        ErrorCode::NotFound => "not_found",
+        ErrorCode::AlreadyExists => "already_exists",
+        ErrorCode::InvalidState => "invalid_state",
+        ErrorCode::BackendUnavailable => "backend_unavailable",
+        ErrorCode::QuotaExceeded => "quota_exceeded",
+        ErrorCode::Unauthorized => "unauthorized",
+        ErrorCode::Forbidden => "forbidden",
+        ErrorCode::Internal => "internal",
+    }
+    .to_string()
+}
+
+impl From<RecorderResponse> for proto::ControlResponse {
+    fn from(response: RecorderResponse) -> Self {
+        Self {
+            success: response.success,
+            message: response.message,
+            recording_id: response.recording_id,
+            bucket_name: response.bucket_name,
+            status: response.status.map(status_name),
+            errors: response
+                .errors
+                .into_iter()
+                .map(|e| proto::FieldError {
+                    field: e.field,
+                    message: e.message,
+                })
+                .collect(),
+            error_code: response.error_code.map(error_code_name),
+        }
+    }
+}
+
+impl From<StatusResponse> for proto::StatusReply {
+    fn from(response: StatusResponse) -> Self {
+        Self {
+            success: response.success,
+            message: response.message,
+            status: status_name(response.status),
+            scene: response.scene,
+            skills: response.skills,
+            organization: response.organization,
+            task_id: response.task_id,
+            device_id: response.device_id,
+            data_collector_id: response.data_collector_id,
+            active_topics: response.active_topics,
+            buffer_size_bytes: response.buffer_size_bytes,
+            total_recorded_bytes: response.total_recorded_bytes,
+            total_payload_bytes: response.total_payload_bytes,
+            compression_ratio: response.compression_ratio,
+            samples_missing_timestamp: response.samples_missing_timestamp,
+            topic_paused: response.topic_paused.into_iter().collect(),
+            per_topic: response
+                .per_topic
+                .into_iter()
+                .map(|t| proto::TopicStats {
+                    topic: t.topic,
+                    samples_received: t.samples_received,
+                    bytes_buffered: t.bytes_buffered,
+                    bytes_flushed: t.bytes_flushed,
+                    bytes_stored: t.bytes_stored,
+                    compression_ratio: t.compression_ratio,
+                    last_sample_at: t.last_sample_at,
+                    dropped: t.dropped,
+                    shm_samples: t.shm_samples,
+                    duplicates_suppressed: t.duplicates_suppressed,
+                    high_watermark_bytes: t.high_watermark_bytes,
+                    flushes: Some(proto::FlushCounts {
+                        size: t.flushes.size,
+                        time: t.flushes.time,
+                        forced: t.flushes.forced,
+                        shutdown: t.flushes.shutdown,
+                    }),
+                    last_flush_reason: t.last_flush_reason.map(flush_reason_name),
+                })
+                .collect(),
+            uploads: response
+                .uploads
+                .into_iter()
+                .map(|u| proto::SegmentUpload {
+                    topic: u.topic,
+                    timestamp_us: u.timestamp_us,
+                    bytes_uploaded: u.bytes_uploaded,
+                    bytes_total: u.bytes_total,
+                })
+                .collect(),
+            upload_percent: response.upload_percent,
+            environment: response.environment.into_iter().collect(),
+            error_code: response.error_code.map(error_code_name),
+        }
+    }
+}
+
+impl From<CompletionResponse> for proto::CompletionReply {
+    fn from(response: CompletionResponse) -> Self {
+        Self {
+            success: response.success,
+            message: response.message,
+            recording_id: response.recording_id,
+            status: status_name(response.status),
+            completed: response.completed,
+            upload_percent: response.upload_percent,
+            backlog_bytes: response.backlog_bytes,
+        }
+    }
+}
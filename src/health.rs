@@ -0,0 +1,147 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Recorder health announcements
+//
+// A liveliness token on `{key_prefix}/alive/{device_id}` exists for as long as
+// the recorder runs and is connected, so fleet managers notice a dead recorder
+// from the token going away instead of timing out control queries. Every
+// `heartbeat_interval_ms` a `Heartbeat` with the version, uptime, free disk and
+// backend health is published on `{key_prefix}/health/{device_id}`.
+
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use zenoh::liveliness::LivelinessToken;
+use zenoh::{Session, Wait};
+
+use crate::config::HealthConfig;
+use crate::protocol::Heartbeat;
+use crate::runtime;
+use crate::storage::StorageBackend;
+
+/// Liveliness token and heartbeat publisher of the recorder
+pub struct HealthAnnouncer {
+    session: RwLock<Arc<Session>>,
+    device_id: String,
+    alive_key: String,
+    health_key: String,
+    started: Instant,
+    /// Declared on the current session (None after `undeclare`)
+    token: Mutex<Option<LivelinessToken>>,
+}
+
+impl HealthAnnouncer {
+    /// Announcer of `device_id`; nothing is declared before `declare`
+    pub fn new(session: Arc<Session>, config: &HealthConfig, device_id: &str) -> Self {
+        Self {
+            session: RwLock::new(session),
+            device_id: device_id.to_string(),
+            alive_key: format!("{}/alive/{}", config.key_prefix, device_id),
+            health_key: format!("{}/health/{}", config.key_prefix, device_id),
+            started: Instant::now(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Declare the liveliness token on `session`, replacing the token of the
+    /// previous session
+    pub fn declare(&self, session: Arc<Session>) {
+        *self.session.write().unwrap() = session.clone();
+        let mut token = self.token.lock().unwrap();
+        // The token of a closed session is gone already
+        token.take();
+        match session.liveliness().declare_token(&self.alive_key).wait() {
+            Ok(declared) => *token = Some(declared),
+            Err(e) => warn!(
+                "Failed to declare liveliness token {}: {}",
+                self.alive_key, e
+            ),
+        }
+    }
+
+    /// Withdraw the liveliness token (the recorder shuts down)
+    pub fn undeclare(&self) {
+        let Some(token) = self.token.lock().unwrap().take() else {
+            return;
+        };
+        if let Err(e) = token.undeclare().wait() {
+            warn!(
+                "Failed to undeclare liveliness token {}: {}",
+                self.alive_key, e
+            );
+        }
+    }
+
+    /// Report of the recorder's current health
+    ///
+    /// The backend health check counts as failed when it takes longer than
+    /// `check_timeout`, so a hanging backend doesn't hold back the heartbeat.
+    pub async fn heartbeat(
+        &self,
+        backend: &dyn StorageBackend,
+        active_recordings: usize,
+        check_timeout: Duration,
+    ) -> Heartbeat {
+        let checked = tokio::select! {
+            result = backend.health_check() => Some(result),
+            _ = runtime::sleep(check_timeout) => None,
+        };
+        let (backend_healthy, backend_error) = match checked {
+            Some(Ok(healthy)) => (healthy, None),
+            Some(Err(e)) => (false, Some(e.to_string())),
+            None => (
+                false,
+                Some(format!(
+                    "health check timed out after {} ms",
+                    check_timeout.as_millis()
+                )),
+            ),
+        };
+        let free_disk_bytes = match backend.free_bytes().await {
+            Ok(free) => free,
+            Err(e) => {
+                warn!("Failed to read the free disk space: {}", e);
+                None
+            }
+        };
+
+        Heartbeat {
+            device_id: self.device_id.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            free_disk_bytes,
+            backend_type: backend.backend_type().to_string(),
+            backend_healthy,
+            backend_error,
+            active_recordings,
+            at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Publish `heartbeat` on the health key
+    pub fn publish(&self, heartbeat: &Heartbeat) {
+        let payload = match serde_json::to_vec(heartbeat) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize heartbeat: {}", e);
+                return;
+            }
+        };
+        let session = self.session.read().unwrap().clone();
+        if let Err(e) = session.put(&self.health_key, payload).wait() {
+            warn!("Failed to publish heartbeat on {}: {}", self.health_key, e);
+        }
+    }
+}
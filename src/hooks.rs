@@ -0,0 +1,193 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Recording event hooks
+//
+// The hooks of `recorder.hooks` run when a recording starts, finishes or is
+// cancelled, and when one of its uploads or its finish fails. A webhook POSTs
+// the `RecordingEvent` as JSON; an exec hook runs a command with the event on
+// stdin and its kind and recording ID in `RECORDER_EVENT` and
+// `RECORDER_RECORDING_ID`. Hooks run in the background, so a slow endpoint
+// never holds up a recording, and their failures are only logged.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::config::{HookAction, HookConfig};
+use crate::protocol::{RecordingEvent, RecordingEventKind, TaskStage};
+use crate::runtime;
+use crate::task_registry::TaskRegistry;
+
+/// Runs the configured hooks on recording events
+pub struct HookRunner {
+    hooks: Vec<HookConfig>,
+    client: reqwest::Client,
+    /// Registry the hooks run in
+    tasks: TaskRegistry,
+}
+
+impl HookRunner {
+    pub fn new(hooks: Vec<HookConfig>, tasks: TaskRegistry) -> Self {
+        Self {
+            hooks,
+            client: reqwest::Client::new(),
+            tasks,
+        }
+    }
+
+    /// Run the hooks of `event.event` in the background, as jobs of the
+    /// runner's registry
+    pub fn fire(self: &Arc<Self>, event: RecordingEvent) {
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => Arc::new(payload),
+            Err(e) => {
+                warn!("Failed to serialize recording event: {}", e);
+                return;
+            }
+        };
+
+        for (i, hook) in self.hooks.iter().enumerate() {
+            if !hook.fires_on(event.event) {
+                continue;
+            }
+            let runner = self.clone();
+            let payload = payload.clone();
+            let kind = event.event;
+            let recording_id = event.recording_id.clone();
+            let name = format!("hook {} on {:?} of {}", i, kind, recording_id);
+            self.tasks.spawn(TaskStage::Background, name, async move {
+                let hook = &runner.hooks[i];
+                match runner.run(hook, kind, &recording_id, payload).await {
+                    Ok(()) => debug!("Hook {} ran on {:?} of '{}'", i, kind, recording_id),
+                    Err(e) => warn!(
+                        "Hook {} failed on {:?} of '{}': {:#}",
+                        i, kind, recording_id, e
+                    ),
+                }
+            });
+        }
+    }
+
+    async fn run(
+        &self,
+        hook: &HookConfig,
+        kind: RecordingEventKind,
+        recording_id: &str,
+        payload: Arc<Vec<u8>>,
+    ) -> Result<()> {
+        match &hook.action {
+            HookAction::Webhook { url, headers } => {
+                let mut request = self
+                    .client
+                    .post(url)
+                    .timeout(hook.timeout())
+                    .header(reqwest::header::CONTENT_TYPE, "application/json");
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                let response = request.body(payload.to_vec()).send().await?;
+                if !response.status().is_success() {
+                    bail!("'{}' answered {}", url, response.status());
+                }
+                Ok(())
+            }
+            HookAction::Exec { command } => {
+                let command = command.clone();
+                let event = serde_json::to_value(kind)?
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                let recording_id = recording_id.to_string();
+                let timeout = hook.timeout();
+                runtime::unblock(move || exec(&command, &event, &recording_id, &payload, timeout))
+                    .await
+            }
+        }
+    }
+}
+
+/// Run `command` with `payload` on stdin, killing it after `timeout`
+fn exec(
+    command: &[String],
+    event: &str,
+    recording_id: &str,
+    payload: &[u8],
+    timeout: Duration,
+) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("empty command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .env("RECORDER_EVENT", event)
+        .env("RECORDER_RECORDING_ID", recording_id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run '{}'", program))?;
+    // Written from a thread of its own, so a command that doesn't read its
+    // stdin can't block the timeout; the write fails once the command is gone
+    if let Some(mut stdin) = child.stdin.take() {
+        let program = program.clone();
+        let payload = payload.to_vec();
+        std::thread::spawn(move || {
+            // A command ignoring its stdin may close it early
+            if let Err(e) = stdin.write_all(&payload) {
+                debug!("'{}' didn't read the whole event: {}", program, e);
+            }
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                bail!("'{}' exited with {}", program, status);
+            }
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("'{}' still running after {:?}, killed", program, timeout);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exec_timeout_with_unread_stdin() {
+        // More than a pipe holds, to a command that never reads it
+        let payload = vec![b'x'; 1 << 20];
+        let started = Instant::now();
+        let error = exec(
+            &["sleep".to_string(), "10".to_string()],
+            "start",
+            "rec-1",
+            &payload,
+            Duration::from_millis(200),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("killed"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Upload of finished recordings to a remote ingestion API (`recorder.ingestion`)
+//
+// Once a recording is finished and its metadata written, its records (with
+// `include_data`) and then its manifest, the recording metadata, are pushed to
+// `endpoint`:
+//
+// - `HEAD {endpoint}/recordings/{recording_id}/records/{entry}/{timestamp_us}`
+//   answers the bytes of the record the API already holds in `Upload-Offset`
+//   (404 = none yet), and `PATCH` on the same URL appends the next chunk at
+//   `Upload-Offset`, with the record size in `Upload-Length`. A chunk that
+//   fails is sent again from the offset the API holds, so an upload resumes
+//   instead of starting over; a 409 answer carries the offset to continue at.
+// - `PUT {endpoint}/recordings/{recording_id}` sends the manifest last, with
+//   the CRC32C of every record. A 2xx answer means the recording is accepted.
+//
+// Requests carry a bearer token: the static `token`, or one obtained from
+// `token_url` with the OAuth 2.0 client credentials grant, reused until it
+// is about to expire and fetched again when the API answers 401. Failed
+// requests are retried `max_retries` times with a doubling delay.
+
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::config::IngestionConfig;
+use crate::protocol::{RecordChecksum, RecordingMetadata};
+use crate::runtime;
+use crate::storage::StorageBackend;
+
+const UPLOAD_OFFSET: &str = "upload-offset";
+const UPLOAD_LENGTH: &str = "upload-length";
+
+/// Tokens are fetched again this long before they expire
+const TOKEN_MARGIN: Duration = Duration::from_secs(30);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// What an ingestion uploaded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestionSummary {
+    /// Records uploaded
+    pub records: usize,
+    /// Record bytes sent
+    pub bytes: u64,
+    /// Record bytes the API already held from an earlier attempt
+    pub resumed_bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Lifetime in seconds
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+struct CachedToken {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| Instant::now() + TOKEN_MARGIN < expires_at)
+    }
+}
+
+/// Client of the ingestion API
+pub struct IngestionClient {
+    config: IngestionConfig,
+    client: reqwest::Client,
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl IngestionClient {
+    pub fn new(config: IngestionConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout())
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Upload a finished recording: its records (with `include_data`),
+    /// then its manifest
+    pub async fn ingest(
+        &self,
+        storage: &dyn StorageBackend,
+        metadata: &RecordingMetadata,
+    ) -> Result<IngestionSummary> {
+        let recording_url = format!(
+            "{}/recordings/{}",
+            self.config.endpoint.trim_end_matches('/'),
+            metadata.recording_id
+        );
+
+        let mut summary = IngestionSummary::default();
+        if self.config.include_data {
+            for record in &metadata.records {
+                let url = format!(
+                    "{}/records/{}/{}",
+                    recording_url, record.entry, record.timestamp_us
+                );
+                let held = self
+                    .upload_record(storage, &url, record)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "failed to upload record {} of '{}'",
+                            record.timestamp_us, record.entry
+                        )
+                    })?;
+                summary.records += 1;
+                summary.bytes += record.bytes as u64 - held;
+                summary.resumed_bytes += held;
+            }
+        }
+
+        let manifest = Bytes::from(serde_json::to_vec(metadata)?);
+        let (url, manifest) = (&recording_url, &manifest);
+        self.retry(url, move || async move {
+            let response = self
+                .send(|| {
+                    self.client
+                        .put(url)
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(manifest.clone())
+                })
+                .await?;
+            if !response.status().is_success() {
+                bail!("'{}' answered {} to the manifest", url, response.status());
+            }
+            Ok(())
+        })
+        .await
+        .context("manifest was not accepted")?;
+        Ok(summary)
+    }
+
+    /// Upload the bytes of `record` the API doesn't hold yet; returns the
+    /// bytes it held before
+    async fn upload_record(
+        &self,
+        storage: &dyn StorageBackend,
+        url: &str,
+        record: &RecordChecksum,
+    ) -> Result<u64> {
+        let length = record.bytes as u64;
+        let held = self.retry(url, || self.held_bytes(url)).await?;
+        let mut offset = held;
+        while offset < length {
+            offset = self
+                .retry(url, move || self.append_chunk(storage, url, record, offset))
+                .await?;
+        }
+        debug!("Uploaded {} ({} bytes held before)", url, held);
+        Ok(held)
+    }
+
+    /// Bytes of a record the API holds
+    async fn held_bytes(&self, url: &str) -> Result<u64> {
+        let response = self.send(|| self.client.head(url)).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(0),
+            status if status.is_success() => upload_offset(&response)
+                .ok_or_else(|| anyhow!("'{}' answered without {}", url, UPLOAD_OFFSET)),
+            status => bail!("'{}' answered {}", url, status),
+        }
+    }
+
+    /// Send the chunk of `record` at `offset`; returns the bytes the API
+    /// holds after it
+    async fn append_chunk(
+        &self,
+        storage: &dyn StorageBackend,
+        url: &str,
+        record: &RecordChecksum,
+        offset: u64,
+    ) -> Result<u64> {
+        let length = record.bytes as u64;
+        let chunk = storage
+            .read_record_range(
+                &record.entry,
+                record.timestamp_us,
+                offset,
+                self.config.chunk_size_bytes.min(length - offset),
+            )
+            .await?;
+        if chunk.is_empty() {
+            bail!("stored record ends at {} of {} bytes", offset, length);
+        }
+        let sent = chunk.len() as u64;
+        let chunk = Bytes::from(chunk);
+        let response = self
+            .send(|| {
+                self.client
+                    .patch(url)
+                    .header(UPLOAD_OFFSET, offset)
+                    .header(UPLOAD_LENGTH, length)
+                    .header(CONTENT_TYPE, "application/offset+octet-stream")
+                    .body(chunk.clone())
+            })
+            .await?;
+        match response.status() {
+            // An earlier attempt made it after all, or the API lost bytes
+            StatusCode::CONFLICT => upload_offset(&response)
+                .ok_or_else(|| anyhow!("'{}' answered 409 without {}", url, UPLOAD_OFFSET)),
+            status if status.is_success() => Ok(upload_offset(&response).unwrap_or(offset + sent)),
+            status => bail!("'{}' answered {}", url, status),
+        }
+    }
+
+    /// Send a request with the bearer token, fetching a new token once if
+    /// the API rejects it
+    async fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut refreshed = false;
+        loop {
+            let mut builder = request();
+            if let Some(token) = self.token(refreshed).await? {
+                builder = builder.bearer_auth(token);
+            }
+            let response = builder.send().await?;
+            if response.status() == StatusCode::UNAUTHORIZED
+                && !refreshed
+                && self.config.token_url.is_some()
+            {
+                refreshed = true;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Bearer token of the requests, fetched again when `refresh` is set or
+    /// the cached one is about to expire
+    async fn token(&self, refresh: bool) -> Result<Option<String>> {
+        if let Some(token) = &self.config.token {
+            return Ok(Some(token.clone()));
+        }
+        let Some(token_url) = &self.config.token_url else {
+            return Ok(None);
+        };
+        let mut cached = self.token.lock().await;
+        if let Some(token) = cached.as_ref().filter(|t| !refresh && t.is_fresh()) {
+            return Ok(Some(token.value.clone()));
+        }
+
+        let mut form = vec![("grant_type", "client_credentials")];
+        for (name, value) in [
+            ("client_id", &self.config.client_id),
+            ("client_secret", &self.config.client_secret),
+            ("scope", &self.config.scope),
+        ] {
+            if let Some(value) = value {
+                form.push((name, value));
+            }
+        }
+        let response = self
+            .client
+            .post(token_url)
+            .form(&form)
+            .send()
+            .await
+            .with_context(|| format!("token request to '{}' failed", token_url))?;
+        if !response.status().is_success() {
+            bail!(
+                "'{}' answered {} to the token request",
+                token_url,
+                response.status()
+            );
+        }
+        let token: TokenResponse = response
+            .json()
+            .await
+            .with_context(|| format!("'{}' answered an invalid token", token_url))?;
+        debug!("Fetched an ingestion token from '{}'", token_url);
+        *cached = Some(CachedToken {
+            value: token.access_token.clone(),
+            expires_at: token
+                .expires_in
+                .map(|seconds| Instant::now() + Duration::from_secs(seconds)),
+        });
+        Ok(Some(token.access_token))
+    }
+
+    /// Run `attempt` until it succeeds or `max_retries` retries failed
+    async fn retry<T, F, Fut>(&self, what: &str, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut delay = self.config.retry_delay();
+        let mut retries = 0;
+        loop {
+            match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(e) if retries < self.config.max_retries => {
+                    retries += 1;
+                    warn!(
+                        "Ingestion request to '{}' failed, retry {}/{} in {:?}: {:#}",
+                        what, retries, self.config.max_retries, delay, e
+                    );
+                    runtime::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// `Upload-Offset` of a response
+fn upload_offset(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(UPLOAD_OFFSET)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,518 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Viewer for stored batches (`zenoh-recorder inspect`)
+//
+// Parses the records written by `McapSerializer`: the codec is detected from
+// the frame magic, then the header line and the length-prefixed protobuf
+// messages are decoded. With a time range, only the frames of seekable Zstd
+// batches whose messages may fall in it are decompressed.
+
+use anyhow::{bail, Context};
+use prost::Message;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::coalesce;
+use crate::error::{RecorderError, Result};
+use crate::proto::{RecordedMessage, SchemaInfo};
+use crate::protocol::CompressionType;
+use crate::seekable;
+use crate::storage::consolidated;
+use crate::transform;
+
+const HEADER_MAGIC: &str = "ZENOH_MCAP|";
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const LZ4_MAGIC: [u8; 4] = [0x04, 0x22, 0x4d, 0x18];
+
+/// Decoded batch
+#[derive(Debug)]
+pub struct Batch {
+    pub codec: CompressionType,
+    /// Size as stored (compressed)
+    pub stored_bytes: usize,
+    /// Size after decompression
+    pub raw_bytes: usize,
+    pub topic: String,
+    pub recording_id: String,
+    /// Message count announced by the header
+    pub header_count: usize,
+    pub messages: Vec<RecordedMessage>,
+    /// (decompressed, total) frames of seekable Zstd batches
+    pub frames: Option<(usize, usize)>,
+}
+
+impl Batch {
+    /// (first, last) message timestamp in nanoseconds
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        let first = self.messages.iter().map(|m| m.timestamp_ns).min()?;
+        let last = self.messages.iter().map(|m| m.timestamp_ns).max()?;
+        Some((first, last))
+    }
+
+    pub fn payload_bytes(&self) -> usize {
+        self.messages.iter().map(|m| m.payload.len()).sum()
+    }
+}
+
+/// Output format of dumped messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DumpFormat {
+    Hex,
+    Json,
+}
+
+/// What `inspect` prints besides the batch summaries
+#[derive(Debug, Clone)]
+pub struct InspectOptions {
+    /// Indexes of the messages to dump (per batch)
+    pub dump: Vec<usize>,
+    /// Dump every message
+    pub dump_all: bool,
+    pub format: DumpFormat,
+    /// Only messages with timestamps in this range (ns, inclusive)
+    pub time_range: Option<(i64, i64)>,
+}
+
+/// Parse a stored batch
+pub fn parse_batch(data: &[u8]) -> Result<Batch> {
+    decode_batch(data).map_err(RecorderError::serialization)
+}
+
+/// Parse the messages of a stored batch with timestamps in
+/// `start_ns..=end_ns`
+///
+/// Seekable Zstd batches only have the frames overlapping the range
+/// decompressed; other batches are decompressed whole.
+pub fn parse_batch_range(data: &[u8], start_ns: i64, end_ns: i64) -> Result<Batch> {
+    let seekable = if data.starts_with(&ZSTD_MAGIC) {
+        seekable::decompress_range(data, start_ns, end_ns).map_err(RecorderError::serialization)?
+    } else {
+        None
+    };
+    // Delta-encoded messages can't be restored when the range starts between
+    // keyframes; those batches are decompressed whole
+    let partial = seekable.and_then(|(raw, selected)| {
+        parse_raw(CompressionType::Zstd, data.len(), raw)
+            .ok()
+            .map(|batch| (batch, selected.len()))
+    });
+    let mut batch = match partial {
+        Some((batch, selected)) => {
+            let total = seekable::read_frames(data)
+                .map_err(RecorderError::serialization)?
+                .map_or(0, |frames| frames.len());
+            Batch {
+                frames: Some((selected, total)),
+                ..batch
+            }
+        }
+        None => parse_batch(data)?,
+    };
+    batch
+        .messages
+        .retain(|m| (start_ns..=end_ns).contains(&m.timestamp_ns));
+    Ok(batch)
+}
+
+fn decode_batch(data: &[u8]) -> anyhow::Result<Batch> {
+    if data.starts_with(&ZSTD_MAGIC) {
+        let raw = zstd::decode_all(data).context("Zstd decompression failed")?;
+        let frames = seekable::read_frames(data)?.map(|frames| (frames.len(), frames.len()));
+        return Ok(Batch {
+            frames,
+            ..parse_raw(CompressionType::Zstd, data.len(), raw)?
+        });
+    }
+    let (codec, raw) = if data.starts_with(&LZ4_MAGIC) {
+        let mut raw = Vec::new();
+        lz4::Decoder::new(data)
+            .context("Failed to create LZ4 decoder")?
+            .read_to_end(&mut raw)
+            .context("LZ4 decompression failed")?;
+        (CompressionType::Lz4, raw)
+    } else {
+        (CompressionType::None, data.to_vec())
+    };
+    parse_raw(codec, data.len(), raw)
+}
+
+/// Parse the decompressed header and messages of a batch
+fn parse_raw(codec: CompressionType, stored_bytes: usize, raw: Vec<u8>) -> anyhow::Result<Batch> {
+    let header_end = raw
+        .iter()
+        .position(|&b| b == b'\n')
+        .context("Missing batch header")?;
+    let header = std::str::from_utf8(&raw[..header_end]).context("Header is not UTF-8")?;
+    let Some(fields) = header.strip_prefix(HEADER_MAGIC) else {
+        bail!("Not a recorder batch (header: {:?})", truncate(header, 40));
+    };
+
+    let mut topic = String::new();
+    let mut recording_id = String::new();
+    let mut header_count = 0;
+    let mut schema_len: Option<usize> = None;
+    for field in fields.split('|') {
+        match field.split_once('=') {
+            Some(("topic", value)) => topic = value.to_string(),
+            Some(("recording_id", value)) => recording_id = value.to_string(),
+            Some(("count", value)) => {
+                header_count = value.parse().context("Invalid message count in header")?
+            }
+            Some(("schema", value)) => {
+                schema_len = Some(value.parse().context("Invalid schema length in header")?)
+            }
+            _ => {}
+        }
+    }
+
+    // Schema metadata shared by the messages without one of their own
+    let mut offset = header_end + 1;
+    let schema = match schema_len {
+        Some(len) => {
+            let Some(body) = raw.get(offset..offset + len) else {
+                bail!("Schema of the batch header is truncated");
+            };
+            offset += len;
+            Some(SchemaInfo::decode(body).context("Failed to decode batch schema")?)
+        }
+        None => None,
+    };
+
+    let mut messages = Vec::with_capacity(header_count);
+    while offset < raw.len() {
+        let index = messages.len();
+        let Some(prefix) = raw.get(offset..offset + 4) else {
+            bail!("Truncated length prefix of message {}", index);
+        };
+        let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        offset += 4;
+        let Some(body) = raw.get(offset..offset + len) else {
+            bail!(
+                "Message {} is truncated ({} of {} bytes)",
+                index,
+                raw.len() - offset,
+                len
+            );
+        };
+        let mut message =
+            RecordedMessage::decode(body).context(format!("Failed to decode message {}", index))?;
+        if message.schema.is_none() {
+            message.schema = schema.clone();
+        }
+        messages.push(message);
+        offset += len;
+    }
+    transform::decode_messages(&mut messages)?;
+
+    Ok(Batch {
+        codec,
+        stored_bytes,
+        raw_bytes: raw.len(),
+        topic,
+        recording_id,
+        header_count,
+        messages,
+        frames: None,
+    })
+}
+
+/// Print the batches of a record file or of every record in an entry directory
+pub fn inspect_path(path: &Path, options: &InspectOptions, out: &mut impl Write) -> Result<()> {
+    write_path(path, options, out).map_err(|e| match e.downcast::<RecorderError>() {
+        Ok(e) => e,
+        Err(e) => RecorderError::backend(e),
+    })
+}
+
+fn write_path(path: &Path, options: &InspectOptions, out: &mut impl Write) -> anyhow::Result<()> {
+    let files = if path.is_dir() {
+        record_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    // Consolidated files hold one record per batch of their index
+    let mut records = Vec::new();
+    for file in files {
+        match consolidated::index_of(&file) {
+            Some(index) => records.extend(
+                consolidated::read_index(&index)?
+                    .into_iter()
+                    .map(|range| (file.clone(), Some(range))),
+            ),
+            None => records.push((file, None)),
+        }
+    }
+
+    let mut total_messages = 0;
+    let mut total_records = 0;
+    for (file, range) in &records {
+        // Coalesced records hold one batch per part of their index
+        let batches = match range {
+            Some(range) => vec![(
+                format!("{} @ {}", file.display(), range.timestamp_us),
+                consolidated::read_batch(file, range)?,
+            )],
+            None => {
+                let data =
+                    std::fs::read(file).context(format!("Failed to read {}", file.display()))?;
+                match coalesce::decode(&data) {
+                    Ok(index) => index
+                        .parts
+                        .iter()
+                        .map(|part| {
+                            (
+                                format!(
+                                    "{} @ {} ({})",
+                                    file.display(),
+                                    part.timestamp_us,
+                                    part.topic
+                                ),
+                                coalesce::part_data(&data, part).to_vec(),
+                            )
+                        })
+                        .collect(),
+                    Err(_) => vec![(file.display().to_string(), data)],
+                }
+            }
+        };
+
+        let several = records.len() > 1 || batches.len() > 1;
+        for (name, data) in batches {
+            total_records += 1;
+            writeln!(out, "{}", name)?;
+            if data.is_empty() {
+                writeln!(out, "  empty record")?;
+                continue;
+            }
+            let parsed = match options.time_range {
+                Some((start_ns, end_ns)) => parse_batch_range(&data, start_ns, end_ns),
+                None => parse_batch(&data),
+            };
+            match parsed {
+                Ok(batch) => {
+                    total_messages += batch.messages.len();
+                    write_batch(&batch, options, out)?;
+                }
+                // Keep going through an entry; a single record is an error
+                Err(e) if several => writeln!(out, "  error: {:#}", anyhow::Error::from(e))?,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    if total_records > 1 {
+        writeln!(
+            out,
+            "{} records, {} messages",
+            total_records, total_messages
+        )?;
+    }
+    Ok(())
+}
+
+/// Record files of an entry directory in timestamp order (label sidecars
+/// skipped), or the consolidated files of a recording directory
+fn record_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    let mut consolidated: Vec<PathBuf> = paths
+        .iter()
+        .filter(|path| consolidated::index_of(path).is_some())
+        .cloned()
+        .collect();
+    if !consolidated.is_empty() {
+        consolidated.sort();
+        return Ok(consolidated);
+    }
+
+    let mut files: Vec<(u64, PathBuf)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            if name.ends_with(".meta.json") {
+                return None;
+            }
+            let timestamp = name.split('.').next()?.parse().ok()?;
+            Some((timestamp, path))
+        })
+        .collect();
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+fn write_batch(
+    batch: &Batch,
+    options: &InspectOptions,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "  codec:        {:?}", batch.codec)?;
+    writeln!(out, "  topic:        {}", batch.topic)?;
+    writeln!(out, "  recording_id: {}", batch.recording_id)?;
+    if options.time_range.is_some() {
+        writeln!(
+            out,
+            "  messages:     {} in range (of {})",
+            batch.messages.len(),
+            batch.header_count
+        )?;
+    } else if batch.header_count == batch.messages.len() {
+        writeln!(out, "  messages:     {}", batch.messages.len())?;
+    } else {
+        writeln!(
+            out,
+            "  messages:     {} (header announces {})",
+            batch.messages.len(),
+            batch.header_count
+        )?;
+    }
+    if let Some((first, last)) = batch.time_range() {
+        writeln!(
+            out,
+            "  time range:   {} .. {} ({:.3} s)",
+            format_timestamp(first),
+            format_timestamp(last),
+            (last - first) as f64 / 1e9
+        )?;
+    }
+    writeln!(
+        out,
+        "  size:         {} bytes stored, {} bytes raw, {} bytes payload",
+        batch.stored_bytes,
+        batch.raw_bytes,
+        batch.payload_bytes()
+    )?;
+    if let Some((decompressed, total)) = batch.frames {
+        writeln!(
+            out,
+            "  frames:       {} of {} decompressed",
+            decompressed, total
+        )?;
+    }
+
+    for (index, message) in batch.messages.iter().enumerate() {
+        if options.dump_all || options.dump.contains(&index) {
+            write_message(index, message, options.format, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_message(
+    index: usize,
+    message: &RecordedMessage,
+    format: DumpFormat,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    match format {
+        DumpFormat::Json => {
+            let json = serde_json::json!({
+                "index": index,
+                "topic": message.topic,
+                "timestamp_ns": message.timestamp_ns,
+                "schema": message.schema.as_ref().map(|schema| serde_json::json!({
+                    "format": schema.format,
+                    "schema_name": schema.schema_name,
+                    "schema_hash": schema.schema_hash,
+                })),
+                "sample": message.sample.as_ref().map(|sample| serde_json::json!({
+                    "encoding": sample.encoding,
+                    "kind": sample.kind,
+                    "source_timestamp_ns": sample.source_timestamp_ns,
+                    "source_id": sample.source_id,
+                    "received_ns": sample.received_ns,
+                    "congestion_control": sample.congestion_control,
+                    "priority": sample.priority,
+                    "express": sample.express,
+                    "key_expr": sample.key_expr,
+                    "liveliness": sample.liveliness,
+                })),
+                "attachment": (!message.attachment.is_empty())
+                    .then(|| payload_json(&message.attachment)),
+                "payload": payload_json(&message.payload),
+            });
+            writeln!(out, "{}", json)
+        }
+        DumpFormat::Hex => {
+            write!(
+                out,
+                "  #{} {} ({} bytes)",
+                index,
+                format_timestamp(message.timestamp_ns),
+                message.payload.len()
+            )?;
+            if let Some(sample) = &message.sample {
+                if sample.liveliness {
+                    write!(out, " liveliness {} {}", sample.kind, sample.key_expr)?;
+                } else {
+                    write!(out, " {} {}", sample.kind, sample.encoding)?;
+                }
+                if sample.received_ns > 0 {
+                    write!(out, " received {}", format_timestamp(sample.received_ns))?;
+                }
+            }
+            writeln!(out)?;
+            write_hex(&message.payload, out)
+        }
+    }
+}
+
+/// Payload as JSON if it is JSON, as a string if it is UTF-8, hex otherwise
+fn payload_json(payload: &[u8]) -> serde_json::Value {
+    if let Ok(value) = serde_json::from_slice(payload) {
+        return value;
+    }
+    match std::str::from_utf8(payload) {
+        Ok(text) => serde_json::Value::String(text.to_string()),
+        Err(_) => serde_json::Value::String(payload.iter().map(|b| format!("{:02x}", b)).collect()),
+    }
+}
+
+/// Classic hexdump: offset, 16 bytes, printable ASCII
+fn write_hex(data: &[u8], out: &mut impl Write) -> std::io::Result<()> {
+    for (line, chunk) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(
+            out,
+            "    {:08x}  {:<47}  |{}|",
+            line * 16,
+            hex.join(" "),
+            ascii
+        )?;
+    }
+    Ok(())
+}
+
+fn format_timestamp(timestamp_ns: i64) -> String {
+    chrono::DateTime::from_timestamp_nanos(timestamp_ns)
+        .format("%Y-%m-%dT%H:%M:%S%.9fZ")
+        .to_string()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
@@ -20,25 +20,79 @@
 // - Serializes to MCAP format with protobuf messages
 // - Stores in ReductStore with configurable compression
 // - Supports distributed recording control via request-response protocol
+// - Recovers recordings interrupted by a crash
+// - Serves recorded data back over Zenoh
 
+pub mod backfill;
+pub mod batch_index;
+pub mod black_box;
 pub mod buffer;
+pub mod clock;
+pub mod coalesce;
+pub mod compression_pool;
 pub mod config;
 pub mod control;
+pub mod control_auth;
+pub mod controller_watch;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod data_bridge;
+pub mod download;
+pub mod drift;
+pub mod error;
+pub mod flush_pool;
+pub mod format_sniff;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod hooks;
+pub mod ingestion;
+pub mod inspect;
+pub mod logging;
 pub mod mcap_writer;
+pub mod power_events;
+pub mod preview;
 pub mod protocol;
+pub mod quota;
+pub mod record_timestamps;
 pub mod recorder;
+pub mod recovery;
+pub mod replay;
+pub mod ros2_msg;
+pub mod runtime;
+pub mod sample_filter;
+pub mod sample_transform;
+pub mod schema_registry;
+pub mod seekable;
+pub mod session_supervisor;
+pub mod snapshot;
+pub mod status_events;
 pub mod storage;
+pub mod subscription_hub;
+pub mod tap;
+pub mod task_registry;
+pub mod throughput;
+pub mod topic_stats;
+pub mod topology;
+pub mod transform;
+pub mod upload_gate;
+pub mod upload_limiter;
+pub mod validation;
+pub mod work_dir;
 
 // Re-export main types
 pub use buffer::{FlushTask, TopicBuffer};
 pub use config::{load_config, load_config_with_env, RecorderConfig};
 pub use control::ControlInterface;
+pub use error::RecorderError;
 pub use mcap_writer::McapSerializer;
 pub use protocol::{
     CompressionLevel, CompressionType, RecorderCommand, RecorderRequest, RecorderResponse,
     RecordingMetadata, RecordingStatus, StatusResponse,
 };
-pub use recorder::{RecorderManager, RecordingSession};
+pub use recorder::{RecorderManager, RecorderManagerBuilder, RecordingSession};
+pub use recovery::{SessionState, SessionStateStore};
+pub use schema_registry::SchemaRegistry;
 pub use storage::topic_to_entry_name;
 
 // Include protobuf definitions
@@ -0,0 +1,275 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Log output setup
+//
+// Builds the tracing subscriber from `LoggingConfig`: plain text or JSON lines,
+// written to stdout or to rotating files. In JSON mode the fields of the
+// enclosing spans (recording_id, device_id, topic) are included in every line.
+// The level filter sits behind a reload handle so it can change at runtime.
+//
+// With the `otlp` feature and `logging.otlp` set, the spans are also exported
+// to an OpenTelemetry collector. Flush workers open a `flush` span per batch
+// with `serialize`, `compress`, `throttle` and `upload` children, so a trace
+// shows where the time of a flush went.
+
+use anyhow::{Context, Result};
+use tracing::level_filters::LevelFilter;
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{reload, Layer, Registry};
+
+#[cfg(feature = "otlp")]
+use crate::config::OtlpConfig;
+use crate::config::{LogFileConfig, LoggingConfig};
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+/// Handle changing the level of an installed subscriber
+#[derive(Clone)]
+pub struct LogLevel(reload::Handle<LevelFilter, Registry>);
+
+impl LogLevel {
+    pub fn set(&self, level: &str) -> Result<()> {
+        self.0
+            .reload(level_filter(level))
+            .context("Failed to change log level")
+    }
+}
+
+/// Output kept alive by `init`
+///
+/// Dropping it flushes buffered file output and the spans not yet exported.
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+#[cfg(feature = "otlp")]
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to export remaining spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber
+///
+/// The returned guard must be kept alive for the lifetime of the process.
+pub fn init(config: &LoggingConfig) -> Result<(LogGuard, LogLevel)> {
+    let (writer, guard) = match &config.file {
+        Some(file) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_appender(file)?);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+
+    let (subscriber, level) = subscriber(config, writer);
+    #[cfg(feature = "otlp")]
+    let tracer_provider = config.otlp.as_ref().map(tracer_provider).transpose()?;
+    #[cfg(feature = "otlp")]
+    let subscriber = subscriber.with(tracer_provider.as_ref().map(|provider| {
+        use opentelemetry::trace::TracerProvider;
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("zenoh-recorder"))
+    }));
+    tracing::subscriber::set_global_default(subscriber)
+        .context("Failed to install log subscriber")?;
+    #[cfg(not(feature = "otlp"))]
+    if config.otlp.is_some() {
+        tracing::warn!("Ignoring logging.otlp: built without the `otlp` feature");
+    }
+
+    Ok((
+        LogGuard {
+            _file: guard,
+            #[cfg(feature = "otlp")]
+            tracer_provider,
+        },
+        level,
+    ))
+}
+
+/// Span exporter sending batches to the OTLP/HTTP endpoint
+#[cfg(feature = "otlp")]
+fn tracer_provider(config: &OtlpConfig) -> Result<SdkTracerProvider> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.endpoint.clone())
+        .with_timeout(config.timeout())
+        .build()
+        .context("Failed to create OTLP span exporter")?;
+    let resource = opentelemetry_sdk::Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .build();
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+/// Build the subscriber writing to `writer`
+pub fn subscriber(
+    config: &LoggingConfig,
+    writer: BoxMakeWriter,
+) -> (
+    impl Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+    LogLevel,
+) {
+    let layer = if config.format == "json" {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(config.file.is_none())
+            .with_writer(writer)
+            .boxed()
+    };
+
+    let (filter, handle) = reload::Layer::new(level_filter(&config.level));
+    let subscriber = tracing_subscriber::registry().with(filter).with(layer);
+    (subscriber, LogLevel(handle))
+}
+
+fn level_filter(level: &str) -> LevelFilter {
+    match level.to_lowercase().as_str() {
+        "trace" => LevelFilter::TRACE,
+        "debug" => LevelFilter::DEBUG,
+        "info" => LevelFilter::INFO,
+        "warn" => LevelFilter::WARN,
+        "error" => LevelFilter::ERROR,
+        _ => LevelFilter::INFO,
+    }
+}
+
+fn file_appender(config: &LogFileConfig) -> Result<RollingFileAppender> {
+    let rotation = match config.rotation.as_str() {
+        "minutely" => Rotation::MINUTELY,
+        "hourly" => Rotation::HOURLY,
+        "never" => Rotation::NEVER,
+        _ => Rotation::DAILY,
+    };
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(&config.prefix);
+    if config.max_files > 0 {
+        builder = builder.max_log_files(config.max_files);
+    }
+    builder.build(&config.directory).context(format!(
+        "Failed to open log directory: {}",
+        config.directory
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer collecting the log output in memory
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_output_with_span_fields() {
+        let config = LoggingConfig {
+            level: "info".to_string(),
+            format: "json".to_string(),
+            file: None,
+            otlp: None,
+        };
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let (subscriber, _) = subscriber(&config, BoxMakeWriter::new(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("recording", recording_id = "rec-1", device_id = "d1");
+            let _enter = span.enter();
+            tracing::info!("flushed");
+            tracing::debug!("filtered out");
+        });
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let line: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(line["fields"]["message"], "flushed");
+        assert_eq!(line["span"]["recording_id"], "rec-1");
+        assert_eq!(line["span"]["device_id"], "d1");
+    }
+
+    #[test]
+    fn test_level_change() {
+        let config = LoggingConfig::default();
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let (subscriber, level) = subscriber(&config, BoxMakeWriter::new(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("before");
+            level.set("debug").unwrap();
+            tracing::debug!("after");
+        });
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("before"));
+        assert!(output.contains("after"));
+    }
+
+    #[test]
+    fn test_file_appender() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = LogFileConfig {
+            directory: dir.path().to_string_lossy().to_string(),
+            prefix: "recorder.log".to_string(),
+            rotation: "never".to_string(),
+            max_files: 0,
+        };
+
+        let mut appender = file_appender(&config).unwrap();
+        appender.write_all(b"line\n").unwrap();
+        appender.flush().unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join("recorder.log")).unwrap();
+        assert_eq!(content, "line\n");
+    }
+}
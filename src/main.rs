@@ -13,25 +13,75 @@
 // limitations under the License.
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{info, warn};
 use zenoh::config::Config;
 use zenoh::Wait;
 
+mod backfill;
+mod batch_index;
+mod black_box;
 mod buffer;
+mod clock;
+mod coalesce;
+mod compression_pool;
 mod config;
 mod control;
+mod control_auth;
+mod controller_watch;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+mod data_bridge;
+mod download;
+mod drift;
+mod error;
+mod flush_pool;
+mod format_sniff;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod health;
+mod hooks;
+mod ingestion;
+mod inspect;
+mod logging;
 mod mcap_writer;
+#[cfg(all(feature = "power-events", target_os = "linux"))]
+mod power_events;
+mod preview;
 mod protocol;
+mod quota;
+mod record_timestamps;
 mod recorder;
+mod recovery;
+mod replay;
+mod ros2_msg;
+mod runtime;
+mod sample_filter;
+mod sample_transform;
+mod schema_registry;
+mod seekable;
+mod session_supervisor;
+mod snapshot;
+mod status_events;
 mod storage;
+mod subscription_hub;
+mod tap;
+mod task_registry;
+mod throughput;
+mod topic_stats;
+mod topology;
+mod transform;
+mod upload_gate;
+mod upload_limiter;
+mod validation;
+mod work_dir;
 
-use config::load_config_with_env;
+use config::{ConfigFormat, ConfigLoader, ConfigSource};
 use control::ControlInterface;
-use recorder::RecorderManager;
+use protocol::TaskStage;
+use recorder::{RecorderManager, RecorderManagerBuilder};
 use storage::BackendFactory;
 
 /// Zenoh Recorder - Record Zenoh topics to storage backends
@@ -45,6 +95,170 @@ struct Args {
     /// Device ID (overrides config file)
     #[arg(short, long)]
     device_id: Option<String>,
+
+    /// Take over the active recordings of a recorder already running on this device
+    #[arg(long)]
+    handoff: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the header, message count, time range and sizes of stored batches
+    Inspect {
+        /// Record file, or entry directory to inspect every record of
+        path: PathBuf,
+
+        /// Dump the message with this index (repeatable)
+        #[arg(long)]
+        dump: Vec<usize>,
+
+        /// Dump every message
+        #[arg(long)]
+        all: bool,
+
+        /// Format of dumped messages
+        #[arg(long, value_enum, default_value = "hex")]
+        format: inspect::DumpFormat,
+
+        /// Only messages at or after this timestamp (ns)
+        #[arg(long)]
+        from_ns: Option<i64>,
+
+        /// Only messages at or before this timestamp (ns)
+        #[arg(long)]
+        to_ns: Option<i64>,
+    },
+
+    /// Check filesystem recordings after a crash or power loss and repair them
+    Repair {
+        /// Base directory of the filesystem backend
+        path: PathBuf,
+
+        /// Extension of the record files
+        #[arg(long, default_value = "mcap")]
+        file_format: String,
+    },
+
+    /// Republish a stored recording on Zenoh with its original timing
+    Replay {
+        /// Recording to replay
+        recording_id: String,
+
+        /// Playback speed (2.0 replays twice as fast)
+        #[arg(long, default_value_t = 1.0)]
+        rate: f64,
+
+        /// Only replay topics matching this pattern (repeatable)
+        #[arg(long)]
+        topic: Vec<String>,
+
+        /// Publish keys under FROM on TO instead, e.g. robot1=replay/robot1 or
+        /// '**=sim/**' (repeatable)
+        #[arg(long, value_parser = replay::parse_remap)]
+        remap: Vec<(String, String)>,
+
+        /// Publish remapped topics under their recorded name
+        #[arg(long)]
+        recorded_names: bool,
+
+        /// How far ahead each topic is read to merge samples recorded out of order
+        #[arg(long, default_value_t = 1000)]
+        lookahead_ms: u64,
+
+        /// Only messages at or after this timestamp (ns)
+        #[arg(long)]
+        from_ns: Option<i64>,
+
+        /// Only messages at or before this timestamp (ns)
+        #[arg(long)]
+        to_ns: Option<i64>,
+
+        /// Bucket the recording was written to (default: the configured one)
+        #[arg(long)]
+        bucket: Option<String>,
+    },
+
+    /// Download a stored recording to a local directory, resuming interrupted downloads
+    Download {
+        /// Recording to download
+        recording_id: String,
+
+        /// Directory to download into (default: the recording ID)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Bytes fetched per request
+        #[arg(long, default_value_t = 8 * 1024 * 1024)]
+        chunk_size: u64,
+
+        /// Retries of a failed chunk or a record failing its checksum
+        #[arg(long, default_value_t = 5)]
+        retries: u32,
+
+        /// Bucket the recording was written to (default: the configured one)
+        #[arg(long)]
+        bucket: Option<String>,
+    },
+
+    /// List stored recordings started with the given environment seed
+    List {
+        /// Environment seed entry the recordings must have, e.g. sim_seed=42
+        /// (repeatable)
+        #[arg(long = "env", value_parser = replay::parse_environment, required = true)]
+        environment: Vec<(String, String)>,
+
+        /// Bucket the recordings were written to (default: the configured one)
+        #[arg(long)]
+        bucket: Option<String>,
+    },
+
+    /// Import MCAP files or rosbag2 bags (stored as MCAP) as a new recording
+    Import {
+        /// MCAP files and rosbag2 directories, imported in order
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// ID of the new recording (default: a new UUID)
+        #[arg(long)]
+        recording_id: Option<String>,
+
+        /// Scene of the new recording
+        #[arg(long)]
+        scene: Option<String>,
+
+        /// Label of every record, e.g. site=lab (repeatable)
+        #[arg(long = "label", value_parser = replay::parse_environment)]
+        labels: Vec<(String, String)>,
+
+        /// Bucket to write the recording to (default: the configured one)
+        #[arg(long)]
+        bucket: Option<String>,
+    },
+
+    /// Configuration file tools
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Convert a configuration file between TOML and YAML
+    Convert {
+        /// Configuration file to convert
+        input: PathBuf,
+
+        /// File to write (stdout when omitted)
+        output: Option<PathBuf>,
+
+        /// Target format (default: from the output extension, else the other format)
+        #[arg(long, value_enum)]
+        to: Option<ConfigFormat>,
+    },
 }
 
 // Include protobuf definitions
@@ -57,26 +271,169 @@ async fn main() -> Result<()> {
     // Parse CLI arguments
     let args = Args::parse();
 
-    // Load configuration from file
-    let mut recorder_config = load_config_with_env(&args.config)?;
+    let storage_command = match args.command {
+        Some(Command::Inspect {
+            path,
+            dump,
+            all,
+            format,
+            from_ns,
+            to_ns,
+        }) => {
+            let time_range = (from_ns.is_some() || to_ns.is_some())
+                .then(|| (from_ns.unwrap_or(i64::MIN), to_ns.unwrap_or(i64::MAX)));
+            let options = inspect::InspectOptions {
+                dump,
+                dump_all: all,
+                format,
+                time_range,
+            };
+            inspect::inspect_path(&path, &options, &mut std::io::stdout().lock())?;
+            return Ok(());
+        }
+        Some(Command::Config {
+            command: ConfigCommand::Convert { input, output, to },
+        }) => {
+            let to = to.or_else(|| output.as_deref().and_then(ConfigFormat::from_path));
+            let converted = ConfigLoader::convert(&input, to)?;
+            match output {
+                Some(output) => std::fs::write(&output, converted)?,
+                None => print!("{}", converted),
+            }
+            return Ok(());
+        }
+        Some(Command::Repair { path, file_format }) => {
+            let report = storage::repair::repair(&path, &file_format).await?;
+            println!(
+                "Checked {} entries: {} records intact, {} reindexed, {} torn, {} labels restored, {} missing",
+                report.entries,
+                report.valid_records,
+                report.reindexed,
+                report.torn_records,
+                report.restored_labels,
+                report.missing_records
+            );
+            for recording_id in &report.rebuilt_recordings {
+                println!("Rebuilt metadata of interrupted recording {}", recording_id);
+            }
+            return Ok(());
+        }
+        Some(
+            command @ (Command::Replay { .. }
+            | Command::Download { .. }
+            | Command::List { .. }
+            | Command::Import { .. }),
+        ) => Some(command),
+        None => None,
+    };
+
+    // Load configuration from file, with CLI overrides
+    let config_source = ConfigSource {
+        path: args.config.clone(),
+        device_id: args.device_id,
+    };
+    let (recorder_config, config_warnings) = config_source.load_with_warnings()?;
+
+    // Initialize logging (stdout or rotating files, text or JSON)
+    let (_log_guard, log_level) = logging::init(&recorder_config.logging)?;
+    for warning in &config_warnings {
+        warn!("{}", warning);
+    }
 
-    // Apply CLI overrides
-    if let Some(device_id) = args.device_id {
-        recorder_config.recorder.device_id = device_id;
+    // Download a recording; needs the storage backend but no Zenoh session
+    if let Some(Command::Download {
+        recording_id,
+        output,
+        chunk_size,
+        retries,
+        bucket,
+    }) = storage_command
+    {
+        let storage_backend = BackendFactory::create(&recorder_config.storage)?;
+        let storage_backend = match bucket {
+            Some(bucket) => storage_backend.with_bucket(&bucket)?,
+            None => storage_backend,
+        };
+        let metadata = replay::find_recording(storage_backend.as_ref(), &recording_id).await?;
+        let output = output.unwrap_or_else(|| PathBuf::from(&recording_id));
+        let options = download::DownloadOptions {
+            chunk_size,
+            max_retries: retries,
+            ..Default::default()
+        };
+        let summary =
+            download::download(storage_backend.as_ref(), &metadata, &output, &options).await?;
+        println!(
+            "Downloaded {} to {}: {} records fetched ({} bytes, {} resumed), {} already present, {} fetched again after a checksum mismatch",
+            recording_id,
+            output.display(),
+            summary.downloaded,
+            summary.bytes,
+            summary.resumed_bytes,
+            summary.skipped,
+            summary.refetched
+        );
+        return Ok(());
     }
 
-    // Initialize tracing with configured level
-    let log_level = match recorder_config.logging.level.to_lowercase().as_str() {
-        "trace" => Level::TRACE,
-        "debug" => Level::DEBUG,
-        "info" => Level::INFO,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
-    };
+    // List recordings by environment seed; needs the storage backend only
+    if let Some(Command::List {
+        environment,
+        bucket,
+    }) = storage_command
+    {
+        let storage_backend = BackendFactory::create(&recorder_config.storage)?;
+        let storage_backend = match bucket {
+            Some(bucket) => storage_backend.with_bucket(&bucket)?,
+            None => storage_backend,
+        };
+        let environment = environment.into_iter().collect();
+        let recordings =
+            replay::find_recordings_by_environment(storage_backend.as_ref(), &environment).await?;
+        for metadata in &recordings {
+            let seed: Vec<String> = metadata
+                .environment
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            println!(
+                "{}\t{}\t{}\t{}",
+                metadata.recording_id,
+                metadata.start_time,
+                metadata.device_id,
+                seed.join(" ")
+            );
+        }
+        return Ok(());
+    }
 
-    let subscriber = FmtSubscriber::builder().with_max_level(log_level).finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+    // Import files as a recording; needs the storage backend only
+    if let Some(Command::Import {
+        paths,
+        recording_id,
+        scene,
+        labels,
+        bucket,
+    }) = storage_command
+    {
+        let storage_backend = BackendFactory::create(&recorder_config.storage)?;
+        let storage_backend = match bucket {
+            Some(bucket) => storage_backend.with_bucket(&bucket)?,
+            None => storage_backend,
+        };
+        let options = backfill::ImportOptions {
+            recording_id,
+            scene,
+            labels: labels.into_iter().collect(),
+            ..backfill::ImportOptions::from_config(&recorder_config)
+        };
+        let summary = backfill::import(storage_backend.as_ref(), &paths, &options).await?;
+        println!(
+            "Imported {} files as recording {}: {} messages in {} records ({} bytes)",
+            summary.files, summary.recording_id, summary.messages, summary.records, summary.bytes
+        );
+        return Ok(());
+    }
 
     info!("Starting Zenoh Recorder");
     info!("Loaded configuration from: {:?}", args.config);
@@ -125,7 +482,31 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Open Zenoh session
+    // Shared memory transport (zero-copy ingest from local publishers)
+    #[cfg(feature = "shared-memory")]
+    {
+        zenoh_config
+            .insert_json5(
+                "transport/shared_memory/enabled",
+                &recorder_config.zenoh.shared_memory.to_string(),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to set shared memory: {}", e))?;
+        info!("Shared memory: {}", recorder_config.zenoh.shared_memory);
+    }
+    #[cfg(not(feature = "shared-memory"))]
+    if recorder_config.zenoh.shared_memory {
+        warn!("Ignoring zenoh.shared_memory: built without the `shared-memory` feature");
+    }
+
+    // Admin space answering the topology snapshots taken at Start
+    if recorder_config.recorder.topology.snapshot {
+        zenoh_config
+            .insert_json5("adminspace/enabled", "true")
+            .map_err(|e| anyhow::anyhow!("Failed to enable the admin space: {}", e))?;
+    }
+
+    // Open Zenoh session (the supervisor reopens it with the same config)
+    let reopen_config = zenoh_config.clone();
     let session = Arc::new(
         zenoh::open(zenoh_config)
             .wait()
@@ -141,26 +522,219 @@ async fn main() -> Result<()> {
         storage_backend.backend_type()
     );
 
-    // Initialize storage backend
-    storage_backend.initialize().await?;
+    // Replay a recording instead of recording
+    if let Some(Command::Replay {
+        recording_id,
+        rate,
+        topic,
+        remap,
+        recorded_names,
+        lookahead_ms,
+        from_ns,
+        to_ns,
+        bucket,
+    }) = storage_command
+    {
+        let storage_backend = match bucket {
+            Some(bucket) => storage_backend.with_bucket(&bucket)?,
+            None => storage_backend,
+        };
+        let metadata = replay::find_recording(storage_backend.as_ref(), &recording_id).await?;
+        let options = replay::ReplayOptions {
+            rate,
+            topics: topic,
+            remap,
+            recorded_names,
+            lookahead: std::time::Duration::from_millis(lookahead_ms),
+            time_range: (from_ns.is_some() || to_ns.is_some())
+                .then(|| (from_ns.unwrap_or(i64::MIN), to_ns.unwrap_or(i64::MAX))),
+        };
+        tokio::select! {
+            result = replay::replay(&session, storage_backend.as_ref(), &metadata, &options) => {
+                result?;
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Received Ctrl+C, stopping replay");
+            }
+        }
+        return Ok(());
+    }
+
+    // Initialize the storage backend and create the recorder manager
+    let recorder_manager = Arc::new(
+        RecorderManagerBuilder::new(recorder_config.clone())
+            .session(session.clone())
+            .storage_backend(storage_backend.clone())
+            .config_source(config_source)
+            .log_level(log_level)
+            .build()
+            .await?,
+    );
+
+    // Fetch protobuf descriptors published by other nodes
+    if let Err(e) = recorder_manager.fetch_schemas().await {
+        warn!("Failed to fetch protobuf descriptors: {:#}", e);
+    }
+
+    // Take over from the previous recorder version before serving commands,
+    // and before the state files of its live recordings look interrupted
+    if args.handoff {
+        let taken_over = recorder_manager.take_over().await?;
+        info!("Took over {} recording(s)", taken_over.len());
+    }
+
+    // Recover recordings interrupted by a previous crash
+    let recovered = recorder_manager.recover_sessions().await?;
+    if !recovered.is_empty() {
+        info!("Recovered {} interrupted recording(s)", recovered.len());
+    }
+
+    // Remove scratch directories of recordings that ended in a crash
+    let swept = recorder_manager.sweep_work_dirs().await;
+    if swept > 0 {
+        info!("Removed {} stale work directories", swept);
+    }
+
+    // Re-read the configuration on SIGHUP
+    #[cfg(unix)]
+    {
+        let tasks = recorder_manager.tasks().clone();
+        let recorder_manager = recorder_manager.clone();
+        tasks.spawn_service(TaskStage::Background, "reload on SIGHUP", async move {
+            if let Err(e) = reload_on_hangup(recorder_manager).await {
+                warn!("Failed to install SIGHUP handler: {}", e);
+            }
+        });
+    }
+
+    // Flush before the power goes out
+    let power_events = &recorder_config.recorder.power_events;
+    if power_events.enabled {
+        #[cfg(all(feature = "power-events", target_os = "linux"))]
+        {
+            let tasks = recorder_manager.tasks().clone();
+            let (recorder_manager, power_events) = (recorder_manager.clone(), power_events.clone());
+            tasks.spawn_service(TaskStage::Background, "power events", async move {
+                if let Err(e) = power_events::run(recorder_manager, power_events).await {
+                    warn!("Failed to listen for power events: {}", e);
+                }
+            });
+        }
+        #[cfg(not(all(feature = "power-events", target_os = "linux")))]
+        warn!("Ignoring power_events: built without the `power-events` feature");
+    }
 
-    // Create recorder manager
-    let recorder_manager = Arc::new(RecorderManager::new(
-        session.clone(),
-        storage_backend,
-        recorder_config.clone(),
-    ));
+    // Keep the Zenoh session connected
+    let session_updates = recorder_config.zenoh.reconnect.enabled.then(|| {
+        let supervisor = session_supervisor::SessionSupervisor::new(
+            session.clone(),
+            reopen_config,
+            &recorder_config.zenoh,
+        );
+        let updates = supervisor.sessions();
+        let tasks = recorder_manager.tasks().clone();
+        let recorder_manager = recorder_manager.clone();
+        tasks.spawn_service(TaskStage::Control, "session supervisor", async move {
+            supervisor.run(recorder_manager).await
+        });
+        updates
+    });
 
     // Start control interface
     let device_id = recorder_config.recorder.device_id.clone();
-    let control_interface =
-        ControlInterface::new(session.clone(), recorder_manager.clone(), device_id.clone());
+    let control_auth = control_auth::ControlAuth::new(&recorder_config.recorder.control.auth);
+    if control_auth.is_enabled() {
+        info!(
+            "Control requests need credentials ({} principals)",
+            recorder_config.recorder.control.auth.principals.len()
+        );
+    }
+    let mut control_interface =
+        ControlInterface::new(session.clone(), recorder_manager.clone(), device_id.clone())
+            .with_auth(control_auth.clone())
+            .with_groups(
+                recorder_config.recorder.control.groups.clone(),
+                std::time::Duration::from_millis(
+                    recorder_config.recorder.control.group_max_delay_ms,
+                ),
+            );
+    if let Some(updates) = &session_updates {
+        control_interface = control_interface.with_session_updates(updates.clone());
+    }
 
     info!(
         "Starting control interface on recorder/control/{}",
         device_id
     );
 
+    // Serve recorded data back over Zenoh when configured
+    let bridge_config = &recorder_config.recorder.data_bridge;
+    if bridge_config.enabled {
+        let mut data_bridge = data_bridge::DataBridge::new(
+            session.clone(),
+            storage_backend.clone(),
+            bridge_config.clone(),
+            device_id.clone(),
+        )
+        .with_auth(control_auth.clone())
+        .with_tasks(recorder_manager.tasks().clone());
+        if let Some(updates) = &session_updates {
+            data_bridge = data_bridge.with_session_updates(updates.clone());
+        }
+        let tasks = recorder_manager.tasks();
+        tasks.spawn_service(TaskStage::Control, "data bridge", async move {
+            if let Err(e) = data_bridge.run().await {
+                tracing::error!("Data bridge error: {}", e);
+            }
+        });
+    }
+
+    // Serve the same commands over gRPC when configured
+    if let Some(listen) = &recorder_config.recorder.control.grpc_listen {
+        #[cfg(feature = "grpc")]
+        {
+            let addr = listen
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid grpc_listen address '{}': {}", listen, e))?;
+            let grpc_control = grpc::GrpcControl::new(recorder_manager.clone())
+                .with_auth(control_auth.clone(), device_id.clone());
+            let tasks = recorder_manager.tasks();
+            tasks.spawn_service(TaskStage::Control, "grpc control", async move {
+                if let Err(e) = grpc_control.serve(addr).await {
+                    tracing::error!("gRPC control interface error: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        warn!(
+            "Ignoring grpc_listen '{}': built without the `grpc` feature",
+            listen
+        );
+    }
+
+    // Serve the status dashboard when configured
+    if let Some(listen) = &recorder_config.recorder.control.http_listen {
+        #[cfg(feature = "dashboard")]
+        {
+            let addr = listen
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid http_listen address '{}': {}", listen, e))?;
+            let dashboard = dashboard::Dashboard::new(recorder_manager.clone())
+                .with_auth(control_auth, device_id.clone());
+            let tasks = recorder_manager.tasks();
+            tasks.spawn_service(TaskStage::Control, "status dashboard", async move {
+                if let Err(e) = dashboard.serve(addr).await {
+                    tracing::error!("Status dashboard error: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "dashboard"))]
+        warn!(
+            "Ignoring http_listen '{}': built without the `dashboard` feature",
+            listen
+        );
+    }
+
     // Run the control interface (blocks until Ctrl+C)
     tokio::select! {
         result = control_interface.run() => {
@@ -169,14 +743,37 @@ async fn main() -> Result<()> {
             }
             info!("Control interface stopped");
         }
+        _ = recorder_manager.watch_controllers() => {}
+        _ = recorder_manager.enforce_quotas() => {}
+        _ = recorder_manager.handed_off(), if recorder_config.recorder.handoff.exit_after_handoff => {
+            info!("Recordings handed off to a new recorder, shutting down");
+        }
         _ = tokio::signal::ctrl_c() => {
             info!("Received Ctrl+C, shutting down");
         }
     }
 
     // Cleanup
-    recorder_manager.shutdown().await?;
+    recorder_manager
+        .shutdown(recorder_config.recorder.workers.shutdown_deadline())
+        .await?;
     info!("Zenoh Recorder shut down successfully");
 
     Ok(())
 }
+
+/// Reload the configuration on every SIGHUP
+#[cfg(unix)]
+async fn reload_on_hangup(recorder_manager: Arc<RecorderManager>) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    while hangup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading configuration");
+        let response = recorder_manager.reload_config();
+        if !response.success {
+            warn!("{}", response.message);
+        }
+    }
+    Ok(())
+}
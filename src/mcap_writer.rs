@@ -30,14 +30,85 @@
 /// - Efficient protobuf encoding via prost
 /// - SIMD-accelerated compression (via native libraries)
 ///
-use anyhow::{Context, Result};
+use crate::error::{RecorderError, Result};
+use anyhow::Context;
 use prost::Message;
+use std::collections::HashMap;
 use std::io::Write;
-use tracing::debug;
-use zenoh::sample::Sample;
-
-use crate::config::SchemaConfig;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::span::EnteredSpan;
+use tracing::{debug, field, info_span, warn};
+use zenoh::sample::{Sample, SampleKind};
+
+use crate::batch_index::{BatchIndex, IndexFrame};
+use crate::clock::{system_clock, Clock};
+use crate::config::{find_per_topic, SchemaConfig};
+use crate::format_sniff;
 use crate::protocol::{CompressionLevel, CompressionType};
+use crate::sample_transform::{self, SampleTransform};
+use crate::schema_registry::SchemaRegistry;
+use crate::seekable::{self, SeekableEncoder};
+use crate::transform::{self, Transform};
+
+/// Zenoh metadata of a sample received at `received_ns` (a liveliness token
+/// change when `liveliness` is set)
+fn sample_info(sample: &Sample, received_ns: u64, liveliness: bool) -> crate::proto::SampleInfo {
+    let timestamp = sample.timestamp();
+    crate::proto::SampleInfo {
+        encoding: sample.encoding().to_string(),
+        kind: match sample.kind() {
+            SampleKind::Put => "put",
+            SampleKind::Delete => "delete",
+        }
+        .to_string(),
+        source_timestamp_ns: timestamp
+            .map(|ts| ts.get_time().to_duration().as_nanos() as i64)
+            .unwrap_or_default(),
+        source_id: timestamp
+            .map(|ts| ts.get_id().to_string())
+            .unwrap_or_default(),
+        received_ns: received_ns as i64,
+        congestion_control: format!("{:?}", sample.congestion_control()).to_lowercase(),
+        priority: sample.priority() as u32,
+        express: sample.express(),
+        key_expr: sample.key_expr().to_string(),
+        liveliness,
+    }
+}
+
+/// Labels of a batch taken from the JSON-object attachments of its samples
+///
+/// Each of `keys` found in an attachment becomes a label; the distinct values
+/// of a key across the batch are joined with ','. Other attachments are
+/// still recorded, but give no labels.
+pub fn attachment_labels(samples: &[Sample], keys: &[String]) -> HashMap<String, String> {
+    let mut values: HashMap<&str, Vec<String>> = HashMap::new();
+    if keys.is_empty() {
+        return HashMap::new();
+    }
+    for attachment in samples.iter().filter_map(Sample::attachment) {
+        let Ok(serde_json::Value::Object(fields)) = serde_json::from_slice(&attachment.to_bytes())
+        else {
+            continue;
+        };
+        for key in keys {
+            let value = match fields.get(key) {
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+                None => continue,
+            };
+            let seen = values.entry(key).or_default();
+            if !seen.contains(&value) {
+                seen.push(value);
+            }
+        }
+    }
+    values
+        .into_iter()
+        .map(|(key, seen)| (key.to_string(), seen.join(",")))
+        .collect()
+}
 
 /// MCAP writer that serializes Zenoh samples into compressed protobuf format
 ///
@@ -60,6 +131,21 @@ pub struct McapSerializer {
     compression_type: CompressionType,
     compression_level: CompressionLevel,
     schema_config: SchemaConfig,
+    schema_registry: Option<Arc<SchemaRegistry>>,
+    /// Uncompressed bytes per frame of seekable Zstd batches (0 = one frame)
+    seekable_frame_bytes: usize,
+    /// Filters and redactions applied to every sample first
+    sample_transforms: Vec<Arc<dyn SampleTransform>>,
+    /// Payload transform applied before compression
+    transform: Option<Arc<dyn Transform>>,
+    /// Correction added to every record timestamp (ns)
+    clock_offset_ns: i64,
+    /// Samples are liveliness token changes
+    liveliness: bool,
+    /// Payload format detected for the topic (`default_format = "auto"`)
+    detected_format: Option<String>,
+    /// Stamps the samples that have neither a flush nor a Zenoh timestamp
+    clock: Arc<dyn Clock>,
 }
 
 impl McapSerializer {
@@ -86,6 +172,14 @@ impl McapSerializer {
             compression_type,
             compression_level,
             schema_config: SchemaConfig::default(),
+            schema_registry: None,
+            seekable_frame_bytes: 0,
+            sample_transforms: Vec::new(),
+            transform: None,
+            clock_offset_ns: 0,
+            liveliness: false,
+            detected_format: None,
+            clock: system_clock(),
         }
     }
 
@@ -99,9 +193,74 @@ impl McapSerializer {
             compression_type,
             compression_level,
             schema_config,
+            schema_registry: None,
+            seekable_frame_bytes: 0,
+            sample_transforms: Vec::new(),
+            transform: None,
+            clock_offset_ns: 0,
+            liveliness: false,
+            detected_format: None,
+            clock: system_clock(),
         }
     }
 
+    /// Use `registry` to embed protobuf descriptors for `format = "protobuf"`
+    /// topics and to cache the ROS 2 definitions of `format = "cdr"` topics
+    pub fn with_schema_registry(mut self, registry: Arc<SchemaRegistry>) -> Self {
+        self.schema_registry = Some(registry);
+        self
+    }
+
+    /// Write Zstd batches in the seekable format, with frames of about
+    /// `frame_bytes` of messages (0 = a single frame)
+    pub fn with_seekable_frame_bytes(mut self, frame_bytes: usize) -> Self {
+        self.seekable_frame_bytes = frame_bytes;
+        self
+    }
+
+    /// Filter and redact the samples with `transforms`, in order, before
+    /// anything else
+    pub fn with_sample_transforms(mut self, transforms: Vec<Arc<dyn SampleTransform>>) -> Self {
+        self.sample_transforms = transforms;
+        self
+    }
+
+    /// Transform payloads with `transform` before compressing them
+    pub fn with_transform(mut self, transform: Option<Arc<dyn Transform>>) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Shift the record timestamps by `offset_ns` to correct the skew of the
+    /// clock they were taken from
+    ///
+    /// The publisher timestamp and receive time in the sample metadata are
+    /// kept as they were observed.
+    pub fn with_clock_offset_ns(mut self, offset_ns: i64) -> Self {
+        self.clock_offset_ns = offset_ns;
+        self
+    }
+
+    /// Mark the samples as liveliness token changes (topics under
+    /// `@liveliness/`)
+    pub fn with_liveliness(mut self, liveliness: bool) -> Self {
+        self.liveliness = liveliness;
+        self
+    }
+
+    /// Tag the samples with `format` when the default format is "auto"
+    pub fn with_detected_format(mut self, format: Option<String>) -> Self {
+        self.detected_format = format;
+        self
+    }
+
+    /// Read the time of untimestamped samples from `clock` instead of the
+    /// system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Get schema info for a topic
     fn get_schema_info(&self, topic: &str) -> Option<crate::proto::SchemaInfo> {
         if !self.schema_config.include_metadata {
@@ -109,24 +268,90 @@ impl McapSerializer {
         }
 
         // Check per-topic schema config
-        if let Some(topic_schema) = self.schema_config.per_topic.get(topic) {
+        if let Some(topic_schema) = find_per_topic(&self.schema_config.per_topic, topic) {
+            let schema_name = topic_schema.schema_name.clone().unwrap_or_default();
+            let schema_data = match topic_schema.format.as_str() {
+                "cdr" => self.load_ros2_definition(topic, &schema_name),
+                "protobuf" => self.load_protobuf_descriptor(topic, &schema_name),
+                _ => vec![],
+            };
+
             return Some(crate::proto::SchemaInfo {
                 format: topic_schema.format.clone(),
-                schema_name: topic_schema.schema_name.clone().unwrap_or_default(),
+                schema_name,
                 schema_hash: topic_schema.schema_hash.clone().unwrap_or_default(),
-                schema_data: vec![],
+                schema_data,
             });
         }
 
         // Use default format if metadata is enabled
+        let format = if self.schema_config.default_format == format_sniff::AUTO {
+            self.detected_format
+                .clone()
+                .unwrap_or_else(|| format_sniff::RAW.to_string())
+        } else {
+            self.schema_config.default_format.clone()
+        };
         Some(crate::proto::SchemaInfo {
-            format: self.schema_config.default_format.clone(),
+            format,
             schema_name: String::new(),
             schema_hash: String::new(),
             schema_data: vec![],
         })
     }
 
+    /// Load the ROS 2 message definition (`ros2msg` encoding) for a CDR topic
+    ///
+    /// Missing definitions are logged and recorded without schema data rather
+    /// than failing the whole batch. With a schema registry, each type is read
+    /// from disk once.
+    fn load_ros2_definition(&self, topic: &str, type_name: &str) -> Vec<u8> {
+        let Some(msg_dir) = &self.schema_config.ros2_msg_dir else {
+            return vec![];
+        };
+        if type_name.is_empty() {
+            warn!("CDR topic '{}' has no schema_name configured", topic);
+            return vec![];
+        }
+
+        let load = || match crate::ros2_msg::load_message_definition(Path::new(msg_dir), type_name)
+        {
+            Ok(definition) => definition.into_bytes(),
+            Err(e) => {
+                warn!(
+                    "Failed to load ROS 2 definition for topic '{}': {:#}",
+                    topic, e
+                );
+                vec![]
+            }
+        };
+        match &self.schema_registry {
+            Some(registry) => registry.ros2_definition(msg_dir, type_name, load),
+            None => load(),
+        }
+    }
+
+    /// Look up the serialized `FileDescriptorSet` of a protobuf topic
+    fn load_protobuf_descriptor(&self, topic: &str, message_name: &str) -> Vec<u8> {
+        let Some(registry) = &self.schema_registry else {
+            return vec![];
+        };
+        if message_name.is_empty() {
+            warn!("Protobuf topic '{}' has no schema_name configured", topic);
+            return vec![];
+        }
+
+        registry
+            .descriptor_set_for(message_name)
+            .unwrap_or_else(|| {
+                warn!(
+                    "No protobuf descriptor for '{}' (topic '{}')",
+                    message_name, topic
+                );
+                vec![]
+            })
+    }
+
     /// Serialize a batch of samples to protobuf-encoded format
     ///
     /// This method:
@@ -156,60 +381,287 @@ impl McapSerializer {
     ///
     /// Time complexity: O(n * m) where n = sample count, m = avg sample size
     /// Space complexity: O(total_size + compression_overhead)
+    #[allow(dead_code)]
     pub fn serialize_batch(
         &self,
         topic: &str,
         samples: Vec<Sample>,
         recording_id: &str,
     ) -> Result<Vec<u8>> {
+        self.serialize_timestamped_batch(topic, samples, &[], recording_id)
+    }
+
+    /// Serialize a batch using timestamps resolved at receive time
+    ///
+    /// `timestamps_ns[i]` is used as the record timestamp of `samples[i]`.
+    /// Samples without a resolved timestamp fall back to the publisher
+    /// timestamp, then to the current time.
+    pub fn serialize_timestamped_batch(
+        &self,
+        topic: &str,
+        samples: Vec<Sample>,
+        timestamps_ns: &[u64],
+        recording_id: &str,
+    ) -> Result<Vec<u8>> {
+        self.serialize_received_batch(topic, samples, timestamps_ns, &[], recording_id)
+    }
+
+    /// Serialize a batch with resolved timestamps and receive times
+    ///
+    /// `received_ns[i]` is stored in the sample metadata as the time the
+    /// recorder received `samples[i]` (0 when missing).
+    pub fn serialize_received_batch(
+        &self,
+        topic: &str,
+        samples: Vec<Sample>,
+        timestamps_ns: &[u64],
+        received_ns: &[u64],
+        recording_id: &str,
+    ) -> Result<Vec<u8>> {
+        self.serialize_indexed_batch(topic, samples, timestamps_ns, received_ns, recording_id)
+            .map(|(batch, _)| batch)
+    }
+
+    /// Serialize a batch like `serialize_received_batch`, with its index
+    /// (see `crate::batch_index`)
+    pub fn serialize_indexed_batch(
+        &self,
+        topic: &str,
+        samples: Vec<Sample>,
+        timestamps_ns: &[u64],
+        received_ns: &[u64],
+        recording_id: &str,
+    ) -> Result<(Vec<u8>, BatchIndex)> {
+        self.encode_batch(topic, samples, timestamps_ns, received_ns, recording_id)
+            .map_err(RecorderError::serialization)
+    }
+
+    /// Serialize the samples of several topics into a single batch
+    ///
+    /// `samples` are (topic, sample, receive time in ns). Each sample is
+    /// filtered and redacted with the transforms `transforms` returns for its
+    /// topic and stored with its topic's schema metadata; the header names
+    /// `label` as the topic. Returns the batch (empty when every sample was
+    /// dropped) and the topics whose sample was dropped.
+    pub fn serialize_snapshot(
+        &self,
+        label: &str,
+        samples: Vec<(String, Sample, u64)>,
+        transforms: impl Fn(&str) -> Vec<Arc<dyn SampleTransform>>,
+        recording_id: &str,
+    ) -> Result<(Vec<u8>, Vec<String>)> {
+        let serialize =
+            info_span!("serialize", samples = samples.len(), bytes = field::Empty).entered();
+        let mut recorded = Vec::with_capacity(samples.len());
+        let mut dropped = Vec::new();
+        for (topic, sample, received_ns) in samples {
+            let timestamp_ns = sample
+                .timestamp()
+                .map(|ts| ts.get_time().to_duration().as_nanos() as u64)
+                .unwrap_or(received_ns);
+            let mut schema_info = self.get_schema_info(&topic);
+            // Snapshot samples are tagged one by one
+            if let Some(info) = schema_info.as_mut() {
+                if format_sniff::enabled_for(&self.schema_config, &topic) {
+                    info.format = format_sniff::detect([sample.payload().to_bytes().as_ref()])
+                        .unwrap_or(format_sniff::RAW)
+                        .to_string();
+                }
+            }
+            match self.recorded_message(
+                &topic,
+                &sample,
+                timestamp_ns,
+                received_ns,
+                &transforms(&topic),
+                schema_info,
+            ) {
+                Some(message) => recorded.push(message),
+                None => dropped.push(topic),
+            }
+        }
+
+        if recorded.is_empty() {
+            return Ok((Vec::new(), dropped));
+        }
+        let (batch, _) = self
+            .encode_recorded(label, recorded, None, recording_id, serialize)
+            .map_err(RecorderError::serialization)?;
+        Ok((batch, dropped))
+    }
+
+    /// Serialize messages read from elsewhere (see `crate::backfill`) into
+    /// a batch headed `topic`
+    pub fn serialize_messages(
+        &self,
+        topic: &str,
+        messages: Vec<crate::proto::RecordedMessage>,
+        recording_id: &str,
+    ) -> Result<Vec<u8>> {
+        if messages.is_empty() {
+            return Ok(Vec::new());
+        }
+        let serialize =
+            info_span!("serialize", samples = messages.len(), bytes = field::Empty).entered();
+        self.encode_recorded(topic, messages, None, recording_id, serialize)
+            .map(|(batch, _)| batch)
+            .map_err(RecorderError::serialization)
+    }
+
+    fn encode_batch(
+        &self,
+        topic: &str,
+        samples: Vec<Sample>,
+        timestamps_ns: &[u64],
+        received_ns: &[u64],
+        recording_id: &str,
+    ) -> anyhow::Result<(Vec<u8>, BatchIndex)> {
         if samples.is_empty() {
             debug!("Empty sample batch for topic '{}'", topic);
-            return Ok(Vec::new());
+            return Ok(Default::default());
         }
 
-        let mut all_messages = Vec::with_capacity(samples.len());
-        let mut total_payload_size = 0usize;
+        let serialize =
+            info_span!("serialize", samples = samples.len(), bytes = field::Empty).entered();
+        let mut recorded = Vec::with_capacity(samples.len());
+
+        // Schema metadata is identical for every sample of the batch and is
+        // written once, in the batch header
+        let schema_info = self.get_schema_info(topic);
 
         // Encode all samples to protobuf
-        for sample in &samples {
-            let timestamp = sample
-                .timestamp()
-                .as_ref()
-                .map(|ts| ts.get_time().as_u64())
-                .unwrap_or_else(|| {
-                    std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_nanos() as u64
-                });
-
-            // Create generic protobuf message from sample (schema-agnostic)
-            let schema_info = self.get_schema_info(topic);
-            let recorded_msg = crate::proto::RecordedMessage {
-                topic: topic.to_string(),
-                timestamp_ns: timestamp as i64,
-                payload: sample.payload().to_bytes().to_vec(),
-                schema: schema_info,
-            };
+        for (i, sample) in samples.iter().enumerate() {
+            let timestamp = timestamps_ns
+                .get(i)
+                .copied()
+                .or_else(|| {
+                    sample
+                        .timestamp()
+                        .map(|ts| ts.get_time().to_duration().as_nanos() as u64)
+                })
+                .unwrap_or_else(|| self.clock.since_epoch().as_nanos() as u64);
+            recorded.extend(self.recorded_message(
+                topic,
+                sample,
+                timestamp,
+                received_ns.get(i).copied().unwrap_or_default(),
+                &self.sample_transforms,
+                None,
+            ));
+        }
+
+        if recorded.len() < samples.len() {
+            debug!(
+                "Sample transforms dropped {} of {} samples of topic '{}'",
+                samples.len() - recorded.len(),
+                samples.len(),
+                topic
+            );
+            if recorded.is_empty() {
+                return Ok(Default::default());
+            }
+        }
+
+        self.encode_recorded(
+            topic,
+            recorded,
+            schema_info.as_ref(),
+            recording_id,
+            serialize,
+        )
+    }
 
+    /// Wrap a sample recorded at `timestamp_ns` in a protobuf message, or
+    /// None when `sample_transforms` drop it
+    fn recorded_message(
+        &self,
+        topic: &str,
+        sample: &Sample,
+        timestamp_ns: u64,
+        received_ns: u64,
+        sample_transforms: &[Arc<dyn SampleTransform>],
+        schema_info: Option<crate::proto::SchemaInfo>,
+    ) -> Option<crate::proto::RecordedMessage> {
+        let mut payload = sample.payload().to_bytes().to_vec();
+        if !sample_transform::apply_all(sample_transforms, topic, &mut payload) {
+            return None;
+        }
+
+        // Create generic protobuf message from sample (schema-agnostic)
+        Some(crate::proto::RecordedMessage {
+            topic: topic.to_string(),
+            timestamp_ns: timestamp_ns.saturating_add_signed(self.clock_offset_ns) as i64,
+            payload,
+            schema: schema_info,
+            sample: Some(sample_info(sample, received_ns, self.liveliness)),
+            transform: String::new(),
+            keyframe: false,
+            attachment: sample
+                .attachment()
+                .map(|attachment| attachment.to_bytes().to_vec())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Transform, encode and compress the messages of a batch headed `topic`,
+    /// and index them
+    ///
+    /// `schema` is the schema metadata of messages without one of their own.
+    /// `serialize` is the span of the batch, closed once the messages are
+    /// encoded.
+    fn encode_recorded(
+        &self,
+        topic: &str,
+        mut recorded: Vec<crate::proto::RecordedMessage>,
+        schema: Option<&crate::proto::SchemaInfo>,
+        recording_id: &str,
+        serialize: EnteredSpan,
+    ) -> anyhow::Result<(Vec<u8>, BatchIndex)> {
+        let mut total_payload_size = 0usize;
+
+        if let Some(transform) = &self.transform {
+            transform::encode_messages(transform.as_ref(), &mut recorded);
+        }
+
+        let mut all_messages = Vec::with_capacity(recorded.len());
+        for recorded_msg in &recorded {
             let mut msg_data = Vec::new();
             recorded_msg
                 .encode(&mut msg_data)
                 .context("Failed to encode protobuf message")?;
 
             total_payload_size += msg_data.len();
-            all_messages.push(msg_data);
+            all_messages.push((recorded_msg.timestamp_ns, msg_data));
+        }
+        serialize.record("bytes", total_payload_size);
+        drop(serialize);
+
+        let mut header = Vec::new();
+        self.write_header(&mut header, topic, recording_id, recorded.len(), schema)?;
+        let mut index = BatchIndex::new(
+            header.len(),
+            all_messages
+                .iter()
+                .map(|(timestamp_ns, msg)| (*timestamp_ns, msg.len())),
+        );
+
+        if self.compression_type == CompressionType::Zstd && self.seekable_frame_bytes > 0 {
+            let compressed = self.in_compress_span(total_payload_size, || {
+                self.encode_seekable(header, &all_messages)
+            })?;
+            if let Some(frames) = seekable::read_frames(&compressed)? {
+                index.frames = frames.iter().map(IndexFrame::from).collect();
+            }
+            return Ok((compressed, index));
         }
 
         // Pre-allocate buffer based on estimated size
-        let estimated_size = total_payload_size + (all_messages.len() * 4) + 256; // +4 bytes per length prefix, +256 for header
+        let estimated_size = total_payload_size + (all_messages.len() * 4) + header.len(); // +4 bytes per length prefix
         let mut buffer = Vec::with_capacity(estimated_size);
-
-        // Write header with metadata
-        self.write_header(&mut buffer, topic, recording_id, samples.len())?;
+        buffer.extend_from_slice(&header);
 
         // Write all messages with length prefixes
-        for msg in &all_messages {
+        for (_, msg) in &all_messages {
             // Write length prefix (4 bytes, little-endian)
             buffer.extend_from_slice(&(msg.len() as u32).to_le_bytes());
             // Write message data
@@ -220,12 +672,12 @@ impl McapSerializer {
 
         debug!(
             "Serialized {} samples to protobuf format ({} bytes uncompressed)",
-            samples.len(),
+            recorded.len(),
             uncompressed_size
         );
 
         // Apply compression
-        let compressed = self.compress(buffer)?;
+        let compressed = self.in_compress_span(uncompressed_size, || self.compress(buffer))?;
 
         debug!(
             "Compressed data from {} to {} bytes using {:?} (ratio: {:.2}x)",
@@ -235,6 +687,30 @@ impl McapSerializer {
             uncompressed_size as f64 / compressed.len().max(1) as f64
         );
 
+        Ok((compressed, index))
+    }
+
+    /// Write a Zstd batch as seekable frames (see `crate::seekable`)
+    fn encode_seekable(
+        &self,
+        header: Vec<u8>,
+        messages: &[(i64, Vec<u8>)],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = SeekableEncoder::new(
+            self.compression_level.to_zstd_level(),
+            self.seekable_frame_bytes,
+        );
+        encoder.write_header(&header)?;
+        for (timestamp_ns, msg) in messages {
+            encoder.write_message(msg, *timestamp_ns)?;
+        }
+        let compressed = encoder.finish()?;
+
+        debug!(
+            "Serialized {} samples into {} bytes of seekable Zstd frames",
+            messages.len(),
+            compressed.len()
+        );
         Ok(compressed)
     }
 
@@ -242,21 +718,50 @@ impl McapSerializer {
     ///
     /// Header format (ASCII text for debugging):
     /// ```text
-    /// ZENOH_MCAP|topic={topic}|recording_id={id}|count={n}\n
+    /// ZENOH_MCAP|topic={topic}|recording_id={id}|count={n}[|schema={len}]\n
     /// ```
+    ///
+    /// With `schema`, the line is followed by the `len` bytes of the encoded
+    /// `SchemaInfo` shared by the messages that have none of their own.
     fn write_header(
         &self,
         buffer: &mut Vec<u8>,
         topic: &str,
         recording_id: &str,
         count: usize,
-    ) -> Result<()> {
-        writeln!(
+        schema: Option<&crate::proto::SchemaInfo>,
+    ) -> anyhow::Result<()> {
+        write!(
             buffer,
             "ZENOH_MCAP|topic={}|recording_id={}|count={}",
             topic, recording_id, count
         )
-        .context("Failed to write header")
+        .context("Failed to write header")?;
+        let Some(schema) = schema else {
+            return writeln!(buffer).context("Failed to write header");
+        };
+        let schema = schema.encode_to_vec();
+        writeln!(buffer, "|schema={}", schema.len()).context("Failed to write header")?;
+        buffer.extend_from_slice(&schema);
+        Ok(())
+    }
+
+    /// Run a compression step in a `compress` span recording the output size
+    fn in_compress_span(
+        &self,
+        bytes: usize,
+        compress: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let span = info_span!(
+            "compress",
+            codec = ?self.compression_type,
+            bytes,
+            compressed_bytes = field::Empty
+        );
+        let _enter = span.enter();
+        let compressed = compress()?;
+        span.record("compressed_bytes", compressed.len());
+        Ok(compressed)
     }
 
     /// Compress data based on configured compression type
@@ -265,7 +770,7 @@ impl McapSerializer {
     ///
     /// - LZ4: ~500 MB/s compression, ~2 GB/s decompression
     /// - Zstd: ~100-200 MB/s compression, ~500 MB/s decompression
-    fn compress(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+    fn compress(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
         match self.compression_type {
             CompressionType::None => Ok(data),
             CompressionType::Lz4 => self.compress_lz4(data),
@@ -277,7 +782,7 @@ impl McapSerializer {
     ///
     /// LZ4 provides very fast compression/decompression with moderate compression ratio.
     /// Ideal for real-time recording where CPU is a bottleneck.
-    fn compress_lz4(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+    fn compress_lz4(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
         let level = self.compression_level.to_lz4_level();
         let mut encoder = lz4::EncoderBuilder::new()
             .level(level)
@@ -302,7 +807,7 @@ impl McapSerializer {
     /// # Implementation Notes
     ///
     /// Uses zstd-rs which wraps the native C library with SIMD optimizations.
-    fn compress_zstd(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+    fn compress_zstd(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
         let level = self.compression_level.to_zstd_level();
         zstd::encode_all(&data[..], level).context("Zstd compression failed")
     }
@@ -324,7 +829,7 @@ mod tests {
         let serializer = McapSerializer::new(CompressionType::None, CompressionLevel::Default);
         let mut buffer = Vec::new();
         serializer
-            .write_header(&mut buffer, "/test/topic", "rec-123", 42)
+            .write_header(&mut buffer, "/test/topic", "rec-123", 42, None)
             .unwrap();
 
         let header = String::from_utf8(buffer).unwrap();
@@ -334,6 +839,147 @@ mod tests {
         assert!(header.contains("count=42"));
     }
 
+    #[test]
+    fn test_cdr_schema_embeds_ros2_definition() {
+        let msg_dir = tempfile::TempDir::new().unwrap();
+        let pkg_dir = msg_dir.path().join("std_msgs").join("msg");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(pkg_dir.join("String.msg"), "string data\n").unwrap();
+
+        let mut schema_config = SchemaConfig {
+            include_metadata: true,
+            ros2_msg_dir: Some(msg_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        schema_config.per_topic.insert(
+            "/chatter".to_string(),
+            crate::config::TopicSchemaInfo {
+                format: "cdr".to_string(),
+                schema_name: Some("std_msgs/msg/String".to_string()),
+                schema_hash: None,
+            },
+        );
+
+        let serializer = McapSerializer::with_schema_config(
+            CompressionType::None,
+            CompressionLevel::Default,
+            schema_config,
+        );
+        let info = serializer.get_schema_info("/chatter").unwrap();
+        assert_eq!(info.format, "cdr");
+        assert_eq!(info.schema_name, "std_msgs/msg/String");
+        assert_eq!(info.schema_data, b"string data\n");
+
+        // Unknown types are recorded without schema data
+        let mut schema_config = serializer.schema_config.clone();
+        schema_config
+            .per_topic
+            .get_mut("/chatter")
+            .unwrap()
+            .schema_name = Some("std_msgs/msg/Missing".to_string());
+        let serializer = McapSerializer::with_schema_config(
+            CompressionType::None,
+            CompressionLevel::Default,
+            schema_config,
+        );
+        assert!(serializer
+            .get_schema_info("/chatter")
+            .unwrap()
+            .schema_data
+            .is_empty());
+    }
+
+    #[test]
+    fn test_registry_caches_ros2_definitions() {
+        let msg_dir = tempfile::TempDir::new().unwrap();
+        let pkg_dir = msg_dir.path().join("std_msgs").join("msg");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(pkg_dir.join("String.msg"), "string data\n").unwrap();
+
+        let mut schema_config = SchemaConfig {
+            include_metadata: true,
+            ros2_msg_dir: Some(msg_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        schema_config.per_topic.insert(
+            "/chatter".to_string(),
+            crate::config::TopicSchemaInfo {
+                format: "cdr".to_string(),
+                schema_name: Some("std_msgs/msg/String".to_string()),
+                schema_hash: None,
+            },
+        );
+        let registry = Arc::new(SchemaRegistry::new());
+        let serializer = || {
+            McapSerializer::with_schema_config(
+                CompressionType::None,
+                CompressionLevel::Default,
+                schema_config.clone(),
+            )
+            .with_schema_registry(registry.clone())
+        };
+        assert_eq!(
+            serializer()
+                .get_schema_info("/chatter")
+                .unwrap()
+                .schema_data,
+            b"string data\n"
+        );
+
+        // Later serializers (flushes) don't read the definition again
+        std::fs::remove_file(pkg_dir.join("String.msg")).unwrap();
+        assert_eq!(
+            serializer()
+                .get_schema_info("/chatter")
+                .unwrap()
+                .schema_data,
+            b"string data\n"
+        );
+    }
+
+    #[test]
+    fn test_protobuf_schema_embeds_descriptor_set() {
+        use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+
+        let set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("sensors/imu.proto".to_string()),
+                package: Some("sensors".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Imu".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let registry = Arc::new(SchemaRegistry::new());
+        registry.add_descriptor_set(&set.encode_to_vec()).unwrap();
+
+        let mut schema_config = SchemaConfig {
+            include_metadata: true,
+            ..Default::default()
+        };
+        schema_config.per_topic.insert(
+            "/sensors/**".to_string(),
+            crate::config::TopicSchemaInfo {
+                format: "protobuf".to_string(),
+                schema_name: Some("sensors.Imu".to_string()),
+                schema_hash: None,
+            },
+        );
+
+        let serializer = McapSerializer::with_schema_config(
+            CompressionType::None,
+            CompressionLevel::Default,
+            schema_config,
+        )
+        .with_schema_registry(registry);
+        let info = serializer.get_schema_info("/sensors/imu/front").unwrap();
+        assert_eq!(info.format, "protobuf");
+        let embedded = FileDescriptorSet::decode(info.schema_data.as_slice()).unwrap();
+        assert_eq!(embedded, set);
+    }
+
     #[test]
     fn test_empty_batch() {
         let serializer = McapSerializer::new(CompressionType::None, CompressionLevel::Default);
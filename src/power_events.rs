@@ -0,0 +1,230 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Emergency flush on imminent power loss (`recorder.power_events`)
+//
+// On Linux with the `power-events` feature, the recorder listens on the
+// system D-Bus for systemd-logind's `PrepareForShutdown` signal and for the
+// UPower display device discharging to `low_battery_percent`. Either one
+// triggers `RecorderManager::emergency_flush`, which writes out every buffer
+// and checkpoints the recordings for crash recovery. To get the time to do
+// so, the recorder holds a logind "delay" inhibitor lock: logind waits for it
+// to be released (at most `InhibitDelayMaxSec`) before shutting down. The
+// lock is released once the flush is done and taken again when a shutdown is
+// cancelled.
+
+/// Margin above the threshold the battery must recover before another
+/// low-battery flush
+const REARM_MARGIN_PERCENT: f64 = 5.0;
+
+/// Decides when the battery level calls for an emergency flush
+///
+/// Fires once when the battery discharges to the threshold, and again only
+/// after it charged or recovered `REARM_MARGIN_PERCENT` above it, so a level
+/// hovering around the threshold doesn't flush on every reading.
+#[derive(Debug, Clone)]
+pub struct BatteryMonitor {
+    threshold_percent: f64,
+    armed: bool,
+}
+
+impl BatteryMonitor {
+    pub fn new(threshold_percent: f64) -> Self {
+        Self {
+            threshold_percent,
+            armed: true,
+        }
+    }
+
+    /// Feed a reading; true when it should trigger a flush
+    pub fn update(&mut self, percent: f64, discharging: bool) -> bool {
+        if self.threshold_percent <= 0.0 {
+            return false;
+        }
+        if !discharging || percent >= self.threshold_percent + REARM_MARGIN_PERCENT {
+            self.armed = true;
+            return false;
+        }
+        if self.armed && percent <= self.threshold_percent {
+            self.armed = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(all(feature = "power-events", target_os = "linux"))]
+pub use listener::run;
+
+#[cfg(all(feature = "power-events", target_os = "linux"))]
+mod listener {
+    use futures_util::StreamExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tracing::{info, warn};
+    use zbus::zvariant::OwnedFd;
+    use zbus::{proxy, Connection};
+
+    use super::BatteryMonitor;
+    use crate::config::PowerEventsConfig;
+    use crate::recorder::RecorderManager;
+
+    /// UPower device state while running on battery
+    const UPOWER_DISCHARGING: u32 = 2;
+
+    #[proxy(
+        interface = "org.freedesktop.login1.Manager",
+        default_service = "org.freedesktop.login1",
+        default_path = "/org/freedesktop/login1"
+    )]
+    trait Login1Manager {
+        fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+        #[zbus(signal)]
+        fn prepare_for_shutdown(&self, start: bool) -> zbus::Result<()>;
+    }
+
+    #[proxy(
+        interface = "org.freedesktop.UPower.Device",
+        default_service = "org.freedesktop.UPower",
+        default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+    )]
+    trait UPowerDevice {
+        #[zbus(property)]
+        fn percentage(&self) -> zbus::Result<f64>;
+
+        #[zbus(property)]
+        fn state(&self) -> zbus::Result<u32>;
+    }
+
+    /// Listen for shutdown and battery signals until the bus goes away
+    pub async fn run(
+        manager: Arc<RecorderManager>,
+        config: PowerEventsConfig,
+    ) -> anyhow::Result<()> {
+        let connection = Connection::system().await?;
+        let timeout = Duration::from_secs(config.flush_timeout_seconds);
+        let shutdown = async {
+            if config.on_shutdown {
+                if let Err(e) = watch_shutdown(&connection, &manager, timeout).await {
+                    warn!("Not watching for shutdowns: {}", e);
+                }
+            }
+        };
+        let battery = async {
+            if config.low_battery_percent > 0.0 {
+                let threshold = config.low_battery_percent;
+                if let Err(e) = watch_battery(&connection, &manager, threshold, timeout).await {
+                    warn!("Not watching the battery: {}", e);
+                }
+            }
+        };
+        tokio::join!(shutdown, battery);
+        Ok(())
+    }
+
+    async fn watch_shutdown(
+        connection: &Connection,
+        manager: &RecorderManager,
+        timeout: Duration,
+    ) -> zbus::Result<()> {
+        let login = Login1ManagerProxy::new(connection).await?;
+        let mut signals = login.receive_prepare_for_shutdown().await?;
+        let mut inhibitor = inhibit(&login).await;
+        info!("Watching for system shutdowns");
+
+        while let Some(signal) = signals.next().await {
+            if signal.args()?.start {
+                let flushed = manager.emergency_flush("system shutdown", timeout).await;
+                info!("Flushed {} recordings before shutdown", flushed);
+                // Let the shutdown go ahead
+                drop(inhibitor.take());
+            } else if inhibitor.is_none() {
+                info!("Shutdown cancelled");
+                inhibitor = inhibit(&login).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delay lock holding shutdowns back until dropped
+    async fn inhibit(login: &Login1ManagerProxy<'_>) -> Option<OwnedFd> {
+        match login
+            .inhibit("shutdown", "zenoh-recorder", "Flushing recordings", "delay")
+            .await
+        {
+            Ok(fd) => Some(fd),
+            Err(e) => {
+                warn!("Failed to take a shutdown inhibitor lock: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn watch_battery(
+        connection: &Connection,
+        manager: &RecorderManager,
+        threshold: f64,
+        timeout: Duration,
+    ) -> zbus::Result<()> {
+        let device = UPowerDeviceProxy::new(connection).await?;
+        let mut percentages = device.receive_percentage_changed().await;
+        let mut states = device.receive_state_changed().await;
+        let mut monitor = BatteryMonitor::new(threshold);
+        info!("Watching the battery (flush at {}%)", threshold);
+
+        loop {
+            let percent = device.percentage().await?;
+            let discharging = device.state().await? == UPOWER_DISCHARGING;
+            if monitor.update(percent, discharging) {
+                let reason = format!("battery at {:.0}%", percent);
+                let flushed = manager.emergency_flush(&reason, timeout).await;
+                info!("Flushed {} recordings on low battery", flushed);
+            }
+            let changed = tokio::select! {
+                changed = percentages.next() => changed.is_some(),
+                changed = states.next() => changed.is_some(),
+            };
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_monitor_fires_once() {
+        let mut monitor = BatteryMonitor::new(10.0);
+        assert!(!monitor.update(50.0, true));
+        assert!(monitor.update(10.0, true));
+        // Hovering around the threshold
+        assert!(!monitor.update(9.0, true));
+        assert!(!monitor.update(12.0, true));
+        assert!(!monitor.update(8.0, true));
+
+        // Charging re-arms it
+        assert!(!monitor.update(8.0, false));
+        assert!(monitor.update(7.0, true));
+        // So does recovering past the margin
+        assert!(!monitor.update(15.0, true));
+        assert!(monitor.update(10.0, true));
+
+        let mut off = BatteryMonitor::new(0.0);
+        assert!(!off.update(0.0, true));
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Low-rate preview channels of heavy topics
+//
+// A preview keeps at most one sample per interval of its topic and records
+// it under `{topic}/preview`, so the platform can render quick previews
+// without downloading the full-rate data. Samples are kept as published;
+// the recorder doesn't decode or re-encode payloads.
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh::sample::Sample;
+
+use crate::buffer::TopicBuffer;
+
+/// Last chunk of the recorded name of a preview channel
+pub const PREVIEW_SUFFIX: &str = "preview";
+
+/// Recorded name of the preview channel of `recorded_topic`
+pub fn preview_topic(recorded_topic: &str) -> String {
+    format!("{}/{}", recorded_topic, PREVIEW_SUFFIX)
+}
+
+/// Decimated copy of one topic, fed by the topic's subscriber
+pub struct PreviewStream {
+    buffer: Arc<TopicBuffer>,
+    interval_ns: u64,
+    /// Receive time of the last sample taken, in ns (0 = none yet)
+    last_taken_ns: AtomicU64,
+}
+
+impl PreviewStream {
+    pub fn new(buffer: Arc<TopicBuffer>, interval: Duration) -> Self {
+        Self {
+            buffer,
+            interval_ns: interval.as_nanos() as u64,
+            last_taken_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether a sample received at `received_ns` goes into the preview
+    pub fn admit(&self, received_ns: u64) -> bool {
+        let last = self.last_taken_ns.load(Ordering::Relaxed);
+        if last != 0 && received_ns.saturating_sub(last) < self.interval_ns {
+            return false;
+        }
+        self.last_taken_ns
+            .compare_exchange(last, received_ns, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Record `sample` in the preview if its interval is due
+    pub async fn offer(&self, sample: &Sample) -> Result<()> {
+        self.offer_at(sample, None).await
+    }
+
+    /// `offer` with the timestamp chosen by the caller (see
+    /// `TopicBuffer::push_sample_at`)
+    pub async fn offer_at(&self, sample: &Sample, timestamp_ns: Option<u64>) -> Result<()> {
+        let now_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        if self.admit(now_ns) {
+            self.buffer
+                .push_sample_at(sample.clone(), timestamp_ns)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub fn buffer(&self) -> &Arc<TopicBuffer> {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flush_pool::FlushPool;
+
+    #[test]
+    fn test_preview_topic() {
+        assert_eq!(preview_topic("camera/front"), "camera/front/preview");
+    }
+
+    #[test]
+    fn test_admit_one_per_interval() {
+        let buffer = Arc::new(TopicBuffer::new(
+            "camera/preview".to_string(),
+            "rec".to_string(),
+            1024,
+            Duration::from_secs(10),
+            Arc::new(FlushPool::new(1, 1)),
+        ));
+        let preview = PreviewStream::new(buffer, Duration::from_secs(1));
+        let second = 1_000_000_000;
+
+        assert!(preview.admit(10 * second));
+        assert!(!preview.admit(10 * second + second / 2));
+        assert!(preview.admit(11 * second));
+        assert!(!preview.admit(11 * second + 1));
+        assert!(preview.admit(15 * second));
+    }
+}
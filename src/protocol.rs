@@ -13,20 +13,70 @@
 // limitations under the License.
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::recovery::SessionState;
+
+/// Key prefix of the handoff queryable (`{prefix}/{device_id}/offer|ready`)
+pub const HANDOFF_KEY_PREFIX: &str = "recorder/handoff";
+
+/// Key prefix of the group control queryables (`{prefix}/{group_id}`)
+pub const GROUP_KEY_PREFIX: &str = "recorder/control/group";
 
 /// Command types for recorder control
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum RecorderCommand {
+    #[default]
     Start,
     Pause,
     Resume,
     Cancel,
     Finish,
+    /// Fetch the payload schema drift report of a recording
+    #[serde(rename = "drift_report")]
+    DriftReport,
+    /// Change the backend upload limits at runtime
+    #[serde(rename = "set_upload_limit")]
+    SetUploadLimit,
+    /// Mute the listed `topics` of a recording
+    #[serde(rename = "pause_topics")]
+    PauseTopics,
+    /// Resume topics muted with `pause_topics`
+    #[serde(rename = "resume_topics")]
+    ResumeTopics,
+    /// Attach the listed `topics` to an active recording
+    #[serde(rename = "add_topics")]
+    AddTopics,
+    /// Detach the listed `topics` from an active recording
+    #[serde(rename = "remove_topics")]
+    RemoveTopics,
+    /// Dry run: estimate the data rates of `topics` from past recordings
+    #[serde(rename = "estimate")]
+    Estimate,
+    /// Re-read the configuration file and apply the settings that can change live
+    Reload,
+    /// Wait up to `wait_timeout_ms` for a finishing recording to be uploaded
+    /// and report its upload progress
+    #[serde(rename = "wait_for_completion")]
+    WaitForCompletion,
+    /// List the live background tasks and the recent task panics
+    Tasks,
+    /// Report the quota usage per organization and task
+    Quotas,
+    /// Store the latest sample of each of `topics` (default
+    /// `snapshot.topics`) as one batch, without starting a recording
+    Snapshot,
+    /// Store the black box rings of `topics` (default `black_box.topics`)
+    /// as a new recording
+    Dump,
+    /// Finish the active recording and continue it in a new one with the
+    /// same settings and topics
+    Rollover,
 }
 
 /// Compression level (0-4)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
 pub enum CompressionLevel {
     Fastest = 0,
     Fast = 1,
@@ -37,6 +87,18 @@ pub enum CompressionLevel {
 }
 
 impl CompressionLevel {
+    /// Level from its configured index (0-4)
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(CompressionLevel::Fastest),
+            1 => Some(CompressionLevel::Fast),
+            2 => Some(CompressionLevel::Default),
+            3 => Some(CompressionLevel::Slow),
+            4 => Some(CompressionLevel::Slowest),
+            _ => None,
+        }
+    }
+
     pub fn to_zstd_level(self) -> i32 {
         match self {
             CompressionLevel::Fastest => 1,
@@ -58,6 +120,12 @@ impl CompressionLevel {
     }
 }
 
+/// Highest level of the LZ4 (HC) compressor
+pub const MAX_LZ4_LEVEL: u32 = 12;
+
+/// Highest Zstd level
+pub const MAX_ZSTD_LEVEL: i32 = 22;
+
 /// Compression type
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
@@ -68,8 +136,23 @@ pub enum CompressionType {
     Zstd,
 }
 
+impl CompressionType {
+    /// Type from its configured name ("none", "lz4", "zstd")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(CompressionType::None),
+            "lz4" => Some(CompressionType::Lz4),
+            "zstd" => Some(CompressionType::Zstd),
+            _ => None,
+        }
+    }
+}
+
 /// Request message for recording control operations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The default is a Start of no topics; other fields are set with struct
+/// update syntax.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RecorderRequest {
     pub command: RecorderCommand,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -91,6 +174,143 @@ pub struct RecorderRequest {
     pub compression_level: CompressionLevel,
     #[serde(default)]
     pub compression_type: CompressionType,
+    /// New limits for `set_upload_limit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_limit: Option<UploadLimit>,
+    /// Target bucket (ReductStore) or subdirectory (filesystem) of a Start;
+    /// the configured default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    /// Liveliness key of the controlling application; the recording is
+    /// finished (or paused) when no matching token is alive for the grace period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller_liveliness: Option<String>,
+    /// IO priority of a Start; higher-priority recordings get their records
+    /// written first when uploads queue up (default 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// What a Start with the `recording_id` of an existing recording does
+    /// (default `return_existing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_exists: Option<IfExists>,
+    /// How long a `wait_for_completion` waits for the upload to end
+    /// (default 0: report the progress right away), or a `snapshot` for the
+    /// samples of its topics (default `snapshot.timeout_ms`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_timeout_ms: Option<u64>,
+    /// Credentials of the request when `control.auth` is enabled; removed
+    /// before the request is processed or stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<RequestAuth>,
+    /// Environment seed of a Start (simulation seed, map version, software
+    /// stack digest, ...), stored with the recording so replays can be
+    /// matched on identical conditions
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    /// Tags of a Start (`weather=rain`, ...), attached as labels to every
+    /// record of the recording
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    /// Zenoh time (ns since the epoch) at which a Start or Finish sent to a
+    /// group key takes effect on every recorder of the group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execute_at_ns: Option<u64>,
+    /// Keys the `topics` of a Start skip (`robot/**/debug/**`), on top of
+    /// `recorder.exclude_topics`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_topics: Vec<String>,
+    /// Buffer strategy of a Start, instead of `flush_policy.strategy`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_strategy: Option<BufferStrategy>,
+    /// How long the records of a Start are kept, instead of
+    /// `expiry.default_ttl_seconds` (needs `recorder.expiry.enabled`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_seconds: Option<u64>,
+}
+
+/// Credentials of a control, status or handoff request
+///
+/// Sent in the `auth` field of control requests, or as the JSON attachment
+/// of any query.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum RequestAuth {
+    /// HMAC-SHA256 with the secret of principal `key_id`, over
+    /// `{action}\n{device_id}\n{recording_id}\n{timestamp}\n{nonce}\n{body_sha256}`
+    /// as lowercase hex (see `control_auth::sign`)
+    Hmac {
+        key_id: String,
+        /// Unix time in seconds
+        timestamp: i64,
+        /// Unique per request; a signature is accepted once
+        #[serde(default)]
+        nonce: String,
+        signature: String,
+    },
+    /// Token of a principal
+    Token { token: String },
+}
+
+/// Policy of a Start whose `recording_id` is already taken
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IfExists {
+    /// Reject the request
+    Error,
+    /// Leave the recording as it is and return its state (retried Starts)
+    #[default]
+    ReturnExisting,
+    /// Cancel the recording and start it over under the same ID
+    Restart,
+}
+
+/// Named flush thresholds of the topic buffers, instead of hand-tuned
+/// `flush_policy` bytes and seconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferStrategy {
+    /// Small, frequent flushes: records reach storage within a second
+    LowLatency,
+    /// Large, rare flushes into pre-allocated buffers: fewer, bigger records
+    HighThroughput,
+    /// Small buffers allocated on demand and released soon when idle
+    LowMemory,
+}
+
+impl RecorderRequest {
+    /// Parse a request; compression settings it leaves out are taken from the
+    /// configured defaults
+    pub fn from_json_with_defaults(
+        bytes: &[u8],
+        compression_type: CompressionType,
+        compression_level: CompressionLevel,
+    ) -> serde_json::Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_slice(bytes)?;
+        if let Some(fields) = value.as_object_mut() {
+            if !fields.contains_key("compression_type") {
+                fields.insert(
+                    "compression_type".to_string(),
+                    serde_json::to_value(compression_type)?,
+                );
+            }
+            if !fields.contains_key("compression_level") {
+                fields.insert(
+                    "compression_level".to_string(),
+                    serde_json::to_value(compression_level)?,
+                );
+            }
+        }
+        serde_json::from_value(value)
+    }
+}
+
+/// Backend upload limits (0 = unlimited)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct UploadLimit {
+    #[serde(default)]
+    pub max_bytes_per_sec: u64,
+    #[serde(default)]
+    pub max_concurrent_uploads: usize,
 }
 
 /// Response message for recording control operations
@@ -102,6 +322,56 @@ pub struct RecorderResponse {
     pub recording_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bucket_name: Option<String>,
+    /// State of the recording a Start created or found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<RecordingStatus>,
+    /// Every problem found in a rejected request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationError>,
+    /// Machine-readable reason of a failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+}
+
+/// Reason a control request failed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Malformed request or invalid field values
+    InvalidRequest,
+    /// No recording with the given ID
+    NotFound,
+    /// A recording with the given ID already exists
+    AlreadyExists,
+    /// The recording's state does not allow the command
+    InvalidState,
+    /// The storage backend cannot be reached or failed
+    BackendUnavailable,
+    /// Disk space or another limit is exhausted
+    QuotaExceeded,
+    /// Missing or invalid credentials
+    Unauthorized,
+    /// Valid credentials without the required role
+    Forbidden,
+    /// Unexpected failure inside the recorder
+    Internal,
+}
+
+/// Problem with one field of a request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationError {
+    /// Field path, e.g. `device_id` or `topics[2]`
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
 
 /// Recording status
@@ -113,6 +383,8 @@ pub enum RecordingStatus {
     Paused,
     Uploading,
     Finished,
+    /// Finished and accepted by the ingestion API (`recorder.ingestion`)
+    Archived,
     Cancelled,
 }
 
@@ -137,6 +409,459 @@ pub struct StatusResponse {
     pub active_topics: Vec<String>,
     pub buffer_size_bytes: i32,
     pub total_recorded_bytes: i64,
+    /// Payload the recorded bytes were compressed from
+    #[serde(default)]
+    pub total_payload_bytes: u64,
+    /// Payload bytes per recorded byte (None while nothing was written)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    /// Samples received without a publisher timestamp
+    #[serde(default)]
+    pub samples_missing_timestamp: u64,
+    /// Paused flag of every topic of the recording
+    #[serde(default)]
+    pub topic_paused: HashMap<String, bool>,
+    /// Device-wide flush worker utilization (status queries only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flush_workers: Vec<FlushWorkerStatus>,
+    /// Device-wide serialization and compression load (status queries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionPoolStatus>,
+    /// Device-wide record timestamp collisions (status queries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_timestamps: Option<RecordTimestampStats>,
+    /// Rates of the last 30 seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throughput: Option<ThroughputStats>,
+    /// Counters of every subscribed topic, by topic
+    #[serde(default)]
+    pub per_topic: Vec<TopicStats>,
+    /// Records being uploaded, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uploads: Vec<SegmentUpload>,
+    /// Share of the payload left at Finish that has been written (0 - 100;
+    /// set once the recording is finishing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_percent: Option<f64>,
+    /// Environment seed given on Start
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    /// Machine-readable reason of a failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+}
+
+impl StatusResponse {
+    pub fn error(code: ErrorCode, message: String) -> Self {
+        Self {
+            success: false,
+            message,
+            error_code: Some(code),
+            status: RecordingStatus::Idle,
+            scene: None,
+            skills: vec![],
+            organization: None,
+            task_id: None,
+            device_id: String::new(),
+            data_collector_id: None,
+            active_topics: vec![],
+            buffer_size_bytes: 0,
+            total_recorded_bytes: 0,
+            total_payload_bytes: 0,
+            compression_ratio: None,
+            samples_missing_timestamp: 0,
+            topic_paused: Default::default(),
+            flush_workers: vec![],
+            compression: None,
+            record_timestamps: None,
+            throughput: None,
+            per_topic: vec![],
+            uploads: vec![],
+            upload_percent: None,
+            environment: BTreeMap::new(),
+        }
+    }
+}
+
+/// Response of a `wait_for_completion` query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_id: Option<String>,
+    pub status: RecordingStatus,
+    /// Whether the upload ended and the metadata was written
+    pub completed: bool,
+    /// Share of the payload left at Finish that has been written (0 - 100)
+    pub upload_percent: f64,
+    /// Payload still waiting to be written
+    pub backlog_bytes: u64,
+}
+
+/// Response of a `snapshot` command
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SnapshotResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+    /// Manifest of the stored snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<SnapshotManifest>,
+    /// Machine-readable reason of a failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+}
+
+impl SnapshotResponse {
+    pub fn error(code: ErrorCode, message: String) -> Self {
+        Self {
+            success: false,
+            message,
+            error_code: Some(code),
+            ..Default::default()
+        }
+    }
+}
+
+/// Stored next to a snapshot batch in `snapshots_metadata`, at the same
+/// timestamp
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    pub snapshot_id: String,
+    pub device_id: String,
+    pub taken_at: String,
+    /// Entry and timestamp of the batch record
+    pub entry: String,
+    pub timestamp_us: u64,
+    pub compression_type: String,
+    pub compression_level: i32,
+    /// Bytes of the stored batch
+    pub bytes: u64,
+    /// Topics with a sample in the batch
+    #[serde(default)]
+    pub topics: Vec<SnapshotTopic>,
+    /// Requested topics without a sample before the timeout (or whose
+    /// sample was dropped by a sample transform)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+    /// Tags given with the request, labels of both records
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+/// Sample of one topic in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotTopic {
+    /// Requested topic
+    pub topic: String,
+    /// Key expression of the sample
+    pub key_expr: String,
+    /// Receive time of the sample (ns since the epoch)
+    pub received_ns: u64,
+    /// Taken from a subscriber of an active recording rather than waited for
+    pub cached: bool,
+    pub payload_bytes: u64,
+}
+
+/// Shutdown stage of a background task; stages stop in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStage {
+    /// Serving control, status and data requests
+    Control,
+    /// Feeding samples into the topic buffers
+    Ingest,
+    /// Writing and uploading records
+    Flush,
+    /// Housekeeping (upload gate, signal handlers, ...)
+    Background,
+}
+
+impl TaskStage {
+    /// Every stage, in shutdown order
+    pub const ALL: [TaskStage; 4] = [
+        TaskStage::Control,
+        TaskStage::Ingest,
+        TaskStage::Flush,
+        TaskStage::Background,
+    ];
+}
+
+/// Whether a task ends on its own or runs until stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Ends once its work is done; waited for on shutdown
+    Job,
+    /// Loops until aborted
+    Service,
+}
+
+/// Live background task
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskInfo {
+    /// `process` or the ID of the recording the task belongs to
+    pub scope: String,
+    pub name: String,
+    pub stage: TaskStage,
+    pub kind: TaskKind,
+    pub started_at: String,
+    pub running_ms: u64,
+}
+
+/// Background task that panicked
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskPanic {
+    pub scope: String,
+    pub name: String,
+    pub stage: TaskStage,
+    pub message: String,
+    pub at: String,
+}
+
+/// Periodic health report of a recorder, published on
+/// `{health.key_prefix}/health/{device_id}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Heartbeat {
+    pub device_id: String,
+    /// Recorder version
+    pub version: String,
+    pub uptime_secs: u64,
+    /// Free space of the disk the backend writes to (None when the backend
+    /// doesn't write to local disk)
+    pub free_disk_bytes: Option<u64>,
+    pub backend_type: String,
+    pub backend_healthy: bool,
+    /// Why the backend health check failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_error: Option<String>,
+    /// Recordings recording or paused
+    pub active_recordings: usize,
+    pub at: String,
+}
+
+/// Recording event that fires the hooks configured in `recorder.hooks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingEventKind {
+    Start,
+    Finish,
+    Cancel,
+    /// Failed upload or finish
+    Error,
+}
+
+/// Payload of a recording event hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingEvent {
+    pub event: RecordingEventKind,
+    pub recording_id: String,
+    pub device_id: String,
+    pub at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Status of the recording when the event fired
+    pub status: StatusResponse,
+}
+
+/// Response of a `tasks` query
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TasksResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default)]
+    pub tasks: Vec<TaskInfo>,
+    /// Most recent panics first
+    #[serde(default)]
+    pub panics: Vec<TaskPanic>,
+}
+
+/// Response of a `quotas` query
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct QuotasResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default)]
+    pub quotas: Vec<QuotaUsage>,
+}
+
+/// What a quota is counted against
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    Organization,
+    Task,
+}
+
+impl QuotaScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Organization => "organization",
+            Self::Task => "task",
+        }
+    }
+}
+
+/// Usage and limits of one organization or task (0 = unlimited)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuotaUsage {
+    pub scope: QuotaScope,
+    /// Organization or task ID
+    pub name: String,
+    /// Recordings recording or paused
+    pub active_recordings: usize,
+    pub max_concurrent_recordings: usize,
+    /// Bytes stored since the start of the UTC day
+    pub bytes_today: u64,
+    pub max_bytes_per_day: u64,
+}
+
+/// Progress of one record upload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SegmentUpload {
+    /// Recorded topic name
+    pub topic: String,
+    pub timestamp_us: u64,
+    pub bytes_uploaded: u64,
+    pub bytes_total: u64,
+}
+
+/// Why a topic buffer was flushed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FlushReason {
+    /// The buffer reached `max_buffer_size_bytes`
+    Size,
+    /// The buffered samples reached `max_buffer_duration_seconds`
+    Time,
+    /// Pause, topic removal or Finish
+    Forced,
+    /// Recorder shutdown or emergency flush
+    Shutdown,
+}
+
+/// Flushes of a topic buffer by reason
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FlushCounts {
+    pub size: u64,
+    pub time: u64,
+    pub forced: u64,
+    pub shutdown: u64,
+}
+
+/// Counters of one topic of a recording
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicStats {
+    pub topic: String,
+    pub samples_received: u64,
+    /// Payload waiting in the topic buffer
+    pub bytes_buffered: u64,
+    /// Payload written to the storage backend
+    pub bytes_flushed: u64,
+    /// Bytes the flushed payload takes in the storage backend (after
+    /// compression)
+    #[serde(default)]
+    pub bytes_stored: u64,
+    /// Payload bytes per stored byte (None while nothing was written)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    /// Receive time of the last sample (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sample_at: Option<String>,
+    /// Samples rejected for a missing timestamp or lost to a full flush queue
+    pub dropped: u64,
+    /// Samples received through shared memory (`shared-memory` feature)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub shm_samples: u64,
+    /// Repeated samples skipped (topics of `recorder.dedupe`)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub duplicates_suppressed: u64,
+    /// Most payload the topic buffer held at once
+    #[serde(default)]
+    pub high_watermark_bytes: u64,
+    /// Flushes of the topic buffer by reason
+    #[serde(default)]
+    pub flushes: FlushCounts,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_flush_reason: Option<FlushReason>,
+}
+
+/// Rolling-window throughput of a recording
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThroughputStats {
+    /// Length of the window the rates are computed over
+    pub window_seconds: f64,
+    /// Payload received from the subscribers
+    pub ingest_bytes_per_sec: f64,
+    /// Payload written to the storage backend
+    pub upload_bytes_per_sec: f64,
+    /// Bytes written to the storage backend (after compression)
+    pub stored_bytes_per_sec: f64,
+    /// Payload bytes per stored byte; None while nothing was written
+    pub compression_ratio: Option<f64>,
+    /// Payload received but not written yet (buffered or queued)
+    pub backlog_bytes: u64,
+    /// Time to write the backlog at the current upload rate; None while the
+    /// backlog isn't moving
+    pub backlog_seconds: Option<f64>,
+}
+
+/// Utilization of one flush worker since the recorder started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FlushWorkerStatus {
+    pub worker: usize,
+    /// Flush tasks waiting in the worker's partition
+    pub queued: usize,
+    /// Flush tasks processed
+    pub tasks: u64,
+    pub busy_seconds: f64,
+    /// Busy share of the wall time (0.0 - 1.0)
+    pub utilization: f64,
+    /// Most flush tasks waiting at once
+    #[serde(default)]
+    pub max_queued: usize,
+    /// Flush tasks dropped because the worker's queue was full
+    #[serde(default)]
+    pub dropped: u64,
+    /// Average and longest time a task waited for the worker, in ms
+    #[serde(default)]
+    pub avg_wait_ms: f64,
+    #[serde(default)]
+    pub max_wait_ms: f64,
+}
+
+/// Load of the batch compression pool since the recorder started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompressionPoolStatus {
+    /// Batches compressed at once at most
+    pub threads: usize,
+    pub running: usize,
+    /// Batches waiting for a thread
+    pub queued: usize,
+    /// Most batches waiting at once
+    pub max_queued: usize,
+    /// Batches compressed
+    pub jobs: u64,
+    /// Batches dropped or discarded because their recording was cancelled
+    pub cancelled: u64,
+    pub busy_seconds: f64,
+    /// Busy share of the pool's capacity (0.0 - 1.0)
+    pub utilization: f64,
+}
+
+/// Record timestamps handed out since the recorder started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordTimestampStats {
+    /// Collision strategy (`bump` or `sequence`)
+    pub strategy: String,
+    /// Records stamped
+    pub allocated: u64,
+    /// Records whose clock time was taken by an earlier record of the entry
+    pub collisions: u64,
+    /// Furthest a record was moved past its clock time, in microseconds
+    pub max_shift_us: u64,
 }
 
 impl RecorderResponse {
@@ -146,15 +871,34 @@ impl RecorderResponse {
             message: "Operation completed successfully".to_string(),
             recording_id,
             bucket_name,
+            status: None,
+            errors: vec![],
+            error_code: None,
         }
     }
 
-    pub fn error(message: String) -> Self {
+    pub fn error(code: ErrorCode, message: String) -> Self {
         Self {
             success: false,
             message,
             recording_id: None,
             bucket_name: None,
+            status: None,
+            errors: vec![],
+            error_code: Some(code),
+        }
+    }
+
+    /// Rejection of a request listing all of its problems
+    pub fn invalid(errors: Vec<ValidationError>) -> Self {
+        let problems: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        Self {
+            message: format!("Invalid request: {}", problems.join("; ")),
+            errors,
+            ..Self::error(ErrorCode::InvalidRequest, String::new())
         }
     }
 }
@@ -176,5 +920,376 @@ pub struct RecordingMetadata {
     pub end_time: Option<String>,
     pub total_bytes: i64,
     pub total_samples: i64,
+    /// Payload `total_bytes` was compressed from
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub payload_bytes: u64,
+    /// Payload bytes per stored byte (None while nothing was written)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
     pub per_topic_stats: serde_json::Value,
+    /// Set when the recording was finalized by crash recovery instead of Finish
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Payload structure changes detected while recording
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schema_drift: Vec<DriftEvent>,
+    /// Overlap with the recording of another recorder process during a handoff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handoff: Option<HandoffInfo>,
+    /// Recording this one continues after a `rollover`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_recording_id: Option<String>,
+    /// Group of recorders started together (`recorder/control/group/{group_id}`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Topics attached or detached after Start, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topic_changes: Vec<TopicChange>,
+    /// Intervals the Zenoh session was disconnected while recording, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_gaps: Vec<SessionGap>,
+    /// Checksums of the records written for this recording, in upload order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<RecordChecksum>,
+    /// Records whose read-back verification found a checksum mismatch
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub verify_failures: u64,
+    /// Records read back after upload (see `integrity.verify_percent`)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub records_verified: u64,
+    /// Bucket requested on Start (None = configured default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    /// Liveliness key of the controlling application given on Start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller_liveliness: Option<String>,
+    /// IO priority requested on Start (None = 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// Recorded name of each remapped topic, keyed by the original topic
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub topic_aliases: BTreeMap<String, String>,
+    /// Recorded name of each preview channel, keyed by its recorded topic
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub previews: BTreeMap<String, String>,
+    /// Payload format detected for each recorded topic
+    /// (`schema.default_format = "auto"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub formats: BTreeMap<String, String>,
+    /// Zenoh nodes online when the recording started (`recorder.topology`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology: Option<TopologySnapshot>,
+    /// Environment seed given on Start, also stored as `env_{key}` labels of
+    /// the metadata record
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    /// Tags given on Start, labels of every record of the recording
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+    /// Keys the recorded topics skipped (`recorder.exclude_topics` and the
+    /// Start's `exclude_topics`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_topics: Vec<String>,
+    /// Buffer strategy the recording was buffered with (None = the raw
+    /// `flush_policy` thresholds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_strategy: Option<BufferStrategy>,
+    /// How long the records are kept (None = until deleted otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_seconds: Option<u64>,
+    /// Unix time (s) the records expire at, their `expires_at` label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Payload bytes per stored byte (None while nothing was stored)
+pub fn compression_ratio(payload_bytes: u64, stored_bytes: u64) -> Option<f64> {
+    (stored_bytes > 0).then(|| payload_bytes as f64 / stored_bytes as f64)
+}
+
+/// Stored record of a recording with the CRC32C of its bytes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordChecksum {
+    pub topic: String,
+    pub entry: String,
+    pub timestamp_us: u64,
+    pub bytes: usize,
+    pub crc32c: String,
+    /// Set when the record was stored within a coalesced record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coalesced: Option<CoalescedLocation>,
+    /// CRC32C of each chunk of the record, so downloads can check and fetch
+    /// again one chunk at a time (None = the record is one chunk)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<ChunkChecksums>,
+}
+
+/// CRC32C of consecutive `chunk_bytes` ranges of a record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChunkChecksums {
+    pub chunk_bytes: u64,
+    /// One per chunk, the last one covering the rest of the record
+    pub crc32c: Vec<String>,
+}
+
+/// Place of a record within a coalesced record (see `crate::coalesce`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoalescedLocation {
+    /// Timestamp of the coalesced record
+    pub timestamp_us: u64,
+    /// Offset of the record in the coalesced record
+    pub offset: u64,
+}
+
+/// Topic attached to or detached from a live recording
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicChange {
+    pub topic: String,
+    pub change: TopicChangeKind,
+    pub at: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicChangeKind {
+    Added,
+    Removed,
+}
+
+/// Interval without a connection to the Zenoh network; samples published in
+/// it were not recorded
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionGap {
+    pub started_at: String,
+    /// None while still disconnected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    /// The session was reopened (rather than reconnected by Zenoh) to end the gap
+    #[serde(default)]
+    pub reopened: bool,
+}
+
+/// Change of a topic's payload structure between two flushed segments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DriftEvent {
+    pub topic: String,
+    pub detected_at: String,
+    /// Index of the segment (flushed batch) that introduced the change
+    pub segment_index: u64,
+    pub previous_kind: String,
+    pub previous_hash: String,
+    pub new_kind: String,
+    pub new_hash: String,
+    #[serde(default)]
+    pub added_fields: Vec<String>,
+    #[serde(default)]
+    pub removed_fields: Vec<String>,
+}
+
+/// Current payload signature of a topic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicSignature {
+    pub topic: String,
+    /// "json" or "protobuf"
+    pub kind: String,
+    pub hash: String,
+    /// JSON key paths or protobuf `field:wire_type` tags
+    pub fields: Vec<String>,
+    /// Number of segments observed
+    pub segments: u64,
+}
+
+/// Response message for drift report queries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReportResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_id: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<TopicSignature>,
+    #[serde(default)]
+    pub events: Vec<DriftEvent>,
+}
+
+/// Response message for estimate (dry-run) queries
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EstimateResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(default)]
+    pub topics: Vec<TopicEstimate>,
+    /// Requested topics without statistics
+    #[serde(default)]
+    pub unknown_topics: Vec<String>,
+    /// Raw payload bytes per second of the known topics
+    #[serde(default)]
+    pub bytes_per_sec: f64,
+    /// Bytes per second written to the backend after compression
+    #[serde(default)]
+    pub stored_bytes_per_sec: f64,
+    /// Current upload limit (0 = unlimited)
+    #[serde(default)]
+    pub upload_limit_bytes_per_sec: u64,
+    /// Whether the upload limit keeps up with `stored_bytes_per_sec`
+    #[serde(default)]
+    pub upload_feasible: bool,
+}
+
+/// Estimated data rates of one topic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicEstimate {
+    pub topic: String,
+    /// Number of recordings the estimate is based on
+    pub recordings: u64,
+    pub samples_per_sec: f64,
+    pub avg_sample_bytes: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    pub bytes_per_sec: f64,
+    pub stored_bytes_per_sec: f64,
+}
+
+/// Response message for `reload` commands
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReloadResponse {
+    pub success: bool,
+    pub message: String,
+    /// Changed settings that were applied (dotted config paths)
+    #[serde(default)]
+    pub applied: Vec<String>,
+    /// Changed settings that only take effect after a restart
+    #[serde(default)]
+    pub restart_required: Vec<String>,
+}
+
+/// Zenoh topology seen by the recorder when a recording started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TopologySnapshot {
+    /// When the snapshot was taken (RFC 3339)
+    pub taken_at: String,
+    /// Zenoh ID of the recorder's session
+    pub zid: String,
+    /// Locators the recorder's session listens on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locators: Vec<String>,
+    /// Publishers on keys matching the recorded topics
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub publishers: Vec<TopologyPublisher>,
+    /// Nodes the recorder's session was connected to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<TopologySession>,
+}
+
+/// Node publishing on a key expression
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopologyPublisher {
+    pub key_expr: String,
+    pub zid: String,
+    /// "router", "peer" or "client"
+    pub whatami: String,
+    /// Remote locators of the links to the node (empty for local publishers)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locators: Vec<String>,
+}
+
+/// Node connected to the recorder's session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopologySession {
+    pub zid: String,
+    pub whatami: String,
+    /// Remote locators of the links to the node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locators: Vec<String>,
+}
+
+/// Handoff links of a recording to recordings of other recorder processes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct HandoffInfo {
+    /// Recording of the old recorder that this recording continues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predecessor: Option<HandoffOverlap>,
+    /// Recording of the new recorder that continues this recording
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<HandoffOverlap>,
+}
+
+/// Overlapping coverage with another recording
+///
+/// Samples between `overlap_start` and `overlap_end` are present in both recordings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HandoffOverlap {
+    pub recording_id: String,
+    pub overlap_start: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlap_end: Option<String>,
+}
+
+/// How a successor recorder takes over the active recordings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoffMode {
+    /// Restart every recording under a new recording_id; the predecessor
+    /// finishes its recordings
+    #[default]
+    Split,
+    /// Continue every recording under its recording_id from the predecessor's
+    /// state; the predecessor drains its buffers without finishing them
+    Continue,
+}
+
+/// Active recording offered to a successor recorder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffSession {
+    pub recording_id: String,
+    /// Start request recreating the recording in the successor
+    pub request: RecorderRequest,
+    /// Snapshot of the recording, for a successor continuing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<SessionState>,
+}
+
+/// Reply of a running recorder to a handoff `offer` query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffOffer {
+    pub device_id: String,
+    #[serde(default)]
+    pub sessions: Vec<HandoffSession>,
+}
+
+/// Predecessor/successor recording pair
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HandoffMapping {
+    pub predecessor: String,
+    pub successor: String,
+}
+
+/// Sent by the successor once all its subscriptions are declared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffReady {
+    #[serde(default)]
+    pub mappings: Vec<HandoffMapping>,
+    /// Time (RFC 3339) from which the successor records every topic
+    pub ready_at: String,
+    #[serde(default)]
+    pub mode: HandoffMode,
+}
+
+/// Reply of the predecessor after finishing the handed-off recordings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffAck {
+    pub success: bool,
+    pub message: String,
+    /// Predecessor recordings that were finished (or drained)
+    #[serde(default)]
+    pub finished: Vec<String>,
+    /// Time (RFC 3339) at which the predecessor stopped recording
+    pub finished_at: String,
+    /// Final state of the drained recordings (`continue` mode)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub states: Vec<SessionState>,
 }
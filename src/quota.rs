@@ -0,0 +1,237 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Recording quotas per organization and task (`recorder.quotas`)
+//
+// A recording counts against the organization and the task ID of its Start
+// request. The tracker adds up the bytes each of them stored during the
+// current UTC day; the manager counts their active recordings itself, refuses
+// Starts over a limit and finishes the recordings of a scope whose bytes for
+// the day are used up.
+//
+// Usage is kept in memory: a restarted recorder starts the day over.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::clock::{system_clock, Clock};
+use crate::config::{QuotaConfig, QuotaLimits};
+use crate::protocol::QuotaScope;
+
+/// Bytes stored by one scope on one day
+#[derive(Debug, Clone, Copy)]
+struct DayUsage {
+    day: NaiveDate,
+    bytes: u64,
+}
+
+/// Daily bytes per organization and task
+pub struct QuotaTracker {
+    config: QuotaConfig,
+    usage: Mutex<HashMap<(QuotaScope, String), DayUsage>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl QuotaTracker {
+    pub fn new(config: QuotaConfig) -> Self {
+        Self {
+            config,
+            usage: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Tell the day from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The current UTC day
+    fn today(&self) -> NaiveDate {
+        DateTime::<Utc>::from(self.clock.now()).date_naive()
+    }
+
+    /// Whether any limit is configured
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// Limits of a scope
+    pub fn limits(&self, scope: QuotaScope, name: &str) -> QuotaLimits {
+        match scope {
+            QuotaScope::Organization => self.config.organization(name),
+            QuotaScope::Task => self.config.task(name),
+        }
+    }
+
+    /// Scopes a recording counts against, with their limits
+    pub fn scopes(
+        &self,
+        organization: Option<&str>,
+        task_id: Option<&str>,
+    ) -> Vec<(QuotaScope, String, QuotaLimits)> {
+        [
+            (QuotaScope::Organization, organization),
+            (QuotaScope::Task, task_id),
+        ]
+        .into_iter()
+        .filter_map(|(scope, name)| {
+            let name = name.filter(|name| !name.is_empty())?;
+            let limits = self.limits(scope, name);
+            (!limits.is_unlimited()).then(|| (scope, name.to_string(), limits))
+        })
+        .collect()
+    }
+
+    /// Scopes that were configured or stored bytes today
+    pub fn known_scopes(&self) -> Vec<(QuotaScope, String)> {
+        let today = self.today();
+        let mut scopes: Vec<_> = self
+            .config
+            .organizations
+            .keys()
+            .map(|name| (QuotaScope::Organization, name.clone()))
+            .chain(
+                self.config
+                    .tasks
+                    .keys()
+                    .map(|name| (QuotaScope::Task, name.clone())),
+            )
+            .chain(
+                self.usage
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(_, usage)| usage.day == today)
+                    .map(|(key, _)| key.clone()),
+            )
+            .collect();
+        scopes.sort();
+        scopes.dedup();
+        scopes
+    }
+
+    /// Count bytes stored by a recording
+    pub fn record(&self, organization: Option<&str>, task_id: Option<&str>, bytes: u64) {
+        let today = self.today();
+        let mut usage = self.usage.lock().unwrap();
+        for (scope, name) in [
+            (QuotaScope::Organization, organization),
+            (QuotaScope::Task, task_id),
+        ] {
+            let Some(name) = name.filter(|name| !name.is_empty()) else {
+                continue;
+            };
+            let entry = usage.entry((scope, name.to_string())).or_insert(DayUsage {
+                day: today,
+                bytes: 0,
+            });
+            if entry.day != today {
+                *entry = DayUsage {
+                    day: today,
+                    bytes: 0,
+                };
+            }
+            entry.bytes += bytes;
+        }
+    }
+
+    /// Bytes a scope stored since the start of the UTC day
+    pub fn bytes_today(&self, scope: QuotaScope, name: &str) -> u64 {
+        let today = self.today();
+        self.usage
+            .lock()
+            .unwrap()
+            .get(&(scope, name.to_string()))
+            .filter(|usage| usage.day == today)
+            .map_or(0, |usage| usage.bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn limits(max_concurrent_recordings: usize, max_bytes_per_day: u64) -> QuotaLimits {
+        QuotaLimits {
+            max_concurrent_recordings,
+            max_bytes_per_day,
+        }
+    }
+
+    #[test]
+    fn test_scopes_resolve_limits() {
+        let tracker = QuotaTracker::new(QuotaConfig {
+            default_organization: limits(2, 0),
+            organizations: HashMap::from([("acme".to_string(), limits(5, 100))]),
+            tasks: HashMap::from([("t1".to_string(), limits(0, 10))]),
+        });
+        assert!(tracker.is_enabled());
+
+        let scopes = tracker.scopes(Some("acme"), Some("t1"));
+        assert_eq!(
+            scopes,
+            vec![
+                (QuotaScope::Organization, "acme".to_string(), limits(5, 100)),
+                (QuotaScope::Task, "t1".to_string(), limits(0, 10)),
+            ]
+        );
+        // Other organizations get the default, unknown tasks no limits
+        assert_eq!(
+            tracker.scopes(Some("other"), Some("t2")),
+            vec![(QuotaScope::Organization, "other".to_string(), limits(2, 0))]
+        );
+        assert!(tracker.scopes(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_record_bytes_per_scope() {
+        let tracker = QuotaTracker::new(QuotaConfig::default());
+        assert!(!tracker.is_enabled());
+
+        tracker.record(Some("acme"), Some("t1"), 10);
+        tracker.record(Some("acme"), None, 5);
+        assert_eq!(tracker.bytes_today(QuotaScope::Organization, "acme"), 15);
+        assert_eq!(tracker.bytes_today(QuotaScope::Task, "t1"), 10);
+        assert_eq!(tracker.bytes_today(QuotaScope::Task, "t2"), 0);
+        assert_eq!(
+            tracker.known_scopes(),
+            vec![
+                (QuotaScope::Organization, "acme".to_string()),
+                (QuotaScope::Task, "t1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usage_starts_over_each_day() {
+        // 2023-11-14T23:00:00Z
+        let clock = Arc::new(MockClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_002_800),
+        ));
+        let tracker = QuotaTracker::new(QuotaConfig::default()).with_clock(clock.clone());
+        tracker.record(Some("acme"), None, 10);
+        assert_eq!(tracker.bytes_today(QuotaScope::Organization, "acme"), 10);
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(tracker.bytes_today(QuotaScope::Organization, "acme"), 0);
+        assert!(tracker.known_scopes().is_empty());
+        tracker.record(Some("acme"), None, 3);
+        assert_eq!(tracker.bytes_today(QuotaScope::Organization, "acme"), 3);
+    }
+}
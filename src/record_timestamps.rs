@@ -0,0 +1,220 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Unique timestamps of stored records (`recorder.timestamps.collision_strategy`)
+//
+// Storage backends key records by entry and microsecond timestamp, so two
+// batches of an entry flushed within the same microsecond (the parts of a
+// split batch, or two recordings of the same topic) would overwrite each
+// other. Every record timestamp is allocated here instead of read off the
+// clock: the allocator remembers the last timestamp handed out per entry and
+// lets a strategy pick one past it.
+//
+// - `bump` keeps the clock time and moves colliding records forward a
+//   microsecond at a time, labelling them with `timestamp_bumped_us`.
+// - `sequence` stamps records with the millisecond and numbers the records of
+//   the entry within it, labelled `flush_seq`.
+//
+// Timestamps only ever increase per entry, so no record of this recorder
+// process replaces another one.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::config::CollisionStrategy;
+use crate::protocol::RecordTimestampStats;
+
+/// Label of records moved past a collision, with the shift in microseconds
+pub const BUMPED_LABEL: &str = "timestamp_bumped_us";
+/// Label numbering the records of an entry within a millisecond
+pub const SEQUENCE_LABEL: &str = "flush_seq";
+
+/// Timestamp picked for a record, with the label explaining it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    pub timestamp_us: u64,
+    pub label: Option<(String, String)>,
+}
+
+/// How a record timestamp is picked past the last one of its entry
+pub trait TimestampStrategy: Send + Sync {
+    /// Name reported in the status
+    fn name(&self) -> &'static str;
+
+    /// Timestamp for a record flushed at `now_us`; must be after `last_us`
+    fn allocate(&self, last_us: Option<u64>, now_us: u64) -> Allocation;
+}
+
+/// Clock time, bumped past the last record on collisions
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BumpStrategy;
+
+impl TimestampStrategy for BumpStrategy {
+    fn name(&self) -> &'static str {
+        "bump"
+    }
+
+    fn allocate(&self, last_us: Option<u64>, now_us: u64) -> Allocation {
+        match last_us {
+            Some(last) if last >= now_us => Allocation {
+                timestamp_us: last + 1,
+                label: Some((BUMPED_LABEL.to_string(), (last + 1 - now_us).to_string())),
+            },
+            _ => Allocation {
+                timestamp_us: now_us,
+                label: None,
+            },
+        }
+    }
+}
+
+/// Millisecond of the clock time plus a counter within it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SequenceStrategy;
+
+impl TimestampStrategy for SequenceStrategy {
+    fn name(&self) -> &'static str {
+        "sequence"
+    }
+
+    fn allocate(&self, last_us: Option<u64>, now_us: u64) -> Allocation {
+        let millisecond_us = now_us / 1000 * 1000;
+        let timestamp_us = match last_us {
+            Some(last) if last >= millisecond_us => last + 1,
+            _ => millisecond_us,
+        };
+        // Past 999 when more than a thousand records fall in a millisecond
+        let sequence = timestamp_us - millisecond_us;
+        Allocation {
+            timestamp_us,
+            label: Some((SEQUENCE_LABEL.to_string(), sequence.to_string())),
+        }
+    }
+}
+
+/// Strategy configured for the recorder
+pub fn strategy(config: CollisionStrategy) -> Box<dyn TimestampStrategy> {
+    match config {
+        CollisionStrategy::Bump => Box::new(BumpStrategy),
+        CollisionStrategy::Sequence => Box::new(SequenceStrategy),
+    }
+}
+
+/// Hands out record timestamps, increasing per entry
+pub struct RecordTimestamps {
+    strategy: Box<dyn TimestampStrategy>,
+    last: Mutex<HashMap<String, u64>>,
+    allocated: AtomicU64,
+    collisions: AtomicU64,
+    max_shift_us: AtomicU64,
+}
+
+impl RecordTimestamps {
+    pub fn new(strategy: Box<dyn TimestampStrategy>) -> Self {
+        Self {
+            strategy,
+            last: Mutex::new(HashMap::new()),
+            allocated: AtomicU64::new(0),
+            collisions: AtomicU64::new(0),
+            max_shift_us: AtomicU64::new(0),
+        }
+    }
+
+    /// Timestamp for a record of `entry` flushed at `now_us`
+    pub fn allocate(&self, entry: &str, now_us: u64) -> Allocation {
+        let allocation = {
+            let mut last = self.last.lock().unwrap();
+            let previous = last.get(entry).copied();
+            let allocation = self.strategy.allocate(previous, now_us);
+            debug_assert!(previous.is_none_or(|p| allocation.timestamp_us > p));
+            if previous.is_some_and(|p| p >= now_us) {
+                self.collisions.fetch_add(1, Ordering::Relaxed);
+            }
+            last.insert(entry.to_string(), allocation.timestamp_us);
+            allocation
+        };
+        self.allocated.fetch_add(1, Ordering::Relaxed);
+        self.max_shift_us.fetch_max(
+            allocation.timestamp_us.saturating_sub(now_us),
+            Ordering::Relaxed,
+        );
+        allocation
+    }
+
+    /// Records stamped and collisions avoided since the recorder started
+    pub fn stats(&self) -> RecordTimestampStats {
+        RecordTimestampStats {
+            strategy: self.strategy.name().to_string(),
+            allocated: self.allocated.load(Ordering::Relaxed),
+            collisions: self.collisions.load(Ordering::Relaxed),
+            max_shift_us: self.max_shift_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_past_collisions() {
+        let timestamps = RecordTimestamps::new(Box::new(BumpStrategy));
+        let first = timestamps.allocate("camera", 5_000);
+        assert_eq!(first.timestamp_us, 5_000);
+        assert_eq!(first.label, None);
+
+        let second = timestamps.allocate("camera", 5_000);
+        assert_eq!(second.timestamp_us, 5_001);
+        assert_eq!(
+            second.label,
+            Some((BUMPED_LABEL.to_string(), "1".to_string()))
+        );
+        // The clock went back
+        assert_eq!(timestamps.allocate("camera", 4_000).timestamp_us, 5_002);
+        // Entries don't collide with each other
+        assert_eq!(timestamps.allocate("lidar", 5_000).timestamp_us, 5_000);
+        assert_eq!(timestamps.allocate("camera", 9_000).timestamp_us, 9_000);
+
+        let stats = timestamps.stats();
+        assert_eq!(stats.strategy, "bump");
+        assert_eq!((stats.allocated, stats.collisions), (5, 2));
+        assert_eq!(stats.max_shift_us, 1_002);
+    }
+
+    #[test]
+    fn test_sequence_within_millisecond() {
+        let timestamps = RecordTimestamps::new(Box::new(SequenceStrategy));
+        let sequence = |a: Allocation| (a.timestamp_us, a.label.unwrap().1);
+        assert_eq!(
+            sequence(timestamps.allocate("camera", 5_123)),
+            (5_000, "0".to_string())
+        );
+        assert_eq!(
+            sequence(timestamps.allocate("camera", 5_456)),
+            (5_001, "1".to_string())
+        );
+        assert_eq!(
+            sequence(timestamps.allocate("camera", 6_001)),
+            (6_000, "0".to_string())
+        );
+        // Only records at or before the last one are collisions
+        assert_eq!(timestamps.stats().collisions, 0);
+        assert_eq!(
+            sequence(timestamps.allocate("camera", 6_000)),
+            (6_001, "1".to_string())
+        );
+        assert_eq!(timestamps.stats().collisions, 1);
+    }
+}
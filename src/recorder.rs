@@ -12,26 +12,86 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::Result;
-use crossbeam::queue::ArrayQueue;
+use crate::error::{RecorderError, Result};
 use dashmap::DashMap;
-use std::collections::HashMap;
+use futures_util::future::join_all;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, Notify, RwLock};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use uuid::Uuid;
-use zenoh::Session;
-use zenoh::Wait;
+use zenoh::key_expr::KeyExpr;
+use zenoh::sample::{Sample, SampleBuilder};
+use zenoh::{Config, Session};
 
+use crate::batch_index::{index_entry, BatchIndex};
+use crate::black_box::BlackBox;
 use crate::buffer::{FlushTask, TopicBuffer};
-use crate::config::RecorderConfig;
-use crate::mcap_writer::McapSerializer;
+use crate::clock::{rfc3339, system_clock, Clock, SharedClock};
+use crate::coalesce::{self, CoalescedPart, Coalescer, COALESCED_ENTRY};
+use crate::compression_pool::{CancelToken, CompressionPool};
+use crate::config::matching::{matches_any, topic_matches};
+use crate::config::{
+    find_per_topic, ConfigSource, ControllerLostAction, QuotaLimits, RecorderConfig,
+};
+use crate::control_auth::ControlAuth;
+use crate::controller_watch::ControllerWatch;
+use crate::drift::DriftTracker;
+use crate::flush_pool::{Dequeued, FlushPool};
+use crate::format_sniff;
+use crate::health::HealthAnnouncer;
+use crate::hooks::HookRunner;
+use crate::ingestion::IngestionClient;
+use crate::logging::LogLevel;
+use crate::mcap_writer::{attachment_labels, McapSerializer};
+use crate::preview::{preview_topic, PreviewStream};
 use crate::protocol::{
-    CompressionLevel, CompressionType, RecorderRequest, RecorderResponse, RecordingMetadata,
-    RecordingStatus, StatusResponse,
+    compression_ratio, CompletionResponse, CompressionLevel, CompressionType, DriftReportResponse,
+    ErrorCode, EstimateResponse, FlushReason, HandoffAck, HandoffInfo, HandoffMapping, HandoffMode,
+    HandoffOffer, HandoffOverlap, HandoffReady, HandoffSession, IfExists, QuotaScope, QuotaUsage,
+    QuotasResponse, RecordChecksum, RecorderCommand, RecorderRequest, RecorderResponse,
+    RecordingEvent, RecordingEventKind, RecordingMetadata, RecordingStatus, ReloadResponse,
+    SegmentUpload, SessionGap, SnapshotManifest, SnapshotResponse, SnapshotTopic, StatusResponse,
+    TaskStage, TasksResponse, TopicChange, TopicChangeKind, TopicEstimate, TopicStats,
+    TopologySnapshot, UploadLimit, HANDOFF_KEY_PREFIX,
+};
+use crate::quota::QuotaTracker;
+use crate::record_timestamps::{self, RecordTimestamps};
+use crate::recovery::{SessionState, SessionStateStore};
+use crate::runtime;
+use crate::sample_transform::{SampleTransform, SampleTransforms};
+use crate::schema_registry::SchemaRegistry;
+use crate::snapshot;
+use crate::status_events::StatusEventPublisher;
+use crate::storage::{
+    checksum, chunk_checksums, topic_to_entry_name, validate_bucket_name, BackendFactory,
+    StorageBackend, UploadProgress, CHECKSUM_CHUNK_BYTES, CHECKSUM_LABEL, EXPIRES_AT_LABEL,
 };
-use crate::storage::{topic_to_entry_name, StorageBackend};
+use crate::subscription_hub::{liveliness_key, SubscriptionHub};
+use crate::tap::{self, RecordingTap, TapPublisher, TapSample, TopicTap};
+use crate::task_registry::{TaskRegistry, PROCESS_SCOPE};
+use crate::throughput::ThroughputMeter;
+use crate::topic_stats::{TopicStatsStore, TopicTotals};
+use crate::topology;
+use crate::upload_gate::UploadGate;
+use crate::upload_limiter::UploadLimiter;
+use crate::validation;
+use crate::work_dir::{DeferredRecord, ShutdownManifest, WorkDirs};
+
+/// Serialized batch waiting to be coalesced with others
+type CoalescedBatch = (DeferredRecord, Vec<u8>);
+
+/// Subscription state of one topic of a recording
+#[derive(Default)]
+pub struct TopicSubscription {
+    /// Muted with `pause_topics`; the recording leaves the shared subscriber while paused
+    pub paused: AtomicBool,
+    stop: Notify,
+}
 
 /// Recording session state
 pub struct RecordingSession {
@@ -39,416 +99,5221 @@ pub struct RecordingSession {
     pub status: RwLock<RecordingStatus>,
     pub metadata: RecordingMetadata,
     pub topic_buffers: Arc<DashMap<String, Arc<TopicBuffer>>>,
+    pub subscriptions: DashMap<String, Arc<TopicSubscription>>,
     pub start_time: SystemTime,
     pub pause_time: RwLock<Option<SystemTime>>,
     pub total_bytes: RwLock<i64>,
+    pub flushed_batches: RwLock<u64>,
+    pub last_flush_us: RwLock<Option<u64>>,
     pub compression_type: CompressionType,
     pub compression_level: CompressionLevel,
+    pub drift: DriftTracker,
+    /// Number of topics whose subscriber has been declared
+    pub subscribed_topics: Arc<AtomicUsize>,
+    pub handoff: RwLock<Option<HandoffInfo>>,
+    /// Current topic set (`metadata.topics` plus/minus `topic_changes`)
+    pub topics: RwLock<Vec<String>>,
+    pub topic_changes: RwLock<Vec<TopicChange>>,
+    /// Zenoh session disconnections while recording
+    pub session_gaps: RwLock<Vec<SessionGap>>,
+    /// Per-topic totals of the segments written so far
+    pub topic_totals: DashMap<String, TopicTotals>,
+    /// Checksums of the records written so far
+    pub records: RwLock<Vec<RecordChecksum>>,
+    pub verify_failures: AtomicU64,
+    /// Records read back after upload, matching or not
+    pub records_verified: AtomicU64,
+    /// Backend writing to the bucket selected on Start
+    pub storage: Arc<dyn StorageBackend>,
+    /// Dead-man switch of recordings started with a controller liveliness key
+    pub controller: Option<Arc<ControllerWatch>>,
+    /// Rolling ingest/upload rates reported in status responses
+    pub throughput: Arc<ThroughputMeter>,
+    /// Records being uploaded, by (recorded topic, record timestamp)
+    pub uploads: DashMap<(String, u64), Arc<UploadProgress>>,
+    /// Preview channels, by requested topic
+    pub previews: DashMap<String, Arc<PreviewStream>>,
+    /// Live tap of the recording, fed by `taps`
+    pub tap: Arc<RecordingTap>,
+    /// Tapped topics, by requested topic
+    pub taps: DashMap<String, Arc<TopicTap>>,
+    /// Payload formats detected so far (`schema.default_format = "auto"`),
+    /// by recorded topic
+    pub formats: DashMap<String, String>,
+    /// Payload backlog when the recording started finishing
+    pub finish_backlog: AtomicU64,
+    /// Woken once a finishing recording is uploaded and its metadata written
+    pub completed: Notify,
+    /// Fired on Cancel; drops the recording's pending compression jobs
+    pub cancel: CancelToken,
+    /// Subscriber tasks of the recording
+    pub tasks: TaskRegistry,
+    /// Held while batches of the recording taken from the coalescer are stored
+    coalesce_lock: Mutex<()>,
 }
 
-/// Recorder manager handles all recording sessions
-pub struct RecorderManager {
-    session: Arc<Session>,
-    sessions: Arc<DashMap<String, Arc<RecordingSession>>>,
-    storage_backend: Arc<dyn StorageBackend>,
-    flush_queue: Arc<ArrayQueue<FlushTask>>,
-    config: RecorderConfig,
-}
-
-impl RecorderManager {
-    /// Create a new RecorderManager with configuration
-    pub fn new(
-        session: Arc<Session>,
-        storage_backend: Arc<dyn StorageBackend>,
-        config: RecorderConfig,
+impl RecordingSession {
+    /// Rebuild a session from its state, persisted before a crash or handed
+    /// over by a predecessor recorder
+    fn from_state(
+        state: SessionState,
+        storage: Arc<dyn StorageBackend>,
+        tap: Arc<RecordingTap>,
+        tasks: TaskRegistry,
+        clock: Arc<dyn Clock>,
     ) -> Self {
-        let flush_queue = Arc::new(ArrayQueue::new(config.recorder.workers.queue_capacity));
-
-        let manager = Self {
-            session,
-            sessions: Arc::new(DashMap::new()),
-            storage_backend,
-            flush_queue: flush_queue.clone(),
-            config,
-        };
+        let start_time = chrono::DateTime::parse_from_rfc3339(&state.metadata.start_time)
+            .map(SystemTime::from)
+            .unwrap_or_else(|_| clock.now());
 
-        // Start flush worker threads
-        manager.start_flush_workers();
+        let metadata = state.metadata;
+        let handoff = metadata.handoff.clone();
+        let topics = metadata.topics.clone();
+        let topic_changes = metadata.topic_changes.clone();
+        let session_gaps = metadata.session_gaps.clone();
+        let records = metadata.records.clone();
+        let verify_failures = metadata.verify_failures;
+        let records_verified = metadata.records_verified;
+        let controller = metadata
+            .controller_liveliness
+            .clone()
+            .map(|key_expr| Arc::new(ControllerWatch::new(key_expr, clock)));
 
-        manager
+        Self {
+            recording_id: state.recording_id,
+            status: RwLock::new(RecordingStatus::Recording),
+            metadata,
+            topic_buffers: Arc::new(DashMap::new()),
+            subscriptions: DashMap::new(),
+            start_time,
+            pause_time: RwLock::new(None),
+            total_bytes: RwLock::new(state.flushed_bytes),
+            flushed_batches: RwLock::new(state.flushed_batches),
+            last_flush_us: RwLock::new(state.last_flush_us),
+            compression_type: state.compression_type,
+            compression_level: state.compression_level,
+            drift: DriftTracker::new(),
+            subscribed_topics: Arc::new(AtomicUsize::new(0)),
+            handoff: RwLock::new(handoff),
+            topics: RwLock::new(topics),
+            topic_changes: RwLock::new(topic_changes),
+            session_gaps: RwLock::new(session_gaps),
+            topic_totals: DashMap::new(),
+            records: RwLock::new(records),
+            verify_failures: AtomicU64::new(verify_failures),
+            records_verified: AtomicU64::new(records_verified),
+            storage,
+            controller,
+            throughput: Arc::new(ThroughputMeter::new()),
+            uploads: DashMap::new(),
+            previews: DashMap::new(),
+            tap,
+            taps: DashMap::new(),
+            formats: DashMap::new(),
+            finish_backlog: AtomicU64::new(0),
+            completed: Notify::new(),
+            cancel: CancelToken::default(),
+            tasks,
+            coalesce_lock: Mutex::new(()),
+        }
     }
 
-    /// Start recording
+    /// Feed `sample`, published on `topic`, to the buffers of the requested
+    /// topics it matches, as their subscribers would
     ///
-    /// The recording_id is always generated by the recorder to ensure uniqueness.
-    /// Clients receive the generated ID in the response.
-    pub async fn start_recording(&self, request: RecorderRequest) -> RecorderResponse {
-        let recording_id = Uuid::new_v4().to_string();
+    /// Returns the number of buffers fed: none unless the recording is
+    /// `Recording`, and none for muted topics.
+    pub async fn ingest(&self, topic: &str, sample: &Sample, timestamp_ns: Option<u64>) -> usize {
+        if *self.status.read().await != RecordingStatus::Recording
+            || matches_any(&self.metadata.exclude_topics, topic)
+        {
+            return 0;
+        }
+        let buffers: Vec<(String, Arc<TopicBuffer>)> = self
+            .topic_buffers
+            .iter()
+            .filter(|entry| topic_matches(entry.key(), topic))
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
 
-        info!("Starting recording '{}'", recording_id);
+        let mut fed = 0;
+        for (requested, buffer) in buffers {
+            let muted = self
+                .subscriptions
+                .get(&requested)
+                .is_some_and(|subscription| subscription.paused.load(Ordering::SeqCst));
+            if muted {
+                continue;
+            }
+            self.throughput.record_ingest(sample.payload().len());
+            let preview = self.previews.get(&requested).map(|p| p.value().clone());
+            if let Some(preview) = preview {
+                if let Err(e) = preview.offer_at(sample, timestamp_ns).await {
+                    error!("Failed to push sample to preview: {}", e);
+                }
+            }
+            if let Some(tap) = self.taps.get(&requested) {
+                tap.offer(sample);
+            }
+            match buffer.push_sample_at(sample.clone(), timestamp_ns).await {
+                Ok(()) => fed += 1,
+                Err(e) => error!("Failed to push sample to buffer: {}", e),
+            }
+        }
+        fed
+    }
 
-        // Initialize storage backend
-        if let Err(e) = self.storage_backend.initialize().await {
-            error!("Failed to initialize storage backend: {}", e);
-            return RecorderResponse::error(format!("Failed to initialize storage: {}", e));
+    /// Start request of a recording with the same settings and current topics
+    async fn start_request(&self) -> RecorderRequest {
+        let metadata = &self.metadata;
+        RecorderRequest {
+            command: RecorderCommand::Start,
+            recording_id: None,
+            scene: metadata.scene.clone(),
+            skills: metadata.skills.clone(),
+            organization: metadata.organization.clone(),
+            task_id: metadata.task_id.clone(),
+            device_id: metadata.device_id.clone(),
+            data_collector_id: metadata.data_collector_id.clone(),
+            topics: self.topics.read().await.clone(),
+            compression_level: self.compression_level,
+            compression_type: self.compression_type,
+            upload_limit: None,
+            bucket: metadata.bucket.clone(),
+            controller_liveliness: metadata.controller_liveliness.clone(),
+            priority: metadata.priority,
+            if_exists: None,
+            wait_timeout_ms: None,
+            auth: None,
+            environment: metadata.environment.clone(),
+            labels: metadata.labels.clone(),
+            execute_at_ns: None,
+            exclude_topics: metadata.exclude_topics.clone(),
+            buffer_strategy: metadata.buffer_strategy,
+            retention_seconds: metadata.retention_seconds,
         }
+    }
 
-        let metadata = RecordingMetadata {
-            recording_id: recording_id.clone(),
-            scene: request.scene.clone(),
-            skills: request.skills.clone(),
-            organization: request.organization.clone(),
-            task_id: request.task_id.clone(),
-            device_id: request.device_id.clone(),
-            data_collector_id: request.data_collector_id.clone(),
-            topics: request.topics.clone(),
-            compression_type: format!("{:?}", request.compression_type),
-            compression_level: request.compression_level as i32,
-            start_time: chrono::Utc::now().to_rfc3339(),
-            end_time: None,
-            total_bytes: 0,
-            total_samples: 0,
-            per_topic_stats: serde_json::json!({}),
-        };
+    /// Snapshot the state persisted for crash recovery
+    async fn to_state(&self) -> SessionState {
+        let mut metadata = self.metadata.clone();
+        metadata.handoff = self.handoff.read().await.clone();
+        metadata.topics = self.topics.read().await.clone();
+        metadata.topic_changes = self.topic_changes.read().await.clone();
+        metadata.session_gaps = self.session_gaps.read().await.clone();
+        metadata.records = self.records.read().await.clone();
+        metadata.verify_failures = self.verify_failures.load(Ordering::Relaxed);
+        metadata.records_verified = self.records_verified.load(Ordering::Relaxed);
 
-        let recording_session = Arc::new(RecordingSession {
-            recording_id: recording_id.clone(),
-            status: RwLock::new(RecordingStatus::Recording),
+        SessionState {
+            recording_id: self.recording_id.clone(),
             metadata,
-            topic_buffers: Arc::new(DashMap::new()),
-            start_time: SystemTime::now(),
-            pause_time: RwLock::new(None),
-            total_bytes: RwLock::new(0),
-            compression_type: request.compression_type,
-            compression_level: request.compression_level,
-        });
-
-        // Subscribe to topics
-        for topic in &request.topics {
-            // Use configured flush policy
-            let flush_policy = &self.config.recorder.flush_policy;
-            let buffer = Arc::new(TopicBuffer::new(
-                topic.clone(),
-                recording_id.clone(),
-                flush_policy.max_buffer_size_bytes,
-                flush_policy.max_duration(),
-                self.flush_queue.clone(),
-            ));
-
-            recording_session
-                .topic_buffers
-                .insert(topic.clone(), buffer.clone());
-
-            // Subscribe to topic
-            let session = self.session.clone();
-            let recording_id_clone = recording_id.clone();
-            let topic_clone = topic.clone();
-
-            tokio::spawn(async move {
-                match session.declare_subscriber(&topic_clone).wait() {
-                    Ok(subscriber) => {
-                        info!(
-                            "Subscribed to topic '{}' for recording '{}'",
-                            topic_clone, recording_id_clone
-                        );
+            compression_type: self.compression_type,
+            compression_level: self.compression_level,
+            flushed_batches: *self.flushed_batches.read().await,
+            flushed_bytes: *self.total_bytes.read().await,
+            last_flush_us: *self.last_flush_us.read().await,
+        }
+    }
 
-                        loop {
-                            match subscriber.recv_async().await {
-                                Ok(sample) => {
-                                    if let Err(e) = buffer.push_sample(sample).await {
-                                        error!("Failed to push sample to buffer: {}", e);
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Error receiving sample: {}", e);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to subscribe to topic '{}': {}", topic_clone, e);
-                    }
-                }
-            });
+    /// Add what a predecessor wrote to this continued recording after
+    /// offering the state it was rebuilt from (`offered`); `drained` is the
+    /// predecessor's state once drained
+    async fn merge_drained(&self, offered: &SessionState, drained: SessionState) {
+        {
+            let mut records = self.records.write().await;
+            let offered_len = offered.metadata.records.len().min(records.len());
+            let own = records.split_off(offered_len);
+            *records = drained.metadata.records;
+            records.extend(own);
         }
+        *self.total_bytes.write().await += drained.flushed_bytes - offered.flushed_bytes;
+        *self.flushed_batches.write().await += drained
+            .flushed_batches
+            .saturating_sub(offered.flushed_batches);
+        let mut last_flush_us = self.last_flush_us.write().await;
+        *last_flush_us = (*last_flush_us).max(drained.last_flush_us);
+        self.verify_failures.fetch_add(
+            drained
+                .metadata
+                .verify_failures
+                .saturating_sub(offered.metadata.verify_failures),
+            Ordering::Relaxed,
+        );
+        self.records_verified.fetch_add(
+            drained
+                .metadata
+                .records_verified
+                .saturating_sub(offered.metadata.records_verified),
+            Ordering::Relaxed,
+        );
+    }
 
-        self.sessions
-            .insert(recording_id.clone(), recording_session);
+    /// Current status of the recording
+    pub async fn status_response(&self) -> StatusResponse {
+        let status = *self.status.read().await;
+        let buffer_size_bytes: usize = self
+            .topic_buffers
+            .iter()
+            .map(|entry| entry.value().stats().1)
+            .sum();
+        let total_recorded_bytes = *self.total_bytes.read().await;
+        let total_payload_bytes = self.payload_bytes();
 
-        // Get bucket name from config (if ReductStore backend)
-        let bucket_name = self
-            .config
-            .storage
-            .backend_config
-            .as_reductstore()
-            .map(|reduct_config| reduct_config.bucket_name.clone());
+        StatusResponse {
+            success: true,
+            message: "Status retrieved successfully".to_string(),
+            status,
+            scene: self.metadata.scene.clone(),
+            skills: self.metadata.skills.clone(),
+            organization: self.metadata.organization.clone(),
+            task_id: self.metadata.task_id.clone(),
+            device_id: self.metadata.device_id.clone(),
+            data_collector_id: self.metadata.data_collector_id.clone(),
+            active_topics: self.topics.read().await.clone(),
+            buffer_size_bytes: buffer_size_bytes as i32,
+            total_recorded_bytes,
+            total_payload_bytes,
+            compression_ratio: compression_ratio(total_payload_bytes, total_recorded_bytes as u64),
+            samples_missing_timestamp: self
+                .topic_buffers
+                .iter()
+                .map(|entry| entry.value().timestamp_stats().0)
+                .sum(),
+            topic_paused: self
+                .subscriptions
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.key().clone(),
+                        entry.value().paused.load(Ordering::SeqCst),
+                    )
+                })
+                .collect(),
+            flush_workers: vec![],
+            compression: None,
+            record_timestamps: None,
+            throughput: Some(self.throughput.stats()),
+            per_topic: self.topic_stats(),
+            uploads: self.upload_progress(),
+            upload_percent: self.upload_percent(status),
+            environment: self.metadata.environment.clone(),
+            error_code: None,
+        }
+    }
 
-        RecorderResponse::success(Some(recording_id), bucket_name)
+    /// Payload of the records written so far, before compression
+    fn payload_bytes(&self) -> u64 {
+        self.topic_totals
+            .iter()
+            .map(|totals| totals.raw_bytes.load(Ordering::Relaxed))
+            .sum()
     }
 
-    /// Pause recording
-    pub async fn pause_recording(&self, recording_id: &str) -> RecorderResponse {
-        match self.sessions.get(recording_id) {
-            Some(session) => {
-                let mut status = session.status.write().await;
-                if *status == RecordingStatus::Recording {
-                    *status = RecordingStatus::Paused;
-                    *session.pause_time.write().await = Some(SystemTime::now());
-                    info!("Recording '{}' paused", recording_id);
-                    RecorderResponse::success(Some(recording_id.to_string()), None)
-                } else {
-                    RecorderResponse::error("Recording is not in Recording state".to_string())
-                }
+    /// Share of the payload left at Finish that has been written
+    /// (None until the recording is finishing)
+    fn upload_percent(&self, status: RecordingStatus) -> Option<f64> {
+        match status {
+            RecordingStatus::Uploading => {
+                let backlog = self.finish_backlog.load(Ordering::Relaxed);
+                let written = backlog.saturating_sub(self.throughput.backlog_bytes());
+                Some(match backlog {
+                    0 => 100.0,
+                    _ => written as f64 * 100.0 / backlog as f64,
+                })
             }
-            None => RecorderResponse::error(format!("Recording '{}' not found", recording_id)),
+            RecordingStatus::Finished | RecordingStatus::Archived => Some(100.0),
+            _ => None,
         }
     }
 
-    /// Resume recording
-    pub async fn resume_recording(&self, recording_id: &str) -> RecorderResponse {
-        match self.sessions.get(recording_id) {
-            Some(session) => {
-                let mut status = session.status.write().await;
-                if *status == RecordingStatus::Paused {
-                    *status = RecordingStatus::Recording;
-                    *session.pause_time.write().await = None;
-                    info!("Recording '{}' resumed", recording_id);
-                    RecorderResponse::success(Some(recording_id.to_string()), None)
-                } else {
-                    RecorderResponse::error("Recording is not in Paused state".to_string())
+    /// Progress of the running record uploads, oldest first
+    fn upload_progress(&self) -> Vec<SegmentUpload> {
+        let mut uploads: Vec<SegmentUpload> = self
+            .uploads
+            .iter()
+            .map(|entry| {
+                let (topic, timestamp_us) = entry.key();
+                SegmentUpload {
+                    topic: topic.clone(),
+                    timestamp_us: *timestamp_us,
+                    bytes_uploaded: entry.value().uploaded(),
+                    bytes_total: entry.value().total(),
                 }
-            }
-            None => RecorderResponse::error(format!("Recording '{}' not found", recording_id)),
-        }
+            })
+            .collect();
+        uploads.sort_by(|a, b| (a.timestamp_us, &a.topic).cmp(&(b.timestamp_us, &b.topic)));
+        uploads
     }
 
-    /// Cancel recording
-    pub async fn cancel_recording(&self, recording_id: &str) -> RecorderResponse {
-        match self.sessions.get(recording_id) {
-            Some(session) => {
-                *session.status.write().await = RecordingStatus::Cancelled;
-                info!("Recording '{}' cancelled", recording_id);
-                RecorderResponse::success(Some(recording_id.to_string()), None)
-            }
-            None => RecorderResponse::error(format!("Recording '{}' not found", recording_id)),
-        }
+    /// Counters of the subscribed topics, sorted by topic
+    fn topic_stats(&self) -> Vec<TopicStats> {
+        let mut stats: Vec<TopicStats> = self
+            .topic_buffers
+            .iter()
+            .map(|entry| {
+                let buffer = entry.value();
+                let (received, dropped, last_sample_ns) = buffer.ingest_stats();
+                let (_, rejected) = buffer.timestamp_stats();
+                let (flushes, last_flush_reason) = buffer.flush_stats();
+                // Totals are kept by recorded name
+                let (bytes_flushed, bytes_stored) = self
+                    .topic_totals
+                    .get(buffer.topic_name())
+                    .map(|totals| {
+                        (
+                            totals.raw_bytes.load(Ordering::Relaxed),
+                            totals.stored_bytes.load(Ordering::Relaxed),
+                        )
+                    })
+                    .unwrap_or_default();
+                TopicStats {
+                    topic: entry.key().clone(),
+                    samples_received: received,
+                    bytes_buffered: buffer.stats().1 as u64,
+                    bytes_flushed,
+                    bytes_stored,
+                    compression_ratio: compression_ratio(bytes_flushed, bytes_stored),
+                    last_sample_at: last_sample_ns
+                        .map(|ns| chrono::DateTime::from_timestamp_nanos(ns as i64).to_rfc3339()),
+                    dropped: dropped + rejected,
+                    shm_samples: buffer.shm_samples(),
+                    duplicates_suppressed: buffer.duplicates_suppressed(),
+                    high_watermark_bytes: buffer.high_watermark_bytes(),
+                    flushes,
+                    last_flush_reason,
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.topic.cmp(&b.topic));
+        stats
     }
 
-    /// Finish recording
-    pub async fn finish_recording(&self, recording_id: &str) -> RecorderResponse {
-        match self.sessions.get(recording_id) {
-            Some(session) => {
-                info!("Finishing recording '{}'", recording_id);
+    async fn record_topic_change(&self, topic: &str, change: TopicChangeKind, at: SystemTime) {
+        self.topic_changes.write().await.push(TopicChange {
+            topic: topic.to_string(),
+            change,
+            at: rfc3339(at),
+        });
+    }
+}
 
-                // Flush all remaining buffers
-                for entry in session.topic_buffers.iter() {
-                    if let Err(e) = entry.value().force_flush().await {
-                        error!("Failed to flush buffer for topic '{}': {}", entry.key(), e);
-                    }
-                }
+/// List every topic that was part of the recording at some point in `topics`
+fn include_changed_topics(metadata: &mut RecordingMetadata) {
+    for change in &metadata.topic_changes {
+        if !metadata.topics.contains(&change.topic) {
+            metadata.topics.push(change.topic.clone());
+        }
+    }
+}
 
-                // Wait a bit for flush tasks to complete
-                tokio::time::sleep(Duration::from_secs(2)).await;
+/// Entry of the recording metadata records
+const METADATA_ENTRY: &str = "recordings_metadata";
 
-                *session.status.write().await = RecordingStatus::Finished;
+/// Settings `reload` applies without a restart (dotted config paths)
+const LIVE_SETTINGS: &[&str] = &[
+    "recorder.flush_policy",
+    "recorder.compression",
+    "recorder.upload_limit",
+    "logging.level",
+];
 
-                // Write metadata
-                if let Err(e) = self.write_metadata(&session).await {
-                    error!("Failed to write metadata: {}", e);
-                }
+fn is_live_setting(path: &str) -> bool {
+    LIVE_SETTINGS.iter().any(|live| {
+        path.strip_prefix(live)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
 
-                info!("Recording '{}' finished", recording_id);
-                RecorderResponse::success(Some(recording_id.to_string()), None)
+/// Collect the dotted paths of the leaves that differ between two configs
+fn changed_settings(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    path: &str,
+    out: &mut Vec<String>,
+) {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                let null = serde_json::Value::Null;
+                changed_settings(
+                    old.get(key).unwrap_or(&null),
+                    new.get(key).unwrap_or(&null),
+                    &child,
+                    out,
+                );
             }
-            None => RecorderResponse::error(format!("Recording '{}' not found", recording_id)),
         }
+        (old, new) if old != new => out.push(path.to_string()),
+        _ => {}
     }
+}
 
-    /// Get recording status
-    pub async fn get_status(&self, recording_id: &str) -> StatusResponse {
-        match self.sessions.get(recording_id) {
-            Some(session) => {
-                let status = *session.status.read().await;
-                let (_total_samples, total_bytes) = self.calculate_stats(&session).await;
-
-                StatusResponse {
-                    success: true,
-                    message: "Status retrieved successfully".to_string(),
-                    status,
-                    scene: session.metadata.scene.clone(),
-                    skills: session.metadata.skills.clone(),
-                    organization: session.metadata.organization.clone(),
-                    task_id: session.metadata.task_id.clone(),
-                    device_id: session.metadata.device_id.clone(),
-                    data_collector_id: session.metadata.data_collector_id.clone(),
-                    active_topics: session.metadata.topics.clone(),
-                    buffer_size_bytes: total_bytes as i32,
-                    total_recorded_bytes: *session.total_bytes.read().await,
-                }
-            }
-            None => StatusResponse {
-                success: false,
-                message: format!("Recording '{}' not found", recording_id),
-                status: RecordingStatus::Idle,
-                scene: None,
-                skills: vec![],
-                organization: None,
-                task_id: None,
-                device_id: String::new(),
-                data_collector_id: None,
-                active_topics: vec![],
-                buffer_size_bytes: 0,
-                total_recorded_bytes: 0,
-            },
+/// Recordings a new recording is related to, kept in its metadata
+#[derive(Default)]
+struct RecordingLinks {
+    /// Recording continued after a `rollover`
+    previous_recording_id: Option<String>,
+    /// Group of recorders it was started for
+    group_id: Option<String>,
+    /// Topics muted in the recording it continues, which stay muted
+    paused_topics: Vec<String>,
+}
+
+/// Set once the shutdown deadline passed: records go to the work directory
+/// instead of the backend
+struct ShutdownSpill {
+    armed: AtomicBool,
+    notify: Notify,
+    /// Time uploads in flight get to complete once armed
+    grace: Duration,
+}
+
+impl ShutdownSpill {
+    fn new(grace: Duration) -> Self {
+        Self {
+            armed: AtomicBool::new(false),
+            notify: Notify::new(),
+            grace,
         }
     }
 
-    /// Calculate current statistics
-    async fn calculate_stats(&self, session: &RecordingSession) -> (usize, usize) {
-        let mut total_samples = 0;
-        let mut total_bytes = 0;
+    fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Relaxed)
+    }
 
-        for entry in session.topic_buffers.iter() {
-            let (samples, bytes) = entry.value().stats();
-            total_samples += samples;
-            total_bytes += bytes;
-        }
+    fn arm(&self) {
+        self.armed.store(true, Ordering::Relaxed);
+        self.notify.notify_waiters();
+    }
 
-        (total_samples, total_bytes)
+    /// Completes when an upload in flight should be given up
+    async fn abandon_uploads(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_armed() {
+                break;
+            }
+            notified.await;
+        }
+        runtime::sleep(self.grace).await;
     }
+}
 
-    /// Write metadata to storage backend
-    async fn write_metadata(&self, session: &RecordingSession) -> Result<()> {
-        let metadata = serde_json::to_vec(&session.metadata)?;
-        let timestamp_us = session.start_time.duration_since(UNIX_EPOCH)?.as_micros() as u64;
+/// Shared state of the flush workers
+#[derive(Clone)]
+struct FlushContext {
+    sessions: Arc<DashMap<String, Arc<RecordingSession>>>,
+    schema_config: crate::config::SchemaConfig,
+    state_store: Option<Arc<SessionStateStore>>,
+    schema_registry: Arc<SchemaRegistry>,
+    upload_limiter: Arc<UploadLimiter>,
+    topic_stats: Option<Arc<TopicStatsStore>>,
+    status_events: Option<Arc<StatusEventPublisher>>,
+    /// Hooks of the recording events (None = none configured)
+    hooks: Option<Arc<HookRunner>>,
+    /// Background tasks of the process, for held back status events
+    tasks: TaskRegistry,
+    /// Wall clock of the manager
+    clock: Arc<SharedClock>,
+    work_dirs: Option<Arc<WorkDirs>>,
+    /// Holds uploads back outside the configured windows / connectivity
+    upload_gate: Option<Arc<UploadGate>>,
+    coalescer: Option<Arc<Coalescer<CoalescedBatch>>>,
+    /// Spills the records once the shutdown deadline passed
+    shutdown_spill: Arc<ShutdownSpill>,
+    /// Device of this recorder, attached to the flush log spans
+    device_id: String,
+    /// Read-back sampling of uploaded records
+    integrity: crate::config::IntegrityConfig,
+    /// Serializes and compresses batches off the async executor
+    compression_pool: Arc<CompressionPool>,
+    /// Frame size of seekable Zstd batches
+    seekable_frame_bytes: usize,
+    /// Filters and redactions of the samples, by topic
+    sample_transforms: Arc<std::sync::RwLock<SampleTransforms>>,
+    /// Per-topic compression settings, for payload transforms
+    topic_compression: HashMap<String, crate::config::TopicCompression>,
+    /// Timestamp policies, for the per-topic clock offsets
+    timestamps: crate::config::TimestampConfig,
+    /// Unique timestamps of the records written
+    record_timestamps: Arc<RecordTimestamps>,
+    /// Bytes stored per organization and task
+    quotas: Arc<QuotaTracker>,
+    max_record_size_bytes: Arc<AtomicUsize>,
+    /// Original topic of each remapped topic, keyed by the recorded name
+    original_topics: Arc<HashMap<String, String>>,
+    /// Interval of upload progress events (None = off)
+    progress_interval: Option<Duration>,
+    /// Attachment keys copied to the record labels
+    attachment_labels: Arc<Vec<String>>,
+    /// Write an index record after every stored batch
+    index: bool,
+}
 
-        let mut labels = HashMap::new();
-        labels.insert("recording_id".to_string(), session.recording_id.clone());
-        labels.insert("device_id".to_string(), session.metadata.device_id.clone());
-        if let Some(scene) = &session.metadata.scene {
-            labels.insert("scene".to_string(), scene.clone());
+/// Builds a `RecorderManager` for applications embedding the recorder
+///
+/// Only the configuration is required. The storage backend defaults to the
+/// one configured in `config.storage` and is initialized by `build`. Without
+/// a Zenoh session, the manager opens an isolated one (no scouting, listeners
+/// or connections), and samples only come from `ingest_sample`.
+pub struct RecorderManagerBuilder {
+    config: RecorderConfig,
+    storage_backend: Option<Arc<dyn StorageBackend>>,
+    session: Option<Arc<Session>>,
+    config_source: Option<ConfigSource>,
+    log_level: Option<LogLevel>,
+    sample_transforms: Vec<(String, Arc<dyn SampleTransform>)>,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl RecorderManagerBuilder {
+    pub fn new(config: RecorderConfig) -> Self {
+        Self {
+            config,
+            storage_backend: None,
+            session: None,
+            config_source: None,
+            log_level: None,
+            sample_transforms: Vec::new(),
+            clock: None,
         }
+    }
 
-        self.storage_backend
-            .write_with_retry("recordings_metadata", timestamp_us, metadata, labels, 3)
-            .await
+    /// Subscribe to the recorded topics on `session`
+    pub fn session(mut self, session: Arc<Session>) -> Self {
+        self.session = Some(session);
+        self
     }
 
-    /// Start flush worker threads
-    fn start_flush_workers(&self) {
-        let worker_count = self.config.recorder.workers.flush_workers;
-        for i in 0..worker_count {
-            let flush_queue = self.flush_queue.clone();
-            let storage_backend = self.storage_backend.clone();
-            let sessions = self.sessions.clone();
-            let schema_config = self.config.recorder.schema.clone();
-
-            tokio::spawn(async move {
-                debug!("Flush worker {} started", i);
-                loop {
-                    if let Some(task) = flush_queue.pop() {
-                        Self::process_flush_task(
-                            task,
-                            storage_backend.clone(),
-                            sessions.clone(),
-                            schema_config.clone(),
-                        )
-                        .await;
-                    } else {
-                        tokio::time::sleep(Duration::from_millis(100)).await;
-                    }
-                }
-            });
-        }
+    /// Write to `storage_backend` instead of the configured backend
+    pub fn storage_backend(mut self, storage_backend: Arc<dyn StorageBackend>) -> Self {
+        self.storage_backend = Some(storage_backend);
+        self
     }
 
-    /// Process a flush task
-    async fn process_flush_task(
-        task: FlushTask,
-        storage_backend: Arc<dyn StorageBackend>,
-        sessions: Arc<DashMap<String, Arc<RecordingSession>>>,
-        schema_config: crate::config::SchemaConfig,
-    ) {
-        debug!(
-            "Processing flush task for topic '{}' ({} samples)",
-            task.topic,
-            task.samples.len()
-        );
+    /// File the configuration is re-read from on `reload`
+    pub fn config_source(mut self, source: ConfigSource) -> Self {
+        self.config_source = Some(source);
+        self
+    }
 
-        let session = match sessions.get(&task.recording_id) {
-            Some(s) => s,
-            None => {
-                warn!(
-                    "Recording session '{}' not found, dropping flush task",
-                    task.recording_id
-                );
-                return;
-            }
-        };
+    /// Handle of the installed log subscriber, for log level reloads
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.log_level = Some(log_level);
+        self
+    }
 
-        // Serialize to MCAP
-        let serializer = McapSerializer::with_schema_config(
-            session.compression_type,
-            session.compression_level,
-            schema_config,
-        );
-        let mcap_data =
-            match serializer.serialize_batch(&task.topic, task.samples, &task.recording_id) {
-                Ok(data) => data,
-                Err(e) => {
-                    error!("Failed to serialize MCAP data: {}", e);
-                    return;
-                }
-            };
+    /// Filter or redact the samples of the topics matching `pattern` with
+    /// `transform`, after the transforms configured for them
+    #[allow(dead_code)]
+    pub fn sample_transform(
+        mut self,
+        pattern: impl Into<String>,
+        transform: Arc<dyn SampleTransform>,
+    ) -> Self {
+        self.sample_transforms.push((pattern.into(), transform));
+        self
+    }
 
-        // Upload to storage backend
-        let entry_name = topic_to_entry_name(&task.topic);
-        let timestamp_us = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_micros() as u64;
+    /// Read the time from `clock` instead of the system clock
+    #[allow(dead_code)]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 
-        let mut labels = HashMap::new();
-        labels.insert("recording_id".to_string(), task.recording_id.clone());
-        labels.insert("topic".to_string(), task.topic.clone());
-        labels.insert("format".to_string(), "mcap".to_string());
+    /// Initialize the storage backend and start the manager's workers
+    pub async fn build(self) -> Result<RecorderManager> {
+        let storage_backend = match self.storage_backend {
+            Some(storage_backend) => storage_backend,
+            None => BackendFactory::create(&self.config.storage)?,
+        };
 
-        match storage_backend
-            .write_with_retry(&entry_name, timestamp_us, mcap_data, labels, 3)
-            .await
-        {
-            Ok(_) => {
-                debug!(
-                    "Successfully uploaded flush task for topic '{}'",
-                    task.topic
-                );
-            }
-            Err(e) => {
-                error!(
-                    "Failed to upload flush task for topic '{}': {}",
-                    task.topic, e
-                );
-            }
+        let session = match self.session {
+            Some(session) => session,
+            None => Arc::new(isolated_session().await?),
+        };
+
+        // Initialized once the manager gave it its task registry
+        let mut manager = RecorderManager::new(session, storage_backend.clone(), self.config);
+        if let Err(e) = storage_backend.initialize().await {
+            manager.tasks.abort_all();
+            return Err(e);
+        }
+        if let Some(source) = self.config_source {
+            manager = manager.with_config_source(source);
         }
+        if let Some(log_level) = self.log_level {
+            manager = manager.with_log_level(log_level);
+        }
+        for (pattern, transform) in self.sample_transforms {
+            manager = manager.with_sample_transform(&pattern, transform);
+        }
+        if let Some(clock) = self.clock {
+            manager = manager.with_clock(clock);
+        }
+        Ok(manager)
     }
+}
 
-    /// Shutdown recorder manager
-    pub async fn shutdown(&self) -> Result<()> {
-        info!("Shutting down recorder manager");
+/// Zenoh session that reaches no other node
+async fn isolated_session() -> Result<Session> {
+    let mut config = Config::default();
+    for (key, value) in [
+        ("mode", "\"peer\""),
+        ("listen/endpoints", "[]"),
+        ("connect/endpoints", "[]"),
+        ("scouting/multicast/enabled", "false"),
+        ("scouting/gossip/enabled", "false"),
+    ] {
+        config
+            .insert_json5(key, value)
+            .map_err(|e| RecorderError::config(e.to_string()))?;
+    }
+    zenoh::open(config).await.map_err(RecorderError::zenoh)
+}
 
-        // Finish all active recordings
-        let recording_ids: Vec<String> = self.sessions.iter().map(|e| e.key().clone()).collect();
-        for recording_id in recording_ids {
-            let response = self.finish_recording(&recording_id).await;
-            if !response.success {
+/// Recorder manager handles all recording sessions
+pub struct RecorderManager {
+    /// Replaced when the session supervisor reopens the Zenoh session
+    session: std::sync::RwLock<Arc<Session>>,
+    sessions: Arc<DashMap<String, Arc<RecordingSession>>>,
+    /// Zenoh subscribers shared by the recordings of the same topic
+    subscription_hub: SubscriptionHub,
+    /// Rings of the `black_box` topics, stored on `dump`
+    black_box: BlackBox,
+    storage_backend: Arc<dyn StorageBackend>,
+    /// Initialized backends of the buckets requested on Start
+    buckets: Arc<DashMap<String, Arc<dyn StorageBackend>>>,
+    flush_pool: Arc<FlushPool>,
+    compression_pool: Arc<CompressionPool>,
+    record_timestamps: Arc<RecordTimestamps>,
+    quotas: Arc<QuotaTracker>,
+    /// Read per flush task, so transforms added after `new` reach the workers
+    sample_transforms: Arc<std::sync::RwLock<SampleTransforms>>,
+    state_store: Option<Arc<SessionStateStore>>,
+    schema_registry: Arc<SchemaRegistry>,
+    upload_limiter: Arc<UploadLimiter>,
+    topic_stats: Option<Arc<TopicStatsStore>>,
+    status_events: Option<Arc<StatusEventPublisher>>,
+    /// Liveliness token and heartbeats (None = `health` disabled)
+    health: Option<Arc<HealthAnnouncer>>,
+    /// Re-publishes tapped samples (None = `tap.publish` disabled)
+    tap_publisher: Option<Arc<TapPublisher>>,
+    /// Wall clock of the manager and its components (`with_clock`)
+    clock: Arc<SharedClock>,
+    hooks: Option<Arc<HookRunner>>,
+    /// Uploads finished recordings to the ingestion API (None = disabled)
+    ingestion: Option<Arc<IngestionClient>>,
+    work_dirs: Option<Arc<WorkDirs>>,
+    upload_gate: Option<Arc<UploadGate>>,
+    /// Collects small batches across topics (None = `coalesce` disabled)
+    coalescer: Option<Arc<Coalescer<CoalescedBatch>>>,
+    shutdown_spill: Arc<ShutdownSpill>,
+    /// Set once `shutdown` started, so the last flushes are told apart
+    shutting_down: AtomicBool,
+    /// Background tasks of the process (recordings have their own)
+    tasks: TaskRegistry,
+    /// Tick the recorder's timers are aligned to (low-power mode)
+    wakeup_granularity: Option<Duration>,
+    /// Signalled once the active recordings were handed off to a successor
+    handed_off: Notify,
+    /// Held by Starts with a client-supplied ID or a quota, so retries and
+    /// concurrent Starts of a scope don't race
+    start_lock: Mutex<()>,
+    /// Configuration the recorder was started with
+    config: RecorderConfig,
+    /// Last loaded configuration; its live settings are the effective ones
+    live_config: std::sync::RwLock<RecorderConfig>,
+    max_record_size_bytes: Arc<AtomicUsize>,
+    config_source: Option<ConfigSource>,
+    log_level: Option<LogLevel>,
+}
+
+impl RecorderManager {
+    /// Create a new RecorderManager with configuration
+    pub fn new(
+        session: Arc<Session>,
+        storage_backend: Arc<dyn StorageBackend>,
+        mut config: RecorderConfig,
+    ) -> Self {
+        config.recorder.apply_low_power();
+        let wakeup_granularity = config.recorder.low_power.wakeup_granularity();
+        if config.recorder.low_power.enabled {
+            info!(
+                "Low-power mode: flushing every {}s, timers aligned to {:?}",
+                config.recorder.flush_policy.max_buffer_duration_seconds, wakeup_granularity
+            );
+        }
+
+        let flush_pool = Arc::new(FlushPool::new(
+            config.recorder.workers.flush_workers,
+            config.recorder.workers.queue_capacity,
+        ));
+        let compression_pool = Arc::new(CompressionPool::new(
+            config.recorder.workers.compression_threads,
+        ));
+        let record_timestamps = Arc::new(RecordTimestamps::new(record_timestamps::strategy(
+            config.recorder.timestamps.collision_strategy,
+        )));
+        let state_store = config
+            .recorder
+            .recovery
+            .enabled
+            .then(|| Arc::new(SessionStateStore::new(&config.recorder.recovery.state_dir)));
+
+        let schema_registry = Arc::new(SchemaRegistry::new());
+        match schema_registry.load_paths(&config.recorder.schema.descriptor_sets) {
+            Ok(0) => {}
+            Ok(count) => info!("Loaded {} protobuf message types", count),
+            Err(e) => warn!("Failed to load protobuf descriptor sets: {:#}", e),
+        }
+
+        let upload_limiter = Arc::new(UploadLimiter::new(UploadLimit {
+            max_bytes_per_sec: config.recorder.upload_limit.max_bytes_per_sec,
+            max_concurrent_uploads: config.recorder.upload_limit.max_concurrent_uploads,
+        }));
+
+        let topic_stats = config.recorder.topic_stats.enabled.then(|| {
+            let stats_config = &config.recorder.topic_stats;
+            let store = TopicStatsStore::new(
+                &stats_config.path,
+                stats_config.smoothing,
+                stats_config.incompressible_ratio,
+            );
+            match store.load() {
+                Ok(0) => {}
+                Ok(count) => info!("Loaded statistics of {} topics", count),
+                Err(e) => warn!("Failed to load topic statistics: {:#}", e),
+            }
+            Arc::new(store)
+        });
+
+        let events_config = &config.recorder.status_events;
+        let status_events = events_config.enabled.then(|| {
+            Arc::new(StatusEventPublisher::new(
+                session.clone(),
+                format!("{}/{}", events_config.key_prefix, config.recorder.device_id),
+                events_config.max_per_sec,
+            ))
+        });
+
+        let tasks = TaskRegistry::with_events(PROCESS_SCOPE, status_events.clone());
+        storage_backend.set_tasks(&tasks);
+
+        let health_config = &config.recorder.health;
+        let health = health_config.enabled.then(|| {
+            let health = Arc::new(HealthAnnouncer::new(
+                session.clone(),
+                health_config,
+                &config.recorder.device_id,
+            ));
+            health.declare(session.clone());
+            health
+        });
+
+        let tap_config = &config.recorder.tap;
+        let tap_publisher = tap_config.publish.then(|| {
+            Arc::new(TapPublisher::new(
+                session.clone(),
+                tap_config.key_prefix.clone(),
+            ))
+        });
+
+        let work_dir_config = &config.recorder.work_dir;
+        let work_dirs = work_dir_config.enabled.then(|| {
+            Arc::new(WorkDirs::new(
+                &work_dir_config.path,
+                work_dir_config.retain_failed(),
+            ))
+        });
+
+        let deferral_config = &config.recorder.upload_deferral;
+        let upload_gate = match deferral_config.enabled {
+            true => match UploadGate::new(deferral_config) {
+                Ok(gate) => {
+                    let gate = Arc::new(gate.with_wakeup_granularity(wakeup_granularity));
+                    tasks.spawn_service(TaskStage::Background, "upload gate", {
+                        let gate = gate.clone();
+                        async move { gate.run().await }
+                    });
+                    Some(gate)
+                }
+                Err(e) => {
+                    warn!("Upload deferral disabled: {:#}", e);
+                    None
+                }
+            },
+            false => None,
+        };
+
+        let clock = Arc::new(SharedClock::new(system_clock()));
+        let subscription_hub = SubscriptionHub::new(session.clone()).with_clock(clock.clone());
+        let black_box = BlackBox::start(&config.recorder.black_box, &subscription_hub, &tasks);
+
+        let manager = Self {
+            subscription_hub,
+            black_box,
+            session: std::sync::RwLock::new(session),
+            sessions: Arc::new(DashMap::new()),
+            storage_backend,
+            buckets: Arc::new(DashMap::new()),
+            flush_pool,
+            compression_pool,
+            record_timestamps,
+            quotas: Arc::new(
+                QuotaTracker::new(config.recorder.quotas.clone()).with_clock(clock.clone()),
+            ),
+            sample_transforms: Arc::new(std::sync::RwLock::new(SampleTransforms::from_config(
+                &config.recorder.transforms,
+            ))),
+            state_store,
+            schema_registry,
+            upload_limiter,
+            topic_stats,
+            status_events,
+            health,
+            tap_publisher,
+            clock,
+            hooks: (!config.recorder.hooks.is_empty()).then(|| {
+                Arc::new(HookRunner::new(
+                    config.recorder.hooks.clone(),
+                    tasks.clone(),
+                ))
+            }),
+            ingestion: config
+                .recorder
+                .ingestion
+                .enabled
+                .then(|| Arc::new(IngestionClient::new(config.recorder.ingestion.clone()))),
+            work_dirs,
+            upload_gate,
+            coalescer: config.recorder.coalesce.enabled.then(|| {
+                let coalesce = &config.recorder.coalesce;
+                Arc::new(Coalescer::new(
+                    coalesce.max_batch_bytes,
+                    coalesce.max_record_bytes,
+                    coalesce.interval(),
+                ))
+            }),
+            shutdown_spill: Arc::new(ShutdownSpill::new(
+                config.recorder.workers.shutdown_timeout(),
+            )),
+            shutting_down: AtomicBool::new(false),
+            tasks,
+            wakeup_granularity,
+            handed_off: Notify::new(),
+            start_lock: Mutex::new(()),
+            live_config: std::sync::RwLock::new(config.clone()),
+            max_record_size_bytes: Arc::new(AtomicUsize::new(
+                config.recorder.flush_policy.max_record_size_bytes,
+            )),
+            config_source: None,
+            log_level: None,
+            config,
+        };
+
+        // Start flush worker threads
+        manager.start_flush_workers();
+        manager.start_heartbeat();
+        manager.start_expiry_sweep();
+
+        manager
+    }
+
+    /// Remove expired records every `expiry.sweep_interval_seconds`
+    ///
+    /// Sweeps the configured bucket and the buckets requested since the
+    /// recorder started, by the time of the manager clock.
+    fn start_expiry_sweep(&self) {
+        let Some(interval) = self.config.recorder.expiry.sweep_interval() else {
+            return;
+        };
+        let backend = self.storage_backend.clone();
+        let buckets = self.buckets.clone();
+        let clock = self.clock.clone();
+        self.tasks
+            .spawn_service(TaskStage::Background, "expiry sweep", async move {
+                loop {
+                    let now_secs = clock.since_epoch().as_secs();
+                    let mut backends = vec![backend.clone()];
+                    backends.extend(buckets.iter().map(|bucket| bucket.value().clone()));
+                    for backend in backends {
+                        match backend.remove_expired(now_secs).await {
+                            Ok(0) => {}
+                            Ok(removed) => info!(
+                                "Removed {} expired record(s) from bucket '{}'",
+                                removed,
+                                backend.bucket().unwrap_or("default")
+                            ),
+                            Err(e) => warn!("Failed to remove expired records: {}", e),
+                        }
+                    }
+                    runtime::sleep(interval).await;
+                }
+            });
+    }
+
+    /// Publish heartbeats every `health.heartbeat_interval_ms`
+    fn start_heartbeat(&self) {
+        let (Some(health), Some(interval)) = (
+            self.health.clone(),
+            self.config.recorder.health.heartbeat_interval(),
+        ) else {
+            return;
+        };
+        let backend = self.storage_backend.clone();
+        let sessions = self.sessions.clone();
+        self.tasks
+            .spawn_service(TaskStage::Background, "heartbeat", async move {
+                loop {
+                    let recordings: Vec<_> = sessions.iter().map(|e| e.value().clone()).collect();
+                    let mut active_recordings = 0;
+                    for recording in recordings {
+                        if matches!(
+                            *recording.status.read().await,
+                            RecordingStatus::Recording | RecordingStatus::Paused
+                        ) {
+                            active_recordings += 1;
+                        }
+                    }
+                    let heartbeat = health
+                        .heartbeat(backend.as_ref(), active_recordings, interval)
+                        .await;
+                    health.publish(&heartbeat);
+                    runtime::sleep(interval).await;
+                }
+            });
+    }
+
+    /// Current Zenoh session
+    fn zenoh_session(&self) -> Arc<Session> {
+        self.session.read().unwrap().clone()
+    }
+
+    /// Zenoh subscribers of the active recordings (for monitoring and tests)
+    #[allow(dead_code)]
+    pub fn subscription_hub(&self) -> &SubscriptionHub {
+        &self.subscription_hub
+    }
+
+    /// Append `transform` to the sample transforms of the topics matching
+    /// `pattern`, after the configured ones
+    pub fn with_sample_transform(self, pattern: &str, transform: Arc<dyn SampleTransform>) -> Self {
+        self.sample_transforms
+            .write()
+            .unwrap()
+            .add(pattern, transform);
+        self
+    }
+
+    /// File the configuration is re-read from on `reload`
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        self.config_source = Some(source);
+        self
+    }
+
+    /// Handle of the installed log subscriber, for log level reloads
+    pub fn with_log_level(mut self, log_level: LogLevel) -> Self {
+        self.log_level = Some(log_level);
+        self
+    }
+
+    /// Read the time from `clock` instead of the system clock
+    #[allow(dead_code)]
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.clock.set(clock);
+        self
+    }
+
+    /// Record a sample that didn't arrive over Zenoh
+    ///
+    /// `payload` goes to every active recording of a topic matching `topic`,
+    /// stored at `timestamp_ns` (ns since the epoch) whatever the timestamp
+    /// policy. Returns the number of recordings that took it.
+    #[allow(dead_code)]
+    pub async fn ingest_sample(
+        &self,
+        topic: &str,
+        payload: impl Into<Vec<u8>>,
+        timestamp_ns: u64,
+    ) -> Result<usize> {
+        let key_expr = KeyExpr::try_from(topic.trim_start_matches('/').to_string())
+            .map_err(RecorderError::zenoh)?;
+        let sample: Sample = SampleBuilder::put(key_expr, payload.into()).into();
+
+        let mut recordings = 0;
+        for session in self.session_list() {
+            if session.ingest(topic, &sample, Some(timestamp_ns)).await > 0 {
+                recordings += 1;
+            }
+        }
+        Ok(recordings)
+    }
+
+    /// Fetch protobuf descriptor sets from the configured Zenoh queryable
+    ///
+    /// Returns the number of message types added (0 when no key is configured).
+    pub async fn fetch_schemas(&self) -> Result<usize> {
+        let schema_config = &self.config.recorder.schema;
+        match &schema_config.descriptor_key {
+            Some(key_expr) => self
+                .schema_registry
+                .fetch_from_zenoh(
+                    &self.zenoh_session(),
+                    key_expr,
+                    Duration::from_millis(schema_config.descriptor_timeout_ms),
+                )
+                .await
+                .map_err(RecorderError::zenoh),
+            None => Ok(0),
+        }
+    }
+
+    /// Snapshot of all sessions (avoids holding map guards across awaits)
+    fn session_list(&self) -> Vec<Arc<RecordingSession>> {
+        self.sessions.iter().map(|e| e.value().clone()).collect()
+    }
+
+    /// Describe the active recordings for a successor recorder
+    pub async fn handoff_offer(&self) -> HandoffOffer {
+        let mut sessions = Vec::new();
+        for session in self.session_list() {
+            if !matches!(
+                *session.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                continue;
+            }
+
+            sessions.push(HandoffSession {
+                recording_id: session.recording_id.clone(),
+                request: session.start_request().await,
+                state: Some(session.to_state().await),
+            });
+        }
+
+        HandoffOffer {
+            device_id: self.config.recorder.device_id.clone(),
+            sessions,
+        }
+    }
+
+    /// Finish recordings taken over by a successor (predecessor side)
+    ///
+    /// Each finished recording is linked to its successor with the overlap
+    /// `[ready.ready_at, finished_at]` in its metadata. In `continue` mode the
+    /// recordings are drained instead.
+    pub async fn complete_handoff(&self, ready: HandoffReady) -> HandoffAck {
+        if ready.mode == HandoffMode::Continue {
+            return self.drain_handoff(ready).await;
+        }
+        let finished_at = rfc3339(self.clock.now());
+        let mut finished = Vec::new();
+
+        for mapping in &ready.mappings {
+            let Some(session) = self
+                .sessions
+                .get(&mapping.predecessor)
+                .map(|s| s.value().clone())
+            else {
+                warn!(
+                    "Handoff for unknown recording '{}' ignored",
+                    mapping.predecessor
+                );
+                continue;
+            };
+
+            session
+                .handoff
+                .write()
+                .await
+                .get_or_insert_with(HandoffInfo::default)
+                .successor = Some(HandoffOverlap {
+                recording_id: mapping.successor.clone(),
+                overlap_start: ready.ready_at.clone(),
+                overlap_end: Some(finished_at.clone()),
+            });
+
+            let response = self.finish_recording(&mapping.predecessor).await;
+            if response.success {
+                info!(
+                    "Recording '{}' handed off to '{}'",
+                    mapping.predecessor, mapping.successor
+                );
+                finished.push(mapping.predecessor.clone());
+            } else {
+                error!(
+                    "Failed to finish handed-off recording '{}': {}",
+                    mapping.predecessor, response.message
+                );
+            }
+        }
+
+        let success = finished.len() == ready.mappings.len();
+        if success {
+            self.handed_off.notify_one();
+        }
+
+        HandoffAck {
+            success,
+            message: format!(
+                "Finished {} of {} recording(s)",
+                finished.len(),
+                ready.mappings.len()
+            ),
+            finished,
+            finished_at,
+            states: vec![],
+        }
+    }
+
+    /// Drain recordings continued by a successor (predecessor side)
+    ///
+    /// Each recording stops receiving samples and its buffers are flushed and
+    /// written. It is neither finished nor is its state file removed: the
+    /// successor goes on with both under the same recording_id. The final
+    /// state of each recording is returned for the successor to merge.
+    async fn drain_handoff(&self, ready: HandoffReady) -> HandoffAck {
+        let timeout = Duration::from_secs(self.config.recorder.handoff.timeout_seconds);
+        let mut finished = Vec::new();
+        let mut states = Vec::new();
+
+        for mapping in &ready.mappings {
+            let Some(session) = self
+                .sessions
+                .get(&mapping.predecessor)
+                .map(|s| s.value().clone())
+            else {
+                warn!(
+                    "Handoff for unknown recording '{}' ignored",
+                    mapping.predecessor
+                );
+                continue;
+            };
+
+            match self.drain_session(&session, timeout).await {
+                Ok(state) => {
+                    info!(
+                        "Recording '{}' drained and continued by the successor",
+                        mapping.predecessor
+                    );
+                    finished.push(mapping.predecessor.clone());
+                    states.push(state);
+                }
+                Err(e) => error!(
+                    "Failed to drain handed-off recording '{}': {}",
+                    mapping.predecessor, e
+                ),
+            }
+        }
+
+        let success = finished.len() == ready.mappings.len();
+        if success {
+            self.handed_off.notify_one();
+        }
+
+        HandoffAck {
+            success,
+            message: format!(
+                "Drained {} of {} recording(s)",
+                finished.len(),
+                ready.mappings.len()
+            ),
+            finished,
+            finished_at: rfc3339(self.clock.now()),
+            states,
+        }
+    }
+
+    /// Stop a recording and write its buffered samples, then hand it over
+    ///
+    /// Records still being written after `timeout` are left out of the
+    /// returned state. The recording is dropped from this recorder.
+    async fn drain_session(
+        &self,
+        session: &RecordingSession,
+        timeout: Duration,
+    ) -> Result<SessionState> {
+        {
+            let mut status = session.status.write().await;
+            if !matches!(
+                *status,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                return Err(RecorderError::InvalidState(format!(
+                    "Recording is {:?}, not in Recording or Paused state",
+                    *status
+                )));
+            }
+            *status = RecordingStatus::Uploading;
+        }
+        if let Some(controller) = &session.controller {
+            controller.stop();
+        }
+        Self::stop_subscribers(session);
+        Self::flush_remaining(session, FlushReason::Forced).await;
+
+        let deadline = Instant::now() + timeout;
+        while self.flush_pool.pending_tasks(&session.recording_id) > 0 && Instant::now() < deadline
+        {
+            runtime::sleep(Duration::from_millis(100)).await;
+        }
+        self.store_pending_coalesced(session).await;
+        while self.flush_pool.pending_tasks(&session.recording_id) > 0
+            || self.has_deferred(session).await
+        {
+            if Instant::now() >= deadline {
+                warn!(
+                    "Recording '{}' handed over with records still being written",
+                    session.recording_id
+                );
+                break;
+            }
+            runtime::sleep(Duration::from_millis(100)).await;
+        }
+
+        let state = session.to_state().await;
+        self.sessions.remove(&session.recording_id);
+        session.tasks.abort_all();
+        session.completed.notify_waiters();
+        Ok(state)
+    }
+
+    /// Wait until the active recordings were handed off to a successor
+    pub async fn handed_off(&self) {
+        self.handed_off.notified().await;
+    }
+
+    /// Take over the active recordings of a running recorder (successor side)
+    ///
+    /// Every offered recording is restarted under a new recording_id, or in
+    /// `continue` mode rebuilt from the offered state under its recording_id.
+    /// Once all subscriptions are declared the predecessor is told to finish
+    /// (or drain) its recordings, so samples between the two points are
+    /// recorded twice rather than lost. Returns the ids of the new recordings.
+    pub async fn take_over(&self) -> Result<Vec<String>> {
+        let key = format!("{}/{}", HANDOFF_KEY_PREFIX, self.config.recorder.device_id);
+        let timeout = Duration::from_secs(self.config.recorder.handoff.timeout_seconds);
+
+        let Some(offer) = self
+            .handoff_query::<HandoffOffer>(&format!("{}/offer", key), None, timeout)
+            .await?
+        else {
+            info!("No running recorder to take over from");
+            return Ok(Vec::new());
+        };
+
+        let mut mode = self.config.recorder.handoff.mode;
+        if mode == HandoffMode::Continue && offer.sessions.iter().any(|s| s.state.is_none()) {
+            warn!("Predecessor did not offer the state of its recordings, restarting them");
+            mode = HandoffMode::Split;
+        }
+
+        let mut mappings = Vec::new();
+        let mut offered_states = HashMap::new();
+        for offered in offer.sessions {
+            if let (HandoffMode::Continue, Some(state)) = (mode, offered.state) {
+                match self.continue_recording(state.clone()).await {
+                    Ok(()) => {
+                        mappings.push(HandoffMapping {
+                            predecessor: offered.recording_id.clone(),
+                            successor: offered.recording_id.clone(),
+                        });
+                        offered_states.insert(offered.recording_id, state);
+                    }
+                    Err(e) => error!(
+                        "Failed to continue recording '{}': {}",
+                        offered.recording_id, e
+                    ),
+                }
+                continue;
+            }
+
+            let response = self.start_recording(offered.request).await;
+            match response.recording_id {
+                Some(successor) if response.success => mappings.push(HandoffMapping {
+                    predecessor: offered.recording_id,
+                    successor,
+                }),
+                _ => error!(
+                    "Failed to take over recording '{}': {}",
+                    offered.recording_id, response.message
+                ),
+            }
+        }
+
+        // Ready once every topic of every new recording is subscribed
+        let deadline = Instant::now() + timeout;
+        for mapping in &mappings {
+            let Some(session) = self
+                .sessions
+                .get(&mapping.successor)
+                .map(|s| s.value().clone())
+            else {
+                continue;
+            };
+            while session.subscribed_topics.load(Ordering::SeqCst) < session.metadata.topics.len() {
+                if Instant::now() >= deadline {
+                    return Err(RecorderError::zenoh(format!(
+                        "Timed out waiting for subscriptions of recording '{}'",
+                        mapping.successor
+                    )));
+                }
+                runtime::sleep(Duration::from_millis(10)).await;
+            }
+        }
+
+        let ready = HandoffReady {
+            mappings: mappings.clone(),
+            ready_at: rfc3339(self.clock.now()),
+            mode,
+        };
+        for mapping in &mappings {
+            if let Some(session) = self
+                .sessions
+                .get(&mapping.successor)
+                .map(|s| s.value().clone())
+            {
+                *session.handoff.write().await = Some(HandoffInfo {
+                    predecessor: Some(HandoffOverlap {
+                        recording_id: mapping.predecessor.clone(),
+                        overlap_start: ready.ready_at.clone(),
+                        overlap_end: None,
+                    }),
+                    successor: None,
+                });
+            }
+        }
+
+        let ack = self
+            .handoff_query::<HandoffAck>(
+                &format!("{}/ready", key),
+                Some(serde_json::to_vec(&ready).map_err(RecorderError::serialization)?),
+                timeout,
+            )
+            .await?;
+        match ack {
+            Some(ack) => {
+                if !ack.success {
+                    warn!("Handoff partially acknowledged: {}", ack.message);
+                }
+                let mut drained: HashMap<String, SessionState> = ack
+                    .states
+                    .into_iter()
+                    .map(|state| (state.recording_id.clone(), state))
+                    .collect();
+                for mapping in &mappings {
+                    let Some(session) = self
+                        .sessions
+                        .get(&mapping.successor)
+                        .map(|s| s.value().clone())
+                    else {
+                        continue;
+                    };
+                    if ack.finished.contains(&mapping.predecessor) {
+                        if let Some(predecessor) = session
+                            .handoff
+                            .write()
+                            .await
+                            .as_mut()
+                            .and_then(|h| h.predecessor.as_mut())
+                        {
+                            predecessor.overlap_end = Some(ack.finished_at.clone());
+                        }
+                    }
+                    if let (Some(offered), Some(drained)) = (
+                        offered_states.get(&mapping.successor),
+                        drained.remove(&mapping.predecessor),
+                    ) {
+                        session.merge_drained(offered, drained).await;
+                    }
+                    self.persist_state(&session).await;
+                }
+            }
+            None => {
+                warn!("Predecessor did not acknowledge the handoff; both recorders keep recording")
+            }
+        }
+
+        Ok(mappings.into_iter().map(|m| m.successor).collect())
+    }
+
+    /// Continue a predecessor's recording from its state (`continue` handoff)
+    async fn continue_recording(&self, state: SessionState) -> Result<()> {
+        if self.sessions.contains_key(&state.recording_id) {
+            return Err(RecorderError::InvalidState(format!(
+                "Recording '{}' already exists",
+                state.recording_id
+            )));
+        }
+        self.resume_session(state).await?;
+        Ok(())
+    }
+
+    /// Publishers and connected nodes to store with a new recording
+    ///
+    /// None when `recorder.topology.snapshot` is off or the snapshot failed;
+    /// a failed snapshot doesn't keep the recording from starting.
+    async fn topology_snapshot(&self, topics: &[String]) -> Option<TopologySnapshot> {
+        let settings = &self.config.recorder.topology;
+        if !settings.snapshot {
+            return None;
+        }
+        match topology::snapshot(&self.zenoh_session(), topics, settings.timeout()).await {
+            Ok(snapshot) => {
+                debug!(
+                    "Topology snapshot: {} publisher(s), {} session(s)",
+                    snapshot.publishers.len(),
+                    snapshot.sessions.len()
+                );
+                Some(snapshot)
+            }
+            Err(e) => {
+                warn!("Failed to take topology snapshot: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Send a handoff query and decode the first successful reply
+    async fn handoff_query<T: DeserializeOwned>(
+        &self,
+        selector: &str,
+        payload: Option<Vec<u8>>,
+        timeout: Duration,
+    ) -> Result<Option<T>> {
+        let session = self.zenoh_session();
+        let mut builder = session.get(selector).timeout(timeout);
+        // The predecessor checks these when control.auth is enabled
+        let auth = ControlAuth::new(&self.config.recorder.control.auth);
+        let credentials = auth.handoff_credentials(
+            &self.config.recorder.device_id,
+            payload.as_deref().unwrap_or_default(),
+        );
+        if let Some(credentials) = credentials {
+            builder = builder.attachment(
+                serde_json::to_vec(&credentials).map_err(RecorderError::serialization)?,
+            );
+        }
+        if let Some(payload) = payload {
+            builder = builder.payload(payload);
+        }
+        let replies = builder.await.map_err(|e| {
+            RecorderError::zenoh(format!("Handoff query on '{}' failed: {}", selector, e))
+        })?;
+
+        while let Ok(reply) = replies.recv_async().await {
+            match reply.result() {
+                Ok(sample) => {
+                    return serde_json::from_slice(&sample.payload().to_bytes())
+                        .map(Some)
+                        .map_err(RecorderError::serialization)
+                }
+                Err(e) => warn!("Handoff query on '{}' returned an error: {:?}", selector, e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Change the backend upload limits for all recordings
+    pub fn set_upload_limit(&self, limit: UploadLimit) -> RecorderResponse {
+        let previous = self.upload_limiter.limit();
+        self.upload_limiter.set_limit(limit);
+        info!(
+            "Upload limit changed from {} to {} bytes/s, {} to {} concurrent uploads (0 = unlimited)",
+            previous.max_bytes_per_sec,
+            limit.max_bytes_per_sec,
+            previous.max_concurrent_uploads,
+            limit.max_concurrent_uploads
+        );
+        RecorderResponse {
+            success: true,
+            message: format!(
+                "Upload limit set to {} bytes/s, {} concurrent uploads",
+                limit.max_bytes_per_sec, limit.max_concurrent_uploads
+            ),
+            recording_id: None,
+            bucket_name: None,
+            status: None,
+            errors: vec![],
+            error_code: None,
+        }
+    }
+
+    /// Compression of Start requests that don't choose one
+    pub fn compression_defaults(&self) -> (CompressionType, CompressionLevel) {
+        let live = self.live_config.read().unwrap();
+        let compression = &live.recorder.compression;
+        (
+            CompressionType::from_name(&compression.default_type).unwrap_or_default(),
+            CompressionLevel::from_index(compression.default_level).unwrap_or_default(),
+        )
+    }
+
+    /// Re-read the configuration file and apply the settings that can change live
+    ///
+    /// Flush policy (new and active buffers), compression defaults (later
+    /// Start requests), upload limits and log level take effect immediately;
+    /// other changes are reported as requiring a restart.
+    pub fn reload_config(&self) -> ReloadResponse {
+        let Some(source) = &self.config_source else {
+            return ReloadResponse {
+                success: false,
+                message: "No configuration file to reload".to_string(),
+                ..Default::default()
+            };
+        };
+        let config = match source.load() {
+            Ok(config) => config,
+            Err(e) => {
+                return ReloadResponse {
+                    success: false,
+                    message: format!("Failed to reload configuration: {}", e),
+                    ..Default::default()
+                }
+            }
+        };
+
+        let new = serde_json::to_value(&config).unwrap_or_default();
+        let mut live = self.live_config.write().unwrap();
+
+        let mut applied = Vec::new();
+        let previous = serde_json::to_value(&*live).unwrap_or_default();
+        changed_settings(&previous, &new, "", &mut applied);
+        applied.retain(|path| is_live_setting(path));
+
+        let mut restart_required = Vec::new();
+        let startup = serde_json::to_value(&self.config).unwrap_or_default();
+        changed_settings(&startup, &new, "", &mut restart_required);
+        restart_required.retain(|path| !is_live_setting(path));
+
+        let changed = |section: &str| applied.iter().any(|path| path.starts_with(section));
+        if changed("recorder.flush_policy") {
+            self.max_record_size_bytes.store(
+                config.recorder.flush_policy.max_record_size_bytes,
+                Ordering::Relaxed,
+            );
+            for session in self.sessions.iter() {
+                let flush_policy = config
+                    .recorder
+                    .effective_flush_policy(session.metadata.buffer_strategy);
+                for buffer in session.topic_buffers.iter() {
+                    buffer.set_flush_limits(
+                        flush_policy.max_buffer_size_bytes,
+                        flush_policy.max_duration(),
+                    );
+                    buffer.set_idle_trim(flush_policy.idle_trim());
+                }
+                for preview in session.previews.iter() {
+                    preview.buffer().set_flush_limits(
+                        flush_policy.max_buffer_size_bytes,
+                        flush_policy.max_duration(),
+                    );
+                }
+            }
+        }
+        if changed("recorder.upload_limit") {
+            let upload_limit = &config.recorder.upload_limit;
+            self.set_upload_limit(UploadLimit {
+                max_bytes_per_sec: upload_limit.max_bytes_per_sec,
+                max_concurrent_uploads: upload_limit.max_concurrent_uploads,
+            });
+        }
+        if changed("logging.level") {
+            if let Some(log_level) = &self.log_level {
+                if let Err(e) = log_level.set(&config.logging.level) {
+                    warn!("{:#}", e);
+                }
+            }
+        }
+        *live = config;
+
+        info!("Configuration reloaded, applied: {:?}", applied);
+        if !restart_required.is_empty() {
+            warn!(
+                "Configuration changes that require a restart: {:?}",
+                restart_required
+            );
+        }
+
+        ReloadResponse {
+            success: true,
+            message: format!(
+                "Applied {} setting(s), {} setting(s) require a restart",
+                applied.len(),
+                restart_required.len()
+            ),
+            applied,
+            restart_required,
+        }
+    }
+
+    /// Start recording
+    ///
+    /// The recording_id is generated by the recorder unless the client supplies
+    /// one; a Start with the ID of a known recording is handled by `if_exists`,
+    /// so retried Starts don't create a second session. Clients receive the ID
+    /// in the response. Invalid requests are rejected with all of their
+    /// problems in `errors`, as are compression levels above the configured
+    /// limits unless `compression.over_limit` lowers them.
+    pub async fn start_recording(&self, request: RecorderRequest) -> RecorderResponse {
+        self.open_recording(request, true, RecordingLinks::default())
+            .await
+    }
+
+    /// Start a recording for the recorders of `group_id`, as requested on
+    /// `recorder/control/group/{group_id}`
+    pub async fn start_group_recording(
+        &self,
+        request: RecorderRequest,
+        group_id: &str,
+    ) -> RecorderResponse {
+        let links = RecordingLinks {
+            group_id: Some(group_id.to_string()),
+            ..Default::default()
+        };
+        self.open_recording(request, true, links).await
+    }
+
+    /// Recording or paused recordings started for `group_id`
+    pub async fn group_recordings(&self, group_id: &str) -> Vec<String> {
+        let sessions: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|s| s.metadata.group_id.as_deref() == Some(group_id))
+            .map(|s| s.value().clone())
+            .collect();
+        let mut recording_ids = Vec::new();
+        for session in sessions {
+            if matches!(
+                *session.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                recording_ids.push(session.recording_id.clone());
+            }
+        }
+        recording_ids
+    }
+
+    /// Create the session of a Start, Dump or Rollover request; `live`
+    /// recordings subscribe to their topics, the others are fed by the caller
+    #[tracing::instrument(skip_all, fields(recording_id, device_id = %self.config.recorder.device_id))]
+    async fn open_recording(
+        &self,
+        mut request: RecorderRequest,
+        live: bool,
+        links: RecordingLinks,
+    ) -> RecorderResponse {
+        let (_, default_level) = self.compression_defaults();
+        let mut errors = validation::validate_start(&request, default_level);
+        let limited = {
+            let live = self.live_config.read().unwrap();
+            validation::limit_compression(&mut request, &live.recorder.compression)
+        };
+        let lowered = match limited {
+            Ok(lowered) => lowered,
+            Err(error) => {
+                errors.push(error);
+                None
+            }
+        };
+        let retention_seconds = match validation::retention(&request, &self.config.recorder.expiry)
+        {
+            Ok(retention_seconds) => retention_seconds,
+            Err(error) => {
+                errors.push(error);
+                None
+            }
+        };
+        if !errors.is_empty() {
+            warn!("Rejected Start request with {} problem(s)", errors.len());
+            return RecorderResponse::invalid(errors);
+        }
+
+        let recording_id = request
+            .recording_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        tracing::Span::current().record("recording_id", recording_id.as_str());
+
+        let quota_scopes = self
+            .quotas
+            .scopes(request.organization.as_deref(), request.task_id.as_deref());
+        let _start_guard = if request.recording_id.is_some() || !quota_scopes.is_empty() {
+            Some(self.start_lock.lock().await)
+        } else {
+            None
+        };
+        let existing = self.sessions.get(&recording_id).map(|s| s.value().clone());
+        if let Some(existing) = existing {
+            match request.if_exists.unwrap_or_default() {
+                IfExists::Error => {
+                    warn!("Recording '{}' already exists", recording_id);
+                    return RecorderResponse::error(
+                        ErrorCode::AlreadyExists,
+                        format!("Recording '{}' already exists", recording_id),
+                    );
+                }
+                IfExists::ReturnExisting => {
+                    let status = *existing.status.read().await;
+                    info!(
+                        "Recording '{}' already exists ({:?}), not starting it again",
+                        recording_id, status
+                    );
+                    return RecorderResponse {
+                        message: format!("Recording '{}' already exists", recording_id),
+                        status: Some(status),
+                        ..RecorderResponse::success(
+                            Some(recording_id),
+                            existing.storage.bucket().map(String::from),
+                        )
+                    };
+                }
+                IfExists::Restart => self.discard_for_restart(&existing).await,
+            }
+        }
+
+        // A rolled over recording makes way for its successor
+        let replaced = links.previous_recording_id.as_deref();
+        if let Some(problem) = self.quota_problem(&quota_scopes, replaced).await {
+            warn!(
+                "Refusing to start recording '{}': {}",
+                recording_id, problem
+            );
+            return RecorderResponse::error(
+                ErrorCode::QuotaExceeded,
+                format!("Refusing to start recording: {}", problem),
+            );
+        }
+
+        info!("Starting recording '{}'", recording_id);
+
+        // Initialize the storage backend of the requested bucket
+        let storage = match self.bucket_backend(request.bucket.as_deref()).await {
+            Ok(storage) => storage,
+            Err(e) => {
+                error!("Failed to initialize storage backend: {}", e);
+                return RecorderResponse::error(
+                    ErrorCode::BackendUnavailable,
+                    format!("Failed to initialize storage: {}", e),
+                );
+            }
+        };
+        if let Err(e) = storage.check_capacity().await {
+            error!("Refusing to start recording: {}", e);
+            return RecorderResponse::error(
+                e.code(),
+                format!("Refusing to start recording: {}", e),
+            );
+        }
+        let topology = self.topology_snapshot(&request.topics).await;
+        let start_time = self.clock.now();
+
+        let metadata = RecordingMetadata {
+            recording_id: recording_id.clone(),
+            scene: request.scene.clone(),
+            skills: request.skills.clone(),
+            organization: request.organization.clone(),
+            task_id: request.task_id.clone(),
+            device_id: request.device_id.clone(),
+            data_collector_id: request.data_collector_id.clone(),
+            topics: request.topics.clone(),
+            compression_type: format!("{:?}", request.compression_type),
+            compression_level: request.compression_level as i32,
+            start_time: rfc3339(start_time),
+            end_time: None,
+            total_bytes: 0,
+            total_samples: 0,
+            payload_bytes: 0,
+            compression_ratio: None,
+            per_topic_stats: serde_json::json!({}),
+            interrupted: false,
+            schema_drift: vec![],
+            handoff: None,
+            previous_recording_id: links.previous_recording_id,
+            group_id: links.group_id,
+            topic_changes: vec![],
+            session_gaps: vec![],
+            records: vec![],
+            verify_failures: 0,
+            records_verified: 0,
+            bucket: request.bucket.clone(),
+            controller_liveliness: request.controller_liveliness.clone(),
+            priority: request.priority,
+            topic_aliases: self.topic_aliases(&request.topics),
+            previews: BTreeMap::new(),
+            formats: BTreeMap::new(),
+            topology,
+            environment: request.environment.clone(),
+            labels: request.labels.clone(),
+            exclude_topics: self.exclude_topics(&request.exclude_topics),
+            buffer_strategy: request
+                .buffer_strategy
+                .or(self.config.recorder.flush_policy.strategy),
+            retention_seconds,
+            expires_at: retention_seconds.map(|ttl| {
+                start_time
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    + ttl
+            }),
+        };
+
+        let recording_session = Arc::new(RecordingSession {
+            recording_id: recording_id.clone(),
+            status: RwLock::new(RecordingStatus::Recording),
+            metadata,
+            topic_buffers: Arc::new(DashMap::new()),
+            subscriptions: DashMap::new(),
+            start_time,
+            pause_time: RwLock::new(None),
+            total_bytes: RwLock::new(0),
+            flushed_batches: RwLock::new(0),
+            last_flush_us: RwLock::new(None),
+            compression_type: request.compression_type,
+            compression_level: request.compression_level,
+            drift: DriftTracker::new(),
+            subscribed_topics: Arc::new(AtomicUsize::new(0)),
+            handoff: RwLock::new(None),
+            topics: RwLock::new(request.topics.clone()),
+            topic_changes: RwLock::new(Vec::new()),
+            session_gaps: RwLock::new(Vec::new()),
+            topic_totals: DashMap::new(),
+            records: RwLock::new(Vec::new()),
+            verify_failures: AtomicU64::new(0),
+            records_verified: AtomicU64::new(0),
+            storage: storage.clone(),
+            controller: request
+                .controller_liveliness
+                .clone()
+                .map(|key_expr| Arc::new(ControllerWatch::new(key_expr, self.clock.clone()))),
+            throughput: Arc::new(ThroughputMeter::new()),
+            uploads: DashMap::new(),
+            previews: DashMap::new(),
+            tap: self.new_tap(&recording_id),
+            taps: DashMap::new(),
+            formats: DashMap::new(),
+            finish_backlog: AtomicU64::new(0),
+            completed: Notify::new(),
+            cancel: CancelToken::default(),
+            tasks: self.tasks.child(&recording_id),
+            coalesce_lock: Mutex::new(()),
+        });
+
+        if let Some(controller) = &recording_session.controller {
+            controller.spawn(self.zenoh_session(), &recording_session.tasks);
+        }
+        self.create_work_dir(&recording_id).await;
+
+        // Subscribe to topics
+        for topic in &request.topics {
+            if live && links.paused_topics.contains(topic) {
+                let (_, subscription) = self.add_topic_buffer(&recording_session, topic);
+                subscription.paused.store(true, Ordering::SeqCst);
+            } else if live {
+                self.subscribe_topic(&recording_session, topic);
+            } else {
+                self.add_topic_buffer(&recording_session, topic);
+            }
+        }
+
+        // A dump has nothing to resume after a crash
+        if live {
+            self.persist_state(&recording_session).await;
+        }
+        self.publish_status(&recording_session).await;
+        self.fire_hooks(&recording_session, RecordingEventKind::Start, None)
+            .await;
+        self.sessions
+            .insert(recording_id.clone(), recording_session);
+
+        let bucket_name = storage.bucket().map(String::from);
+        let mut response = RecorderResponse {
+            status: Some(RecordingStatus::Recording),
+            ..RecorderResponse::success(Some(recording_id), bucket_name)
+        };
+        if let Some(requested) = lowered {
+            warn!(
+                "Compression level {:?} is above the configured limit, recording with {:?}",
+                requested, request.compression_level
+            );
+            response.message = format!(
+                "Recording started with compression level {:?} instead of {:?} (compression limit)",
+                request.compression_level, requested
+            );
+        }
+        response
+    }
+
+    /// Cancel a recording (if still running) and stop its subscribers, so a
+    /// Start can take over its ID
+    async fn discard_for_restart(&self, session: &RecordingSession) {
+        let status = *session.status.read().await;
+        info!(
+            "Restarting recording '{}' ({:?})",
+            session.recording_id, status
+        );
+        if matches!(status, RecordingStatus::Recording | RecordingStatus::Paused) {
+            self.cancel_recording(&session.recording_id).await;
+        }
+        Self::stop_subscribers(session);
+    }
+
+    /// Stop the subscriber tasks of a recording
+    ///
+    /// Shared Zenoh subscribers are undeclared once no other recording uses them.
+    fn stop_subscribers(session: &RecordingSession) {
+        for entry in session.subscriptions.iter() {
+            entry.value().stop.notify_one();
+        }
+        session.tap.close();
+    }
+
+    /// Live tap of a new recording
+    fn new_tap(&self, recording_id: &str) -> Arc<RecordingTap> {
+        Arc::new(RecordingTap::new(
+            recording_id,
+            self.config.recorder.tap.channel_capacity,
+            self.tap_publisher.clone(),
+        ))
+    }
+
+    /// Backend of a bucket requested on Start (the configured one when unset)
+    ///
+    /// Requested buckets are created or verified on first use and cached.
+    async fn bucket_backend(&self, bucket: Option<&str>) -> Result<Arc<dyn StorageBackend>> {
+        let Some(bucket) = bucket.filter(|b| Some(*b) != self.storage_backend.bucket()) else {
+            self.storage_backend.initialize().await?;
+            return Ok(self.storage_backend.clone());
+        };
+
+        if let Some(storage) = self.buckets.get(bucket) {
+            return Ok(storage.value().clone());
+        }
+
+        validate_bucket_name(bucket)?;
+        let storage = self.storage_backend.with_bucket(bucket)?;
+        storage.initialize().await?;
+        info!("Initialized storage bucket '{}'", bucket);
+        self.buckets.insert(bucket.to_string(), storage.clone());
+        Ok(storage)
+    }
+
+    /// Name `topic` is recorded under (`recorder.topic_remap`, else itself)
+    fn recorded_topic(&self, topic: &str) -> String {
+        self.config
+            .recorder
+            .topic_remap
+            .get(topic)
+            .cloned()
+            .unwrap_or_else(|| topic.to_string())
+    }
+
+    /// Keys skipped by the topics of a recording: the configured ones, then
+    /// the ones of its Start
+    fn exclude_topics(&self, requested: &[String]) -> Vec<String> {
+        let mut exclude_topics = self.config.recorder.exclude_topics.clone();
+        for pattern in requested {
+            if !exclude_topics.contains(pattern) {
+                exclude_topics.push(pattern.clone());
+            }
+        }
+        exclude_topics
+    }
+
+    /// Recorded names of the remapped topics among `topics`
+    fn topic_aliases(&self, topics: &[String]) -> BTreeMap<String, String> {
+        topics
+            .iter()
+            .filter_map(|topic| {
+                let name = self.config.recorder.topic_remap.get(topic)?;
+                Some((topic.clone(), name.clone()))
+            })
+            .collect()
+    }
+
+    /// Create a buffer for `topic` and spawn its subscriber task
+    fn subscribe_topic(&self, recording_session: &RecordingSession, topic: &str) {
+        let (buffer, subscription) = self.add_topic_buffer(recording_session, topic);
+        self.spawn_subscriber(recording_session, topic, buffer.clone(), subscription);
+        self.spawn_initial_query(recording_session, topic, buffer);
+    }
+
+    /// Query the latest value of `topic` into `buffer` when it's one of
+    /// `initial_query.topics`
+    ///
+    /// Runs next to the subscriber, so live samples aren't held back; a value
+    /// published while the query runs may be recorded twice.
+    fn spawn_initial_query(
+        &self,
+        recording_session: &RecordingSession,
+        topic: &str,
+        buffer: Arc<TopicBuffer>,
+    ) {
+        let query = &self.config.recorder.initial_query;
+        if !matches_any(&query.topics, topic) || liveliness_key(topic).is_some() {
+            return;
+        }
+        let session = self.zenoh_session();
+        let timeout = query.timeout();
+        let recording_id = recording_session.recording_id.clone();
+        let throughput = recording_session.throughput.clone();
+        let exclude_topics = recording_session.metadata.exclude_topics.clone();
+        let topic = topic.to_string();
+        recording_session.tasks.spawn(
+            TaskStage::Ingest,
+            format!("initial query {}", topic),
+            async move {
+                let replies = match session.get(&topic).timeout(timeout).await {
+                    Ok(replies) => replies,
+                    Err(e) => {
+                        warn!("Failed to query the latest value of '{}': {}", topic, e);
+                        return;
+                    }
+                };
+                let mut recorded = 0;
+                while let Ok(reply) = replies.recv_async().await {
+                    let sample = match reply.into_result() {
+                        Ok(sample) => sample,
+                        Err(e) => {
+                            warn!("Query of '{}' returned an error: {:?}", topic, e);
+                            continue;
+                        }
+                    };
+                    if matches_any(&exclude_topics, sample.key_expr().as_str()) {
+                        continue;
+                    }
+                    throughput.record_ingest(sample.payload().len());
+                    match buffer.push_sample(sample).await {
+                        Ok(()) => recorded += 1,
+                        Err(e) => error!("Failed to push sample to buffer: {}", e),
+                    }
+                }
+                info!(
+                    "Recorded {} queried value(s) of '{}' for recording '{}'",
+                    recorded, topic, recording_id
+                );
+            },
+        );
+    }
+
+    /// Create the buffer (and preview) of `topic`, without a subscriber
+    fn add_topic_buffer(
+        &self,
+        recording_session: &RecordingSession,
+        topic: &str,
+    ) -> (Arc<TopicBuffer>, Arc<TopicSubscription>) {
+        let recording_id = recording_session.recording_id.clone();
+        let recorded_topic = self.recorded_topic(topic);
+
+        // Use configured flush policy, or the recording's buffer strategy
+        let flush_policy = self
+            .live_config
+            .read()
+            .unwrap()
+            .recorder
+            .effective_flush_policy(recording_session.metadata.buffer_strategy);
+        let timestamps = &self.config.recorder.timestamps;
+        let timestamp_policy = find_per_topic(&timestamps.per_topic, &recorded_topic)
+            .unwrap_or(&timestamps.default)
+            .clone();
+        if let Some(preview) =
+            find_per_topic(&self.config.recorder.preview.per_topic, &recorded_topic)
+        {
+            let buffer = Arc::new(
+                TopicBuffer::new(
+                    preview_topic(&recorded_topic),
+                    recording_id.clone(),
+                    flush_policy.max_buffer_size_bytes,
+                    flush_policy.max_duration(),
+                    self.flush_pool.clone(),
+                )
+                .with_timestamp_policy(timestamp_policy.clone())
+                .with_pending_counter(recording_session.throughput.pending_counter())
+                .with_clock(self.clock.clone()),
+            );
+            recording_session.previews.insert(
+                topic.to_string(),
+                Arc::new(PreviewStream::new(buffer, preview.interval())),
+            );
+        }
+        if let Some(tap) = find_per_topic(&self.config.recorder.tap.per_topic, &recorded_topic) {
+            recording_session.taps.insert(
+                topic.to_string(),
+                Arc::new(TopicTap::new(
+                    recording_session.tap.clone(),
+                    topic,
+                    tap.interval(),
+                )),
+            );
+        }
+        let capacity = self
+            .topic_stats
+            .as_ref()
+            .filter(|_| flush_policy.preallocate)
+            .and_then(|stats| {
+                stats.expected_samples(
+                    &recorded_topic,
+                    flush_policy.max_duration(),
+                    flush_policy.max_buffer_size_bytes,
+                )
+            })
+            .unwrap_or(0);
+        let dedupe = find_per_topic(&self.config.recorder.dedupe.per_topic, &recorded_topic);
+        let flush_priority = self.config.recorder.workers.flush_priority(&recorded_topic);
+        let mut buffer = TopicBuffer::new(
+            recorded_topic,
+            recording_id.clone(),
+            flush_policy.max_buffer_size_bytes,
+            flush_policy.max_duration(),
+            self.flush_pool.clone(),
+        )
+        .with_capacity(capacity)
+        .with_timestamp_policy(timestamp_policy)
+        .with_pending_counter(recording_session.throughput.pending_counter())
+        .with_flush_priority(flush_priority)
+        .with_clock(self.clock.clone());
+        if let Some(policy) = dedupe {
+            buffer = buffer.with_dedupe(policy);
+        }
+        let buffer = Arc::new(buffer);
+        buffer.set_idle_trim(flush_policy.idle_trim());
+
+        recording_session
+            .topic_buffers
+            .insert(topic.to_string(), buffer.clone());
+
+        let subscription = Arc::new(TopicSubscription::default());
+        recording_session
+            .subscriptions
+            .insert(topic.to_string(), subscription.clone());
+        (buffer, subscription)
+    }
+
+    /// Flush the preview channel of `topic`, if it has one
+    async fn flush_preview(session: &RecordingSession, topic: &str) {
+        let Some(preview) = session.previews.get(topic).map(|p| p.value().clone()) else {
+            return;
+        };
+        if preview.buffer().stats().0 > 0 {
+            if let Err(e) = preview.buffer().force_flush().await {
+                error!("Failed to flush preview of topic '{}': {}", topic, e);
+            }
+        }
+    }
+
+    /// Subscribe to `topic` through the shared subscriber, feeding `buffer` until stopped
+    fn spawn_subscriber(
+        &self,
+        recording_session: &RecordingSession,
+        topic: &str,
+        buffer: Arc<TopicBuffer>,
+        subscription: Arc<TopicSubscription>,
+    ) {
+        let recording_id = recording_session.recording_id.clone();
+        let topic_clone = topic.to_string();
+        let subscribed_topics = recording_session.subscribed_topics.clone();
+        let throughput = recording_session.throughput.clone();
+        let wakeup_granularity = self.wakeup_granularity;
+        let preview = recording_session
+            .previews
+            .get(topic)
+            .map(|p| p.value().clone());
+        let tap = recording_session.taps.get(topic).map(|t| t.value().clone());
+        let exclude_topics = recording_session.metadata.exclude_topics.clone();
+        let span = info_span!(
+            "subscriber",
+            recording_id = %recording_id,
+            device_id = %self.config.recorder.device_id,
+            topic = %topic
+        );
+
+        let subscription_result = self.subscription_hub.subscribe(topic);
+        let subscriber_task = async move {
+            match subscription_result {
+                Ok(subscriber) => {
+                    info!(
+                        "Subscribed to topic '{}' for recording '{}'",
+                        topic_clone, recording_id
+                    );
+                    subscribed_topics.fetch_add(1, Ordering::SeqCst);
+
+                    // Buffered samples are flushed on time even when no more
+                    // arrive; idle topics give back their spare buffer capacity once.
+                    // In low-power mode the timers end on the shared wakeup tick
+                    let mut trimmed = false;
+                    loop {
+                        let flush_due_in = buffer
+                            .flush_due_in()
+                            .into_iter()
+                            .chain(preview.as_ref().and_then(|p| p.buffer().flush_due_in()))
+                            .min()
+                            .map(|due| runtime::aligned(due, wakeup_granularity));
+                        let idle_trim = buffer
+                            .idle_trim()
+                            .filter(|_| !trimmed)
+                            .map(|trim| runtime::aligned(trim, wakeup_granularity));
+                        tokio::select! {
+                            result = subscriber.recv_async() => match result {
+                                // Skipped before it costs buffer memory
+                                Ok(sample) if matches_any(&exclude_topics, sample.key_expr().as_str()) => {}
+                                Ok(sample) => {
+                                    trimmed = false;
+                                    throughput.record_ingest(sample.payload().len());
+                                    if let Some(preview) = &preview {
+                                        if let Err(e) = preview.offer(&sample).await {
+                                            error!("Failed to push sample to preview: {}", e);
+                                        }
+                                    }
+                                    if let Some(tap) = &tap {
+                                        tap.offer(&sample);
+                                    }
+                                    if let Err(e) = buffer.push_sample(sample).await {
+                                        error!("Failed to push sample to buffer: {}", e);
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("Error receiving sample: {}", e);
+                                    break;
+                                }
+                            },
+                            _ = subscription.stop.notified() => {
+                                info!(
+                                    "Unsubscribed from topic '{}' for recording '{}'",
+                                    topic_clone, recording_id
+                                );
+                                break;
+                            }
+                            _ = runtime::sleep(flush_due_in.unwrap_or_default()), if flush_due_in.is_some() => {
+                                buffer.flush_if_due().await;
+                                if let Some(preview) = &preview {
+                                    preview.buffer().flush_if_due().await;
+                                }
+                            }
+                            _ = runtime::sleep(idle_trim.unwrap_or_default()), if idle_trim.is_some() => {
+                                buffer.trim().await;
+                                trimmed = true;
+                            }
+                        }
+                    }
+                    subscribed_topics.fetch_sub(1, Ordering::SeqCst);
+                }
+                Err(e) => {
+                    error!("Failed to subscribe to topic '{}': {:#}", topic_clone, e);
+                }
+            }
+        };
+        recording_session.tasks.spawn(
+            TaskStage::Ingest,
+            format!("subscriber {}", topic),
+            subscriber_task.instrument(span),
+        );
+    }
+
+    /// Persist session state for crash recovery (no-op when recovery is disabled)
+    async fn persist_state(&self, session: &RecordingSession) {
+        if let Some(store) = &self.state_store {
+            if let Err(e) = store.save(&session.to_state().await).await {
+                warn!(
+                    "Failed to persist state for recording '{}': {}",
+                    session.recording_id, e
+                );
+            }
+        }
+    }
+
+    /// Drop the persisted state of a recording that ended cleanly
+    async fn clear_state(&self, recording_id: &str) {
+        if let Some(store) = &self.state_store {
+            if let Err(e) = store.remove(recording_id).await {
+                warn!(
+                    "Failed to remove state for recording '{}': {}",
+                    recording_id, e
+                );
+            }
+        }
+    }
+
+    /// Create the scratch directory of a recording (when enabled)
+    async fn create_work_dir(&self, recording_id: &str) {
+        if let Some(work_dirs) = &self.work_dirs {
+            if let Err(e) = work_dirs.create(recording_id).await {
+                warn!("{:#}", e);
+            }
+        }
+    }
+
+    /// Clean up the scratch directory of an ended recording and sweep the
+    /// directories of other recordings that are no longer active
+    async fn release_work_dir(&self, recording_id: &str) {
+        if let Some(work_dirs) = &self.work_dirs {
+            if let Err(e) = work_dirs.release(recording_id).await {
+                warn!("{:#}", e);
+            }
+        }
+        self.sweep_work_dirs().await;
+    }
+
+    /// Remove scratch directories left by recordings that are no longer active
+    ///
+    /// Directories holding spilled records are kept for
+    /// `work_dir.retain_failed_hours`. Returns the number removed.
+    pub async fn sweep_work_dirs(&self) -> usize {
+        let Some(work_dirs) = &self.work_dirs else {
+            return 0;
+        };
+        let mut active = HashSet::new();
+        for session in self.session_list() {
+            if !matches!(
+                *session.status.read().await,
+                RecordingStatus::Finished | RecordingStatus::Archived | RecordingStatus::Cancelled
+            ) {
+                active.insert(session.recording_id.clone());
+            }
+        }
+        match work_dirs.sweep(&active).await {
+            Ok(removed) => removed,
+            Err(e) => {
+                warn!("Failed to sweep work directories: {:#}", e);
+                0
+            }
+        }
+    }
+
+    /// Recover recordings interrupted by a crash
+    ///
+    /// Every state file left behind by a previous process belongs to a recording
+    /// that never finished. With `recovery.resume` enabled the recording is resumed
+    /// under its original recording_id; otherwise its metadata is finalized with
+    /// `interrupted = true` and the state file is removed. Recordings spilled
+    /// by a shutdown that ran out of time are completed first.
+    ///
+    /// Returns the ids of the recovered recordings.
+    pub async fn recover_sessions(&self) -> Result<Vec<String>> {
+        let mut recovered = self.complete_spilled().await;
+        let store = match &self.state_store {
+            Some(store) => store.clone(),
+            None => return Ok(recovered),
+        };
+
+        for state in store.load_all().await.map_err(RecorderError::backend)? {
+            let recording_id = state.recording_id.clone();
+            if self.sessions.contains_key(&recording_id) {
+                continue;
+            }
+
+            if self.config.recorder.recovery.resume {
+                info!("Resuming interrupted recording '{}'", recording_id);
+
+                let mut state = state;
+                state.metadata.interrupted = true;
+                if let Err(e) = self.resume_session(state).await {
+                    error!(
+                        "Failed to resume interrupted recording '{}': {}",
+                        recording_id, e
+                    );
+                    continue;
+                }
+            } else {
+                info!("Finalizing interrupted recording '{}'", recording_id);
+
+                let start_time = chrono::DateTime::parse_from_rfc3339(&state.metadata.start_time)
+                    .map(SystemTime::from)
+                    .unwrap_or_else(|_| self.clock.now());
+                let end_time = state
+                    .last_flush_us
+                    .and_then(|us| chrono::DateTime::from_timestamp_micros(us as i64))
+                    .unwrap_or_else(|| self.clock.now().into());
+
+                let mut metadata = state.metadata;
+                metadata.end_time = Some(end_time.to_rfc3339());
+                metadata.total_bytes = state.flushed_bytes;
+                metadata.interrupted = true;
+                include_changed_topics(&mut metadata);
+
+                // Keep the state file on failure so the next restart retries
+                let written = match self.bucket_backend(metadata.bucket.as_deref()).await {
+                    Ok(storage) => {
+                        Self::write_metadata_record(
+                            storage.as_ref(),
+                            &self.record_timestamps,
+                            &metadata,
+                            start_time,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = written {
+                    error!(
+                        "Failed to finalize interrupted recording '{}': {}",
+                        recording_id, e
+                    );
+                    continue;
+                }
+                self.clear_state(&recording_id).await;
+            }
+
+            recovered.push(recording_id);
+        }
+
+        Ok(recovered)
+    }
+
+    /// Rebuild a recording from its state under its recording_id and
+    /// subscribe to its topics
+    async fn resume_session(&self, state: SessionState) -> Result<Arc<RecordingSession>> {
+        let recording_id = state.recording_id.clone();
+        let storage = self
+            .bucket_backend(state.metadata.bucket.as_deref())
+            .await?;
+        let tasks = self.tasks.child(&recording_id);
+        let tap = self.new_tap(&recording_id);
+        let recording_session = Arc::new(RecordingSession::from_state(
+            state,
+            storage,
+            tap,
+            tasks,
+            self.clock.clone(),
+        ));
+        if let Some(controller) = &recording_session.controller {
+            controller.spawn(self.zenoh_session(), &recording_session.tasks);
+        }
+        self.create_work_dir(&recording_id).await;
+        for topic in &recording_session.metadata.topics {
+            self.subscribe_topic(&recording_session, topic);
+        }
+        self.persist_state(&recording_session).await;
+        self.sessions
+            .insert(recording_id, recording_session.clone());
+        Ok(recording_session)
+    }
+
+    /// Complete the recordings spilled by a shutdown that ran out of time:
+    /// upload their spilled records, then write their metadata
+    ///
+    /// A recording whose records can't all be uploaded keeps its manifest,
+    /// updated with the records that were, for the next start. Returns the
+    /// ids of the completed recordings.
+    async fn complete_spilled(&self) -> Vec<String> {
+        let Some(work_dirs) = &self.work_dirs else {
+            return Vec::new();
+        };
+
+        let mut completed = Vec::new();
+        for mut manifest in work_dirs.manifests().await {
+            let recording_id = manifest.metadata.recording_id.clone();
+            if self.sessions.contains_key(&recording_id) {
+                continue;
+            }
+            info!(
+                "Completing recording '{}' spilled at shutdown",
+                recording_id
+            );
+
+            let storage = match self
+                .bucket_backend(manifest.metadata.bucket.as_deref())
+                .await
+            {
+                Ok(storage) => storage,
+                Err(e) => {
+                    error!(
+                        "Failed to complete spilled recording '{}': {}",
+                        recording_id, e
+                    );
+                    continue;
+                }
+            };
+            let mut uploaded = true;
+            for sidecar in work_dirs.deferred(&recording_id).await {
+                let (record, data) = match work_dirs.load_deferred(&sidecar).await {
+                    Ok(record) => record,
+                    Err(e) => {
+                        warn!("Skipping spilled record: {:#}", e);
+                        uploaded = false;
+                        continue;
+                    }
+                };
+                let bytes = data.len();
+                let chunks = Some(chunk_checksums(&data, CHECKSUM_CHUNK_BYTES));
+                let written = storage
+                    .write_with_retry(&record.entry, record.timestamp_us, data, record.labels, 3)
+                    .await;
+                if let Err(e) = written {
+                    // An abandoned upload may have completed after all
+                    let stored = storage
+                        .verify_record(&record.entry, record.timestamp_us, &record.crc32c)
+                        .await;
+                    if !matches!(stored, Ok(true)) {
+                        error!(
+                            "Failed to upload spilled record of topic '{}': {}",
+                            record.topic, e
+                        );
+                        uploaded = false;
+                        break;
+                    }
+                }
+                manifest.metadata.total_bytes += bytes as i64;
+                if record.parts.is_empty() {
+                    manifest.metadata.records.push(RecordChecksum {
+                        topic: record.topic,
+                        entry: record.entry,
+                        timestamp_us: record.timestamp_us,
+                        bytes,
+                        crc32c: record.crc32c,
+                        coalesced: None,
+                        chunks,
+                    });
+                }
+                manifest.metadata.records.extend(
+                    record
+                        .parts
+                        .iter()
+                        .map(|part| coalesce::record_checksum(part, record.timestamp_us)),
+                );
+                if let Err(e) = work_dirs.remove_deferred(&sidecar).await {
+                    warn!("{:#}", e);
+                }
+            }
+
+            if uploaded {
+                if let Err(e) = storage.finish_recording(&recording_id).await {
+                    error!("Failed to complete the stored files: {}", e);
+                }
+                let start_time =
+                    chrono::DateTime::parse_from_rfc3339(&manifest.metadata.start_time)
+                        .map(SystemTime::from)
+                        .unwrap_or_else(|_| self.clock.now());
+                match Self::write_metadata_record(
+                    storage.as_ref(),
+                    &self.record_timestamps,
+                    &manifest.metadata,
+                    start_time,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        if let Err(e) = work_dirs.remove_manifest(&recording_id).await {
+                            warn!("{:#}", e);
+                        }
+                        // Left behind if the shutdown stopped before removing it
+                        self.clear_state(&recording_id).await;
+                        self.release_work_dir(&recording_id).await;
+                        info!("Recording '{}' finished", recording_id);
+                        completed.push(recording_id);
+                        continue;
+                    }
+                    Err(e) => error!(
+                        "Failed to write metadata of spilled recording '{}': {}",
+                        recording_id, e
+                    ),
+                }
+            }
+            // Keep what was uploaded for the next attempt
+            if let Err(e) = work_dirs.write_manifest(&manifest).await {
+                error!("{:#}", e);
+            }
+        }
+        completed
+    }
+
+    /// Pause recording
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn pause_recording(&self, recording_id: &str) -> RecorderResponse {
+        match self.sessions.get(recording_id) {
+            Some(session) => {
+                let mut status = session.status.write().await;
+                if *status == RecordingStatus::Recording {
+                    *status = RecordingStatus::Paused;
+                    drop(status);
+                    *session.pause_time.write().await = Some(self.clock.now());
+                    info!("Recording '{}' paused", recording_id);
+                    self.publish_status(&session).await;
+                    RecorderResponse::success(Some(recording_id.to_string()), None)
+                } else {
+                    RecorderResponse::error(
+                        ErrorCode::InvalidState,
+                        "Recording is not in Recording state".to_string(),
+                    )
+                }
+            }
+            None => RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            ),
+        }
+    }
+
+    /// Resume recording
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn resume_recording(&self, recording_id: &str) -> RecorderResponse {
+        match self.sessions.get(recording_id) {
+            Some(session) => {
+                let mut status = session.status.write().await;
+                if *status == RecordingStatus::Paused {
+                    *status = RecordingStatus::Recording;
+                    drop(status);
+                    *session.pause_time.write().await = None;
+                    info!("Recording '{}' resumed", recording_id);
+                    self.publish_status(&session).await;
+                    RecorderResponse::success(Some(recording_id.to_string()), None)
+                } else {
+                    RecorderResponse::error(
+                        ErrorCode::InvalidState,
+                        "Recording is not in Paused state".to_string(),
+                    )
+                }
+            }
+            None => RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            ),
+        }
+    }
+
+    /// Mute topics of a recording while the other topics keep recording
+    ///
+    /// The recording stops receiving the paused topics (their subscribers are
+    /// undeclared unless other recordings share them) and their buffered
+    /// samples are flushed.
+    pub async fn pause_topics(&self, recording_id: &str, topics: &[String]) -> RecorderResponse {
+        self.set_topics_paused(recording_id, topics, true).await
+    }
+
+    /// Resume topics muted with `pause_topics`
+    pub async fn resume_topics(&self, recording_id: &str, topics: &[String]) -> RecorderResponse {
+        self.set_topics_paused(recording_id, topics, false).await
+    }
+
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    async fn set_topics_paused(
+        &self,
+        recording_id: &str,
+        topics: &[String],
+        paused: bool,
+    ) -> RecorderResponse {
+        let session = match self.active_session(recording_id, topics).await {
+            Ok(session) => session,
+            Err(response) => return response,
+        };
+        if let Err(response) = Self::check_recorded_topics(&session, topics) {
+            return response;
+        }
+
+        for topic in topics {
+            let Some(subscription) = session.subscriptions.get(topic).map(|s| s.value().clone())
+            else {
+                continue;
+            };
+            // Already in the requested state
+            if subscription.paused.swap(paused, Ordering::SeqCst) == paused {
+                continue;
+            }
+
+            let Some(buffer) = session.topic_buffers.get(topic).map(|b| b.value().clone()) else {
+                continue;
+            };
+            if paused {
+                subscription.stop.notify_one();
+                Self::flush_preview(&session, topic).await;
+                if buffer.stats().0 == 0 {
+                    info!("Topic '{}' of recording '{}' paused", topic, recording_id);
+                    continue;
+                }
+                if let Err(e) = buffer.force_flush().await {
+                    error!("Failed to flush buffer for topic '{}': {}", topic, e);
+                }
+                info!("Topic '{}' of recording '{}' paused", topic, recording_id);
+            } else {
+                self.spawn_subscriber(&session, topic, buffer, subscription);
+                info!("Topic '{}' of recording '{}' resumed", topic, recording_id);
+            }
+        }
+        self.publish_status(&session).await;
+
+        RecorderResponse::success(Some(recording_id.to_string()), None)
+    }
+
+    /// Attach topics to an active recording
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn add_topics(&self, recording_id: &str, topics: &[String]) -> RecorderResponse {
+        let session = match self.active_session(recording_id, topics).await {
+            Ok(session) => session,
+            Err(response) => return response,
+        };
+
+        let recorded: Vec<&str> = topics
+            .iter()
+            .filter(|topic| session.subscriptions.contains_key(topic.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !recorded.is_empty() {
+            return RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Topics already part of recording '{}': {}",
+                    recording_id,
+                    recorded.join(", ")
+                ),
+            );
+        }
+
+        for topic in topics {
+            // Listed twice
+            if session.subscriptions.contains_key(topic) {
+                continue;
+            }
+            self.subscribe_topic(&session, topic);
+            session.topics.write().await.push(topic.clone());
+            session
+                .record_topic_change(topic, TopicChangeKind::Added, self.clock.now())
+                .await;
+            info!("Topic '{}' added to recording '{}'", topic, recording_id);
+        }
+        self.persist_state(&session).await;
+        self.publish_status(&session).await;
+
+        RecorderResponse::success(Some(recording_id.to_string()), None)
+    }
+
+    /// Detach topics from an active recording
+    ///
+    /// The recording leaves the topics' subscribers and the buffered samples
+    /// are flushed before the buffers are dropped.
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn remove_topics(&self, recording_id: &str, topics: &[String]) -> RecorderResponse {
+        let session = match self.active_session(recording_id, topics).await {
+            Ok(session) => session,
+            Err(response) => return response,
+        };
+        if let Err(response) = Self::check_recorded_topics(&session, topics) {
+            return response;
+        }
+
+        for topic in topics {
+            if let Some((_, subscription)) = session.subscriptions.remove(topic) {
+                subscription.stop.notify_one();
+            }
+            if let Some((_, buffer)) = session.topic_buffers.remove(topic) {
+                if buffer.stats().0 > 0 {
+                    if let Err(e) = buffer.force_flush().await {
+                        error!("Failed to flush buffer for topic '{}': {}", topic, e);
+                    }
+                }
+            }
+            Self::flush_preview(&session, topic).await;
+            session.taps.remove(topic);
+            session.topics.write().await.retain(|t| t != topic);
+            session
+                .record_topic_change(topic, TopicChangeKind::Removed, self.clock.now())
+                .await;
+            info!(
+                "Topic '{}' removed from recording '{}'",
+                topic, recording_id
+            );
+        }
+        self.persist_state(&session).await;
+        self.publish_status(&session).await;
+
+        RecorderResponse::success(Some(recording_id.to_string()), None)
+    }
+
+    /// Look up a recording that can change its topics
+    async fn active_session(
+        &self,
+        recording_id: &str,
+        topics: &[String],
+    ) -> std::result::Result<Arc<RecordingSession>, RecorderResponse> {
+        let Some(session) = self.sessions.get(recording_id).map(|s| s.value().clone()) else {
+            return Err(RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            ));
+        };
+
+        if !matches!(
+            *session.status.read().await,
+            RecordingStatus::Recording | RecordingStatus::Paused
+        ) {
+            return Err(RecorderResponse::error(
+                ErrorCode::InvalidState,
+                "Recording is not active".to_string(),
+            ));
+        }
+        if topics.is_empty() {
+            return Err(RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                "No topics specified".to_string(),
+            ));
+        }
+        Ok(session)
+    }
+
+    /// Reject topics that are not part of the recording
+    fn check_recorded_topics(
+        session: &RecordingSession,
+        topics: &[String],
+    ) -> std::result::Result<(), RecorderResponse> {
+        let unknown: Vec<&str> = topics
+            .iter()
+            .filter(|topic| !session.subscriptions.contains_key(topic.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(RecorderResponse::error(
+                ErrorCode::InvalidRequest,
+                format!(
+                    "Topics not part of recording '{}': {}",
+                    session.recording_id,
+                    unknown.join(", ")
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Estimate the data rates of recording `topics` from their learned statistics
+    ///
+    /// This is a dry run: nothing is subscribed. Without topics every known topic
+    /// is estimated.
+    pub fn estimate(&self, topics: &[String]) -> EstimateResponse {
+        let Some(stats) = &self.topic_stats else {
+            return EstimateResponse {
+                success: false,
+                message: "Topic statistics are disabled".to_string(),
+                ..Default::default()
+            };
+        };
+
+        let topics = if topics.is_empty() {
+            stats.topics()
+        } else {
+            topics.to_vec()
+        };
+
+        let mut response = EstimateResponse {
+            success: true,
+            message: "Estimate computed successfully".to_string(),
+            upload_limit_bytes_per_sec: self.upload_limiter.limit().max_bytes_per_sec,
+            ..Default::default()
+        };
+        for topic in topics {
+            let Some(profile) = stats.profile(&self.recorded_topic(&topic)) else {
+                response.unknown_topics.push(topic);
+                continue;
+            };
+            let bytes_per_sec = profile.samples_per_sec * profile.avg_sample_bytes;
+            let stored_bytes_per_sec = bytes_per_sec * profile.compression_ratio.unwrap_or(1.0);
+            response.bytes_per_sec += bytes_per_sec;
+            response.stored_bytes_per_sec += stored_bytes_per_sec;
+            response.topics.push(TopicEstimate {
+                topic,
+                recordings: profile.recordings,
+                samples_per_sec: profile.samples_per_sec,
+                avg_sample_bytes: profile.avg_sample_bytes,
+                compression_ratio: profile.compression_ratio,
+                bytes_per_sec,
+                stored_bytes_per_sec,
+            });
+        }
+        response.upload_feasible = response.upload_limit_bytes_per_sec == 0
+            || response.stored_bytes_per_sec <= response.upload_limit_bytes_per_sec as f64;
+
+        response
+    }
+
+    /// Cancel recording
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn cancel_recording(&self, recording_id: &str) -> RecorderResponse {
+        match self.sessions.get(recording_id) {
+            Some(session) => {
+                *session.status.write().await = RecordingStatus::Cancelled;
+                session.cancel.cancel();
+                if let Some(coalescer) = &self.coalescer {
+                    let dropped = coalescer.take(recording_id);
+                    session
+                        .throughput
+                        .settle(dropped.iter().map(|(record, _)| record.raw_bytes).sum());
+                }
+                Self::stop_subscribers(&session);
+                session.tasks.abort_all();
+                if let Some(controller) = &session.controller {
+                    controller.stop();
+                }
+                self.clear_state(recording_id).await;
+                info!("Recording '{}' cancelled", recording_id);
+                self.publish_status(&session).await;
+                self.fire_hooks(&session, RecordingEventKind::Cancel, None)
+                    .await;
+                self.release_work_dir(recording_id).await;
+                RecorderResponse::success(Some(recording_id.to_string()), None)
+            }
+            None => RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            ),
+        }
+    }
+
+    /// Finish a recording and wait until its data is uploaded
+    ///
+    /// Control queries use `begin_finish` and run `complete_finish` in the
+    /// background instead, so a large backlog doesn't outlast the query.
+    pub async fn finish_recording(&self, recording_id: &str) -> RecorderResponse {
+        let response = self.begin_finish(recording_id).await;
+        if !response.success {
+            return response;
+        }
+        self.complete_finish(recording_id).await
+    }
+
+    /// Stop recording and move the recording to `Uploading`
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn begin_finish(&self, recording_id: &str) -> RecorderResponse {
+        let Some(session) = self.sessions.get(recording_id).map(|s| s.value().clone()) else {
+            return RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            );
+        };
+        {
+            let mut status = session.status.write().await;
+            if !matches!(
+                *status,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                return RecorderResponse::error(
+                    ErrorCode::InvalidState,
+                    format!(
+                        "Recording is {:?}, not in Recording or Paused state",
+                        *status
+                    ),
+                );
+            }
+            *status = RecordingStatus::Uploading;
+        }
+        info!("Finishing recording '{}'", recording_id);
+        if let Some(controller) = &session.controller {
+            controller.stop();
+        }
+        Self::stop_subscribers(&session);
+        session
+            .finish_backlog
+            .store(session.throughput.backlog_bytes(), Ordering::Relaxed);
+        self.publish_status(&session).await;
+
+        RecorderResponse {
+            message: "Recording is uploading".to_string(),
+            status: Some(RecordingStatus::Uploading),
+            ..RecorderResponse::success(Some(recording_id.to_string()), None)
+        }
+    }
+
+    /// Flush and upload the rest of a recording moved to `Uploading` by
+    /// `begin_finish`, then write its metadata
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn complete_finish(&self, recording_id: &str) -> RecorderResponse {
+        let Some(session) = self.sessions.get(recording_id).map(|s| s.value().clone()) else {
+            return RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            );
+        };
+
+        let reason = if self.shutting_down.load(Ordering::SeqCst) {
+            FlushReason::Shutdown
+        } else {
+            FlushReason::Forced
+        };
+        Self::flush_remaining(&session, reason).await;
+
+        // Wait for the flush workers to write (or give up on) the last
+        // records, store the batches left to coalesce, and wait for the
+        // deferred records to be uploaded
+        while !self.shutdown_spill.is_armed() && self.flush_pool.pending_tasks(recording_id) > 0 {
+            runtime::sleep(Duration::from_millis(100)).await;
+        }
+        self.store_pending_coalesced(&session).await;
+        while !self.shutdown_spill.is_armed()
+            && (self.flush_pool.pending_tasks(recording_id) > 0
+                || self.has_deferred(&session).await)
+        {
+            runtime::sleep(Duration::from_millis(100)).await;
+        }
+        if self.shutdown_spill.is_armed() {
+            info!("Recording '{}' is spilled for the next start", recording_id);
+            return RecorderResponse::error(
+                ErrorCode::InvalidState,
+                "Recording was spilled at shutdown".to_string(),
+            );
+        }
+        if *session.status.read().await != RecordingStatus::Uploading {
+            info!("Recording '{}' was cancelled while uploading", recording_id);
+            session.completed.notify_waiters();
+            return RecorderResponse::error(
+                ErrorCode::InvalidState,
+                "Recording was cancelled while uploading".to_string(),
+            );
+        }
+
+        if let Err(e) = session.storage.finish_recording(recording_id).await {
+            error!("Failed to complete the stored files: {}", e);
+            self.fire_hooks(
+                &session,
+                RecordingEventKind::Error,
+                Some(format!("Failed to complete the stored files: {}", e)),
+            )
+            .await;
+        }
+
+        // Write metadata
+        let metadata = match self.write_metadata(&session).await {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                error!("Failed to write metadata: {}", e);
+                self.fire_hooks(
+                    &session,
+                    RecordingEventKind::Error,
+                    Some(format!("Failed to write metadata: {}", e)),
+                )
+                .await;
+                None
+            }
+        };
+        self.clear_state(recording_id).await;
+        self.update_topic_stats(&session).await;
+
+        *session.status.write().await = RecordingStatus::Finished;
+        session.tasks.abort_all();
+        session.completed.notify_waiters();
+        info!("Recording '{}' finished", recording_id);
+        self.publish_status(&session).await;
+        self.fire_hooks(&session, RecordingEventKind::Finish, None)
+            .await;
+        if let Some(metadata) = metadata {
+            self.spawn_ingestion(&session, metadata);
+        }
+        self.release_work_dir(recording_id).await;
+        RecorderResponse::success(Some(recording_id.to_string()), None)
+    }
+
+    /// Finish a recording and continue it in a new one with the same
+    /// settings and topics (`rollover` command)
+    ///
+    /// The next recording subscribes before the current one stops, so no
+    /// sample is lost in between; samples arriving during the switch may be
+    /// stored in both. The current recording is moved to `Uploading` like on
+    /// Finish and the caller completes it with `complete_finish`; if it can't
+    /// be, the next recording is cancelled. Returns the next recording's ID.
+    #[tracing::instrument(skip_all, fields(recording_id = %recording_id, device_id = %self.config.recorder.device_id))]
+    pub async fn rollover(&self, recording_id: &str) -> RecorderResponse {
+        let Some(session) = self.sessions.get(recording_id).map(|s| s.value().clone()) else {
+            return RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            );
+        };
+        let status = *session.status.read().await;
+        if status != RecordingStatus::Recording {
+            return RecorderResponse::error(
+                ErrorCode::InvalidState,
+                format!("Recording is {:?}, not in Recording state", status),
+            );
+        }
+
+        let request = session.start_request().await;
+        let links = RecordingLinks {
+            previous_recording_id: Some(recording_id.to_string()),
+            group_id: session.metadata.group_id.clone(),
+            paused_topics: session
+                .subscriptions
+                .iter()
+                .filter(|entry| entry.value().paused.load(Ordering::SeqCst))
+                .map(|entry| entry.key().clone())
+                .collect(),
+        };
+        let response = self.open_recording(request, true, links).await;
+        let Some(next_id) = response.recording_id.clone().filter(|_| response.success) else {
+            warn!(
+                "Recording '{}' not rolled over: {}",
+                recording_id, response.message
+            );
+            return response;
+        };
+
+        let finishing = self.begin_finish(recording_id).await;
+        if !finishing.success {
+            warn!(
+                "Recording '{}' not rolled over, cancelling '{}': {}",
+                recording_id, next_id, finishing.message
+            );
+            self.cancel_recording(&next_id).await;
+            return finishing;
+        }
+        info!("Recording '{}' continues in '{}'", recording_id, next_id);
+        RecorderResponse {
+            message: format!(
+                "Recording '{}' is uploading, continued by '{}'",
+                recording_id, next_id
+            ),
+            ..response
+        }
+    }
+
+    /// Flush the buffers and previews of a recording
+    async fn flush_remaining(session: &RecordingSession, reason: FlushReason) {
+        for entry in session.topic_buffers.iter() {
+            if let Err(e) = entry.value().flush(reason).await {
+                error!("Failed to flush buffer for topic '{}': {}", entry.key(), e);
+            }
+        }
+        let topics: Vec<String> = session.previews.iter().map(|p| p.key().clone()).collect();
+        for topic in topics {
+            Self::flush_preview(session, &topic).await;
+        }
+    }
+
+    /// Store the batches of a recording still waiting to be coalesced
+    async fn store_pending_coalesced(&self, session: &RecordingSession) {
+        let Some(coalescer) = &self.coalescer else {
+            return;
+        };
+        let _storing = session.coalesce_lock.lock().await;
+        let batches = coalescer.take(&session.recording_id);
+        if !batches.is_empty() {
+            Self::write_coalesced(batches, session, &self.flush_context()).await;
+        }
+    }
+
+    /// Whether an uploading recording still has records held back by the
+    /// upload deferral
+    async fn has_deferred(&self, session: &RecordingSession) -> bool {
+        let (Some(_), Some(work_dirs)) = (&self.upload_gate, &self.work_dirs) else {
+            return false;
+        };
+        *session.status.read().await == RecordingStatus::Uploading
+            && !work_dirs.deferred(&session.recording_id).await.is_empty()
+    }
+
+    /// Wait up to `timeout` for a finishing recording to be uploaded and
+    /// report its upload progress
+    pub async fn wait_for_completion(
+        &self,
+        recording_id: &str,
+        timeout: Duration,
+    ) -> CompletionResponse {
+        let Some(session) = self.sessions.get(recording_id).map(|s| s.value().clone()) else {
+            return CompletionResponse {
+                success: false,
+                message: format!("Recording '{}' not found", recording_id),
+                recording_id: None,
+                status: RecordingStatus::Idle,
+                completed: false,
+                upload_percent: 0.0,
+                backlog_bytes: 0,
+            };
+        };
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            // Registered before the check so a completion in between isn't missed
+            let completed = session.completed.notified();
+            let status = *session.status.read().await;
+            if status != RecordingStatus::Uploading {
+                break status;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            tokio::select! {
+                _ = completed => {}
+                _ = runtime::sleep(remaining) => break status,
+            }
+        };
+
+        let completed = matches!(
+            status,
+            RecordingStatus::Finished | RecordingStatus::Archived
+        );
+        let message = match status {
+            RecordingStatus::Finished => "Recording uploaded".to_string(),
+            RecordingStatus::Archived => "Recording uploaded and archived".to_string(),
+            RecordingStatus::Uploading => "Upload in progress".to_string(),
+            RecordingStatus::Cancelled => "Recording was cancelled".to_string(),
+            _ => "Recording is not finishing".to_string(),
+        };
+        CompletionResponse {
+            success: true,
+            message,
+            recording_id: Some(recording_id.to_string()),
+            status,
+            completed,
+            upload_percent: session.upload_percent(status).unwrap_or(0.0),
+            backlog_bytes: if completed {
+                0
+            } else {
+                session.throughput.backlog_bytes()
+            },
+        }
+    }
+
+    /// Registry of the process's background tasks
+    pub fn tasks(&self) -> &TaskRegistry {
+        &self.tasks
+    }
+
+    /// Live background tasks of the process and the recordings, and the
+    /// recent task panics
+    pub fn list_tasks(&self) -> TasksResponse {
+        let mut tasks = self.tasks.tasks();
+        for session in self.session_list() {
+            tasks.extend(session.tasks.tasks());
+        }
+        TasksResponse {
+            success: true,
+            message: format!("{} live tasks", tasks.len()),
+            tasks,
+            panics: self.tasks.panics(),
+        }
+    }
+
+    /// Recordings of a quota scope that are recording or paused, other than
+    /// `except`
+    async fn active_recordings(
+        &self,
+        scope: QuotaScope,
+        name: &str,
+        except: Option<&str>,
+    ) -> usize {
+        let mut active = 0;
+        for session in self.session_list() {
+            if except == Some(session.recording_id.as_str()) {
+                continue;
+            }
+            let owner = match scope {
+                QuotaScope::Organization => session.metadata.organization.as_deref(),
+                QuotaScope::Task => session.metadata.task_id.as_deref(),
+            };
+            if owner == Some(name)
+                && matches!(
+                    *session.status.read().await,
+                    RecordingStatus::Recording | RecordingStatus::Paused
+                )
+            {
+                active += 1;
+            }
+        }
+        active
+    }
+
+    /// Why a new recording of these quota scopes would exceed a limit, not
+    /// counting the recording `replaced` by it
+    async fn quota_problem(
+        &self,
+        scopes: &[(QuotaScope, String, QuotaLimits)],
+        replaced: Option<&str>,
+    ) -> Option<String> {
+        for (scope, name, limits) in scopes {
+            if limits.max_concurrent_recordings > 0 {
+                let active = self.active_recordings(*scope, name, replaced).await;
+                if active >= limits.max_concurrent_recordings {
+                    return Some(format!(
+                        "{} '{}' already runs {} of {} concurrent recordings",
+                        scope.as_str(),
+                        name,
+                        active,
+                        limits.max_concurrent_recordings
+                    ));
+                }
+            }
+            if limits.max_bytes_per_day > 0 {
+                let bytes = self.quotas.bytes_today(*scope, name);
+                if bytes >= limits.max_bytes_per_day {
+                    return Some(format!(
+                        "{} '{}' stored {} of {} bytes today",
+                        scope.as_str(),
+                        name,
+                        bytes,
+                        limits.max_bytes_per_day
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    /// Usage and limits of the configured quota scopes and of the
+    /// organizations and tasks that recorded today
+    pub async fn quota_usage(&self) -> QuotasResponse {
+        let mut scopes = self.quotas.known_scopes();
+        for session in self.session_list() {
+            if let Some(organization) = &session.metadata.organization {
+                scopes.push((QuotaScope::Organization, organization.clone()));
+            }
+            if let Some(task_id) = &session.metadata.task_id {
+                scopes.push((QuotaScope::Task, task_id.clone()));
+            }
+        }
+        scopes.retain(|(_, name)| !name.is_empty());
+        scopes.sort();
+        scopes.dedup();
+
+        let mut quotas = Vec::with_capacity(scopes.len());
+        for (scope, name) in scopes {
+            let limits = self.quotas.limits(scope, &name);
+            quotas.push(QuotaUsage {
+                scope,
+                active_recordings: self.active_recordings(scope, &name, None).await,
+                max_concurrent_recordings: limits.max_concurrent_recordings,
+                bytes_today: self.quotas.bytes_today(scope, &name),
+                max_bytes_per_day: limits.max_bytes_per_day,
+                name,
+            });
+        }
+        QuotasResponse {
+            success: true,
+            message: format!("{} quota scopes", quotas.len()),
+            quotas,
+        }
+    }
+
+    /// Store the latest sample of each requested topic as one batch
+    ///
+    /// Nothing is recorded: topics of active recordings contribute the last
+    /// sample their subscriber received, the others the next one within the
+    /// timeout. The batch goes to the `snapshots` entry of the requested
+    /// bucket and its manifest to `snapshots_metadata`, at the same timestamp.
+    #[tracing::instrument(skip_all, fields(snapshot_id, device_id = %self.config.recorder.device_id))]
+    pub async fn snapshot(&self, mut request: RecorderRequest) -> SnapshotResponse {
+        let timeout = {
+            let live = self.live_config.read().unwrap();
+            if request.topics.is_empty() {
+                request.topics = live.recorder.snapshot.topics.clone();
+            }
+            request
+                .wait_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or_else(|| live.recorder.snapshot.timeout())
+        };
+        let mut errors = validation::validate_snapshot(&request);
+        if let Err(error) = {
+            let live = self.live_config.read().unwrap();
+            validation::limit_compression(&mut request, &live.recorder.compression)
+        } {
+            errors.push(error);
+        }
+        if !errors.is_empty() {
+            warn!("Rejected Snapshot request with {} problem(s)", errors.len());
+            return SnapshotResponse::error(
+                ErrorCode::InvalidRequest,
+                RecorderResponse::invalid(errors).message,
+            );
+        }
+
+        let snapshot_id = Uuid::new_v4().to_string();
+        tracing::Span::current().record("snapshot_id", snapshot_id.as_str());
+        let storage = match self.bucket_backend(request.bucket.as_deref()).await {
+            Ok(storage) => storage,
+            Err(e) => {
+                error!("Failed to initialize storage backend: {}", e);
+                return SnapshotResponse::error(
+                    ErrorCode::BackendUnavailable,
+                    format!("Failed to initialize storage: {}", e),
+                );
+            }
+        };
+
+        let (captured, mut missing) =
+            snapshot::capture(&self.subscription_hub, &request.topics, timeout).await;
+        let mut topics: Vec<SnapshotTopic> = captured
+            .iter()
+            .map(|capture| SnapshotTopic {
+                topic: capture.topic.clone(),
+                key_expr: capture.sample.key_expr().to_string(),
+                received_ns: capture.received_ns,
+                cached: capture.cached,
+                payload_bytes: capture.sample.payload().len() as u64,
+            })
+            .collect();
+
+        // Stored under the recorded names, which the sample transforms and
+        // schemas are configured for
+        let samples: Vec<(String, Sample, u64)> = captured
+            .into_iter()
+            .map(|c| (self.recorded_topic(&c.topic), c.sample, c.received_ns))
+            .collect();
+        let serializer = McapSerializer::with_schema_config(
+            request.compression_type,
+            request.compression_level,
+            self.config.recorder.schema.clone(),
+        )
+        .with_schema_registry(self.schema_registry.clone())
+        .with_clock(self.clock.clone());
+        let sample_transforms = self.sample_transforms.clone();
+        let serialized = self
+            .compression_pool
+            .run(&CancelToken::default(), {
+                let snapshot_id = snapshot_id.clone();
+                move || {
+                    serializer.serialize_snapshot(
+                        snapshot::SNAPSHOT_ENTRY,
+                        samples,
+                        |topic| sample_transforms.read().unwrap().for_topic(topic),
+                        &snapshot_id,
+                    )
+                }
+            })
+            .await;
+        let data = match serialized {
+            Some(Ok((data, dropped))) => {
+                topics.retain(|t| !dropped.contains(&self.recorded_topic(&t.topic)));
+                missing.extend(
+                    request
+                        .topics
+                        .iter()
+                        .filter(|topic| dropped.contains(&self.recorded_topic(topic)))
+                        .cloned(),
+                );
+                data
+            }
+            Some(Err(e)) => {
+                error!("Failed to serialize snapshot: {}", e);
+                return SnapshotResponse::error(
+                    ErrorCode::Internal,
+                    format!("Failed to serialize snapshot: {}", e),
+                );
+            }
+            None => unreachable!("snapshots are never cancelled"),
+        };
+        if data.is_empty() {
+            warn!("Snapshot captured no sample of {:?}", request.topics);
+            return SnapshotResponse::error(
+                ErrorCode::NotFound,
+                format!("No sample of the requested topics within {:?}", timeout),
+            );
+        }
+
+        let allocation = self.record_timestamps.allocate(
+            snapshot::SNAPSHOT_ENTRY,
+            self.clock.since_epoch().as_micros() as u64,
+        );
+        let timestamp_us = allocation.timestamp_us;
+        let manifest = SnapshotManifest {
+            snapshot_id: snapshot_id.clone(),
+            device_id: request.device_id.clone(),
+            taken_at: rfc3339(self.clock.now()),
+            entry: snapshot::SNAPSHOT_ENTRY.to_string(),
+            timestamp_us,
+            compression_type: format!("{:?}", request.compression_type),
+            compression_level: request.compression_level as i32,
+            bytes: data.len() as u64,
+            topics,
+            missing,
+            labels: request.labels.clone(),
+        };
+
+        // Request tags first, so the system labels win
+        let mut labels = request.labels.clone();
+        labels.insert("snapshot_id".to_string(), snapshot_id.clone());
+        labels.insert("device_id".to_string(), request.device_id.clone());
+        let mut batch_labels = labels.clone();
+        batch_labels.extend(allocation.label);
+        batch_labels.insert("format".to_string(), "mcap".to_string());
+        batch_labels.insert(CHECKSUM_LABEL.to_string(), checksum(&data));
+        let manifest_bytes = match serde_json::to_vec(&manifest) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return SnapshotResponse::error(
+                    ErrorCode::Internal,
+                    format!("Failed to serialize snapshot manifest: {}", e),
+                )
+            }
+        };
+        let written = async {
+            storage
+                .write_with_retry(
+                    snapshot::SNAPSHOT_ENTRY,
+                    timestamp_us,
+                    data,
+                    batch_labels,
+                    3,
+                )
+                .await?;
+            storage
+                .write_with_retry(
+                    snapshot::SNAPSHOT_METADATA_ENTRY,
+                    timestamp_us,
+                    manifest_bytes,
+                    labels,
+                    3,
+                )
+                .await
+        };
+        if let Err(e) = written.await {
+            error!("Failed to store snapshot '{}': {}", snapshot_id, e);
+            return SnapshotResponse::error(e.code(), format!("Failed to store snapshot: {}", e));
+        }
+        self.quotas.record(
+            request.organization.as_deref(),
+            request.task_id.as_deref(),
+            manifest.bytes,
+        );
+
+        info!(
+            "Stored snapshot '{}' of {} topics ({} missing, {} bytes)",
+            snapshot_id,
+            manifest.topics.len(),
+            manifest.missing.len(),
+            manifest.bytes
+        );
+        SnapshotResponse {
+            success: true,
+            message: format!("Snapshot of {} topics stored", manifest.topics.len()),
+            snapshot_id: Some(snapshot_id),
+            manifest: Some(manifest),
+            error_code: None,
+        }
+    }
+
+    /// Store the current contents of the black box rings as a new recording
+    ///
+    /// Dumps `topics`, by default every `black_box.topics`. The recording is
+    /// created like a Start without subscribers, filled with the ring
+    /// samples as of their receive times and moved to `Uploading`; the
+    /// caller completes it with `complete_finish`. The rings keep filling.
+    pub async fn dump(&self, mut request: RecorderRequest) -> RecorderResponse {
+        let black_box_topics = self.black_box.topics();
+        if black_box_topics.is_empty() {
+            return RecorderResponse::error(
+                ErrorCode::InvalidState,
+                "No black_box topics are configured".to_string(),
+            );
+        }
+        if request.topics.is_empty() {
+            request.topics = black_box_topics.clone();
+        }
+        let errors = validation::validate_dump(&request, &black_box_topics);
+        if !errors.is_empty() {
+            warn!("Rejected Dump request with {} problem(s)", errors.len());
+            return RecorderResponse::invalid(errors);
+        }
+
+        let rings: Vec<(String, Vec<(Sample, u64)>)> = self
+            .black_box
+            .contents()
+            .into_iter()
+            .filter(|(topic, samples)| request.topics.contains(topic) && !samples.is_empty())
+            .collect();
+        if rings.is_empty() {
+            return RecorderResponse::error(
+                ErrorCode::NotFound,
+                "The black box holds no sample of the requested topics".to_string(),
+            );
+        }
+
+        // A dump never adds to an existing recording
+        request.if_exists = Some(IfExists::Error);
+        let response = self
+            .open_recording(request, false, RecordingLinks::default())
+            .await;
+        let Some(recording_id) = response.recording_id.clone().filter(|_| response.success) else {
+            return response;
+        };
+        let Some(session) = self.sessions.get(&recording_id).map(|s| s.value().clone()) else {
+            return RecorderResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            );
+        };
+
+        let mut dumped = 0;
+        for (topic, samples) in rings {
+            let Some(buffer) = session.topic_buffers.get(&topic).map(|b| b.value().clone()) else {
+                continue;
+            };
+            for (sample, received_ns) in samples {
+                session.throughput.record_ingest(sample.payload().len());
+                match buffer.push_sample_received(sample, received_ns).await {
+                    Ok(()) => dumped += 1,
+                    Err(e) => error!("Failed to push sample to buffer: {}", e),
+                }
+            }
+        }
+        info!(
+            "Dumped {} black box samples into recording '{}'",
+            dumped, recording_id
+        );
+
+        let finishing = self.begin_finish(&recording_id).await;
+        if !finishing.success {
+            return finishing;
+        }
+        RecorderResponse {
+            message: format!("Dumped {} samples, recording is uploading", dumped),
+            status: Some(RecordingStatus::Uploading),
+            ..RecorderResponse::success(
+                Some(recording_id),
+                session.storage.bucket().map(String::from),
+            )
+        }
+    }
+
+    /// Status of every recording, by recording ID
+    #[cfg_attr(not(feature = "dashboard"), allow(dead_code))]
+    pub async fn recording_statuses(&self) -> Vec<(String, StatusResponse)> {
+        let mut statuses = Vec::new();
+        for session in self.session_list() {
+            statuses.push((
+                session.recording_id.clone(),
+                session.status_response().await,
+            ));
+        }
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        statuses
+    }
+
+    /// Get recording status
+    pub async fn get_status(&self, recording_id: &str) -> StatusResponse {
+        match self.sessions.get(recording_id).map(|s| s.value().clone()) {
+            Some(session) => StatusResponse {
+                flush_workers: self.flush_pool.stats(),
+                compression: Some(self.compression_pool.stats()),
+                record_timestamps: Some(self.record_timestamps.stats()),
+                ..session.status_response().await
+            },
+            None => StatusResponse::error(
+                ErrorCode::NotFound,
+                format!("Recording '{}' not found", recording_id),
+            ),
+        }
+    }
+
+    /// Live, down-sampled copy of the tapped topics of a recording
+    /// (`recorder.tap.per_topic`)
+    ///
+    /// The stream ends once the recording stops receiving samples; None when
+    /// the recording doesn't exist or already stopped.
+    #[allow(dead_code)]
+    pub fn tap(&self, recording_id: &str) -> Option<impl Stream<Item = TapSample>> {
+        let session = self.sessions.get(recording_id)?;
+        session.tap.subscribe().map(tap::stream)
+    }
+
+    /// Publish the status of a recording as a status event (when enabled)
+    async fn publish_status(&self, session: &RecordingSession) {
+        if let Some(events) = &self.status_events {
+            events.publish(
+                &self.tasks,
+                &session.recording_id,
+                &session.status_response().await,
+            );
+        }
+    }
+
+    /// Run the hooks of a recording event (when configured)
+    async fn fire_hooks(
+        &self,
+        session: &RecordingSession,
+        event: RecordingEventKind,
+        error: Option<String>,
+    ) {
+        Self::fire_session_hooks(
+            &self.hooks,
+            &self.config.recorder.device_id,
+            self.clock.as_ref(),
+            session,
+            event,
+            error,
+        )
+        .await;
+    }
+
+    async fn fire_session_hooks(
+        hooks: &Option<Arc<HookRunner>>,
+        device_id: &str,
+        clock: &dyn Clock,
+        session: &RecordingSession,
+        event: RecordingEventKind,
+        error: Option<String>,
+    ) {
+        if let Some(hooks) = hooks {
+            hooks.fire(RecordingEvent {
+                event,
+                recording_id: session.recording_id.clone(),
+                device_id: device_id.to_string(),
+                at: rfc3339(clock.now()),
+                error,
+                status: session.status_response().await,
+            });
+        }
+    }
+
+    /// Get the payload schema drift report of a recording
+    pub async fn get_drift_report(&self, recording_id: &str) -> DriftReportResponse {
+        match self.sessions.get(recording_id) {
+            Some(session) => DriftReportResponse {
+                success: true,
+                message: "Drift report retrieved successfully".to_string(),
+                recording_id: Some(recording_id.to_string()),
+                topics: session.drift.signatures(),
+                events: session.drift.events(),
+            },
+            None => DriftReportResponse {
+                success: false,
+                message: format!("Recording '{}' not found", recording_id),
+                recording_id: None,
+                topics: vec![],
+                events: vec![],
+            },
+        }
+    }
+
+    /// Write metadata to storage backend
+    async fn write_metadata(&self, session: &RecordingSession) -> Result<RecordingMetadata> {
+        let metadata = self.final_metadata(session).await;
+        Self::write_metadata_record(
+            session.storage.as_ref(),
+            &self.record_timestamps,
+            &metadata,
+            session.start_time,
+        )
+        .await?;
+        Ok(metadata)
+    }
+
+    /// Upload a finished recording to the ingestion API in the background
+    /// and mark it `Archived` once accepted (when `recorder.ingestion` is
+    /// enabled)
+    fn spawn_ingestion(&self, session: &Arc<RecordingSession>, metadata: RecordingMetadata) {
+        let Some(ingestion) = self.ingestion.clone() else {
+            return;
+        };
+        let session = session.clone();
+        let status_events = self.status_events.clone();
+        let hooks = self.hooks.clone();
+        let device_id = self.config.recorder.device_id.clone();
+        let tasks = self.tasks.clone();
+        let clock = self.clock.clone();
+        self.tasks.spawn(
+            TaskStage::Flush,
+            format!("ingestion of {}", session.recording_id),
+            async move {
+                let recording_id = &session.recording_id;
+                match ingestion.ingest(session.storage.as_ref(), &metadata).await {
+                    Ok(summary) => {
+                        info!(
+                            "Recording '{}' archived ({} records, {} bytes sent, {} bytes resumed)",
+                            recording_id, summary.records, summary.bytes, summary.resumed_bytes
+                        );
+                        {
+                            let mut status = session.status.write().await;
+                            if *status != RecordingStatus::Finished {
+                                return;
+                            }
+                            *status = RecordingStatus::Archived;
+                        }
+                        if let Some(events) = &status_events {
+                            events.publish(&tasks, recording_id, &session.status_response().await);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to ingest recording '{}': {:#}", recording_id, e);
+                        Self::fire_session_hooks(
+                            &hooks,
+                            &device_id,
+                            clock.as_ref(),
+                            &session,
+                            RecordingEventKind::Error,
+                            Some(format!("Failed to ingest the recording: {:#}", e)),
+                        )
+                        .await;
+                    }
+                }
+            },
+        );
+    }
+
+    /// Metadata of an ending recording, with the records uploaded so far
+    async fn final_metadata(&self, session: &RecordingSession) -> RecordingMetadata {
+        let mut metadata = session.metadata.clone();
+        metadata.end_time = Some(rfc3339(self.clock.now()));
+        metadata.total_bytes = *session.total_bytes.read().await;
+        metadata.schema_drift = session.drift.events();
+        metadata.handoff = session.handoff.read().await.clone();
+        metadata.topic_changes = session.topic_changes.read().await.clone();
+        metadata.session_gaps = session.session_gaps.read().await.clone();
+        metadata.records = session.records.read().await.clone();
+        metadata.verify_failures = session.verify_failures.load(Ordering::Relaxed);
+        metadata.records_verified = session.records_verified.load(Ordering::Relaxed);
+        include_changed_topics(&mut metadata);
+        metadata.topic_aliases = self.topic_aliases(&metadata.topics);
+        metadata.previews = session
+            .previews
+            .iter()
+            .map(|p| {
+                let recorded = self.recorded_topic(p.key());
+                (recorded, p.buffer().topic_name().to_string())
+            })
+            .collect();
+
+        // Keyed by recorded name, like the records and their totals
+        let mut per_topic_stats = serde_json::Map::new();
+        for entry in session.topic_buffers.iter() {
+            let (missing, rejected) = entry.value().timestamp_stats();
+            let mut stats = serde_json::json!({
+                "missing_timestamps": missing,
+                "rejected_samples": rejected,
+            });
+            let suppressed = entry.value().duplicates_suppressed();
+            if suppressed > 0 {
+                stats["duplicates_suppressed"] = suppressed.into();
+            }
+            stats["high_watermark_bytes"] = entry.value().high_watermark_bytes().into();
+            stats["flushes"] =
+                serde_json::to_value(entry.value().flush_stats().0).unwrap_or_default();
+            per_topic_stats.insert(self.recorded_topic(entry.key()), stats);
+        }
+        for entry in session.topic_totals.iter() {
+            let totals = entry.value();
+            let stats = per_topic_stats
+                .entry(entry.key().clone())
+                .or_insert_with(|| serde_json::json!({}));
+            let raw_bytes = totals.raw_bytes.load(Ordering::Relaxed);
+            let stored_bytes = totals.stored_bytes.load(Ordering::Relaxed);
+            stats["samples"] = totals.samples.load(Ordering::Relaxed).into();
+            stats["raw_bytes"] = raw_bytes.into();
+            stats["stored_bytes"] = stored_bytes.into();
+            if let Some(ratio) = compression_ratio(raw_bytes, stored_bytes) {
+                stats["compression_ratio"] = ratio.into();
+            }
+        }
+        metadata.per_topic_stats = serde_json::Value::Object(per_topic_stats);
+        metadata.payload_bytes = session.payload_bytes();
+        metadata.compression_ratio =
+            compression_ratio(metadata.payload_bytes, metadata.total_bytes as u64);
+        metadata.formats = session
+            .formats
+            .iter()
+            .map(|f| (f.key().clone(), f.value().clone()))
+            .collect();
+        metadata
+    }
+
+    /// Fold the per-topic totals of a finished recording into the topic statistics
+    async fn update_topic_stats(&self, session: &RecordingSession) {
+        let Some(stats) = &self.topic_stats else {
+            return;
+        };
+
+        // Topics attached or detached midway weren't recorded for the whole duration
+        let changed: Vec<String> = session
+            .topic_changes
+            .read()
+            .await
+            .iter()
+            .map(|change| self.recorded_topic(&change.topic))
+            .collect();
+        let duration = self
+            .clock
+            .now()
+            .duration_since(session.start_time)
+            .unwrap_or_default();
+        for entry in session.topic_totals.iter() {
+            if !changed.contains(entry.key()) {
+                stats.observe(entry.key(), entry.value(), duration);
+            }
+        }
+        if let Err(e) = stats.save().await {
+            warn!("Failed to save topic statistics: {:#}", e);
+        }
+    }
+
+    /// Write a metadata record keyed by the recording start time
+    ///
+    /// The timestamp comes from `timestamps`, so recordings started within
+    /// the same microsecond don't overwrite each other's metadata.
+    pub(crate) async fn write_metadata_record(
+        storage: &dyn StorageBackend,
+        timestamps: &RecordTimestamps,
+        metadata: &RecordingMetadata,
+        start_time: SystemTime,
+    ) -> Result<()> {
+        let start_us = start_time
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| RecorderError::InvalidState(e.to_string()))?
+            .as_micros() as u64;
+        let allocation = timestamps.allocate(METADATA_ENTRY, start_us);
+
+        let mut labels = metadata.labels.clone();
+        labels.extend(allocation.label);
+        labels.insert("recording_id".to_string(), metadata.recording_id.clone());
+        labels.insert("device_id".to_string(), metadata.device_id.clone());
+        if let Some(scene) = &metadata.scene {
+            labels.insert("scene".to_string(), scene.clone());
+        }
+        if let Some(group_id) = &metadata.group_id {
+            labels.insert("group_id".to_string(), group_id.clone());
+        }
+        if metadata.interrupted {
+            labels.insert("interrupted".to_string(), "true".to_string());
+        }
+        for (key, value) in &metadata.environment {
+            labels.insert(format!("env_{}", key), value.clone());
+        }
+        if let Some(expires_at) = metadata.expires_at {
+            labels.insert(EXPIRES_AT_LABEL.to_string(), expires_at.to_string());
+        }
+        if let Some(handoff) = &metadata.handoff {
+            if let Some(predecessor) = &handoff.predecessor {
+                labels.insert(
+                    "handoff_predecessor".to_string(),
+                    predecessor.recording_id.clone(),
+                );
+            }
+            if let Some(successor) = &handoff.successor {
+                labels.insert(
+                    "handoff_successor".to_string(),
+                    successor.recording_id.clone(),
+                );
+            }
+        }
+        let metadata = serde_json::to_vec(metadata).map_err(RecorderError::serialization)?;
+
+        storage
+            .write_with_retry(METADATA_ENTRY, allocation.timestamp_us, metadata, labels, 3)
+            .await
+    }
+
+    /// Everything the flush workers need to store batches
+    fn flush_context(&self) -> FlushContext {
+        let original_topics: Arc<HashMap<String, String>> = Arc::new(
+            self.config
+                .recorder
+                .topic_remap
+                .iter()
+                .map(|(topic, name)| (name.clone(), topic.clone()))
+                .collect(),
+        );
+        FlushContext {
+            sessions: self.sessions.clone(),
+            schema_config: self.config.recorder.schema.clone(),
+            state_store: self.state_store.clone(),
+            schema_registry: self.schema_registry.clone(),
+            upload_limiter: self.upload_limiter.clone(),
+            topic_stats: self.topic_stats.clone(),
+            status_events: self.status_events.clone(),
+            hooks: self.hooks.clone(),
+            tasks: self.tasks.clone(),
+            clock: self.clock.clone(),
+            work_dirs: self.work_dirs.clone(),
+            upload_gate: self.upload_gate.clone(),
+            coalescer: self.coalescer.clone(),
+            shutdown_spill: self.shutdown_spill.clone(),
+            device_id: self.config.recorder.device_id.clone(),
+            integrity: self.config.recorder.integrity.clone(),
+            compression_pool: self.compression_pool.clone(),
+            seekable_frame_bytes: self.config.recorder.compression.seekable_frame_bytes,
+            sample_transforms: self.sample_transforms.clone(),
+            topic_compression: self.config.recorder.compression.per_topic.clone(),
+            timestamps: self.config.recorder.timestamps.clone(),
+            record_timestamps: self.record_timestamps.clone(),
+            quotas: self.quotas.clone(),
+            max_record_size_bytes: self.max_record_size_bytes.clone(),
+            original_topics,
+            progress_interval: self.config.recorder.status_events.progress_interval(),
+            attachment_labels: Arc::new(self.config.recorder.attachment_labels.clone()),
+            index: self.config.recorder.index.enabled,
+        }
+    }
+
+    /// Start flush worker threads
+    ///
+    /// Each worker drains its own partition of the flush pool, so the flush
+    /// tasks of a topic are written one after another, in order.
+    fn start_flush_workers(&self) {
+        let context = self.flush_context();
+        if let (Some(gate), Some(work_dirs)) = (&self.upload_gate, &self.work_dirs) {
+            self.tasks.spawn_service(
+                TaskStage::Flush,
+                "deferred uploads",
+                Self::upload_deferred(gate.clone(), work_dirs.clone(), context.clone()),
+            );
+        }
+        if let Some(coalescer) = &self.coalescer {
+            self.tasks.spawn_service(
+                TaskStage::Flush,
+                "coalesced writes",
+                Self::write_due_coalesced(coalescer.clone(), context.clone()),
+            );
+        }
+        for i in 0..self.flush_pool.workers() {
+            let flush_pool = self.flush_pool.clone();
+            let context = context.clone();
+
+            let name = format!("flush worker {}", i);
+            self.tasks
+                .spawn_service(TaskStage::Flush, name, async move {
+                    debug!("Flush worker {} started", i);
+                    loop {
+                        let Dequeued {
+                            task,
+                            queued,
+                            evicted,
+                        } = flush_pool.recv(i).await;
+                        if evicted {
+                            let recording_id = task.recording_id.clone();
+                            Self::drop_evicted_task(task, &context);
+                            flush_pool.task_done(&recording_id);
+                            continue;
+                        }
+                        let span = info_span!(
+                            "flush",
+                            recording_id = %task.recording_id,
+                            device_id = %context.device_id,
+                            topic = %task.topic,
+                            worker = i,
+                            samples = task.samples.len(),
+                            bytes = task.samples.iter().map(|s| s.payload().len()).sum::<usize>(),
+                            queued_ms = queued.as_millis() as u64
+                        );
+                        let started = Instant::now();
+                        let recording_id = task.recording_id.clone();
+                        Self::process_flush_task(task, &context)
+                            .instrument(span)
+                            .await;
+                        flush_pool.record_busy(i, started.elapsed());
+                        flush_pool.task_done(&recording_id);
+                    }
+                });
+        }
+    }
+
+    /// Account the samples of a task evicted from a full flush queue
+    fn drop_evicted_task(task: FlushTask, context: &FlushContext) {
+        warn!(
+            "Flush queue full, dropped {} samples of topic '{}' for higher-priority topics",
+            task.samples.len(),
+            task.topic
+        );
+        let Some(session) = context.sessions.get(&task.recording_id) else {
+            return;
+        };
+        session
+            .throughput
+            .settle(task.samples.iter().map(|s| s.payload().len()).sum());
+        let buffer = session
+            .topic_buffers
+            .iter()
+            .find(|buffer| buffer.topic_name() == task.topic)
+            .map(|buffer| buffer.value().clone());
+        if let Some(buffer) = buffer {
+            buffer.count_dropped(task.samples.len());
+        }
+    }
+
+    /// Process a flush task
+    async fn process_flush_task(task: FlushTask, context: &FlushContext) {
+        let schema_config = &context.schema_config;
+        debug!(
+            "Processing flush task for topic '{}' ({} samples)",
+            task.topic,
+            task.samples.len()
+        );
+
+        let session = match context.sessions.get(&task.recording_id) {
+            Some(s) => s.value().clone(),
+            None => {
+                warn!(
+                    "Recording session '{}' not found, dropping flush task",
+                    task.recording_id
+                );
+                return;
+            }
+        };
+
+        // Compare the segment's payload structure with the previous one
+        if schema_config.detect_drift {
+            let payloads: Vec<_> = task
+                .samples
+                .iter()
+                .take(schema_config.drift_samples_per_batch)
+                .map(|sample| sample.payload().to_bytes())
+                .collect();
+            if let Some(event) = session
+                .drift
+                .observe(&task.topic, payloads.iter().map(|p| p.as_ref()))
+            {
+                warn!(
+                    "Payload schema drift on topic '{}' in recording '{}': +{:?} -{:?}",
+                    event.topic, task.recording_id, event.added_fields, event.removed_fields
+                );
+            }
+        }
+
+        // Tag the topic with the format of its first samples
+        if format_sniff::enabled_for(schema_config, &task.topic)
+            && !session.formats.contains_key(&task.topic)
+        {
+            let payloads: Vec<_> = task
+                .samples
+                .iter()
+                .take(format_sniff::SNIFF_SAMPLES)
+                .map(|sample| sample.payload().to_bytes())
+                .collect();
+            if let Some(format) = format_sniff::detect(payloads.iter().map(|p| p.as_ref())) {
+                debug!(
+                    "Detected '{}' payloads on topic '{}' in recording '{}'",
+                    format, task.topic, task.recording_id
+                );
+                session
+                    .formats
+                    .insert(task.topic.clone(), format.to_string());
+            }
+        }
+
+        // Topics with their own settings override the recording's
+        let (compression_type, compression_level) =
+            find_per_topic(&context.topic_compression, &task.topic)
+                .and_then(|topic_compression| topic_compression.settings())
+                .unwrap_or((session.compression_type, session.compression_level));
+        // Skip compression of topics where it was found not to pay off
+        let compression_type = match &context.topic_stats {
+            Some(stats)
+                if compression_type != CompressionType::None
+                    && stats.is_incompressible(&task.topic) =>
+            {
+                CompressionType::None
+            }
+            _ => compression_type,
+        };
+        // Oversized batches are stored as several records
+        let parts = task.split(context.max_record_size_bytes.load(Ordering::Relaxed));
+        let count = parts.len();
+        if count > 1 {
+            debug!("Splitting flush batch into {} records", count);
+        }
+        for (index, part) in parts.into_iter().enumerate() {
+            let part_index = (count > 1).then_some((index, count));
+            Self::upload_part(
+                part,
+                part_index,
+                &session,
+                (compression_type, compression_level),
+                context,
+            )
+            .await;
+        }
+    }
+
+    /// Drive an upload, publishing status events with its progress every
+    /// `progress_interval` until it is done
+    async fn report_progress<F: std::future::Future<Output = Result<()>>>(
+        upload: F,
+        session: &RecordingSession,
+        context: &FlushContext,
+    ) -> Result<()> {
+        let (Some(events), Some(interval)) = (&context.status_events, context.progress_interval)
+        else {
+            return upload.await;
+        };
+        tokio::pin!(upload);
+        loop {
+            tokio::select! {
+                result = &mut upload => return result,
+                _ = runtime::sleep(interval) => {
+                    events.publish(
+                        &context.tasks,
+                        &session.recording_id,
+                        &session.status_response().await,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Serialize and upload one record of a flush task
+    ///
+    /// `part` is (index, count) when the task was split.
+    async fn upload_part(
+        task: FlushTask,
+        part: Option<(usize, usize)>,
+        session: &RecordingSession,
+        (compression_type, compression_level): (CompressionType, CompressionLevel),
+        context: &FlushContext,
+    ) {
+        let schema_config = &context.schema_config;
+        let sample_count = task.samples.len();
+        let raw_bytes: usize = task.samples.iter().map(|s| s.payload().len()).sum();
+        let batch_labels = attachment_labels(&task.samples, &context.attachment_labels);
+
+        // Serialize to MCAP on the compression pool
+        let serializer = McapSerializer::with_schema_config(
+            compression_type,
+            compression_level,
+            schema_config.clone(),
+        )
+        .with_schema_registry(context.schema_registry.clone())
+        .with_seekable_frame_bytes(context.seekable_frame_bytes)
+        .with_sample_transforms(
+            context
+                .sample_transforms
+                .read()
+                .unwrap()
+                .for_topic(&task.topic),
+        )
+        .with_transform(crate::transform::for_topic(
+            &context.topic_compression,
+            &task.topic,
+        ))
+        .with_detected_format(session.formats.get(&task.topic).map(|f| f.clone()))
+        .with_clock(context.clock.clone())
+        .with_clock_offset_ns(
+            find_per_topic(&context.timestamps.per_topic, &task.topic)
+                .unwrap_or(&context.timestamps.default)
+                .clock_offset_ns,
+        )
+        .with_liveliness(
+            liveliness_key(
+                context
+                    .original_topics
+                    .get(&task.topic)
+                    .unwrap_or(&task.topic),
+            )
+            .is_some(),
+        );
+        let FlushTask {
+            topic,
+            samples,
+            recording_id,
+            timestamps_ns,
+            received_ns,
+        } = task;
+        let serialized = context
+            .compression_pool
+            .run(&session.cancel, {
+                let (topic, recording_id) = (topic.clone(), recording_id.clone());
+                move || {
+                    serializer.serialize_indexed_batch(
+                        &topic,
+                        samples,
+                        &timestamps_ns,
+                        &received_ns,
+                        &recording_id,
+                    )
+                }
+            })
+            .await;
+        let (mcap_data, index) = match serialized {
+            Some(Ok(serialized)) => serialized,
+            Some(Err(e)) => {
+                error!("Failed to serialize MCAP data: {}", e);
+                session.throughput.settle(raw_bytes);
+                return;
+            }
+            None => {
+                debug!(
+                    "Dropped flush batch of '{}': recording '{}' was cancelled",
+                    topic, recording_id
+                );
+                session.throughput.settle(raw_bytes);
+                return;
+            }
+        };
+        if mcap_data.is_empty() {
+            debug!("Sample transforms dropped the whole batch of '{}'", topic);
+            session.throughput.settle(raw_bytes);
+            return;
+        }
+
+        // Upload to storage backend
+        let entry_name = topic_to_entry_name(&topic);
+        let allocation = context
+            .record_timestamps
+            .allocate(&entry_name, context.clock.since_epoch().as_micros() as u64);
+        let timestamp_us = allocation.timestamp_us;
+
+        // Request tags first, so the system labels win
+        let mut labels = session.metadata.labels.clone();
+        labels.extend(batch_labels);
+        labels.extend(allocation.label);
+        labels.insert("recording_id".to_string(), recording_id.clone());
+        labels.insert("topic".to_string(), topic.clone());
+        if let Some(original) = context.original_topics.get(&topic) {
+            labels.insert("original_topic".to_string(), original.clone());
+        }
+        labels.insert("format".to_string(), "mcap".to_string());
+        if let Some((index, count)) = part {
+            labels.insert("part".to_string(), format!("{}/{}", index + 1, count));
+        }
+        if let Some(expires_at) = session.metadata.expires_at {
+            labels.insert(EXPIRES_AT_LABEL.to_string(), expires_at.to_string());
+        }
+        let crc32c = checksum(&mcap_data);
+        labels.insert(CHECKSUM_LABEL.to_string(), crc32c.clone());
+
+        let record = DeferredRecord {
+            topic,
+            entry: entry_name,
+            timestamp_us,
+            labels,
+            crc32c,
+            raw_bytes,
+            samples: sample_count,
+            compressed: compression_type != CompressionType::None,
+            index: context.index.then_some(index),
+            parts: Vec::new(),
+        };
+
+        // Small batches wait to be stored together with those of other topics
+        if let Some(coalescer) = context
+            .coalescer
+            .as_ref()
+            .filter(|coalescer| coalescer.accepts(mcap_data.len()))
+        {
+            if let Some(batches) =
+                coalescer.push(&recording_id, mcap_data.len(), (record, mcap_data))
+            {
+                Self::write_coalesced(batches, session, context).await;
+            }
+            return;
+        }
+
+        Self::store_record(record, mcap_data, session, context).await;
+    }
+
+    /// Upload a serialized record, or keep it in the work directory while
+    /// uploads are deferred (or for the next start once the shutdown deadline
+    /// passed)
+    async fn store_record(
+        record: DeferredRecord,
+        data: Vec<u8>,
+        session: &RecordingSession,
+        context: &FlushContext,
+    ) {
+        let defer = context.shutdown_spill.is_armed()
+            || context
+                .upload_gate
+                .as_ref()
+                .is_some_and(|gate| !gate.is_open());
+        if let (true, Some(work_dirs)) = (defer, &context.work_dirs) {
+            match work_dirs
+                .defer(&session.recording_id, &record, data.clone())
+                .await
+            {
+                Ok(path) => {
+                    debug!("Deferred record to {}", path.display());
+                    return;
+                }
+                Err(e) => error!("Failed to defer record, uploading it now: {:#}", e),
+            }
+        }
+
+        Self::write_record(record, data, session, context).await;
+    }
+
+    /// Store batches collected by the coalescer as one record of the
+    /// coalesced entry (a single batch as a record of its own)
+    ///
+    /// The labels of the batches are dropped; the coalesced record carries
+    /// those of the recording.
+    async fn write_coalesced(
+        mut batches: Vec<CoalescedBatch>,
+        session: &RecordingSession,
+        context: &FlushContext,
+    ) {
+        if batches.len() == 1 {
+            let (record, data) = batches.remove(0);
+            Self::store_record(record, data, session, context).await;
+            return;
+        }
+
+        let raw_bytes = batches.iter().map(|(record, _)| record.raw_bytes).sum();
+        let samples = batches.iter().map(|(record, _)| record.samples).sum();
+        let compressed = batches.iter().any(|(record, _)| record.compressed);
+        let count = batches.len();
+        let parts = batches.into_iter().map(|(record, data)| {
+            let part = CoalescedPart {
+                topic: record.topic,
+                entry: record.entry,
+                timestamp_us: record.timestamp_us,
+                offset: 0,
+                length: 0,
+                crc32c: record.crc32c,
+                raw_bytes: record.raw_bytes,
+                samples: record.samples,
+                compressed: record.compressed,
+            };
+            (part, data)
+        });
+        let (data, parts) = match coalesce::encode(&session.recording_id, parts) {
+            Ok(coalesced) => coalesced,
+            Err(e) => {
+                error!("Failed to coalesce {} batches: {}", count, e);
+                session.throughput.settle(raw_bytes);
+                return;
+            }
+        };
+
+        let allocation = context.record_timestamps.allocate(
+            COALESCED_ENTRY,
+            context.clock.since_epoch().as_micros() as u64,
+        );
+        let mut labels = session.metadata.labels.clone();
+        labels.extend(allocation.label);
+        labels.insert("recording_id".to_string(), session.recording_id.clone());
+        labels.insert("format".to_string(), "coalesced".to_string());
+        labels.insert("batches".to_string(), count.to_string());
+        if let Some(expires_at) = session.metadata.expires_at {
+            labels.insert(EXPIRES_AT_LABEL.to_string(), expires_at.to_string());
+        }
+        let crc32c = checksum(&data);
+        labels.insert(CHECKSUM_LABEL.to_string(), crc32c.clone());
+        debug!(
+            "Coalesced {} batches of recording '{}' into {} bytes",
+            count,
+            session.recording_id,
+            data.len()
+        );
+
+        let record = DeferredRecord {
+            topic: COALESCED_ENTRY.to_string(),
+            entry: COALESCED_ENTRY.to_string(),
+            timestamp_us: allocation.timestamp_us,
+            labels,
+            crc32c,
+            raw_bytes,
+            samples,
+            compressed,
+            index: None,
+            parts,
+        };
+        Self::store_record(record, data, session, context).await;
+    }
+
+    /// Store the batches of every recording collecting for
+    /// `coalesce.interval_ms` (runs until dropped)
+    async fn write_due_coalesced(coalescer: Arc<Coalescer<CoalescedBatch>>, context: FlushContext) {
+        let period = (coalescer.interval() / 4).max(Duration::from_millis(10));
+        loop {
+            runtime::sleep(period).await;
+            for recording_id in coalescer.due() {
+                let Some(session) = context
+                    .sessions
+                    .get(&recording_id)
+                    .map(|s| s.value().clone())
+                else {
+                    coalescer.take(&recording_id);
+                    continue;
+                };
+                let _storing = session.coalesce_lock.lock().await;
+                let batches = coalescer.take(&recording_id);
+                if !batches.is_empty() {
+                    Self::write_coalesced(batches, &session, &context).await;
+                }
+            }
+        }
+    }
+
+    /// Upload deferred records whenever the upload gate is open (runs until
+    /// dropped)
+    async fn upload_deferred(
+        gate: Arc<UploadGate>,
+        work_dirs: Arc<WorkDirs>,
+        context: FlushContext,
+    ) {
+        loop {
+            gate.wait_open().await;
+            let sessions: Vec<_> = context.sessions.iter().map(|e| e.value().clone()).collect();
+            for session in sessions {
+                if *session.status.read().await == RecordingStatus::Cancelled {
+                    continue;
+                }
+                for sidecar in work_dirs.deferred(&session.recording_id).await {
+                    // Past the shutdown deadline they're kept for the next start
+                    if !gate.is_open() || context.shutdown_spill.is_armed() {
+                        break;
+                    }
+                    let (record, data) = match work_dirs.load_deferred(&sidecar).await {
+                        Ok(record) => record,
+                        Err(e) => {
+                            warn!("Skipping deferred record: {:#}", e);
+                            continue;
+                        }
+                    };
+                    // A failed upload spills the record again
+                    if let Err(e) = work_dirs.remove_deferred(&sidecar).await {
+                        warn!("Skipping deferred record: {:#}", e);
+                        continue;
+                    }
+                    Self::write_record(record, data, &session, &context).await;
+                }
+            }
+            gate.sleep_interval().await;
+        }
+    }
+
+    /// Write the index record of the record at `timestamp_us` of `entry_name`
+    ///
+    /// A failed write only costs readers the shortcut; the record itself is
+    /// stored.
+    async fn write_index(
+        index: BatchIndex,
+        entry_name: &str,
+        timestamp_us: u64,
+        topic: &str,
+        session: &RecordingSession,
+    ) {
+        let data = match serde_json::to_vec(&index) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to serialize the index of '{}': {}", topic, e);
+                return;
+            }
+        };
+        let mut labels = index.labels();
+        labels.insert("recording_id".to_string(), session.recording_id.clone());
+        labels.insert("topic".to_string(), topic.to_string());
+        if let Some(expires_at) = session.metadata.expires_at {
+            labels.insert(EXPIRES_AT_LABEL.to_string(), expires_at.to_string());
+        }
+        if let Err(e) = session
+            .storage
+            .write_with_retry(&index_entry(entry_name), timestamp_us, data, labels, 3)
+            .await
+        {
+            warn!(
+                "Failed to write the index of record {} of topic '{}': {}",
+                timestamp_us, topic, e
+            );
+        }
+    }
+
+    /// Upload one serialized record and account for it in its session
+    async fn write_record(
+        record: DeferredRecord,
+        mcap_data: Vec<u8>,
+        session: &RecordingSession,
+        context: &FlushContext,
+    ) {
+        // Kept to spill the record if its upload is abandoned at shutdown
+        let abandoned = context.work_dirs.is_some().then(|| record.clone());
+        let DeferredRecord {
+            topic,
+            entry: entry_name,
+            timestamp_us,
+            labels,
+            crc32c,
+            raw_bytes,
+            samples: sample_count,
+            compressed,
+            index,
+            parts,
+        } = record;
+        let recording_id = &session.recording_id;
+
+        let data_len = mcap_data.len() as i64;
+        let chunks = parts
+            .is_empty()
+            .then(|| chunk_checksums(&mcap_data, CHECKSUM_CHUNK_BYTES));
+        let spill_data = context.work_dirs.is_some().then(|| mcap_data.clone());
+        let _permit = context
+            .upload_limiter
+            .acquire(
+                recording_id,
+                session.metadata.priority.unwrap_or_default(),
+                mcap_data.len(),
+            )
+            .instrument(info_span!("throttle", bytes = data_len))
+            .await;
+        let upload_key = (topic.clone(), timestamp_us);
+        let progress = Arc::new(UploadProgress::new(mcap_data.len() as u64));
+        session.uploads.insert(upload_key.clone(), progress.clone());
+        // Carries its own IDs: deferred uploads run outside of a flush span
+        let span = info_span!(
+            "upload",
+            recording_id = %recording_id,
+            topic = %topic,
+            backend = session.storage.backend_type(),
+            bytes = data_len
+        );
+        let upload = session
+            .storage
+            .write_with_progress(&entry_name, timestamp_us, mcap_data, labels, 3, progress)
+            .instrument(span);
+        let result = tokio::select! {
+            result = Self::report_progress(upload, session, context) => Some(result),
+            _ = context.shutdown_spill.abandon_uploads(), if abandoned.is_some() => None,
+        };
+        session.uploads.remove(&upload_key);
+        session.throughput.settle(raw_bytes);
+        let Some(result) = result else {
+            // Still in flight after the shutdown timeout: kept for the next start
+            if let (Some(work_dirs), Some(record), Some(data)) =
+                (&context.work_dirs, abandoned, spill_data)
+            {
+                match work_dirs.defer(recording_id, &record, data).await {
+                    Ok(path) => warn!("Abandoned upload, kept record in {}", path.display()),
+                    Err(e) => error!("Failed to keep abandoned record: {:#}", e),
+                }
+            }
+            return;
+        };
+        match result {
+            Ok(_) => {
+                debug!("Successfully uploaded flush task for topic '{}'", topic);
+                session
+                    .throughput
+                    .record_upload(raw_bytes, data_len as usize);
+
+                *session.total_bytes.write().await += data_len;
+                context.quotas.record(
+                    session.metadata.organization.as_deref(),
+                    session.metadata.task_id.as_deref(),
+                    data_len as u64,
+                );
+                let batch = {
+                    let mut flushed_batches = session.flushed_batches.write().await;
+                    *flushed_batches += 1;
+                    *flushed_batches
+                };
+                *session.last_flush_us.write().await = Some(timestamp_us);
+                if parts.is_empty() {
+                    session
+                        .topic_totals
+                        .entry(topic.clone())
+                        .or_default()
+                        .record(sample_count, raw_bytes, data_len as usize, compressed);
+                }
+                for part in &parts {
+                    session
+                        .topic_totals
+                        .entry(part.topic.clone())
+                        .or_default()
+                        .record(
+                            part.samples,
+                            part.raw_bytes,
+                            part.length as usize,
+                            part.compressed,
+                        );
+                }
+
+                // Read a sample of the records back to catch silent corruption
+                if context.integrity.should_verify(batch) {
+                    let verified = session
+                        .storage
+                        .verify_record(&entry_name, timestamp_us, &crc32c)
+                        .await;
+                    if verified.is_ok() {
+                        session.records_verified.fetch_add(1, Ordering::Relaxed);
+                    }
+                    match verified {
+                        Ok(true) => {
+                            debug!("Verified record {} of topic '{}'", timestamp_us, topic)
+                        }
+                        Ok(false) => {
+                            session.verify_failures.fetch_add(1, Ordering::Relaxed);
+                            error!(
+                                "Checksum mismatch on record {} of topic '{}' in recording '{}'",
+                                timestamp_us, topic, recording_id
+                            );
+                            // Keep the local copy for a re-upload
+                            if let (Some(work_dirs), Some(data)) = (&context.work_dirs, spill_data)
+                            {
+                                match work_dirs
+                                    .spill(recording_id, &entry_name, timestamp_us, data)
+                                    .await
+                                {
+                                    Ok(path) => warn!("Kept local copy in {}", path.display()),
+                                    Err(e) => error!("Failed to keep local copy: {:#}", e),
+                                }
+                            }
+                        }
+                        Err(e) => warn!(
+                            "Failed to verify record {} of topic '{}': {}",
+                            timestamp_us, topic, e
+                        ),
+                    }
+                }
+                if parts.is_empty() {
+                    session.records.write().await.push(RecordChecksum {
+                        topic: topic.clone(),
+                        entry: entry_name.clone(),
+                        timestamp_us,
+                        bytes: data_len as usize,
+                        crc32c,
+                        coalesced: None,
+                        chunks,
+                    });
+                } else {
+                    session.records.write().await.extend(
+                        parts
+                            .iter()
+                            .map(|part| coalesce::record_checksum(part, timestamp_us)),
+                    );
+                }
+                if let Some(index) = index {
+                    Self::write_index(index, &entry_name, timestamp_us, &topic, session).await;
+                }
+
+                // Ended sessions already had their state removed; don't resurrect it
+                let active = matches!(
+                    *session.status.read().await,
+                    RecordingStatus::Recording | RecordingStatus::Paused
+                );
+                if let Some(events) = &context.status_events {
+                    events.publish(
+                        &context.tasks,
+                        recording_id,
+                        &session.status_response().await,
+                    );
+                }
+                if let (Some(store), true) = (&context.state_store, active) {
+                    if let Err(e) = store.save(&session.to_state().await).await {
+                        warn!(
+                            "Failed to persist state for recording '{}': {}",
+                            recording_id, e
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to upload flush task for topic '{}': {}", topic, e);
+                Self::fire_session_hooks(
+                    &context.hooks,
+                    &context.device_id,
+                    context.clock.as_ref(),
+                    session,
+                    RecordingEventKind::Error,
+                    Some(format!(
+                        "Failed to upload record {} of topic '{}': {}",
+                        timestamp_us, topic, e
+                    )),
+                )
+                .await;
+                if let (Some(work_dirs), Some(data)) = (&context.work_dirs, spill_data) {
+                    match work_dirs
+                        .spill(recording_id, &entry_name, timestamp_us, data)
+                        .await
+                    {
+                        Ok(path) => warn!("Spilled record to {}", path.display()),
+                        Err(e) => error!("Failed to spill record: {:#}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Open a session gap in every active recording (Zenoh session
+    /// disconnected)
+    ///
+    /// Returns the number of recordings affected.
+    pub async fn begin_session_gap(&self) -> usize {
+        let started_at = rfc3339(self.clock.now());
+        let mut affected = 0;
+        for session in self.session_list() {
+            if !matches!(
+                *session.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                continue;
+            }
+            {
+                let mut gaps = session.session_gaps.write().await;
+                if gaps.last().is_some_and(|gap| gap.ended_at.is_none()) {
+                    continue;
+                }
+                gaps.push(SessionGap {
+                    started_at: started_at.clone(),
+                    ended_at: None,
+                    reopened: false,
+                });
+            }
+            self.persist_state(&session).await;
+            affected += 1;
+        }
+        affected
+    }
+
+    /// Declare the subscribers of the active recordings again on `session`
+    ///
+    /// A new session replaces the old one, which the caller has closed: status
+    /// events, tap publications and controller liveliness watches move to it
+    /// as well. Returns the number of subscribers declared.
+    pub async fn redeclare(&self, session: Arc<Session>) -> usize {
+        let replaced = !Arc::ptr_eq(&self.zenoh_session(), &session);
+        *self.session.write().unwrap() = session.clone();
+        let declared = self.subscription_hub.redeclare(session.clone());
+        if !replaced {
+            return declared;
+        }
+
+        if let Some(status_events) = &self.status_events {
+            status_events.set_session(session.clone());
+        }
+        if let Some(tap_publisher) = &self.tap_publisher {
+            tap_publisher.set_session(session.clone());
+        }
+        if let Some(health) = &self.health {
+            health.declare(session.clone());
+        }
+        for recording in self.session_list() {
+            let Some(controller) = &recording.controller else {
+                continue;
+            };
+            if matches!(
+                *recording.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                controller.spawn(session.clone(), &recording.tasks);
+            }
+        }
+        declared
+    }
+
+    /// Close the open session gaps (Zenoh session connected again)
+    ///
+    /// `reopened` marks gaps ended by replacing the session. Returns the number
+    /// of recordings affected.
+    pub async fn end_session_gap(&self, reopened: bool) -> usize {
+        let ended_at = rfc3339(self.clock.now());
+        let mut affected = 0;
+        for recording in self.session_list() {
+            {
+                let mut gaps = recording.session_gaps.write().await;
+                let Some(gap) = gaps.last_mut().filter(|gap| gap.ended_at.is_none()) else {
+                    continue;
+                };
+                gap.ended_at = Some(ended_at.clone());
+                gap.reopened = reopened;
+                warn!(
+                    "Recording '{}' missed the samples published from {} to {}",
+                    recording.recording_id, gap.started_at, ended_at
+                );
+            }
+            self.persist_state(&recording).await;
+            affected += 1;
+        }
+        affected
+    }
+
+    /// Dead-man switch: act on recordings whose controller disappeared
+    ///
+    /// Recordings started with `controller_liveliness` are finished (or paused,
+    /// see `controller_liveliness.on_lost`) once no matching liveliness token
+    /// has been alive for the grace period. Paused recordings resume when the
+    /// controller comes back. Runs until dropped.
+    pub async fn watch_controllers(&self) {
+        let settings = &self.config.recorder.controller_liveliness;
+        let grace_period = settings.grace_period();
+        let interval = (grace_period / 4).clamp(Duration::from_millis(100), Duration::from_secs(1));
+
+        loop {
+            runtime::sleep_aligned(interval, self.wakeup_granularity).await;
+
+            for session in self.session_list() {
+                let Some(controller) = &session.controller else {
+                    continue;
+                };
+                let status = *session.status.read().await;
+                let recording_id = &session.recording_id;
+
+                match controller.lost_for() {
+                    Some(lost_for) if lost_for >= grace_period => match settings.on_lost {
+                        ControllerLostAction::Finish
+                            if matches!(
+                                status,
+                                RecordingStatus::Recording | RecordingStatus::Paused
+                            ) =>
+                        {
+                            warn!(
+                                "Controller '{}' of recording '{}' lost for {:?}, finishing",
+                                controller.key_expr, recording_id, lost_for
+                            );
+                            self.finish_recording(recording_id).await;
+                        }
+                        ControllerLostAction::Pause if status == RecordingStatus::Recording => {
+                            warn!(
+                                "Controller '{}' of recording '{}' lost for {:?}, pausing",
+                                controller.key_expr, recording_id, lost_for
+                            );
+                            if self.pause_recording(recording_id).await.success {
+                                controller.set_paused(true);
+                            }
+                        }
+                        _ => {}
+                    },
+                    None if status == RecordingStatus::Paused && controller.set_paused(false) => {
+                        info!(
+                            "Controller '{}' of recording '{}' is back, resuming",
+                            controller.key_expr, recording_id
+                        );
+                        self.resume_recording(recording_id).await;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Finish the recordings of organizations and tasks that used up their
+    /// bytes for the day
+    ///
+    /// Checks every second; pends forever when no quota is configured. Runs
+    /// until dropped.
+    pub async fn enforce_quotas(&self) {
+        if !self.quotas.is_enabled() {
+            return std::future::pending().await;
+        }
+
+        loop {
+            runtime::sleep_aligned(Duration::from_secs(1), self.wakeup_granularity).await;
+
+            for session in self.session_list() {
+                let status = *session.status.read().await;
+                if !matches!(status, RecordingStatus::Recording | RecordingStatus::Paused) {
+                    continue;
+                }
+                let exceeded = self
+                    .quotas
+                    .scopes(
+                        session.metadata.organization.as_deref(),
+                        session.metadata.task_id.as_deref(),
+                    )
+                    .into_iter()
+                    .find(|(scope, name, limits)| {
+                        limits.max_bytes_per_day > 0
+                            && self.quotas.bytes_today(*scope, name) >= limits.max_bytes_per_day
+                    });
+                if let Some((scope, name, limits)) = exceeded {
+                    warn!(
+                        "{} '{}' used up its {} bytes for today, finishing recording '{}'",
+                        scope.as_str(),
+                        name,
+                        limits.max_bytes_per_day,
+                        session.recording_id
+                    );
+                    self.finish_recording(&session.recording_id).await;
+                }
+            }
+        }
+    }
+
+    /// Flush every active recording now and checkpoint its state
+    ///
+    /// For imminent power loss: the buffers of recording and paused sessions
+    /// are flushed, highest priority recordings first, the flush workers get
+    /// up to `timeout` to write them, and the state of each recording is
+    /// persisted so that recovery continues from the last record. The
+    /// recordings keep running. Returns how many were flushed.
+    #[cfg_attr(
+        not(all(feature = "power-events", target_os = "linux")),
+        allow(dead_code)
+    )]
+    pub async fn emergency_flush(&self, reason: &str, timeout: Duration) -> usize {
+        let mut sessions = Vec::new();
+        for session in self.session_list() {
+            if matches!(
+                *session.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                sessions.push(session);
+            }
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.metadata.priority.unwrap_or_default()));
+        warn!(
+            "Emergency flush of {} recordings: {}",
+            sessions.len(),
+            reason
+        );
+
+        for session in &sessions {
+            for entry in session.topic_buffers.iter() {
+                if let Err(e) = entry.value().flush(FlushReason::Shutdown).await {
+                    error!("Failed to flush buffer for topic '{}': {}", entry.key(), e);
+                }
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+        for session in &sessions {
+            while self.flush_pool.pending_tasks(&session.recording_id) > 0 {
+                if Instant::now() >= deadline {
+                    warn!(
+                        "Recording '{}' still has {} flush tasks after the emergency flush timeout",
+                        session.recording_id,
+                        self.flush_pool.pending_tasks(&session.recording_id)
+                    );
+                    break;
+                }
+                runtime::sleep(Duration::from_millis(50)).await;
+            }
+            self.persist_state(session).await;
+            self.publish_status(session).await;
+        }
+        sessions.len()
+    }
+
+    /// Shutdown recorder manager
+    ///
+    /// Active recordings are finished and uploaded. With a `deadline`, the
+    /// recordings still uploading when it passes are spilled to the work
+    /// directory instead (see `spill_uploading`) and completed by
+    /// `recover_sessions` on the next start.
+    pub async fn shutdown(&self, deadline: Option<Duration>) -> Result<()> {
+        info!("Shutting down recorder manager");
+        self.shutting_down.store(true, Ordering::SeqCst);
+
+        // Finish all active recordings (handed-off recordings are already finished)
+        let mut recording_ids = Vec::new();
+        for session in self.session_list() {
+            if !matches!(
+                *session.status.read().await,
+                RecordingStatus::Recording | RecordingStatus::Paused
+            ) {
+                continue;
+            }
+            let response = self.begin_finish(&session.recording_id).await;
+            if response.success {
+                recording_ids.push(session.recording_id.clone());
+            } else {
                 error!(
                     "Failed to finish recording '{}': {}",
-                    recording_id, response.message
+                    session.recording_id, response.message
                 );
             }
         }
+        let uploaded = async {
+            let finishes = recording_ids.iter().map(|id| self.complete_finish(id));
+            for (recording_id, response) in recording_ids.iter().zip(join_all(finishes).await) {
+                if !response.success {
+                    error!(
+                        "Failed to finish recording '{}': {}",
+                        recording_id, response.message
+                    );
+                }
+            }
+            // Finishes started by a command run as background jobs
+            while !self.uploading().await.is_empty() {
+                runtime::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        match deadline {
+            Some(deadline) => {
+                tokio::select! {
+                    _ = uploaded => {}
+                    _ = runtime::sleep(deadline) => {
+                        warn!(
+                            "Recordings still uploading after the shutdown deadline of {:?}",
+                            deadline
+                        );
+                        self.spill_uploading().await;
+                    }
+                }
+            }
+            None => uploaded.await,
+        }
+
+        // Then stop the background tasks in order
+        let timeout = self.config.recorder.workers.shutdown_timeout();
+        for session in self.session_list() {
+            session.tasks.abort_all();
+        }
+        let aborted = self.tasks.shutdown(timeout).await;
+        debug!("Stopped background tasks, aborted {}", aborted);
+
+        // Gone once there's nothing left to upload
+        if let Some(health) = &self.health {
+            health.undeclare();
+        }
 
         Ok(())
     }
+
+    /// Recordings finishing their upload
+    async fn uploading(&self) -> Vec<Arc<RecordingSession>> {
+        let mut uploading = Vec::new();
+        for session in self.session_list() {
+            if *session.status.read().await == RecordingStatus::Uploading {
+                uploading.push(session);
+            }
+        }
+        uploading
+    }
+
+    /// Spill the recordings still uploading when the shutdown deadline passed
+    ///
+    /// From now on flush tasks are written to the work directory instead of
+    /// the backend, with the remaining buffers. Uploads in flight get
+    /// `workers.shutdown_timeout_ms` to complete before they're abandoned and
+    /// their records written there as well. Then each recording gets a
+    /// recovery manifest with its metadata so far and its state is removed.
+    /// Returns how many were spilled.
+    async fn spill_uploading(&self) -> usize {
+        let sessions = self.uploading().await;
+        let Some(work_dirs) = &self.work_dirs else {
+            error!(
+                "Dropping what's left of {} recording(s): spilling needs work_dir.enabled",
+                sessions.len()
+            );
+            return 0;
+        };
+        self.shutdown_spill.arm();
+        warn!(
+            "Spilling {} recording(s) for the next start",
+            sessions.len()
+        );
+
+        for session in &sessions {
+            Self::flush_remaining(session, FlushReason::Shutdown).await;
+        }
+        // Abandoned uploads are kept once their timeout is over
+        let deadline = Instant::now() + 2 * self.config.recorder.workers.shutdown_timeout();
+        for session in &sessions {
+            while self.flush_pool.pending_tasks(&session.recording_id) > 0 {
+                if Instant::now() >= deadline {
+                    warn!(
+                        "Recording '{}' still has {} flush tasks after the shutdown timeout",
+                        session.recording_id,
+                        self.flush_pool.pending_tasks(&session.recording_id)
+                    );
+                    break;
+                }
+                runtime::sleep(Duration::from_millis(50)).await;
+            }
+        }
+
+        let mut spilled = 0;
+        for session in &sessions {
+            let manifest = ShutdownManifest {
+                metadata: self.final_metadata(session).await,
+                spilled_at: rfc3339(self.clock.now()),
+            };
+            match work_dirs.write_manifest(&manifest).await {
+                Ok(path) => {
+                    warn!(
+                        "Spilled recording '{}', manifest in {}",
+                        session.recording_id,
+                        path.display()
+                    );
+                    // Completed from the manifest, not resumed
+                    self.clear_state(&session.recording_id).await;
+                    spilled += 1;
+                }
+                Err(e) => error!(
+                    "Failed to spill recording '{}': {:#}",
+                    session.recording_id, e
+                ),
+            }
+        }
+        spilled
+    }
 }
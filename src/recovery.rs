@@ -0,0 +1,229 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Crash recovery state for in-flight recordings
+//
+// Every active recording keeps a small JSON state file in the configured
+// state directory. The file is rewritten after each successful flush and
+// removed when the recording finishes or is cancelled, so any file still
+// present at startup belongs to a recording interrupted by a crash.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::protocol::{CompressionLevel, CompressionType, RecordingMetadata};
+use crate::runtime::fs;
+
+/// Persisted snapshot of an in-flight recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    pub recording_id: String,
+    pub metadata: RecordingMetadata,
+    pub compression_type: CompressionType,
+    pub compression_level: CompressionLevel,
+    /// Number of batches successfully written to the backend
+    pub flushed_batches: u64,
+    /// Bytes successfully written to the backend
+    pub flushed_bytes: i64,
+    /// Backend timestamp (microseconds) of the last successful flush
+    #[serde(default)]
+    pub last_flush_us: Option<u64>,
+}
+
+/// Directory-backed store of session state files (one `{recording_id}.json` per recording)
+pub struct SessionStateStore {
+    state_dir: PathBuf,
+}
+
+impl SessionStateStore {
+    pub fn new<P: AsRef<Path>>(state_dir: P) -> Self {
+        Self {
+            state_dir: state_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn state_path(&self, recording_id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.json", recording_id))
+    }
+
+    /// Persist state atomically (write to a temp file, then rename)
+    pub async fn save(&self, state: &SessionState) -> Result<()> {
+        fs::create_dir_all(&self.state_dir)
+            .await
+            .context("Failed to create state directory")?;
+
+        let path = self.state_path(&state.recording_id);
+        let tmp_path = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(state).context("Failed to serialize state")?;
+
+        fs::write(&tmp_path, content).await.context(format!(
+            "Failed to write state file: {}",
+            tmp_path.display()
+        ))?;
+        fs::rename(&tmp_path, &path)
+            .await
+            .context(format!("Failed to move state file: {}", path.display()))?;
+
+        debug!("Saved session state to {}", path.display());
+        Ok(())
+    }
+
+    /// Remove the state file of a recording that ended cleanly
+    pub async fn remove(&self, recording_id: &str) -> Result<()> {
+        let path = self.state_path(recording_id);
+        match fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).context(format!("Failed to remove state file: {}", path.display())),
+        }
+    }
+
+    /// Load every state file left in the state directory
+    ///
+    /// Unreadable or corrupt files are skipped with a warning so that one bad
+    /// file cannot block recovery of the others.
+    pub async fn load_all(&self) -> Result<Vec<SessionState>> {
+        let mut states = Vec::new();
+
+        let entries = match fs::read_dir(&self.state_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(states),
+            Err(e) => return Err(e).context("Failed to read state directory"),
+        };
+
+        for path in entries {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            match fs::read(&path).await {
+                Ok(content) => match serde_json::from_slice::<SessionState>(&content) {
+                    Ok(state) => states.push(state),
+                    Err(e) => warn!("Skipping corrupt state file {}: {}", path.display(), e),
+                },
+                Err(e) => warn!("Skipping unreadable state file {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+    use tempfile::TempDir;
+
+    fn sample_state(recording_id: &str) -> SessionState {
+        SessionState {
+            recording_id: recording_id.to_string(),
+            metadata: RecordingMetadata {
+                recording_id: recording_id.to_string(),
+                scene: None,
+                skills: vec![],
+                organization: None,
+                task_id: None,
+                device_id: "device-01".to_string(),
+                data_collector_id: None,
+                topics: vec!["/test/topic".to_string()],
+                compression_type: "Zstd".to_string(),
+                compression_level: 2,
+                start_time: "2025-01-01T00:00:00+00:00".to_string(),
+                end_time: None,
+                total_bytes: 0,
+                total_samples: 0,
+                payload_bytes: 0,
+                compression_ratio: None,
+                per_topic_stats: serde_json::json!({}),
+                interrupted: false,
+                schema_drift: vec![],
+                handoff: None,
+                previous_recording_id: None,
+                group_id: None,
+                topic_changes: vec![],
+                session_gaps: vec![],
+                records: vec![],
+                verify_failures: 0,
+                records_verified: 0,
+                bucket: None,
+                controller_liveliness: None,
+                priority: None,
+                topic_aliases: BTreeMap::new(),
+                previews: BTreeMap::new(),
+                exclude_topics: vec![],
+                buffer_strategy: None,
+                retention_seconds: None,
+                expires_at: None,
+                formats: BTreeMap::new(),
+                topology: None,
+                environment: BTreeMap::new(),
+                labels: HashMap::new(),
+            },
+            compression_type: CompressionType::Zstd,
+            compression_level: CompressionLevel::Default,
+            flushed_batches: 3,
+            flushed_bytes: 4096,
+            last_flush_us: Some(1_700_000_000_000_000),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SessionStateStore::new(temp_dir.path());
+
+        store.save(&sample_state("rec-1")).await.unwrap();
+        store.save(&sample_state("rec-2")).await.unwrap();
+
+        let mut states = store.load_all().await.unwrap();
+        states.sort_by(|a, b| a.recording_id.cmp(&b.recording_id));
+        assert_eq!(states.len(), 2);
+        assert_eq!(states[0].recording_id, "rec-1");
+        assert_eq!(states[0].flushed_bytes, 4096);
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SessionStateStore::new(temp_dir.path());
+
+        store.save(&sample_state("rec-1")).await.unwrap();
+        store.remove("rec-1").await.unwrap();
+        // Removing twice is not an error
+        store.remove("rec-1").await.unwrap();
+
+        assert!(store.load_all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_skips_corrupt_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SessionStateStore::new(temp_dir.path());
+
+        store.save(&sample_state("rec-1")).await.unwrap();
+        std::fs::write(temp_dir.path().join("broken.json"), b"not json").unwrap();
+
+        let states = store.load_all().await.unwrap();
+        assert_eq!(states.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_directory() {
+        let store = SessionStateStore::new("/nonexistent/zenoh-recorder-state");
+        assert!(store.load_all().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,569 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Replay of stored recordings (`zenoh-recorder replay`)
+//
+// The records listed in the recording metadata are read back from the storage
+// backend, the next batch of every topic in parallel, merged by timestamp and
+// republished on Zenoh with their original spacing (optionally scaled). Each
+// topic is read ahead up to a lookahead window past the next message, so
+// samples that arrived out of order within the window (across batches or
+// topics) go out in timestamp order while memory stays bounded. Ties are broken
+// by entry name, so every replay publishes in the same order. Samples go out on
+// the key they were received on, with their encoding and QoS; key prefixes
+// can be rewritten to keep replayed data apart from live data. Deletions are
+// replayed as deletes, and recorded liveliness tokens are declared again
+// (undeclared where the recording saw them go, or when the replay ends).
+// A replay limited to a time range checks the index record of each batch
+// (see `crate::batch_index`) and leaves the batches outside of the range
+// unread. Batches stored in coalesced records (see `crate::coalesce`) are read
+// from their range of the coalesced record.
+
+use anyhow::{bail, Context, Result};
+use futures_util::future::join_all;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use zenoh::bytes::Encoding;
+use zenoh::key_expr::KeyExpr;
+use zenoh::liveliness::LivelinessToken;
+use zenoh::qos::{CongestionControl, Priority};
+use zenoh::Session;
+
+use crate::batch_index::{index_entry, BatchIndex};
+use crate::coalesce;
+use crate::config::matching::topic_matches;
+use crate::inspect::{parse_batch, parse_batch_range};
+use crate::proto::RecordedMessage;
+use crate::protocol::{RecordChecksum, RecordingMetadata};
+use crate::runtime;
+use crate::storage::StorageBackend;
+
+/// Entry holding the recording metadata
+const METADATA_ENTRY: &str = "recordings_metadata";
+
+/// How a recording is replayed
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Playback speed (2.0 replays twice as fast)
+    pub rate: f64,
+    /// Only replay topics matching one of these patterns (all when empty)
+    pub topics: Vec<String>,
+    /// Key prefix rewrites (`from`, `to`), the first match wins; an empty
+    /// `from` matches every key
+    pub remap: Vec<(String, String)>,
+    /// Publish remapped topics under their recorded name instead of the original
+    pub recorded_names: bool,
+    /// How far each topic is read ahead of the next message to merge samples
+    /// recorded out of order
+    pub lookahead: Duration,
+    /// Only replay messages with timestamps in this range (ns, inclusive)
+    pub time_range: Option<(i64, i64)>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            topics: Vec::new(),
+            remap: Vec::new(),
+            recorded_names: false,
+            lookahead: Duration::from_secs(1),
+            time_range: None,
+        }
+    }
+}
+
+/// What a replay published
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub records: usize,
+    pub messages: usize,
+    /// Records that could not be read or parsed
+    pub skipped_records: usize,
+    /// Records left unread because their index record puts them outside of
+    /// the time range
+    pub unread_records: usize,
+}
+
+/// Parse a `--remap from=to` argument
+///
+/// Both sides are key prefixes and may end in `/**` (`robot1/**=sim/**`); a
+/// bare `**` on the left matches every key (`**=sim/**` replays under `sim`).
+pub fn parse_remap(value: &str) -> std::result::Result<(String, String), String> {
+    let (from, to) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FROM=TO, got '{}'", value))?;
+    let prefix = |side: &str| {
+        let side = side.trim_end_matches('/');
+        if side == "**" {
+            String::new()
+        } else {
+            side.strip_suffix("/**").unwrap_or(side).to_string()
+        }
+    };
+    let (from_prefix, to_prefix) = (prefix(from), prefix(to));
+    if to_prefix.is_empty() || (from_prefix.is_empty() && from.trim_end_matches('/') != "**") {
+        return Err(format!("empty key prefix in '{}'", value));
+    }
+    if KeyExpr::try_from(to_prefix.as_str()).is_err() || to_prefix.contains('*') {
+        return Err(format!("'{}' is not a key prefix", to));
+    }
+    Ok((from_prefix, to_prefix))
+}
+
+/// Parse an environment seed entry given as `KEY=VALUE`
+pub fn parse_environment(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", value)),
+    }
+}
+
+/// Rewrite the prefix of `key` with the first matching rule
+pub fn remap_key(key: &str, remap: &[(String, String)]) -> String {
+    for (from, to) in remap {
+        if from.is_empty() {
+            return format!("{}/{}", to, key);
+        }
+        if key == from {
+            return to.clone();
+        }
+        if let Some(rest) = key.strip_prefix(from.as_str()) {
+            if rest.starts_with('/') {
+                return format!("{}{}", to, rest);
+            }
+        }
+    }
+    key.to_string()
+}
+
+/// Read the metadata of a recording from the storage backend
+pub async fn find_recording(
+    storage: &dyn StorageBackend,
+    recording_id: &str,
+) -> Result<RecordingMetadata> {
+    let timestamps = storage
+        .find_records(METADATA_ENTRY, "recording_id", recording_id)
+        .await
+        .context("Failed to look up the recording metadata")?;
+    let Some(&timestamp_us) = timestamps.last() else {
+        bail!("Recording {} not found", recording_id);
+    };
+    let data = storage
+        .read_record(METADATA_ENTRY, timestamp_us)
+        .await
+        .context("Failed to read the recording metadata")?;
+    serde_json::from_slice(&data).context("Failed to parse the recording metadata")
+}
+
+/// Recordings started with every key of `environment` set to its value,
+/// oldest first
+///
+/// Looked up by the `env_{key}` labels of the metadata records.
+pub async fn find_recordings_by_environment(
+    storage: &dyn StorageBackend,
+    environment: &BTreeMap<String, String>,
+) -> Result<Vec<RecordingMetadata>> {
+    let Some((key, value)) = environment.iter().next() else {
+        bail!("At least one environment key is required");
+    };
+    let timestamps = storage
+        .find_records(METADATA_ENTRY, &format!("env_{}", key), value)
+        .await
+        .context("Failed to look up the recording metadata")?;
+
+    // Later metadata records of a recording replace earlier ones
+    let mut recordings: BTreeMap<String, RecordingMetadata> = BTreeMap::new();
+    for timestamp_us in timestamps {
+        let data = storage
+            .read_record(METADATA_ENTRY, timestamp_us)
+            .await
+            .context("Failed to read the recording metadata")?;
+        let metadata: RecordingMetadata =
+            serde_json::from_slice(&data).context("Failed to parse the recording metadata")?;
+        recordings.insert(metadata.recording_id.clone(), metadata);
+    }
+
+    let mut recordings: Vec<_> = recordings
+        .into_values()
+        .filter(|metadata| {
+            environment
+                .iter()
+                .all(|(key, value)| metadata.environment.get(key) == Some(value))
+        })
+        .collect();
+    recordings.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+    Ok(recordings)
+}
+
+/// Records of one topic, read a batch at a time
+struct Cursor {
+    records: VecDeque<RecordChecksum>,
+    /// Latest message timestamp read so far
+    read_until: Option<i64>,
+}
+
+impl Cursor {
+    /// Whether the next batch is needed to merge up to `horizon_ns`
+    fn due(&self, horizon_ns: Option<i64>) -> bool {
+        !self.records.is_empty()
+            && match (horizon_ns, self.read_until) {
+                (Some(horizon_ns), Some(read_until)) => read_until < horizon_ns,
+                _ => true,
+            }
+    }
+}
+
+/// Message waiting to be published, ordered by timestamp, then cursor, then
+/// read order so ties go out the same way on every replay
+struct Pending {
+    timestamp_ns: i64,
+    cursor: usize,
+    seq: u64,
+    message: RecordedMessage,
+}
+
+impl Pending {
+    fn key(&self) -> (i64, usize, u64) {
+        (self.timestamp_ns, self.cursor, self.seq)
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Read the messages of a batch in `time_range` (all without one)
+///
+/// With a time range the index record of the batch is checked first, and
+/// None is returned without reading the batch when none of its messages is in
+/// the range. Batches without an index record are read and filtered.
+async fn read_batch(
+    storage: &dyn StorageBackend,
+    record: &RecordChecksum,
+    time_range: Option<(i64, i64)>,
+) -> Result<Option<Vec<RecordedMessage>>> {
+    let Some((start_ns, end_ns)) = time_range else {
+        let data = coalesce::read(storage, record).await?;
+        return Ok(Some(parse_batch(&data)?.messages));
+    };
+    let index = match record.coalesced {
+        Some(_) => None,
+        None => storage
+            .read_record(&index_entry(&record.entry), record.timestamp_us)
+            .await
+            .ok()
+            .and_then(|data| serde_json::from_slice::<BatchIndex>(&data).ok()),
+    };
+    if index.is_some_and(|index| !index.overlaps(start_ns, end_ns)) {
+        return Ok(None);
+    }
+    let data = coalesce::read(storage, record).await?;
+    Ok(Some(parse_batch_range(&data, start_ns, end_ns)?.messages))
+}
+
+/// Republish the samples of a recording
+pub async fn replay(
+    session: &Session,
+    storage: &dyn StorageBackend,
+    metadata: &RecordingMetadata,
+    options: &ReplayOptions,
+) -> Result<ReplaySummary> {
+    if !(options.rate > 0.0 && options.rate.is_finite()) {
+        bail!("Replay rate must be positive, got {}", options.rate);
+    }
+
+    // Original topic of each recorded name
+    let originals: HashMap<&str, &str> = metadata
+        .topic_aliases
+        .iter()
+        .map(|(original, recorded)| (recorded.as_str(), original.as_str()))
+        .collect();
+    let original_of = |topic: &str| originals.get(topic).copied().unwrap_or(topic).to_string();
+
+    // Previews repeat samples of their topics
+    let previews: HashSet<&str> = metadata.previews.values().map(String::as_str).collect();
+
+    let mut by_entry: BTreeMap<&str, Vec<RecordChecksum>> = BTreeMap::new();
+    for record in &metadata.records {
+        if previews.contains(record.topic.as_str()) {
+            continue;
+        }
+        let selected = options.topics.is_empty()
+            || options.topics.iter().any(|pattern| {
+                topic_matches(pattern, &record.topic)
+                    || topic_matches(pattern, &original_of(&record.topic))
+            });
+        if selected {
+            by_entry
+                .entry(record.entry.as_str())
+                .or_default()
+                .push(record.clone());
+        }
+    }
+    let mut cursors: Vec<Cursor> = by_entry
+        .into_values()
+        .map(|mut records| {
+            records.sort_by_key(|record| record.timestamp_us);
+            records.dedup_by_key(|record| record.timestamp_us);
+            Cursor {
+                records: records.into(),
+                read_until: None,
+            }
+        })
+        .collect();
+
+    info!(
+        "Replaying recording {} ({} entries) at {}x",
+        metadata.recording_id,
+        cursors.len(),
+        options.rate
+    );
+
+    let lookahead_ns = options.lookahead.as_nanos().min(i64::MAX as u128) as i64;
+    let mut pending: BinaryHeap<Reverse<Pending>> = BinaryHeap::new();
+    let mut seq = 0u64;
+    let mut summary = ReplaySummary::default();
+    let mut clock: Option<(Instant, i64)> = None;
+    let mut tokens: HashMap<String, LivelinessToken> = HashMap::new();
+    loop {
+        // Read ahead until every topic is merged up to the lookahead horizon,
+        // the next batch of each topic in parallel
+        loop {
+            let horizon_ns = pending
+                .peek()
+                .map(|Reverse(next)| next.timestamp_ns.saturating_add(lookahead_ns));
+            let due: Vec<(usize, RecordChecksum)> = cursors
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, cursor)| cursor.due(horizon_ns))
+                .filter_map(|(i, cursor)| Some((i, cursor.records.pop_front()?)))
+                .collect();
+            if due.is_empty() {
+                break;
+            }
+            let batches = join_all(
+                due.iter()
+                    .map(|(_, record)| read_batch(storage, record, options.time_range)),
+            )
+            .await;
+            for ((i, record), batch) in due.into_iter().zip(batches) {
+                let (entry, timestamp_us) = (&record.entry, record.timestamp_us);
+                let messages = match batch {
+                    Ok(Some(messages)) => messages,
+                    Ok(None) => {
+                        debug!(
+                            "Skipping record {}/{} outside of the time range",
+                            entry, timestamp_us
+                        );
+                        summary.unread_records += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Skipping record {}/{}: {:#}", entry, timestamp_us, e);
+                        summary.skipped_records += 1;
+                        continue;
+                    }
+                };
+                summary.records += 1;
+                let cursor = &mut cursors[i];
+                for message in messages {
+                    cursor.read_until = cursor.read_until.max(Some(message.timestamp_ns));
+                    pending.push(Reverse(Pending {
+                        timestamp_ns: message.timestamp_ns,
+                        cursor: i,
+                        seq,
+                        message,
+                    }));
+                    seq += 1;
+                }
+            }
+        }
+
+        // Earliest pending message across all topics
+        let Some(Reverse(Pending { message, .. })) = pending.pop() else {
+            break;
+        };
+
+        // Keep the original spacing, scaled by the rate
+        let (started, first_ns) = *clock.get_or_insert((Instant::now(), message.timestamp_ns));
+        let offset_ns = (message.timestamp_ns - first_ns).max(0) as f64 / options.rate;
+        let due = started + Duration::from_nanos(offset_ns as u64);
+        let now = Instant::now();
+        if due > now {
+            runtime::sleep(due - now).await;
+        }
+
+        let key = publish_key(&message, options.recorded_names, original_of);
+        let key = remap_key(&key, &options.remap);
+        publish(session, &key, message, &mut tokens).await?;
+        summary.messages += 1;
+    }
+
+    info!(
+        "Replayed {} messages from {} records ({} skipped, {} outside of the time range)",
+        summary.messages, summary.records, summary.skipped_records, summary.unread_records
+    );
+    Ok(summary)
+}
+
+/// Key a message is republished on, before remapping
+fn publish_key(
+    message: &RecordedMessage,
+    recorded_names: bool,
+    original_of: impl Fn(&str) -> String,
+) -> String {
+    let original = original_of(&message.topic);
+    if recorded_names && original != message.topic {
+        return message.topic.clone();
+    }
+    match &message.sample {
+        Some(sample) if !sample.key_expr.is_empty() => sample.key_expr.clone(),
+        // Recorded before key expressions were kept
+        _ => original,
+    }
+}
+
+async fn publish(
+    session: &Session,
+    key: &str,
+    message: RecordedMessage,
+    tokens: &mut HashMap<String, LivelinessToken>,
+) -> Result<()> {
+    let sample = message.sample.unwrap_or_default();
+    let attachment = (!message.attachment.is_empty()).then_some(message.attachment);
+    if sample.liveliness {
+        if sample.kind == "delete" {
+            tokens.remove(key);
+        } else if !tokens.contains_key(key) {
+            let token = session
+                .liveliness()
+                .declare_token(key)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to declare token {}: {}", key, e))?;
+            tokens.insert(key.to_string(), token);
+        }
+        debug!("Replayed liveliness {} on {}", sample.kind, key);
+        return Ok(());
+    }
+    let congestion_control = match sample.congestion_control.as_str() {
+        "block" => CongestionControl::Block,
+        _ => CongestionControl::Drop,
+    };
+    let priority = u8::try_from(sample.priority)
+        .ok()
+        .and_then(|p| Priority::try_from(p).ok())
+        .unwrap_or_default();
+
+    let result = if sample.kind == "delete" {
+        session
+            .delete(key)
+            .congestion_control(congestion_control)
+            .priority(priority)
+            .express(sample.express)
+            .attachment(attachment)
+            .await
+    } else {
+        let mut put = session
+            .put(key, message.payload)
+            .congestion_control(congestion_control)
+            .priority(priority)
+            .express(sample.express)
+            .attachment(attachment);
+        if !sample.encoding.is_empty() {
+            put = put.encoding(Encoding::from(sample.encoding));
+        }
+        put.await
+    };
+    debug!("Replayed {} sample on {}", sample.kind, key);
+    result.map_err(|e| anyhow::anyhow!("Failed to publish on {}: {}", key, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remap() {
+        assert_eq!(
+            parse_remap("robot1/=replay/robot1").unwrap(),
+            ("robot1".to_string(), "replay/robot1".to_string())
+        );
+        assert!(parse_remap("robot1").is_err());
+        assert!(parse_remap("=replay").is_err());
+        assert!(parse_remap("robot1=**").is_err());
+        assert!(parse_remap("robot1=sim/*/x").is_err());
+        assert_eq!(
+            parse_remap("robot1/**=sim/robot1/**").unwrap(),
+            ("robot1".to_string(), "sim/robot1".to_string())
+        );
+        assert_eq!(
+            parse_remap("**=sim/**").unwrap(),
+            (String::new(), "sim".to_string())
+        );
+    }
+
+    #[test]
+    fn test_remap_key() {
+        let remap = vec![
+            ("robot1/camera".to_string(), "sim/camera".to_string()),
+            ("robot1".to_string(), "replay/robot1".to_string()),
+        ];
+        assert_eq!(remap_key("robot1/camera", &remap), "sim/camera");
+        assert_eq!(remap_key("robot1/camera/left", &remap), "sim/camera/left");
+        assert_eq!(remap_key("robot1/lidar", &remap), "replay/robot1/lidar");
+        // Only whole chunks match
+        assert_eq!(remap_key("robot10/lidar", &remap), "robot10/lidar");
+        assert_eq!(remap_key("robot2/lidar", &remap), "robot2/lidar");
+
+        let everything = vec![(String::new(), "sim".to_string())];
+        assert_eq!(remap_key("robot2/lidar", &everything), "sim/robot2/lidar");
+    }
+
+    #[test]
+    fn test_pending_order() {
+        let pending = |timestamp_ns, cursor, seq| Pending {
+            timestamp_ns,
+            cursor,
+            seq,
+            message: RecordedMessage::default(),
+        };
+        let mut heap = BinaryHeap::new();
+        heap.push(Reverse(pending(20, 0, 0)));
+        heap.push(Reverse(pending(10, 1, 1)));
+        heap.push(Reverse(pending(10, 0, 3)));
+        heap.push(Reverse(pending(10, 0, 2)));
+        let order: Vec<(i64, usize, u64)> =
+            std::iter::from_fn(|| heap.pop().map(|Reverse(p)| p.key())).collect();
+        assert_eq!(order, vec![(10, 0, 2), (10, 0, 3), (10, 1, 1), (20, 0, 0)]);
+    }
+}
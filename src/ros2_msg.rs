@@ -0,0 +1,247 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// ROS 2 message definition loader
+//
+// Resolves `.msg` files for CDR-encoded topics and concatenates them with all
+// of their nested dependencies in the `ros2msg` schema encoding used by
+// rosbag2/MCAP, so viewers such as Foxglove can decode the recorded payloads.
+//
+// Expected directory layout (same as an installed ROS 2 share directory):
+//
+// ```text
+// {msg_dir}/{package}/msg/{Type}.msg
+// ```
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Separator between concatenated definitions in the `ros2msg` encoding
+const DEFINITION_SEPARATOR: &str =
+    "================================================================================";
+
+/// Builtin field types that never reference another message
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "byte", "char", "float32", "float64", "int8", "uint8", "int16", "uint16", "int32",
+    "uint32", "int64", "uint64", "string", "wstring",
+];
+
+/// Fully-qualified ROS 2 message type
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MessageType {
+    package: String,
+    name: String,
+}
+
+impl MessageType {
+    /// Parse `pkg/msg/Type` or `pkg/Type`
+    fn parse(type_name: &str) -> Result<Self> {
+        let parts: Vec<&str> = type_name.split('/').collect();
+        let (package, name) = match parts.as_slice() {
+            [package, "msg", name] | [package, name] => (*package, *name),
+            _ => bail!("Invalid ROS 2 message type name: '{}'", type_name),
+        };
+
+        if package.is_empty() || name.is_empty() {
+            bail!("Invalid ROS 2 message type name: '{}'", type_name);
+        }
+
+        Ok(Self {
+            package: package.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    fn file_path(&self, msg_dir: &Path) -> std::path::PathBuf {
+        msg_dir
+            .join(&self.package)
+            .join("msg")
+            .join(format!("{}.msg", self.name))
+    }
+
+    fn qualified_name(&self) -> String {
+        format!("{}/{}", self.package, self.name)
+    }
+}
+
+/// Load the definition of `type_name` and all nested types from `msg_dir`
+///
+/// Returns the root definition followed by each dependency, separated by
+/// `MSG: pkg/Type` headers (the `ros2msg` schema encoding).
+pub fn load_message_definition(msg_dir: &Path, type_name: &str) -> Result<String> {
+    let root = MessageType::parse(type_name)?;
+
+    let mut output = String::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![root.clone()];
+    visited.insert(root.clone());
+
+    while let Some(msg_type) = pending.pop() {
+        let path = msg_type.file_path(msg_dir);
+        let definition = std::fs::read_to_string(&path).context(format!(
+            "Failed to read message definition for '{}' at {}",
+            msg_type.qualified_name(),
+            path.display()
+        ))?;
+
+        if msg_type != root {
+            output.push('\n');
+            output.push_str(DEFINITION_SEPARATOR);
+            output.push_str(&format!("\nMSG: {}\n", msg_type.qualified_name()));
+        }
+        output.push_str(definition.trim_end());
+        output.push('\n');
+
+        // Push in reverse so dependencies are emitted in declaration order
+        let mut dependencies = field_dependencies(&definition, &msg_type.package);
+        dependencies.reverse();
+        for dependency in dependencies {
+            if visited.insert(dependency.clone()) {
+                pending.push(dependency);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Collect the non-primitive field types referenced by a definition
+fn field_dependencies(definition: &str, package: &str) -> Vec<MessageType> {
+    let mut dependencies = Vec::new();
+
+    for line in definition.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let (Some(field_type), Some(field_name)) = (tokens.next(), tokens.next()) else {
+            continue;
+        };
+
+        // Constants (`int32 FOO=1`) never introduce dependencies
+        if field_name.contains('=') || tokens.next().is_some_and(|t| t.starts_with('=')) {
+            continue;
+        }
+
+        // Strip array (`[]`, `[3]`, `[<=3]`) and bounded string (`string<=10`) suffixes
+        let base_type = field_type.split('[').next().unwrap_or(field_type);
+        let base_type = base_type.split("<=").next().unwrap_or(base_type);
+
+        if PRIMITIVE_TYPES.contains(&base_type) {
+            continue;
+        }
+
+        let dependency = if base_type.contains('/') {
+            MessageType::parse(base_type).ok()
+        } else {
+            Some(MessageType {
+                package: package.to_string(),
+                name: base_type.to_string(),
+            })
+        };
+
+        if let Some(dependency) = dependency {
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+        }
+    }
+
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_msg(dir: &Path, package: &str, name: &str, content: &str) {
+        let msg_dir = dir.join(package).join("msg");
+        std::fs::create_dir_all(&msg_dir).unwrap();
+        std::fs::write(msg_dir.join(format!("{}.msg", name)), content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_type_names() {
+        let t = MessageType::parse("sensor_msgs/msg/Image").unwrap();
+        assert_eq!(t.qualified_name(), "sensor_msgs/Image");
+        let t = MessageType::parse("sensor_msgs/Image").unwrap();
+        assert_eq!(t.qualified_name(), "sensor_msgs/Image");
+        assert!(MessageType::parse("Image").is_err());
+        assert!(MessageType::parse("a/b/c/d").is_err());
+    }
+
+    #[test]
+    fn test_load_with_nested_dependencies() {
+        let dir = TempDir::new().unwrap();
+        write_msg(
+            dir.path(),
+            "builtin_interfaces",
+            "Time",
+            "int32 sec\nuint32 nanosec\n",
+        );
+        write_msg(
+            dir.path(),
+            "std_msgs",
+            "Header",
+            "builtin_interfaces/Time stamp\nstring frame_id\n",
+        );
+        write_msg(
+            dir.path(),
+            "geometry_msgs",
+            "Point",
+            "float64 x\nfloat64 y\nfloat64 z\n",
+        );
+        write_msg(
+            dir.path(),
+            "geometry_msgs",
+            "PointStamped",
+            "# A point with a header\nstd_msgs/Header header\nPoint point\nPoint[] history\nuint8 MODE_A=1\n",
+        );
+
+        let definition =
+            load_message_definition(dir.path(), "geometry_msgs/msg/PointStamped").unwrap();
+
+        assert!(definition.starts_with("# A point with a header"));
+        assert!(definition.contains("MSG: std_msgs/Header\nbuiltin_interfaces/Time stamp"));
+        assert!(definition.contains("MSG: builtin_interfaces/Time\nint32 sec"));
+        assert!(definition.contains("MSG: geometry_msgs/Point\nfloat64 x"));
+        // Each dependency appears once even when referenced twice
+        assert_eq!(definition.matches("MSG: geometry_msgs/Point\n").count(), 1);
+        // Declaration order is preserved
+        assert!(
+            definition.find("MSG: std_msgs/Header").unwrap()
+                < definition.find("MSG: geometry_msgs/Point").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_primitive_and_bounded_fields() {
+        let deps = field_dependencies(
+            "string<=10 name\nuint8[<=4] data\nfloat32[3] v\nint32 X = 3\nbool flag # comment\n",
+            "pkg",
+        );
+        assert!(deps.is_empty());
+    }
+
+    #[test]
+    fn test_missing_definition() {
+        let dir = TempDir::new().unwrap();
+        let result = load_message_definition(dir.path(), "missing_msgs/msg/Nothing");
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("missing_msgs/Nothing"));
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Async runtime compatibility layer
+//
+// The library only needs three runtime services: spawning detached tasks,
+// timers, and running blocking (filesystem) work off the executor. They are
+// selected at compile time:
+// - `runtime-tokio` (default)
+// - `runtime-smol`
+// - `runtime-async-std`
+//
+// If several runtime features are enabled, smol wins over async-std, which
+// wins over tokio, so an embedding application only has to add its runtime
+// feature. Synchronization primitives (`tokio::sync`) are executor-agnostic
+// and used with every runtime. The ReductStore backend is built on reqwest and
+// still requires a tokio reactor; the filesystem backend works everywhere.
+
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(not(any(
+    feature = "runtime-tokio",
+    feature = "runtime-smol",
+    feature = "runtime-async-std"
+)))]
+compile_error!(
+    "zenoh-recorder needs one of the features `runtime-tokio`, `runtime-smol` or `runtime-async-std`"
+);
+
+pub use imp::{sleep, spawn, unblock};
+
+#[cfg(feature = "runtime-smol")]
+mod imp {
+    use super::*;
+
+    /// Spawn a detached background task
+    pub fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        smol::spawn(future).detach();
+    }
+
+    /// Sleep for `duration`
+    pub async fn sleep(duration: Duration) {
+        smol::Timer::after(duration).await;
+    }
+
+    /// Run blocking work on the runtime's blocking thread pool
+    pub async fn unblock<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        smol::unblock(f).await
+    }
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-smol")))]
+mod imp {
+    use super::*;
+
+    /// Spawn a detached background task
+    pub fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        async_std::task::spawn(future);
+    }
+
+    /// Sleep for `duration`
+    pub async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await;
+    }
+
+    /// Run blocking work on the runtime's blocking thread pool
+    pub async fn unblock<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        async_std::task::spawn_blocking(f).await
+    }
+}
+
+#[cfg(all(
+    feature = "runtime-tokio",
+    not(any(feature = "runtime-smol", feature = "runtime-async-std"))
+))]
+mod imp {
+    use super::*;
+
+    /// Spawn a detached background task
+    pub fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        tokio::spawn(future);
+    }
+
+    /// Sleep for `duration`
+    pub async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    /// Run blocking work on the runtime's blocking thread pool
+    pub async fn unblock<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        tokio::task::spawn_blocking(f)
+            .await
+            .expect("blocking task panicked")
+    }
+}
+
+/// Sleep for at least `duration`, waking on the next multiple of
+/// `granularity` of the wall clock
+///
+/// Timers of different tasks aligned to the same granularity expire together,
+/// so the CPU wakes once for all of them.
+pub async fn sleep_aligned(duration: Duration, granularity: Option<Duration>) {
+    sleep(aligned(duration, granularity)).await;
+}
+
+/// `duration` extended to end on the next multiple of `granularity` of the
+/// wall clock
+pub fn aligned(duration: Duration, granularity: Option<Duration>) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    align(now, duration, granularity)
+}
+
+/// `duration` extended so that `now + duration` is a multiple of `granularity`
+fn align(now: Duration, duration: Duration, granularity: Option<Duration>) -> Duration {
+    let Some(granularity) = granularity.filter(|g| !g.is_zero()) else {
+        return duration;
+    };
+    let deadline = (now + duration).as_nanos();
+    let granularity = granularity.as_nanos();
+    let aligned = deadline.div_ceil(granularity) * granularity;
+    duration + Duration::from_nanos((aligned - deadline) as u64)
+}
+
+/// Filesystem operations executed on the blocking thread pool
+pub mod fs {
+    use super::unblock;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    pub async fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+        unblock(move || std::fs::create_dir_all(path)).await
+    }
+
+    pub async fn write(path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+        let contents = contents.into();
+        unblock(move || std::fs::write(path, contents)).await
+    }
+
+    pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref().to_path_buf();
+        unblock(move || std::fs::read(path)).await
+    }
+
+    pub async fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        let from = from.as_ref().to_path_buf();
+        let to = to.as_ref().to_path_buf();
+        unblock(move || std::fs::rename(from, to)).await
+    }
+
+    pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+        unblock(move || std::fs::remove_file(path)).await
+    }
+
+    pub async fn metadata(path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+        let path = path.as_ref().to_path_buf();
+        unblock(move || std::fs::metadata(path)).await
+    }
+
+    /// List the paths of a directory's entries
+    pub async fn read_dir(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let path = path.as_ref().to_path_buf();
+        unblock(move || {
+            std::fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_spawn_and_sleep() {
+        let done = Arc::new(AtomicBool::new(false));
+        let flag = done.clone();
+        spawn(async move {
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(done.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_align() {
+        let second = Some(Duration::from_secs(1));
+        let now = Duration::from_millis(10_250);
+        assert_eq!(
+            align(now, Duration::from_millis(300), second),
+            Duration::from_millis(750)
+        );
+        // Already on the tick
+        assert_eq!(
+            align(now, Duration::from_millis(750), second),
+            Duration::from_millis(750)
+        );
+        assert_eq!(
+            align(now, Duration::from_millis(300), None),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).await.unwrap();
+
+        let file = nested.join("data.bin");
+        fs::write(&file, b"hello".to_vec()).await.unwrap();
+        assert_eq!(fs::read(&file).await.unwrap(), b"hello");
+
+        let moved = nested.join("moved.bin");
+        fs::rename(&file, &moved).await.unwrap();
+        assert_eq!(fs::read_dir(&nested).await.unwrap(), vec![moved.clone()]);
+        assert!(fs::metadata(&moved).await.unwrap().is_file());
+
+        fs::remove_file(&moved).await.unwrap();
+        assert!(fs::read_dir(&nested).await.unwrap().is_empty());
+    }
+}
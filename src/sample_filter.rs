@@ -0,0 +1,528 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Expressions of the `filter` sample transform
+//
+// A filter keeps the samples of a topic whose JSON payload satisfies an
+// expression such as `payload.speed > 2.0 && payload.mode != "idle"`. The
+// language is deliberately small: fields of the payload, literals,
+// comparisons and boolean operators. There are no function calls, variables
+// or loops, and nesting is limited to `MAX_DEPTH`, so an expression is parsed
+// once when the transform is built and evaluates in time linear in its size.
+//
+//     expression := or
+//     or         := and (("||" | "or") and)*
+//     and        := not (("&&" | "and") not)*
+//     not        := ("!" | "not") not | comparison
+//     comparison := operand (("==" | "!=" | "<" | "<=" | ">" | ">=") operand)?
+//     operand    := "(" expression ")" | field | number | string
+//                   | "true" | "false" | "null"
+//     field      := "payload" ("." name | "[" (index | string) "]")*
+//
+// Missing fields are null. Numbers compare by value (`2 == 2.0`), strings in
+// byte order, and ordering a number against anything else is false. An
+// operand on its own is true unless it is null, false, 0 or empty.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Deepest nesting of parentheses and negations
+const MAX_DEPTH: usize = 32;
+
+/// Longest expression accepted, in bytes
+const MAX_LENGTH: usize = 4096;
+
+/// Parsed filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Or(Vec<Node>),
+    And(Vec<Node>),
+    Not(Box<Node>),
+    Compare(Operand, CompareOp, Operand),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Literal(Value),
+    Field(Vec<Step>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Expression {
+    /// Parse `source`; the error names the offending position
+    pub fn parse(source: &str) -> Result<Self> {
+        if source.len() > MAX_LENGTH {
+            bail!("Expression is longer than {} bytes", MAX_LENGTH);
+        }
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            depth: 0,
+        };
+        let root = parser.or()?;
+        if let Some((token, at)) = parser.tokens.get(parser.position) {
+            bail!("Unexpected {} at {}", token, at);
+        }
+        Ok(Self { root })
+    }
+
+    /// Whether `payload` satisfies the expression
+    pub fn matches(&self, payload: &Value) -> bool {
+        self.root.eval(payload)
+    }
+}
+
+impl Node {
+    fn eval(&self, payload: &Value) -> bool {
+        match self {
+            Node::Or(nodes) => nodes.iter().any(|node| node.eval(payload)),
+            Node::And(nodes) => nodes.iter().all(|node| node.eval(payload)),
+            Node::Not(node) => !node.eval(payload),
+            Node::Compare(left, op, right) => {
+                op.holds(left.resolve(payload), right.resolve(payload))
+            }
+            Node::Truthy(operand) => truthy(operand.resolve(payload)),
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, payload: &'a Value) -> &'a Value {
+        match self {
+            Operand::Literal(value) => value,
+            Operand::Field(steps) => steps
+                .iter()
+                .try_fold(payload, |current, step| match step {
+                    Step::Key(key) => current.get(key),
+                    Step::Index(index) => current.get(index),
+                })
+                .unwrap_or(&Value::Null),
+        }
+    }
+}
+
+impl CompareOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+
+    fn holds(self, left: &Value, right: &Value) -> bool {
+        match self {
+            CompareOp::Eq => equal(left, right),
+            CompareOp::Ne => !equal(left, right),
+            CompareOp::Lt => order(left, right) == Some(Ordering::Less),
+            CompareOp::Le => matches!(order(left, right), Some(Ordering::Less | Ordering::Equal)),
+            CompareOp::Gt => order(left, right) == Some(Ordering::Greater),
+            CompareOp::Ge => matches!(
+                order(left, right),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+        }
+    }
+}
+
+fn equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(left), Some(right)) => left == right,
+        _ => left == right,
+    }
+}
+
+fn order(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Number(f64),
+    Text(String),
+    Compare(CompareOp),
+    And,
+    Or,
+    Not,
+    Minus,
+    Dot,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Compare(op) => write!(f, "'{}'", op.symbol()),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::Minus => write!(f, "'-'"),
+            Token::Dot => write!(f, "'.'"),
+            Token::OpenParen => write!(f, "'('"),
+            Token::CloseParen => write!(f, "')'"),
+            Token::OpenBracket => write!(f, "'['"),
+            Token::CloseBracket => write!(f, "']'"),
+        }
+    }
+}
+
+/// Tokens of `source`, each with its byte offset
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, c)| *c == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '.' => Token::Dot,
+            '-' => Token::Minus,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '=' if next_is('=') => Token::Compare(CompareOp::Eq),
+            '!' if next_is('=') => Token::Compare(CompareOp::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Compare(CompareOp::Le),
+            '<' => Token::Compare(CompareOp::Lt),
+            '>' if next_is('=') => Token::Compare(CompareOp::Ge),
+            '>' => Token::Compare(CompareOp::Gt),
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == c => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => bail!("Unterminated string at {}", at),
+                        },
+                        Some((_, other)) => text.push(other),
+                        None => bail!("Unterminated string at {}", at),
+                    }
+                }
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = at + c.len_utf8();
+                let mut exponent = false;
+                while let Some(&(i, next)) = chars.peek() {
+                    let sign = (next == '+' || next == '-')
+                        && exponent
+                        && source[..i].ends_with(['e', 'E']);
+                    if !(next.is_ascii_digit() || next == '.' || sign || "eE".contains(next)) {
+                        break;
+                    }
+                    exponent |= "eE".contains(next);
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                let number = source[at..end]
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid number '{}' at {}", &source[at..end], at))?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = at + c.len_utf8();
+                while let Some((i, next)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    end = i + next.len_utf8();
+                }
+                match &source[at..end] {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    name => Token::Name(name.to_string()),
+                }
+            }
+            other => bail!("Unexpected '{}' at {}", other, at),
+        };
+        tokens.push((token, at));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<(Token, usize)> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next()? {
+            (token, _) if token == expected => Ok(()),
+            (token, at) => bail!("Expected {} but found {} at {}", expected, token, at),
+        }
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            bail!("Expression is nested deeper than {} levels", MAX_DEPTH);
+        }
+        let parsed = parse(self);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.and()?];
+        while self.eat(&Token::Or) {
+            nodes.push(self.and()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::Or(nodes),
+        })
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.not()?];
+        while self.eat(&Token::And) {
+            nodes.push(self.not()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::And(nodes),
+        })
+    }
+
+    fn not(&mut self) -> Result<Node> {
+        if self.eat(&Token::Not) {
+            return self.nested(|parser| Ok(Node::Not(Box::new(parser.not()?))));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Node> {
+        if self.eat(&Token::OpenParen) {
+            let node = self.nested(Self::or)?;
+            self.expect(Token::CloseParen)?;
+            return Ok(node);
+        }
+        let left = self.operand()?;
+        let Some(&Token::Compare(op)) = self.peek() else {
+            return Ok(Node::Truthy(left));
+        };
+        self.position += 1;
+        Ok(Node::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        let literal = match self.next()? {
+            (Token::Number(number), _) => number_value(number),
+            (Token::Minus, at) => match self.next()? {
+                (Token::Number(number), _) => number_value(-number),
+                _ => bail!("Expected a number after '-' at {}", at),
+            },
+            (Token::Text(text), _) => Value::String(text),
+            (Token::Name(name), at) => match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                "payload" => return Ok(Operand::Field(self.steps()?)),
+                _ => bail!(
+                    "Unknown name '{}' at {}; fields start with 'payload.'",
+                    name,
+                    at
+                ),
+            },
+            (token, at) => bail!("Unexpected {} at {}", token, at),
+        };
+        Ok(Operand::Literal(literal))
+    }
+
+    fn steps(&mut self) -> Result<Vec<Step>> {
+        let mut steps = Vec::new();
+        loop {
+            if self.eat(&Token::Dot) {
+                match self.next()? {
+                    (Token::Name(name), _) => steps.push(Step::Key(name)),
+                    (token, at) => bail!("Expected a field name but found {} at {}", token, at),
+                }
+            } else if self.eat(&Token::OpenBracket) {
+                match self.next()? {
+                    (Token::Text(key), _) => steps.push(Step::Key(key)),
+                    (Token::Number(index), _) if index >= 0.0 && index.fract() == 0.0 => {
+                        steps.push(Step::Index(index as usize))
+                    }
+                    (token, at) => {
+                        bail!("Expected an index or a key but found {} at {}", token, at)
+                    }
+                }
+                self.expect(Token::CloseBracket)?;
+            } else {
+                return Ok(steps);
+            }
+        }
+    }
+}
+
+fn number_value(number: f64) -> Value {
+    serde_json::Number::from_f64(number).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn holds(expression: &str, payload: Value) -> bool {
+        Expression::parse(expression).unwrap().matches(&payload)
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let payload = json!({"speed": 2.5, "mode": "auto", "gear": 2, "tags": ["a", "b"]});
+        assert!(holds("payload.speed > 2.0", payload.clone()));
+        assert!(!holds("payload.speed <= 2", payload.clone()));
+        assert!(holds("payload.gear == 2.0", payload.clone()));
+        assert!(holds("payload.mode == 'auto'", payload.clone()));
+        assert!(holds(r#"payload.mode != "manual""#, payload.clone()));
+        assert!(holds("payload.mode < 'b'", payload.clone()));
+        assert!(holds("payload.tags[1] == 'b'", payload.clone()));
+        assert!(holds("payload['mode'] == 'auto'", payload.clone()));
+        assert!(holds("payload.speed > -1e1", payload.clone()));
+        assert!(holds("payload.speed >= payload.gear", payload.clone()));
+        // Mismatched types don't order
+        assert!(!holds("payload.mode > 1", payload.clone()));
+        assert!(!holds("payload.mode < 1", payload));
+    }
+
+    #[test]
+    fn test_missing_fields_are_null() {
+        let payload = json!({"pose": {"x": 1.0}});
+        assert!(holds("payload.pose.y == null", payload.clone()));
+        assert!(!holds("payload.pose.y > 0", payload.clone()));
+        assert!(!holds("payload.pose.y", payload.clone()));
+        assert!(holds("payload.pose.x", payload.clone()));
+        assert!(!holds("payload.pose.x.z", payload));
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let payload = json!({"speed": 3.0, "estop": false, "mode": "auto"});
+        assert!(holds(
+            "payload.speed > 2 && !payload.estop",
+            payload.clone()
+        ));
+        assert!(holds(
+            "payload.speed > 5 or payload.mode == 'auto'",
+            payload.clone()
+        ));
+        assert!(!holds(
+            "not (payload.speed > 2 and payload.mode == 'auto')",
+            payload.clone()
+        ));
+        // && binds tighter than ||
+        assert!(holds(
+            "payload.estop && payload.speed > 5 || payload.speed > 2",
+            payload
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (expression, error) in [
+            ("speed > 2", "Unknown name 'speed'"),
+            ("payload.speed >", "Unexpected end"),
+            ("payload.speed > 2 2", "Unexpected 2 at 18"),
+            ("(payload.speed > 2", "Unexpected end"),
+            ("payload.mode == 'auto", "Unterminated string"),
+            ("payload.speed = 2", "Unexpected '='"),
+            ("payload.tags[-1]", "Expected an index"),
+            ("1.2.3 > 0", "Invalid number"),
+        ] {
+            let err = Expression::parse(expression).unwrap_err().to_string();
+            assert!(err.contains(error), "{}: {}", expression, err);
+        }
+
+        let nested = format!("{}payload.x{}", "(".repeat(40), ")".repeat(40));
+        assert!(Expression::parse(&nested).is_err());
+        assert!(Expression::parse(&"!".repeat(40)).is_err());
+        assert!(Expression::parse(&"payload.x || ".repeat(1000)).is_err());
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Sample filters and redactions before storage (`recorder.transforms`)
+//
+// Sample transforms run in the flush path, on the payload of every sample as
+// its batch is serialized, before the pre-compression transform and the codec.
+// Each one rewrites the payload in place or drops the sample; a topic's chain
+// stops at the first drop. What they remove never reaches the storage backend,
+// which makes them the place for PII such as GPS fixes or camera regions. A
+// `filter` keeps only the samples matching an expression on their JSON payload
+// (see `crate::sample_filter`), e.g. the intervals a robot actually moved.
+//
+// The built-ins are configured per topic; applications embedding the recorder
+// add their own with `RecorderManagerBuilder::sample_transform`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use tracing::{error, trace};
+
+use crate::config::{find_per_topic, SampleTransformConfig, TransformsConfig};
+use crate::sample_filter::Expression;
+
+/// Lossy rewrite of the samples of a topic
+pub trait SampleTransform: fmt::Debug + Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Rewrite the payload of a sample of `topic` in place; false drops the
+    /// sample
+    fn apply(&self, topic: &str, payload: &mut Vec<u8>) -> bool;
+}
+
+/// Drops the samples matching every condition given
+#[derive(Debug, Clone)]
+pub struct DropSamples {
+    min_bytes: Option<usize>,
+    json_field: Option<Vec<String>>,
+    equals: Option<serde_json::Value>,
+}
+
+impl DropSamples {
+    pub fn new(
+        min_bytes: Option<usize>,
+        json_field: Option<&str>,
+        equals: Option<serde_json::Value>,
+    ) -> Self {
+        Self {
+            min_bytes,
+            json_field: json_field.map(field_path),
+            equals,
+        }
+    }
+
+    fn matches(&self, payload: &[u8]) -> bool {
+        if self.min_bytes.is_some_and(|min| payload.len() < min) {
+            return false;
+        }
+        let Some(path) = &self.json_field else {
+            return true;
+        };
+        let Ok(value) = serde_json::from_slice::<serde_json::Value>(payload) else {
+            return false;
+        };
+        match path
+            .iter()
+            .try_fold(&value, |current, key| current.get(key))
+        {
+            Some(field) => self.equals.as_ref().is_none_or(|equals| field == equals),
+            None => false,
+        }
+    }
+}
+
+impl SampleTransform for DropSamples {
+    fn name(&self) -> &'static str {
+        "drop"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        !self.matches(payload)
+    }
+}
+
+/// Keeps the JSON samples matching an expression, drops all others
+#[derive(Debug, Clone)]
+pub struct FilterSamples {
+    expression: Expression,
+}
+
+impl FilterSamples {
+    pub fn new(expression: Expression) -> Self {
+        Self { expression }
+    }
+}
+
+impl SampleTransform for FilterSamples {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        serde_json::from_slice::<serde_json::Value>(payload)
+            .is_ok_and(|value| self.expression.matches(&value))
+    }
+}
+
+/// Keeps every sample unchanged, in place of an invalid filter
+#[derive(Debug, Clone)]
+struct KeepAll;
+
+impl SampleTransform for KeepAll {
+    fn name(&self) -> &'static str {
+        "keep_all"
+    }
+
+    fn apply(&self, _topic: &str, _payload: &mut Vec<u8>) -> bool {
+        true
+    }
+}
+
+/// Cuts payloads to their first `max_bytes`
+#[derive(Debug, Clone)]
+pub struct Truncate {
+    max_bytes: usize,
+}
+
+impl Truncate {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl SampleTransform for Truncate {
+    fn name(&self) -> &'static str {
+        "truncate"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        payload.truncate(self.max_bytes);
+        true
+    }
+}
+
+/// Overwrites byte ranges of payloads, e.g. a region of raw camera frames
+#[derive(Debug, Clone)]
+pub struct RedactBytes {
+    ranges: Vec<Range<usize>>,
+    fill: u8,
+}
+
+impl RedactBytes {
+    pub fn new(ranges: Vec<Range<usize>>, fill: u8) -> Self {
+        Self { ranges, fill }
+    }
+}
+
+impl SampleTransform for RedactBytes {
+    fn name(&self) -> &'static str {
+        "redact_bytes"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        let len = payload.len();
+        for range in &self.ranges {
+            let (start, end) = (range.start.min(len), range.end.min(len));
+            if start < end {
+                payload[start..end].fill(self.fill);
+            }
+        }
+        true
+    }
+}
+
+/// Replaces fields of JSON payloads; other payloads are kept as they are
+#[derive(Debug, Clone)]
+pub struct RedactJson {
+    fields: Vec<Vec<String>>,
+    replacement: serde_json::Value,
+}
+
+impl RedactJson {
+    pub fn new(fields: &[String], replacement: serde_json::Value) -> Self {
+        Self {
+            fields: fields.iter().map(|field| field_path(field)).collect(),
+            replacement,
+        }
+    }
+}
+
+impl SampleTransform for RedactJson {
+    fn name(&self) -> &'static str {
+        "redact_json"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(payload) else {
+            return true;
+        };
+        let mut redacted = false;
+        for path in &self.fields {
+            let Some((last, parents)) = path.split_last() else {
+                continue;
+            };
+            let field = parents
+                .iter()
+                .try_fold(&mut value, |current, key| current.get_mut(key))
+                .and_then(|parent| parent.get_mut(last));
+            if let Some(field) = field {
+                *field = self.replacement.clone();
+                redacted = true;
+            }
+        }
+        if redacted {
+            if let Ok(rewritten) = serde_json::to_vec(&value) {
+                *payload = rewritten;
+            }
+        }
+        true
+    }
+}
+
+fn field_path(field: &str) -> Vec<String> {
+    field.split('.').map(String::from).collect()
+}
+
+/// Built-in transform of a configuration entry
+pub fn build(config: &SampleTransformConfig) -> Arc<dyn SampleTransform> {
+    match config {
+        SampleTransformConfig::Drop {
+            min_bytes,
+            json_field,
+            equals,
+        } => Arc::new(DropSamples::new(
+            *min_bytes,
+            json_field.as_deref(),
+            equals.clone(),
+        )),
+        SampleTransformConfig::Filter { expression } => match Expression::parse(expression) {
+            Ok(expression) => Arc::new(FilterSamples::new(expression)),
+            // Rejected by config validation; keep the samples rather than lose them
+            Err(e) => {
+                error!("Ignoring filter '{}': {:#}", expression, e);
+                Arc::new(KeepAll)
+            }
+        },
+        SampleTransformConfig::Truncate { max_bytes } => Arc::new(Truncate::new(*max_bytes)),
+        SampleTransformConfig::RedactBytes { ranges, fill } => Arc::new(RedactBytes::new(
+            ranges.iter().map(|[start, end]| *start..*end).collect(),
+            *fill,
+        )),
+        SampleTransformConfig::RedactJson {
+            fields,
+            replacement,
+        } => Arc::new(RedactJson::new(
+            fields,
+            replacement
+                .clone()
+                .map_or(serde_json::Value::Null, serde_json::Value::String),
+        )),
+    }
+}
+
+/// Transform chains keyed by topic pattern
+#[derive(Debug, Clone, Default)]
+pub struct SampleTransforms {
+    per_topic: HashMap<String, Vec<Arc<dyn SampleTransform>>>,
+}
+
+impl SampleTransforms {
+    pub fn from_config(config: &TransformsConfig) -> Self {
+        Self {
+            per_topic: config
+                .per_topic
+                .iter()
+                .map(|(pattern, transforms)| {
+                    (pattern.clone(), transforms.iter().map(build).collect())
+                })
+                .collect(),
+        }
+    }
+
+    /// Append `transform` to the chain of `pattern`
+    pub fn add(&mut self, pattern: &str, transform: Arc<dyn SampleTransform>) {
+        self.per_topic
+            .entry(pattern.to_string())
+            .or_default()
+            .push(transform);
+    }
+
+    /// Chain of a topic (empty when no pattern matches)
+    pub fn for_topic(&self, topic: &str) -> Vec<Arc<dyn SampleTransform>> {
+        find_per_topic(&self.per_topic, topic)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Run a chain over a payload; false when a transform dropped the sample
+pub fn apply_all(
+    transforms: &[Arc<dyn SampleTransform>],
+    topic: &str,
+    payload: &mut Vec<u8>,
+) -> bool {
+    for transform in transforms {
+        if !transform.apply(topic, payload) {
+            trace!("{} dropped a sample of '{}'", transform.name(), topic);
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_conditions() {
+        let by_size = DropSamples::new(Some(4), None, None);
+        assert!(by_size.apply("t", &mut vec![0; 3]));
+        assert!(!by_size.apply("t", &mut vec![0; 4]));
+
+        let by_field = DropSamples::new(None, Some("fix.status"), Some(serde_json::json!(-1)));
+        assert!(!by_field.apply("t", &mut br#"{"fix": {"status": -1}}"#.to_vec()));
+        assert!(by_field.apply("t", &mut br#"{"fix": {"status": 0}}"#.to_vec()));
+        assert!(by_field.apply("t", &mut br#"{"fix": {}}"#.to_vec()));
+        assert!(by_field.apply("t", &mut b"not json".to_vec()));
+
+        let present = DropSamples::new(None, Some("debug"), None);
+        assert!(!present.apply("t", &mut br#"{"debug": false}"#.to_vec()));
+    }
+
+    #[test]
+    fn test_filter_keeps_matching_json() {
+        let filter = FilterSamples::new(Expression::parse("payload.speed > 2.0").unwrap());
+        assert!(filter.apply("t", &mut br#"{"speed": 2.5}"#.to_vec()));
+        assert!(!filter.apply("t", &mut br#"{"speed": 1.0}"#.to_vec()));
+        assert!(!filter.apply("t", &mut br#"{"heading": 90}"#.to_vec()));
+        assert!(!filter.apply("t", &mut b"not json".to_vec()));
+
+        // Invalid expressions that got past validation keep everything
+        let config = SampleTransformConfig::Filter {
+            expression: "speed >".to_string(),
+        };
+        assert!(build(&config).apply("t", &mut b"not json".to_vec()));
+    }
+
+    #[test]
+    fn test_redact_bytes_clips_ranges() {
+        let redact = RedactBytes::new(vec![1..3, 5..100], 0xff);
+        let mut payload = vec![0u8; 6];
+        assert!(redact.apply("t", &mut payload));
+        assert_eq!(payload, vec![0, 0xff, 0xff, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn test_redact_json_fields() {
+        let redact = RedactJson::new(
+            &[
+                "gps.lat".to_string(),
+                "gps.lon".to_string(),
+                "missing".to_string(),
+            ],
+            serde_json::Value::Null,
+        );
+        let mut payload = br#"{"gps": {"lat": 48.1, "lon": 11.5, "alt": 520}, "id": 7}"#.to_vec();
+        assert!(redact.apply("t", &mut payload));
+        let value: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"gps": {"lat": null, "lon": null, "alt": 520}, "id": 7})
+        );
+
+        // Binary payloads pass through
+        let mut binary = vec![0xde, 0xad];
+        assert!(redact.apply("t", &mut binary));
+        assert_eq!(binary, vec![0xde, 0xad]);
+    }
+
+    #[test]
+    fn test_chain_stops_at_drop() {
+        let mut transforms = SampleTransforms::default();
+        transforms.add("camera/**", Arc::new(Truncate::new(2)));
+        transforms.add("camera/**", Arc::new(DropSamples::new(Some(2), None, None)));
+        let chain = transforms.for_topic("camera/front");
+        assert_eq!(chain.len(), 2);
+        assert!(!apply_all(&chain, "camera/front", &mut vec![1, 2, 3]));
+        let mut short = vec![1];
+        assert!(apply_all(&chain, "camera/front", &mut short));
+        assert_eq!(short, vec![1]);
+        assert!(transforms.for_topic("lidar/top").is_empty());
+    }
+}
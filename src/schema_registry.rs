@@ -0,0 +1,282 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Protobuf descriptor registry
+//
+// Loads `FileDescriptorSet` blobs (as produced by `protoc --descriptor_set_out
+// --include_imports`) from disk or from a Zenoh queryable and indexes every
+// message type they define. For a fully-qualified message name the registry
+// returns a self-contained `FileDescriptorSet` (the defining file plus its
+// transitive imports), which is what MCAP readers expect as protobuf schema data.
+//
+// It also caches the ROS 2 message definitions of CDR topics, which would
+// otherwise be read from disk on every flush.
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use prost::Message;
+use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use zenoh::Session;
+
+/// Registry of protobuf file descriptors indexed by message type
+#[derive(Default)]
+pub struct SchemaRegistry {
+    /// File name -> file descriptor
+    files: DashMap<String, FileDescriptorProto>,
+    /// Fully-qualified message name (without leading dot) -> defining file name
+    messages: DashMap<String, String>,
+    /// (message directory, ROS 2 type name) -> `ros2msg` definition
+    ros2_definitions: DashMap<(String, String), Vec<u8>>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register every file of an encoded `FileDescriptorSet`
+    ///
+    /// Returns the number of message types added.
+    pub fn add_descriptor_set(&self, data: &[u8]) -> Result<usize> {
+        let set = FileDescriptorSet::decode(data).context("Failed to decode FileDescriptorSet")?;
+
+        let mut added = 0;
+        for file in set.file {
+            let file_name = file.name().to_string();
+            let package = file.package().to_string();
+            for message in &file.message_type {
+                added += self.index_message(&package, message, &file_name);
+            }
+            self.files.insert(file_name, file);
+        }
+        Ok(added)
+    }
+
+    fn index_message(&self, scope: &str, message: &DescriptorProto, file_name: &str) -> usize {
+        let full_name = if scope.is_empty() {
+            message.name().to_string()
+        } else {
+            format!("{}.{}", scope, message.name())
+        };
+
+        let mut added = 1;
+        for nested in &message.nested_type {
+            added += self.index_message(&full_name, nested, file_name);
+        }
+        self.messages.insert(full_name, file_name.to_string());
+        added
+    }
+
+    /// Load descriptor sets from files or directories
+    ///
+    /// Directories are scanned (non-recursively) for `.desc`, `.pb` and `.binpb` files.
+    pub fn load_paths(&self, paths: &[String]) -> Result<usize> {
+        let mut added = 0;
+        for path in paths {
+            let path = Path::new(path);
+            if path.is_dir() {
+                let entries = std::fs::read_dir(path)
+                    .context(format!("Failed to read descriptor dir: {}", path.display()))?;
+                for entry in entries {
+                    let file = entry?.path();
+                    let is_descriptor = file
+                        .extension()
+                        .is_some_and(|ext| ext == "desc" || ext == "pb" || ext == "binpb");
+                    if is_descriptor {
+                        added += self.load_file(&file)?;
+                    }
+                }
+            } else {
+                added += self.load_file(path)?;
+            }
+        }
+        Ok(added)
+    }
+
+    fn load_file(&self, path: &Path) -> Result<usize> {
+        let data = std::fs::read(path)
+            .context(format!("Failed to read descriptor set: {}", path.display()))?;
+        let added = self
+            .add_descriptor_set(&data)
+            .context(format!("Invalid descriptor set: {}", path.display()))?;
+        debug!("Loaded {} message types from {}", added, path.display());
+        Ok(added)
+    }
+
+    /// Fetch descriptor sets from a Zenoh queryable
+    ///
+    /// Every reply payload must be an encoded `FileDescriptorSet`. Invalid
+    /// replies are skipped with a warning.
+    pub async fn fetch_from_zenoh(
+        &self,
+        session: &Session,
+        key_expr: &str,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let replies = session
+            .get(key_expr)
+            .timeout(timeout)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to query schemas on '{}': {}", key_expr, e))?;
+
+        let mut added = 0;
+        while let Ok(reply) = replies.recv_async().await {
+            match reply.result() {
+                Ok(sample) => match self.add_descriptor_set(&sample.payload().to_bytes()) {
+                    Ok(n) => added += n,
+                    Err(e) => warn!(
+                        "Skipping invalid descriptor set from '{}': {:#}",
+                        sample.key_expr(),
+                        e
+                    ),
+                },
+                Err(e) => warn!("Schema query on '{}' returned an error: {:?}", key_expr, e),
+            }
+        }
+
+        info!("Fetched {} message types from '{}'", added, key_expr);
+        Ok(added)
+    }
+
+    /// Build the encoded `FileDescriptorSet` describing `message_name`
+    ///
+    /// Dependencies come before the files importing them. Returns `None` if
+    /// the message type is unknown.
+    pub fn descriptor_set_for(&self, message_name: &str) -> Option<Vec<u8>> {
+        let file_name = self
+            .messages
+            .get(message_name.trim_start_matches('.'))?
+            .clone();
+
+        let mut set = FileDescriptorSet { file: Vec::new() };
+        let mut visited = HashSet::new();
+        self.collect_file(&file_name, &mut visited, &mut set.file);
+        Some(set.encode_to_vec())
+    }
+
+    /// ROS 2 definition of `type_name` in `msg_dir`, from `load` the first
+    /// time the type is asked for
+    ///
+    /// Failed loads (empty definitions) are cached as well, so a missing
+    /// definition is reported once.
+    pub fn ros2_definition(
+        &self,
+        msg_dir: &str,
+        type_name: &str,
+        load: impl FnOnce() -> Vec<u8>,
+    ) -> Vec<u8> {
+        self.ros2_definitions
+            .entry((msg_dir.to_string(), type_name.to_string()))
+            .or_insert_with(load)
+            .clone()
+    }
+
+    fn collect_file(
+        &self,
+        file_name: &str,
+        visited: &mut HashSet<String>,
+        out: &mut Vec<FileDescriptorProto>,
+    ) {
+        if !visited.insert(file_name.to_string()) {
+            return;
+        }
+
+        let Some(file) = self.files.get(file_name).map(|f| f.clone()) else {
+            warn!(
+                "Descriptor for '{}' is missing from the registry",
+                file_name
+            );
+            return;
+        };
+        for dependency in &file.dependency {
+            self.collect_file(dependency, visited, out);
+        }
+        out.push(file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::FieldDescriptorProto;
+
+    fn message(name: &str, nested: Vec<DescriptorProto>) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_string()),
+            field: vec![FieldDescriptorProto {
+                name: Some("value".to_string()),
+                number: Some(1),
+                ..Default::default()
+            }],
+            nested_type: nested,
+            ..Default::default()
+        }
+    }
+
+    fn test_set() -> Vec<u8> {
+        let common = FileDescriptorProto {
+            name: Some("common/header.proto".to_string()),
+            package: Some("common".to_string()),
+            message_type: vec![message("Header", vec![])],
+            ..Default::default()
+        };
+        let sensors = FileDescriptorProto {
+            name: Some("sensors/imu.proto".to_string()),
+            package: Some("sensors".to_string()),
+            dependency: vec!["common/header.proto".to_string()],
+            message_type: vec![message("Imu", vec![message("Covariance", vec![])])],
+            ..Default::default()
+        };
+        FileDescriptorSet {
+            file: vec![sensors, common],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_index_messages() {
+        let registry = SchemaRegistry::new();
+        assert_eq!(registry.add_descriptor_set(&test_set()).unwrap(), 3);
+        assert!(registry.descriptor_set_for("sensors.Imu").is_some());
+        assert!(registry
+            .descriptor_set_for(".sensors.Imu.Covariance")
+            .is_some());
+        assert!(registry.descriptor_set_for("common.Header").is_some());
+        assert!(registry.descriptor_set_for("sensors.Gps").is_none());
+    }
+
+    #[test]
+    fn test_descriptor_set_includes_dependencies() {
+        let registry = SchemaRegistry::new();
+        registry.add_descriptor_set(&test_set()).unwrap();
+
+        let data = registry.descriptor_set_for("sensors.Imu").unwrap();
+        let set = FileDescriptorSet::decode(data.as_slice()).unwrap();
+        let names: Vec<&str> = set.file.iter().map(|f| f.name()).collect();
+        assert_eq!(names, vec!["common/header.proto", "sensors/imu.proto"]);
+
+        assert!(registry.descriptor_set_for("unknown.Msg").is_none());
+    }
+
+    #[test]
+    fn test_invalid_descriptor_set() {
+        let registry = SchemaRegistry::new();
+        assert!(registry.add_descriptor_set(b"\xff\xff\xff").is_err());
+        assert!(registry.messages.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Zstd seekable format of compressed batches
+//
+// Zstd batches are written as independent frames of about
+// `compression.seekable_frame_bytes` of serialized messages each, followed by
+// the seek table of the zstd seekable format: a skippable frame listing the
+// compressed and decompressed size of every frame. The first frame holds only
+// the batch header and frames end on message boundaries, so the header frame
+// followed by any of the others decompresses to a valid batch. Right before
+// the seek table, a second skippable frame holds the time range of the
+// messages of every frame, so a reader extracting a time range decompresses
+// only the frames that overlap it. Plain zstd decoders skip skippable frames,
+// so seekable batches decode like single-frame ones.
+
+use anyhow::{bail, Context, Result};
+
+/// Magic of the skippable frame holding the seek table
+const SEEK_TABLE_FRAME_MAGIC: u32 = 0x184D_2A5E;
+/// Magic of the skippable frame holding the time index
+const TIME_INDEX_FRAME_MAGIC: u32 = 0x184D_2A5D;
+/// Last four bytes of a seekable stream
+const SEEKABLE_MAGIC: u32 = 0x8F92_EAB1;
+/// Tag opening the time index, after the skippable frame header
+const TIME_INDEX_TAG: &[u8; 4] = b"ZRTI";
+/// Seek table footer: frame count, descriptor, magic
+const FOOTER_LEN: usize = 9;
+/// Skippable frame header: magic, size
+const FRAME_HEADER_LEN: usize = 8;
+
+/// One compressed frame of a seekable batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// Offset of the frame in the stored batch
+    pub offset: usize,
+    pub compressed_size: usize,
+    pub decompressed_size: usize,
+    /// (first, last) message timestamp in nanoseconds; None for the header
+    /// frame and batches without a time index
+    pub time_range: Option<(i64, i64)>,
+}
+
+/// Writes a batch as seekable zstd frames
+pub struct SeekableEncoder {
+    level: i32,
+    frame_bytes: usize,
+    out: Vec<u8>,
+    pending: Vec<u8>,
+    pending_range: Option<(i64, i64)>,
+    frames: Vec<Frame>,
+}
+
+impl SeekableEncoder {
+    /// Encoder compressing at zstd `level`, cutting frames after `frame_bytes`
+    /// of messages
+    pub fn new(level: i32, frame_bytes: usize) -> Self {
+        Self {
+            level,
+            frame_bytes: frame_bytes.max(1),
+            out: Vec::new(),
+            pending: Vec::new(),
+            pending_range: None,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Write the batch header as the first frame
+    pub fn write_header(&mut self, header: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(header);
+        self.flush_frame()
+    }
+
+    /// Append a message with its length prefix
+    pub fn write_message(&mut self, message: &[u8], timestamp_ns: i64) -> Result<()> {
+        self.pending
+            .extend_from_slice(&(message.len() as u32).to_le_bytes());
+        self.pending.extend_from_slice(message);
+        self.pending_range = Some(match self.pending_range {
+            Some((first, last)) => (first.min(timestamp_ns), last.max(timestamp_ns)),
+            None => (timestamp_ns, timestamp_ns),
+        });
+        if self.pending.len() >= self.frame_bytes {
+            self.flush_frame()?;
+        }
+        Ok(())
+    }
+
+    /// Compress the last frame and append the time index and seek table
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        if !self.pending.is_empty() {
+            self.flush_frame()?;
+        }
+
+        let mut index = TIME_INDEX_TAG.to_vec();
+        for frame in &self.frames {
+            let (first, last) = frame.time_range.unwrap_or((i64::MAX, i64::MIN));
+            index.extend_from_slice(&first.to_le_bytes());
+            index.extend_from_slice(&last.to_le_bytes());
+        }
+        write_skippable(&mut self.out, TIME_INDEX_FRAME_MAGIC, &index);
+
+        let mut table = Vec::with_capacity(self.frames.len() * 8 + FOOTER_LEN);
+        for frame in &self.frames {
+            table.extend_from_slice(&(frame.compressed_size as u32).to_le_bytes());
+            table.extend_from_slice(&(frame.decompressed_size as u32).to_le_bytes());
+        }
+        table.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        // Descriptor: no per-frame checksums
+        table.push(0);
+        table.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        write_skippable(&mut self.out, SEEK_TABLE_FRAME_MAGIC, &table);
+        Ok(self.out)
+    }
+
+    fn flush_frame(&mut self) -> Result<()> {
+        let compressed =
+            zstd::bulk::compress(&self.pending, self.level).context("Zstd compression failed")?;
+        self.frames.push(Frame {
+            offset: self.out.len(),
+            compressed_size: compressed.len(),
+            decompressed_size: self.pending.len(),
+            time_range: self.pending_range.take(),
+        });
+        self.out.extend_from_slice(&compressed);
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+fn write_skippable(out: &mut Vec<u8>, magic: u32, content: &[u8]) {
+    out.extend_from_slice(&magic.to_le_bytes());
+    out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    out.extend_from_slice(content);
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn i64_at(data: &[u8], offset: usize) -> Option<i64> {
+    Some(i64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Frames of a seekable batch; None when `data` has no seek table
+pub fn read_frames(data: &[u8]) -> Result<Option<Vec<Frame>>> {
+    if data.len() < FRAME_HEADER_LEN + FOOTER_LEN
+        || u32_at(data, data.len() - 4) != Some(SEEKABLE_MAGIC)
+    {
+        return Ok(None);
+    }
+    let count = u32_at(data, data.len() - FOOTER_LEN).unwrap_or_default() as usize;
+    let descriptor = data[data.len() - 5];
+    if descriptor & 0x7c != 0 {
+        bail!("Unsupported seek table descriptor {:#04x}", descriptor);
+    }
+    let entry_len = if descriptor & 0x80 != 0 { 12 } else { 8 };
+    let table_len = count * entry_len + FOOTER_LEN;
+    let Some(table_start) = data.len().checked_sub(table_len + FRAME_HEADER_LEN) else {
+        bail!("Seek table of {} frames is longer than the batch", count);
+    };
+    if u32_at(data, table_start) != Some(SEEK_TABLE_FRAME_MAGIC)
+        || u32_at(data, table_start + 4) != Some(table_len as u32)
+    {
+        bail!("Corrupt seek table");
+    }
+
+    let mut frames = Vec::with_capacity(count);
+    let mut offset = 0;
+    for i in 0..count {
+        let entry = table_start + FRAME_HEADER_LEN + i * entry_len;
+        let compressed_size = u32_at(data, entry).unwrap_or_default() as usize;
+        let decompressed_size = u32_at(data, entry + 4).unwrap_or_default() as usize;
+        frames.push(Frame {
+            offset,
+            compressed_size,
+            decompressed_size,
+            time_range: None,
+        });
+        offset += compressed_size;
+    }
+    if offset > table_start {
+        bail!("Seek table frames run past the seek table");
+    }
+
+    // The time index, when present, sits between the frames and the seek table
+    let index_len = TIME_INDEX_TAG.len() + count * 16;
+    if table_start == offset + FRAME_HEADER_LEN + index_len
+        && u32_at(data, offset) == Some(TIME_INDEX_FRAME_MAGIC)
+        && data.get(offset + FRAME_HEADER_LEN..offset + FRAME_HEADER_LEN + 4)
+            == Some(TIME_INDEX_TAG.as_slice())
+    {
+        let entries = offset + FRAME_HEADER_LEN + TIME_INDEX_TAG.len();
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let first = i64_at(data, entries + i * 16).unwrap_or(i64::MAX);
+            let last = i64_at(data, entries + i * 16 + 8).unwrap_or(i64::MIN);
+            frame.time_range = (first <= last).then_some((first, last));
+        }
+    }
+    Ok(Some(frames))
+}
+
+/// Decompressed header and messages of the frames of a seekable batch whose
+/// messages may fall in `start_ns..=end_ns`, with the frames decompressed;
+/// None when `data` has no seek table or time index
+pub fn decompress_range(
+    data: &[u8],
+    start_ns: i64,
+    end_ns: i64,
+) -> Result<Option<(Vec<u8>, Vec<Frame>)>> {
+    let Some(frames) = read_frames(data)? else {
+        return Ok(None);
+    };
+    let Some((header, messages)) = frames.split_first() else {
+        return Ok(None);
+    };
+    if header.time_range.is_some() || messages.iter().all(|f| f.time_range.is_none()) {
+        return Ok(None);
+    }
+
+    let selected: Vec<Frame> = std::iter::once(*header)
+        .chain(messages.iter().copied().filter(|frame| {
+            frame
+                .time_range
+                .is_some_and(|(first, last)| first <= end_ns && last >= start_ns)
+        }))
+        .collect();
+    let mut raw = Vec::new();
+    for frame in &selected {
+        let compressed = data
+            .get(frame.offset..frame.offset + frame.compressed_size)
+            .context("Seekable frame is truncated")?;
+        raw.extend(
+            zstd::bulk::decompress(compressed, frame.decompressed_size)
+                .context("Zstd decompression failed")?,
+        );
+    }
+    Ok(Some((raw, selected)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(frame_bytes: usize) -> Vec<u8> {
+        let mut encoder = SeekableEncoder::new(3, frame_bytes);
+        encoder.write_header(b"HEADER\n").unwrap();
+        for i in 0..10u8 {
+            encoder
+                .write_message(&[i; 100], 1_000 + i as i64 * 10)
+                .unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_frames_and_time_index() {
+        let data = encode(250);
+        let frames = read_frames(&data).unwrap().unwrap();
+        // Header, then three messages (312 bytes) per frame
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].time_range, None);
+        assert_eq!(frames[1].time_range, Some((1_000, 1_020)));
+        assert_eq!(frames[4].time_range, Some((1_090, 1_090)));
+
+        // Plain decoders read every frame and skip the index and seek table
+        let raw = zstd::decode_all(&data[..]).unwrap();
+        assert_eq!(raw.len(), 7 + 10 * 104);
+        assert!(raw.starts_with(b"HEADER\n"));
+
+        let (raw, selected) = decompress_range(&data, 1_035, 1_045).unwrap().unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(raw.len(), 7 + 3 * 104);
+        assert_eq!(raw[7 + 4], 3);
+
+        assert!(read_frames(&zstd::encode_all(&raw[..], 3).unwrap())
+            .unwrap()
+            .is_none());
+    }
+}
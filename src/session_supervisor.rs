@@ -0,0 +1,210 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Supervision of the Zenoh session (`zenoh.reconnect`)
+//
+// Once the session has reached a router or peer, it's checked periodically.
+// Losing every router and peer (a restarting router, say) opens a session gap
+// in the metadata of the active recordings. When the connection comes back,
+// the recording subscribers and the queryables are declared again and the gap
+// is closed. A session that was closed, or (in client mode) stays without a
+// router for `reopen_after_seconds`, is replaced by a new one opened with the
+// same configuration; the components declaring queryables follow the
+// replacements through `SessionSupervisor::sessions`. Peers come and go on
+// their own, so in peer mode a session is only reopened once closed.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{info, warn};
+use zenoh::{Config, Session};
+
+use crate::config::{ReconnectConfig, ZenohConfig};
+use crate::recorder::RecorderManager;
+use crate::runtime;
+
+/// Current session of the recorder, updated whenever its queryables have to
+/// be declared again
+pub type SessionUpdates = watch::Receiver<Arc<Session>>;
+
+/// Wait until the queryables have to be declared again (never without a
+/// supervisor)
+pub async fn session_changed(updates: &mut Option<SessionUpdates>) {
+    if let Some(updates) = updates {
+        if updates.changed().await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
+/// Session of `updates`, or `fallback` without a supervisor
+pub fn current_session(
+    updates: &mut Option<SessionUpdates>,
+    fallback: &Arc<Session>,
+) -> Arc<Session> {
+    match updates {
+        Some(updates) => updates.borrow_and_update().clone(),
+        None => fallback.clone(),
+    }
+}
+
+/// What a connectivity check calls for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The session lost every router and peer
+    Lost,
+    /// The session is connected again after `Lost`
+    Restored { reopened: bool },
+    /// The session is closed or disconnected for too long
+    Reopen,
+}
+
+/// Connection state of the session across checks
+#[derive(Debug)]
+pub struct ConnectionTracker {
+    reopen_after: Option<Duration>,
+    /// Connected at least once (a node that never was has nothing to lose)
+    seen: bool,
+    lost_since: Option<Instant>,
+    /// The session was replaced since it was lost
+    reopened: bool,
+}
+
+impl ConnectionTracker {
+    pub fn new(reopen_after: Option<Duration>) -> Self {
+        Self {
+            reopen_after,
+            seen: false,
+            lost_since: None,
+            reopened: false,
+        }
+    }
+
+    /// Record the result of a check made at `now`
+    pub fn observe(&mut self, connected: bool, closed: bool, now: Instant) -> Option<Transition> {
+        if connected && !closed {
+            self.seen = true;
+            return self.lost_since.take().map(|_| Transition::Restored {
+                reopened: std::mem::take(&mut self.reopened),
+            });
+        }
+        if !self.seen && !closed {
+            return None;
+        }
+        match self.lost_since {
+            None => {
+                self.lost_since = Some(now);
+                Some(Transition::Lost)
+            }
+            Some(since)
+                if closed
+                    || self
+                        .reopen_after
+                        .is_some_and(|after| now.duration_since(since) >= after) =>
+            {
+                Some(Transition::Reopen)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// The session was replaced at `now`; it counts as lost until it connects
+    pub fn reopened(&mut self, now: Instant) {
+        self.lost_since = Some(now);
+        self.reopened = true;
+    }
+}
+
+/// Whether the session reaches a router (or, unless `client`, a peer)
+pub async fn is_connected(session: &Session, client: bool) -> bool {
+    if session.is_closed() {
+        return false;
+    }
+    let info = session.info();
+    info.routers_zid().await.next().is_some()
+        || (!client && info.peers_zid().await.next().is_some())
+}
+
+/// Keeps the recorder's Zenoh session connected
+pub struct SessionSupervisor {
+    config: ReconnectConfig,
+    /// Client mode: only routers count, and a session without one is reopened
+    client: bool,
+    /// Configuration replacement sessions are opened with
+    zenoh_config: Config,
+    sessions: watch::Sender<Arc<Session>>,
+}
+
+impl SessionSupervisor {
+    pub fn new(session: Arc<Session>, zenoh_config: Config, settings: &ZenohConfig) -> Self {
+        Self {
+            config: settings.reconnect.clone(),
+            client: settings.mode == "client",
+            zenoh_config,
+            sessions: watch::Sender::new(session),
+        }
+    }
+
+    /// Follow the session the queryables are declared on
+    pub fn sessions(&self) -> SessionUpdates {
+        self.sessions.subscribe()
+    }
+
+    /// Supervise the session of `recorder_manager` (runs until dropped)
+    pub async fn run(&self, recorder_manager: Arc<RecorderManager>) {
+        let reopen_after = self.config.reopen_after().filter(|_| self.client);
+        let mut tracker = ConnectionTracker::new(reopen_after);
+        loop {
+            runtime::sleep(self.config.check_interval()).await;
+
+            let session = self.sessions.borrow().clone();
+            let closed = session.is_closed();
+            let connected = is_connected(&session, self.client).await;
+            match tracker.observe(connected, closed, Instant::now()) {
+                Some(Transition::Lost) => {
+                    let affected = recorder_manager.begin_session_gap().await;
+                    warn!(
+                        "Zenoh session {}, {} recording(s) affected",
+                        if closed { "closed" } else { "disconnected" },
+                        affected
+                    );
+                }
+                Some(Transition::Restored { reopened }) => {
+                    let declared = recorder_manager.redeclare(session.clone()).await;
+                    self.sessions.send_replace(session);
+                    recorder_manager.end_session_gap(reopened).await;
+                    info!(
+                        "Zenoh session connected again, declared {} subscriber(s) again",
+                        declared
+                    );
+                }
+                Some(Transition::Reopen) => match zenoh::open(self.zenoh_config.clone()).await {
+                    Ok(replacement) => {
+                        let replacement = Arc::new(replacement);
+                        if let Err(e) = session.close().await {
+                            warn!("Failed to close the old Zenoh session: {}", e);
+                        }
+                        recorder_manager.redeclare(replacement.clone()).await;
+                        self.sessions.send_replace(replacement);
+                        tracker.reopened(Instant::now());
+                        info!("Reopened the Zenoh session");
+                    }
+                    Err(e) => warn!("Failed to reopen the Zenoh session: {}", e),
+                },
+                None => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// One-off captures of the latest sample of each topic (`snapshot` command)
+//
+// A topic some recording is subscribed to already has its last sample cached
+// by the subscription hub, which is taken as is. The other topics join the
+// hub's subscribers until their next sample arrives or the timeout passes, so
+// a snapshot never starts a recording. The samples end up in one batch in the
+// `snapshots` entry, with a manifest in `snapshots_metadata` at the same
+// timestamp.
+
+use futures_util::future::join_all;
+use std::time::Duration;
+use tracing::{debug, warn};
+use zenoh::sample::Sample;
+
+use crate::runtime;
+use crate::subscription_hub::SubscriptionHub;
+
+/// Entry of the snapshot batches
+pub const SNAPSHOT_ENTRY: &str = "snapshots";
+/// Entry of the snapshot manifests
+pub const SNAPSHOT_METADATA_ENTRY: &str = "snapshots_metadata";
+
+/// Sample taken for a snapshot
+pub struct CapturedSample {
+    /// Requested topic
+    pub topic: String,
+    pub sample: Sample,
+    /// Receive time (ns since the epoch)
+    pub received_ns: u64,
+    /// Taken from the hub's cache rather than waited for
+    pub cached: bool,
+}
+
+/// Take the latest sample of every topic, waiting up to `timeout` for those
+/// without a cached one
+///
+/// Returns the samples in the order of `topics` and the topics that didn't
+/// get one.
+pub async fn capture(
+    hub: &SubscriptionHub,
+    topics: &[String],
+    timeout: Duration,
+) -> (Vec<CapturedSample>, Vec<String>) {
+    let captures = topics.iter().map(|topic| async move {
+        if let Some((sample, received_ns)) = hub.latest(topic) {
+            return Some(CapturedSample {
+                topic: topic.clone(),
+                sample,
+                received_ns,
+                cached: true,
+            });
+        }
+        let sink = match hub.subscribe(topic) {
+            Ok(sink) => sink,
+            Err(e) => {
+                warn!("Failed to subscribe to '{}' for a snapshot: {:#}", topic, e);
+                return None;
+            }
+        };
+        tokio::select! {
+            result = sink.recv_async() => match result {
+                Ok(sample) => Some(CapturedSample {
+                    topic: topic.clone(),
+                    received_ns: hub.now_ns(),
+                    sample,
+                    cached: false,
+                }),
+                Err(e) => {
+                    warn!("Failed to receive a sample of '{}': {}", topic, e);
+                    None
+                }
+            },
+            _ = runtime::sleep(timeout) => {
+                debug!("No sample of '{}' within {:?}", topic, timeout);
+                None
+            }
+        }
+    });
+
+    let mut captured = Vec::new();
+    let mut missing = Vec::new();
+    for (topic, result) in topics.iter().zip(join_all(captures).await) {
+        match result {
+            Some(sample) => captured.push(sample),
+            None => missing.push(topic.clone()),
+        }
+    }
+    (captured, missing)
+}
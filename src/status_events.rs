@@ -0,0 +1,160 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Status event publication
+//
+// Every state change of a recording (start, pause, flush, topic changes, ...)
+// publishes its `StatusResponse` on `{key_prefix}/{recording_id}`. A coalescing
+// rate limiter keeps each recording at `max_per_sec` publications: the first
+// update of an interval goes out immediately, later ones replace each other and
+// only the latest is published when the interval ends. Panics of background
+// tasks go out unthrottled on `{key_prefix}/task_panics` as `TaskPanic`s.
+
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use zenoh::Session;
+use zenoh::Wait;
+
+use crate::protocol::{StatusResponse, TaskPanic, TaskStage};
+use crate::runtime;
+use crate::task_registry::TaskRegistry;
+
+/// Rate limiter state of one recording
+#[derive(Default)]
+struct Slot {
+    last_published: Option<Instant>,
+    /// Latest update held back by the limiter
+    pending: Option<Vec<u8>>,
+    /// A delayed publication of `pending` is scheduled
+    scheduled: bool,
+}
+
+/// Publisher of status events with per-recording coalescing
+pub struct StatusEventPublisher {
+    session: RwLock<Arc<Session>>,
+    key_prefix: String,
+    /// Minimum time between two publications of a recording (zero = unlimited)
+    min_interval: Duration,
+    slots: DashMap<String, Arc<Mutex<Slot>>>,
+}
+
+impl StatusEventPublisher {
+    pub fn new(session: Arc<Session>, key_prefix: String, max_per_sec: f64) -> Self {
+        let min_interval = if max_per_sec > 0.0 {
+            Duration::from_secs_f64(1.0 / max_per_sec)
+        } else {
+            Duration::ZERO
+        };
+
+        Self {
+            session: RwLock::new(session),
+            key_prefix,
+            min_interval,
+            slots: DashMap::new(),
+        }
+    }
+
+    /// Publish on `session` from now on (after the old one was replaced)
+    pub fn set_session(&self, session: Arc<Session>) {
+        *self.session.write().unwrap() = session;
+    }
+
+    /// Key expression the events of a recording are published on
+    pub fn key(&self, recording_id: &str) -> String {
+        format!("{}/{}", self.key_prefix, recording_id)
+    }
+
+    /// Publish a status update, coalescing updates beyond the rate limit
+    ///
+    /// A held back update is published by a job of `tasks`. The publisher
+    /// doesn't keep a registry of its own: the registries keep it to report
+    /// task panics.
+    pub fn publish(
+        self: &Arc<Self>,
+        tasks: &TaskRegistry,
+        recording_id: &str,
+        status: &StatusResponse,
+    ) {
+        let payload = match serde_json::to_vec(status) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize status event: {}", e);
+                return;
+            }
+        };
+
+        let slot = self
+            .slots
+            .entry(recording_id.to_string())
+            .or_default()
+            .clone();
+        let delay = {
+            let mut state = slot.lock().unwrap();
+            if state.scheduled {
+                // Latest wins
+                state.pending = Some(payload);
+                return;
+            }
+
+            let now = Instant::now();
+            match state.last_published {
+                Some(last) if now < last + self.min_interval => {
+                    state.pending = Some(payload);
+                    state.scheduled = true;
+                    last + self.min_interval - now
+                }
+                _ => {
+                    state.last_published = Some(now);
+                    drop(state);
+                    self.put(recording_id, payload);
+                    return;
+                }
+            }
+        };
+
+        let publisher = self.clone();
+        let name = format!("status event {}", recording_id);
+        let recording_id = recording_id.to_string();
+        tasks.spawn(TaskStage::Background, name, async move {
+            runtime::sleep(delay).await;
+            let payload = {
+                let mut state = slot.lock().unwrap();
+                state.scheduled = false;
+                state.last_published = Some(Instant::now());
+                state.pending.take()
+            };
+            if let Some(payload) = payload {
+                publisher.put(&recording_id, payload);
+            }
+        });
+    }
+
+    /// Publish the panic of a background task
+    pub fn publish_task_panic(&self, panic: &TaskPanic) {
+        match serde_json::to_vec(panic) {
+            Ok(payload) => self.put("task_panics", payload),
+            Err(e) => warn!("Failed to serialize task panic event: {}", e),
+        }
+    }
+
+    fn put(&self, recording_id: &str, payload: Vec<u8>) {
+        let key = self.key(recording_id);
+        let session = self.session.read().unwrap().clone();
+        if let Err(e) = session.put(&key, payload).wait() {
+            warn!("Failed to publish status event on '{}': {}", key, e);
+        }
+    }
+}
@@ -14,9 +14,109 @@
 
 // Storage backend trait for write-only recording
 
-use anyhow::Result;
+use crate::error::{RecorderError, Result};
+use crate::protocol::ChunkChecksums;
+use crate::task_registry::TaskRegistry;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Label carrying the CRC32C of a record's bytes
+pub const CHECKSUM_LABEL: &str = "crc32c";
+
+/// Label carrying the Unix time (s) a record expires at (`recorder.expiry`)
+pub const EXPIRES_AT_LABEL: &str = "expires_at";
+
+/// Whether `labels` mark a record expired at `now_secs`
+pub fn is_expired(labels: &HashMap<String, String>, now_secs: u64) -> bool {
+    labels
+        .get(EXPIRES_AT_LABEL)
+        .and_then(|expires_at| expires_at.parse::<u64>().ok())
+        .is_some_and(|expires_at| expires_at <= now_secs)
+}
+
+/// Check a per-recording bucket / subdirectory name
+///
+/// Names are limited to ASCII letters, digits, `-` and `_` so they are valid
+/// ReductStore bucket names and can't escape the filesystem base directory.
+pub fn validate_bucket_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(RecorderError::InvalidState(format!(
+            "Invalid bucket name '{}': use 1-64 letters, digits, '-' or '_'",
+            name
+        )))
+    }
+}
+
+/// Bytes of a record handed to the backend so far
+#[derive(Debug, Default)]
+pub struct UploadProgress {
+    uploaded: AtomicU64,
+    total: u64,
+}
+
+impl UploadProgress {
+    pub fn new(total: u64) -> Self {
+        Self {
+            uploaded: AtomicU64::new(0),
+            total,
+        }
+    }
+
+    /// Count `bytes` more as sent
+    pub fn advance(&self, bytes: u64) {
+        self.uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Mark the whole record as sent
+    pub fn complete(&self) {
+        self.uploaded.store(self.total, Ordering::Relaxed);
+    }
+
+    /// Start over (before a retry)
+    pub fn reset(&self) {
+        self.uploaded.store(0, Ordering::Relaxed);
+    }
+
+    pub fn uploaded(&self) -> u64 {
+        self.uploaded.load(Ordering::Relaxed).min(self.total)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+/// `length` bytes of `data` from `offset`, cut short at its end
+pub fn slice_range(data: &[u8], offset: u64, length: u64) -> &[u8] {
+    let start = (offset as usize).min(data.len());
+    let end = start.saturating_add(length as usize).min(data.len());
+    &data[start..end]
+}
+
+/// CRC32C of `data` as 8 lowercase hex digits
+pub fn checksum(data: &[u8]) -> String {
+    format!("{:08x}", crc32c::crc32c(data))
+}
+
+/// Chunk size of the checksums listed for stored records
+pub const CHECKSUM_CHUNK_BYTES: u64 = 8 * 1024 * 1024;
+
+/// CRC32C of each `chunk_bytes` range of `data`
+pub fn chunk_checksums(data: &[u8], chunk_bytes: u64) -> ChunkChecksums {
+    ChunkChecksums {
+        chunk_bytes,
+        crc32c: data.chunks(chunk_bytes as usize).map(checksum).collect(),
+    }
+}
 
 /// Generic storage backend trait for write-only recording
 ///
@@ -25,6 +125,9 @@ use std::collections::HashMap;
 ///
 /// Query operations are NOT part of this trait - users should query
 /// backends directly using their specialized tools (ReductStore UI, Grafana, etc.)
+/// The only reads are `read_record`, used to verify uploads, and
+/// `find_records` and `read_record_range`, used to replay and download
+/// recordings.
 #[async_trait]
 pub trait StorageBackend: Send + Sync {
     /// Initialize the backend (create bucket/database if needed)
@@ -61,7 +164,8 @@ pub trait StorageBackend: Send + Sync {
         labels: HashMap<String, String>,
         max_retries: u32,
     ) -> Result<()> {
-        use tokio::time::{sleep, Duration};
+        use crate::runtime::sleep;
+        use std::time::Duration;
         use tracing::{info, warn};
 
         let mut attempt = 0;
@@ -108,8 +212,126 @@ pub trait StorageBackend: Send + Sync {
         }
     }
 
-    /// Health check (available for monitoring, not yet integrated into main flow)
-    #[allow(dead_code)]
+    /// `write_with_retry` reporting the bytes sent through `progress`
+    ///
+    /// Backends that can't tell report the whole record once it is written.
+    async fn write_with_progress(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+        max_retries: u32,
+        progress: Arc<UploadProgress>,
+    ) -> Result<()> {
+        self.write_with_retry(entry_name, timestamp_us, data, labels, max_retries)
+            .await?;
+        progress.complete();
+        Ok(())
+    }
+
+    /// Read back the record written at `timestamp_us` (for upload verification)
+    async fn read_record(&self, entry_name: &str, timestamp_us: u64) -> Result<Vec<u8>> {
+        let _ = (entry_name, timestamp_us);
+        Err(RecorderError::backend(format!(
+            "{} backend cannot read records back",
+            self.backend_type()
+        )))
+    }
+
+    /// Read `length` bytes of a record from `offset` (for resumable downloads)
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        let _ = (entry_name, timestamp_us, offset, length);
+        Err(RecorderError::backend(format!(
+            "{} backend cannot read record ranges",
+            self.backend_type()
+        )))
+    }
+
+    /// Timestamps of the records of `entry_name` labeled `label` = `value`, in order
+    async fn find_records(&self, entry_name: &str, label: &str, value: &str) -> Result<Vec<u64>> {
+        let _ = (entry_name, label, value);
+        Err(RecorderError::backend(format!(
+            "{} backend cannot query records",
+            self.backend_type()
+        )))
+    }
+
+    /// Read back a record and compare it with the checksum computed before upload
+    ///
+    /// Returns false when the stored bytes don't match `expected`.
+    async fn verify_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        expected: &str,
+    ) -> Result<bool> {
+        let data = self.read_record(entry_name, timestamp_us).await?;
+        Ok(checksum(&data) == expected)
+    }
+
+    /// Complete what the backend keeps open for a recording, once its last
+    /// record is written (before its metadata)
+    async fn finish_recording(&self, recording_id: &str) -> Result<()> {
+        let _ = recording_id;
+        Ok(())
+    }
+
+    /// Backend writing to another bucket (ReductStore) or subdirectory (filesystem)
+    ///
+    /// `bucket` has been checked with `validate_bucket_name`. The target is
+    /// created by `initialize` on the returned backend.
+    fn with_bucket(&self, bucket: &str) -> Result<Arc<dyn StorageBackend>> {
+        Err(RecorderError::backend(format!(
+            "{} backend does not support per-recording buckets (requested '{}')",
+            self.backend_type(),
+            bucket
+        )))
+    }
+
+    /// Bucket the records are written to, reported to clients on Start
+    fn bucket(&self) -> Option<&str> {
+        None
+    }
+
+    /// Run the backend's background tasks (retention passes, batch writers)
+    /// as tasks of `tasks`
+    ///
+    /// Set by the manager before it initializes the backend; the backends of
+    /// other buckets inherit it.
+    fn set_tasks(&self, tasks: &TaskRegistry) {
+        let _ = tasks;
+    }
+
+    /// Check there is room for a new recording
+    ///
+    /// Backends writing to local disk fail with `InsufficientSpace` when the
+    /// free space is below their limit.
+    async fn check_capacity(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Free space of the local disk the records are written to
+    ///
+    /// `None` for backends that don't write to local disk.
+    async fn free_bytes(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Remove the records whose `expires_at` label is at or before
+    /// `now_secs`, returning how many were removed
+    async fn remove_expired(&self, now_secs: u64) -> Result<usize> {
+        let _ = now_secs;
+        Ok(0)
+    }
+
+    /// Health check (reported in the recorder heartbeat)
     async fn health_check(&self) -> Result<bool>;
 
     /// Get backend type identifier
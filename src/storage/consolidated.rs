@@ -0,0 +1,236 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Consolidated layout of the filesystem backend (`layout = "consolidated"`)
+//
+// The batches of a topic are appended to `{recording_id}/{entry}.{format}.part`
+// instead of one file per flush. `{recording_id}/{entry}.records.jsonl` holds
+// one line per batch with its timestamp, byte range and labels, so single
+// records can still be read back. Finishing the recording renames the data
+// files to `{entry}.{format}`; a file without the `.part` suffix is complete.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Suffix of data files of recordings that are still being written
+pub const PART_SUFFIX: &str = "part";
+
+/// Suffix of the batch index next to each data file
+pub const INDEX_SUFFIX: &str = "records.jsonl";
+
+/// One batch of a consolidated file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchRange {
+    pub timestamp_us: u64,
+    /// Byte range of the batch in the data file
+    pub offset: u64,
+    pub length: u64,
+    pub labels: HashMap<String, String>,
+}
+
+/// Data file of `entry` in a recording directory (finished or not)
+pub fn data_path(dir: &Path, entry: &str, file_format: &str) -> PathBuf {
+    let finished = dir.join(format!("{}.{}", entry, file_format));
+    if finished.exists() {
+        finished
+    } else {
+        dir.join(format!("{}.{}.{}", entry, file_format, PART_SUFFIX))
+    }
+}
+
+pub fn index_path(dir: &Path, entry: &str) -> PathBuf {
+    dir.join(format!("{}.{}", entry, INDEX_SUFFIX))
+}
+
+/// Index of a consolidated data file, `None` for other files
+pub fn index_of(data_file: &Path) -> Option<PathBuf> {
+    let name = data_file.file_name()?.to_str()?;
+    let name = name
+        .strip_suffix(&format!(".{}", PART_SUFFIX))
+        .unwrap_or(name);
+    let (entry, _format) = name.rsplit_once('.')?;
+    let index = index_path(data_file.parent()?, entry);
+    index.is_file().then_some(index)
+}
+
+/// Append a batch to the data file of `entry` and record it in the index
+///
+/// Blocking; appends to the same entry must not run concurrently. With
+/// `sync`, the batch is on disk before its index line.
+pub fn append(
+    dir: &Path,
+    entry: &str,
+    file_format: &str,
+    timestamp_us: u64,
+    data: &[u8],
+    labels: HashMap<String, String>,
+    sync: bool,
+) -> Result<BatchRange> {
+    fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+    let path = data_path(dir, entry, file_format);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context(format!("Failed to open {}", path.display()))?;
+    let offset = file.metadata()?.len();
+    file.write_all(data)
+        .context(format!("Failed to append to {}", path.display()))?;
+    if sync {
+        file.sync_data()?;
+    }
+
+    let range = BatchRange {
+        timestamp_us,
+        offset,
+        length: data.len() as u64,
+        labels,
+    };
+    let mut line = serde_json::to_vec(&range)?;
+    line.push(b'\n');
+    let index = index_path(dir, entry);
+    let mut index_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&index)
+        .context(format!("Failed to open {}", index.display()))?;
+    index_file
+        .write_all(&line)
+        .context(format!("Failed to append to {}", index.display()))?;
+    if sync {
+        index_file.sync_data()?;
+    }
+    Ok(range)
+}
+
+/// Batches listed in an index file, in write order
+///
+/// Lines that don't parse (a torn last line after a power loss) are skipped;
+/// a missing index has no batches.
+pub fn read_index(path: &Path) -> Result<Vec<BatchRange>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).context(format!("Failed to open {}", path.display())),
+    };
+    let mut ranges = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(range) = serde_json::from_str(&line?) {
+            ranges.push(range);
+        }
+    }
+    Ok(ranges)
+}
+
+/// Read the bytes of one batch
+pub fn read_batch(data_path: &Path, range: &BatchRange) -> Result<Vec<u8>> {
+    read_batch_range(data_path, range, 0, range.length)
+}
+
+/// Read `length` bytes of a batch from `offset`, cut short at its end
+pub fn read_batch_range(
+    data_path: &Path,
+    range: &BatchRange,
+    offset: u64,
+    length: u64,
+) -> Result<Vec<u8>> {
+    let offset = offset.min(range.length);
+    let length = length.min(range.length - offset);
+    let mut file =
+        File::open(data_path).context(format!("Failed to open {}", data_path.display()))?;
+    file.seek(SeekFrom::Start(range.offset + offset))?;
+    let mut data = vec![0; length as usize];
+    file.read_exact(&mut data).context(format!(
+        "Batch {} is truncated in {}",
+        range.timestamp_us,
+        data_path.display()
+    ))?;
+    Ok(data)
+}
+
+/// Index files of `entry` in the recording directories below `base`
+pub fn indexes_of(base: &Path, entry: &str) -> Vec<PathBuf> {
+    let Ok(dirs) = fs::read_dir(base) else {
+        return vec![];
+    };
+    dirs.filter_map(|dir| dir.ok())
+        .map(|dir| index_path(&dir.path(), entry))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Mark the data files of a recording directory complete; returns how many
+pub fn finish(dir: &Path) -> Result<usize> {
+    let files = match fs::read_dir(dir) {
+        Ok(files) => files,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context(format!("Failed to read {}", dir.display())),
+    };
+    let mut finished = 0;
+    for file in files {
+        let path = file?.path();
+        if path.extension().is_some_and(|ext| ext == PART_SUFFIX) {
+            let complete = path.with_extension("");
+            fs::rename(&path, &complete).context(format!("Failed to rename {}", path.display()))?;
+            finished += 1;
+        }
+    }
+    Ok(finished)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_read_and_finish() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("rec-1");
+        let labels = HashMap::from([("topic".to_string(), "/camera".to_string())]);
+
+        let first = append(&dir, "camera", "mcap", 10, b"first", labels.clone(), true).unwrap();
+        let second = append(&dir, "camera", "mcap", 20, b"second", HashMap::new(), false).unwrap();
+        assert_eq!((first.offset, first.length), (0, 5));
+        assert_eq!((second.offset, second.length), (5, 6));
+
+        let part = dir.join("camera.mcap.part");
+        assert_eq!(fs::read(&part).unwrap(), b"firstsecond");
+        let ranges = read_index(&index_path(&dir, "camera")).unwrap();
+        assert_eq!(ranges, vec![first, second.clone()]);
+        assert_eq!(read_batch(&part, &ranges[1]).unwrap(), b"second");
+        assert_eq!(indexes_of(temp_dir.path(), "camera").len(), 1);
+        assert_eq!(index_of(&part), Some(index_path(&dir, "camera")));
+        assert_eq!(index_of(&dir.join("lidar.mcap")), None);
+
+        // A torn index line is skipped
+        let mut index = OpenOptions::new()
+            .append(true)
+            .open(index_path(&dir, "camera"))
+            .unwrap();
+        index.write_all(b"{\"timestamp_us\":3").unwrap();
+        assert_eq!(read_index(&index_path(&dir, "camera")).unwrap().len(), 2);
+
+        assert_eq!(finish(&dir).unwrap(), 1);
+        assert!(!part.exists());
+        let complete = data_path(&dir, "camera", "mcap");
+        assert_eq!(complete, dir.join("camera.mcap"));
+        assert_eq!(read_batch(&complete, &second).unwrap(), b"second");
+        assert_eq!(finish(&temp_dir.path().join("missing")).unwrap(), 0);
+    }
+}
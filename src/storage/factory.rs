@@ -16,9 +16,10 @@
 
 use super::backend::StorageBackend;
 use super::filesystem::FilesystemBackend;
+use super::mock::MockBackend;
 use super::reductstore::ReductStoreBackend;
 use crate::config::StorageConfig;
-use anyhow::{bail, Result};
+use crate::error::{RecorderError, Result};
 use std::sync::Arc;
 
 #[cfg(test)]
@@ -34,7 +35,7 @@ impl BackendFactory {
                 let backend_config = config
                     .backend_config
                     .as_reductstore()
-                    .ok_or_else(|| anyhow::anyhow!("ReductStore config missing"))?;
+                    .ok_or_else(|| RecorderError::config("ReductStore config missing"))?;
 
                 let backend = ReductStoreBackend::new(backend_config.clone())?;
                 Ok(Arc::new(backend))
@@ -44,26 +45,39 @@ impl BackendFactory {
                 let backend_config = config
                     .backend_config
                     .as_filesystem()
-                    .ok_or_else(|| anyhow::anyhow!("Filesystem config missing"))?;
+                    .ok_or_else(|| RecorderError::config("Filesystem config missing"))?;
 
                 let backend = FilesystemBackend::new(backend_config.clone())?;
                 Ok(Arc::new(backend))
             }
 
+            "mock" => {
+                let backend_config = config
+                    .backend_config
+                    .as_mock()
+                    .ok_or_else(|| RecorderError::config("Mock config missing"))?;
+
+                Ok(Arc::new(MockBackend::new(backend_config.clone())))
+            }
+
             "influxdb" => {
                 // TODO: Implement InfluxDB backend (optional)
-                bail!("InfluxDB backend not yet implemented. Coming in Phase 3!")
+                Err(RecorderError::config(
+                    "InfluxDB backend not yet implemented. Coming in Phase 3!",
+                ))
             }
 
             "s3" => {
                 // TODO: Implement S3 backend (optional)
-                bail!("S3 backend not yet implemented. Coming in Phase 3!")
+                Err(RecorderError::config(
+                    "S3 backend not yet implemented. Coming in Phase 3!",
+                ))
             }
 
-            unknown => bail!(
-                "Unknown storage backend: '{}'. Supported: reductstore, filesystem (influxdb, s3 coming soon)",
+            unknown => Err(RecorderError::config(format!(
+                "Unknown storage backend: '{}'. Supported: reductstore, filesystem, mock (influxdb, s3 coming soon)",
                 unknown
-            ),
+            ))),
         }
     }
 }
@@ -14,20 +14,35 @@
 
 // Filesystem backend implementation
 
-use super::backend::StorageBackend;
-use crate::config::FilesystemConfig;
-use anyhow::{Context, Result};
+use super::backend::{validate_bucket_name, StorageBackend};
+use super::consolidated::{self, BatchRange};
+use super::record_index::{self, IndexEntry};
+use super::retention::{self, RetentionManager};
+use crate::config::{FileLayout, FilesystemConfig};
+use crate::error::{RecorderError, Result};
+use crate::runtime::{self, fs};
+use crate::task_registry::TaskRegistry;
+use anyhow::Context;
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, warn};
 
 /// Filesystem backend for writing MCAP files to local disk
 pub struct FilesystemBackend {
     base_path: PathBuf,
     file_format: String,
+    record_index: bool,
+    layout: FileLayout,
+    /// Serializes appends to consolidated files (shared with the bucket backends)
+    append_lock: Arc<Mutex<()>>,
+    /// Configured base path; per-recording buckets are subdirectories of it
+    root: PathBuf,
+    bucket: Option<String>,
+    /// Retention of the whole base path, shared with the bucket backends
+    retention: Arc<RetentionManager>,
 }
 
 impl FilesystemBackend {
@@ -39,14 +54,26 @@ impl FilesystemBackend {
             base_path.display()
         );
 
+        let retention = Arc::new(RetentionManager::new(
+            &base_path,
+            &config.file_format,
+            config.retention,
+        ));
+
         Ok(Self {
+            root: base_path.clone(),
             base_path,
             file_format: config.file_format,
+            record_index: config.record_index,
+            layout: config.layout,
+            append_lock: Arc::new(Mutex::new(())),
+            bucket: None,
+            retention,
         })
     }
 
     /// Ensure base directory exists
-    async fn ensure_base_directory(&self) -> Result<()> {
+    async fn ensure_base_directory(&self) -> anyhow::Result<()> {
         if !self.base_path.exists() {
             info!("Creating base directory: {}", self.base_path.display());
             fs::create_dir_all(&self.base_path)
@@ -79,7 +106,7 @@ impl FilesystemBackend {
     }
 
     /// Ensure entry directory exists
-    async fn ensure_entry_directory(&self, entry_name: &str) -> Result<()> {
+    async fn ensure_entry_directory(&self, entry_name: &str) -> anyhow::Result<()> {
         let entry_dir = self.base_path.join(entry_name);
         if !entry_dir.exists() {
             debug!("Creating entry directory: {}", entry_dir.display());
@@ -89,21 +116,119 @@ impl FilesystemBackend {
         }
         Ok(())
     }
-}
 
-#[async_trait]
-impl StorageBackend for FilesystemBackend {
-    async fn initialize(&self) -> Result<()> {
-        self.ensure_base_directory().await
+    /// Directory a record is consolidated into, `None` when it gets its own file
+    ///
+    /// Only recorded batches are consolidated; the recording metadata isn't.
+    fn consolidated_dir(&self, labels: &HashMap<String, String>) -> Option<PathBuf> {
+        if self.layout != FileLayout::Consolidated || !labels.contains_key("format") {
+            return None;
+        }
+        let recording_id = labels.get("recording_id")?;
+        validate_bucket_name(recording_id).ok()?;
+        Some(self.base_path.join(recording_id))
     }
 
-    async fn write_record(
+    /// Append a record to the consolidated file of its recording and entry
+    async fn append_consolidated(
         &self,
+        dir: PathBuf,
         entry_name: &str,
         timestamp_us: u64,
         data: Vec<u8>,
         labels: HashMap<String, String>,
-    ) -> Result<()> {
+    ) -> anyhow::Result<()> {
+        let lock = self.append_lock.clone();
+        let entry = entry_name.to_string();
+        let file_format = self.file_format.clone();
+        let sync = self.record_index;
+        let range = runtime::unblock(move || {
+            let _guard = lock.lock().unwrap();
+            consolidated::append(
+                &dir,
+                &entry,
+                &file_format,
+                timestamp_us,
+                &data,
+                labels,
+                sync,
+            )
+        })
+        .await?;
+        debug!(
+            "Appended {} bytes to entry '{}' at timestamp {} (offset {})",
+            range.length, entry_name, timestamp_us, range.offset
+        );
+        Ok(())
+    }
+
+    /// Consolidated batch of `entry_name` written at `timestamp_us`, with the
+    /// file holding it
+    async fn find_consolidated(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+    ) -> anyhow::Result<Option<(PathBuf, BatchRange)>> {
+        let base = self.base_path.clone();
+        let entry = entry_name.to_string();
+        let file_format = self.file_format.clone();
+        runtime::unblock(move || {
+            for index in consolidated::indexes_of(&base, &entry) {
+                let range = consolidated::read_index(&index)?
+                    .into_iter()
+                    .find(|range| range.timestamp_us == timestamp_us);
+                if let (Some(range), Some(dir)) = (range, index.parent()) {
+                    return Ok(Some((
+                        consolidated::data_path(dir, &entry, &file_format),
+                        range,
+                    )));
+                }
+            }
+            Ok(None)
+        })
+        .await
+    }
+
+    /// Timestamps of the consolidated batches of `entry_name` labeled
+    /// `label` = `value`
+    async fn find_consolidated_records(
+        &self,
+        entry_name: &str,
+        label: &str,
+        value: &str,
+    ) -> anyhow::Result<Vec<u64>> {
+        let base = self.base_path.clone();
+        let entry = entry_name.to_string();
+        let (label, value) = (label.to_string(), value.to_string());
+        runtime::unblock(move || {
+            let mut timestamps = Vec::new();
+            for index in consolidated::indexes_of(&base, &entry) {
+                timestamps.extend(
+                    consolidated::read_index(&index)?
+                        .into_iter()
+                        .filter(|range| range.labels.get(&label) == Some(&value))
+                        .map(|range| range.timestamp_us),
+                );
+            }
+            Ok(timestamps)
+        })
+        .await
+    }
+
+    /// Write the data file and the labels sidecar of a record
+    async fn write_files(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> anyhow::Result<()> {
+        if let Some(dir) = self.consolidated_dir(&labels) {
+            return self
+                .append_consolidated(dir, entry_name, timestamp_us, data, labels)
+                .await;
+        }
+
         // Ensure entry directory exists
         self.ensure_entry_directory(entry_name).await?;
 
@@ -114,15 +239,19 @@ impl StorageBackend for FilesystemBackend {
         // Write data file
         debug!("Writing {} bytes to {}", data.len(), file_path.display());
 
-        let mut file = fs::File::create(&file_path)
-            .await
-            .context(format!("Failed to create file: {}", file_path.display()))?;
-
-        file.write_all(&data)
-            .await
-            .context("Failed to write data")?;
-
-        file.flush().await.context("Failed to flush data")?;
+        let index_entry = self
+            .record_index
+            .then(|| IndexEntry::for_record(timestamp_us, &data));
+        if self.record_index {
+            // Indexed records must be on disk before their index entry
+            write_synced(file_path.clone(), data.clone())
+                .await
+                .context(format!("Failed to write file: {}", file_path.display()))?;
+        } else {
+            fs::write(&file_path, data.as_slice())
+                .await
+                .context(format!("Failed to write file: {}", file_path.display()))?;
+        }
 
         // Write metadata file with labels
         if !labels.is_empty() {
@@ -131,20 +260,16 @@ impl StorageBackend for FilesystemBackend {
             let metadata_json =
                 serde_json::to_string_pretty(&labels).context("Failed to serialize metadata")?;
 
-            let mut meta_file = fs::File::create(&metadata_path).await.context(format!(
-                "Failed to create metadata file: {}",
-                metadata_path.display()
-            ))?;
-
-            meta_file
-                .write_all(metadata_json.as_bytes())
+            fs::write(&metadata_path, metadata_json)
                 .await
-                .context("Failed to write metadata")?;
+                .context(format!(
+                    "Failed to write metadata file: {}",
+                    metadata_path.display()
+                ))?;
+        }
 
-            meta_file
-                .flush()
-                .await
-                .context("Failed to flush metadata")?;
+        if let Some(index_entry) = index_entry {
+            record_index::append(&self.base_path.join(entry_name), index_entry).await?;
         }
 
         info!(
@@ -156,6 +281,186 @@ impl StorageBackend for FilesystemBackend {
 
         Ok(())
     }
+}
+
+/// Read `length` bytes of a file from `offset`, cut short at its end
+fn read_file_range(path: &Path, offset: u64, length: u64) -> anyhow::Result<Vec<u8>> {
+    let mut file =
+        std::fs::File::open(path).context(format!("Failed to read file: {}", path.display()))?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    file.take(length)
+        .read_to_end(&mut data)
+        .context(format!("Failed to read file: {}", path.display()))?;
+    Ok(data)
+}
+
+/// Write a file and flush it to disk
+async fn write_synced(path: PathBuf, data: Vec<u8>) -> std::io::Result<()> {
+    runtime::unblock(move || {
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(&data)?;
+        file.sync_data()
+    })
+    .await
+}
+
+#[async_trait]
+impl StorageBackend for FilesystemBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.ensure_base_directory()
+            .await
+            .map_err(RecorderError::backend)?;
+        self.retention.start();
+        Ok(())
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        self.write_files(entry_name, timestamp_us, data, labels)
+            .await
+            .map_err(RecorderError::backend)
+    }
+
+    async fn read_record(&self, entry_name: &str, timestamp_us: u64) -> Result<Vec<u8>> {
+        let file_path = self.get_file_path(entry_name, timestamp_us);
+        match fs::read(&file_path).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            result => {
+                return result
+                    .context(format!("Failed to read file: {}", file_path.display()))
+                    .map_err(RecorderError::backend)
+            }
+        }
+
+        // Not a file of its own; look for it in the consolidated files
+        match self.find_consolidated(entry_name, timestamp_us).await {
+            Ok(Some((data_path, range))) => {
+                runtime::unblock(move || consolidated::read_batch(&data_path, &range))
+                    .await
+                    .map_err(RecorderError::backend)
+            }
+            Ok(None) => Err(RecorderError::backend(format!(
+                "Failed to read file: {}: not found",
+                file_path.display()
+            ))),
+            Err(e) => Err(RecorderError::backend(e)),
+        }
+    }
+
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        let file_path = self.get_file_path(entry_name, timestamp_us);
+        if file_path.exists() {
+            return runtime::unblock(move || read_file_range(&file_path, offset, length))
+                .await
+                .map_err(RecorderError::backend);
+        }
+        match self.find_consolidated(entry_name, timestamp_us).await {
+            Ok(Some((data_path, range))) => runtime::unblock(move || {
+                consolidated::read_batch_range(&data_path, &range, offset, length)
+            })
+            .await
+            .map_err(RecorderError::backend),
+            Ok(None) => Err(RecorderError::backend(format!(
+                "Failed to read file: {}: not found",
+                file_path.display()
+            ))),
+            Err(e) => Err(RecorderError::backend(e)),
+        }
+    }
+
+    async fn find_records(&self, entry_name: &str, label: &str, value: &str) -> Result<Vec<u64>> {
+        let entry_dir = self.base_path.join(entry_name);
+        let mut timestamps = self
+            .find_consolidated_records(entry_name, label, value)
+            .await
+            .map_err(RecorderError::backend)?;
+        let files = match fs::read_dir(&entry_dir).await {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                timestamps.sort_unstable();
+                return Ok(timestamps);
+            }
+            Err(e) => {
+                return Err(RecorderError::backend(anyhow::Error::new(e).context(
+                    format!("Failed to read entry directory: {}", entry_dir.display()),
+                )))
+            }
+        };
+
+        for path in files {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(Ok(timestamp_us)) = name.strip_suffix(".meta.json").map(str::parse::<u64>)
+            else {
+                continue;
+            };
+            let Ok(content) = fs::read(&path).await else {
+                continue;
+            };
+            let labels: HashMap<String, String> = match serde_json::from_slice(&content) {
+                Ok(labels) => labels,
+                Err(e) => {
+                    warn!("Skipping unreadable labels {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if labels.get(label).map(String::as_str) == Some(value) {
+                timestamps.push(timestamp_us);
+            }
+        }
+        timestamps.sort_unstable();
+        Ok(timestamps)
+    }
+
+    async fn free_bytes(&self) -> Result<Option<u64>> {
+        self.retention
+            .free_bytes()
+            .await
+            .map_err(|e| RecorderError::backend(format!("{:#}", e)))
+    }
+
+    async fn remove_expired(&self, now_secs: u64) -> Result<usize> {
+        let base_path = self.base_path.clone();
+        let file_format = self.file_format.clone();
+        runtime::unblock(move || retention::remove_expired(&base_path, &file_format, now_secs))
+            .await
+            .map_err(|e| RecorderError::backend(format!("{:#}", e)))
+    }
+
+    async fn check_capacity(&self) -> Result<()> {
+        let min_free_bytes = self.retention.config().min_free_bytes;
+        if min_free_bytes == 0 {
+            return Ok(());
+        }
+        match self.retention.free_bytes().await {
+            Ok(Some(free_bytes)) if free_bytes < min_free_bytes => {
+                Err(RecorderError::InsufficientSpace(format!(
+                    "{} bytes free at {}, at least {} required",
+                    free_bytes,
+                    self.root.display(),
+                    min_free_bytes
+                )))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!("Failed to read free disk space: {:#}", e);
+                Ok(())
+            }
+        }
+    }
 
     async fn health_check(&self) -> Result<bool> {
         // Check if base directory is accessible and writable
@@ -163,19 +468,14 @@ impl StorageBackend for FilesystemBackend {
             Ok(metadata) if metadata.is_dir() => {
                 // Try to create a temporary test file to verify write permissions
                 let test_file = self.base_path.join(".health_check_test");
-                match fs::File::create(&test_file).await {
-                    Ok(mut f) => {
-                        // Write a test byte
-                        if let Err(e) = f.write_all(b"test").await {
-                            warn!("Health check failed - cannot write: {}", e);
-                            return Ok(false);
-                        }
+                match fs::write(&test_file, b"test".as_slice()).await {
+                    Ok(_) => {
                         // Clean up test file
                         let _ = fs::remove_file(&test_file).await;
                         Ok(true)
                     }
                     Err(e) => {
-                        warn!("Health check failed - cannot create file: {}", e);
+                        warn!("Health check failed - cannot write: {}", e);
                         Ok(false)
                     }
                 }
@@ -198,6 +498,42 @@ impl StorageBackend for FilesystemBackend {
         }
     }
 
+    async fn finish_recording(&self, recording_id: &str) -> Result<()> {
+        if self.layout != FileLayout::Consolidated || validate_bucket_name(recording_id).is_err() {
+            return Ok(());
+        }
+        let dir = self.base_path.join(recording_id);
+        let finished = runtime::unblock(move || consolidated::finish(&dir))
+            .await
+            .map_err(RecorderError::backend)?;
+        debug!(
+            "Completed {} consolidated file(s) of recording '{}'",
+            finished, recording_id
+        );
+        Ok(())
+    }
+
+    fn with_bucket(&self, bucket: &str) -> Result<Arc<dyn StorageBackend>> {
+        Ok(Arc::new(Self {
+            base_path: self.root.join(bucket),
+            root: self.root.clone(),
+            file_format: self.file_format.clone(),
+            record_index: self.record_index,
+            layout: self.layout,
+            append_lock: self.append_lock.clone(),
+            bucket: Some(bucket.to_string()),
+            retention: self.retention.clone(),
+        }))
+    }
+
+    fn bucket(&self) -> Option<&str> {
+        self.bucket.as_deref()
+    }
+
+    fn set_tasks(&self, tasks: &TaskRegistry) {
+        self.retention.set_tasks(tasks);
+    }
+
     fn backend_type(&self) -> &str {
         "filesystem"
     }
@@ -206,6 +542,7 @@ impl StorageBackend for FilesystemBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::checksum;
     use tempfile::TempDir;
 
     fn create_test_backend() -> (FilesystemBackend, TempDir) {
@@ -213,6 +550,7 @@ mod tests {
         let config = FilesystemConfig {
             base_path: temp_dir.path().to_string_lossy().to_string(),
             file_format: "mcap".to_string(),
+            ..Default::default()
         };
         let backend = FilesystemBackend::new(config).unwrap();
         (backend, temp_dir)
@@ -263,6 +601,56 @@ mod tests {
         assert_eq!(parsed_labels, labels);
     }
 
+    #[tokio::test]
+    async fn test_verify_record() {
+        let (backend, _temp_dir) = create_test_backend();
+        backend.initialize().await.unwrap();
+
+        let data = b"test data".to_vec();
+        let expected = checksum(&data);
+        backend
+            .write_record("test_entry", 42, data, HashMap::new())
+            .await
+            .unwrap();
+        assert!(backend
+            .verify_record("test_entry", 42, &expected)
+            .await
+            .unwrap());
+
+        // Corrupt the stored file
+        std::fs::write(backend.get_file_path("test_entry", 42), b"test data!").unwrap();
+        assert!(!backend
+            .verify_record("test_entry", 42, &expected)
+            .await
+            .unwrap());
+
+        // Missing records are an error, not a mismatch
+        assert!(backend
+            .verify_record("test_entry", 43, &expected)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_with_bucket() {
+        let (backend, temp_dir) = create_test_backend();
+        assert_eq!(backend.bucket(), None);
+
+        let customer = backend.with_bucket("customer_a").unwrap();
+        assert_eq!(customer.bucket(), Some("customer_a"));
+        customer.initialize().await.unwrap();
+        customer
+            .write_record("entry", 1, b"data".to_vec(), HashMap::new())
+            .await
+            .unwrap();
+        assert!(temp_dir.path().join("customer_a/entry/1.mcap").exists());
+
+        // Buckets are siblings under the base path
+        let other = customer.with_bucket("customer_b").unwrap();
+        other.initialize().await.unwrap();
+        assert!(temp_dir.path().join("customer_b").is_dir());
+    }
+
     #[tokio::test]
     async fn test_health_check() {
         let (backend, _temp_dir) = create_test_backend();
@@ -0,0 +1,368 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// In-memory backend with fault injection (`backend = "mock"`)
+//
+// Records are kept in memory so the retry, deferral and spill paths can be
+// exercised without storage services. Writes can be delayed, failed at a
+// configured rate, refused past a capacity, or failed altogether while the
+// backend is offline; the faults can be changed while recording.
+
+use super::backend::{is_expired, slice_range, StorageBackend};
+use crate::config::MockConfig;
+use crate::error::{RecorderError, Result};
+use crate::runtime;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::info;
+
+/// Record held by the mock backend
+#[derive(Debug, Clone)]
+pub struct MockRecord {
+    pub data: Vec<u8>,
+    pub labels: HashMap<String, String>,
+}
+
+/// Records and faults, shared with the bucket backends
+#[derive(Default)]
+struct MockState {
+    faults: Mutex<MockConfig>,
+    offline: AtomicBool,
+    /// By entry (`{bucket}/{entry}` for bucket backends), then timestamp
+    records: Mutex<HashMap<String, BTreeMap<u64, MockRecord>>>,
+    stored_bytes: AtomicU64,
+    write_attempts: AtomicU64,
+    failed_writes: AtomicU64,
+}
+
+/// In-memory storage backend for tests
+pub struct MockBackend {
+    state: Arc<MockState>,
+    bucket: Option<String>,
+}
+
+impl MockBackend {
+    pub fn new(config: MockConfig) -> Self {
+        info!(
+            "Initializing mock backend ({} ms latency, {} error rate, {} bytes capacity)",
+            config.latency_ms, config.error_rate, config.capacity_bytes
+        );
+        Self {
+            state: Arc::new(MockState {
+                faults: Mutex::new(config),
+                ..Default::default()
+            }),
+            bucket: None,
+        }
+    }
+
+    /// Delay every write by `latency`
+    #[allow(dead_code)]
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.faults.lock().unwrap().latency_ms = latency.as_millis() as u64;
+    }
+
+    /// Fail `error_rate` (0.0-1.0) of the following write attempts
+    #[allow(dead_code)]
+    pub fn set_error_rate(&self, error_rate: f64) {
+        self.state.faults.lock().unwrap().error_rate = error_rate;
+    }
+
+    /// Limit the stored records to `capacity_bytes` (0 = no limit)
+    #[allow(dead_code)]
+    pub fn set_capacity_bytes(&self, capacity_bytes: u64) {
+        self.state.faults.lock().unwrap().capacity_bytes = capacity_bytes;
+    }
+
+    /// Fail every write and health check while `offline` is set
+    #[allow(dead_code)]
+    pub fn set_offline(&self, offline: bool) {
+        self.state.offline.store(offline, Ordering::Relaxed);
+    }
+
+    /// Records of `entry_name`, in timestamp order
+    #[allow(dead_code)]
+    pub fn records(&self, entry_name: &str) -> Vec<(u64, MockRecord)> {
+        self.state
+            .records
+            .lock()
+            .unwrap()
+            .get(&self.key(entry_name))
+            .map(|records| {
+                records
+                    .iter()
+                    .map(|(timestamp_us, record)| (*timestamp_us, record.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Bytes of the stored records, in every bucket
+    #[allow(dead_code)]
+    pub fn stored_bytes(&self) -> u64 {
+        self.state.stored_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Writes tried so far, failed or not
+    #[allow(dead_code)]
+    pub fn write_attempts(&self) -> u64 {
+        self.state.write_attempts.load(Ordering::Relaxed)
+    }
+
+    /// Writes failed by an injected fault
+    #[allow(dead_code)]
+    pub fn failed_writes(&self) -> u64 {
+        self.state.failed_writes.load(Ordering::Relaxed)
+    }
+
+    fn key(&self, entry_name: &str) -> String {
+        match &self.bucket {
+            Some(bucket) => format!("{}/{}", bucket, entry_name),
+            None => entry_name.to_string(),
+        }
+    }
+
+    /// Whether the records under `key` belong to this backend's bucket
+    fn owns(&self, key: &str) -> bool {
+        match &self.bucket {
+            Some(bucket) => key
+                .strip_prefix(bucket.as_str())
+                .is_some_and(|entry| entry.starts_with('/')),
+            None => !key.contains('/'),
+        }
+    }
+
+    fn fail(&self, error: RecorderError) -> Result<()> {
+        self.state.failed_writes.fetch_add(1, Ordering::Relaxed);
+        Err(error)
+    }
+}
+
+/// Whether the `attempt`th write (1-based) fails at `error_rate`
+///
+/// Failures are spread evenly over the attempts, so runs are reproducible.
+fn injected_failure(attempt: u64, error_rate: f64) -> bool {
+    error_rate > 0.0
+        && (attempt as f64 * error_rate).floor()
+            > (attempt.saturating_sub(1) as f64 * error_rate).floor()
+}
+
+#[async_trait]
+impl StorageBackend for MockBackend {
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        let attempt = self.state.write_attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        let faults = self.state.faults.lock().unwrap().clone();
+        if faults.latency_ms > 0 {
+            runtime::sleep(Duration::from_millis(faults.latency_ms)).await;
+        }
+        if self.state.offline.load(Ordering::Relaxed) {
+            return self.fail(RecorderError::backend("mock backend is offline"));
+        }
+        if injected_failure(attempt, faults.error_rate) {
+            return self.fail(RecorderError::backend(format!(
+                "injected failure of write {} to entry '{}'",
+                attempt, entry_name
+            )));
+        }
+
+        let mut records = self.state.records.lock().unwrap();
+        let entry = records.entry(self.key(entry_name)).or_default();
+        let replaced = entry
+            .get(&timestamp_us)
+            .map_or(0, |record| record.data.len() as u64);
+        let stored_bytes = self.state.stored_bytes.load(Ordering::Relaxed) - replaced;
+        let size = data.len() as u64;
+        if faults.capacity_bytes > 0 && stored_bytes + size > faults.capacity_bytes {
+            return self.fail(RecorderError::InsufficientSpace(format!(
+                "mock backend holds {} of {} bytes, {} more requested",
+                stored_bytes, faults.capacity_bytes, size
+            )));
+        }
+        entry.insert(timestamp_us, MockRecord { data, labels });
+        self.state
+            .stored_bytes
+            .store(stored_bytes + size, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn read_record(&self, entry_name: &str, timestamp_us: u64) -> Result<Vec<u8>> {
+        self.state
+            .records
+            .lock()
+            .unwrap()
+            .get(&self.key(entry_name))
+            .and_then(|records| records.get(&timestamp_us))
+            .map(|record| record.data.clone())
+            .ok_or_else(|| {
+                RecorderError::backend(format!(
+                    "No record at {} in entry '{}'",
+                    timestamp_us, entry_name
+                ))
+            })
+    }
+
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        self.state
+            .records
+            .lock()
+            .unwrap()
+            .get(&self.key(entry_name))
+            .and_then(|records| records.get(&timestamp_us))
+            .map(|record| slice_range(&record.data, offset, length).to_vec())
+            .ok_or_else(|| {
+                RecorderError::backend(format!(
+                    "No record at {} in entry '{}'",
+                    timestamp_us, entry_name
+                ))
+            })
+    }
+
+    async fn find_records(&self, entry_name: &str, label: &str, value: &str) -> Result<Vec<u64>> {
+        Ok(self
+            .records(entry_name)
+            .into_iter()
+            .filter(|(_, record)| record.labels.get(label).is_some_and(|v| v == value))
+            .map(|(timestamp_us, _)| timestamp_us)
+            .collect())
+    }
+
+    async fn check_capacity(&self) -> Result<()> {
+        let capacity_bytes = self.state.faults.lock().unwrap().capacity_bytes;
+        let stored_bytes = self.stored_bytes();
+        if capacity_bytes > 0 && stored_bytes >= capacity_bytes {
+            return Err(RecorderError::InsufficientSpace(format!(
+                "mock backend holds {} of {} bytes",
+                stored_bytes, capacity_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    async fn free_bytes(&self) -> Result<Option<u64>> {
+        let capacity_bytes = self.state.faults.lock().unwrap().capacity_bytes;
+        Ok((capacity_bytes > 0).then(|| capacity_bytes.saturating_sub(self.stored_bytes())))
+    }
+
+    async fn remove_expired(&self, now_secs: u64) -> Result<usize> {
+        if self.state.offline.load(Ordering::Relaxed) {
+            return Err(RecorderError::backend("mock backend is offline"));
+        }
+        let mut records = self.state.records.lock().unwrap();
+        let mut removed = 0;
+        let mut freed = 0;
+        for (_, entry) in records.iter_mut().filter(|(key, _)| self.owns(key)) {
+            entry.retain(|_, record| {
+                let expired = is_expired(&record.labels, now_secs);
+                if expired {
+                    removed += 1;
+                    freed += record.data.len() as u64;
+                }
+                !expired
+            });
+        }
+        self.state.stored_bytes.fetch_sub(freed, Ordering::Relaxed);
+        Ok(removed)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(!self.state.offline.load(Ordering::Relaxed))
+    }
+
+    fn with_bucket(&self, bucket: &str) -> Result<Arc<dyn StorageBackend>> {
+        Ok(Arc::new(Self {
+            state: self.state.clone(),
+            bucket: Some(bucket.to_string()),
+        }))
+    }
+
+    fn bucket(&self) -> Option<&str> {
+        self.bucket.as_deref()
+    }
+
+    fn backend_type(&self) -> &str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_injected_failures_follow_rate() {
+        let failures = (1..=100).filter(|n| injected_failure(*n, 0.25)).count();
+        assert_eq!(failures, 25);
+        assert!(!(1..=100).any(|n| injected_failure(n, 0.0)));
+        assert!((1..=100).all(|n| injected_failure(n, 1.0)));
+    }
+
+    #[tokio::test]
+    async fn test_capacity_and_offline() {
+        let backend = MockBackend::new(MockConfig {
+            capacity_bytes: 10,
+            ..Default::default()
+        });
+        backend
+            .write_record("entry", 1, vec![0; 6], HashMap::new())
+            .await
+            .unwrap();
+        // Replacing a record only counts the difference
+        backend
+            .write_record("entry", 1, vec![1; 8], HashMap::new())
+            .await
+            .unwrap();
+        let error = backend
+            .write_record("entry", 2, vec![0; 4], HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RecorderError::InsufficientSpace(_)));
+        assert_eq!(backend.stored_bytes(), 8);
+        assert!(backend.check_capacity().await.is_ok());
+
+        backend.set_offline(true);
+        assert!(!backend.health_check().await.unwrap());
+        assert!(backend
+            .write_record("entry", 3, vec![0; 1], HashMap::new())
+            .await
+            .is_err());
+        backend.set_offline(false);
+        backend
+            .write_record("entry", 3, vec![0; 2], HashMap::new())
+            .await
+            .unwrap();
+        assert!(backend.check_capacity().await.is_err());
+        assert_eq!(backend.write_attempts(), 5);
+        assert_eq!(backend.failed_writes(), 2);
+        assert_eq!(backend.read_record("entry", 1).await.unwrap(), vec![1; 8]);
+    }
+}
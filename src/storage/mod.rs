@@ -18,15 +18,29 @@
 // allowing the recorder to write to different storage systems
 // (ReductStore, filesystem, InfluxDB, S3, etc.)
 //
-// This module focuses on WRITE-ONLY operations.
-// Users should query backends directly using their specialized tools.
+// This module focuses on WRITE-ONLY operations (plus reading records back
+// to verify their checksums). Users should query backends directly using
+// their specialized tools.
 
 pub mod backend;
+pub mod consolidated;
 pub mod factory;
 pub mod filesystem;
+pub mod mock;
+pub mod record_index;
 pub mod reductstore;
+pub mod reductstore_batch;
+pub mod repair;
+pub mod retention;
 
-pub use backend::StorageBackend;
+pub use backend::{
+    checksum, chunk_checksums, validate_bucket_name, StorageBackend, UploadProgress,
+    CHECKSUM_CHUNK_BYTES, CHECKSUM_LABEL, EXPIRES_AT_LABEL,
+};
 pub use factory::BackendFactory;
 #[allow(unused_imports)]
+pub use mock::MockBackend;
+#[allow(unused_imports)]
 pub use reductstore::{topic_to_entry_name, ReductStoreBackend};
+#[allow(unused_imports)]
+pub use retention::RetentionManager;
@@ -0,0 +1,218 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Append-only record index of filesystem entries
+//
+// `{entry}/records.idx` gets one fixed-size frame per record, appended once
+// the record file is on disk: timestamp, offset and length of the record
+// data, and its CRC32C. Each record is a file of its own, so the offset is
+// that of the data in `{timestamp_us}.{format}` (always 0 today). Frames carry
+// their own checksum; a frame torn by power loss is dropped on read, as is
+// anything after it.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::runtime;
+
+/// Index file of an entry directory
+pub const INDEX_FILE: &str = "records.idx";
+
+const FRAME_MAGIC: u32 = 0x5a52_4958; // "ZRIX"
+const FRAME_LEN: usize = 36;
+
+/// Location and checksum of a stored record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub timestamp_us: u64,
+    /// Offset of the record data in its file
+    pub offset: u64,
+    pub length: u64,
+    pub crc32c: u32,
+}
+
+impl IndexEntry {
+    /// Entry of a record stored whole in its own file
+    pub fn for_record(timestamp_us: u64, data: &[u8]) -> Self {
+        Self {
+            timestamp_us,
+            offset: 0,
+            length: data.len() as u64,
+            crc32c: crc32c::crc32c(data),
+        }
+    }
+
+    /// Whether `data` (the record file) holds the indexed record
+    pub fn matches(&self, data: &[u8]) -> bool {
+        let Ok(start) = usize::try_from(self.offset) else {
+            return false;
+        };
+        let Some(end) = start.checked_add(self.length as usize) else {
+            return false;
+        };
+        data.get(start..end)
+            .is_some_and(|record| crc32c::crc32c(record) == self.crc32c)
+    }
+
+    fn encode(&self) -> [u8; FRAME_LEN] {
+        let mut frame = [0u8; FRAME_LEN];
+        frame[0..4].copy_from_slice(&FRAME_MAGIC.to_le_bytes());
+        frame[4..12].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        frame[12..20].copy_from_slice(&self.offset.to_le_bytes());
+        frame[20..28].copy_from_slice(&self.length.to_le_bytes());
+        frame[28..32].copy_from_slice(&self.crc32c.to_le_bytes());
+        let check = crc32c::crc32c(&frame[..32]);
+        frame[32..36].copy_from_slice(&check.to_le_bytes());
+        frame
+    }
+
+    fn decode(frame: &[u8]) -> Option<Self> {
+        let u32_at = |at: usize| u32::from_le_bytes(frame[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(frame[at..at + 8].try_into().unwrap());
+        if frame.len() != FRAME_LEN
+            || u32_at(0) != FRAME_MAGIC
+            || u32_at(32) != crc32c::crc32c(&frame[..32])
+        {
+            return None;
+        }
+        Some(Self {
+            timestamp_us: u64_at(4),
+            offset: u64_at(12),
+            length: u64_at(20),
+            crc32c: u32_at(28),
+        })
+    }
+}
+
+/// Frames read from an index file
+#[derive(Debug, Default)]
+pub struct IndexContents {
+    pub entries: Vec<IndexEntry>,
+    /// Bytes after the last valid frame (torn write)
+    pub torn_bytes: usize,
+}
+
+/// Path of the index of an entry directory
+pub fn index_path(entry_dir: &Path) -> PathBuf {
+    entry_dir.join(INDEX_FILE)
+}
+
+/// Append an entry and flush it to disk
+pub async fn append(entry_dir: &Path, entry: IndexEntry) -> Result<()> {
+    let entry_dir = entry_dir.to_path_buf();
+    runtime::unblock(move || append_blocking(&entry_dir, entry)).await
+}
+
+/// `append` for callers already off the async runtime
+pub fn append_blocking(entry_dir: &Path, entry: IndexEntry) -> Result<()> {
+    let path = index_path(entry_dir);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context(format!("Failed to open index: {}", path.display()))?;
+    file.write_all(&entry.encode())
+        .and_then(|()| file.sync_data())
+        .context(format!("Failed to append to index: {}", path.display()))
+}
+
+/// Read the index of an entry directory (empty when there is none)
+pub fn read(entry_dir: &Path) -> Result<IndexContents> {
+    let path = index_path(entry_dir);
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(IndexContents::default()),
+        Err(e) => return Err(e).context(format!("Failed to read index: {}", path.display())),
+    };
+
+    let mut contents = IndexContents::default();
+    for (i, frame) in data.chunks(FRAME_LEN).enumerate() {
+        match IndexEntry::decode(frame) {
+            Some(entry) => contents.entries.push(entry),
+            None => {
+                contents.torn_bytes = data.len() - i * FRAME_LEN;
+                break;
+            }
+        }
+    }
+    Ok(contents)
+}
+
+/// Replace the index of an entry directory with `entries`
+pub fn rewrite(entry_dir: &Path, entries: &[IndexEntry]) -> Result<()> {
+    let path = index_path(entry_dir);
+    let temp_path = entry_dir.join(format!("{}.tmp", INDEX_FILE));
+    let data: Vec<u8> = entries.iter().flat_map(|entry| entry.encode()).collect();
+    let mut file = std::fs::File::create(&temp_path)
+        .context(format!("Failed to create index: {}", temp_path.display()))?;
+    file.write_all(&data)
+        .and_then(|()| file.sync_all())
+        .context(format!("Failed to write index: {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, &path)
+        .context(format!("Failed to replace index: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_append_and_read() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = IndexEntry::for_record(1000, b"first record");
+        let second = IndexEntry::for_record(2000, b"second");
+        append(temp_dir.path(), first).await.unwrap();
+        append(temp_dir.path(), second).await.unwrap();
+
+        let contents = read(temp_dir.path()).unwrap();
+        assert_eq!(contents.entries, vec![first, second]);
+        assert_eq!(contents.torn_bytes, 0);
+        assert!(first.matches(b"first record"));
+        assert!(!first.matches(b"first rec"));
+        assert!(!first.matches(b"first recorx"));
+    }
+
+    #[tokio::test]
+    async fn test_torn_frame_is_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = IndexEntry::for_record(1000, b"record");
+        append(temp_dir.path(), entry).await.unwrap();
+        append(temp_dir.path(), IndexEntry::for_record(2000, b"lost"))
+            .await
+            .unwrap();
+
+        // Cut the second frame short, as a power loss mid-append would
+        let path = index_path(temp_dir.path());
+        let data = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &data[..FRAME_LEN + 10]).unwrap();
+
+        let contents = read(temp_dir.path()).unwrap();
+        assert_eq!(contents.entries, vec![entry]);
+        assert_eq!(contents.torn_bytes, 10);
+
+        rewrite(temp_dir.path(), &contents.entries).unwrap();
+        let contents = read(temp_dir.path()).unwrap();
+        assert_eq!(contents.entries, vec![entry]);
+        assert_eq!(contents.torn_bytes, 0);
+    }
+
+    #[test]
+    fn test_missing_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let contents = read(temp_dir.path()).unwrap();
+        assert!(contents.entries.is_empty());
+    }
+}
@@ -14,21 +14,42 @@
 
 // ReductStore backend implementation
 
-use super::backend::StorageBackend;
-use crate::config::ReductStoreConfig;
-use anyhow::{bail, Context, Result};
+use super::backend::{slice_range, StorageBackend, UploadProgress, EXPIRES_AT_LABEL};
+use super::reductstore_batch::BatchWriter;
+use crate::config::{ReductStoreBucketSettings, ReductStoreConfig, ReductStoreTlsConfig};
+use crate::error::{RecorderError, Result};
+use crate::runtime;
+use crate::task_registry::TaskRegistry;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
-use reqwest::Client;
+use bytes::Bytes;
+use reqwest::{Body, Client};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{info, warn};
 
+/// Size of the chunks a record body is streamed in
+const UPLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Prefix of the headers labels are sent as
+const LABEL_HEADER_PREFIX: &str = "x-reduct-label-";
+
+/// Label counting the labels dropped from a record by `max_label_bytes`
+pub const LABELS_TRUNCATED_LABEL: &str = "labels_truncated";
+
 /// ReductStore client for uploading data
 pub struct ReductStoreBackend {
     client: Client,
     base_url: String,
     bucket_name: String,
     max_retries: u32,
+    max_label_bytes: usize,
+    batch_max_records: usize,
+    /// Quota and block settings pushed to the bucket
+    bucket_settings: ReductStoreBucketSettings,
+    /// Groups small records of an entry into batch requests
+    batches: Arc<BatchWriter>,
 }
 
 impl ReductStoreBackend {
@@ -45,45 +66,62 @@ impl ReductStoreBackend {
             let auth_value = format!("Bearer {}", token);
             headers.insert(
                 reqwest::header::AUTHORIZATION,
-                reqwest::header::HeaderValue::from_str(&auth_value).context("Invalid API token")?,
+                reqwest::header::HeaderValue::from_str(&auth_value)
+                    .context("Invalid API token")
+                    .map_err(RecorderError::config)?,
             );
             client_builder = client_builder.default_headers(headers);
         }
+        client_builder = with_tls(client_builder, &config.tls).map_err(RecorderError::config)?;
 
         let client = client_builder
             .build()
-            .context("Failed to build HTTP client")?;
+            .context("Failed to build HTTP client")
+            .map_err(RecorderError::backend)?;
 
         Ok(Self {
+            batches: Arc::new(BatchWriter::new(
+                client.clone(),
+                config.url.clone(),
+                config.bucket_name.clone(),
+                config.batch_max_records,
+            )),
             client,
             base_url: config.url,
             bucket_name: config.bucket_name,
             max_retries: config.max_retries,
+            max_label_bytes: config.max_label_bytes,
+            batch_max_records: config.batch_max_records,
+            bucket_settings: config.bucket_settings,
         })
     }
 
-    /// Create bucket if it doesn't exist
-    async fn ensure_bucket(&self) -> Result<()> {
+    /// Create bucket if it doesn't exist, with the configured settings
+    ///
+    /// The settings of an existing bucket are updated when any is configured.
+    async fn ensure_bucket(&self) -> anyhow::Result<()> {
         let url = format!("{}/api/v1/b/{}", self.base_url, self.bucket_name);
 
         match self.client.head(&url).send().await {
             Ok(response) if response.status().is_success() => {
                 info!("Bucket '{}' already exists", self.bucket_name);
-                Ok(())
+                self.update_bucket_settings().await
             }
             _ => {
                 info!("Creating bucket '{}'", self.bucket_name);
                 let create_url = format!("{}/api/v1/b/{}", self.base_url, self.bucket_name);
-                let response = self
-                    .client
-                    .post(&create_url)
-                    .send()
-                    .await
-                    .context("Failed to create bucket")?;
-
-                if response.status().is_success() || response.status().as_u16() == 409 {
+                let mut request = self.client.post(&create_url);
+                if !self.bucket_settings.is_empty() {
+                    request = request.json(&bucket_settings_json(&self.bucket_settings));
+                }
+                let response = request.send().await.context("Failed to create bucket")?;
+
+                if response.status().is_success() {
                     info!("Bucket '{}' created successfully", self.bucket_name);
                     Ok(())
+                } else if response.status().as_u16() == 409 {
+                    // Created by another client meanwhile
+                    self.update_bucket_settings().await
                 } else {
                     let status = response.status();
                     let error_text = response.text().await.unwrap_or_default();
@@ -92,21 +130,121 @@ impl ReductStoreBackend {
             }
         }
     }
-}
 
-#[async_trait]
-impl StorageBackend for ReductStoreBackend {
-    async fn initialize(&self) -> Result<()> {
-        self.ensure_bucket().await
+    /// Apply the configured settings to the existing bucket
+    async fn update_bucket_settings(&self) -> anyhow::Result<()> {
+        if self.bucket_settings.is_empty() {
+            return Ok(());
+        }
+        let url = format!("{}/api/v1/b/{}", self.base_url, self.bucket_name);
+        let response = self
+            .client
+            .put(&url)
+            .json(&bucket_settings_json(&self.bucket_settings))
+            .send()
+            .await
+            .context("Failed to update bucket settings")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            bail!(
+                "Failed to update settings of bucket '{}': {} - {}",
+                self.bucket_name,
+                status,
+                error_text
+            );
+        }
+        info!("Updated settings of bucket '{}'", self.bucket_name);
+        Ok(())
     }
 
-    async fn write_record(
+    /// Names of the entries of the bucket
+    async fn entry_names(&self) -> anyhow::Result<Vec<String>> {
+        let url = format!("{}/api/v1/b/{}", self.base_url, self.bucket_name);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send request")?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(vec![]),
+            status if !status.is_success() => {
+                let error_text = response.text().await.unwrap_or_default();
+                bail!(
+                    "ReductStore bucket info failed with status {}: {}",
+                    status,
+                    error_text
+                );
+            }
+            _ => {}
+        }
+        let info: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse bucket info")?;
+        Ok(info
+            .get("entries")
+            .and_then(|entries| entries.as_array())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.get("name")?.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Remove the records of `entry_name` that expired at `now_secs`, with a
+    /// conditional remove query
+    async fn remove_expired_records(&self, entry_name: &str, now_secs: u64) -> anyhow::Result<u64> {
+        let url = format!(
+            "{}/api/v1/b/{}/{}/q",
+            self.base_url, self.bucket_name, entry_name
+        );
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({
+                "query_type": "REMOVE",
+                "when": { format!("&{}", EXPIRES_AT_LABEL): { "$lte": now_secs } },
+            }))
+            .send()
+            .await
+            .context("Failed to send request")?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(0),
+            status if !status.is_success() => {
+                let error_text = response.text().await.unwrap_or_default();
+                bail!(
+                    "ReductStore remove query failed with status {}: {}",
+                    status,
+                    error_text
+                );
+            }
+            _ => {}
+        }
+        let removed: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse remove query response")?;
+        Ok(removed
+            .get("removed_records")
+            .and_then(|removed| removed.as_u64())
+            .unwrap_or(0))
+    }
+
+    /// Post a record with its labels as `x-reduct-label-*` headers
+    ///
+    /// `progress` counts the chunks as the HTTP stack takes them.
+    async fn post_record(
         &self,
         entry_name: &str,
         timestamp_us: u64,
-        data: Vec<u8>,
+        data: Bytes,
         labels: HashMap<String, String>,
-    ) -> Result<()> {
+        progress: Option<Arc<UploadProgress>>,
+    ) -> anyhow::Result<()> {
         let url = format!(
             "{}/api/v1/b/{}/{}?ts={}",
             self.base_url, self.bucket_name, entry_name, timestamp_us
@@ -121,11 +259,20 @@ impl StorageBackend for ReductStoreBackend {
 
         // Add labels as headers
         for (key, value) in labels {
-            request = request.header(format!("x-reduct-label-{}", key), value);
+            request = request.header(format!("{}{}", LABEL_HEADER_PREFIX, key), value);
         }
 
+        // Streamed so the HTTP stack doesn't buffer another copy of the record
         let response = request
-            .body(data)
+            .body(Body::wrap_stream(futures_util::stream::iter(
+                chunks(data)
+                    .inspect(move |chunk| {
+                        if let Some(progress) = &progress {
+                            progress.advance(chunk.len() as u64);
+                        }
+                    })
+                    .map(Ok::<_, std::io::Error>),
+            )))
             .send()
             .await
             .context("Failed to send request")?;
@@ -143,13 +290,60 @@ impl StorageBackend for ReductStoreBackend {
         Ok(())
     }
 
-    async fn write_with_retry(
+    /// Send a record, in a batch with other records of the entry when it's
+    /// small and the server supports batches
+    async fn send_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Bytes,
+        labels: HashMap<String, String>,
+        progress: Option<Arc<UploadProgress>>,
+    ) -> anyhow::Result<()> {
+        if self.batches.accepts(&data, &labels) && self.batches.supported().await {
+            self.batches
+                .write(entry_name, timestamp_us, data, labels)
+                .await?;
+            if let Some(progress) = progress {
+                progress.complete();
+            }
+            return Ok(());
+        }
+        self.post_record(entry_name, timestamp_us, data, labels, progress)
+            .await
+    }
+
+    /// Labels of a record within `max_label_bytes`, warning about the ones
+    /// dropped
+    fn record_labels(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        labels: HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let (labels, dropped) = cap_labels(labels, self.max_label_bytes);
+        if !dropped.is_empty() {
+            warn!(
+                "Dropped {} labels of record {} in entry '{}' (invalid or over {} bytes): {}",
+                dropped.len(),
+                timestamp_us,
+                entry_name,
+                self.max_label_bytes,
+                dropped.join(", ")
+            );
+        }
+        labels
+    }
+
+    /// Post a record, retrying with exponential backoff
+    async fn post_with_retry(
         &self,
         entry_name: &str,
         timestamp_us: u64,
         data: Vec<u8>,
         labels: HashMap<String, String>,
         max_retries: u32,
+        progress: Option<Arc<UploadProgress>>,
     ) -> Result<()> {
         // Use the configured max_retries or override
         let retries = if max_retries > 0 {
@@ -158,14 +352,26 @@ impl StorageBackend for ReductStoreBackend {
             self.max_retries
         };
 
-        // Call the default trait implementation
+        // Retries share the buffer instead of copying the record per attempt
+        let data = Bytes::from(data);
+        let labels = self.record_labels(entry_name, timestamp_us, labels);
         let mut attempt = 0;
         let mut delay = Duration::from_millis(100);
 
         loop {
+            if let Some(progress) = &progress {
+                progress.reset();
+            }
             match self
-                .write_record(entry_name, timestamp_us, data.clone(), labels.clone())
+                .send_record(
+                    entry_name,
+                    timestamp_us,
+                    data.clone(),
+                    labels.clone(),
+                    progress.clone(),
+                )
                 .await
+                .map_err(RecorderError::backend)
             {
                 Ok(_) => {
                     if attempt > 0 {
@@ -185,7 +391,7 @@ impl StorageBackend for ReductStoreBackend {
                         e,
                         delay
                     );
-                    tokio::time::sleep(delay).await;
+                    runtime::sleep(delay).await;
                     delay *= 2; // Exponential backoff
                     delay = delay.min(Duration::from_secs(30)); // Cap at 30 seconds
                     attempt += 1;
@@ -203,6 +409,261 @@ impl StorageBackend for ReductStoreBackend {
         }
     }
 
+    /// Fetch the body of the record stored at `timestamp_us`
+    async fn get_record(&self, entry_name: &str, timestamp_us: u64) -> anyhow::Result<Vec<u8>> {
+        let url = format!(
+            "{}/api/v1/b/{}/{}?ts={}",
+            self.base_url, self.bucket_name, entry_name, timestamp_us
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to send request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            bail!(
+                "ReductStore read failed with status {}: {}",
+                status,
+                error_text
+            );
+        }
+
+        Ok(response
+            .bytes()
+            .await
+            .context("Failed to read response body")?
+            .to_vec())
+    }
+
+    /// Read part of a record with a `Range` request
+    ///
+    /// Servers that ignore the range answer with the whole record, which is
+    /// cut here.
+    async fn get_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> anyhow::Result<Vec<u8>> {
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+        let url = format!(
+            "{}/api/v1/b/{}/{}?ts={}",
+            self.base_url, self.bucket_name, entry_name, timestamp_us
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{}", offset, offset + length - 1),
+            )
+            .send()
+            .await
+            .context("Failed to send request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            bail!(
+                "ReductStore read failed with status {}: {}",
+                status,
+                error_text
+            );
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .context("Failed to read response body")?;
+        if status == reqwest::StatusCode::PARTIAL_CONTENT {
+            Ok(body.to_vec())
+        } else {
+            Ok(slice_range(&body, offset, length).to_vec())
+        }
+    }
+
+    /// Timestamps of the records labeled `label` = `value`, via a query
+    async fn query_timestamps(
+        &self,
+        entry_name: &str,
+        label: &str,
+        value: &str,
+    ) -> anyhow::Result<Vec<u64>> {
+        let url = format!(
+            "{}/api/v1/b/{}/{}/q",
+            self.base_url, self.bucket_name, entry_name
+        );
+        let response = self
+            .client
+            .get(&url)
+            .query(&[(format!("include-{}", label), value)])
+            .send()
+            .await
+            .context("Failed to send request")?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => return Ok(vec![]),
+            status if !status.is_success() => {
+                let error_text = response.text().await.unwrap_or_default();
+                bail!(
+                    "ReductStore query failed with status {}: {}",
+                    status,
+                    error_text
+                );
+            }
+            _ => {}
+        }
+        let query: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse query response")?;
+        let Some(query_id) = query.get("id").and_then(|id| id.as_u64()) else {
+            bail!("ReductStore query response has no id: {}", query);
+        };
+
+        // Records come one at a time until the query is exhausted (204)
+        let mut timestamps = Vec::new();
+        loop {
+            let response = self
+                .client
+                .get(format!(
+                    "{}/api/v1/b/{}/{}",
+                    self.base_url, self.bucket_name, entry_name
+                ))
+                .query(&[("q", query_id)])
+                .send()
+                .await
+                .context("Failed to send request")?;
+
+            let status = response.status();
+            if status == reqwest::StatusCode::NO_CONTENT {
+                break;
+            }
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                bail!(
+                    "ReductStore query failed with status {}: {}",
+                    status,
+                    error_text
+                );
+            }
+
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let timestamp_us = header("x-reduct-time")
+                .and_then(|time| time.parse().ok())
+                .context("Query response has no x-reduct-time header")?;
+            timestamps.push(timestamp_us);
+            if header("x-reduct-last").as_deref() == Some("true") {
+                break;
+            }
+        }
+        timestamps.sort_unstable();
+        Ok(timestamps)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ReductStoreBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.ensure_bucket().await.map_err(RecorderError::backend)
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        let labels = self.record_labels(entry_name, timestamp_us, labels);
+        self.send_record(entry_name, timestamp_us, Bytes::from(data), labels, None)
+            .await
+            .map_err(RecorderError::backend)
+    }
+
+    async fn read_record(&self, entry_name: &str, timestamp_us: u64) -> Result<Vec<u8>> {
+        self.get_record(entry_name, timestamp_us)
+            .await
+            .map_err(RecorderError::backend)
+    }
+
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        self.get_record_range(entry_name, timestamp_us, offset, length)
+            .await
+            .map_err(RecorderError::backend)
+    }
+
+    async fn find_records(&self, entry_name: &str, label: &str, value: &str) -> Result<Vec<u64>> {
+        self.query_timestamps(entry_name, label, value)
+            .await
+            .map_err(RecorderError::backend)
+    }
+
+    async fn write_with_retry(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+        max_retries: u32,
+    ) -> Result<()> {
+        self.post_with_retry(entry_name, timestamp_us, data, labels, max_retries, None)
+            .await
+    }
+
+    async fn write_with_progress(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+        max_retries: u32,
+        progress: Arc<UploadProgress>,
+    ) -> Result<()> {
+        self.post_with_retry(
+            entry_name,
+            timestamp_us,
+            data,
+            labels,
+            max_retries,
+            Some(progress),
+        )
+        .await
+    }
+
+    async fn remove_expired(&self, now_secs: u64) -> Result<usize> {
+        let entries = self.entry_names().await.map_err(RecorderError::backend)?;
+        let mut removed = 0;
+        for entry_name in entries {
+            removed += self
+                .remove_expired_records(&entry_name, now_secs)
+                .await
+                .map_err(RecorderError::backend)? as usize;
+        }
+        Ok(removed)
+    }
+
     async fn health_check(&self) -> Result<bool> {
         let url = format!("{}/api/v1/info", self.base_url);
         match self.client.get(&url).send().await {
@@ -218,6 +679,34 @@ impl StorageBackend for ReductStoreBackend {
         }
     }
 
+    fn with_bucket(&self, bucket: &str) -> Result<Arc<dyn StorageBackend>> {
+        let batches = BatchWriter::new(
+            self.client.clone(),
+            self.base_url.clone(),
+            bucket.to_string(),
+            self.batch_max_records,
+        );
+        batches.set_tasks(&self.batches.tasks());
+        Ok(Arc::new(Self {
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            bucket_name: bucket.to_string(),
+            max_retries: self.max_retries,
+            max_label_bytes: self.max_label_bytes,
+            batch_max_records: self.batch_max_records,
+            bucket_settings: self.bucket_settings.clone(),
+            batches: Arc::new(batches),
+        }))
+    }
+
+    fn bucket(&self) -> Option<&str> {
+        Some(&self.bucket_name)
+    }
+
+    fn set_tasks(&self, tasks: &TaskRegistry) {
+        self.batches.set_tasks(tasks);
+    }
+
     fn backend_type(&self) -> &str {
         "reductstore"
     }
@@ -229,4 +718,213 @@ pub fn topic_to_entry_name(topic: &str) -> String {
         .trim_start_matches('/')
         .replace('/', "_")
         .replace("**", "all")
+        .replace('@', "at_")
+}
+
+/// Body of a bucket create or update request with the settings that are set
+fn bucket_settings_json(settings: &ReductStoreBucketSettings) -> serde_json::Value {
+    let mut json = serde_json::Map::new();
+    if let Some(quota_type) = settings.quota_type {
+        json.insert("quota_type".to_string(), quota_type.as_api_str().into());
+    }
+    if let Some(quota_size) = settings.quota_size {
+        json.insert("quota_size".to_string(), quota_size.into());
+    }
+    if let Some(max_block_size) = settings.max_block_size {
+        json.insert("max_block_size".to_string(), max_block_size.into());
+    }
+    if let Some(max_block_records) = settings.max_block_records {
+        json.insert("max_block_records".to_string(), max_block_records.into());
+    }
+    serde_json::Value::Object(json)
+}
+
+/// Trust the CAs of `tls.ca_file`, present the client certificate and skip
+/// verification as configured
+fn with_tls(
+    mut builder: reqwest::ClientBuilder,
+    tls: &ReductStoreTlsConfig,
+) -> anyhow::Result<reqwest::ClientBuilder> {
+    if let Some(ca_file) = &tls.ca_file {
+        let pem = std::fs::read(ca_file)
+            .with_context(|| format!("Failed to read reductstore.tls.ca_file '{}'", ca_file))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Invalid CA bundle '{}'", ca_file))?;
+        if certificates.is_empty() {
+            bail!("No certificate in CA bundle '{}'", ca_file);
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let (Some(cert_file), Some(key_file)) = (&tls.client_cert_file, &tls.client_key_file) {
+        let cert = std::fs::read(cert_file).with_context(|| {
+            format!(
+                "Failed to read reductstore.tls.client_cert_file '{}'",
+                cert_file
+            )
+        })?;
+        let key = std::fs::read(key_file).with_context(|| {
+            format!(
+                "Failed to read reductstore.tls.client_key_file '{}'",
+                key_file
+            )
+        })?;
+        let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).with_context(|| {
+            format!(
+                "Invalid client certificate '{}' or key '{}'",
+                cert_file, key_file
+            )
+        })?;
+        builder = builder.identity(identity);
+    }
+    if tls.insecure_skip_verify {
+        warn!("reductstore.tls.insecure_skip_verify is set: server certificates are not checked");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// Split a record body into `UPLOAD_CHUNK_SIZE` slices (no copies)
+fn chunks(data: Bytes) -> impl Iterator<Item = Bytes> + Send + 'static {
+    let len = data.len();
+    (0..len)
+        .step_by(UPLOAD_CHUNK_SIZE)
+        .map(move |start| data.slice(start..(start + UPLOAD_CHUNK_SIZE).min(len)))
+}
+
+/// Header bytes a label takes
+fn label_bytes(key: &str, value: &str) -> usize {
+    LABEL_HEADER_PREFIX.len() + key.len() + value.len()
+}
+
+/// Labels that can be sent, within `max_bytes` of header names and values
+/// (0 = no cap), and the names of the ones dropped
+///
+/// Labels that aren't valid headers are always dropped. When the others
+/// don't fit, the smallest are kept, and `labels_truncated` is set to the
+/// number of labels dropped.
+pub fn cap_labels(
+    labels: HashMap<String, String>,
+    max_bytes: usize,
+) -> (HashMap<String, String>, Vec<String>) {
+    let mut dropped = Vec::new();
+    let mut valid: Vec<(String, String)> = Vec::with_capacity(labels.len());
+    for (key, value) in labels {
+        let header = format!("{}{}", LABEL_HEADER_PREFIX, key);
+        if reqwest::header::HeaderName::from_bytes(header.as_bytes()).is_ok()
+            && reqwest::header::HeaderValue::from_str(&value).is_ok()
+        {
+            valid.push((key, value));
+        } else {
+            dropped.push(key);
+        }
+    }
+
+    let total: usize = valid.iter().map(|(k, v)| label_bytes(k, v)).sum();
+    if max_bytes > 0 && (total > max_bytes || !dropped.is_empty()) {
+        valid.sort_by(|(a, va), (b, vb)| (label_bytes(a, va), a).cmp(&(label_bytes(b, vb), b)));
+        // Room for the marker, whose count has at most as many digits as
+        // the label count
+        let marker = label_bytes(
+            LABELS_TRUNCATED_LABEL,
+            &(valid.len() + dropped.len()).to_string(),
+        );
+        let mut used = marker;
+        let mut kept = Vec::with_capacity(valid.len());
+        for (key, value) in valid {
+            let bytes = label_bytes(&key, &value);
+            if used + bytes <= max_bytes {
+                used += bytes;
+                kept.push((key, value));
+            } else {
+                dropped.push(key);
+            }
+        }
+        valid = kept;
+    }
+    dropped.sort();
+
+    let mut labels: HashMap<String, String> = valid.into_iter().collect();
+    if !dropped.is_empty() {
+        labels.insert(
+            LABELS_TRUNCATED_LABEL.to_string(),
+            dropped.len().to_string(),
+        );
+    }
+    (labels, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_labels() {
+        let labels: HashMap<String, String> = [
+            ("recording_id", "rec-1".to_string()),
+            ("topic", "/camera".to_string()),
+            ("scene", "x".repeat(200)),
+            ("env_note", "y".repeat(100)),
+            ("env_bad", "line\nbreak".to_string()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        // Within the cap, only invalid labels go
+        let (capped, dropped) = cap_labels(labels.clone(), 8192);
+        assert_eq!(dropped, vec!["env_bad"]);
+        assert_eq!(capped.len(), 5);
+        assert_eq!(capped[LABELS_TRUNCATED_LABEL], "1");
+
+        // Over it, the largest go first
+        let (capped, dropped) = cap_labels(labels.clone(), 200);
+        assert_eq!(dropped, vec!["env_bad", "env_note", "scene"]);
+        assert_eq!(capped["recording_id"], "rec-1");
+        assert_eq!(capped["topic"], "/camera");
+        assert_eq!(capped[LABELS_TRUNCATED_LABEL], "3");
+        let bytes: usize = capped.iter().map(|(k, v)| label_bytes(k, v)).sum();
+        assert!(bytes <= 200);
+
+        // No cap
+        let (capped, dropped) = cap_labels(labels, 0);
+        assert_eq!(dropped, vec!["env_bad"]);
+        assert_eq!(capped.len(), 5);
+    }
+
+    #[test]
+    fn test_bucket_settings_json() {
+        let settings = ReductStoreBucketSettings {
+            quota_type: Some(crate::config::ReductStoreQuotaType::Fifo),
+            quota_size: Some(1_000_000_000),
+            max_block_records: Some(1024),
+            ..Default::default()
+        };
+        assert_eq!(
+            bucket_settings_json(&settings),
+            serde_json::json!({
+                "quota_type": "FIFO",
+                "quota_size": 1_000_000_000u64,
+                "max_block_records": 1024,
+            })
+        );
+        assert_eq!(
+            bucket_settings_json(&ReductStoreBucketSettings::default()),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_chunks() {
+        let data: Vec<u8> = (0..UPLOAD_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        let parts: Vec<Bytes> = chunks(Bytes::from(data.clone())).collect();
+        assert_eq!(
+            parts.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![UPLOAD_CHUNK_SIZE, UPLOAD_CHUNK_SIZE, 10]
+        );
+        assert_eq!(parts.concat(), data);
+
+        assert_eq!(chunks(Bytes::new()).count(), 0);
+    }
 }
@@ -0,0 +1,396 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Batched writes to ReductStore (`storage.reductstore.batch_max_records`)
+//
+// Records of an entry written while a request for that entry is in flight
+// queue up and go out together in the next request, through the batch
+// endpoint (`POST /api/v1/b/{bucket}/{entry}/batch`, ReductStore 1.7+), up to
+// `batch_max_records` records and `BATCH_MAX_BYTES` per request. Entries are
+// written in parallel. Every record keeps its own result: the server names
+// the ones it rejected in `x-reduct-error-{timestamp}` headers. Whether the
+// server has the endpoint is asked once (`/api/v1/info`); older servers get a
+// request per record.
+
+use anyhow::{anyhow, bail, Context, Result};
+use bytes::Bytes;
+use reqwest::Client;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+use crate::protocol::TaskStage;
+use crate::task_registry::{TaskRegistry, PROCESS_SCOPE};
+
+/// Largest body of a batch request; bigger records are streamed on their own
+pub const BATCH_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Largest record that goes into a batch
+const BATCH_MAX_RECORD_BYTES: usize = 1024 * 1024;
+
+/// First ReductStore version with the batch endpoint
+const BATCH_MIN_VERSION: (u64, u64) = (1, 7);
+
+/// Prefix of the headers a batch describes its records in
+const TIME_HEADER_PREFIX: &str = "x-reduct-time-";
+
+/// Prefix of the headers the server reports rejected records in
+const ERROR_HEADER_PREFIX: &str = "x-reduct-error-";
+
+/// Record waiting for the next batch of its entry
+struct PendingRecord {
+    timestamp_us: u64,
+    data: Bytes,
+    labels: HashMap<String, String>,
+    done: oneshot::Sender<Result<()>>,
+}
+
+/// Records of one entry and whether a batch of it is in flight
+#[derive(Default)]
+struct EntryQueue {
+    pending: VecDeque<PendingRecord>,
+    sending: bool,
+}
+
+/// Groups the writes of each entry of a bucket into batch requests
+pub struct BatchWriter {
+    client: Client,
+    base_url: String,
+    bucket_name: String,
+    max_records: usize,
+    queues: Mutex<HashMap<String, EntryQueue>>,
+    /// Whether the server has the batch endpoint (None until it answered)
+    supported: Mutex<Option<bool>>,
+    /// Registry the batches of an entry are sent from
+    tasks: Mutex<TaskRegistry>,
+}
+
+impl BatchWriter {
+    pub fn new(client: Client, base_url: String, bucket_name: String, max_records: usize) -> Self {
+        Self {
+            client,
+            base_url,
+            bucket_name,
+            max_records,
+            queues: Mutex::new(HashMap::new()),
+            supported: Mutex::new(None),
+            tasks: Mutex::new(TaskRegistry::new(PROCESS_SCOPE)),
+        }
+    }
+
+    /// Registry the batches are sent from
+    pub fn tasks(&self) -> TaskRegistry {
+        self.tasks.lock().unwrap().clone()
+    }
+
+    /// Send the batches as tasks of `tasks` from now on
+    pub fn set_tasks(&self, tasks: &TaskRegistry) {
+        *self.tasks.lock().unwrap() = tasks.clone();
+    }
+
+    /// Whether a record can go into a batch: batching is on, the record is
+    /// small and its labels can be written in a batch header
+    pub fn accepts(&self, data: &[u8], labels: &HashMap<String, String>) -> bool {
+        self.max_records > 1
+            && data.len() <= BATCH_MAX_RECORD_BYTES
+            && labels.values().all(|value| !value.contains('"'))
+    }
+
+    /// Whether the server has the batch endpoint, asked on first use
+    ///
+    /// Without an answer (server unreachable), records go out on their own
+    /// and the server is asked again next time.
+    pub async fn supported(&self) -> bool {
+        if let Some(supported) = *self.supported.lock().unwrap() {
+            return supported;
+        }
+        match self.server_version().await {
+            Ok(version) => {
+                let supported = parse_version(&version).is_some_and(|v| v >= BATCH_MIN_VERSION);
+                if supported {
+                    info!("ReductStore {} supports batched writes", version);
+                } else {
+                    info!(
+                        "ReductStore {} has no batch endpoint, writing records one by one",
+                        version
+                    );
+                }
+                *self.supported.lock().unwrap() = Some(supported);
+                supported
+            }
+            Err(e) => {
+                debug!("Failed to get the ReductStore version: {:#}", e);
+                false
+            }
+        }
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        let info: serde_json::Value = self
+            .client
+            .get(format!("{}/api/v1/info", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        info.get("version")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("no version in {}", info))
+    }
+
+    /// Write a record with the next batch of its entry
+    pub async fn write(
+        self: &Arc<Self>,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Bytes,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        let (done, result) = oneshot::channel();
+        let start = {
+            let mut queues = self.queues.lock().unwrap();
+            let queue = queues.entry(entry_name.to_string()).or_default();
+            queue.pending.push_back(PendingRecord {
+                timestamp_us,
+                data,
+                labels,
+                done,
+            });
+            !std::mem::replace(&mut queue.sending, true)
+        };
+        if start {
+            let writer = self.clone();
+            let entry_name = entry_name.to_string();
+            self.tasks().spawn(
+                TaskStage::Flush,
+                format!("batch writer {}", entry_name),
+                async move { writer.drain(&entry_name).await },
+            );
+        }
+        result
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("Batch writer dropped the record")))
+    }
+
+    /// Send the batches of an entry until its queue is empty
+    async fn drain(&self, entry_name: &str) {
+        loop {
+            let batch = {
+                let mut queues = self.queues.lock().unwrap();
+                let Some(queue) = queues.get_mut(entry_name) else {
+                    return;
+                };
+                let batch = take_batch(&mut queue.pending, self.max_records);
+                if batch.is_empty() {
+                    queues.remove(entry_name);
+                    return;
+                }
+                batch
+            };
+
+            debug!(
+                "Writing {} records to entry '{}' in one batch",
+                batch.len(),
+                entry_name
+            );
+            match self.post_batch(entry_name, &batch).await {
+                Ok(mut rejected) => {
+                    for record in batch {
+                        let result = match rejected.remove(&record.timestamp_us) {
+                            Some(error) => Err(anyhow!(
+                                "ReductStore rejected record {}: {}",
+                                record.timestamp_us,
+                                error
+                            )),
+                            None => Ok(()),
+                        };
+                        let _ = record.done.send(result);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Batch write of {} records to entry '{}' failed: {:#}",
+                        batch.len(),
+                        entry_name,
+                        e
+                    );
+                    for record in batch {
+                        let _ = record.done.send(Err(anyhow!("{:#}", e)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Post a batch; returns the errors of the records the server rejected,
+    /// by timestamp
+    async fn post_batch(
+        &self,
+        entry_name: &str,
+        batch: &[PendingRecord],
+    ) -> Result<HashMap<u64, String>> {
+        let url = format!(
+            "{}/api/v1/b/{}/{}/batch",
+            self.base_url, self.bucket_name, entry_name
+        );
+
+        // The body holds the records in timestamp order
+        let mut order: Vec<&PendingRecord> = batch.iter().collect();
+        order.sort_by_key(|record| record.timestamp_us);
+        let mut body = Vec::with_capacity(order.iter().map(|r| r.data.len()).sum());
+        let mut request = self.client.post(&url);
+        for record in order {
+            body.extend_from_slice(&record.data);
+            request = request.header(
+                format!("{}{}", TIME_HEADER_PREFIX, record.timestamp_us),
+                record_header(record.data.len(), &record.labels),
+            );
+        }
+
+        let response = request
+            .header("Content-Type", "application/octet-stream")
+            .header("Content-Length", body.len().to_string())
+            .body(body)
+            .send()
+            .await
+            .context("Failed to send batch request")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            bail!(
+                "ReductStore batch write failed with status {}: {}",
+                status,
+                error_text
+            );
+        }
+
+        Ok(response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let timestamp_us = name
+                    .as_str()
+                    .strip_prefix(ERROR_HEADER_PREFIX)?
+                    .parse()
+                    .ok()?;
+                Some((timestamp_us, value.to_str().unwrap_or_default().to_string()))
+            })
+            .collect())
+    }
+}
+
+/// Next records of a queue for one batch: at most `max_records` and
+/// `BATCH_MAX_BYTES` (but at least one record), without two records at the
+/// same timestamp
+fn take_batch(pending: &mut VecDeque<PendingRecord>, max_records: usize) -> Vec<PendingRecord> {
+    let mut batch = Vec::new();
+    let mut bytes = 0;
+    let mut timestamps = HashSet::new();
+    while let Some(next) = pending.front() {
+        let full = batch.len() >= max_records
+            || (!batch.is_empty() && bytes + next.data.len() > BATCH_MAX_BYTES);
+        if full || timestamps.contains(&next.timestamp_us) {
+            break;
+        }
+        let record = pending.pop_front().unwrap();
+        bytes += record.data.len();
+        timestamps.insert(record.timestamp_us);
+        batch.push(record);
+    }
+    batch
+}
+
+/// Value of the `x-reduct-time-{timestamp}` header of a record: its length,
+/// content type and labels (values with a comma quoted)
+pub fn record_header(len: usize, labels: &HashMap<String, String>) -> String {
+    let mut labels: Vec<_> = labels.iter().collect();
+    labels.sort();
+    let mut header = format!("{},application/mcap", len);
+    for (key, value) in labels {
+        if value.contains(',') {
+            header.push_str(&format!(",{}=\"{}\"", key, value));
+        } else {
+            header.push_str(&format!(",{}={}", key, value));
+        }
+    }
+    header
+}
+
+/// (major, minor) of a version like `1.9.3`
+fn parse_version(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(timestamp_us: u64, len: usize) -> PendingRecord {
+        PendingRecord {
+            timestamp_us,
+            data: Bytes::from(vec![0u8; len]),
+            labels: HashMap::new(),
+            done: oneshot::channel().0,
+        }
+    }
+
+    #[test]
+    fn test_take_batch() {
+        let mut queue: VecDeque<_> = (0..5).map(|i| pending(i, 10)).collect();
+        let batch = take_batch(&mut queue, 3);
+        assert_eq!(
+            batch.iter().map(|r| r.timestamp_us).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(queue.len(), 2);
+
+        // A repeated timestamp waits for the next batch
+        let mut queue: VecDeque<_> = [1, 2, 1].into_iter().map(|ts| pending(ts, 10)).collect();
+        assert_eq!(take_batch(&mut queue, 10).len(), 2);
+        assert_eq!(queue.len(), 1);
+
+        // The byte cap still lets one record through
+        let mut queue: VecDeque<_> = [pending(1, BATCH_MAX_BYTES), pending(2, 1)].into();
+        assert_eq!(take_batch(&mut queue, 10).len(), 1);
+        assert_eq!(take_batch(&mut queue, 10).len(), 1);
+        assert!(take_batch(&mut queue, 10).is_empty());
+    }
+
+    #[test]
+    fn test_record_header() {
+        let labels = HashMap::from([
+            ("topic".to_string(), "/camera".to_string()),
+            ("skills".to_string(), "grasp,place".to_string()),
+        ]);
+        assert_eq!(
+            record_header(42, &labels),
+            "42,application/mcap,skills=\"grasp,place\",topic=/camera"
+        );
+        assert_eq!(record_header(0, &HashMap::new()), "0,application/mcap");
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.9.3"), Some((1, 9)));
+        assert_eq!(parse_version("v1.10.0-beta"), Some((1, 10)));
+        assert_eq!(parse_version("dev"), None);
+        assert!(parse_version("1.6.2").unwrap() < BATCH_MIN_VERSION);
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Repair of filesystem recordings after a power loss (`zenoh-recorder repair`)
+//
+// Every entry directory is checked against its record index. Indexed records
+// whose file matches the length and checksum are kept; files missing from the
+// index are kept only if they decode completely, and are indexed. Anything
+// else was torn by the crash and is renamed to `*.torn`. Lost labels sidecars
+// are restored from the batch header, and recordings left without a metadata
+// record (the recorder died before Finish) get one rebuilt from their records,
+// marked as interrupted. Coalesced records (see `crate::coalesce`) count as
+// complete when their index decodes, and list each of their batches in a
+// rebuilt metadata record.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::backend::{checksum, chunk_checksums, CHECKSUM_CHUNK_BYTES, CHECKSUM_LABEL};
+use super::record_index::{self, IndexEntry, INDEX_FILE};
+use crate::coalesce::{self, COALESCED_ENTRY};
+use crate::inspect::parse_batch;
+use crate::protocol::{compression_ratio, CompressionLevel, RecordChecksum, RecordingMetadata};
+use crate::runtime;
+
+/// Entry holding the recording metadata
+const METADATA_ENTRY: &str = "recordings_metadata";
+
+/// What a repair found and changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Entry directories checked
+    pub entries: usize,
+    /// Records found intact
+    pub valid_records: usize,
+    /// Intact records that were missing from the index
+    pub reindexed: usize,
+    /// Incomplete record files, renamed to `*.torn`
+    pub torn_records: usize,
+    /// Labels sidecars rewritten from the record
+    pub restored_labels: usize,
+    /// Index entries of records that no longer exist
+    pub missing_records: usize,
+    /// Bytes of torn index frames dropped
+    pub torn_index_bytes: usize,
+    /// Recordings whose metadata record was rebuilt
+    pub rebuilt_recordings: Vec<String>,
+}
+
+/// Check and repair the recordings below `base_path`
+///
+/// `base_path` is the base directory of the filesystem backend; bucket
+/// subdirectories are repaired as well.
+pub async fn repair(base_path: &Path, file_format: &str) -> Result<RepairReport> {
+    let base_path = base_path.to_path_buf();
+    let file_format = file_format.to_string();
+    runtime::unblock(move || {
+        let mut report = RepairReport::default();
+        repair_store(&base_path, &file_format, &mut report)?;
+        Ok(report)
+    })
+    .await
+}
+
+/// Intact data record found by a repair
+struct FoundRecord {
+    timestamp_us: u64,
+    bytes: usize,
+    crc32c: u32,
+    labels: HashMap<String, String>,
+}
+
+/// Repair a directory holding entry directories (the base or a bucket)
+fn repair_store(dir: &Path, file_format: &str, report: &mut RepairReport) -> Result<()> {
+    let subdirs = subdirectories(dir)?;
+    let mut by_entry: BTreeMap<String, Vec<FoundRecord>> = BTreeMap::new();
+    for subdir in subdirs {
+        let Some(name) = subdir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        if is_entry_dir(&subdir, file_format) {
+            let records = repair_entry(&subdir, file_format, report)?;
+            by_entry.insert(name, records);
+        } else {
+            repair_store(&subdir, file_format, report)?;
+        }
+    }
+
+    // Recordings with data but no metadata record
+    let mut described: Vec<&str> = Vec::new();
+    if let Some(records) = by_entry.get(METADATA_ENTRY) {
+        described.extend(
+            records
+                .iter()
+                .filter_map(|r| r.labels.get("recording_id"))
+                .map(String::as_str),
+        );
+    }
+    let mut orphaned: BTreeMap<String, Vec<(String, &FoundRecord)>> = BTreeMap::new();
+    for (entry, records) in &by_entry {
+        if entry == METADATA_ENTRY {
+            continue;
+        }
+        for record in records {
+            let Some(recording_id) = record.labels.get("recording_id") else {
+                continue;
+            };
+            if !described.contains(&recording_id.as_str()) {
+                orphaned
+                    .entry(recording_id.clone())
+                    .or_default()
+                    .push((entry.clone(), record));
+            }
+        }
+    }
+    for (recording_id, records) in orphaned {
+        rebuild_metadata(dir, file_format, &recording_id, &records)?;
+        info!("Rebuilt metadata of recording '{}'", recording_id);
+        report.rebuilt_recordings.push(recording_id);
+    }
+    Ok(())
+}
+
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).context(format!("Failed to read {}", dir.display())),
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+/// Timestamp of a record file name (`{timestamp_us}.{format}`)
+fn record_timestamp(name: &str, file_format: &str) -> Option<u64> {
+    name.strip_suffix(file_format)?
+        .strip_suffix('.')?
+        .parse()
+        .ok()
+}
+
+fn is_entry_dir(dir: &Path, file_format: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.filter_map(|entry| entry.ok()).any(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        name == INDEX_FILE || record_timestamp(&name, file_format).is_some()
+    })
+}
+
+/// Check the records of an entry against its index; returns the intact ones
+fn repair_entry(
+    dir: &Path,
+    file_format: &str,
+    report: &mut RepairReport,
+) -> Result<Vec<FoundRecord>> {
+    report.entries += 1;
+    let index = record_index::read(dir)?;
+    report.torn_index_bytes += index.torn_bytes;
+    // The last frame of a timestamp wins (rewritten records)
+    let indexed: HashMap<u64, IndexEntry> = index
+        .entries
+        .iter()
+        .map(|entry| (entry.timestamp_us, *entry))
+        .collect();
+
+    let mut files: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let timestamp_us = record_timestamp(&entry.file_name().to_string_lossy(), file_format)?;
+            Some((timestamp_us, entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    let mut records = Vec::new();
+    let mut entries = Vec::new();
+    let mut changed = index.torn_bytes > 0;
+    for (timestamp_us, path) in files {
+        let data = std::fs::read(&path).context(format!("Failed to read {}", path.display()))?;
+        let intact = indexed
+            .get(&timestamp_us)
+            .is_some_and(|entry| entry.matches(&data));
+        let labels_path = dir.join(format!("{}.meta.json", timestamp_us));
+        let stored_labels: Option<HashMap<String, String>> = std::fs::read(&labels_path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok());
+
+        // Unindexed (or mismatched) files are kept only if they decode completely
+        let decoded = if intact && stored_labels.is_some() {
+            None
+        } else {
+            record_labels(&data)
+        };
+        if !intact && decoded.is_none() {
+            let torn_path = path.with_extension(format!("{}.torn", file_format));
+            warn!("Record {} is incomplete, moving it aside", path.display());
+            std::fs::rename(&path, &torn_path)
+                .context(format!("Failed to rename {}", path.display()))?;
+            report.torn_records += 1;
+            changed = true;
+            continue;
+        }
+        if !intact {
+            report.reindexed += 1;
+            changed = true;
+        }
+        report.valid_records += 1;
+
+        let labels = match (stored_labels, decoded) {
+            (Some(labels), _) => labels,
+            (None, Some(mut labels)) => {
+                labels.insert(CHECKSUM_LABEL.to_string(), checksum(&data));
+                let json =
+                    serde_json::to_string_pretty(&labels).context("Failed to serialize labels")?;
+                std::fs::write(&labels_path, json)
+                    .context(format!("Failed to write {}", labels_path.display()))?;
+                report.restored_labels += 1;
+                labels
+            }
+            (None, None) => HashMap::new(),
+        };
+
+        let entry = IndexEntry::for_record(timestamp_us, &data);
+        entries.push(entry);
+        records.push(FoundRecord {
+            timestamp_us,
+            bytes: data.len(),
+            crc32c: entry.crc32c,
+            labels,
+        });
+    }
+
+    let missing = indexed
+        .keys()
+        .filter(|ts| !entries.iter().any(|entry| entry.timestamp_us == **ts))
+        .count();
+    report.missing_records += missing;
+    if changed || missing > 0 || index.entries.len() != entries.len() {
+        record_index::rewrite(dir, &entries)?;
+    }
+    Ok(records)
+}
+
+/// Labels of a complete record, taken from its content
+fn record_labels(data: &[u8]) -> Option<HashMap<String, String>> {
+    let mut labels = HashMap::new();
+    if let Ok(batch) = parse_batch(data) {
+        if batch.header_count != batch.messages.len() {
+            return None;
+        }
+        labels.insert("recording_id".to_string(), batch.recording_id);
+        labels.insert("topic".to_string(), batch.topic);
+        labels.insert("format".to_string(), "mcap".to_string());
+        return Some(labels);
+    }
+    if let Ok(index) = coalesce::decode(data) {
+        labels.insert("recording_id".to_string(), index.recording_id);
+        labels.insert("format".to_string(), "coalesced".to_string());
+        labels.insert("batches".to_string(), index.parts.len().to_string());
+        return Some(labels);
+    }
+    // Metadata records are JSON
+    let metadata: RecordingMetadata = serde_json::from_slice(data).ok()?;
+    labels.insert("recording_id".to_string(), metadata.recording_id);
+    labels.insert("device_id".to_string(), metadata.device_id);
+    if let Some(scene) = metadata.scene {
+        labels.insert("scene".to_string(), scene);
+    }
+    Some(labels)
+}
+
+/// Write an interrupted-recording metadata record for `records`
+fn rebuild_metadata(
+    store: &Path,
+    file_format: &str,
+    recording_id: &str,
+    records: &[(String, &FoundRecord)],
+) -> Result<()> {
+    let to_rfc3339 = |us: u64| {
+        chrono::DateTime::from_timestamp_micros(us as i64)
+            .unwrap_or_default()
+            .to_rfc3339()
+    };
+    let mut topics: Vec<String> = records
+        .iter()
+        .filter_map(|(_, r)| r.labels.get("original_topic").or(r.labels.get("topic")))
+        .cloned()
+        .collect();
+
+    // Sample counts, payload and the codec come from the records themselves
+    let mut checksums: Vec<RecordChecksum> = Vec::new();
+    let mut total_samples = 0;
+    let mut payload_bytes = 0;
+    let mut compression_type = None;
+    let mut count_batch = |data: &[u8]| {
+        if let Ok(batch) = parse_batch(data) {
+            total_samples += batch.messages.len() as i64;
+            payload_bytes += batch
+                .messages
+                .iter()
+                .map(|m| m.payload.len() as u64)
+                .sum::<u64>();
+            compression_type.get_or_insert_with(|| format!("{:?}", batch.codec));
+        }
+    };
+    for (entry, record) in records {
+        let path = store
+            .join(entry)
+            .join(format!("{}.{}", record.timestamp_us, file_format));
+        let data = std::fs::read(&path).ok();
+        // Coalesced records list each of their batches
+        if entry == COALESCED_ENTRY {
+            let Some(data) = data else { continue };
+            let Ok(index) = coalesce::decode(&data) else {
+                continue;
+            };
+            for part in &index.parts {
+                topics.push(part.topic.clone());
+                checksums.push(coalesce::record_checksum(part, record.timestamp_us));
+                count_batch(coalesce::part_data(&data, part));
+            }
+            continue;
+        }
+        checksums.push(RecordChecksum {
+            topic: record.labels.get("topic").cloned().unwrap_or_default(),
+            entry: entry.clone(),
+            timestamp_us: record.timestamp_us,
+            bytes: record.bytes,
+            crc32c: format!("{:08x}", record.crc32c),
+            coalesced: None,
+            chunks: data
+                .as_deref()
+                .map(|data| chunk_checksums(data, CHECKSUM_CHUNK_BYTES)),
+        });
+        if let Some(data) = data {
+            count_batch(&data);
+        }
+    }
+    checksums.sort_by_key(|r| r.timestamp_us);
+    topics.sort();
+    topics.dedup();
+
+    let start_us = checksums.first().map_or(0, |r| r.timestamp_us);
+    let end_us = checksums.last().map_or(0, |r| r.timestamp_us);
+
+    let total_bytes: u64 = checksums.iter().map(|r| r.bytes as u64).sum();
+    let metadata = RecordingMetadata {
+        recording_id: recording_id.to_string(),
+        scene: None,
+        skills: vec![],
+        organization: None,
+        task_id: None,
+        device_id: "unknown".to_string(),
+        data_collector_id: None,
+        topics,
+        compression_type: compression_type.unwrap_or_else(|| "None".to_string()),
+        compression_level: CompressionLevel::Default as i32,
+        start_time: to_rfc3339(start_us),
+        end_time: Some(to_rfc3339(end_us)),
+        total_bytes: total_bytes as i64,
+        total_samples,
+        payload_bytes,
+        compression_ratio: compression_ratio(payload_bytes, total_bytes),
+        per_topic_stats: serde_json::json!({}),
+        interrupted: true,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: checksums,
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: BTreeMap::new(),
+        labels: HashMap::new(),
+    };
+
+    let data = serde_json::to_vec(&metadata).context("Failed to serialize metadata")?;
+    let mut labels = HashMap::new();
+    labels.insert("recording_id".to_string(), recording_id.to_string());
+    labels.insert("device_id".to_string(), metadata.device_id.clone());
+    labels.insert("interrupted".to_string(), "true".to_string());
+
+    let dir = store.join(METADATA_ENTRY);
+    std::fs::create_dir_all(&dir).context(format!("Failed to create {}", dir.display()))?;
+    // Keyed by start time like the recorder does, after any record already there
+    let mut timestamp_us = start_us;
+    while dir
+        .join(format!("{}.{}", timestamp_us, file_format))
+        .exists()
+    {
+        timestamp_us += 1;
+    }
+    let path = dir.join(format!("{}.{}", timestamp_us, file_format));
+    std::fs::write(&path, &data).context(format!("Failed to write {}", path.display()))?;
+    let labels_path = dir.join(format!("{}.meta.json", timestamp_us));
+    std::fs::write(
+        &labels_path,
+        serde_json::to_string_pretty(&labels).context("Failed to serialize labels")?,
+    )
+    .context(format!("Failed to write {}", labels_path.display()))?;
+    record_index::append_blocking(&dir, IndexEntry::for_record(timestamp_us, &data))
+}
@@ -0,0 +1,451 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Disk retention of the filesystem backend
+//
+// A segment is one stored record: `{entry}/{timestamp_us}.{format}` plus its
+// `{timestamp_us}.meta.json` labels, in the base directory or a bucket
+// subdirectory of it. A background pass deletes segments older than the
+// maximum age, then the oldest ones until the total fits the maximum usage.
+// Segments are ordered by their record timestamp, so the records of running
+// recordings are the last to go. Segments labeled `expires_at` are removed
+// separately, by the expiry sweep of the recorder (`remove_expired`).
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+use super::backend::is_expired;
+use crate::clock::{system_clock, Clock};
+use crate::config::RetentionConfig;
+use crate::protocol::TaskStage;
+use crate::runtime;
+use crate::task_registry::{TaskRegistry, PROCESS_SCOPE};
+
+/// Outcome of a retention pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPass {
+    /// Segments deleted
+    pub deleted: usize,
+    pub freed_bytes: u64,
+    /// Size of the segments left
+    pub usage_bytes: u64,
+}
+
+/// Enforces the retention limits of a filesystem backend
+pub struct RetentionManager {
+    root: PathBuf,
+    file_format: String,
+    config: RetentionConfig,
+    started: AtomicBool,
+    clock: Arc<dyn Clock>,
+    /// Registry the background pass runs in
+    tasks: RwLock<TaskRegistry>,
+}
+
+impl RetentionManager {
+    pub fn new<P: AsRef<Path>>(root: P, file_format: &str, config: RetentionConfig) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            file_format: file_format.to_string(),
+            config,
+            started: AtomicBool::new(false),
+            clock: system_clock(),
+            tasks: RwLock::new(TaskRegistry::new(PROCESS_SCOPE)),
+        }
+    }
+
+    /// Age the segments by `clock` instead of the system clock
+    #[allow(dead_code)]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Run the background pass as a task of `tasks` (once started)
+    pub fn set_tasks(&self, tasks: &TaskRegistry) {
+        *self.tasks.write().unwrap() = tasks.clone();
+    }
+
+    pub fn config(&self) -> &RetentionConfig {
+        &self.config
+    }
+
+    /// Start the background pass (once, and only when records are pruned)
+    ///
+    /// The task stops once the manager is dropped.
+    pub fn start(self: &Arc<Self>) {
+        if !self.config.prunes() || self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        info!(
+            "Retention enabled for {}: max usage {} bytes, max age {} hours",
+            self.root.display(),
+            self.config.max_usage_bytes,
+            self.config.max_age_hours
+        );
+
+        let manager = Arc::downgrade(self);
+        let tasks = self.tasks.read().unwrap().clone();
+        tasks.spawn_service(TaskStage::Background, "retention", async move {
+            while let Some(manager) = manager.upgrade() {
+                if let Err(e) = manager.enforce().await {
+                    warn!("Retention pass failed: {:#}", e);
+                }
+                let interval = manager.config.check_interval();
+                drop(manager);
+                runtime::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Delete expired segments, then the oldest ones until under the limit
+    pub async fn enforce(&self) -> Result<RetentionPass> {
+        let root = self.root.clone();
+        let file_format = self.file_format.clone();
+        let config = self.config.clone();
+        let now_us = self.clock.since_epoch().as_micros() as u64;
+        let pass = runtime::unblock(move || prune(&root, &file_format, &config, now_us)).await?;
+        if pass.deleted > 0 {
+            info!(
+                "Retention deleted {} record(s), freed {} bytes, {} bytes stored",
+                pass.deleted, pass.freed_bytes, pass.usage_bytes
+            );
+        }
+        Ok(pass)
+    }
+
+    /// Free space of the disk holding the records (`None` where unsupported)
+    pub async fn free_bytes(&self) -> Result<Option<u64>> {
+        let root = self.root.clone();
+        runtime::unblock(move || free_bytes(&root)).await
+    }
+}
+
+/// Stored record and its labels sidecar
+#[derive(Debug, Default)]
+struct Segment {
+    timestamp_us: u64,
+    bytes: u64,
+    files: Vec<PathBuf>,
+}
+
+fn prune(
+    root: &Path,
+    file_format: &str,
+    config: &RetentionConfig,
+    now_us: u64,
+) -> Result<RetentionPass> {
+    let mut segments = Vec::new();
+    match std::fs::read_dir(root) {
+        Ok(_) => collect_segments(root, file_format, &mut segments),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context(format!("Failed to read {}", root.display())),
+    }
+    segments.sort_by_key(|s| s.timestamp_us);
+
+    let expired_before = config
+        .max_age()
+        .map(|max_age| now_us.saturating_sub(max_age.as_micros() as u64));
+
+    let mut pass = RetentionPass {
+        usage_bytes: segments.iter().map(|s| s.bytes).sum(),
+        ..Default::default()
+    };
+    for segment in segments {
+        let expired = expired_before.is_some_and(|before| segment.timestamp_us < before);
+        let over_limit = config.max_usage_bytes > 0 && pass.usage_bytes > config.max_usage_bytes;
+        if !expired && !over_limit {
+            // Segments are oldest first, so the rest is kept as well
+            break;
+        }
+        if remove_segment(&segment) {
+            pass.deleted += 1;
+            pass.freed_bytes += segment.bytes;
+            pass.usage_bytes -= segment.bytes;
+        }
+    }
+    Ok(pass)
+}
+
+/// Delete the segments of the entries of `dir` whose labels expired at
+/// `now_secs`, returning how many were deleted
+///
+/// Only the entry directories right below `dir` are checked, so the
+/// subdirectories of buckets are left to their own backends. Consolidated
+/// files hold whole recordings and aren't expired record by record.
+pub fn remove_expired(dir: &Path, file_format: &str, now_secs: u64) -> Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context(format!("Failed to read {}", dir.display())),
+    };
+    let mut removed = 0;
+    for entry_dir in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let Ok(files) = std::fs::read_dir(&entry_dir) else {
+            continue;
+        };
+        for meta_path in files.filter_map(|file| file.ok()).map(|file| file.path()) {
+            let Some(timestamp) = meta_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".meta.json"))
+                .filter(|timestamp| timestamp.parse::<u64>().is_ok())
+            else {
+                continue;
+            };
+            let labels: HashMap<String, String> = match std::fs::read(&meta_path)
+                .ok()
+                .and_then(|content| serde_json::from_slice(&content).ok())
+            {
+                Some(labels) => labels,
+                None => continue,
+            };
+            if !is_expired(&labels, now_secs) {
+                continue;
+            }
+            let segment = Segment {
+                files: vec![
+                    entry_dir.join(format!("{}.{}", timestamp, file_format)),
+                    meta_path.clone(),
+                ],
+                ..Default::default()
+            };
+            if remove_segment(&segment) {
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Collect the segments below `dir`
+fn collect_segments(dir: &Path, file_format: &str, segments: &mut Vec<Segment>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let data_suffix = format!(".{}", file_format);
+    let mut found: HashMap<u64, Segment> = HashMap::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_segments(&path, file_format, segments);
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let timestamp = name
+            .strip_suffix(".meta.json")
+            .or_else(|| name.strip_suffix(data_suffix.as_str()));
+        let Some(Ok(timestamp_us)) = timestamp.map(str::parse::<u64>) else {
+            continue;
+        };
+        let segment = found.entry(timestamp_us).or_default();
+        segment.timestamp_us = timestamp_us;
+        segment.bytes += metadata.len();
+        segment.files.push(path);
+    }
+    segments.extend(found.into_values());
+}
+
+/// Delete the files of a segment; false if some could not be removed
+fn remove_segment(segment: &Segment) -> bool {
+    let mut removed = true;
+    for file in &segment.files {
+        match std::fs::remove_file(file) {
+            Ok(()) => debug!("Retention removed {}", file.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                warn!("Failed to remove {}: {}", file.display(), e);
+                removed = false;
+            }
+        }
+    }
+    removed
+}
+
+#[cfg(unix)]
+fn free_bytes(path: &Path) -> Result<Option<u64>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    // The base directory may not have been created yet
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(Path::new("/"));
+    let c_path =
+        CString::new(existing.as_os_str().as_bytes()).context("Path contains a NUL byte")?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context(format!("Failed to stat {}", existing.display()));
+    }
+    #[allow(clippy::useless_conversion)]
+    let free = u64::from(stat.f_bavail) * u64::from(stat.f_frsize);
+    Ok(Some(free))
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_path: &Path) -> Result<Option<u64>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    fn write_segment(dir: &Path, timestamp_us: u64, bytes: usize) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(format!("{}.mcap", timestamp_us)), vec![0u8; bytes]).unwrap();
+        std::fs::write(dir.join(format!("{}.meta.json", timestamp_us)), b"{}").unwrap();
+    }
+
+    fn now_us() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as u64
+    }
+
+    #[tokio::test]
+    async fn test_oldest_segments_deleted_over_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let now = now_us();
+        write_segment(&temp_dir.path().join("camera"), now - 3_000, 1000);
+        write_segment(&temp_dir.path().join("lidar"), now - 2_000, 1000);
+        // Bucket subdirectories count as well
+        write_segment(
+            &temp_dir.path().join("customer_a/camera"),
+            now - 1_000,
+            1000,
+        );
+
+        let config = RetentionConfig {
+            max_usage_bytes: 1500,
+            ..Default::default()
+        };
+        let manager = RetentionManager::new(temp_dir.path(), "mcap", config);
+        let pass = manager.enforce().await.unwrap();
+
+        assert_eq!(pass.deleted, 2);
+        assert_eq!(pass.usage_bytes, 1002);
+        assert!(!temp_dir
+            .path()
+            .join(format!("camera/{}.mcap", now - 3_000))
+            .exists());
+        assert!(!temp_dir
+            .path()
+            .join(format!("camera/{}.meta.json", now - 3_000))
+            .exists());
+        assert!(!temp_dir
+            .path()
+            .join(format!("lidar/{}.mcap", now - 2_000))
+            .exists());
+        assert!(temp_dir
+            .path()
+            .join(format!("customer_a/camera/{}.mcap", now - 1_000))
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn test_expired_segments_deleted() {
+        let temp_dir = TempDir::new().unwrap();
+        let now = 1_700_000_000_000_000;
+        let two_hours = 2 * 3600 * 1_000_000;
+        write_segment(&temp_dir.path().join("camera"), now - two_hours, 10);
+        write_segment(&temp_dir.path().join("camera"), now, 10);
+
+        let config = RetentionConfig {
+            max_age_hours: 1,
+            ..Default::default()
+        };
+        let clock = Arc::new(MockClock::new(UNIX_EPOCH + Duration::from_micros(now)));
+        let manager =
+            RetentionManager::new(temp_dir.path(), "mcap", config).with_clock(clock.clone());
+        assert_eq!(manager.enforce().await.unwrap().deleted, 1);
+        assert!(!temp_dir
+            .path()
+            .join(format!("camera/{}.mcap", now - two_hours))
+            .exists());
+        assert!(temp_dir
+            .path()
+            .join(format!("camera/{}.mcap", now))
+            .exists());
+
+        // Nothing left to do until the last segment is an hour old
+        clock.advance(Duration::from_secs(3599));
+        assert_eq!(manager.enforce().await.unwrap().deleted, 0);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(manager.enforce().await.unwrap().deleted, 1);
+    }
+
+    #[test]
+    fn test_expired_segments_removed_by_label() {
+        let temp_dir = TempDir::new().unwrap();
+        let camera = temp_dir.path().join("camera");
+        write_segment(&camera, 1, 10);
+        write_segment(&camera, 2, 10);
+        write_segment(&camera, 3, 10);
+        std::fs::write(camera.join("1.meta.json"), br#"{"expires_at": "100"}"#).unwrap();
+        std::fs::write(camera.join("2.meta.json"), br#"{"expires_at": "200"}"#).unwrap();
+        // Buckets are swept by their own backends
+        let bucket = temp_dir.path().join("customer_a/camera");
+        write_segment(&bucket, 4, 10);
+        std::fs::write(bucket.join("4.meta.json"), br#"{"expires_at": "100"}"#).unwrap();
+
+        assert_eq!(remove_expired(temp_dir.path(), "mcap", 150).unwrap(), 1);
+        assert!(!camera.join("1.mcap").exists());
+        assert!(!camera.join("1.meta.json").exists());
+        assert!(camera.join("2.mcap").exists());
+        assert!(camera.join("3.mcap").exists());
+        assert!(bucket.join("4.mcap").exists());
+
+        assert_eq!(
+            remove_expired(&temp_dir.path().join("customer_a"), "mcap", 150).unwrap(),
+            1
+        );
+        assert_eq!(remove_expired(temp_dir.path(), "mcap", 200).unwrap(), 1);
+        assert_eq!(
+            remove_expired(&temp_dir.path().join("missing"), "mcap", 200).unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_root_and_free_space() {
+        let manager = RetentionManager::new(
+            "/nonexistent/zenoh-recorder-retention",
+            "mcap",
+            RetentionConfig::default(),
+        );
+        assert_eq!(manager.enforce().await.unwrap(), RetentionPass::default());
+        #[cfg(unix)]
+        assert!(manager.free_bytes().await.unwrap().unwrap() > 0);
+    }
+}
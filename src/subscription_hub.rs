@@ -0,0 +1,298 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Zenoh subscriptions shared between recordings
+//
+// Recordings of the same key expression share one Zenoh subscriber. Its
+// samples are fanned out to one FIFO channel per recording (samples are
+// refcounted, so fanning out doesn't copy payloads). The subscriber is
+// undeclared when the last recording drops its `SampleSink`. After a
+// reconnect, the subscribers are declared again (on a new session, if the old
+// one was replaced) without the recordings noticing. Each subscriber keeps the
+// last sample it received, which snapshots take instead of waiting for the next.
+//
+// Key expressions under `@liveliness/` subscribe to the liveliness tokens of
+// the rest of the key expression: a declared token arrives as a put sample on
+// its key, an undeclared one as a delete. The tokens already alive come
+// first, also to recordings joining a shared subscriber later.
+
+use crate::clock::{system_clock, Clock};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+use zenoh::handlers::{Callback, FifoChannel, FifoChannelHandler, IntoHandler};
+use zenoh::pubsub::Subscriber;
+use zenoh::sample::{Sample, SampleKind};
+use zenoh::{Session, Wait};
+
+type Sinks = Arc<RwLock<HashMap<u64, Callback<Sample>>>>;
+
+/// Last sample of a subscriber and its receive time (ns since the epoch)
+type Latest = Arc<Mutex<Option<(Sample, u64)>>>;
+
+/// Alive liveliness tokens of a liveliness subscriber, by key
+type Tokens = Arc<Mutex<BTreeMap<String, Sample>>>;
+
+/// Samples a sink holds before the subscriber blocks
+const FIFO_CAPACITY: usize = 256;
+
+/// Prefix of the topics recording liveliness tokens
+pub const LIVELINESS_PREFIX: &str = "@liveliness/";
+
+/// Key expression of the tokens a liveliness topic records, None for other
+/// topics
+pub fn liveliness_key(topic: &str) -> Option<&str> {
+    topic.strip_prefix(LIVELINESS_PREFIX)
+}
+
+/// One Zenoh subscriber and the recordings it feeds
+struct Shared {
+    /// None after a failed redeclaration
+    _subscriber: Option<Subscriber<()>>,
+    sinks: Sinks,
+    latest: Latest,
+    tokens: Tokens,
+}
+
+/// Declares one Zenoh subscriber per key expression for all recordings
+pub struct SubscriptionHub {
+    session: RwLock<Arc<Session>>,
+    subscriptions: Arc<Mutex<HashMap<String, Shared>>>,
+    next_id: AtomicU64,
+    /// Stamps the receive time of the samples
+    clock: Arc<dyn Clock>,
+}
+
+impl SubscriptionHub {
+    pub fn new(session: Arc<Session>) -> Self {
+        Self {
+            session: RwLock::new(session),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(0),
+            clock: system_clock(),
+        }
+    }
+
+    /// Stamp the receive time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Receive the samples of `key_expr` until the returned sink is dropped
+    ///
+    /// Declares the Zenoh subscriber of `key_expr` unless another sink
+    /// already holds it.
+    pub fn subscribe(&self, key_expr: &str) -> Result<SampleSink> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let receiver = match subscriptions.get(key_expr) {
+            Some(shared) => {
+                // Held until the sink is added, so no token change is missed
+                let tokens = shared.tokens.lock().unwrap();
+                let (callback, receiver) =
+                    FifoChannel::new(FIFO_CAPACITY + tokens.len()).into_handler();
+                for sample in tokens.values() {
+                    callback.call(sample.clone());
+                }
+                shared.sinks.write().unwrap().insert(id, callback);
+                receiver
+            }
+            None => {
+                let (callback, receiver) = FifoChannel::new(FIFO_CAPACITY).into_handler();
+                let sinks: Sinks = Arc::new(RwLock::new(HashMap::from([(id, callback)])));
+                let latest = Latest::default();
+                let tokens = Tokens::default();
+                let session = self.session.read().unwrap().clone();
+                let subscriber = declare(
+                    &session,
+                    key_expr,
+                    sinks.clone(),
+                    latest.clone(),
+                    tokens.clone(),
+                    self.clock.clone(),
+                )?;
+                debug!("Declared shared subscriber of '{}'", key_expr);
+                subscriptions.insert(
+                    key_expr.to_string(),
+                    Shared {
+                        _subscriber: Some(subscriber),
+                        sinks,
+                        latest,
+                        tokens,
+                    },
+                );
+                receiver
+            }
+        };
+
+        Ok(SampleSink {
+            receiver,
+            key_expr: key_expr.to_string(),
+            id,
+            subscriptions: self.subscriptions.clone(),
+        })
+    }
+
+    /// Declare every subscriber again on `session`
+    ///
+    /// Returns how many were declared. The old subscriber is undeclared first,
+    /// so samples aren't delivered twice; one that fails to declare is retried
+    /// on the next call.
+    pub fn redeclare(&self, session: Arc<Session>) -> usize {
+        *self.session.write().unwrap() = session.clone();
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut declared = 0;
+        for (key_expr, shared) in subscriptions.iter_mut() {
+            drop(shared._subscriber.take());
+            match declare(
+                &session,
+                key_expr,
+                shared.sinks.clone(),
+                shared.latest.clone(),
+                shared.tokens.clone(),
+                self.clock.clone(),
+            ) {
+                Ok(subscriber) => {
+                    shared._subscriber = Some(subscriber);
+                    declared += 1;
+                }
+                Err(e) => warn!(
+                    "Failed to declare the subscriber of '{}' again: {}",
+                    key_expr, e
+                ),
+            }
+        }
+        declared
+    }
+
+    /// Last sample received by the subscriber of `key_expr` and its receive
+    /// time (ns since the epoch); None without a subscriber or before its
+    /// first sample
+    pub fn latest(&self, key_expr: &str) -> Option<(Sample, u64)> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(key_expr)
+            .and_then(|shared| shared.latest.lock().unwrap().clone())
+    }
+
+    /// Receive time of a sample arriving now (ns since the epoch)
+    pub fn now_ns(&self) -> u64 {
+        self.clock.since_epoch().as_nanos() as u64
+    }
+
+    /// Number of declared Zenoh subscribers
+    #[allow(dead_code)]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriptions.lock().unwrap().len()
+    }
+
+    /// Number of sinks fed by the subscriber of `key_expr`
+    #[allow(dead_code)]
+    pub fn sink_count(&self, key_expr: &str) -> usize {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(key_expr)
+            .map_or(0, |shared| shared.sinks.read().unwrap().len())
+    }
+}
+
+/// Declare a subscriber feeding `sinks` and keeping its last sample in
+/// `latest` (and the alive tokens in `tokens`, for liveliness topics), its
+/// receive time read from `clock`
+fn declare(
+    session: &Session,
+    key_expr: &str,
+    sinks: Sinks,
+    latest: Latest,
+    tokens: Tokens,
+    clock: Arc<dyn Clock>,
+) -> Result<Subscriber<()>> {
+    let liveliness = liveliness_key(key_expr).is_some();
+    let callback = move |sample: Sample| {
+        let received_ns = clock.since_epoch().as_nanos() as u64;
+        *latest.lock().unwrap() = Some((sample.clone(), received_ns));
+        // Don't hold the locks while a full channel blocks
+        let sinks: Vec<_> = {
+            let mut tokens = tokens.lock().unwrap();
+            if liveliness {
+                let key = sample.key_expr().to_string();
+                match sample.kind() {
+                    SampleKind::Put => tokens.insert(key, sample.clone()),
+                    SampleKind::Delete => tokens.remove(&key),
+                };
+            }
+            sinks.read().unwrap().values().cloned().collect()
+        };
+        for sink in sinks {
+            sink.call(sample.clone());
+        }
+    };
+    let subscriber = match liveliness_key(key_expr) {
+        Some(tokens_key) => session
+            .liveliness()
+            .declare_subscriber(tokens_key)
+            .history(true)
+            .callback(callback)
+            .wait(),
+        None => session
+            .declare_subscriber(key_expr)
+            .callback(callback)
+            .wait(),
+    };
+    subscriber.map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Samples of one key expression for one recording
+pub struct SampleSink {
+    receiver: FifoChannelHandler<Sample>,
+    key_expr: String,
+    id: u64,
+    subscriptions: Arc<Mutex<HashMap<String, Shared>>>,
+}
+
+impl SampleSink {
+    /// Wait for the next sample
+    pub async fn recv_async(&self) -> zenoh::Result<Sample> {
+        self.receiver.recv_async().await
+    }
+}
+
+impl Drop for SampleSink {
+    fn drop(&mut self) {
+        let unused = {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            let empty = subscriptions.get(&self.key_expr).is_some_and(|shared| {
+                let mut sinks = shared.sinks.write().unwrap();
+                sinks.remove(&self.id);
+                sinks.is_empty()
+            });
+            if empty {
+                subscriptions.remove(&self.key_expr)
+            } else {
+                None
+            }
+        };
+        // The last sink undeclares the subscriber, outside the lock
+        if let Some(shared) = unused {
+            drop(shared);
+            debug!("Undeclared shared subscriber of '{}'", self.key_expr);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Live tap of recorded topics (`recorder.tap`)
+//
+// While a recording runs, at most one sample per `interval_ms` of each topic
+// in `per_topic` is handed to the recording's tap receivers
+// (`RecorderManager::tap`) and, with `publish`, re-published on
+// `{key_prefix}/{recording_id}/{key}`, so operators can watch what is being
+// recorded without a second full-rate subscriber. Tapped samples never hold up
+// the recording: a receiver that falls behind misses the oldest samples, and
+// publications are dropped under congestion. The tap closes, ending the
+// receivers' streams, once the recording stops receiving samples.
+
+use futures_util::Stream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use zenoh::qos::CongestionControl;
+use zenoh::sample::Sample;
+use zenoh::{Session, Wait};
+
+/// Sample copied from a recorded topic
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct TapSample {
+    pub recording_id: String,
+    /// Requested topic the sample was received on (may be a pattern)
+    pub topic: String,
+    pub sample: Sample,
+}
+
+/// Publisher of tapped samples, shared by the recordings
+pub struct TapPublisher {
+    session: RwLock<Arc<Session>>,
+    key_prefix: String,
+}
+
+impl TapPublisher {
+    pub fn new(session: Arc<Session>, key_prefix: String) -> Self {
+        Self {
+            session: RwLock::new(session),
+            key_prefix,
+        }
+    }
+
+    /// Publish on `session` from now on (after the old one was replaced)
+    pub fn set_session(&self, session: Arc<Session>) {
+        *self.session.write().unwrap() = session;
+    }
+
+    /// Key expression a tapped sample of `key_expr` is published on
+    pub fn key(&self, recording_id: &str, key_expr: &str) -> String {
+        format!("{}/{}/{}", self.key_prefix, recording_id, key_expr)
+    }
+
+    fn put(&self, recording_id: &str, sample: &Sample) {
+        let key = self.key(recording_id, sample.key_expr().as_str());
+        let session = self.session.read().unwrap().clone();
+        let result = session
+            .put(&key, sample.payload().clone())
+            .encoding(sample.encoding().clone())
+            .congestion_control(CongestionControl::Drop)
+            .wait();
+        if let Err(e) = result {
+            warn!("Failed to publish tapped sample on '{}': {}", key, e);
+        }
+    }
+}
+
+/// Tap of one recording, fed by its tapped topics
+pub struct RecordingTap {
+    recording_id: String,
+    /// None once the tap is closed
+    sender: Mutex<Option<broadcast::Sender<TapSample>>>,
+    publisher: Option<Arc<TapPublisher>>,
+}
+
+impl RecordingTap {
+    pub fn new(recording_id: &str, capacity: usize, publisher: Option<Arc<TapPublisher>>) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            recording_id: recording_id.to_string(),
+            sender: Mutex::new(Some(sender)),
+            publisher,
+        }
+    }
+
+    /// Receiver of the samples tapped from now on (None once closed)
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<TapSample>> {
+        self.sender.lock().unwrap().as_ref().map(|s| s.subscribe())
+    }
+
+    /// Stop tapping; the receivers end after the samples they hold
+    pub fn close(&self) {
+        self.sender.lock().unwrap().take();
+    }
+
+    fn send(&self, topic: &str, sample: &Sample) {
+        if let Some(publisher) = &self.publisher {
+            publisher.put(&self.recording_id, sample);
+        }
+        let sender = self.sender.lock().unwrap();
+        if let Some(sender) = sender.as_ref().filter(|s| s.receiver_count() > 0) {
+            let _ = sender.send(TapSample {
+                recording_id: self.recording_id.clone(),
+                topic: topic.to_string(),
+                sample: sample.clone(),
+            });
+        }
+    }
+}
+
+/// Decimated tap of one requested topic
+pub struct TopicTap {
+    tap: Arc<RecordingTap>,
+    topic: String,
+    interval_ns: u64,
+    /// Receive time of the last sample tapped, in ns (0 = none yet)
+    last_taken_ns: AtomicU64,
+}
+
+impl TopicTap {
+    pub fn new(tap: Arc<RecordingTap>, topic: &str, interval: Duration) -> Self {
+        Self {
+            tap,
+            topic: topic.to_string(),
+            interval_ns: interval.as_nanos() as u64,
+            last_taken_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether a sample received at `received_ns` is tapped
+    pub fn admit(&self, received_ns: u64) -> bool {
+        let last = self.last_taken_ns.load(Ordering::Relaxed);
+        if last != 0 && received_ns.saturating_sub(last) < self.interval_ns {
+            return false;
+        }
+        self.last_taken_ns
+            .compare_exchange(last, received_ns, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Tap `sample` if its interval is due
+    pub fn offer(&self, sample: &Sample) {
+        let now_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        if self.admit(now_ns) {
+            self.tap.send(&self.topic, sample);
+        }
+    }
+}
+
+/// Stream of the samples of `receiver`, skipping the ones it missed
+pub fn stream(receiver: broadcast::Receiver<TapSample>) -> impl Stream<Item = TapSample> {
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(sample) => return Some((sample, receiver)),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("Tap receiver missed {} samples", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit_one_per_interval() {
+        let tap = Arc::new(RecordingTap::new("rec", 4, None));
+        let topic_tap = TopicTap::new(tap, "camera/front", Duration::from_millis(100));
+        let ms = 1_000_000;
+
+        assert!(topic_tap.admit(1000 * ms));
+        assert!(!topic_tap.admit(1050 * ms));
+        assert!(topic_tap.admit(1100 * ms));
+        assert!(topic_tap.admit(1500 * ms));
+    }
+
+    #[test]
+    fn test_closed_tap_has_no_receivers() {
+        let tap = RecordingTap::new("rec", 4, None);
+        assert!(tap.subscribe().is_some());
+        tap.close();
+        assert!(tap.subscribe().is_none());
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Registry of the background tasks
+//
+// Every task the recorder spawns goes through a `TaskRegistry`: the process
+// registry of the `RecorderManager` (flush workers, control handlers, ...) or
+// the registry of a recording (its subscribers), a child sharing the process
+// registry's panic log. A registered task can be listed, aborted, and waited
+// for on shutdown, which stops the stages in order (`TaskStage::ALL`): jobs
+// of a stage get up to the shutdown timeout to end, then whatever is left of
+// the stage, services included, is aborted. Tasks run on `runtime::spawn`
+// wrapped in an abort handle and `catch_unwind`, so this works on every
+// supported executor. A panicking task is logged, kept in the panic log and
+// published as a status event.
+
+use futures_util::future::{AbortHandle, Abortable};
+use futures_util::FutureExt;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+use crate::protocol::{TaskInfo, TaskKind, TaskPanic, TaskStage};
+use crate::runtime;
+use crate::status_events::StatusEventPublisher;
+
+/// Scope of the process-wide registry
+pub const PROCESS_SCOPE: &str = "process";
+
+/// Panics kept for the `tasks` command
+const MAX_PANICS: usize = 32;
+
+struct Entry {
+    name: String,
+    stage: TaskStage,
+    kind: TaskKind,
+    started_at: String,
+    started: Instant,
+    abort: AbortHandle,
+}
+
+/// State shared by a registry and its children
+#[derive(Default)]
+struct Shared {
+    panics: Mutex<VecDeque<TaskPanic>>,
+    events: Option<Arc<StatusEventPublisher>>,
+}
+
+struct Inner {
+    scope: String,
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, Entry>>,
+    /// Woken whenever a task ends
+    changed: Notify,
+    shared: Arc<Shared>,
+}
+
+/// Tracks the tasks of the process or of one recording
+#[derive(Clone)]
+pub struct TaskRegistry {
+    inner: Arc<Inner>,
+}
+
+impl TaskRegistry {
+    pub fn new(scope: &str) -> Self {
+        Self::with_shared(scope, Arc::default())
+    }
+
+    fn with_shared(scope: &str, shared: Arc<Shared>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                scope: scope.to_string(),
+                next_id: AtomicU64::new(0),
+                tasks: Mutex::new(HashMap::new()),
+                changed: Notify::new(),
+                shared,
+            }),
+        }
+    }
+
+    /// Registry publishing the panics of its tasks (and its children's) as
+    /// status events
+    pub fn with_events(scope: &str, events: Option<Arc<StatusEventPublisher>>) -> Self {
+        Self::with_shared(
+            scope,
+            Arc::new(Shared {
+                panics: Mutex::default(),
+                events,
+            }),
+        )
+    }
+
+    /// Registry of `scope` sharing this registry's panic log and events
+    pub fn child(&self, scope: &str) -> Self {
+        Self::with_shared(scope, self.inner.shared.clone())
+    }
+
+    /// Spawn a task that ends once its work is done
+    pub fn spawn<F>(&self, stage: TaskStage, name: impl Into<String>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.register(stage, TaskKind::Job, name.into(), future);
+    }
+
+    /// Spawn a task that runs until it's aborted
+    pub fn spawn_service<F>(&self, stage: TaskStage, name: impl Into<String>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.register(stage, TaskKind::Service, name.into(), future);
+    }
+
+    fn register<F>(&self, stage: TaskStage, kind: TaskKind, name: String, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        // Listed before it runs, so it can't end before it's registered
+        self.inner.tasks.lock().unwrap().insert(
+            id,
+            Entry {
+                name,
+                stage,
+                kind,
+                started_at: chrono::Utc::now().to_rfc3339(),
+                started: Instant::now(),
+                abort,
+            },
+        );
+
+        let inner = self.inner.clone();
+        runtime::spawn(async move {
+            let result =
+                Abortable::new(AssertUnwindSafe(future).catch_unwind(), registration).await;
+            let entry = inner.tasks.lock().unwrap().remove(&id);
+            if let (Ok(Err(panic)), Some(entry)) = (result, entry) {
+                inner.panicked(&entry, panic.as_ref());
+            }
+            inner.changed.notify_waiters();
+        });
+    }
+
+    /// Live tasks, by stage and start time
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let tasks = self.inner.tasks.lock().unwrap();
+        let mut list: Vec<(Instant, TaskInfo)> = tasks
+            .values()
+            .map(|entry| {
+                (
+                    entry.started,
+                    TaskInfo {
+                        scope: self.inner.scope.clone(),
+                        name: entry.name.clone(),
+                        stage: entry.stage,
+                        kind: entry.kind,
+                        started_at: entry.started_at.clone(),
+                        running_ms: entry.started.elapsed().as_millis() as u64,
+                    },
+                )
+            })
+            .collect();
+        list.sort_by_key(|(started, info)| (info.stage, *started));
+        list.into_iter().map(|(_, info)| info).collect()
+    }
+
+    /// Panics of this registry and its relatives, most recent first
+    pub fn panics(&self) -> Vec<TaskPanic> {
+        let panics = self.inner.shared.panics.lock().unwrap();
+        panics.iter().rev().cloned().collect()
+    }
+
+    /// Abort every task; returns how many were live
+    pub fn abort_all(&self) -> usize {
+        let tasks = self.inner.tasks.lock().unwrap();
+        for entry in tasks.values() {
+            entry.abort.abort();
+        }
+        tasks.len()
+    }
+
+    /// Stop the tasks stage by stage: wait up to `timeout` for the jobs of a
+    /// stage, then abort what's left of it and wait for it to go before the
+    /// next stage. Returns the number of aborted tasks.
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        let mut aborted = 0;
+        for stage in TaskStage::ALL {
+            self.wait_for(timeout, |entry| {
+                entry.stage == stage && entry.kind == TaskKind::Job
+            })
+            .await;
+            aborted += self.abort_stage(stage);
+            // Aborted tasks end on their next poll
+            self.wait_for(timeout, |entry| entry.stage == stage).await;
+        }
+        aborted
+    }
+
+    /// Wait up to `timeout` until no live task matches `matches`
+    async fn wait_for(&self, timeout: Duration, matches: impl Fn(&Entry) -> bool) {
+        let deadline = Instant::now() + timeout;
+        loop {
+            // Created before the check so an end in between isn't missed
+            let changed = self.inner.changed.notified();
+            let left = deadline.saturating_duration_since(Instant::now());
+            let live = self.inner.tasks.lock().unwrap().values().any(&matches);
+            if !live || left.is_zero() {
+                return;
+            }
+            tokio::select! {
+                _ = changed => {}
+                _ = runtime::sleep(left) => {}
+            }
+        }
+    }
+
+    fn abort_stage(&self, stage: TaskStage) -> usize {
+        let tasks = self.inner.tasks.lock().unwrap();
+        let mut aborted = 0;
+        for entry in tasks.values().filter(|entry| entry.stage == stage) {
+            if entry.kind == TaskKind::Job {
+                warn!(
+                    "Aborting task '{}' of {} still running at shutdown",
+                    entry.name, self.inner.scope
+                );
+            }
+            entry.abort.abort();
+            aborted += 1;
+        }
+        aborted
+    }
+}
+
+impl Inner {
+    fn panicked(&self, entry: &Entry, panic: &(dyn Any + Send)) {
+        let panic = TaskPanic {
+            scope: self.scope.clone(),
+            name: entry.name.clone(),
+            stage: entry.stage,
+            message: panic_message(panic),
+            at: chrono::Utc::now().to_rfc3339(),
+        };
+        error!(
+            "Task '{}' of {} panicked: {}",
+            panic.name, panic.scope, panic.message
+        );
+        if let Some(events) = &self.shared.events {
+            events.publish_task_panic(&panic);
+        }
+        let mut panics = self.shared.panics.lock().unwrap();
+        if panics.len() == MAX_PANICS {
+            panics.pop_front();
+        }
+        panics.push_back(panic);
+    }
+}
+
+/// Message a task panicked with
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
@@ -0,0 +1,180 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Rolling throughput of a recording
+//
+// Byte counters in one-second buckets over the last `WINDOW_SECS` seconds:
+// payload bytes received, payload bytes written to the backend and their size
+// as stored. Ingest and upload rates and the effective compression ratio are
+// derived from the window; the backlog is the payload received but not yet
+// written (buffered or waiting for a flush worker).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::protocol::ThroughputStats;
+
+/// Length of the rolling window
+pub const WINDOW_SECS: usize = 30;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Bucket {
+    /// Second since the meter was created this bucket counts
+    second: u64,
+    ingest_bytes: u64,
+    uploaded_bytes: u64,
+    stored_bytes: u64,
+}
+
+/// Rolling-window throughput meter of a recording
+pub struct ThroughputMeter {
+    started: Instant,
+    buckets: Mutex<[Bucket; WINDOW_SECS]>,
+    /// Payload bytes accepted by the topic buffers and not settled yet
+    pending: Arc<AtomicU64>,
+}
+
+impl Default for ThroughputMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThroughputMeter {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            buckets: Mutex::new([Bucket::default(); WINDOW_SECS]),
+            pending: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Counter the topic buffers add accepted payload bytes to
+    pub fn pending_counter(&self) -> Arc<AtomicU64> {
+        self.pending.clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Bucket)) {
+        let second = self.started.elapsed().as_secs();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = &mut buckets[second as usize % WINDOW_SECS];
+        if bucket.second != second {
+            *bucket = Bucket {
+                second,
+                ..Default::default()
+            };
+        }
+        f(bucket);
+    }
+
+    /// Payload bytes received from a subscriber
+    pub fn record_ingest(&self, bytes: usize) {
+        self.update(|bucket| bucket.ingest_bytes += bytes as u64);
+    }
+
+    /// A record with `raw_bytes` of payload was written as `stored_bytes`
+    pub fn record_upload(&self, raw_bytes: usize, stored_bytes: usize) {
+        self.update(|bucket| {
+            bucket.uploaded_bytes += raw_bytes as u64;
+            bucket.stored_bytes += stored_bytes as u64;
+        });
+    }
+
+    /// Payload bytes accepted and not settled yet
+    pub fn backlog_bytes(&self) -> u64 {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Payload bytes left the backlog (written, failed or dropped)
+    pub fn settle(&self, raw_bytes: usize) {
+        let _ = self
+            .pending
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+                Some(pending.saturating_sub(raw_bytes as u64))
+            });
+    }
+
+    /// Rates over the window (shorter right after the start)
+    pub fn stats(&self) -> ThroughputStats {
+        let elapsed = self.started.elapsed();
+        let now = elapsed.as_secs();
+        let window_seconds = elapsed.as_secs_f64().clamp(1.0, WINDOW_SECS as f64);
+
+        let (mut ingest, mut uploaded, mut stored) = (0, 0, 0);
+        for bucket in self.buckets.lock().unwrap().iter() {
+            if now - bucket.second < WINDOW_SECS as u64 {
+                ingest += bucket.ingest_bytes;
+                uploaded += bucket.uploaded_bytes;
+                stored += bucket.stored_bytes;
+            }
+        }
+
+        let upload_rate = uploaded as f64 / window_seconds;
+        let backlog_bytes = self.backlog_bytes();
+        ThroughputStats {
+            window_seconds,
+            ingest_bytes_per_sec: ingest as f64 / window_seconds,
+            upload_bytes_per_sec: upload_rate,
+            stored_bytes_per_sec: stored as f64 / window_seconds,
+            compression_ratio: (stored > 0).then(|| uploaded as f64 / stored as f64),
+            backlog_bytes,
+            backlog_seconds: if backlog_bytes == 0 {
+                Some(0.0)
+            } else {
+                (upload_rate > 0.0).then(|| backlog_bytes as f64 / upload_rate)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_and_ratio() {
+        let meter = ThroughputMeter::new();
+        meter.record_ingest(3000);
+        meter.pending_counter().fetch_add(3000, Ordering::Relaxed);
+        meter.record_upload(2000, 500);
+        meter.settle(2000);
+
+        // Less than a second elapsed: the window counts as one second
+        let stats = meter.stats();
+        assert_eq!(stats.window_seconds, 1.0);
+        assert_eq!(stats.ingest_bytes_per_sec, 3000.0);
+        assert_eq!(stats.upload_bytes_per_sec, 2000.0);
+        assert_eq!(stats.stored_bytes_per_sec, 500.0);
+        assert_eq!(stats.compression_ratio, Some(4.0));
+        assert_eq!(stats.backlog_bytes, 1000);
+        assert_eq!(stats.backlog_seconds, Some(0.5));
+    }
+
+    #[test]
+    fn test_idle_and_stalled() {
+        let meter = ThroughputMeter::new();
+        let stats = meter.stats();
+        assert_eq!(stats.compression_ratio, None);
+        assert_eq!(stats.backlog_seconds, Some(0.0));
+
+        // Data waiting but nothing written in the window
+        meter.pending_counter().fetch_add(100, Ordering::Relaxed);
+        assert_eq!(meter.stats().backlog_seconds, None);
+
+        // Settling never goes below zero
+        meter.settle(1000);
+        assert_eq!(meter.stats().backlog_bytes, 0);
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Per-topic statistics kept across recordings
+//
+// Every finished recording folds its per-topic totals into rolling averages
+// (sample rate, sample size, compression ratio) stored in a small JSON file.
+// The recorder uses them to pre-size topic buffers, to store incompressible
+// topics uncompressed and to answer `estimate` (dry-run) requests.
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::runtime::fs;
+
+/// Upper bound of the pre-allocated sample slots of a topic buffer
+const MAX_BUFFER_CAPACITY: usize = 16 * 1024;
+
+/// Learned characteristics of a topic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicProfile {
+    /// Number of recordings folded into the averages
+    pub recordings: u64,
+    pub samples_per_sec: f64,
+    pub avg_sample_bytes: f64,
+    /// Stored / raw bytes of compressed segments (unknown until one was written)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    pub updated_at: String,
+}
+
+/// Per-topic totals of one recording
+#[derive(Debug, Default)]
+pub struct TopicTotals {
+    pub samples: AtomicU64,
+    pub raw_bytes: AtomicU64,
+    pub stored_bytes: AtomicU64,
+    /// Raw bytes of the segments written with compression
+    pub compressed_raw_bytes: AtomicU64,
+    /// Stored bytes of the segments written with compression
+    pub compressed_stored_bytes: AtomicU64,
+}
+
+impl TopicTotals {
+    /// Account a segment written to the backend
+    pub fn record(&self, samples: usize, raw_bytes: usize, stored_bytes: usize, compressed: bool) {
+        self.samples.fetch_add(samples as u64, Ordering::Relaxed);
+        self.raw_bytes
+            .fetch_add(raw_bytes as u64, Ordering::Relaxed);
+        self.stored_bytes
+            .fetch_add(stored_bytes as u64, Ordering::Relaxed);
+        if compressed {
+            self.compressed_raw_bytes
+                .fetch_add(raw_bytes as u64, Ordering::Relaxed);
+            self.compressed_stored_bytes
+                .fetch_add(stored_bytes as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// JSON-file backed store of topic profiles
+pub struct TopicStatsStore {
+    path: PathBuf,
+    /// Weight of the latest recording in the rolling averages
+    smoothing: f64,
+    incompressible_ratio: f64,
+    profiles: DashMap<String, TopicProfile>,
+}
+
+impl TopicStatsStore {
+    pub fn new<P: AsRef<Path>>(path: P, smoothing: f64, incompressible_ratio: f64) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            smoothing,
+            incompressible_ratio,
+            profiles: DashMap::new(),
+        }
+    }
+
+    /// Load the stored profiles (none if the file doesn't exist yet)
+    ///
+    /// Returns the number of topics loaded.
+    pub fn load(&self) -> Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+
+        let content = std::fs::read(&self.path).context(format!(
+            "Failed to read topic stats: {}",
+            self.path.display()
+        ))?;
+        let stored: BTreeMap<String, TopicProfile> = serde_json::from_slice(&content).context(
+            format!("Failed to parse topic stats: {}", self.path.display()),
+        )?;
+        let count = stored.len();
+        for (topic, profile) in stored {
+            self.profiles.insert(topic, profile);
+        }
+        Ok(count)
+    }
+
+    pub fn profile(&self, topic: &str) -> Option<TopicProfile> {
+        self.profiles.get(topic).map(|p| p.value().clone())
+    }
+
+    /// Topics with a profile, sorted
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self.profiles.iter().map(|e| e.key().clone()).collect();
+        topics.sort();
+        topics
+    }
+
+    /// Fold the totals of a topic recorded for `duration` into its profile
+    pub fn observe(&self, topic: &str, totals: &TopicTotals, duration: Duration) {
+        // Too short to tell a rate
+        if duration < Duration::from_secs(1) {
+            return;
+        }
+
+        let samples = totals.samples.load(Ordering::Relaxed);
+        let raw_bytes = totals.raw_bytes.load(Ordering::Relaxed);
+        let compressed_raw = totals.compressed_raw_bytes.load(Ordering::Relaxed);
+        let compressed_stored = totals.compressed_stored_bytes.load(Ordering::Relaxed);
+
+        let rate = samples as f64 / duration.as_secs_f64();
+        let size = (samples > 0).then(|| raw_bytes as f64 / samples as f64);
+        let ratio = (compressed_raw > 0).then(|| compressed_stored as f64 / compressed_raw as f64);
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let alpha = self.smoothing;
+        let blend = |old: f64, new: f64| alpha * new + (1.0 - alpha) * old;
+        self.profiles
+            .entry(topic.to_string())
+            .and_modify(|profile| {
+                profile.recordings += 1;
+                profile.samples_per_sec = blend(profile.samples_per_sec, rate);
+                if let Some(size) = size {
+                    profile.avg_sample_bytes = blend(profile.avg_sample_bytes, size);
+                }
+                if let Some(ratio) = ratio {
+                    profile.compression_ratio = Some(match profile.compression_ratio {
+                        Some(old) => blend(old, ratio),
+                        None => ratio,
+                    });
+                }
+                profile.updated_at = now.clone();
+            })
+            .or_insert_with(|| TopicProfile {
+                recordings: 1,
+                samples_per_sec: rate,
+                avg_sample_bytes: size.unwrap_or(0.0),
+                compression_ratio: ratio,
+                updated_at: now.clone(),
+            });
+    }
+
+    /// Persist the profiles atomically (write to a temp file, then rename)
+    pub async fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .context("Failed to create topic stats directory")?;
+        }
+
+        let profiles: BTreeMap<String, TopicProfile> = self
+            .profiles
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect();
+        let content =
+            serde_json::to_vec_pretty(&profiles).context("Failed to serialize topic stats")?;
+
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).await.context(format!(
+            "Failed to write topic stats: {}",
+            tmp_path.display()
+        ))?;
+        fs::rename(&tmp_path, &self.path).await.context(format!(
+            "Failed to move topic stats: {}",
+            self.path.display()
+        ))?;
+
+        debug!(
+            "Saved {} topic profiles to {}",
+            profiles.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+
+    /// Samples a topic buffer is expected to hold before it is flushed
+    pub fn expected_samples(
+        &self,
+        topic: &str,
+        max_duration: Duration,
+        max_bytes: usize,
+    ) -> Option<usize> {
+        let profile = self.profiles.get(topic)?;
+        let by_time = profile.samples_per_sec * max_duration.as_secs_f64();
+        let by_size = if profile.avg_sample_bytes > 0.0 {
+            max_bytes as f64 / profile.avg_sample_bytes
+        } else {
+            by_time
+        };
+        Some((by_time.min(by_size).ceil() as usize).min(MAX_BUFFER_CAPACITY))
+    }
+
+    /// Whether compression was found not to pay off for a topic
+    pub fn is_incompressible(&self, topic: &str) -> bool {
+        self.incompressible_ratio > 0.0
+            && self
+                .profiles
+                .get(topic)
+                .and_then(|p| p.compression_ratio)
+                .is_some_and(|ratio| ratio >= self.incompressible_ratio)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(samples: usize, raw: usize, stored: usize, compressed: bool) -> TopicTotals {
+        let totals = TopicTotals::default();
+        totals.record(samples, raw, stored, compressed);
+        totals
+    }
+
+    #[test]
+    fn test_observe_rolling_average() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = TopicStatsStore::new(dir.path().join("stats.json"), 0.5, 0.95);
+
+        store.observe(
+            "/camera",
+            &totals(100, 10_000, 5_000, true),
+            Duration::from_secs(10),
+        );
+        let profile = store.profile("/camera").unwrap();
+        assert_eq!(profile.recordings, 1);
+        assert_eq!(profile.samples_per_sec, 10.0);
+        assert_eq!(profile.avg_sample_bytes, 100.0);
+        assert_eq!(profile.compression_ratio, Some(0.5));
+
+        // Uncompressed recording leaves the ratio alone
+        store.observe(
+            "/camera",
+            &totals(300, 60_000, 60_000, false),
+            Duration::from_secs(10),
+        );
+        let profile = store.profile("/camera").unwrap();
+        assert_eq!(profile.recordings, 2);
+        assert_eq!(profile.samples_per_sec, 20.0);
+        assert_eq!(profile.avg_sample_bytes, 150.0);
+        assert_eq!(profile.compression_ratio, Some(0.5));
+
+        // Sub-second recordings are ignored
+        store.observe("/camera", &totals(1, 1, 1, true), Duration::from_millis(10));
+        assert_eq!(store.profile("/camera").unwrap().recordings, 2);
+    }
+
+    #[test]
+    fn test_expected_samples_and_codec_hint() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = TopicStatsStore::new(dir.path().join("stats.json"), 0.3, 0.95);
+        assert_eq!(
+            store.expected_samples("/imu", Duration::from_secs(10), 1024),
+            None
+        );
+
+        store.observe(
+            "/imu",
+            &totals(1000, 100_000, 98_000, true),
+            Duration::from_secs(10),
+        );
+        // 100 Hz * 10 s, or 1024 bytes / 100 bytes per sample
+        assert_eq!(
+            store.expected_samples("/imu", Duration::from_secs(10), 1 << 20),
+            Some(1000)
+        );
+        assert_eq!(
+            store.expected_samples("/imu", Duration::from_secs(10), 1024),
+            Some(11)
+        );
+        assert!(store.is_incompressible("/imu"));
+        assert!(!store.is_incompressible("/other"));
+    }
+
+    #[tokio::test]
+    async fn test_save_and_reopen() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("nested/stats.json");
+        let store = TopicStatsStore::new(&path, 0.3, 0.95);
+        assert_eq!(store.load().unwrap(), 0);
+        store.observe(
+            "/lidar",
+            &totals(50, 5_000, 2_000, true),
+            Duration::from_secs(5),
+        );
+        store.save().await.unwrap();
+
+        let reopened = TopicStatsStore::new(&path, 0.3, 0.95);
+        assert_eq!(reopened.load().unwrap(), 1);
+        assert_eq!(reopened.profile("/lidar"), store.profile("/lidar"));
+    }
+}
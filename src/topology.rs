@@ -0,0 +1,204 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Zenoh topology snapshots (`recorder.topology`)
+//
+// At Start the recorder asks its own session's admin space which nodes
+// publish on the recorded topics and which nodes it is connected to, and
+// stores the answer in the recording metadata. Only what the session has
+// learned from the routing protocol is listed: publishers of other nodes
+// only show up once they declared interest in matching subscribers. Zenoh
+// doesn't expose the QoS of remote publishers.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::warn;
+use zenoh::key_expr::keyexpr;
+use zenoh::Session;
+
+use crate::protocol::{TopologyPublisher, TopologySession, TopologySnapshot};
+
+/// Nodes declaring a resource, as reported by the admin space
+#[derive(Debug, Default, Deserialize)]
+struct Sources {
+    #[serde(default)]
+    routers: Vec<String>,
+    #[serde(default)]
+    peers: Vec<String>,
+    #[serde(default)]
+    clients: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocalData {
+    #[serde(default)]
+    locators: Vec<String>,
+    #[serde(default)]
+    sessions: Vec<SessionData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionData {
+    peer: String,
+    whatami: String,
+    #[serde(default)]
+    links: Vec<LinkData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkData {
+    dst: String,
+}
+
+/// Take a snapshot of the publishers on `topics` and the connected nodes
+///
+/// Needs the admin space of `session` to be enabled; returns an empty
+/// snapshot otherwise.
+pub async fn snapshot(
+    session: &Session,
+    topics: &[String],
+    timeout: Duration,
+) -> Result<TopologySnapshot> {
+    let zid = session.zid().to_string();
+    let mut snapshot = TopologySnapshot {
+        taken_at: chrono::Utc::now().to_rfc3339(),
+        zid: zid.clone(),
+        ..Default::default()
+    };
+
+    for (key, payload) in query(session, &format!("@/{}/*", zid), timeout).await? {
+        let local: LocalData = serde_json::from_slice(&payload)
+            .with_context(|| format!("Invalid admin space reply on '{}'", key))?;
+        snapshot.locators = local.locators;
+        snapshot.sessions = local
+            .sessions
+            .into_iter()
+            .map(|s| TopologySession {
+                zid: s.peer,
+                whatami: s.whatami,
+                locators: s.links.into_iter().map(|l| l.dst).collect(),
+            })
+            .collect();
+    }
+
+    let publishers = format!("@/{}/*/publisher/**", zid);
+    for (key, payload) in query(session, &publishers, timeout).await? {
+        let Some(key_expr) = publisher_key(&key) else {
+            continue;
+        };
+        if !matches_any(key_expr, topics) {
+            continue;
+        }
+        let sources: Sources = serde_json::from_slice(&payload)
+            .with_context(|| format!("Invalid admin space reply on '{}'", key))?;
+        for (whatami, zids) in [
+            ("router", sources.routers),
+            ("peer", sources.peers),
+            ("client", sources.clients),
+        ] {
+            snapshot
+                .publishers
+                .extend(zids.into_iter().map(|zid| TopologyPublisher {
+                    key_expr: key_expr.to_string(),
+                    zid,
+                    whatami: whatami.to_string(),
+                    locators: vec![],
+                }));
+        }
+    }
+
+    // A node may be listed under several roles; keep one entry per key
+    let publishers = &mut snapshot.publishers;
+    publishers.sort_by(|a, b| (&a.key_expr, &a.zid).cmp(&(&b.key_expr, &b.zid)));
+    publishers.dedup_by(|a, b| a.key_expr == b.key_expr && a.zid == b.zid);
+    for publisher in publishers.iter_mut() {
+        if let Some(node) = snapshot.sessions.iter().find(|s| s.zid == publisher.zid) {
+            publisher.locators = node.locators.clone();
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Keys and payloads of the successful replies to an admin space query
+async fn query(
+    session: &Session,
+    selector: &str,
+    timeout: Duration,
+) -> Result<Vec<(String, Vec<u8>)>> {
+    let replies = session
+        .get(selector)
+        .timeout(timeout)
+        .await
+        .map_err(|e| anyhow::anyhow!("Admin space query on '{}' failed: {}", selector, e))?;
+
+    let mut results = Vec::new();
+    while let Ok(reply) = replies.recv_async().await {
+        match reply.result() {
+            Ok(sample) => results.push((
+                sample.key_expr().to_string(),
+                sample.payload().to_bytes().to_vec(),
+            )),
+            Err(e) => warn!(
+                "Admin space query on '{}' returned an error: {:?}",
+                selector, e
+            ),
+        }
+    }
+    Ok(results)
+}
+
+/// Key expression of an `@/{zid}/{whatami}/publisher/{key_expr}` reply
+fn publisher_key(key: &str) -> Option<&str> {
+    let mut parts = key.splitn(5, '/');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("@"), Some(_), Some(_), Some("publisher")) => parts.next(),
+        _ => None,
+    }
+}
+
+/// Whether `key_expr` intersects one of the recorded topics
+fn matches_any(key_expr: &str, topics: &[String]) -> bool {
+    let Ok(key_expr) = keyexpr::new(key_expr) else {
+        return false;
+    };
+    topics
+        .iter()
+        .filter_map(|topic| keyexpr::new(topic.as_str()).ok())
+        .any(|topic| topic.intersects(key_expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publisher_key() {
+        assert_eq!(
+            publisher_key("@/abc/peer/publisher/camera/front"),
+            Some("camera/front")
+        );
+        assert_eq!(publisher_key("@/abc/peer/subscriber/camera"), None);
+        assert_eq!(publisher_key("@/abc/peer"), None);
+    }
+
+    #[test]
+    fn test_matches_any() {
+        let topics = vec!["camera/**".to_string(), "imu".to_string()];
+        assert!(matches_any("camera/front", &topics));
+        assert!(matches_any("imu", &topics));
+        assert!(matches_any("*/front", &topics));
+        assert!(!matches_any("lidar", &topics));
+    }
+}
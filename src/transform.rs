@@ -0,0 +1,202 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Pre-compression payload transforms (`compression.per_topic.*.transform`)
+//
+// A transform rewrites the payloads of a batch before they are serialized and
+// compressed, so that a general purpose codec finds more redundancy. Every
+// recorded message names the transform applied to it and whether it is a
+// keyframe, stored as published; readers undo the transform after parsing a
+// batch. Batches always start with a keyframe, so each decodes on its own.
+//
+// The built-in `delta` transform keeps every `keyframe_interval`-th sample of
+// a batch and XORs the others with the previous sample. Unchanged bytes of
+// consecutive point clouds or images become zeros, which zstd compresses to
+// almost nothing.
+
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::config::{find_per_topic, TopicCompression, TransformConfig};
+use crate::proto::RecordedMessage;
+
+/// Reversible rewrite of the payloads of a batch
+pub trait Transform: fmt::Debug + Send + Sync {
+    /// Name recorded with transformed messages
+    fn name(&self) -> &'static str;
+
+    /// Rewrite the payloads of a batch in place, in order; true for the
+    /// payloads left as they were (keyframes)
+    fn encode(&self, payloads: &mut [Vec<u8>]) -> Vec<bool>;
+
+    /// Undo `encode` given its keyframe flags
+    fn decode(&self, payloads: &mut [Vec<u8>], keyframes: &[bool]) -> Result<()>;
+}
+
+/// Keyframes every `keyframe_interval` samples, XOR deltas in between
+#[derive(Debug, Clone)]
+pub struct DeltaTransform {
+    keyframe_interval: usize,
+}
+
+impl DeltaTransform {
+    pub fn new(keyframe_interval: usize) -> Self {
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+        }
+    }
+}
+
+impl Transform for DeltaTransform {
+    fn name(&self) -> &'static str {
+        "delta"
+    }
+
+    fn encode(&self, payloads: &mut [Vec<u8>]) -> Vec<bool> {
+        let keyframes: Vec<bool> = (0..payloads.len())
+            .map(|i| i % self.keyframe_interval == 0)
+            .collect();
+        // Backwards, so the previous payload is still the original
+        for i in (1..payloads.len()).rev() {
+            if !keyframes[i] {
+                let (previous, current) = payloads.split_at_mut(i);
+                xor(&mut current[0], &previous[i - 1]);
+            }
+        }
+        keyframes
+    }
+
+    fn decode(&self, payloads: &mut [Vec<u8>], keyframes: &[bool]) -> Result<()> {
+        for i in 0..payloads.len() {
+            if keyframes.get(i).copied().unwrap_or(true) {
+                continue;
+            }
+            if i == 0 {
+                bail!("Delta message without a preceding keyframe");
+            }
+            let (previous, current) = payloads.split_at_mut(i);
+            xor(&mut current[0], &previous[i - 1]);
+        }
+        Ok(())
+    }
+}
+
+/// XOR the bytes both payloads have; the rest of `payload` is kept
+fn xor(payload: &mut [u8], base: &[u8]) {
+    for (byte, base) in payload.iter_mut().zip(base) {
+        *byte ^= base;
+    }
+}
+
+/// Transform configured for a topic
+pub fn for_topic(
+    per_topic: &HashMap<String, TopicCompression>,
+    topic: &str,
+) -> Option<Arc<dyn Transform>> {
+    let config = find_per_topic(per_topic, topic)?.transform.as_ref()?;
+    Some(match config {
+        TransformConfig::Delta { keyframe_interval } => {
+            Arc::new(DeltaTransform::new(*keyframe_interval))
+        }
+    })
+}
+
+/// Transform recorded under `name`
+fn by_name(name: &str) -> Option<Box<dyn Transform>> {
+    match name {
+        "delta" => Some(Box::new(DeltaTransform::new(1))),
+        _ => None,
+    }
+}
+
+/// Apply `transform` to the payloads of a batch's messages
+pub fn encode_messages(transform: &dyn Transform, messages: &mut [RecordedMessage]) {
+    let mut payloads: Vec<Vec<u8>> = messages
+        .iter_mut()
+        .map(|m| std::mem::take(&mut m.payload))
+        .collect();
+    let keyframes = transform.encode(&mut payloads);
+    for ((message, payload), keyframe) in messages.iter_mut().zip(payloads).zip(keyframes) {
+        message.payload = payload;
+        message.transform = transform.name().to_string();
+        message.keyframe = keyframe;
+    }
+}
+
+/// Restore the payloads of transformed messages of a batch, in place
+pub fn decode_messages(messages: &mut [RecordedMessage]) -> Result<()> {
+    let Some(name) = messages
+        .iter()
+        .map(|m| m.transform.as_str())
+        .find(|name| !name.is_empty())
+    else {
+        return Ok(());
+    };
+    let Some(transform) = by_name(name) else {
+        bail!("Unknown payload transform '{}'", name);
+    };
+    if messages.iter().any(|m| m.transform != name) {
+        bail!("Batch mixes payload transforms");
+    }
+
+    let mut payloads: Vec<Vec<u8>> = messages
+        .iter_mut()
+        .map(|m| std::mem::take(&mut m.payload))
+        .collect();
+    let keyframes: Vec<bool> = messages.iter().map(|m| m.keyframe).collect();
+    let decoded = transform.decode(&mut payloads, &keyframes);
+    for (message, payload) in messages.iter_mut().zip(payloads) {
+        message.payload = payload;
+    }
+    decoded?;
+    for message in messages.iter_mut() {
+        message.transform.clear();
+        message.keyframe = false;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let original: Vec<Vec<u8>> = vec![
+            vec![1, 2, 3, 4],
+            vec![1, 2, 3, 5],
+            vec![1, 2, 9],
+            vec![1, 2, 9, 9, 9],
+            vec![7; 4],
+        ];
+        let transform = DeltaTransform::new(3);
+        let mut payloads = original.clone();
+        let keyframes = transform.encode(&mut payloads);
+
+        assert_eq!(keyframes, vec![true, false, false, true, false]);
+        assert_eq!(payloads[0], original[0]);
+        assert_eq!(payloads[1], vec![0, 0, 0, 1]);
+        assert_eq!(payloads[2], vec![0, 0, 10]);
+        assert_eq!(payloads[3], original[3]);
+
+        transform.decode(&mut payloads, &keyframes).unwrap();
+        assert_eq!(payloads, original);
+
+        // A range starting between keyframes can't be decoded
+        let mut tail = payloads[1..3].to_vec();
+        assert!(transform.decode(&mut tail, &keyframes[1..3]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Upload deferral (`recorder.upload_deferral`)
+//
+// The gate is open while the local time is inside one of the configured
+// windows and one of the connectivity probes passes. It is re-evaluated every
+// check interval; while it is closed the flush workers keep records in the
+// work directory instead of uploading them.
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveTime};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{debug, info};
+
+use crate::config::{ConnectivityProbe, UploadDeferralConfig};
+use crate::runtime::{self, unblock};
+
+/// Daily local-time window, e.g. "22:00-06:00" (may wrap past midnight)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn parse(window: &str) -> Result<Self> {
+        let (start, end) = window
+            .split_once('-')
+            .ok_or_else(|| anyhow!("'{}' is not a HH:MM-HH:MM window", window))?;
+        let time = |s: &str| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .map_err(|_| anyhow!("'{}' is not a HH:MM-HH:MM window", window))
+        };
+        Ok(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Decides whether records are uploaded now or kept for later
+pub struct UploadGate {
+    windows: Vec<TimeWindow>,
+    probes: Vec<ConnectivityProbe>,
+    check_interval: Duration,
+    wakeup_granularity: Option<Duration>,
+    open: AtomicBool,
+    opened: Notify,
+}
+
+impl UploadGate {
+    /// Closed until the first check
+    pub fn new(config: &UploadDeferralConfig) -> Result<Self> {
+        Ok(Self {
+            windows: config
+                .windows
+                .iter()
+                .map(|w| TimeWindow::parse(w))
+                .collect::<Result<_>>()?,
+            probes: config.probes.clone(),
+            check_interval: config.check_interval(),
+            wakeup_granularity: None,
+            open: AtomicBool::new(false),
+            opened: Notify::new(),
+        })
+    }
+
+    /// Align the checks to the recorder's wakeup tick (low-power mode)
+    pub fn with_wakeup_granularity(mut self, granularity: Option<Duration>) -> Self {
+        self.wakeup_granularity = granularity;
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    /// Sleep until the next check
+    pub async fn sleep_interval(&self) {
+        runtime::sleep_aligned(self.check_interval, self.wakeup_granularity).await;
+    }
+
+    /// Wait until the gate is open
+    pub async fn wait_open(&self) {
+        loop {
+            let opened = self.opened.notified();
+            if self.is_open() {
+                return;
+            }
+            opened.await;
+        }
+    }
+
+    /// Evaluate the windows and probes and update the gate
+    pub async fn check(&self) -> bool {
+        let open = self.in_window(Local::now().time()) && self.connected().await;
+        let was_open = self.open.swap(open, Ordering::Relaxed);
+        if open != was_open {
+            if open {
+                info!("Upload conditions met, uploading deferred records");
+                self.opened.notify_waiters();
+            } else {
+                info!("Upload conditions not met, deferring uploads");
+            }
+        }
+        open
+    }
+
+    /// Re-check every check interval (runs until dropped)
+    pub async fn run(&self) {
+        loop {
+            self.check().await;
+            self.sleep_interval().await;
+        }
+    }
+
+    fn in_window(&self, time: NaiveTime) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|w| w.contains(time))
+    }
+
+    async fn connected(&self) -> bool {
+        if self.probes.is_empty() {
+            return true;
+        }
+        for probe in &self.probes {
+            if probe_passes(probe.clone()).await {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+async fn probe_passes(probe: ConnectivityProbe) -> bool {
+    let passed = unblock({
+        let probe = probe.clone();
+        move || match &probe {
+            ConnectivityProbe::Interface { name } => {
+                std::fs::read_to_string(format!("/sys/class/net/{}/operstate", name))
+                    .is_ok_and(|state| state.trim() == "up")
+            }
+            ConnectivityProbe::Tcp {
+                address,
+                timeout_ms,
+            } => address.to_socket_addrs().is_ok_and(|mut addrs| {
+                addrs.any(|addr| {
+                    TcpStream::connect_timeout(&addr, Duration::from_millis(*timeout_ms)).is_ok()
+                })
+            }),
+        }
+    })
+    .await;
+    debug!("Connectivity probe {:?}: {}", probe, passed);
+    passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_window() {
+        let day = TimeWindow::parse("09:00-17:30").unwrap();
+        assert!(day.contains(at(9, 0)));
+        assert!(day.contains(at(17, 29)));
+        assert!(!day.contains(at(17, 30)));
+        assert!(!day.contains(at(3, 0)));
+
+        // Wraps past midnight
+        let night = TimeWindow::parse("22:00 - 06:00").unwrap();
+        assert!(night.contains(at(23, 0)));
+        assert!(night.contains(at(5, 59)));
+        assert!(!night.contains(at(12, 0)));
+
+        assert!(TimeWindow::parse("22:00").is_err());
+        assert!(TimeWindow::parse("25:00-06:00").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_probes() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert!(
+            probe_passes(ConnectivityProbe::Tcp {
+                address,
+                timeout_ms: 500
+            })
+            .await
+        );
+        assert!(
+            !probe_passes(ConnectivityProbe::Interface {
+                name: "no-such-interface".to_string()
+            })
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gate_opens_when_a_probe_passes() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let gate = UploadGate::new(&UploadDeferralConfig {
+            enabled: true,
+            probes: vec![
+                ConnectivityProbe::Interface {
+                    name: "no-such-interface".to_string(),
+                },
+                ConnectivityProbe::Tcp {
+                    address: listener.local_addr().unwrap().to_string(),
+                    timeout_ms: 500,
+                },
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!gate.is_open());
+        assert!(gate.check().await);
+        gate.wait_open().await;
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Device-wide upload scheduling
+//
+// Flush workers of all recordings acquire an `UploadPermit` before writing a
+// record to the storage backend. The limiter caps the number of in-flight
+// uploads and paces the upload bandwidth with a token bucket (one second of
+// burst). Both limits can be changed at runtime; 0 means unlimited.
+//
+// Waiting uploads are granted in order: higher recording priority first, then
+// a recording other than the one served last (so concurrent recordings
+// interleave instead of one burst saturating disk and network), then FIFO.
+
+use std::cmp::Reverse;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::debug;
+
+use crate::protocol::UploadLimit;
+use crate::runtime;
+
+struct LimiterState {
+    max_bytes_per_sec: u64,
+    max_concurrent: usize,
+    in_flight: usize,
+    /// Available byte tokens; negative when uploads are paid for in advance
+    tokens: f64,
+    last_refill: Instant,
+    waiting: Vec<Waiter>,
+    next_ticket: u64,
+    /// Recording granted last
+    last_recording: Option<String>,
+}
+
+struct Waiter {
+    ticket: u64,
+    recording_id: String,
+    priority: i32,
+}
+
+impl LimiterState {
+    fn refill(&mut self) {
+        let rate = self.max_bytes_per_sec as f64;
+        if rate > 0.0 {
+            let now = Instant::now();
+            let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * rate).min(rate);
+            self.last_refill = now;
+        }
+    }
+
+    /// Ticket of the waiter to be granted next
+    fn next_waiter(&self) -> Option<u64> {
+        self.waiting
+            .iter()
+            .max_by_key(|w| {
+                (
+                    w.priority,
+                    self.last_recording.as_ref() != Some(&w.recording_id),
+                    Reverse(w.ticket),
+                )
+            })
+            .map(|w| w.ticket)
+    }
+
+    fn has_free_slot(&self) -> bool {
+        self.max_concurrent == 0 || self.in_flight < self.max_concurrent
+    }
+}
+
+/// Shared upload limiter for all flush workers
+pub struct UploadLimiter {
+    state: Mutex<LimiterState>,
+    released: Notify,
+}
+
+/// In-flight upload slot, released on drop
+pub struct UploadPermit<'a> {
+    limiter: &'a UploadLimiter,
+}
+
+/// Place in the waiting list, left on drop (also when the wait is cancelled)
+struct QueueEntry<'a> {
+    limiter: &'a UploadLimiter,
+    ticket: u64,
+}
+
+impl Drop for QueueEntry<'_> {
+    fn drop(&mut self) {
+        self.limiter
+            .state
+            .lock()
+            .unwrap()
+            .waiting
+            .retain(|w| w.ticket != self.ticket);
+        self.limiter.released.notify_waiters();
+    }
+}
+
+impl Drop for UploadPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().in_flight -= 1;
+        self.limiter.released.notify_waiters();
+    }
+}
+
+impl UploadLimiter {
+    pub fn new(limit: UploadLimit) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                max_bytes_per_sec: limit.max_bytes_per_sec,
+                max_concurrent: limit.max_concurrent_uploads,
+                in_flight: 0,
+                tokens: limit.max_bytes_per_sec as f64,
+                last_refill: Instant::now(),
+                waiting: Vec::new(),
+                next_ticket: 0,
+                last_recording: None,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Current limits
+    pub fn limit(&self) -> UploadLimit {
+        let state = self.state.lock().unwrap();
+        UploadLimit {
+            max_bytes_per_sec: state.max_bytes_per_sec,
+            max_concurrent_uploads: state.max_concurrent,
+        }
+    }
+
+    /// Change the limits; waiting uploads pick up the new values immediately
+    pub fn set_limit(&self, limit: UploadLimit) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.max_bytes_per_sec = limit.max_bytes_per_sec;
+            state.max_concurrent = limit.max_concurrent_uploads;
+            // Restart the bucket so a previous debt doesn't outlive the old rate
+            state.tokens = state.tokens.max(0.0).min(limit.max_bytes_per_sec as f64);
+            state.last_refill = Instant::now();
+        }
+        self.released.notify_waiters();
+    }
+
+    /// Wait for an upload slot and for `bytes` of bandwidth budget
+    ///
+    /// Each grant is charged to the bucket up front; the next waiter is held
+    /// back until the debt is paid off.
+    pub async fn acquire(
+        &self,
+        recording_id: &str,
+        priority: i32,
+        bytes: usize,
+    ) -> UploadPermit<'_> {
+        let entry = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting.push(Waiter {
+                ticket,
+                recording_id: recording_id.to_string(),
+                priority,
+            });
+            QueueEntry {
+                limiter: self,
+                ticket,
+            }
+        };
+
+        let debt = loop {
+            let released = self.released.notified();
+            let throttled = {
+                let mut state = self.state.lock().unwrap();
+                state.refill();
+                if state.next_waiter() != Some(entry.ticket) || !state.has_free_slot() {
+                    None
+                } else if state.max_bytes_per_sec > 0 && state.tokens < 0.0 {
+                    Some(Duration::from_secs_f64(
+                        -state.tokens / state.max_bytes_per_sec as f64,
+                    ))
+                } else {
+                    state.in_flight += 1;
+                    state.last_recording = Some(recording_id.to_string());
+                    if state.max_bytes_per_sec > 0 {
+                        state.tokens -= bytes as f64;
+                    }
+                    break (state.tokens < 0.0).then(|| {
+                        Duration::from_secs_f64(-state.tokens / state.max_bytes_per_sec as f64)
+                    });
+                }
+            };
+            match throttled {
+                Some(wait) => {
+                    tokio::select! {
+                        _ = released => {}
+                        _ = runtime::sleep(wait) => {}
+                    }
+                }
+                None => released.await,
+            }
+        };
+        // Leave the waiting list; the next waiter re-evaluates
+        drop(entry);
+        let permit = UploadPermit { limiter: self };
+
+        if let Some(wait) = debt {
+            debug!("Upload of {} bytes throttled for {:?}", bytes, wait);
+            runtime::sleep(wait).await;
+        }
+
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn limit(max_bytes_per_sec: u64, max_concurrent_uploads: usize) -> UploadLimit {
+        UploadLimit {
+            max_bytes_per_sec,
+            max_concurrent_uploads,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_does_not_wait() {
+        let limiter = UploadLimiter::new(UploadLimit::default());
+        let start = Instant::now();
+        for _ in 0..10 {
+            let _permit = limiter.acquire("rec", 0, 10 * 1024 * 1024).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_is_paced() {
+        // 1000 B/s with a 1000 B burst: the second 500 B upload must wait ~0.5s
+        let limiter = UploadLimiter::new(limit(1000, 0));
+        let start = Instant::now();
+        drop(limiter.acquire("rec", 0, 1000).await);
+        drop(limiter.acquire("rec", 0, 500).await);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(400), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(1500), "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_and_runtime_change() {
+        let limiter = Arc::new(UploadLimiter::new(limit(0, 1)));
+        let first = limiter.acquire("rec", 0, 1).await;
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire("rec", 0, 1).await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        // Raising the limit releases the waiting upload
+        limiter.set_limit(limit(0, 2));
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(limiter.limit(), limit(0, 2));
+        drop(first);
+    }
+
+    /// Queue uploads behind a held slot, one every 20 ms, and return the
+    /// order in which they are granted
+    async fn grant_order(limiter: Arc<UploadLimiter>, uploads: &[(&str, i32)]) -> Vec<String> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = limiter.acquire("held", 0, 1).await;
+        let mut handles = Vec::new();
+        for (recording_id, priority) in uploads {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            let (recording_id, priority) = (recording_id.to_string(), *priority);
+            handles.push(tokio::spawn(async move {
+                let _permit = limiter.acquire(&recording_id, priority, 1).await;
+                order.lock().unwrap().push(recording_id);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        let order = order.lock().unwrap().clone();
+        order
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_granted_first() {
+        let limiter = Arc::new(UploadLimiter::new(limit(0, 1)));
+        let order = grant_order(limiter, &[("low", -1), ("normal", 0), ("high", 5)]).await;
+        assert_eq!(order, vec!["high", "normal", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_recordings_interleave() {
+        let limiter = Arc::new(UploadLimiter::new(limit(0, 1)));
+        let order = grant_order(limiter, &[("a", 0), ("a", 0), ("a", 0), ("b", 0), ("b", 0)]).await;
+        assert_eq!(order, vec!["a", "b", "a", "b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_leaves_queue() {
+        let limiter = Arc::new(UploadLimiter::new(limit(0, 1)));
+        let held = limiter.acquire("a", 0, 1).await;
+
+        // A high-priority wait that gives up must not block the others
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(50), limiter.acquire("b", 10, 1)).await;
+        assert!(cancelled.is_err());
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire("c", 0, 1).await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,411 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Validation of control requests
+//
+// Checks collect every problem of a request instead of stopping at the
+// first one, so a client can fix them all in one round trip.
+
+use std::collections::{BTreeMap, HashMap};
+use zenoh::key_expr::KeyExpr;
+
+use crate::config::{CompressionConfig, CompressionLimitAction, ExpiryConfig};
+use crate::protocol::{CompressionLevel, CompressionType, RecorderRequest, ValidationError};
+use crate::storage::validate_bucket_name;
+
+/// Most topics a single recording may subscribe to
+pub const MAX_TOPICS: usize = 1024;
+/// Most skills attached to a recording
+pub const MAX_SKILLS: usize = 64;
+/// Longest identifier or label value (device_id, scene, skills, ...)
+pub const MAX_TEXT_LEN: usize = 256;
+/// Largest IO priority, either way
+pub const MAX_PRIORITY: i32 = 1000;
+/// Longest client-supplied recording ID
+pub const MAX_RECORDING_ID_LEN: usize = 128;
+/// Most environment seed entries of a recording
+pub const MAX_ENVIRONMENT_KEYS: usize = 32;
+/// Longest environment seed key
+pub const MAX_ENVIRONMENT_KEY_LEN: usize = 64;
+/// Most tags of a recording
+pub const MAX_LABELS: usize = 32;
+/// Longest tag name
+pub const MAX_LABEL_KEY_LEN: usize = 64;
+/// Record labels set by the recorder, which tags may not override
+pub const RESERVED_LABELS: &[&str] = &[
+    "recording_id",
+    "device_id",
+    "topic",
+    "original_topic",
+    "format",
+    "part",
+    "crc32c",
+    "scene",
+    "interrupted",
+    "handoff_predecessor",
+    "handoff_successor",
+    "labels_truncated",
+    "timestamp_bumped_us",
+    "flush_seq",
+    "expires_at",
+];
+
+/// Problems of a Start request (empty when it is valid)
+///
+/// `default_level` is the configured compression level, which requests
+/// without compression may carry since it is filled in for them.
+pub fn validate_start(
+    request: &RecorderRequest,
+    default_level: CompressionLevel,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    // Client IDs name work directories and state files
+    if let Some(recording_id) = &request.recording_id {
+        let valid = !recording_id.is_empty()
+            && recording_id.len() <= MAX_RECORDING_ID_LEN
+            && recording_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            errors.push(ValidationError::new(
+                "recording_id",
+                format!("use 1-{} letters, digits, '-' or '_'", MAX_RECORDING_ID_LEN),
+            ));
+        }
+    }
+    if request.device_id.trim().is_empty() {
+        errors.push(ValidationError::new("device_id", "must not be empty"));
+    }
+    check_len(&mut errors, "device_id", &request.device_id);
+    for (field, value) in [
+        ("scene", &request.scene),
+        ("organization", &request.organization),
+        ("task_id", &request.task_id),
+        ("data_collector_id", &request.data_collector_id),
+    ] {
+        if let Some(value) = value {
+            check_len(&mut errors, field, value);
+        }
+    }
+
+    if request.skills.len() > MAX_SKILLS {
+        errors.push(ValidationError::new(
+            "skills",
+            format!("at most {} skills are allowed", MAX_SKILLS),
+        ));
+    }
+    for (i, skill) in request.skills.iter().enumerate() {
+        let field = format!("skills[{}]", i);
+        if skill.trim().is_empty() {
+            errors.push(ValidationError::new(field, "must not be empty"));
+        } else {
+            check_len(&mut errors, &field, skill);
+        }
+    }
+
+    validate_topics(&mut errors, &request.topics);
+    validate_exclude_topics(&mut errors, &request.exclude_topics);
+    validate_environment(&mut errors, &request.environment);
+    validate_labels(&mut errors, &request.labels);
+
+    let level = request.compression_level as i32;
+    if request.compression_type == CompressionType::None
+        && level != CompressionLevel::default() as i32
+        && level != default_level as i32
+    {
+        errors.push(ValidationError::new(
+            "compression_level",
+            format!(
+                "{:?} has no effect without compression; choose lz4 or zstd, or leave it out",
+                request.compression_level
+            ),
+        ));
+    }
+
+    if let Some(bucket) = &request.bucket {
+        if let Err(e) = validate_bucket_name(bucket) {
+            errors.push(ValidationError::new("bucket", e.to_string()));
+        }
+    }
+    if let Some(key_expr) = &request.controller_liveliness {
+        if let Some(problem) = key_expr_problem(key_expr) {
+            errors.push(ValidationError::new("controller_liveliness", problem));
+        }
+    }
+    if let Some(priority) = request.priority {
+        if !(-MAX_PRIORITY..=MAX_PRIORITY).contains(&priority) {
+            errors.push(ValidationError::new(
+                "priority",
+                format!("must be between {} and {}", -MAX_PRIORITY, MAX_PRIORITY),
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Apply the configured compression limits to a Start request
+///
+/// A level compressing harder than `compression.max_zstd_level` or
+/// `max_lz4_level` allow is rejected, or with `over_limit = "clamp"` lowered
+/// to the slowest level within them. Returns the requested level when it was
+/// lowered.
+pub fn limit_compression(
+    request: &mut RecorderRequest,
+    config: &CompressionConfig,
+) -> Result<Option<CompressionLevel>, ValidationError> {
+    let requested = request.compression_level;
+    let Some(problem) = compression_limit_problem(request.compression_type, requested, config)
+    else {
+        return Ok(None);
+    };
+    if config.over_limit == CompressionLimitAction::Reject {
+        return Err(ValidationError::new(
+            "compression_level",
+            format!("{:?} is not allowed: {}", requested, problem),
+        ));
+    }
+
+    // Fastest (level 1) is within every limit the loader accepts
+    request.compression_level = (0..=4)
+        .rev()
+        .filter_map(CompressionLevel::from_index)
+        .find(|level| compression_limit_problem(request.compression_type, *level, config).is_none())
+        .unwrap_or(CompressionLevel::Fastest);
+    Ok(Some(requested))
+}
+
+/// Time to live of a Start's recording: its `retention_seconds`, else
+/// `expiry.default_ttl_seconds` (None = kept)
+pub fn retention(
+    request: &RecorderRequest,
+    config: &ExpiryConfig,
+) -> Result<Option<u64>, ValidationError> {
+    let Some(requested) = request.retention_seconds else {
+        return Ok((config.enabled && config.default_ttl_seconds > 0)
+            .then_some(config.default_ttl_seconds));
+    };
+    if !config.enabled {
+        return Err(ValidationError::new(
+            "retention_seconds",
+            "recorder.expiry is disabled on this recorder",
+        ));
+    }
+    if requested == 0 {
+        return Err(ValidationError::new(
+            "retention_seconds",
+            "must be greater than 0; leave it out to use the default",
+        ));
+    }
+    if config.max_ttl_seconds > 0 && requested > config.max_ttl_seconds {
+        return Err(ValidationError::new(
+            "retention_seconds",
+            format!(
+                "at most {} seconds are allowed (expiry.max_ttl_seconds)",
+                config.max_ttl_seconds
+            ),
+        ));
+    }
+    Ok(Some(requested))
+}
+
+/// Why `level` exceeds the configured limits of `compression_type`, if it does
+pub fn compression_limit_problem(
+    compression_type: CompressionType,
+    level: CompressionLevel,
+    config: &CompressionConfig,
+) -> Option<String> {
+    match compression_type {
+        CompressionType::None => None,
+        CompressionType::Lz4 => {
+            let native = level.to_lz4_level();
+            let max = config.max_lz4_level.filter(|max| native > *max)?;
+            Some(format!(
+                "lz4 level {} is above compression.max_lz4_level ({})",
+                native, max
+            ))
+        }
+        CompressionType::Zstd => {
+            let native = level.to_zstd_level();
+            let max = config.max_zstd_level.filter(|max| native > *max)?;
+            Some(format!(
+                "zstd level {} is above compression.max_zstd_level ({})",
+                native, max
+            ))
+        }
+    }
+}
+
+/// Problems of a Snapshot request whose `topics` were filled in from
+/// `snapshot.topics` when it listed none
+pub fn validate_snapshot(request: &RecorderRequest) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    validate_topics(&mut errors, &request.topics);
+    validate_labels(&mut errors, &request.labels);
+    errors
+}
+
+/// Problems of a Dump request whose `topics` were filled in from
+/// `black_box.topics` when it listed none, beyond those of a Start
+pub fn validate_dump(
+    request: &RecorderRequest,
+    black_box_topics: &[String],
+) -> Vec<ValidationError> {
+    request
+        .topics
+        .iter()
+        .enumerate()
+        .filter(|(_, topic)| !black_box_topics.contains(topic))
+        .map(|(i, topic)| {
+            ValidationError::new(
+                format!("topics[{}]", i),
+                format!("'{}' is not a black_box topic", topic),
+            )
+        })
+        .collect()
+}
+
+fn validate_topics(errors: &mut Vec<ValidationError>, topics: &[String]) {
+    if topics.is_empty() {
+        errors.push(ValidationError::new(
+            "topics",
+            "at least one topic is required",
+        ));
+    } else if topics.len() > MAX_TOPICS {
+        errors.push(ValidationError::new(
+            "topics",
+            format!("at most {} topics are allowed", MAX_TOPICS),
+        ));
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, topic) in topics.iter().enumerate() {
+        let field = format!("topics[{}]", i);
+        if let Some(first) = seen.get(topic.as_str()) {
+            errors.push(ValidationError::new(
+                field,
+                format!("duplicates topics[{}] '{}'", first, topic),
+            ));
+            continue;
+        }
+        seen.insert(topic, i);
+        if let Some(problem) = key_expr_problem(topic) {
+            errors.push(ValidationError::new(field, problem));
+        }
+    }
+}
+
+fn validate_exclude_topics(errors: &mut Vec<ValidationError>, patterns: &[String]) {
+    if patterns.len() > MAX_TOPICS {
+        errors.push(ValidationError::new(
+            "exclude_topics",
+            format!("at most {} patterns are allowed", MAX_TOPICS),
+        ));
+    }
+    for (i, pattern) in patterns.iter().enumerate() {
+        if let Some(problem) = key_expr_problem(pattern) {
+            errors.push(ValidationError::new(
+                format!("exclude_topics[{}]", i),
+                problem,
+            ));
+        }
+    }
+}
+
+fn validate_environment(errors: &mut Vec<ValidationError>, environment: &BTreeMap<String, String>) {
+    if environment.len() > MAX_ENVIRONMENT_KEYS {
+        errors.push(ValidationError::new(
+            "environment",
+            format!("at most {} entries are allowed", MAX_ENVIRONMENT_KEYS),
+        ));
+    }
+    // Keys name the `env_{key}` labels of the metadata record
+    for (key, value) in environment {
+        let field = format!("environment.{}", key);
+        let valid = !key.is_empty()
+            && key.len() <= MAX_ENVIRONMENT_KEY_LEN
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            errors.push(ValidationError::new(
+                &field,
+                format!(
+                    "key must be 1-{} letters, digits or '_'",
+                    MAX_ENVIRONMENT_KEY_LEN
+                ),
+            ));
+        }
+        check_len(errors, &field, value);
+    }
+}
+
+fn validate_labels(errors: &mut Vec<ValidationError>, labels: &HashMap<String, String>) {
+    if labels.len() > MAX_LABELS {
+        errors.push(ValidationError::new(
+            "labels",
+            format!("at most {} labels are allowed", MAX_LABELS),
+        ));
+    }
+    let mut keys: Vec<&String> = labels.keys().collect();
+    keys.sort();
+    for key in keys {
+        let field = format!("labels.{}", key);
+        let valid = !key.is_empty()
+            && key.len() <= MAX_LABEL_KEY_LEN
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            errors.push(ValidationError::new(
+                &field,
+                format!("key must be 1-{} letters, digits or '_'", MAX_LABEL_KEY_LEN),
+            ));
+        } else if RESERVED_LABELS.contains(&key.as_str()) || key.starts_with("env_") {
+            errors.push(ValidationError::new(
+                &field,
+                "is set by the recorder; choose another name",
+            ));
+        }
+        check_len(errors, &field, &labels[key]);
+    }
+}
+
+fn check_len(errors: &mut Vec<ValidationError>, field: &str, value: &str) {
+    if value.len() > MAX_TEXT_LEN {
+        errors.push(ValidationError::new(
+            field,
+            format!(
+                "must be at most {} bytes, got {}",
+                MAX_TEXT_LEN,
+                value.len()
+            ),
+        ));
+    }
+}
+
+/// Why `key_expr` can't be subscribed to, if it can't
+fn key_expr_problem(key_expr: &str) -> Option<String> {
+    let problem = if key_expr.is_empty() {
+        "must not be empty"
+    } else if key_expr.starts_with('/') {
+        "must not start with '/'"
+    } else if key_expr.ends_with('/') {
+        "must not end with '/'"
+    } else if key_expr.contains("//") {
+        "must not contain empty chunks ('//')"
+    } else if KeyExpr::try_from(key_expr).is_err() {
+        "is not a valid Zenoh key expression"
+    } else {
+        return None;
+    };
+    Some(format!("'{}' {}", key_expr, problem))
+}
@@ -0,0 +1,479 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Per-recording scratch directories
+//
+// Each recording gets `{root}/{recording_id}/` for files that only matter
+// while it runs, e.g. `spill/{entry}/{timestamp_us}.mcap` for records whose
+// upload failed after all retries, and `deferred/{entry}/{timestamp_us}.mcap`
+// (plus a `.json` sidecar with its labels) for records kept back by the
+// upload deferral until they can be uploaded. The directory is removed when
+// the recording finishes or is cancelled. Directories holding spilled or
+// deferred records are kept for the failure retention period so the data can
+// be uploaded by hand; directories of recordings that are no longer active
+// (crash leftovers) are swept on startup and whenever a recording ends.
+//
+// A recording still uploading when the shutdown deadline passes gets its
+// remaining records written to `deferred/` and a `shutdown_manifest.json`
+// with its metadata. Its directory is never swept; the next start uploads the
+// records, writes the metadata and removes the manifest.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+use crate::batch_index::BatchIndex;
+use crate::coalesce::CoalescedPart;
+use crate::protocol::RecordingMetadata;
+use crate::runtime::{fs, unblock};
+
+/// Subdirectory holding the records whose upload failed or didn't verify
+pub const SPILL_DIR: &str = "spill";
+
+/// Subdirectory holding the records kept back by the upload deferral
+pub const DEFERRED_DIR: &str = "deferred";
+
+/// Written when a recording ends with spilled records; starts the retention
+const RELEASED_MARKER: &str = ".released";
+
+/// Recovery manifest of a recording spilled at shutdown
+const SHUTDOWN_MANIFEST: &str = "shutdown_manifest.json";
+
+/// Record kept back by the upload deferral (the `.json` sidecar of its data)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeferredRecord {
+    /// Recorded topic name
+    pub topic: String,
+    pub entry: String,
+    pub timestamp_us: u64,
+    pub labels: HashMap<String, String>,
+    pub crc32c: String,
+    /// Payload bytes and samples the record holds
+    pub raw_bytes: usize,
+    pub samples: usize,
+    pub compressed: bool,
+    /// Written to the index entry once the record is uploaded (None =
+    /// `index` disabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<BatchIndex>,
+    /// Batches of a coalesced record (empty for other records)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<CoalescedPart>,
+}
+
+/// Recording whose upload didn't complete before the shutdown deadline; its
+/// remaining records are in `deferred/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownManifest {
+    /// Final metadata, with the records uploaded before the deadline
+    pub metadata: RecordingMetadata,
+    pub spilled_at: String,
+}
+
+/// Root of the per-recording scratch directories
+pub struct WorkDirs {
+    root: PathBuf,
+    retain_failed: Duration,
+}
+
+impl WorkDirs {
+    pub fn new<P: AsRef<Path>>(root: P, retain_failed: Duration) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            retain_failed,
+        }
+    }
+
+    /// Scratch directory of a recording
+    pub fn path(&self, recording_id: &str) -> PathBuf {
+        self.root.join(recording_id)
+    }
+
+    /// Create the scratch directory of a recording (kept if it exists)
+    pub async fn create(&self, recording_id: &str) -> Result<PathBuf> {
+        let path = self.path(recording_id);
+        fs::create_dir_all(&path).await.context(format!(
+            "Failed to create work directory: {}",
+            path.display()
+        ))?;
+        Ok(path)
+    }
+
+    /// Keep a record whose upload failed or didn't verify; returns the file it was written to
+    pub async fn spill(
+        &self,
+        recording_id: &str,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+    ) -> Result<PathBuf> {
+        let dir = self.path(recording_id).join(SPILL_DIR).join(entry_name);
+        fs::create_dir_all(&dir).await.context(format!(
+            "Failed to create spill directory: {}",
+            dir.display()
+        ))?;
+        let path = dir.join(format!("{}.mcap", timestamp_us));
+        fs::write(&path, data)
+            .await
+            .context(format!("Failed to write spill file: {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Keep a record until uploads are no longer deferred; returns its sidecar
+    pub async fn defer(
+        &self,
+        recording_id: &str,
+        record: &DeferredRecord,
+        data: Vec<u8>,
+    ) -> Result<PathBuf> {
+        let dir = self
+            .path(recording_id)
+            .join(DEFERRED_DIR)
+            .join(&record.entry);
+        fs::create_dir_all(&dir).await.context(format!(
+            "Failed to create deferred directory: {}",
+            dir.display()
+        ))?;
+        let data_path = dir.join(format!("{}.mcap", record.timestamp_us));
+        fs::write(&data_path, data).await.context(format!(
+            "Failed to write deferred record: {}",
+            data_path.display()
+        ))?;
+        // Written last: a record without its sidecar is incomplete
+        let sidecar = data_path.with_extension("json");
+        fs::write(&sidecar, serde_json::to_vec(record)?)
+            .await
+            .context(format!(
+                "Failed to write deferred record: {}",
+                sidecar.display()
+            ))?;
+        Ok(sidecar)
+    }
+
+    /// Sidecars of the deferred records of a recording, oldest first
+    pub async fn deferred(&self, recording_id: &str) -> Vec<PathBuf> {
+        let dir = self.path(recording_id).join(DEFERRED_DIR);
+        unblock(move || {
+            let mut sidecars: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .flat_map(|entry| std::fs::read_dir(entry.path()).into_iter().flatten())
+                .filter_map(|file| file.ok().map(|file| file.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|path| {
+                    let timestamp = path.file_stem()?.to_str()?.parse().ok()?;
+                    Some((timestamp, path))
+                })
+                .collect();
+            sidecars.sort();
+            sidecars.into_iter().map(|(_, path)| path).collect()
+        })
+        .await
+    }
+
+    /// Read a deferred record and its data
+    pub async fn load_deferred(&self, sidecar: &Path) -> Result<(DeferredRecord, Vec<u8>)> {
+        let record = fs::read(sidecar)
+            .await
+            .context(format!("Failed to read {}", sidecar.display()))?;
+        let record: DeferredRecord = serde_json::from_slice(&record)
+            .context(format!("Invalid deferred record {}", sidecar.display()))?;
+        let data_path = sidecar.with_extension("mcap");
+        let data = fs::read(&data_path)
+            .await
+            .context(format!("Failed to read {}", data_path.display()))?;
+        Ok((record, data))
+    }
+
+    /// Drop a deferred record once it was uploaded (or spilled)
+    pub async fn remove_deferred(&self, sidecar: &Path) -> Result<()> {
+        fs::remove_file(sidecar)
+            .await
+            .context(format!("Failed to remove {}", sidecar.display()))?;
+        fs::remove_file(sidecar.with_extension("mcap"))
+            .await
+            .context(format!(
+                "Failed to remove {}",
+                sidecar.with_extension("mcap").display()
+            ))?;
+        Ok(())
+    }
+
+    /// Write the recovery manifest of a recording spilled at shutdown
+    pub async fn write_manifest(&self, manifest: &ShutdownManifest) -> Result<PathBuf> {
+        let path = self
+            .create(&manifest.metadata.recording_id)
+            .await?
+            .join(SHUTDOWN_MANIFEST);
+        let data = serde_json::to_vec_pretty(manifest)?;
+        // Replaced in one step, so a crash never leaves half a manifest
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, data)
+            .await
+            .context(format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path)
+            .await
+            .context(format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Recovery manifests left by earlier shutdowns
+    pub async fn manifests(&self) -> Vec<ShutdownManifest> {
+        let Ok(dirs) = fs::read_dir(&self.root).await else {
+            return Vec::new();
+        };
+        let mut manifests = Vec::new();
+        for dir in dirs {
+            let path = dir.join(SHUTDOWN_MANIFEST);
+            let Ok(data) = fs::read(&path).await else {
+                continue;
+            };
+            match serde_json::from_slice(&data) {
+                Ok(manifest) => manifests.push(manifest),
+                Err(e) => warn!("Invalid shutdown manifest {}: {}", path.display(), e),
+            }
+        }
+        manifests
+    }
+
+    /// Remove the recovery manifest of a recording once it's complete
+    pub async fn remove_manifest(&self, recording_id: &str) -> Result<()> {
+        let path = self.path(recording_id).join(SHUTDOWN_MANIFEST);
+        match fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context(format!("Failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Clean up after a recording ended
+    ///
+    /// The directory is removed unless it holds spilled or deferred records;
+    /// those are kept for the retention period. Returns whether it was removed.
+    pub async fn release(&self, recording_id: &str) -> Result<bool> {
+        let path = self.path(recording_id);
+        let spilled = kept_records(&path).await;
+        if spilled > 0 && !self.retain_failed.is_zero() {
+            warn!(
+                "Keeping work directory {} with {} spilled record(s) for {:?}",
+                path.display(),
+                spilled,
+                self.retain_failed
+            );
+            fs::write(path.join(RELEASED_MARKER), Vec::new())
+                .await
+                .context("Failed to mark work directory as released")?;
+            return Ok(false);
+        }
+        remove_dir(path).await?;
+        Ok(true)
+    }
+
+    /// Remove the directories of recordings not in `active`
+    ///
+    /// Directories without spilled or deferred records go at once, others when the
+    /// retention period since the recording ended (or the directory was last
+    /// changed, for crash leftovers) is over. Directories with a shutdown
+    /// manifest are kept. Returns the number removed.
+    pub async fn sweep(&self, active: &HashSet<String>) -> Result<usize> {
+        let dirs = match fs::read_dir(&self.root).await {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context("Failed to read work directory"),
+        };
+
+        let mut removed = 0;
+        for dir in dirs {
+            let Some(recording_id) = dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !dir.is_dir() || active.contains(recording_id) {
+                continue;
+            }
+            if fs::metadata(dir.join(SHUTDOWN_MANIFEST)).await.is_ok() {
+                debug!(
+                    "Keeping work directory {} of a spilled recording",
+                    dir.display()
+                );
+                continue;
+            }
+
+            if kept_records(&dir).await > 0 {
+                let ended = match fs::metadata(dir.join(RELEASED_MARKER)).await {
+                    Ok(metadata) => metadata.modified(),
+                    Err(_) => fs::metadata(&dir).await.and_then(|m| m.modified()),
+                };
+                let age = ended
+                    .ok()
+                    .and_then(|ended| SystemTime::now().duration_since(ended).ok())
+                    .unwrap_or_default();
+                if age < self.retain_failed {
+                    debug!("Retaining work directory {}", dir.display());
+                    continue;
+                }
+            }
+
+            match remove_dir(dir.clone()).await {
+                Ok(()) => {
+                    info!("Removed stale work directory {}", dir.display());
+                    removed += 1;
+                }
+                Err(e) => warn!("{:#}", e),
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Number of records spilled to or deferred in a scratch directory
+async fn kept_records(dir: &Path) -> usize {
+    let dirs = [dir.join(SPILL_DIR), dir.join(DEFERRED_DIR)];
+    unblock(move || dirs.into_iter().map(count_records).sum()).await
+}
+
+/// Number of `.mcap` files below `path` (0 when it doesn't exist)
+fn count_records(path: PathBuf) -> usize {
+    let Ok(entries) = std::fs::read_dir(&path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                count_records(path)
+            } else {
+                usize::from(path.extension().is_some_and(|ext| ext == "mcap"))
+            }
+        })
+        .sum()
+}
+
+async fn remove_dir(path: PathBuf) -> Result<()> {
+    let display = path.display().to_string();
+    match unblock(move || std::fs::remove_dir_all(path)).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context(format!("Failed to remove work directory: {}", display)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_release_removes_clean_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let work_dirs = WorkDirs::new(temp_dir.path(), Duration::from_secs(3600));
+
+        let path = work_dirs.create("rec-1").await.unwrap();
+        assert!(path.is_dir());
+        assert!(work_dirs.release("rec-1").await.unwrap());
+        assert!(!path.exists());
+        // Releasing twice is not an error
+        assert!(work_dirs.release("rec-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_spilled_records_are_retained() {
+        let temp_dir = TempDir::new().unwrap();
+        let work_dirs = WorkDirs::new(temp_dir.path(), Duration::from_secs(3600));
+
+        work_dirs.create("rec-1").await.unwrap();
+        let spilled = work_dirs
+            .spill("rec-1", "camera", 42, b"mcap".to_vec())
+            .await
+            .unwrap();
+        assert!(spilled.ends_with("rec-1/spill/camera/42.mcap"));
+
+        assert!(!work_dirs.release("rec-1").await.unwrap());
+        assert!(spilled.exists());
+        assert_eq!(work_dirs.sweep(&HashSet::new()).await.unwrap(), 0);
+
+        // Without retention the directory goes at once
+        let work_dirs = WorkDirs::new(temp_dir.path(), Duration::ZERO);
+        assert_eq!(work_dirs.sweep(&HashSet::new()).await.unwrap(), 1);
+        assert!(!spilled.exists());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let work_dirs = WorkDirs::new(temp_dir.path(), Duration::from_secs(3600));
+        let record = |entry: &str, timestamp_us| DeferredRecord {
+            topic: entry.to_string(),
+            entry: entry.to_string(),
+            timestamp_us,
+            labels: HashMap::from([("format".to_string(), "mcap".to_string())]),
+            crc32c: "00000000".to_string(),
+            raw_bytes: 4,
+            samples: 1,
+            compressed: false,
+            index: None,
+            parts: vec![],
+        };
+
+        work_dirs
+            .defer("rec-1", &record("lidar", 20), b"late".to_vec())
+            .await
+            .unwrap();
+        let first = work_dirs
+            .defer("rec-1", &record("camera", 10), b"mcap".to_vec())
+            .await
+            .unwrap();
+        assert!(first.ends_with("rec-1/deferred/camera/10.json"));
+
+        let deferred = work_dirs.deferred("rec-1").await;
+        assert_eq!(deferred.len(), 2);
+        assert_eq!(deferred[0], first);
+        let (loaded, data) = work_dirs.load_deferred(&first).await.unwrap();
+        assert_eq!(loaded, record("camera", 10));
+        assert_eq!(data, b"mcap");
+
+        // Kept like spilled records until uploaded
+        assert!(!work_dirs.release("rec-1").await.unwrap());
+        for sidecar in deferred {
+            work_dirs.remove_deferred(&sidecar).await.unwrap();
+        }
+        assert!(work_dirs.deferred("rec-1").await.is_empty());
+        assert!(work_dirs.deferred("missing").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_skips_active_recordings() {
+        let temp_dir = TempDir::new().unwrap();
+        let work_dirs = WorkDirs::new(temp_dir.path(), Duration::from_secs(3600));
+
+        work_dirs.create("active").await.unwrap();
+        work_dirs.create("crashed").await.unwrap();
+
+        let active = HashSet::from(["active".to_string()]);
+        assert_eq!(work_dirs.sweep(&active).await.unwrap(), 1);
+        assert!(work_dirs.path("active").is_dir());
+        assert!(!work_dirs.path("crashed").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_missing_root() {
+        let work_dirs = WorkDirs::new("/nonexistent/zenoh-recorder-work", Duration::ZERO);
+        assert_eq!(work_dirs.sweep(&HashSet::new()).await.unwrap(), 0);
+    }
+}
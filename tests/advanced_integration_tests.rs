@@ -14,6 +14,7 @@
 
 /// Final push to 90% coverage - targeting control.rs and remaining paths
 ///
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use zenoh::Config;
@@ -43,6 +44,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -110,8 +115,6 @@ async fn test_recorder_comprehensive_lifecycle() {
 
     // Test Start -> Get Status -> Pause -> Get Status -> Resume -> Get Status -> Finish
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("lifecycle_test".to_string()),
         skills: vec!["test_skill".to_string()],
         organization: Some("test_org".to_string()),
@@ -121,6 +124,7 @@ async fn test_recorder_comprehensive_lifecycle() {
         topics: vec!["test/lifecycle1".to_string(), "test/lifecycle2".to_string()],
         compression_level: CompressionLevel::Slow,
         compression_type: CompressionType::Lz4,
+        ..Default::default()
     };
 
     let start_resp = manager.start_recording(request).await;
@@ -181,8 +185,6 @@ async fn test_manager_with_many_concurrent_operations() {
         let mgr = manager.clone();
         let handle = tokio::spawn(async move {
             let request = RecorderRequest {
-                command: RecorderCommand::Start,
-                recording_id: None,
                 scene: Some(format!("scene_{}", i)),
                 skills: vec![format!("skill_{}", i)],
                 organization: Some(format!("org_{}", i)),
@@ -200,6 +202,7 @@ async fn test_manager_with_many_concurrent_operations() {
                 } else {
                     CompressionType::Lz4
                 },
+                ..Default::default()
             };
 
             mgr.start_recording(request).await
@@ -235,17 +238,10 @@ async fn test_status_query_for_each_state() {
     ));
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/states".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -287,7 +283,6 @@ async fn test_recording_with_maximum_metadata() {
     let huge_topics: Vec<String> = (0..100).map(|i| format!("test/topic{}", i)).collect();
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some("pre-assigned-max-meta-id".to_string()),
         scene: Some("maximum_metadata_test_scene".to_string()),
         skills: huge_skills,
@@ -297,7 +292,7 @@ async fn test_recording_with_maximum_metadata() {
         data_collector_id: Some("collector-maximum-metadata-001".to_string()),
         topics: huge_topics,
         compression_level: CompressionLevel::Slowest,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -362,7 +357,7 @@ fn test_response_error_with_various_messages() {
     ];
 
     for error_msg in errors {
-        let response = RecorderResponse::error(error_msg.to_string());
+        let response = RecorderResponse::error(ErrorCode::Internal, error_msg.to_string());
         assert!(!response.success);
         assert_eq!(response.message, error_msg);
         assert!(response.recording_id.is_none());
@@ -391,17 +386,11 @@ async fn test_rapid_state_transitions() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/rapid".to_string()],
         compression_level: CompressionLevel::Fastest,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -429,8 +418,6 @@ async fn test_get_status_detailed_fields() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("detailed_scene".to_string()),
         skills: vec!["skill_a".to_string(), "skill_b".to_string()],
         organization: Some("detailed_org".to_string()),
@@ -443,7 +430,7 @@ async fn test_get_status_detailed_fields() {
             "test/detailed3".to_string(),
         ],
         compression_level: CompressionLevel::Slow,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -517,17 +504,10 @@ async fn test_finish_with_buffer_flush() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/flush_finish".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -593,7 +573,32 @@ fn test_recording_metadata_all_optional_fields() {
         end_time: None,
         total_bytes: 0,
         total_samples: 0,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({}),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     let json1 = serde_json::to_string(&meta1).unwrap();
@@ -617,7 +622,32 @@ fn test_recording_metadata_all_optional_fields() {
         end_time: Some("2024-01-01T01:00:00Z".to_string()),
         total_bytes: 1000,
         total_samples: 100,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({"t": {}}),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     let json2 = serde_json::to_string(&meta2).unwrap();
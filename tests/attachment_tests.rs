@@ -0,0 +1,84 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the capture of Zenoh sample attachments
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/attachment/events";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_attachments_recorded_and_labeled() {
+    let mut config = RecorderConfig::default();
+    config.recorder.attachment_labels = vec!["mission_id".to_string(), "operator".to_string()];
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            compression_type: CompressionType::None,
+            ..common::start_request(&[TOPIC])
+        })
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let attachments: [Option<&str>; 4] = [
+        Some(r#"{"mission_id":"m-7","attempt":1}"#),
+        Some(r#"{"mission_id":"m-8"}"#),
+        Some("not json"),
+        None,
+    ];
+    for attachment in attachments {
+        let put = session.put(TOPIC, "event");
+        match attachment {
+            Some(attachment) => put.attachment(attachment).wait().unwrap(),
+            None => put.wait().unwrap(),
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let records = backend.records(&topic_to_entry_name(TOPIC));
+    assert_eq!(records.len(), 1);
+    let (_, record) = &records[0];
+    let recorded: Vec<Vec<u8>> = parse_batch(&record.data)
+        .unwrap()
+        .messages
+        .into_iter()
+        .map(|message| message.attachment)
+        .collect();
+    let expected: Vec<Vec<u8>> = attachments
+        .iter()
+        .map(|attachment| attachment.unwrap_or_default().as_bytes().to_vec())
+        .collect();
+    assert_eq!(recorded, expected);
+
+    // Only the configured keys found in JSON attachments become labels
+    assert_eq!(record.labels["mission_id"], "m-7,m-8");
+    assert!(!record.labels.contains_key("operator"));
+    assert!(!record.labels.contains_key("attempt"));
+    assert_eq!(record.labels["recording_id"], recording_id);
+}
@@ -0,0 +1,143 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the black box rings and the dump command
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::RecorderConfig;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+const CAMERA: &str = "test/black_box/camera";
+const IMU: &str = "test/black_box/imu";
+const LIDAR: &str = "test/black_box/lidar";
+const RADAR: &str = "test/black_box/radar";
+
+fn black_box_manager(
+    data_dir: &TempDir,
+    configure: impl FnOnce(&mut RecorderConfig),
+) -> (Arc<Session>, RecorderManager) {
+    let mut config = common::filesystem_config(data_dir.path());
+    configure(&mut config);
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session.clone(), config);
+    (session, manager)
+}
+
+fn dump_request(topics: &[&str]) -> RecorderRequest {
+    RecorderRequest {
+        command: RecorderCommand::Dump,
+        scene: Some("incident".to_string()),
+        compression_type: CompressionType::None,
+        ..common::start_request(topics)
+    }
+}
+
+/// Payloads stored in an entry, in timestamp order
+fn stored_payloads(data_dir: &Path, topic: &str) -> Vec<String> {
+    let Ok(files) = std::fs::read_dir(data_dir.join(topic_to_entry_name(topic))) else {
+        return vec![];
+    };
+    let mut files: Vec<_> = files
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mcap"))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .flat_map(|path| parse_batch(&std::fs::read(path).unwrap()).unwrap().messages)
+        .map(|message| String::from_utf8(message.payload).unwrap())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dump_stores_ring_contents() {
+    let data_dir = TempDir::new().unwrap();
+    let (session, manager) = black_box_manager(&data_dir, |config| {
+        config.recorder.black_box.topics = vec![CAMERA.to_string(), IMU.to_string()];
+        // Room for two 7-byte frames
+        config.recorder.black_box.max_bytes_per_topic = 16;
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    for i in 1..=5 {
+        session.put(CAMERA, format!("frame-{}", i)).wait().unwrap();
+    }
+    session.put(IMU, "imu-1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Nothing is written before the dump
+    assert!(stored_payloads(data_dir.path(), CAMERA).is_empty());
+
+    let response = manager.dump(dump_request(&[])).await;
+    assert!(response.success, "{}", response.message);
+    assert_eq!(response.status, Some(RecordingStatus::Uploading));
+    let recording_id = response.recording_id.unwrap();
+    assert!(manager.complete_finish(&recording_id).await.success);
+
+    assert_eq!(
+        stored_payloads(data_dir.path(), CAMERA),
+        vec!["frame-4", "frame-5"]
+    );
+    assert_eq!(stored_payloads(data_dir.path(), IMU), vec!["imu-1"]);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.status, RecordingStatus::Finished);
+    assert_eq!(status.scene.as_deref(), Some("incident"));
+
+    // The rings keep filling after a dump, and a dump can take some of them
+    session.put(CAMERA, "frame-6").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let response = manager.dump(dump_request(&[CAMERA])).await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    assert!(manager.complete_finish(&recording_id).await.success);
+    assert_eq!(
+        stored_payloads(data_dir.path(), CAMERA),
+        vec!["frame-4", "frame-5", "frame-5", "frame-6"]
+    );
+    assert_eq!(stored_payloads(data_dir.path(), IMU), vec!["imu-1"]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dump_rejections() {
+    let data_dir = TempDir::new().unwrap();
+
+    // No black box configured
+    let (_session, manager) = black_box_manager(&data_dir, |_| {});
+    let response = manager.dump(dump_request(&[])).await;
+    assert_eq!(response.error_code, Some(ErrorCode::InvalidState));
+
+    let (_session, manager) = black_box_manager(&data_dir, |config| {
+        config.recorder.black_box.topics = vec![RADAR.to_string()];
+    });
+
+    // Only the black box topics can be dumped
+    let response = manager.dump(dump_request(&[LIDAR])).await;
+    assert_eq!(response.error_code, Some(ErrorCode::InvalidRequest));
+    assert_eq!(response.errors[0].field, "topics[0]");
+
+    // Nothing received yet
+    let response = manager.dump(dump_request(&[])).await;
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
+    assert!(manager.recording_statuses().await.is_empty());
+}
@@ -0,0 +1,135 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for per-recording bucket selection
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::{topic_to_entry_name, validate_bucket_name};
+
+fn create_test_manager(session: Arc<zenoh::Session>, data_dir: &Path) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+
+    common::create_test_manager(session, config)
+}
+
+fn start_request(topic: &str, bucket: Option<&str>) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        bucket: bucket.map(String::from),
+        ..common::start_request(&[topic])
+    }
+}
+
+fn count_files(dir: &Path, extension: &str) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter(|e| {
+                    e.as_ref()
+                        .unwrap()
+                        .path()
+                        .extension()
+                        .is_some_and(|ext| ext == extension)
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_bucket_name_validation() {
+    assert!(validate_bucket_name("customer-a_01").is_ok());
+    assert!(validate_bucket_name("").is_err());
+    assert!(validate_bucket_name("../etc").is_err());
+    assert!(validate_bucket_name("a/b").is_err());
+    assert!(validate_bucket_name(&"x".repeat(65)).is_err());
+}
+
+#[test]
+fn test_bucket_request_parsing() {
+    let json =
+        r#"{"command": "start", "device_id": "d", "topics": ["/a"], "bucket": "customer_a"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.bucket.as_deref(), Some("customer_a"));
+
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"]}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.bucket, None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_written_to_requested_bucket() {
+    const TOPIC: &str = "test/bucket/customer";
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), data_dir.path());
+
+    let response = manager
+        .start_recording(start_request(TOPIC, Some("customer_a")))
+        .await;
+    assert!(response.success, "{}", response.message);
+    assert_eq!(response.bucket_name.as_deref(), Some("customer_a"));
+    let recording_id = response.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    for _ in 0..3 {
+        publisher.put(vec![1u8; 32]).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    manager.finish_recording(&recording_id).await;
+
+    let bucket_dir = data_dir.path().join("customer_a");
+    assert!(count_files(&bucket_dir.join(topic_to_entry_name(TOPIC)), "mcap") >= 3);
+    assert_eq!(
+        count_files(&bucket_dir.join("recordings_metadata"), "mcap"),
+        1
+    );
+
+    // Nothing lands in the default location
+    assert!(!data_dir.path().join(topic_to_entry_name(TOPIC)).exists());
+    assert!(!data_dir.path().join("recordings_metadata").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_default_and_invalid_bucket() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, data_dir.path());
+
+    // The configured filesystem base path has no bucket name
+    let response = manager
+        .start_recording(start_request("test/bucket/default", None))
+        .await;
+    assert!(response.success);
+    assert_eq!(response.bucket_name, None);
+
+    let response = manager
+        .start_recording(start_request("test/bucket/invalid", Some("../escape")))
+        .await;
+    assert!(!response.success);
+    assert!(response.message.contains("Invalid bucket name"));
+    assert!(!data_dir.path().parent().unwrap().join("escape").exists());
+}
@@ -0,0 +1,118 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the named buffer strategies
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{FlushPolicy, MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/buffer_strategy/odom";
+
+fn start_request(buffer_strategy: Option<BufferStrategy>) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some("strategic".to_string()),
+        compression_type: CompressionType::None,
+        buffer_strategy,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+#[test]
+fn test_strategy_thresholds() {
+    let policy = FlushPolicy::default();
+    let raw = policy.with_strategy(None);
+    assert_eq!(raw.max_buffer_size_bytes, policy.max_buffer_size_bytes);
+    assert_eq!(raw.strategy, None);
+
+    let low_latency = policy.with_strategy(Some(BufferStrategy::LowLatency));
+    assert_eq!(low_latency.max_buffer_duration_seconds, 1);
+    assert!(low_latency.max_buffer_size_bytes < policy.max_buffer_size_bytes);
+
+    let high_throughput = policy.with_strategy(Some(BufferStrategy::HighThroughput));
+    assert!(high_throughput.max_buffer_size_bytes > policy.max_buffer_size_bytes);
+    assert!(high_throughput.max_buffer_duration_seconds > policy.max_buffer_duration_seconds);
+    assert!(high_throughput.preallocate);
+
+    let low_memory = policy.with_strategy(Some(BufferStrategy::LowMemory));
+    assert!(!low_memory.preallocate);
+    assert!(low_memory.idle_trim_seconds < policy.idle_trim_seconds);
+
+    // The configured strategy applies unless a Start picks another one
+    let mut config = RecorderConfig::default();
+    config.recorder.flush_policy.strategy = Some(BufferStrategy::LowMemory);
+    let recorder = &config.recorder;
+    assert_eq!(
+        recorder.effective_flush_policy(None).strategy,
+        Some(BufferStrategy::LowMemory)
+    );
+    assert_eq!(
+        recorder
+            .effective_flush_policy(Some(BufferStrategy::LowLatency))
+            .strategy,
+        Some(BufferStrategy::LowLatency)
+    );
+
+    // Low-power mode still stretches the flush interval
+    config.recorder.low_power.enabled = true;
+    config.recorder.low_power.flush_interval_seconds = 30;
+    let policy = config
+        .recorder
+        .effective_flush_policy(Some(BufferStrategy::LowLatency));
+    assert_eq!(policy.max_buffer_duration_seconds, 30);
+    assert_eq!(policy.idle_trim_seconds, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_low_latency_recording_flushes_within_a_second() {
+    // 10 s between flushes unless the Start asks for low latency
+    let config = RecorderConfig::default();
+    assert_eq!(config.recorder.flush_policy.max_buffer_duration_seconds, 10);
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    let response = manager
+        .start_recording(start_request(Some(BufferStrategy::LowLatency)))
+        .await;
+    assert!(response.success, "{}", response.message);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, "pose").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+
+    // Stored while the recording still runs
+    assert_eq!(backend.records(&topic_to_entry_name(TOPIC)).len(), 1);
+
+    assert!(manager.finish_recording("strategic").await.success);
+    manager
+        .wait_for_completion("strategic", Duration::from_secs(5))
+        .await;
+    let metadata: RecordingMetadata = serde_json::from_slice(
+        &backend
+            .records("recordings_metadata")
+            .last()
+            .unwrap()
+            .1
+            .data,
+    )
+    .unwrap();
+    assert_eq!(metadata.buffer_strategy, Some(BufferStrategy::LowLatency));
+}
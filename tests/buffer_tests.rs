@@ -12,12 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crossbeam::queue::ArrayQueue;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, UNIX_EPOCH};
 use zenoh::key_expr::KeyExpr;
 use zenoh::sample::Sample;
 use zenoh_recorder::buffer::{FlushTask, TopicBuffer};
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::config::{
+    DedupePolicy, MissingTimestampPolicy, TimestampPolicy, TimestampSource, TimestampUnit,
+};
+use zenoh_recorder::flush_pool::FlushPool;
+use zenoh_recorder::protocol::{FlushCounts, FlushReason};
 
 fn create_sample(topic: &'static str, data: Vec<u8>) -> Sample {
     use zenoh::sample::SampleBuilder;
@@ -27,7 +32,7 @@ fn create_sample(topic: &'static str, data: Vec<u8>) -> Sample {
 
 #[tokio::test]
 async fn test_topic_buffer_creation() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -43,7 +48,7 @@ async fn test_topic_buffer_creation() {
 
 #[tokio::test]
 async fn test_topic_buffer_push_sample() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -62,7 +67,7 @@ async fn test_topic_buffer_push_sample() {
 
 #[tokio::test]
 async fn test_topic_buffer_size_trigger() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -86,7 +91,7 @@ async fn test_topic_buffer_size_trigger() {
 
 #[tokio::test]
 async fn test_topic_buffer_force_flush() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -120,6 +125,8 @@ fn test_flush_task_creation() {
         topic: "/test".to_string(),
         samples,
         recording_id: "rec-001".to_string(),
+        timestamps_ns: vec![],
+        received_ns: vec![],
     };
 
     assert_eq!(task.topic, "/test");
@@ -129,7 +136,7 @@ fn test_flush_task_creation() {
 
 #[tokio::test]
 async fn test_buffer_stats_accuracy() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -152,7 +159,7 @@ async fn test_buffer_stats_accuracy() {
 
 #[tokio::test]
 async fn test_multiple_pushes() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -177,7 +184,7 @@ async fn test_multiple_pushes() {
 
 #[tokio::test]
 async fn test_concurrent_pushes() {
-    let flush_queue = Arc::new(ArrayQueue::new(100));
+    let flush_queue = Arc::new(FlushPool::new(1, 100));
     let buffer = Arc::new(TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -208,3 +215,402 @@ async fn test_concurrent_pushes() {
     let (samples, _bytes) = buffer.stats();
     assert_eq!(samples, 50); // 5 tasks * 10 samples
 }
+
+#[tokio::test]
+async fn test_missing_timestamp_receive_time() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    );
+
+    // Locally built samples carry no publisher timestamp
+    buffer
+        .push_sample(create_sample("test/topic", b"data".to_vec()))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+
+    let task = flush_queue.try_pop(0).unwrap();
+    assert_eq!(task.samples.len(), 1);
+    assert_eq!(task.timestamps_ns.len(), 1);
+    assert!(task.timestamps_ns[0] > 0);
+    assert_eq!(buffer.timestamp_stats(), (1, 0));
+}
+
+#[tokio::test]
+async fn test_missing_timestamp_reject() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    )
+    .with_timestamp_policy(TimestampPolicy {
+        missing: MissingTimestampPolicy::Reject,
+        ..Default::default()
+    });
+
+    for _ in 0..3 {
+        buffer
+            .push_sample(create_sample("test/topic", b"data".to_vec()))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(buffer.stats(), (0, 0));
+    assert_eq!(buffer.timestamp_stats(), (3, 3));
+}
+
+#[tokio::test]
+async fn test_missing_timestamp_payload_field() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    )
+    .with_timestamp_policy(TimestampPolicy {
+        missing: MissingTimestampPolicy::PayloadField,
+        payload_field: Some("header.stamp_ms".to_string()),
+        payload_field_unit: TimestampUnit::Ms,
+        ..Default::default()
+    });
+
+    let payload = br#"{"header": {"stamp_ms": 1700000000123}, "x": 1.0}"#.to_vec();
+    buffer
+        .push_sample(create_sample("test/topic", payload))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+
+    let task = flush_queue.try_pop(0).unwrap();
+    assert_eq!(task.timestamps_ns, vec![1_700_000_000_123_000_000]);
+    assert_eq!(buffer.timestamp_stats(), (1, 0));
+}
+
+#[tokio::test]
+async fn test_timestamp_source_payload_field() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    )
+    .with_timestamp_policy(TimestampPolicy {
+        source: TimestampSource::PayloadField,
+        missing: MissingTimestampPolicy::Reject,
+        payload_field: Some("stamp".to_string()),
+        payload_field_unit: TimestampUnit::S,
+        ..Default::default()
+    });
+
+    buffer
+        .push_sample(create_sample(
+            "test/topic",
+            br#"{"stamp": 1700000000}"#.to_vec(),
+        ))
+        .await
+        .unwrap();
+    // Without the field the sample falls back to its Zenoh timestamp, and
+    // without that to the `missing` policy
+    buffer
+        .push_sample(create_sample("test/topic", br#"{"x": 1}"#.to_vec()))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+
+    let task = flush_queue.try_pop(0).unwrap();
+    assert_eq!(task.timestamps_ns, vec![1_700_000_000_000_000_000]);
+    assert_eq!(buffer.timestamp_stats(), (1, 1));
+}
+
+#[tokio::test]
+async fn test_timestamp_source_receive_time() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    )
+    .with_timestamp_policy(TimestampPolicy {
+        source: TimestampSource::ReceiveTime,
+        missing: MissingTimestampPolicy::Reject,
+        ..Default::default()
+    });
+
+    buffer
+        .push_sample(create_sample("test/topic", b"data".to_vec()))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+
+    // Stamped on arrival, so never missing
+    let task = flush_queue.try_pop(0).unwrap();
+    assert_eq!(task.timestamps_ns, task.received_ns);
+    assert_eq!(buffer.timestamp_stats(), (0, 0));
+
+    // Arrival is read from the buffer's clock
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let buffer = buffer.with_clock(clock.clone());
+    clock.advance(Duration::from_nanos(42));
+    buffer
+        .push_sample(create_sample("test/topic", b"data".to_vec()))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+    let task = flush_queue.try_pop(0).unwrap();
+    assert_eq!(task.timestamps_ns, vec![1_700_000_000_000_000_042]);
+}
+
+#[tokio::test]
+async fn test_ingest_stats_count_dropped_flushes() {
+    let flush_queue = Arc::new(FlushPool::new(1, 1));
+    let buffer = TopicBuffer::new(
+        "test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    );
+    assert_eq!(buffer.ingest_stats(), (0, 0, None));
+
+    for i in 0..3 {
+        let sample = create_sample("test/topic", format!("data_{}", i).into_bytes());
+        buffer.push_sample(sample).await.unwrap();
+    }
+    buffer.force_flush().await.unwrap();
+    buffer
+        .push_sample(create_sample("test/topic", b"late".to_vec()))
+        .await
+        .unwrap();
+    // The queue still holds the first task, so this one is dropped
+    buffer.force_flush().await.unwrap();
+
+    let (received, dropped, last_sample_ns) = buffer.ingest_stats();
+    assert_eq!(received, 4);
+    assert_eq!(dropped, 1);
+    assert!(last_sample_ns.is_some());
+    assert_eq!(flush_queue.len(), 1);
+}
+
+#[tokio::test]
+async fn test_trim_releases_spare_capacity() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue,
+    )
+    .with_capacity(100);
+    assert_eq!(buffer.idle_trim(), None);
+    buffer.set_idle_trim(Duration::from_secs(60));
+    assert_eq!(buffer.idle_trim(), Some(Duration::from_secs(60)));
+
+    buffer
+        .push_sample(create_sample("test/topic", b"data".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(buffer.allocated_slots().await, 200);
+
+    // Buffered samples stay, the spare slots go
+    assert_eq!(buffer.trim().await, 199);
+    assert_eq!(buffer.allocated_slots().await, 1);
+    assert_eq!(buffer.stats(), (1, 4));
+    assert_eq!(buffer.trim().await, 0);
+
+    buffer.force_flush().await.unwrap();
+    assert_eq!(buffer.allocated_slots().await, 100);
+}
+
+#[tokio::test]
+async fn test_flush_if_due_without_new_samples() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let buffer = TopicBuffer::new(
+        "/test/topic".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(1),
+        flush_queue.clone(),
+    )
+    .with_clock(clock.clone());
+    assert_eq!(buffer.flush_due_in(), None);
+    assert!(!buffer.flush_if_due().await);
+
+    buffer
+        .push_sample(create_sample("test/topic", b"tail".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(buffer.flush_due_in(), Some(Duration::from_secs(1)));
+    clock.advance(Duration::from_millis(900));
+    assert!(!buffer.flush_if_due().await);
+
+    // The tail goes out once it is old enough, no further push needed
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(buffer.flush_due_in(), Some(Duration::ZERO));
+    assert!(buffer.flush_if_due().await);
+    assert_eq!(flush_queue.try_pop(0).unwrap().samples.len(), 1);
+    assert_eq!(buffer.flush_due_in(), None);
+}
+
+#[tokio::test]
+async fn test_dedupe_records_only_changes() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "robot/**".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue.clone(),
+    )
+    .with_dedupe(&DedupePolicy { heartbeat_ms: 0 });
+
+    for (key, payload) in [
+        ("robot/status", "idle"),
+        ("robot/status", "idle"),
+        ("robot/battery", "idle"), // Repeats are told apart by key
+        ("robot/status", "moving"),
+        ("robot/status", "moving"),
+        ("robot/status", "idle"),
+    ] {
+        buffer
+            .push_sample(create_sample(key, payload.as_bytes().to_vec()))
+            .await
+            .unwrap();
+    }
+    assert_eq!(buffer.ingest_stats().0, 6);
+    assert_eq!(buffer.duplicates_suppressed(), 2);
+
+    buffer.force_flush().await.unwrap();
+    let task = flush_queue.try_pop(0).unwrap();
+    let recorded: Vec<(String, Vec<u8>)> = task
+        .samples
+        .iter()
+        .map(|s| (s.key_expr().to_string(), s.payload().to_bytes().to_vec()))
+        .collect();
+    assert_eq!(
+        recorded,
+        vec![
+            ("robot/status".to_string(), b"idle".to_vec()),
+            ("robot/battery".to_string(), b"idle".to_vec()),
+            ("robot/status".to_string(), b"moving".to_vec()),
+            ("robot/status".to_string(), b"idle".to_vec()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dedupe_heartbeat_keeps_repeat() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let buffer = TopicBuffer::new(
+        "robot/status".to_string(),
+        "rec-123".to_string(),
+        1024 * 1024,
+        Duration::from_secs(10),
+        flush_queue,
+    )
+    .with_dedupe(&DedupePolicy { heartbeat_ms: 200 });
+    // Nothing repeated yet
+    assert_eq!(buffer.duplicates_suppressed(), 0);
+
+    for _ in 0..3 {
+        buffer
+            .push_sample(create_sample("robot/status", b"ok".to_vec()))
+            .await
+            .unwrap();
+    }
+    assert_eq!(buffer.stats().0, 1);
+
+    // A repeat past the heartbeat interval is recorded
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    buffer
+        .push_sample(create_sample("robot/status", b"ok".to_vec()))
+        .await
+        .unwrap();
+    buffer
+        .push_sample(create_sample("robot/status", b"ok".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(buffer.stats().0, 2);
+    assert_eq!(buffer.duplicates_suppressed(), 3);
+}
+
+#[tokio::test]
+async fn test_flush_reasons_and_high_watermark() {
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let buffer = TopicBuffer::new(
+        "test/topic".to_string(),
+        "rec-123".to_string(),
+        100,
+        Duration::from_secs(1),
+        flush_queue,
+    )
+    .with_clock(clock.clone());
+    assert_eq!(buffer.flush_stats(), (FlushCounts::default(), None));
+
+    // 60 + 60 bytes reach the size threshold
+    for _ in 0..2 {
+        buffer
+            .push_sample(create_sample("test/topic", vec![0u8; 60]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(buffer.high_watermark_bytes(), 120);
+    assert_eq!(buffer.flush_stats().1, Some(FlushReason::Size));
+
+    buffer
+        .push_sample(create_sample("test/topic", vec![0u8; 10]))
+        .await
+        .unwrap();
+    clock.advance(Duration::from_secs(1));
+    assert!(buffer.flush_if_due().await);
+    assert_eq!(buffer.flush_stats().1, Some(FlushReason::Time));
+
+    buffer
+        .push_sample(create_sample("test/topic", vec![0u8; 10]))
+        .await
+        .unwrap();
+    buffer.force_flush().await.unwrap();
+    buffer
+        .push_sample(create_sample("test/topic", vec![0u8; 10]))
+        .await
+        .unwrap();
+    buffer.flush(FlushReason::Shutdown).await.unwrap();
+    // Nothing buffered, nothing counted
+    buffer.force_flush().await.unwrap();
+
+    assert_eq!(
+        buffer.flush_stats(),
+        (
+            FlushCounts {
+                size: 1,
+                time: 1,
+                forced: 1,
+                shutdown: 1,
+            },
+            Some(FlushReason::Shutdown)
+        )
+    );
+    assert_eq!(buffer.high_watermark_bytes(), 120);
+}
@@ -0,0 +1,99 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for recordings timed by a mock clock
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::{topic_to_entry_name, RecorderManagerBuilder};
+
+fn start_request() -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some("clocked".to_string()),
+        compression_type: CompressionType::None,
+        ..common::start_request(&["test/clock/odom"])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_times_come_from_the_clock() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = Arc::new(MockClock::new(start));
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let manager = RecorderManagerBuilder::new(RecorderConfig::default())
+        .storage_backend(backend.clone())
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    assert!(manager.start_recording(start_request()).await.success);
+    clock.advance(Duration::from_secs(90));
+    assert!(manager.finish_recording("clocked").await.success);
+    manager
+        .wait_for_completion("clocked", Duration::from_secs(5))
+        .await;
+
+    let metadata: RecordingMetadata = serde_json::from_slice(
+        &backend
+            .records("recordings_metadata")
+            .last()
+            .unwrap()
+            .1
+            .data,
+    )
+    .unwrap();
+    assert_eq!(metadata.start_time, "2023-11-14T22:13:20+00:00");
+    assert_eq!(
+        metadata.end_time.as_deref(),
+        Some("2023-11-14T22:14:50+00:00")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_record_timestamps_come_from_the_clock() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = Arc::new(MockClock::new(start));
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let manager = RecorderManagerBuilder::new(RecorderConfig::default())
+        .storage_backend(backend.clone())
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    assert!(manager.start_recording(start_request()).await.success);
+    manager
+        .ingest_sample("test/clock/odom", b"pose".to_vec(), 1)
+        .await
+        .unwrap();
+    clock.advance(Duration::from_secs(90));
+    assert!(manager.finish_recording("clocked").await.success);
+    manager
+        .wait_for_completion("clocked", Duration::from_secs(5))
+        .await;
+
+    // Flushed at the finish, by the clock
+    let records = backend.records(&topic_to_entry_name("test/clock/odom"));
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].0, 1_700_000_090_000_000);
+    let metadata = backend.records("recordings_metadata");
+    assert_eq!(metadata.last().unwrap().0, 1_700_000_000_000_000);
+}
@@ -0,0 +1,185 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for coalescing small batches of many topics into shared records
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Wait};
+use zenoh_recorder::coalesce::{self, COALESCED_ENTRY};
+use zenoh_recorder::config::{
+    BackendConfig, FilesystemConfig, MockConfig, RecorderConfig, StorageConfig,
+};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay::{self, ReplayOptions};
+use zenoh_recorder::storage::repair::repair;
+use zenoh_recorder::storage::{BackendFactory, MockBackend, StorageBackend};
+use zenoh_recorder::topic_to_entry_name;
+
+fn topics(prefix: &str) -> Vec<String> {
+    (0..4).map(|i| format!("{}/sensor{}", prefix, i)).collect()
+}
+
+fn create_test_config() -> RecorderConfig {
+    let mut config = RecorderConfig::default();
+    // Flush every sample as its own (small) batch
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.coalesce.enabled = true;
+    config.recorder.coalesce.interval_ms = 300;
+    config
+}
+
+fn start_request(recording_id: &str, topics: Vec<String>) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some(recording_id.to_string()),
+        topics,
+        compression_type: CompressionType::None,
+        ..common::start_request(&[])
+    }
+}
+
+/// Payloads of the recorded batches, read through the recording metadata
+async fn recorded_payloads(
+    storage: &dyn StorageBackend,
+    metadata: &RecordingMetadata,
+) -> Vec<String> {
+    let mut payloads = Vec::new();
+    for record in &metadata.records {
+        let data = coalesce::read(storage, record).await.unwrap();
+        let batch = parse_batch(&data).unwrap();
+        assert_eq!(batch.topic, record.topic);
+        payloads.extend(
+            batch
+                .messages
+                .into_iter()
+                .map(|m| String::from_utf8(m.payload).unwrap()),
+        );
+    }
+    payloads.sort();
+    payloads
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sparse_topics_share_records() {
+    let topics = topics("test/coalesce/sparse");
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), create_test_config());
+
+    assert!(
+        manager
+            .start_recording(start_request("sparse", topics.clone()))
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for topic in &topics {
+        session.put(topic, topic.clone()).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(manager.finish_recording("sparse").await.success);
+    manager
+        .wait_for_completion("sparse", Duration::from_secs(5))
+        .await;
+
+    // No record of its own for any topic
+    let coalesced = backend.records(COALESCED_ENTRY);
+    assert!(!coalesced.is_empty());
+    assert!(coalesced.len() < topics.len());
+    for topic in &topics {
+        assert!(backend.records(&topic_to_entry_name(topic)).is_empty());
+    }
+    let (_, record) = &coalesced[0];
+    assert_eq!(record.labels["format"], "coalesced");
+    assert_eq!(
+        coalesce::decode(&record.data).unwrap().recording_id,
+        "sparse"
+    );
+
+    // The metadata lists every batch, each read from its coalesced record
+    let metadata = replay::find_recording(&*backend, "sparse").await.unwrap();
+    assert_eq!(metadata.records.len(), topics.len());
+    assert!(metadata.records.iter().all(|r| r.coalesced.is_some()));
+    assert_eq!(recorded_payloads(&*backend, &metadata).await, topics);
+
+    // Replays read them the same way
+    let replayed = session
+        .declare_subscriber("test/coalesce/sparse/**")
+        .wait()
+        .unwrap();
+    let summary = replay::replay(&session, &*backend, &metadata, &ReplayOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(summary.messages, topics.len());
+    assert_eq!(summary.skipped_records, 0);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut keys = Vec::new();
+    while let Ok(Some(sample)) = replayed.try_recv() {
+        keys.push(sample.key_expr().to_string());
+    }
+    keys.sort();
+    assert_eq!(keys, topics);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_repair_lists_coalesced_batches() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = create_test_config();
+    config.storage = StorageConfig {
+        backend: "filesystem".to_string(),
+        backend_config: BackendConfig::Filesystem {
+            filesystem: FilesystemConfig {
+                base_path: data_dir.path().to_string_lossy().to_string(),
+                file_format: "mcap".to_string(),
+                ..Default::default()
+            },
+        },
+    };
+    let topics = topics("test/coalesce/repair");
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    assert!(
+        manager
+            .start_recording(start_request("interrupted", topics.clone()))
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for topic in &topics {
+        session.put(topic, topic.clone()).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    // The recorder dies here: no Finish, no metadata record
+    drop(manager);
+
+    let report = repair(data_dir.path(), "mcap").await.unwrap();
+    assert_eq!(report.torn_records, 0);
+    assert_eq!(report.rebuilt_recordings, vec!["interrupted".to_string()]);
+
+    let metadata = replay::find_recording(&*storage, "interrupted")
+        .await
+        .unwrap();
+    assert!(metadata.interrupted);
+    assert_eq!(metadata.topics, topics);
+    assert_eq!(metadata.total_samples, topics.len() as i64);
+    assert!(metadata.records.iter().all(|r| r.coalesced.is_some()));
+    assert_eq!(recorded_payloads(&*storage, &metadata).await, topics);
+}
@@ -0,0 +1,92 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Not every test crate uses every helper
+#![allow(dead_code)]
+
+/// Helpers shared by the integration tests
+///
+use std::path::Path;
+use std::sync::Arc;
+use zenoh::Session;
+use zenoh_recorder::config::{BackendConfig, FilesystemConfig, RecorderConfig, StorageConfig};
+use zenoh_recorder::protocol::{RecorderRequest, RecordingMetadata};
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+
+/// Default config storing MCAP records below `base_path`
+pub fn filesystem_config(base_path: impl AsRef<Path>) -> RecorderConfig {
+    RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: base_path.as_ref().to_string_lossy().to_string(),
+                    file_format: "mcap".to_string(),
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    }
+}
+
+/// `filesystem_config` flushing every sample as its own record
+pub fn per_sample_config(base_path: impl AsRef<Path>) -> RecorderConfig {
+    let mut config = filesystem_config(base_path);
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config
+}
+
+/// Manager storing to the backend of `config`
+pub fn create_test_manager(session: Arc<Session>, config: RecorderConfig) -> RecorderManager {
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    RecorderManager::new(session, storage, config)
+}
+
+/// Start of `topics` with every other field at its default
+pub fn start_request(topics: &[&str]) -> RecorderRequest {
+    RecorderRequest {
+        device_id: "device".to_string(),
+        topics: topics.iter().map(|topic| topic.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// Metadata of the only recording stored below `data_dir` (filesystem backend)
+pub fn read_metadata(data_dir: &Path) -> RecordingMetadata {
+    read_all_metadata(data_dir)
+        .pop()
+        .expect("metadata record written")
+}
+
+/// Metadata of `recording_id` stored below `data_dir` (filesystem backend)
+pub fn find_metadata(data_dir: &Path, recording_id: &str) -> RecordingMetadata {
+    read_all_metadata(data_dir)
+        .into_iter()
+        .find(|metadata| metadata.recording_id == recording_id)
+        .expect("metadata record written")
+}
+
+/// Metadata of every recording stored below `data_dir` (filesystem backend)
+pub fn read_all_metadata(data_dir: &Path) -> Vec<RecordingMetadata> {
+    std::fs::read_dir(data_dir.join("recordings_metadata"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mcap"))
+        .map(|path| serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap())
+        .collect()
+}
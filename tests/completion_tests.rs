@@ -0,0 +1,189 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Finish returning before the upload and wait_for_completion
+///
+mod common;
+
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::{BackendFactory, StorageBackend};
+
+const TOPIC: &str = "test/completion/camera";
+
+fn create_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &TempDir,
+) -> (RecorderManager, Arc<dyn StorageBackend>) {
+    let config = common::filesystem_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    (
+        RecorderManager::new(session, storage.clone(), config),
+        storage,
+    )
+}
+
+fn request(command: RecorderCommand, device_id: &str) -> RecorderRequest {
+    RecorderRequest {
+        command,
+        device_id: device_id.to_string(),
+        topics: vec![TOPIC.to_string()],
+        ..Default::default()
+    }
+}
+
+async fn query<T: DeserializeOwned>(
+    session: &zenoh::Session,
+    key: &str,
+    request: &RecorderRequest,
+) -> T {
+    let replies = session
+        .get(key)
+        .payload(serde_json::to_vec(request).unwrap())
+        .timeout(Duration::from_secs(10))
+        .await
+        .unwrap();
+    let reply = replies.recv_async().await.unwrap();
+    serde_json::from_slice(&reply.result().unwrap().payload().to_bytes()).unwrap()
+}
+
+#[test]
+fn test_wait_for_completion_request() {
+    let json = r#"{"command": "wait_for_completion", "recording_id": "r", "device_id": "d",
+                   "wait_timeout_ms": 30000}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(
+        request.command,
+        RecorderCommand::WaitForCompletion
+    ));
+    assert_eq!(request.wait_timeout_ms, Some(30000));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_finish_query_returns_while_uploading() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let (manager, storage) = create_manager(session.clone(), &data_dir);
+    let manager = Arc::new(manager);
+    let device_id = "completion_device";
+    let control = ControlInterface::new(session.clone(), manager.clone(), device_id.to_string());
+    tokio::spawn(async move { control.run().await });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let key = format!("recorder/control/{}", device_id);
+    let started: RecorderResponse =
+        query(&session, &key, &request(RecorderCommand::Start, device_id)).await;
+    let recording_id = started.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, "frame").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mut finish = request(RecorderCommand::Finish, device_id);
+    finish.recording_id = Some(recording_id.clone());
+    let finished: RecorderResponse = query(&session, &key, &finish).await;
+    assert!(finished.success, "{}", finished.message);
+    assert_eq!(finished.status, Some(RecordingStatus::Uploading));
+
+    let mut wait = request(RecorderCommand::WaitForCompletion, device_id);
+    wait.recording_id = Some(recording_id.clone());
+    wait.wait_timeout_ms = Some(5000);
+    let completion: CompletionResponse = query(&session, &key, &wait).await;
+    assert!(completion.success);
+    assert!(completion.completed, "{}", completion.message);
+    assert_eq!(completion.status, RecordingStatus::Finished);
+    assert_eq!(completion.upload_percent, 100.0);
+    assert_eq!(completion.backlog_bytes, 0);
+
+    // The metadata is written by the time the upload is reported complete
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.records.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_progress_reported_while_uploading() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let (manager, _storage) = create_manager(session.clone(), &data_dir);
+    let manager = Arc::new(manager);
+
+    let recording_id = manager
+        .start_recording(request(RecorderCommand::Start, "device"))
+        .await
+        .recording_id
+        .unwrap();
+    assert_eq!(manager.get_status(&recording_id).await.upload_percent, None);
+
+    assert!(manager.begin_finish(&recording_id).await.success);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.status, RecordingStatus::Uploading);
+    assert!(status.upload_percent.is_some());
+
+    // Nothing completes the upload yet
+    let completion = manager
+        .wait_for_completion(&recording_id, Duration::from_millis(100))
+        .await;
+    assert!(completion.success);
+    assert!(!completion.completed);
+    assert_eq!(completion.status, RecordingStatus::Uploading);
+
+    let waiter = {
+        let manager = manager.clone();
+        let recording_id = recording_id.clone();
+        tokio::spawn(async move {
+            manager
+                .wait_for_completion(&recording_id, Duration::from_secs(10))
+                .await
+        })
+    };
+    assert!(manager.complete_finish(&recording_id).await.success);
+    let completion = waiter.await.unwrap();
+    assert!(completion.completed);
+    assert_eq!(completion.upload_percent, 100.0);
+    assert_eq!(
+        manager.get_status(&recording_id).await.upload_percent,
+        Some(100.0)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_finish_only_once() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let (manager, _storage) = create_manager(session, &data_dir);
+
+    let recording_id = manager
+        .start_recording(request(RecorderCommand::Start, "device"))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.begin_finish(&recording_id).await.success);
+    assert!(!manager.begin_finish(&recording_id).await.success);
+    assert!(manager.complete_finish(&recording_id).await.success);
+    assert!(!manager.finish_recording(&recording_id).await.success);
+
+    let unknown = manager
+        .wait_for_completion("missing", Duration::from_secs(1))
+        .await;
+    assert!(!unknown.success);
+}
@@ -14,7 +14,7 @@
 
 /// Comprehensive tests targeting uncovered code paths
 ///
-use crossbeam::queue::ArrayQueue;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use zenoh::key_expr::KeyExpr;
@@ -24,6 +24,7 @@ use zenoh::Wait;
 use zenoh_recorder::buffer::TopicBuffer;
 use zenoh_recorder::config::{BackendConfig, RecorderConfig, ReductStoreConfig, StorageConfig};
 use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::flush_pool::FlushPool;
 use zenoh_recorder::mcap_writer::McapSerializer;
 use zenoh_recorder::protocol::*;
 use zenoh_recorder::recorder::RecorderManager;
@@ -54,6 +55,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -72,7 +77,7 @@ fn create_test_recorder_manager(
 // Buffer edge cases
 #[tokio::test]
 async fn test_buffer_with_zero_max_size() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -92,7 +97,7 @@ async fn test_buffer_with_zero_max_size() {
 
 #[tokio::test]
 async fn test_buffer_with_very_long_duration() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -110,7 +115,7 @@ async fn test_buffer_with_very_long_duration() {
 
 #[tokio::test]
 async fn test_buffer_full_queue() {
-    let flush_queue = Arc::new(ArrayQueue::new(2)); // Small queue
+    let flush_queue = Arc::new(FlushPool::new(1, 2)); // Small queue
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -176,17 +181,10 @@ async fn test_double_pause() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/double".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -217,17 +215,10 @@ async fn test_resume_without_pause() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/resume".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -253,17 +244,10 @@ async fn test_finish_after_cancel() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/cancel_then_finish".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -299,6 +283,10 @@ fn test_storage_client_with_different_configs() {
             api_token: None,
             timeout_seconds: 300,
             max_retries: 3,
+            max_label_bytes: 8192,
+            batch_max_records: 32,
+            tls: Default::default(),
+            bucket_settings: Default::default(),
         };
         let client = ReductStoreBackend::new(config);
         if let Ok(client) = client {
@@ -313,17 +301,10 @@ fn test_request_with_all_skills() {
     let skills: Vec<String> = (0..100).map(|i| format!("skill_{}", i)).collect();
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("test".to_string()),
         skills: skills.clone(),
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
-        topics: vec![],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     assert_eq!(request.skills.len(), 100);
@@ -349,6 +330,19 @@ fn test_status_response_serialization_all_fields() {
         active_topics: vec!["/t1".to_string(), "/t2".to_string(), "/t3".to_string()],
         buffer_size_bytes: 123456,
         total_recorded_bytes: 9876543210,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -379,7 +373,32 @@ fn test_recording_metadata_json_serialization() {
         end_time: Some("2024-01-01T01:00:00Z".to_string()),
         total_bytes: 1000000,
         total_samples: 50000,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({"test": "data"}),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     let json = serde_json::to_string_pretty(&metadata).unwrap();
@@ -450,17 +469,10 @@ async fn test_recorder_with_all_compression_types() {
         );
 
         let request = RecorderRequest {
-            command: RecorderCommand::Start,
-            recording_id: None,
-            scene: None,
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: "device".to_string(),
-            data_collector_id: None,
             topics: vec!["test/compression".to_string()],
-            compression_level: CompressionLevel::Default,
             compression_type: comp_type,
+            ..Default::default()
         };
 
         let response = manager.start_recording(request).await;
@@ -513,24 +525,17 @@ async fn test_shutdown_with_active_recordings() {
 
     // Start a recording
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/shutdown".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let _response = manager.start_recording(request).await;
     tokio::time::sleep(Duration::from_millis(50)).await;
 
     // Shutdown should finish active recordings
-    let result = manager.shutdown().await;
+    let result = manager.shutdown(None).await;
     assert!(result.is_ok());
 }
 
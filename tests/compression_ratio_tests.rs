@@ -0,0 +1,111 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the raw and compressed byte accounting of recordings
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig, TopicCompression};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+
+const LOGS: &str = "test/compression_ratio/logs";
+const NOISE: &str = "test/compression_ratio/noise";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_compressed_bytes_reported_per_topic() {
+    let mut config = RecorderConfig::default();
+    // Every sample is its own record
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.compression.per_topic.insert(
+        NOISE.to_string(),
+        TopicCompression {
+            r#type: "none".to_string(),
+            level: 0,
+            transform: None,
+        },
+    );
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("ratios".to_string()),
+                ..common::start_request(&[LOGS, NOISE])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..4 {
+        session
+            .put(
+                LOGS,
+                format!("{} {}", i, "all systems nominal ".repeat(200)),
+            )
+            .wait()
+            .unwrap();
+        session.put(NOISE, vec![i as u8; 64]).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let status = manager.get_status("ratios").await;
+    let logs = status.per_topic.iter().find(|t| t.topic == LOGS).unwrap();
+    assert!(logs.bytes_flushed > 16_000, "{:?}", logs);
+    assert!(logs.bytes_stored < logs.bytes_flushed / 10, "{:?}", logs);
+    assert!(logs.compression_ratio.unwrap() > 10.0, "{:?}", logs);
+    // Uncompressed records carry their serialization overhead
+    let noise = status.per_topic.iter().find(|t| t.topic == NOISE).unwrap();
+    assert_eq!(noise.bytes_flushed, 256);
+    assert!(noise.compression_ratio.unwrap() < 1.0, "{:?}", noise);
+
+    assert_eq!(
+        status.total_payload_bytes,
+        logs.bytes_flushed + noise.bytes_flushed
+    );
+    assert_eq!(
+        status.total_recorded_bytes as u64,
+        logs.bytes_stored + noise.bytes_stored
+    );
+    assert_eq!(
+        status.compression_ratio,
+        Some(status.total_payload_bytes as f64 / status.total_recorded_bytes as f64)
+    );
+
+    // The metadata keeps the same accounting
+    assert!(manager.finish_recording("ratios").await.success);
+    manager
+        .wait_for_completion("ratios", Duration::from_secs(5))
+        .await;
+    let metadata: RecordingMetadata = serde_json::from_slice(
+        &backend
+            .records("recordings_metadata")
+            .last()
+            .unwrap()
+            .1
+            .data,
+    )
+    .unwrap();
+    assert_eq!(metadata.payload_bytes, status.total_payload_bytes);
+    assert_eq!(metadata.compression_ratio, status.compression_ratio);
+    let stats = &metadata.per_topic_stats["test/compression_ratio/logs"];
+    assert_eq!(stats["raw_bytes"], logs.bytes_flushed);
+    assert_eq!(stats["compression_ratio"], logs.compression_ratio.unwrap());
+}
@@ -16,7 +16,13 @@
 
 use std::fs;
 use std::path::PathBuf;
-use zenoh_recorder::config::{load_config, RecorderConfig};
+use std::time::Duration;
+use zenoh_recorder::config::{
+    load_config, ConfigFormat, ConfigLoader, ConnectivityProbe, ControlRole, HookAction,
+    HookConfig, IntegrityConfig, PreviewPolicy, QuotaLimits, RecorderConfig, SampleTransformConfig,
+    TimestampPolicy, TimestampSource, TopicCompression, UploadDeferralConfig,
+};
+use zenoh_recorder::protocol::{CompressionLevel, CompressionType, RecordingEventKind};
 
 #[test]
 fn test_load_default_config() {
@@ -34,6 +40,9 @@ fn test_load_default_config() {
 
         // Verify defaults
         assert_eq!(config.zenoh.mode, "peer");
+        assert!(!config.zenoh.shared_memory);
+        assert!(!config.recorder.topology.snapshot);
+        assert_eq!(config.recorder.topology.timeout_ms, 500);
         assert_eq!(config.storage.backend, "reductstore");
         assert_eq!(config.recorder.flush_policy.max_buffer_size_bytes, 10485760);
         assert_eq!(config.recorder.flush_policy.max_buffer_duration_seconds, 10);
@@ -180,6 +189,10 @@ fn test_backend_factory() {
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -208,3 +221,822 @@ fn test_config_defaults() {
     assert_eq!(config.recorder.workers.queue_capacity, 1000);
     assert_eq!(config.logging.level, "info");
 }
+
+#[test]
+fn test_verify_percent_spreads_reads() {
+    let integrity = IntegrityConfig {
+        verify_every: 0,
+        verify_percent: 25.0,
+    };
+    let picked: Vec<u64> = (1..=12).filter(|&b| integrity.should_verify(b)).collect();
+    assert_eq!(picked, vec![4, 8, 12]);
+
+    let all = IntegrityConfig {
+        verify_every: 0,
+        verify_percent: 100.0,
+    };
+    assert!((1..=5).all(|b| all.should_verify(b)));
+    assert!(!(1..=100).any(|b| IntegrityConfig::default().should_verify(b)));
+
+    // Either setting picks a record
+    let both = IntegrityConfig {
+        verify_every: 3,
+        verify_percent: 25.0,
+    };
+    let picked: Vec<u64> = (1..=8).filter(|&b| both.should_verify(b)).collect();
+    assert_eq!(picked, vec![3, 4, 6, 8]);
+}
+
+#[test]
+fn test_verify_percent_out_of_range() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.integrity.verify_percent = 150.0;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("verify_percent"));
+}
+
+#[test]
+fn test_upload_deferral_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let deferral: UploadDeferralConfig = toml::from_str(
+        r#"
+enabled = true
+windows = ["22:00-06:00"]
+probes = [
+  { kind = "interface", name = "wlan0" },
+  { kind = "tcp", address = "upload.example.com:443" },
+]
+"#,
+    )
+    .unwrap();
+    assert!(deferral.enabled);
+    assert_eq!(deferral.check_interval_seconds, 30);
+    assert_eq!(
+        deferral.probes,
+        vec![
+            ConnectivityProbe::Interface {
+                name: "wlan0".to_string()
+            },
+            ConnectivityProbe::Tcp {
+                address: "upload.example.com:443".to_string(),
+                timeout_ms: 1000
+            },
+        ]
+    );
+
+    let mut config = RecorderConfig::default();
+    config.recorder.upload_deferral = deferral;
+    config.recorder.work_dir.enabled = true;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert!(load_config(&path).is_ok());
+
+    // Deferred records are kept in the work directory
+    config.recorder.work_dir.enabled = false;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("work_dir"));
+
+    config.recorder.work_dir.enabled = true;
+    config.recorder.upload_deferral.windows = vec!["22:00".to_string()];
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("windows"));
+}
+
+#[test]
+fn test_low_power_profile() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.low_power.enabled = true;
+    config.recorder.schema.detect_drift = true;
+    config.recorder.preview.per_topic.insert(
+        "camera/**".to_string(),
+        toml::from_str::<PreviewPolicy>("").unwrap(),
+    );
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+
+    let settings = load_config(&path).unwrap().recorder;
+    assert_eq!(settings.flush_policy.max_buffer_duration_seconds, 60);
+    assert_eq!(settings.flush_policy.idle_trim_seconds, 0);
+    assert_eq!(settings.status_events.progress_interval_ms, 0);
+    assert!(!settings.schema.detect_drift);
+    assert!(settings.preview.per_topic.is_empty());
+    assert_eq!(
+        settings.low_power.wakeup_granularity(),
+        Some(std::time::Duration::from_secs(1))
+    );
+
+    // Longer flush intervals are kept; applying it again changes nothing
+    config.recorder.flush_policy.max_buffer_duration_seconds = 300;
+    let mut applied = config.recorder.clone();
+    applied.apply_low_power();
+    assert_eq!(applied.flush_policy.max_buffer_duration_seconds, 300);
+    let once = serde_json::to_value(&applied).unwrap();
+    applied.apply_low_power();
+    assert_eq!(once, serde_json::to_value(&applied).unwrap());
+
+    // Off by default
+    assert_eq!(
+        RecorderConfig::default()
+            .recorder
+            .low_power
+            .wakeup_granularity(),
+        None
+    );
+
+    config.recorder.low_power.flush_interval_seconds = 0;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("flush_interval_seconds"));
+}
+
+#[test]
+fn test_control_auth_config() {
+    // Off by default
+    assert!(!RecorderConfig::default().recorder.control.auth.enabled);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.control.auth = toml::from_str(
+        r#"
+        enabled = true
+        handoff_principal = "recorder"
+
+        [[principals]]
+        name = "dashboard"
+        token = "view-token"
+
+        [[principals]]
+        name = "recorder"
+        secret = "handoff-secret"
+        role = "admin"
+        "#,
+    )
+    .unwrap();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let auth = load_config(&path).unwrap().recorder.control.auth;
+    assert_eq!(auth.max_clock_skew_seconds, 300);
+    assert_eq!(auth.principals[0].role, ControlRole::Observer);
+    assert_eq!(auth.principals[1].role, ControlRole::Admin);
+
+    // The handoff principal must be allowed to hand off
+    config.recorder.control.auth.principals[1].role = ControlRole::Operator;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("must be an admin"));
+
+    // Principals need a way to prove who they are
+    config.recorder.control.auth.handoff_principal = None;
+    config.recorder.control.auth.principals[0].token = None;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("needs a token or a secret"));
+
+    // Enabled without principals would lock everyone out
+    config.recorder.control.auth.principals.clear();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("at least one principal"));
+}
+
+#[test]
+fn test_control_groups_config() {
+    let control = RecorderConfig::default().recorder.control;
+    assert!(control.groups.is_empty());
+    assert_eq!(control.group_max_delay_ms, 60_000);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.control.groups = vec!["fleet-a".to_string()];
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(
+        load_config(&path).unwrap().recorder.control.groups,
+        ["fleet-a"]
+    );
+
+    // Group names are a single key chunk
+    config.recorder.control.groups.push("fleet/*".to_string());
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("control.groups"));
+}
+
+#[test]
+fn test_ingestion_config() {
+    let ingestion = RecorderConfig::default().recorder.ingestion;
+    assert!(!ingestion.enabled);
+    assert_eq!(ingestion.chunk_size_bytes, 8 * 1024 * 1024);
+    assert_eq!(ingestion.max_retries, 3);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.ingestion = toml::from_str(
+        r#"
+        enabled = true
+        endpoint = "https://ingest.example.com/api/v1"
+        token_url = "https://auth.example.com/oauth/token"
+        client_id = "robot-01"
+        client_secret = "secret"
+        "#,
+    )
+    .unwrap();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let ingestion = load_config(&path).unwrap().recorder.ingestion;
+    assert_eq!(ingestion.client_id.as_deref(), Some("robot-01"));
+
+    // OAuth needs the client credentials
+    config.recorder.ingestion.client_secret = None;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("client_secret"));
+
+    // Some way to authenticate
+    config.recorder.ingestion.token_url = None;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("token or a token_url"));
+
+    config.recorder.ingestion.token = Some("token".to_string());
+    config.recorder.ingestion.endpoint = "ingest.example.com".to_string();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("ingestion.endpoint"));
+}
+
+#[test]
+fn test_tap_config() {
+    let tap = RecorderConfig::default().recorder.tap;
+    assert!(!tap.publish);
+    assert_eq!(tap.key_prefix, "recorder/tap");
+    assert!(tap.per_topic.is_empty());
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut config = RecorderConfig::default();
+    config.recorder.tap = toml::from_str(
+        r#"
+        publish = true
+        [per_topic."camera/**"]
+        interval_ms = 250
+        "#,
+    )
+    .unwrap();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let tap = load_config(&path).unwrap().recorder.tap;
+    assert_eq!(
+        tap.per_topic["camera/**"].interval(),
+        Duration::from_millis(250)
+    );
+    assert_eq!(tap.channel_capacity, 64);
+
+    config.recorder.tap.key_prefix = "recorder/*".to_string();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("tap.key_prefix"));
+}
+
+#[test]
+fn test_errors_are_categorized() {
+    use zenoh_recorder::config::StorageConfig;
+    use zenoh_recorder::storage::BackendFactory;
+    use zenoh_recorder::RecorderError;
+
+    let result = load_config("/nonexistent/recorder.toml");
+    assert!(matches!(result, Err(RecorderError::Config(_))));
+
+    let storage = StorageConfig {
+        backend: "tape".to_string(),
+        ..RecorderConfig::default().storage
+    };
+    match BackendFactory::create(&storage) {
+        Err(RecorderError::Config(e)) => assert!(e.to_string().contains("tape")),
+        Err(e) => panic!("unexpected error category: {}", e),
+        Ok(_) => panic!("unknown backend accepted"),
+    }
+}
+
+#[test]
+fn test_convert_default_config_to_yaml_and_back() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let toml_path = PathBuf::from("config/default.toml");
+
+    let yaml = ConfigLoader::convert(&toml_path, None).unwrap();
+    assert!(yaml.contains("mode: peer"));
+    // Placeholders survive and are substituted on load
+    assert!(yaml.contains("${DEVICE_ID:-recorder-001}"));
+    let yaml_path = dir.path().join("recorder.yaml");
+    fs::write(&yaml_path, &yaml).unwrap();
+
+    // device_id comes from DEVICE_ID, which other tests of this binary set
+    let load = |path: &PathBuf| {
+        let mut config = load_config(path).unwrap();
+        config.recorder.device_id.clear();
+        serde_json::to_value(config).unwrap()
+    };
+    let from_toml = load(&toml_path);
+    assert_eq!(from_toml, load(&yaml_path));
+
+    let toml = ConfigLoader::convert(&yaml_path, Some(ConfigFormat::Toml)).unwrap();
+    let back_path = dir.path().join("back.toml");
+    fs::write(&back_path, toml).unwrap();
+    assert_eq!(from_toml, load(&back_path));
+}
+
+#[test]
+fn test_config_format_fallbacks_warn() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let yaml = ConfigLoader::convert("config/default.toml", None).unwrap();
+
+    // A missing .toml falls back to the converted .yaml next to it
+    fs::write(dir.path().join("recorder.yaml"), &yaml).unwrap();
+    let (config, warnings) =
+        ConfigLoader::load_with_warnings(dir.path().join("recorder.toml")).unwrap();
+    assert_eq!(config.zenoh.mode, "peer");
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("deprecated"));
+
+    // YAML content behind a .toml extension is detected
+    fs::write(dir.path().join("yaml-content.toml"), &yaml).unwrap();
+    let (_, warnings) =
+        ConfigLoader::load_with_warnings(dir.path().join("yaml-content.toml")).unwrap();
+    assert!(warnings[0].contains("Yaml"));
+
+    // Matching extension and content load without warnings
+    let (_, warnings) = ConfigLoader::load_with_warnings("config/default.toml").unwrap();
+    assert!(warnings.is_empty());
+
+    fs::write(dir.path().join("recorder.conf"), "not a config").unwrap();
+    assert!(ConfigLoader::load_with_warnings(dir.path().join("recorder.conf")).is_err());
+}
+
+#[test]
+fn test_shutdown_deadline_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    assert_eq!(config.recorder.workers.shutdown_deadline(), None);
+    config.recorder.workers.shutdown_deadline_seconds = 20;
+    assert_eq!(
+        config.recorder.workers.shutdown_deadline(),
+        Some(std::time::Duration::from_secs(20))
+    );
+
+    // Recordings are spilled to the work directory
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("work_dir"));
+
+    config.recorder.work_dir.enabled = true;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert!(load_config(&path).is_ok());
+}
+
+#[test]
+fn test_compression_limits_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    config.recorder.compression.max_zstd_level = Some(10);
+    config.recorder.compression.max_lz4_level = Some(9);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert!(load_config(&path).is_ok());
+
+    // LZ4 doesn't go beyond 12
+    config.recorder.compression.max_lz4_level = Some(13);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("max_lz4_level"));
+
+    // The default level applies to every Start without one
+    config.recorder.compression.max_lz4_level = None;
+    config.recorder.compression.default_level = 4;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("compression.default_level"));
+}
+
+#[test]
+fn test_otlp_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut toml_text = toml::to_string(&RecorderConfig::default()).unwrap();
+    toml_text.push_str("\n[logging.otlp]\nendpoint = \"http://collector:4318/v1/traces\"\n");
+    fs::write(&path, &toml_text).unwrap();
+    let config = load_config(&path).unwrap();
+    let otlp = config.logging.otlp.unwrap();
+    assert_eq!(otlp.endpoint, "http://collector:4318/v1/traces");
+    assert_eq!(otlp.service_name, "zenoh-recorder");
+    assert_eq!(otlp.timeout(), std::time::Duration::from_secs(10));
+
+    // OTLP/HTTP only
+    let toml_text = toml_text.replace("http://collector", "collector");
+    fs::write(&path, toml_text).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("logging.otlp.endpoint"));
+}
+
+#[test]
+fn test_timestamp_source_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    assert_eq!(
+        config.recorder.timestamps.default.source,
+        TimestampSource::Sample
+    );
+    let mut policy = TimestampPolicy {
+        source: TimestampSource::PayloadField,
+        clock_offset_ns: -1_500_000,
+        ..Default::default()
+    };
+    config
+        .recorder
+        .timestamps
+        .per_topic
+        .insert("sensors/**".to_string(), policy.clone());
+
+    // The payload field to read is required
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("payload_field must be set"));
+
+    policy.payload_field = Some("header.stamp".to_string());
+    config
+        .recorder
+        .timestamps
+        .per_topic
+        .insert("sensors/**".to_string(), policy);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    let policy = &loaded.recorder.timestamps.per_topic["sensors/**"];
+    assert_eq!(policy.source, TimestampSource::PayloadField);
+    assert_eq!(policy.clock_offset_ns, -1_500_000);
+}
+
+#[test]
+fn test_quota_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    assert!(!config.recorder.quotas.is_enabled());
+    let limits = QuotaLimits {
+        max_concurrent_recordings: 2,
+        max_bytes_per_day: 1_000,
+    };
+    config
+        .recorder
+        .quotas
+        .organizations
+        .insert("acme".to_string(), limits);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    assert!(loaded.recorder.quotas.is_enabled());
+    assert_eq!(loaded.recorder.quotas.organization("acme"), limits);
+    assert_eq!(
+        loaded.recorder.quotas.organization("other"),
+        QuotaLimits::default()
+    );
+
+    config.recorder.quotas.tasks.insert(String::new(), limits);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("empty task ID"));
+}
+
+#[test]
+fn test_sample_transforms_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    let transforms = vec![
+        SampleTransformConfig::Drop {
+            min_bytes: None,
+            json_field: Some("fix.status".to_string()),
+            equals: Some(serde_json::json!(-1)),
+        },
+        SampleTransformConfig::RedactJson {
+            fields: vec!["lat".to_string(), "lon".to_string()],
+            replacement: None,
+        },
+    ];
+    config
+        .recorder
+        .transforms
+        .per_topic
+        .insert("gps/**".to_string(), transforms.clone());
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    assert_eq!(loaded.recorder.transforms.per_topic["gps/**"], transforms);
+
+    // Inverted byte ranges are rejected
+    config.recorder.transforms.per_topic.insert(
+        "camera/**".to_string(),
+        vec![SampleTransformConfig::RedactBytes {
+            ranges: vec![[64, 0]],
+            fill: 0,
+        }],
+    );
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("start < end"));
+}
+
+#[test]
+fn test_snapshot_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    assert!(config.recorder.snapshot.topics.is_empty());
+    assert_eq!(
+        config.recorder.snapshot.timeout(),
+        std::time::Duration::from_secs(1)
+    );
+    config.recorder.snapshot.topics = vec!["robot/state".to_string()];
+    config.recorder.snapshot.timeout_ms = 250;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    assert_eq!(loaded.recorder.snapshot.topics, vec!["robot/state"]);
+    assert_eq!(
+        loaded.recorder.snapshot.timeout(),
+        std::time::Duration::from_millis(250)
+    );
+
+    config.recorder.snapshot.topics = vec!["robot//state".to_string()];
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("snapshot.topics"));
+}
+
+#[test]
+fn test_per_topic_compression_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    config.recorder.compression.per_topic.insert(
+        "camera/**".to_string(),
+        TopicCompression {
+            r#type: "lz4".to_string(),
+            level: 1,
+            transform: None,
+        },
+    );
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    assert_eq!(
+        loaded.recorder.compression.per_topic["camera/**"].settings(),
+        Some((CompressionType::Lz4, CompressionLevel::Fast))
+    );
+
+    // Unknown codec
+    config
+        .recorder
+        .compression
+        .per_topic
+        .get_mut("camera/**")
+        .unwrap()
+        .r#type = "brotli".to_string();
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("compression.per_topic.\"camera/**\""));
+
+    // Above the configured limit
+    let camera = config
+        .recorder
+        .compression
+        .per_topic
+        .get_mut("camera/**")
+        .unwrap();
+    camera.r#type = "zstd".to_string();
+    camera.level = 4;
+    config.recorder.compression.max_zstd_level = Some(10);
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("max_zstd_level"));
+}
+
+#[test]
+fn test_black_box_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut config = RecorderConfig::default();
+    assert!(config.recorder.black_box.topics.is_empty());
+    assert_eq!(
+        config.recorder.black_box.max_bytes_per_topic,
+        16 * 1024 * 1024
+    );
+    assert_eq!(
+        config.recorder.black_box.max_age(),
+        Some(std::time::Duration::from_secs(60))
+    );
+    config.recorder.black_box.topics = vec!["robot/**".to_string()];
+    config.recorder.black_box.max_age_seconds = 0;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let loaded = load_config(&path).unwrap();
+    assert_eq!(loaded.recorder.black_box.topics, vec!["robot/**"]);
+    assert_eq!(loaded.recorder.black_box.max_age(), None);
+
+    config.recorder.black_box.max_bytes_per_topic = 0;
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("black_box.max_bytes_per_topic"));
+
+    config.recorder.black_box.max_bytes_per_topic = 1024;
+    config.recorder.black_box.topics = vec!["robot//state".to_string()];
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("black_box.topics"));
+}
+
+#[test]
+fn test_hooks_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    fs::write(
+        &path,
+        r#"
+[recorder]
+device_id = "robot-01"
+
+[recorder.flush_policy]
+max_buffer_size_bytes = 1024
+max_buffer_duration_seconds = 1
+
+[recorder.compression]
+default_type = "zstd"
+default_level = 2
+
+[[recorder.hooks]]
+events = ["finish", "error"]
+kind = "webhook"
+url = "https://ingest.example.com/recordings"
+headers = { authorization = "Bearer token" }
+
+[[recorder.hooks]]
+kind = "exec"
+command = ["/usr/local/bin/notify", "--json"]
+timeout_ms = 2000
+"#,
+    )
+    .unwrap();
+    let config = load_config(&path).unwrap();
+    let hooks = &config.recorder.hooks;
+    assert_eq!(hooks.len(), 2);
+    assert!(hooks[0].fires_on(RecordingEventKind::Finish));
+    assert!(!hooks[0].fires_on(RecordingEventKind::Start));
+    assert_eq!(hooks[0].timeout(), std::time::Duration::from_secs(10));
+    assert!(matches!(
+        &hooks[0].action,
+        HookAction::Webhook { url, headers }
+            if url == "https://ingest.example.com/recordings" && headers.len() == 1
+    ));
+    assert!(hooks[1].fires_on(RecordingEventKind::Start));
+    assert_eq!(
+        hooks[1].action,
+        HookAction::Exec {
+            command: vec!["/usr/local/bin/notify".to_string(), "--json".to_string()]
+        }
+    );
+
+    // Round-trips through TOML
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(&load_config(&path).unwrap().recorder.hooks, hooks);
+
+    let mut config = RecorderConfig::default();
+    config.recorder.hooks = vec![HookConfig {
+        events: vec![],
+        action: HookAction::Exec { command: vec![] },
+        timeout_ms: 1000,
+    }];
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("hooks[0].command"));
+
+    config.recorder.hooks[0].action = HookAction::Webhook {
+        url: "ftp://example.com".to_string(),
+        headers: Default::default(),
+    };
+    fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("hooks[0].url"));
+}
+
+#[test]
+fn test_dedupe_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    fs::write(
+        &path,
+        r#"
+[recorder]
+device_id = "robot-01"
+
+[recorder.flush_policy]
+max_buffer_size_bytes = 1024
+max_buffer_duration_seconds = 1
+
+[recorder.compression]
+default_type = "zstd"
+default_level = 2
+
+[recorder.dedupe.per_topic."robot/status"]
+
+[recorder.dedupe.per_topic."robot/mode/**"]
+heartbeat_ms = 0
+"#,
+    )
+    .unwrap();
+    let config = load_config(&path).unwrap();
+    let per_topic = &config.recorder.dedupe.per_topic;
+    assert_eq!(per_topic.len(), 2);
+    assert_eq!(
+        per_topic["robot/status"].heartbeat(),
+        Some(std::time::Duration::from_secs(10))
+    );
+    assert_eq!(per_topic["robot/mode/**"].heartbeat(), None);
+    assert!(RecorderConfig::default()
+        .recorder
+        .dedupe
+        .per_topic
+        .is_empty());
+}
+
+#[test]
+fn test_reductstore_tls_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let base = r#"
+[storage]
+backend = "reductstore"
+
+[storage.reductstore]
+url = "https://reduct.internal:8383"
+bucket_name = "recordings"
+
+[storage.reductstore.tls]
+ca_file = "/etc/recorder/ca.pem"
+"#;
+    fs::write(&path, base).unwrap();
+    let config = load_config(&path).unwrap();
+    let tls = &config.storage.backend_config.as_reductstore().unwrap().tls;
+    assert_eq!(tls.ca_file.as_deref(), Some("/etc/recorder/ca.pem"));
+    assert!(tls.client_cert_file.is_none());
+    assert!(!tls.insecure_skip_verify);
+
+    // A client certificate needs its key
+    fs::write(
+        &path,
+        format!("{}client_cert_file = \"/etc/recorder/client.pem\"\n", base),
+    )
+    .unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("client_key_file"));
+}
+
+#[test]
+fn test_mock_backend_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let base = r#"
+[storage]
+backend = "mock"
+
+[storage.mock]
+latency_ms = 20
+capacity_bytes = 4096
+"#;
+    fs::write(&path, base).unwrap();
+    let config = load_config(&path).unwrap();
+    let mock = config.storage.backend_config.as_mock().unwrap();
+    assert_eq!(mock.latency_ms, 20);
+    assert_eq!(mock.error_rate, 0.0);
+    assert_eq!(mock.capacity_bytes, 4096);
+    let backend = zenoh_recorder::storage::BackendFactory::create(&config.storage).unwrap();
+    assert_eq!(backend.backend_type(), "mock");
+
+    // The error rate is a share of the writes
+    fs::write(&path, format!("{}error_rate = 1.5\n", base)).unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("mock.error_rate"));
+}
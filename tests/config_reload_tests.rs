@@ -0,0 +1,201 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Hot config reload tests using the filesystem backend
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{ConfigSource, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/config_reload/camera";
+
+/// Configuration file with the given flush size, compression and zenoh mode
+fn write_config(dir: &Path, max_buffer_size: usize, compression: &str, mode: &str) {
+    let content = format!(
+        r#"
+[zenoh]
+mode = "{mode}"
+
+[storage]
+backend = "filesystem"
+
+[storage.filesystem]
+base_path = "{base_path}"
+
+[recorder]
+device_id = "reload-device"
+
+[recorder.flush_policy]
+max_buffer_size_bytes = {max_buffer_size}
+max_buffer_duration_seconds = 3600
+
+[recorder.compression]
+default_type = "{compression}"
+default_level = 2
+
+[recorder.upload_limit]
+max_bytes_per_sec = 0
+"#,
+        base_path = dir.join("data").to_string_lossy(),
+    );
+    std::fs::write(dir.join("config.toml"), content).unwrap();
+}
+
+fn create_test_manager(session: Arc<zenoh::Session>, dir: &Path) -> RecorderManager {
+    let source = ConfigSource {
+        path: dir.join("config.toml"),
+        device_id: None,
+    };
+    let config = source.load().unwrap();
+    common::create_test_manager(session, config).with_config_source(source)
+}
+
+fn record_count(data_dir: &Path, topic: &str) -> usize {
+    std::fs::read_dir(data_dir.join(topic_to_entry_name(topic)))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_reload_command_parsing() {
+    let json = r#"{"command": "reload", "device_id": "d"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::Reload));
+}
+
+#[test]
+fn test_request_compression_defaults() {
+    let json = br#"{"command": "start", "device_id": "d"}"#;
+    let request = RecorderRequest::from_json_with_defaults(
+        json,
+        CompressionType::Lz4,
+        CompressionLevel::Slow,
+    )
+    .unwrap();
+    assert_eq!(request.compression_type, CompressionType::Lz4);
+    assert!(matches!(request.compression_level, CompressionLevel::Slow));
+
+    // Values chosen by the request win
+    let json = br#"{"command": "start", "device_id": "d", "compression_type": "none"}"#;
+    let request = RecorderRequest::from_json_with_defaults(
+        json,
+        CompressionType::Lz4,
+        CompressionLevel::Slow,
+    )
+    .unwrap();
+    assert_eq!(request.compression_type, CompressionType::None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reload_reports_applied_and_restart_required() {
+    let dir = TempDir::new().unwrap();
+    write_config(dir.path(), 1048576, "zstd", "peer");
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, dir.path());
+
+    // Nothing changed
+    let response = manager.reload_config();
+    assert!(response.success);
+    assert!(response.applied.is_empty());
+    assert!(response.restart_required.is_empty());
+
+    write_config(dir.path(), 2048, "lz4", "client");
+    let response = manager.reload_config();
+    assert!(response.success, "{}", response.message);
+    assert_eq!(
+        response.applied,
+        vec![
+            "recorder.compression.default_type".to_string(),
+            "recorder.flush_policy.max_buffer_size_bytes".to_string(),
+        ]
+    );
+    assert_eq!(response.restart_required, vec!["zenoh.mode".to_string()]);
+    assert_eq!(
+        manager.compression_defaults().0,
+        CompressionType::Lz4,
+        "later Start requests use the new default"
+    );
+
+    // Applied settings aren't reported again; restart-only ones are
+    let response = manager.reload_config();
+    assert!(response.applied.is_empty());
+    assert_eq!(response.restart_required, vec!["zenoh.mode".to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_reload_keeps_settings() {
+    let dir = TempDir::new().unwrap();
+    write_config(dir.path(), 1048576, "zstd", "peer");
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), dir.path());
+
+    write_config(dir.path(), 1048576, "brotli", "peer");
+    let response = manager.reload_config();
+    assert!(!response.success);
+    assert!(response.message.contains("default_type"));
+    assert_eq!(manager.compression_defaults().0, CompressionType::Zstd);
+
+    // Managers created without a config file can't reload
+    let config = RecorderConfig::default();
+    let storage_backend = BackendFactory::create(&config.storage).unwrap();
+    let manager = RecorderManager::new(session, storage_backend, config);
+    assert!(!manager.reload_config().success);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reload_applies_to_active_recording() {
+    let dir = TempDir::new().unwrap();
+    write_config(dir.path(), 1048576, "none", "peer");
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), dir.path());
+
+    let request = RecorderRequest {
+        device_id: "reload-device".to_string(),
+        topics: vec![TOPIC.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    publisher.put("frame-1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let data_dir = dir.path().join("data");
+    assert_eq!(record_count(&data_dir, TOPIC), 0);
+
+    // Every sample is flushed as its own record after the reload
+    write_config(dir.path(), 1, "none", "peer");
+    assert!(manager.reload_config().success);
+    publisher.put("frame-2").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(record_count(&data_dir, TOPIC), 1);
+
+    manager.cancel_recording(&recording_id).await;
+}
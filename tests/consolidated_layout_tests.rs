@@ -0,0 +1,137 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Filesystem backend writing one file per recording and topic
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{
+    BackendConfig, FileLayout, FilesystemConfig, RecorderConfig, StorageConfig,
+};
+use zenoh_recorder::inspect::{inspect_path, parse_batch, DumpFormat, InspectOptions};
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay::{self, ReplayOptions};
+use zenoh_recorder::storage::{topic_to_entry_name, BackendFactory};
+
+const TOPIC: &str = "test/consolidated/camera";
+
+fn create_test_config(base_path: &str) -> RecorderConfig {
+    let mut config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: base_path.to_string(),
+                    file_format: "mcap".to_string(),
+                    layout: FileLayout::Consolidated,
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    // Flush every sample as its own batch, and read every batch back
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.integrity.verify_every = 1;
+    config
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_batches_appended_to_one_file() {
+    let data_dir = TempDir::new().unwrap();
+    let config = create_test_config(&data_dir.path().to_string_lossy());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[TOPIC]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session.put(TOPIC, format!("frame-{}", i)).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Still being written
+    let entry = topic_to_entry_name(TOPIC);
+    let recording_dir = data_dir.path().join(&recording_id);
+    let part = recording_dir.join(format!("{}.mcap.part", entry));
+    assert!(part.exists());
+
+    assert!(manager.finish_recording(&recording_id).await.success);
+    let file = recording_dir.join(format!("{}.mcap", entry));
+    assert!(file.exists());
+    assert!(!part.exists());
+    // No per-flush files
+    assert!(!data_dir.path().join(&entry).exists());
+
+    // Single records are still read back by timestamp
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.records.len(), 3);
+    assert_eq!(metadata.records_verified, 3);
+    assert_eq!(metadata.verify_failures, 0);
+    let mut stored = 0;
+    for record in &metadata.records {
+        let data = storage
+            .read_record(&record.entry, record.timestamp_us)
+            .await
+            .unwrap();
+        assert_eq!(data.len(), record.bytes);
+        assert_eq!(parse_batch(&data).unwrap().messages.len(), 1);
+        stored += data.len();
+    }
+    assert_eq!(std::fs::metadata(&file).unwrap().len() as usize, stored);
+    let found = storage
+        .find_records(&entry, "recording_id", &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), metadata.records.len());
+
+    let summary = replay::replay(
+        &session,
+        storage.as_ref(),
+        &metadata,
+        &ReplayOptions {
+            rate: 10.0,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.messages, 3);
+
+    // inspect splits the file into its batches
+    let mut out = Vec::new();
+    let options = InspectOptions {
+        dump: vec![],
+        dump_all: false,
+        format: DumpFormat::Json,
+        time_range: None,
+    };
+    inspect_path(&recording_dir, &options, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("3 records, 3 messages"), "{}", out);
+}
@@ -0,0 +1,296 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Credentials and roles required of control and status queries
+///
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{
+    BackendConfig, ControlAuthConfig, ControlPrincipal, ControlRole, FilesystemConfig,
+    RecorderConfig, StorageConfig,
+};
+use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::control_auth::{credentials_for, request_body, ControlAction, ControlAuth};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+
+const DEVICE: &str = "auth-test-device";
+
+fn auth_config() -> ControlAuthConfig {
+    ControlAuthConfig {
+        enabled: true,
+        principals: vec![
+            ControlPrincipal {
+                name: "dashboard".to_string(),
+                token: Some("view-token".to_string()),
+                secret: None,
+                role: ControlRole::Observer,
+            },
+            ControlPrincipal {
+                name: "fleet".to_string(),
+                token: None,
+                secret: Some("fleet-secret".to_string()),
+                role: ControlRole::Operator,
+            },
+            ControlPrincipal {
+                name: "successor".to_string(),
+                token: None,
+                secret: Some("successor-secret".to_string()),
+                role: ControlRole::Admin,
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+fn request(command: RecorderCommand, recording_id: Option<String>) -> RecorderRequest {
+    RecorderRequest {
+        command,
+        recording_id,
+        device_id: DEVICE.to_string(),
+        topics: vec!["test/auth/topic".to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    }
+}
+
+/// Body the signature of `request` covers
+fn body(request: &RecorderRequest) -> Vec<u8> {
+    request_body(&serde_json::to_vec(request).unwrap())
+}
+
+/// First reply to a query, with optional payload and attachment
+async fn query<T: DeserializeOwned>(
+    session: &zenoh::Session,
+    key: &str,
+    payload: Option<&RecorderRequest>,
+    attachment: Option<&RequestAuth>,
+) -> T {
+    let mut builder = session.get(key).timeout(Duration::from_secs(5));
+    if let Some(payload) = payload {
+        builder = builder.payload(serde_json::to_vec(payload).unwrap());
+    }
+    if let Some(attachment) = attachment {
+        builder = builder.attachment(serde_json::to_vec(attachment).unwrap());
+    }
+    let replies = builder.await.unwrap();
+    let reply = replies.recv_async().await.unwrap();
+    let sample = reply.into_result().unwrap();
+    serde_json::from_slice(&sample.payload().to_bytes()).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_commands_need_credentials_and_roles() {
+    let data_dir = TempDir::new().unwrap();
+    let config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: data_dir.path().to_string_lossy().to_string(),
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = Arc::new(RecorderManager::new(session.clone(), storage, config));
+    let auth_config = auth_config();
+    let control = ControlInterface::new(session.clone(), manager.clone(), DEVICE.to_string())
+        .with_auth(ControlAuth::new(&auth_config));
+    let control_handle = tokio::spawn(async move { control.run().await });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let control_key = format!("recorder/control/{}", DEVICE);
+    let fleet = &auth_config.principals[1];
+    let view = RequestAuth::Token {
+        token: "view-token".to_string(),
+    };
+
+    // No credentials: nothing starts
+    let start = request(RecorderCommand::Start, Some("auth-rec-1".to_string()));
+    let response: RecorderResponse = query(&session, &control_key, Some(&start), None).await;
+    assert!(!response.success);
+    assert_eq!(response.message, "Unauthorized: missing credentials");
+    assert!(!manager.get_status("auth-rec-1").await.success);
+
+    // Observers may not start recordings
+    let mut observed = start.clone();
+    observed.auth = Some(view.clone());
+    let response: RecorderResponse = query(&session, &control_key, Some(&observed), None).await;
+    assert!(!response.success);
+    assert!(response.message.starts_with("Forbidden"));
+
+    // A signature for another recording doesn't carry over
+    let mut misdirected = start.clone();
+    misdirected.auth = credentials_for(
+        fleet,
+        ControlAction::Command(&RecorderCommand::Start),
+        DEVICE,
+        "auth-rec-2",
+        &body(&start),
+    );
+    let response: RecorderResponse = query(&session, &control_key, Some(&misdirected), None).await;
+    assert_eq!(response.message, "Unauthorized: invalid credentials");
+
+    // A signature doesn't carry over to another body either
+    let mut rebucketed = start.clone();
+    rebucketed.bucket = Some("elsewhere".to_string());
+    rebucketed.auth = credentials_for(
+        fleet,
+        ControlAction::Command(&RecorderCommand::Start),
+        DEVICE,
+        "auth-rec-1",
+        &body(&start),
+    );
+    let response: RecorderResponse = query(&session, &control_key, Some(&rebucketed), None).await;
+    assert_eq!(response.message, "Unauthorized: invalid credentials");
+
+    // Signed by an operator
+    let mut signed = start.clone();
+    signed.auth = credentials_for(
+        fleet,
+        ControlAction::Command(&RecorderCommand::Start),
+        DEVICE,
+        "auth-rec-1",
+        &body(&start),
+    );
+    let response: RecorderResponse = query(&session, &control_key, Some(&signed), None).await;
+    assert!(response.success, "{}", response.message);
+
+    // and only once
+    let response: RecorderResponse = query(&session, &control_key, Some(&signed), None).await;
+    assert_eq!(response.message, "Unauthorized: invalid credentials");
+
+    // Status queries carry credentials as the attachment
+    let status_key = "recorder/status/auth-rec-1";
+    let status: StatusResponse = query(&session, status_key, None, None).await;
+    assert!(!status.success);
+    assert_eq!(status.message, "Unauthorized: missing credentials");
+    let status: StatusResponse = query(&session, status_key, None, Some(&view)).await;
+    assert!(status.success, "{}", status.message);
+    assert_eq!(status.status, RecordingStatus::Recording);
+
+    // Cancelling needs an admin, even for the operator that started it
+    let mut cancel = request(RecorderCommand::Cancel, Some("auth-rec-1".to_string()));
+    cancel.auth = credentials_for(
+        fleet,
+        ControlAction::Command(&RecorderCommand::Cancel),
+        DEVICE,
+        "auth-rec-1",
+        &body(&cancel),
+    );
+    let response: RecorderResponse = query(&session, &control_key, Some(&cancel), None).await;
+    assert_eq!(
+        response.message,
+        "Forbidden: 'fleet' (operator) may not cancel"
+    );
+
+    // Credentials may also come as the attachment of a control query
+    let finish = request(RecorderCommand::Finish, Some("auth-rec-1".to_string()));
+    let credentials = credentials_for(
+        fleet,
+        ControlAction::Command(&RecorderCommand::Finish),
+        DEVICE,
+        "auth-rec-1",
+        &body(&finish),
+    )
+    .unwrap();
+    let response: RecorderResponse =
+        query(&session, &control_key, Some(&finish), Some(&credentials)).await;
+    assert!(response.success, "{}", response.message);
+
+    // Handoffs are for admins
+    let offer: RecorderResponse = query(
+        &session,
+        &format!("{}/{}/offer", HANDOFF_KEY_PREFIX, DEVICE),
+        None,
+        Some(&view),
+    )
+    .await;
+    assert!(offer.message.starts_with("Forbidden"));
+
+    control_handle.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_signed_handoff_covers_its_payload() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: data_dir.path().to_string_lossy().to_string(),
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let manager = Arc::new(RecorderManager::new(session.clone(), storage, config));
+    let auth_config = auth_config();
+    let control = ControlInterface::new(session.clone(), manager.clone(), DEVICE.to_string())
+        .with_auth(ControlAuth::new(&auth_config));
+    let control_handle = tokio::spawn(async move { control.run().await });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let ready_key = format!("{}/{}/ready", HANDOFF_KEY_PREFIX, DEVICE);
+    let ready = |ready_at: &str| {
+        serde_json::to_vec(&HandoffReady {
+            mappings: vec![],
+            ready_at: ready_at.to_string(),
+            mode: HandoffMode::default(),
+        })
+        .unwrap()
+    };
+    let send = |payload: Vec<u8>, credentials: Option<RequestAuth>| {
+        let session = session.clone();
+        let key = ready_key.clone();
+        async move {
+            let replies = session
+                .get(&key)
+                .payload(payload)
+                .attachment(serde_json::to_vec(&credentials).unwrap())
+                .timeout(Duration::from_secs(5))
+                .await
+                .unwrap();
+            let sample = replies.recv_async().await.unwrap().into_result().unwrap();
+            serde_json::from_slice::<serde_json::Value>(&sample.payload().to_bytes()).unwrap()
+        }
+    };
+    let successor = &auth_config.principals[2];
+    let signed = ready("2025-01-01T00:00:00Z");
+    let credentials = credentials_for(successor, ControlAction::Handoff, DEVICE, "", &signed);
+
+    // The signature doesn't carry over to another payload
+    let reply = send(ready("2025-01-01T00:00:01Z"), credentials.clone()).await;
+    assert_eq!(reply["message"], "Unauthorized: invalid credentials");
+
+    // The payload it was made for is acknowledged
+    let reply = send(signed, credentials).await;
+    assert!(reply.get("finished").is_some(), "{}", reply);
+
+    control_handle.abort();
+}
@@ -50,6 +50,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -182,15 +186,8 @@ fn test_recorder_request_all_commands() {
         let request = RecorderRequest {
             command: command.clone(),
             recording_id: Some("test-123".to_string()),
-            scene: None,
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: "device-01".to_string(),
-            data_collector_id: None,
-            topics: vec![],
-            compression_level: CompressionLevel::Default,
-            compression_type: CompressionType::Zstd,
+            ..Default::default()
         };
 
         // Verify serialization works for all commands
@@ -227,6 +224,19 @@ fn test_status_response_all_states() {
             active_topics: vec![],
             buffer_size_bytes: 0,
             total_recorded_bytes: 0,
+            total_payload_bytes: 0,
+            compression_ratio: None,
+            samples_missing_timestamp: 0,
+            topic_paused: Default::default(),
+            flush_workers: vec![],
+            compression: None,
+            record_timestamps: None,
+            throughput: None,
+            per_topic: vec![],
+            uploads: vec![],
+            upload_percent: None,
+            environment: Default::default(),
+            error_code: None,
         };
 
         // Verify serialization works for all states
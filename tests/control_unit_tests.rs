@@ -14,14 +14,13 @@
 
 // Unit tests for control.rs module - mock-based tests without requiring Zenoh infrastructure
 use zenoh_recorder::protocol::{
-    CompressionLevel, CompressionType, RecorderCommand, RecorderRequest, RecorderResponse,
-    RecordingStatus, StatusResponse,
+    CompressionLevel, CompressionType, ErrorCode, RecorderCommand, RecorderRequest,
+    RecorderResponse, RecordingStatus, StatusResponse,
 };
 
 #[test]
 fn test_control_request_parsing_start_command() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some("test-001".to_string()),
         topics: vec!["topic1".to_string(), "topic2".to_string()],
         scene: Some("test_scene".to_string()),
@@ -30,8 +29,7 @@ fn test_control_request_parsing_start_command() {
         task_id: Some("task-123".to_string()),
         device_id: "device-456".to_string(),
         data_collector_id: Some("collector-789".to_string()),
-        compression_type: CompressionType::Zstd,
-        compression_level: CompressionLevel::Default,
+        ..Default::default()
     };
 
     // Serialize and deserialize
@@ -49,15 +47,10 @@ fn test_control_request_parsing_pause_command() {
     let request = RecorderRequest {
         command: RecorderCommand::Pause,
         recording_id: Some("rec-001".to_string()),
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -72,15 +65,10 @@ fn test_control_request_parsing_resume_command() {
     let request = RecorderRequest {
         command: RecorderCommand::Resume,
         recording_id: Some("rec-002".to_string()),
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -95,15 +83,10 @@ fn test_control_request_parsing_cancel_command() {
     let request = RecorderRequest {
         command: RecorderCommand::Cancel,
         recording_id: Some("rec-003".to_string()),
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -117,15 +100,10 @@ fn test_control_request_parsing_finish_command() {
     let request = RecorderRequest {
         command: RecorderCommand::Finish,
         recording_id: Some("rec-004".to_string()),
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -150,13 +128,14 @@ fn test_control_response_success() {
 
 #[test]
 fn test_control_response_error() {
-    let response = RecorderResponse::error("Test error message".to_string());
+    let response = RecorderResponse::error(ErrorCode::Internal, "Test error message".to_string());
 
     let json = serde_json::to_string(&response).unwrap();
     let parsed: RecorderResponse = serde_json::from_str(&json).unwrap();
 
     assert!(!parsed.success);
     assert_eq!(parsed.message, "Test error message");
+    assert_eq!(parsed.error_code, Some(ErrorCode::Internal));
     assert_eq!(parsed.recording_id, None);
 }
 
@@ -175,6 +154,19 @@ fn test_status_response_serialization() {
         active_topics: vec!["topic1".to_string(), "topic2".to_string()],
         buffer_size_bytes: 1024,
         total_recorded_bytes: 10240,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -202,6 +194,19 @@ fn test_status_response_idle_state() {
         active_topics: vec![],
         buffer_size_bytes: 0,
         total_recorded_bytes: 0,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -227,6 +232,19 @@ fn test_status_response_paused_state() {
         active_topics: vec!["topic1".to_string()],
         buffer_size_bytes: 512,
         total_recorded_bytes: 5120,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -274,15 +292,10 @@ fn test_request_with_empty_recording_id() {
     let request = RecorderRequest {
         command: RecorderCommand::Pause,
         recording_id: Some("".to_string()),
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -296,16 +309,10 @@ fn test_request_with_empty_recording_id() {
 fn test_request_with_none_recording_id() {
     let request = RecorderRequest {
         command: RecorderCommand::Cancel,
-        recording_id: None,
-        topics: vec![],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: String::new(),
-        data_collector_id: None,
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -340,6 +347,19 @@ fn test_status_response_json_structure() {
         active_topics: vec![],
         buffer_size_bytes: 0,
         total_recorded_bytes: 0,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -364,15 +384,10 @@ fn test_request_with_all_commands() {
         let request = RecorderRequest {
             command: command.clone(),
             recording_id: Some("test".to_string()),
-            topics: vec![],
-            scene: None,
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: String::new(),
-            data_collector_id: None,
             compression_type: CompressionType::default(),
             compression_level: CompressionLevel::default(),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -401,6 +416,19 @@ fn test_status_with_large_buffer_size() {
         active_topics: vec![],
         buffer_size_bytes: 1_000_000_000,     // 1GB
         total_recorded_bytes: 10_000_000_000, // 10GB
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -427,6 +455,19 @@ fn test_status_with_many_topics() {
         active_topics: topics.clone(),
         buffer_size_bytes: 0,
         total_recorded_bytes: 0,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -439,7 +480,6 @@ fn test_status_with_many_topics() {
 #[test]
 fn test_request_with_special_characters_in_fields() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some("rec-001-special_@#$".to_string()),
         topics: vec!["topic/with/slashes".to_string()],
         scene: Some("scene with spaces".to_string()),
@@ -450,6 +490,7 @@ fn test_request_with_special_characters_in_fields() {
         data_collector_id: Some("collector@789".to_string()),
         compression_type: CompressionType::default(),
         compression_level: CompressionLevel::default(),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -461,7 +502,7 @@ fn test_request_with_special_characters_in_fields() {
 
 #[test]
 fn test_error_response_with_empty_message() {
-    let response = RecorderResponse::error("".to_string());
+    let response = RecorderResponse::error(ErrorCode::Internal, "".to_string());
 
     assert!(!response.success);
     assert_eq!(response.message, "");
@@ -470,7 +511,7 @@ fn test_error_response_with_empty_message() {
 #[test]
 fn test_error_response_with_long_message() {
     let long_message = "Error: ".to_string() + &"x".repeat(1000);
-    let response = RecorderResponse::error(long_message.clone());
+    let response = RecorderResponse::error(ErrorCode::Internal, long_message.clone());
 
     assert!(!response.success);
     assert_eq!(response.message, long_message);
@@ -509,6 +550,19 @@ fn test_status_response_finished_state() {
         active_topics: vec![],
         buffer_size_bytes: 0,
         total_recorded_bytes: 50000,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -534,6 +588,19 @@ fn test_status_response_cancelled_state() {
         active_topics: vec![],
         buffer_size_bytes: 0,
         total_recorded_bytes: 0,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -0,0 +1,209 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the controller liveliness dead-man switch
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::config::ControllerLostAction;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    on_lost: ControllerLostAction,
+    clock: Arc<MockClock>,
+) -> Arc<RecorderManager> {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.controller_liveliness.grace_period_seconds = 1;
+    config.recorder.controller_liveliness.on_lost = on_lost;
+
+    Arc::new(common::create_test_manager(session, config).with_clock(clock))
+}
+
+fn mock_clock() -> Arc<MockClock> {
+    Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ))
+}
+
+fn start_request(topic: &str, controller: Option<&str>) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        controller_liveliness: controller.map(String::from),
+        ..common::start_request(&[topic])
+    }
+}
+
+fn spawn_watch(manager: &Arc<RecorderManager>) -> tokio::task::JoinHandle<()> {
+    let manager = manager.clone();
+    tokio::spawn(async move { manager.watch_controllers().await })
+}
+
+async fn status_of(manager: &RecorderManager, recording_id: &str) -> RecordingStatus {
+    manager.get_status(recording_id).await.status
+}
+
+/// Wait for the watch to bring a recording into `status`
+async fn wait_for_status(manager: &RecorderManager, recording_id: &str, status: RecordingStatus) {
+    for _ in 0..50 {
+        if status_of(manager, recording_id).await == status {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status_of(manager, recording_id).await, status);
+}
+
+#[test]
+fn test_controller_liveliness_request_parsing() {
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"], "controller_liveliness": "app/robot1/alive"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        request.controller_liveliness.as_deref(),
+        Some("app/robot1/alive")
+    );
+
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"]}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.controller_liveliness, None);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_finished_when_controller_lost() {
+    const CONTROLLER: &str = "test/liveliness/finish/controller";
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = mock_clock();
+    let manager = create_test_manager(
+        session.clone(),
+        data_dir.path(),
+        ControllerLostAction::Finish,
+        clock.clone(),
+    );
+    let watch = spawn_watch(&manager);
+
+    let token = session
+        .liveliness()
+        .declare_token(CONTROLLER)
+        .wait()
+        .unwrap();
+    let response = manager
+        .start_recording(start_request("test/liveliness/finish", Some(CONTROLLER)))
+        .await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+
+    // Alive controller: the recording keeps going past the grace period
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    clock.advance(Duration::from_secs(5));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        status_of(&manager, &recording_id).await,
+        RecordingStatus::Recording
+    );
+
+    // Lost, but still within the grace period
+    token.undeclare().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        status_of(&manager, &recording_id).await,
+        RecordingStatus::Recording
+    );
+
+    // Past the grace period: Finish (which waits for pending flushes)
+    clock.advance(Duration::from_secs(2));
+    wait_for_status(&manager, &recording_id, RecordingStatus::Finished).await;
+
+    watch.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_paused_and_resumed_with_controller() {
+    const CONTROLLER: &str = "test/liveliness/pause/controller";
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = mock_clock();
+    let manager = create_test_manager(
+        session.clone(),
+        data_dir.path(),
+        ControllerLostAction::Pause,
+        clock.clone(),
+    );
+    let watch = spawn_watch(&manager);
+
+    let token = session
+        .liveliness()
+        .declare_token(CONTROLLER)
+        .wait()
+        .unwrap();
+    let response = manager
+        .start_recording(start_request("test/liveliness/pause", Some(CONTROLLER)))
+        .await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    token.undeclare().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    clock.advance(Duration::from_secs(2));
+    wait_for_status(&manager, &recording_id, RecordingStatus::Paused).await;
+
+    let _token = session
+        .liveliness()
+        .declare_token(CONTROLLER)
+        .wait()
+        .unwrap();
+    wait_for_status(&manager, &recording_id, RecordingStatus::Recording).await;
+
+    manager.finish_recording(&recording_id).await;
+    watch.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_without_controller_is_not_watched() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = mock_clock();
+    let manager = create_test_manager(
+        session,
+        data_dir.path(),
+        ControllerLostAction::Finish,
+        clock.clone(),
+    );
+    let watch = spawn_watch(&manager);
+
+    let response = manager
+        .start_recording(start_request("test/liveliness/none", None))
+        .await;
+    let recording_id = response.recording_id.unwrap();
+
+    clock.advance(Duration::from_secs(5));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        status_of(&manager, &recording_id).await,
+        RecordingStatus::Recording
+    );
+
+    manager.finish_recording(&recording_id).await;
+    watch.abort();
+}
@@ -0,0 +1,166 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "dashboard")]
+
+/// HTTP status dashboard tests (run with `--features dashboard`)
+///
+mod common;
+
+use std::sync::Arc;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{ControlAuthConfig, ControlPrincipal, ControlRole};
+use zenoh_recorder::control_auth::ControlAuth;
+use zenoh_recorder::dashboard::{Dashboard, DashboardEvent, RecordingView, RecordingsView};
+use zenoh_recorder::protocol::*;
+
+const TOPIC: &str = "test/dashboard/camera";
+
+/// Serve the dashboard on a free port and return its base URL
+async fn serve(dashboard: Dashboard) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, dashboard.router()).await });
+    format!("http://{}", addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dashboard_lists_recordings() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = Arc::new(common::create_test_manager(
+        session,
+        common::filesystem_config(data_dir.path()),
+    ));
+    let response = manager
+        .start_recording(RecorderRequest {
+            compression_type: CompressionType::None,
+            ..common::start_request(&[TOPIC])
+        })
+        .await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    let base = serve(Dashboard::new(manager.clone())).await;
+    let client = reqwest::Client::new();
+
+    let list: RecordingsView = client
+        .get(format!("{}/recordings", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list.recordings.len(), 1);
+    assert_eq!(list.recordings[0].recording_id, recording_id);
+    assert_eq!(list.recordings[0].status.status, RecordingStatus::Recording);
+
+    let reply = client
+        .get(format!("{}/recordings/{}", base, recording_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reply.status(), 200);
+    let recording: RecordingView = reply.json().await.unwrap();
+    assert_eq!(recording.status.active_topics, vec![TOPIC.to_string()]);
+
+    let reply = client
+        .get(format!("{}/recordings/missing", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reply.status(), 404);
+    let status: StatusResponse = reply.json().await.unwrap();
+    assert_eq!(status.error_code, Some(ErrorCode::NotFound));
+
+    let page = client
+        .get(&base)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains("/events"));
+
+    manager.cancel_recording(&recording_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dashboard_requires_credentials() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = Arc::new(common::create_test_manager(
+        session,
+        common::filesystem_config(data_dir.path()),
+    ));
+    let auth = ControlAuth::new(&ControlAuthConfig {
+        enabled: true,
+        principals: vec![ControlPrincipal {
+            name: "technician".to_string(),
+            token: Some("view-token".to_string()),
+            secret: None,
+            role: ControlRole::Observer,
+        }],
+        ..Default::default()
+    });
+    let base = serve(Dashboard::new(manager).with_auth(auth, "dashboard-device".to_string())).await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings", base);
+
+    let reply = client.get(&url).send().await.unwrap();
+    assert_eq!(reply.status(), 401);
+    let status: StatusResponse = reply.json().await.unwrap();
+    assert_eq!(status.error_code, Some(ErrorCode::Unauthorized));
+
+    let reply = client.get(&url).bearer_auth("wrong").send().await.unwrap();
+    assert_eq!(reply.status(), 401);
+
+    let reply = client
+        .get(&url)
+        .bearer_auth("view-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reply.status(), 200);
+
+    // Browsers pass the token in the query
+    let reply = client
+        .get(format!("{}?token=view-token", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reply.status(), 200);
+}
+
+#[test]
+fn test_dashboard_event_serialization() {
+    let json = serde_json::to_value(DashboardEvent::Removed {
+        recording_id: "rec-1".to_string(),
+    })
+    .unwrap();
+    assert_eq!(json["type"], "removed");
+    assert_eq!(json["recording_id"], "rec-1");
+
+    let event = DashboardEvent::Recording(Box::new(RecordingView {
+        recording_id: "rec-1".to_string(),
+        status: StatusResponse::error(ErrorCode::NotFound, "gone".to_string()),
+    }));
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["type"], "recording");
+    assert_eq!(json["recording_id"], "rec-1");
+    assert_eq!(json["status"], "idle");
+}
@@ -0,0 +1,205 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Recorded data served back over Zenoh queries
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::query::Parameters;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::DataBridgeConfig;
+use zenoh_recorder::data_bridge::{self, DataBridge, DataQuery, Pager};
+use zenoh_recorder::proto::RecordedMessage;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+
+fn message(topic: &str, timestamp_ns: i64, payload: &[u8]) -> RecordedMessage {
+    RecordedMessage {
+        topic: topic.to_string(),
+        timestamp_ns,
+        payload: payload.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_query() {
+    let query = DataQuery::parse(
+        "recorder/data",
+        "recorder/data/rec-1/robot/camera",
+        &Parameters::from("from=100;to=200"),
+    )
+    .unwrap();
+    assert_eq!(
+        query,
+        DataQuery {
+            recording_id: "rec-1".to_string(),
+            topic: "robot/camera".to_string(),
+            from_ns: 100,
+            to_ns: 200,
+        }
+    );
+
+    // Both bounds are optional
+    let query = DataQuery::parse(
+        "recorder/data",
+        "recorder/data/rec-1/robot/**",
+        &Parameters::empty(),
+    )
+    .unwrap();
+    assert_eq!(query.topic, "robot/**");
+    assert_eq!((query.from_ns, query.to_ns), (i64::MIN, i64::MAX));
+}
+
+#[test]
+fn test_parse_query_rejects_invalid_selectors() {
+    let parse = |key: &str, parameters: &str| {
+        DataQuery::parse("recorder/data", key, &Parameters::from(parameters))
+    };
+    assert!(parse("recorder/data/rec-1", "").is_err());
+    assert!(parse("recorder/other/rec-1/topic", "").is_err());
+    assert!(parse("recorder/data/*/topic", "").is_err());
+    assert!(parse("recorder/data/rec-1/topic", "from=yesterday").is_err());
+    assert!(parse("recorder/data/rec-1/topic", "from=200;to=100").is_err());
+}
+
+#[test]
+fn test_pager_splits_messages_into_pages() {
+    let mut pager = Pager::new(64);
+    let mut pages = Vec::new();
+    for i in 0..10 {
+        pages.extend(pager.push(&message("a", i, &[i as u8; 20])));
+    }
+    pages.extend(pager.finish());
+    assert!(pages.len() > 1);
+    assert!(pages.iter().all(|page| page.len() <= 64));
+
+    let messages: Vec<RecordedMessage> = pages
+        .iter()
+        .flat_map(|page| data_bridge::decode_page(page).unwrap())
+        .collect();
+    assert_eq!(messages.len(), 10);
+    assert!(messages
+        .iter()
+        .enumerate()
+        .all(|(i, m)| m.timestamp_ns == i as i64));
+}
+
+#[test]
+fn test_pager_gives_large_messages_their_own_page() {
+    let mut pager = Pager::new(16);
+    assert!(pager.push(&message("a", 1, &[0; 4])).is_none());
+    let first = pager.push(&message("a", 2, &[0; 100])).unwrap();
+    assert_eq!(data_bridge::decode_page(&first).unwrap()[0].timestamp_ns, 1);
+    let second = pager.push(&message("a", 3, &[0; 4])).unwrap();
+    assert_eq!(
+        data_bridge::decode_page(&second).unwrap()[0].timestamp_ns,
+        2
+    );
+    assert_eq!(
+        data_bridge::decode_page(&pager.finish().unwrap()).unwrap()[0].timestamp_ns,
+        3
+    );
+    assert!(Pager::new(16).finish().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_bridge_serves_recorded_messages() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let recording_id = manager
+        .start_recording(common::start_request(&["test/bridge/**"]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..5 {
+        session
+            .put(format!("test/bridge/robot/{}", i), format!("frame-{}", i))
+            .wait()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    manager.finish_recording(&recording_id).await;
+
+    let bridge = DataBridge::new(
+        session.clone(),
+        storage.clone(),
+        DataBridgeConfig {
+            enabled: true,
+            key_prefix: "test/bridge_data".to_string(),
+            page_bytes: 1,
+        },
+        "device".to_string(),
+    );
+    tokio::spawn(async move { bridge.run().await });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let fetch = |selector: String| {
+        let session = session.clone();
+        async move {
+            let replies = session.get(selector).await.unwrap();
+            let mut messages = Vec::new();
+            let mut keys = Vec::new();
+            while let Ok(reply) = replies.recv_async().await {
+                let sample = reply.result().unwrap();
+                keys.push(sample.key_expr().to_string());
+                let page = sample.payload().to_bytes().to_vec();
+                messages.extend(data_bridge::decode_page(&page).unwrap());
+            }
+            (messages, keys)
+        }
+    };
+
+    // One reply per message with the smallest pages, each on a key of its own
+    let (messages, mut keys) =
+        fetch(format!("test/bridge_data/{}/test/bridge/**", recording_id)).await;
+    assert_eq!(messages.len(), 5);
+    let payloads: Vec<Vec<u8>> = messages.iter().map(|m| m.payload.clone()).collect();
+    for i in 0..5 {
+        assert!(payloads.contains(&format!("frame-{}", i).into_bytes()));
+    }
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), 5);
+    let page_prefix = format!("test/bridge_data/{}/test/bridge/robot/", recording_id);
+    assert!(keys.iter().all(|key| key.starts_with(&page_prefix)));
+
+    // Only the requested time range
+    let mut timestamps: Vec<i64> = messages.iter().map(|m| m.timestamp_ns).collect();
+    timestamps.sort_unstable();
+    let (messages, _) = fetch(format!(
+        "test/bridge_data/{}/test/bridge/**?from={};to={}",
+        recording_id, timestamps[1], timestamps[3]
+    ))
+    .await;
+    assert_eq!(messages.len(), 3);
+
+    // Unknown recordings get an error reply
+    let replies = session
+        .get("test/bridge_data/missing/test/bridge/**")
+        .await
+        .unwrap();
+    let reply = replies.recv_async().await.unwrap();
+    assert!(reply.result().is_err());
+}
@@ -0,0 +1,377 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Resumable, checksum-verified downloads of stored recordings
+///
+mod common;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{
+    BackendConfig, FileLayout, FilesystemConfig, RecorderConfig, StorageConfig,
+};
+use zenoh_recorder::download::{self, record_path, DownloadOptions};
+use zenoh_recorder::error::{RecorderError, Result};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::{chunk_checksums, BackendFactory, StorageBackend};
+
+const TOPIC: &str = "test/download/camera";
+
+/// Backend failing every other range read
+struct FlakyBackend {
+    inner: Arc<dyn StorageBackend>,
+    reads: AtomicU64,
+}
+
+#[async_trait]
+impl StorageBackend for FlakyBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        self.inner
+            .write_record(entry_name, timestamp_us, data, labels)
+            .await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn backend_type(&self) -> &str {
+        "flaky"
+    }
+
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        if self.reads.fetch_add(1, Ordering::SeqCst).is_multiple_of(2) {
+            return Err(RecorderError::backend("connection reset"));
+        }
+        self.inner
+            .read_record_range(entry_name, timestamp_us, offset, length)
+            .await
+    }
+}
+
+fn create_test_config(base_path: &str, layout: FileLayout) -> RecorderConfig {
+    let mut config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: base_path.to_string(),
+                    file_format: "mcap".to_string(),
+                    layout,
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    // Flush every sample as its own batch
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config
+}
+
+/// Record three samples and return the backend with the recording metadata
+async fn record(
+    base_path: &Path,
+    layout: FileLayout,
+) -> (Arc<dyn StorageBackend>, RecordingMetadata) {
+    let config = create_test_config(&base_path.to_string_lossy(), layout);
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            compression_type: CompressionType::None,
+            ..common::start_request(&[TOPIC])
+        })
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session
+            .put(TOPIC, format!("frame-{}-{}", i, "x".repeat(100)))
+            .wait()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert!(metadata.records.iter().filter(|r| r.bytes > 0).count() >= 3);
+    (storage, metadata)
+}
+
+fn options() -> DownloadOptions {
+    DownloadOptions {
+        chunk_size: 32,
+        max_retries: 3,
+        retry_delay: Duration::from_millis(1),
+    }
+}
+
+async fn assert_downloaded(storage: &dyn StorageBackend, metadata: &RecordingMetadata, dir: &Path) {
+    for record in &metadata.records {
+        let local = std::fs::read(record_path(dir, record)).unwrap();
+        let stored = storage
+            .read_record(&record.entry, record.timestamp_us)
+            .await
+            .unwrap();
+        assert_eq!(local, stored);
+    }
+    let saved: RecordingMetadata =
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(saved.recording_id, metadata.recording_id);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_download_in_chunks_over_flaky_reads() {
+    let data_dir = TempDir::new().unwrap();
+    let (storage, metadata) = record(data_dir.path(), FileLayout::PerFlush).await;
+    let flaky = FlakyBackend {
+        inner: storage.clone(),
+        reads: AtomicU64::new(0),
+    };
+
+    let out_dir = TempDir::new().unwrap();
+    let summary = download::download(&flaky, &metadata, out_dir.path(), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.downloaded, metadata.records.len());
+    assert_eq!(summary.skipped, 0);
+    let total: usize = metadata.records.iter().map(|r| r.bytes).sum();
+    assert_eq!(summary.bytes, total as u64);
+    assert_downloaded(storage.as_ref(), &metadata, out_dir.path()).await;
+    // Records span several chunks, each failing once first
+    assert!(flaky.reads.load(Ordering::SeqCst) >= 2 * (total as u64 / 32));
+
+    // Nothing left to fetch
+    let summary = download::download(storage.as_ref(), &metadata, out_dir.path(), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.downloaded, 0);
+    assert_eq!(summary.skipped, metadata.records.len());
+    assert_eq!(summary.bytes, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_download_resumes_and_refetches_corrupt_records() {
+    let data_dir = TempDir::new().unwrap();
+    let (storage, metadata) = record(data_dir.path(), FileLayout::PerFlush).await;
+    let records: Vec<_> = metadata.records.iter().filter(|r| r.bytes > 0).collect();
+    let out_dir = TempDir::new().unwrap();
+
+    // An interrupted download left the first 40 bytes of the first record
+    let first = records[0];
+    let data = storage
+        .read_record(&first.entry, first.timestamp_us)
+        .await
+        .unwrap();
+    let path = record_path(out_dir.path(), first);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path.with_extension("mcap.part"), &data[..40]).unwrap();
+
+    // The second record was downloaded but got corrupted
+    let second = records[1];
+    let mut data = storage
+        .read_record(&second.entry, second.timestamp_us)
+        .await
+        .unwrap();
+    data[0] ^= 0xff;
+    std::fs::write(record_path(out_dir.path(), second), &data).unwrap();
+
+    // The third has a part that isn't a prefix of the record
+    let third = records[2];
+    let path = record_path(out_dir.path(), third);
+    std::fs::write(path.with_extension("mcap.part"), b"garbage").unwrap();
+
+    let summary = download::download(storage.as_ref(), &metadata, out_dir.path(), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.downloaded, metadata.records.len());
+    assert_eq!(summary.resumed_bytes, 40 + 7);
+    assert_eq!(summary.refetched, 1);
+    assert_downloaded(storage.as_ref(), &metadata, out_dir.path()).await;
+    assert!(!path.with_extension("mcap.part").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_download_consolidated_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let (storage, metadata) = record(data_dir.path(), FileLayout::Consolidated).await;
+
+    // Ranges are cut from the batch, not the whole file
+    let record = metadata.records.iter().find(|r| r.bytes > 0).unwrap();
+    let data = storage
+        .read_record(&record.entry, record.timestamp_us)
+        .await
+        .unwrap();
+    let range = storage
+        .read_record_range(&record.entry, record.timestamp_us, 10, 20)
+        .await
+        .unwrap();
+    assert_eq!(range, &data[10..30]);
+    let tail = storage
+        .read_record_range(&record.entry, record.timestamp_us, 10, 1 << 20)
+        .await
+        .unwrap();
+    assert_eq!(tail, &data[10..]);
+
+    let out_dir = TempDir::new().unwrap();
+    let summary = download::download(storage.as_ref(), &metadata, out_dir.path(), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.downloaded, metadata.records.len());
+    assert_downloaded(storage.as_ref(), &metadata, out_dir.path()).await;
+}
+
+/// Backend flipping a byte of the range read at `corrupt_offset`, once
+struct CorruptingBackend {
+    inner: Arc<dyn StorageBackend>,
+    corrupt_offset: AtomicU64,
+    ranges: std::sync::Mutex<Vec<(u64, u64)>>,
+}
+
+#[async_trait]
+impl StorageBackend for CorruptingBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        self.inner
+            .write_record(entry_name, timestamp_us, data, labels)
+            .await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn backend_type(&self) -> &str {
+        "corrupting"
+    }
+
+    async fn read_record_range(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        offset: u64,
+        length: u64,
+    ) -> Result<Vec<u8>> {
+        self.ranges.lock().unwrap().push((offset, length));
+        let mut data = self
+            .inner
+            .read_record_range(entry_name, timestamp_us, offset, length)
+            .await?;
+        if self
+            .corrupt_offset
+            .compare_exchange(offset, u64::MAX, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            data[0] ^= 0xff;
+        }
+        Ok(data)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_download_refetches_only_the_corrupt_chunk() {
+    let data_dir = TempDir::new().unwrap();
+    let (storage, mut metadata) = record(data_dir.path(), FileLayout::PerFlush).await;
+    // Check the records in 32 byte chunks
+    for record in &mut metadata.records {
+        assert!(record.chunks.is_some());
+        let data = storage
+            .read_record(&record.entry, record.timestamp_us)
+            .await
+            .unwrap();
+        record.chunks = Some(chunk_checksums(&data, 32));
+    }
+    let first = metadata
+        .records
+        .iter()
+        .find(|r| r.bytes > 96)
+        .unwrap()
+        .clone();
+    metadata.records = vec![first.clone()];
+
+    // An interrupted download left two chunks, the second one corrupt
+    let data = storage
+        .read_record(&first.entry, first.timestamp_us)
+        .await
+        .unwrap();
+    let out_dir = TempDir::new().unwrap();
+    let path = record_path(out_dir.path(), &first);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut part = data[..64].to_vec();
+    part[40] ^= 0xff;
+    std::fs::write(path.with_extension("mcap.part"), &part).unwrap();
+
+    // and the third chunk is corrupted in transit once
+    let corrupting = CorruptingBackend {
+        inner: storage.clone(),
+        corrupt_offset: AtomicU64::new(64),
+        ranges: Default::default(),
+    };
+    let summary = download::download(&corrupting, &metadata, out_dir.path(), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.downloaded, 1);
+    assert_eq!(summary.resumed_bytes, 32);
+    assert_eq!(summary.refetched, 1);
+    // All but the kept chunk, plus the refetched one
+    assert_eq!(summary.bytes, first.bytes as u64);
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+
+    // Only the failing chunk was read twice
+    let ranges = corrupting.ranges.lock().unwrap();
+    assert_eq!(ranges[..3], [(32, 32), (64, 32), (64, 32)]);
+    assert!(ranges.iter().skip(3).all(|(offset, _)| *offset >= 96));
+}
@@ -0,0 +1,88 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Payload schema drift detection tests using the filesystem backend
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+fn create_test_manager(session: Arc<zenoh::Session>, data_dir: &TempDir) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir.path());
+    // Flush every sample as its own segment
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.schema.detect_drift = true;
+
+    common::create_test_manager(session, config)
+}
+
+#[test]
+fn test_drift_report_command_parsing() {
+    let json = r#"{"command": "drift_report", "recording_id": "rec-1", "device_id": "d"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::DriftReport));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_drift_report_not_found() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, &data_dir);
+
+    let report = manager.get_drift_report("missing").await;
+    assert!(!report.success);
+    assert!(report.message.contains("not found"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_drift_detected_between_segments() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), &data_dir);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec!["test/drift/pose".to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let response = manager.start_recording(request).await;
+    let recording_id = response.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let publisher = session.declare_publisher("test/drift/pose").wait().unwrap();
+    publisher.put(r#"{"x": 1.0, "y": 2.0}"#).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    publisher.put(r#"{"x": 1.5, "y": 2.5}"#).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    publisher
+        .put(r#"{"x": 1.0, "heading": 0.5}"#)
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let report = manager.get_drift_report(&recording_id).await;
+    assert!(report.success);
+    assert_eq!(report.topics.len(), 1);
+    assert_eq!(report.topics[0].kind, "json");
+    assert_eq!(report.events.len(), 1);
+    assert_eq!(report.events[0].added_fields, vec!["heading".to_string()]);
+    assert_eq!(report.events[0].removed_fields, vec!["y".to_string()]);
+}
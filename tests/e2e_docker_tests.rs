@@ -24,8 +24,7 @@ use zenoh::Wait;
 use zenoh_recorder::config::{BackendConfig, RecorderConfig, ReductStoreConfig, StorageConfig};
 use zenoh_recorder::control::ControlInterface;
 use zenoh_recorder::protocol::{
-    CompressionLevel, CompressionType, RecorderCommand, RecorderRequest, RecordingStatus,
-    StatusResponse,
+    CompressionLevel, CompressionType, RecorderRequest, RecordingStatus, StatusResponse,
 };
 use zenoh_recorder::recorder::RecorderManager;
 use zenoh_recorder::storage::BackendFactory;
@@ -78,6 +77,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -173,8 +176,7 @@ async fn test_e2e_recorder_manager_with_reductstore() {
 
     // Create a start recording request (recording_id is None - server generates it)
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None, // Server generates the ID
+        // Server generates the ID
         topics: vec!["test/topic1".to_string(), "test/topic2".to_string()],
         scene: Some("e2e_test_scene".to_string()),
         skills: vec!["skill1".to_string()],
@@ -182,8 +184,7 @@ async fn test_e2e_recorder_manager_with_reductstore() {
         task_id: Some("task-001".to_string()),
         device_id: "device-001".to_string(),
         data_collector_id: Some("collector-001".to_string()),
-        compression_type: CompressionType::Zstd,
-        compression_level: CompressionLevel::Default,
+        ..Default::default()
     };
 
     // Start recording
@@ -242,17 +243,11 @@ async fn test_e2e_multiple_recordings() {
 
     for i in 1..=3 {
         let request = RecorderRequest {
-            command: RecorderCommand::Start,
-            recording_id: None, // Server generates
+            // Server generates
             topics: vec![format!("test/topic/multi{}", i)],
             scene: Some("multi_test".to_string()),
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: "device-001".to_string(),
-            data_collector_id: None,
-            compression_type: CompressionType::Zstd,
-            compression_level: CompressionLevel::Default,
+            ..Default::default()
         };
 
         let response = manager.start_recording(request).await;
@@ -294,17 +289,11 @@ async fn test_e2e_recording_with_compression_types() {
 
     for compression_type in compression_types.into_iter() {
         let request = RecorderRequest {
-            command: RecorderCommand::Start,
-            recording_id: None, // Server generates
+            // Server generates
             topics: vec!["test/compression".to_string()],
-            scene: None,
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: "device-001".to_string(),
-            data_collector_id: None,
             compression_type,
-            compression_level: CompressionLevel::Default,
+            ..Default::default()
         };
 
         let response = manager.start_recording(request).await;
@@ -336,17 +325,10 @@ async fn test_e2e_cancel_recording() {
         create_test_recorder_manager(session_arc, get_reductstore_url(), get_test_bucket());
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None, // Server generates
+        // Server generates
         topics: vec!["test/cancel".to_string()],
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device-001".to_string(),
-        data_collector_id: None,
-        compression_type: CompressionType::Zstd,
-        compression_level: CompressionLevel::Default,
+        ..Default::default()
     };
 
     // Start recording
@@ -408,8 +390,7 @@ async fn test_e2e_recording_lifecycle_with_metadata() {
         create_test_recorder_manager(session_arc, get_reductstore_url(), get_test_bucket());
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None, // Server generates
+        // Server generates
         topics: vec![
             "test/sensor/lidar".to_string(),
             "test/sensor/camera".to_string(),
@@ -421,8 +402,8 @@ async fn test_e2e_recording_lifecycle_with_metadata() {
         task_id: Some("task-12345".to_string()),
         device_id: "robot-001".to_string(),
         data_collector_id: Some("collector-001".to_string()),
-        compression_type: CompressionType::Zstd,
         compression_level: CompressionLevel::Slow,
+        ..Default::default()
     };
 
     // Start recording
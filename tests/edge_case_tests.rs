@@ -54,6 +54,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -79,17 +83,11 @@ async fn test_empty_topics_list() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
-        topics: vec![], // Empty topics list
+        // Empty topics list
         compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let _response = manager.start_recording(request).await;
@@ -109,17 +107,10 @@ async fn test_many_topics() {
     let topics: Vec<String> = (0..50).map(|i| format!("test/topic{}", i)).collect();
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics,
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -202,17 +193,11 @@ async fn test_rapid_start_stop() {
     // Rapidly start and stop recordings
     for i in 0..5 {
         let request = RecorderRequest {
-            command: RecorderCommand::Start,
-            recording_id: None,
-            scene: None,
-            skills: vec![],
-            organization: None,
-            task_id: None,
             device_id: format!("device-{}", i),
-            data_collector_id: None,
             topics: vec![format!("test/rapid{}", i)],
             compression_level: CompressionLevel::Fastest,
             compression_type: CompressionType::None,
+            ..Default::default()
         };
 
         let response = manager.start_recording(request).await;
@@ -235,7 +220,7 @@ fn test_recorder_response_builder_functions() {
     assert_eq!(success_resp.bucket_name, Some("bucket".to_string()));
 
     // Test error builder
-    let error_resp = RecorderResponse::error("Test error message".to_string());
+    let error_resp = RecorderResponse::error(ErrorCode::Internal, "Test error message".to_string());
     assert!(!error_resp.success);
     assert_eq!(error_resp.message, "Test error message");
     assert!(error_resp.recording_id.is_none());
@@ -245,17 +230,8 @@ fn test_recorder_response_builder_functions() {
 #[test]
 fn test_request_with_minimal_fields() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "minimal-device".to_string(),
-        data_collector_id: None,
-        topics: vec![],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -269,7 +245,6 @@ fn test_request_with_minimal_fields() {
 #[test]
 fn test_request_with_maximal_fields() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some("pre-assigned-id".to_string()),
         scene: Some("scene".to_string()),
         skills: vec!["s1".to_string(), "s2".to_string(), "s3".to_string()],
@@ -280,6 +255,7 @@ fn test_request_with_maximal_fields() {
         topics: vec!["t1".to_string(), "t2".to_string()],
         compression_level: CompressionLevel::Slowest,
         compression_type: CompressionType::Lz4,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -300,17 +276,10 @@ async fn test_finish_immediately_after_start() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/immediate".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -351,6 +320,19 @@ fn test_status_response_with_large_values() {
         active_topics: (0..50).map(|i| format!("/topic{}", i)).collect(), // 50 topics
         buffer_size_bytes: i32::MAX,
         total_recorded_bytes: i64::MAX,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     assert_eq!(response.skills.len(), 100);
@@ -0,0 +1,130 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Embedding the recorder: builder and samples ingested without Zenoh
+///
+mod common;
+
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use zenoh_recorder::data_bridge::{self, DataQuery};
+use zenoh_recorder::error::RecorderError;
+use zenoh_recorder::proto::RecordedMessage;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::RecorderManagerBuilder;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_ingested_samples_are_recorded() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let manager = RecorderManagerBuilder::new(config.clone())
+        .build()
+        .await
+        .unwrap();
+
+    let recording_id = manager
+        .start_recording(common::start_request(&["embed/camera/**"]))
+        .await
+        .recording_id
+        .unwrap();
+
+    for i in 0..3u64 {
+        let recordings = manager
+            .ingest_sample(
+                "embed/camera/front",
+                format!("frame-{}", i),
+                1_700_000_000_000_000_000 + i,
+            )
+            .await
+            .unwrap();
+        assert_eq!(recordings, 1);
+    }
+    // No recording of this topic
+    assert_eq!(
+        manager
+            .ingest_sample("embed/lidar", b"scan".to_vec(), 1)
+            .await
+            .unwrap(),
+        0
+    );
+    assert!(matches!(
+        manager
+            .ingest_sample("embed/bad#topic", Vec::<u8>::new(), 1)
+            .await,
+        Err(RecorderError::Zenoh(_))
+    ));
+
+    let response = manager.finish_recording(&recording_id).await;
+    assert!(response.success, "{}", response.message);
+
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let query = DataQuery {
+        recording_id,
+        topic: "embed/camera/**".to_string(),
+        from_ns: i64::MIN,
+        to_ns: i64::MAX,
+    };
+    let messages = Arc::new(Mutex::new(Vec::<RecordedMessage>::new()));
+    data_bridge::read_pages(storage.as_ref(), &query, 1 << 20, |_, _, page| {
+        let messages = messages.clone();
+        async move {
+            messages
+                .lock()
+                .unwrap()
+                .extend(data_bridge::decode_page(&page)?);
+            Ok(())
+        }
+    })
+    .await
+    .unwrap();
+
+    let mut messages = messages.lock().unwrap().clone();
+    messages.sort_by_key(|m| m.timestamp_ns);
+    assert_eq!(messages.len(), 3);
+    for (i, message) in messages.iter().enumerate() {
+        assert_eq!(message.timestamp_ns, 1_700_000_000_000_000_000 + i as i64);
+        assert_eq!(message.payload, format!("frame-{}", i).into_bytes());
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_paused_recordings_drop_ingested_samples() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let manager = RecorderManagerBuilder::new(config).build().await.unwrap();
+
+    let recording_id = manager
+        .start_recording(common::start_request(&["embed/imu"]))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.pause_recording(&recording_id).await.success);
+    assert_eq!(
+        manager
+            .ingest_sample("embed/imu", b"sample".to_vec(), 1)
+            .await
+            .unwrap(),
+        0
+    );
+
+    assert!(manager.resume_recording(&recording_id).await.success);
+    assert_eq!(
+        manager
+            .ingest_sample("embed/imu", b"sample".to_vec(), 2)
+            .await
+            .unwrap(),
+        1
+    );
+    manager.cancel_recording(&recording_id).await;
+}
@@ -0,0 +1,132 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Environment seed metadata given on Start
+///
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{BackendConfig, FilesystemConfig, RecorderConfig, StorageConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::BackendFactory;
+
+fn start_request(environment: &[(&str, &str)]) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        environment: environment
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        ..common::start_request(&["test/environment/topic"])
+    }
+}
+
+fn environment(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_environment_in_status_and_lookup() {
+    let data_dir = TempDir::new().unwrap();
+    let config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: data_dir.path().to_string_lossy().to_string(),
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session, storage.clone(), config);
+
+    let seeds = [
+        [("sim_seed", "42"), ("map_version", "v3")],
+        [("sim_seed", "7"), ("map_version", "v3")],
+        [("sim_seed", "42"), ("map_version", "v4")],
+    ];
+    let mut recording_ids = vec![];
+    for seed in &seeds {
+        let response = manager.start_recording(start_request(seed)).await;
+        assert!(response.success, "{}", response.message);
+        let recording_id = response.recording_id.unwrap();
+
+        let status = manager.get_status(&recording_id).await;
+        assert_eq!(status.environment, environment(seed));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(manager.finish_recording(&recording_id).await.success);
+        recording_ids.push(recording_id);
+    }
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_ids[0])
+        .await
+        .unwrap();
+    assert_eq!(metadata.environment, environment(&seeds[0]));
+
+    // Every given key must match
+    let found = replay::find_recordings_by_environment(
+        storage.as_ref(),
+        &environment(&[("sim_seed", "42")]),
+    )
+    .await
+    .unwrap();
+    let found: Vec<_> = found.iter().map(|m| m.recording_id.as_str()).collect();
+    assert_eq!(found, vec![&recording_ids[0], &recording_ids[2]]);
+
+    let found = replay::find_recordings_by_environment(
+        storage.as_ref(),
+        &environment(&[("map_version", "v3"), ("sim_seed", "7")]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].recording_id, recording_ids[1]);
+
+    let found = replay::find_recordings_by_environment(
+        storage.as_ref(),
+        &environment(&[("sim_seed", "1")]),
+    )
+    .await
+    .unwrap();
+    assert!(found.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_environment_key_rejected() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, common::filesystem_config(data_dir.path()));
+
+    let response = manager
+        .start_recording(start_request(&[("sim-seed", "42")]))
+        .await;
+    assert!(!response.success);
+    assert_eq!(response.errors[0].field, "environment.sim-seed");
+}
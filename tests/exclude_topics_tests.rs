@@ -0,0 +1,87 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for keys excluded from wildcard topics
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/exclude/**";
+
+fn start_request(exclude_topics: Vec<String>) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        exclude_topics,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_excluded_keys_are_not_recorded() {
+    let mut config = RecorderConfig::default();
+    config.recorder.exclude_topics = vec!["test/exclude/**/debug/**".to_string()];
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    let recording_id = manager
+        .start_recording(start_request(vec!["test/exclude/noisy".to_string()]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for key in [
+        "test/exclude/arm/joints",
+        "test/exclude/arm/debug/trace",
+        "test/exclude/noisy",
+        "test/exclude/debug/log",
+        "test/exclude/base/odom",
+    ] {
+        session.put(key, "sample").wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.per_topic[0].samples_received, 2);
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let mut keys: Vec<String> = backend
+        .records(&topic_to_entry_name(TOPIC))
+        .into_iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| message.sample.unwrap().key_expr)
+        .collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec!["test/exclude/arm/joints", "test/exclude/base/odom"]
+    );
+
+    // The configured patterns come first
+    let metadata: RecordingMetadata =
+        serde_json::from_slice(&backend.records("recordings_metadata")[0].1.data).unwrap();
+    assert_eq!(
+        metadata.exclude_topics,
+        vec!["test/exclude/**/debug/**", "test/exclude/noisy"]
+    );
+}
@@ -0,0 +1,144 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the per-recording time to live
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use zenoh::{Config, Wait};
+use zenoh_recorder::clock::{Clock, MockClock};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::{MockBackend, EXPIRES_AT_LABEL};
+
+const TOPIC: &str = "test/expiry/data";
+const ENTRY: &str = "test_expiry_data";
+
+fn start_request(recording_id: &str, retention_seconds: Option<u64>) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some(recording_id.to_string()),
+        compression_type: CompressionType::None,
+        retention_seconds,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+/// Labels of the stored records of `recording_id`, data and metadata
+fn recording_labels(
+    backend: &MockBackend,
+    recording_id: &str,
+) -> Vec<std::collections::HashMap<String, String>> {
+    [ENTRY, "recordings_metadata"]
+        .iter()
+        .flat_map(|entry| backend.records(entry))
+        .map(|(_, record)| record.labels)
+        .filter(|labels| labels.get("recording_id").map(String::as_str) == Some(recording_id))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_expired_recordings_swept() {
+    let mut config = RecorderConfig::default();
+    config.recorder.expiry.enabled = true;
+    config.recorder.expiry.sweep_interval_seconds = 1;
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let manager =
+        RecorderManager::new(session.clone(), backend.clone(), config).with_clock(clock.clone());
+
+    for (recording_id, retention_seconds) in [
+        ("debug", Some(60)),
+        ("week", Some(7 * 86400)),
+        ("kept", None),
+    ] {
+        let started = clock.since_epoch().as_secs();
+        let response = manager
+            .start_recording(start_request(recording_id, retention_seconds))
+            .await;
+        assert!(response.success, "{}", response.message);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        session.put(TOPIC, recording_id).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(manager.finish_recording(recording_id).await.success);
+        manager
+            .wait_for_completion(recording_id, Duration::from_secs(5))
+            .await;
+
+        if let Some(ttl) = retention_seconds {
+            let labels = recording_labels(&backend, recording_id);
+            assert_eq!(labels.len(), 2);
+            for labels in labels {
+                assert_eq!(labels[EXPIRES_AT_LABEL], (started + ttl).to_string());
+            }
+        }
+    }
+    let labels = recording_labels(&backend, "kept");
+    assert_eq!(labels.len(), 2);
+    assert!(labels.iter().all(|l| !l.contains_key(EXPIRES_AT_LABEL)));
+
+    // Nothing expired while the clock stands still
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(recording_labels(&backend, "debug").len(), 2);
+
+    // The debug recording goes with the next sweeps, the others stay
+    clock.advance(Duration::from_secs(60));
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while !recording_labels(&backend, "debug").is_empty() {
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "debug recording not swept"
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(recording_labels(&backend, "week").len(), 2);
+    assert_eq!(recording_labels(&backend, "kept").len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retention_outside_limits_rejected() {
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+
+    // Not accepted unless the recorder enables expiry
+    let manager = RecorderManager::new(session.clone(), backend.clone(), RecorderConfig::default());
+    let response = manager
+        .start_recording(start_request("disabled", Some(60)))
+        .await;
+    assert!(!response.success);
+    assert_eq!(response.errors[0].field, "retention_seconds");
+
+    let mut config = RecorderConfig::default();
+    config.recorder.expiry.enabled = true;
+    config.recorder.expiry.max_ttl_seconds = 3600;
+    let manager = RecorderManager::new(session, backend, config);
+    for retention_seconds in [0, 3601] {
+        let response = manager
+            .start_recording(start_request("limited", Some(retention_seconds)))
+            .await;
+        assert!(!response.success, "{}", retention_seconds);
+        assert_eq!(response.errors[0].field, "retention_seconds");
+    }
+    assert!(
+        manager
+            .start_recording(start_request("limited", Some(3600)))
+            .await
+            .success
+    );
+}
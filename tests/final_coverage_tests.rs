@@ -16,7 +16,7 @@
 ///
 /// This test suite targets all remaining uncovered code paths
 ///
-use crossbeam::queue::ArrayQueue;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use zenoh::key_expr::KeyExpr;
@@ -26,6 +26,7 @@ use zenoh::Wait;
 use zenoh_recorder::buffer::{FlushTask, TopicBuffer};
 use zenoh_recorder::config::{BackendConfig, RecorderConfig, ReductStoreConfig, StorageConfig};
 use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::flush_pool::FlushPool;
 use zenoh_recorder::mcap_writer::McapSerializer;
 use zenoh_recorder::protocol::*;
 use zenoh_recorder::recorder::RecorderManager;
@@ -56,6 +57,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -74,7 +79,7 @@ fn create_test_recorder_manager(
 // Additional buffer tests
 #[tokio::test]
 async fn test_buffer_exact_size_trigger() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -95,7 +100,7 @@ async fn test_buffer_exact_size_trigger() {
 
 #[tokio::test]
 async fn test_buffer_just_under_size_trigger() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -127,8 +132,6 @@ async fn test_recording_with_single_topic() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("single_topic_test".to_string()),
         skills: vec!["skill1".to_string()],
         organization: Some("test_org".to_string()),
@@ -138,6 +141,7 @@ async fn test_recording_with_single_topic() {
         topics: vec!["test/single_topic".to_string()],
         compression_level: CompressionLevel::Slow,
         compression_type: CompressionType::Lz4,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -169,17 +173,10 @@ async fn test_pause_resume_multiple_times() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/pause_resume_multi".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -236,6 +233,10 @@ fn test_reductstore_client_drop() {
         api_token: None,
         timeout_seconds: 300,
         max_retries: 3,
+        max_label_bytes: 8192,
+        batch_max_records: 32,
+        tls: Default::default(),
+        bucket_settings: Default::default(),
     };
     let client = ReductStoreBackend::new(config);
     if let Ok(client) = client {
@@ -254,6 +255,8 @@ fn test_flush_task_with_large_batch() {
         topic: "/test/large_batch".to_string(),
         samples: samples.clone(),
         recording_id: "rec-large-batch".to_string(),
+        timestamps_ns: vec![],
+        received_ns: vec![],
     };
 
     assert_eq!(task.samples.len(), 1000);
@@ -264,17 +267,10 @@ fn test_flush_task_with_large_batch() {
 #[test]
 fn test_empty_skills_array() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![], // Empty
+        // Empty
         organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
-        topics: vec![],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -286,7 +282,6 @@ fn test_very_long_strings() {
     let long_string = "a".repeat(10000);
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some(long_string.clone()),
         scene: Some(long_string.clone()),
         skills: vec![long_string.clone()],
@@ -295,8 +290,7 @@ fn test_very_long_strings() {
         device_id: long_string.clone(),
         data_collector_id: Some(long_string.clone()),
         topics: vec![long_string.clone()],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -339,7 +333,32 @@ fn test_metadata_with_empty_per_topic_stats() {
         end_time: None,
         total_bytes: 0,
         total_samples: 0,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({}),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     let json = serde_json::to_string(&metadata).unwrap();
@@ -382,17 +401,10 @@ async fn test_recording_with_slowest_compression() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/slowest".to_string()],
         compression_level: CompressionLevel::Slowest,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -413,17 +425,11 @@ async fn test_recording_with_fastest_compression() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/fastest".to_string()],
         compression_level: CompressionLevel::Fastest,
         compression_type: CompressionType::Lz4,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -442,6 +448,8 @@ fn test_flush_task_clone() {
         topic: "/test".to_string(),
         samples: samples.clone(),
         recording_id: "rec-clone".to_string(),
+        timestamps_ns: vec![],
+        received_ns: vec![],
     };
 
     let cloned = task.clone();
@@ -453,7 +461,7 @@ fn test_flush_task_clone() {
 // Test buffer with various durations
 #[tokio::test]
 async fn test_buffer_1_second_duration() {
-    let flush_queue = Arc::new(ArrayQueue::new(10));
+    let flush_queue = Arc::new(FlushPool::new(1, 10));
     let buffer = TopicBuffer::new(
         "/test/topic".to_string(),
         "rec-123".to_string(),
@@ -512,17 +520,10 @@ async fn test_finish_recording_twice() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/double_finish".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -558,6 +559,19 @@ fn test_status_response_zero_bytes() {
         active_topics: vec![],
         buffer_size_bytes: 0,
         total_recorded_bytes: 0,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     assert_eq!(response.buffer_size_bytes, 0);
@@ -663,6 +677,19 @@ fn test_status_response_clone() {
         active_topics: vec![],
         buffer_size_bytes: 100,
         total_recorded_bytes: 1000,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     let cloned = response.clone();
@@ -673,17 +700,8 @@ fn test_status_response_clone() {
 #[test]
 fn test_request_clone() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
-        topics: vec![],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let cloned = request.clone();
@@ -707,7 +725,32 @@ fn test_metadata_clone() {
         end_time: None,
         total_bytes: 0,
         total_samples: 0,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({}),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     let cloned = metadata.clone();
@@ -0,0 +1,110 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the partitioned flush worker pool
+///
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::topic_to_entry_name;
+
+fn create_test_manager(session: Arc<zenoh::Session>, data_dir: &Path) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    // One record per sample, spread over several workers
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.workers.flush_workers = 4;
+    config.recorder.workers.compression_threads = 2;
+
+    common::create_test_manager(session, config)
+}
+
+fn start_request(topics: &[&str]) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        ..common::start_request(topics)
+    }
+}
+
+/// Record files of an entry in timestamp order
+fn record_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "mcap"))
+        .map(|p| {
+            let timestamp = p.file_stem().unwrap().to_str().unwrap().parse().unwrap();
+            (timestamp, p)
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, p)| p).collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_records_of_a_topic_stay_in_order() {
+    let topics = ["test/pool/a", "test/pool/b", "test/pool/c"];
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), data_dir.path());
+
+    let response = manager.start_recording(start_request(&topics)).await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publishers: Vec<_> = topics
+        .iter()
+        .map(|t| session.declare_publisher(*t).wait().unwrap())
+        .collect();
+    for sequence in 0..20u8 {
+        for publisher in &publishers {
+            publisher.put(vec![sequence; 16]).wait().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.flush_workers.len(), 4);
+    assert!(status.flush_workers.iter().map(|w| w.tasks).sum::<u64>() >= 60);
+    assert!(status
+        .flush_workers
+        .iter()
+        .all(|w| (0.0..=1.0).contains(&w.utilization)));
+    let compression = status.compression.unwrap();
+    assert_eq!(compression.threads, 2);
+    assert!(compression.jobs >= 60);
+    assert_eq!(compression.cancelled, 0);
+
+    manager.finish_recording(&recording_id).await;
+
+    for topic in topics {
+        let payloads: Vec<u8> = record_files(&data_dir.path().join(topic_to_entry_name(topic)))
+            .iter()
+            .map(|file| std::fs::read(file).unwrap())
+            .filter(|data| !data.is_empty())
+            .flat_map(|data| parse_batch(&data).unwrap().messages)
+            .map(|message| message.payload[0])
+            .collect();
+        assert_eq!(payloads, (0..20).collect::<Vec<u8>>(), "{}", topic);
+    }
+}
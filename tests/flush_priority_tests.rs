@@ -0,0 +1,115 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the flush priority classes with slow storage
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{FlushPriority, MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+const VIDEO: &str = "test/flush_priority/video";
+const SAFETY: &str = "test/flush_priority/safety";
+
+#[test]
+fn test_flush_priority_by_topic() {
+    let mut config = RecorderConfig::default();
+    let workers = &mut config.recorder.workers;
+    workers
+        .flush_priority
+        .insert("test/**".to_string(), FlushPriority::Low);
+    workers
+        .flush_priority
+        .insert(SAFETY.to_string(), FlushPriority::High);
+    assert_eq!(workers.flush_priority(SAFETY), FlushPriority::High);
+    assert_eq!(workers.flush_priority(VIDEO), FlushPriority::Low);
+    assert_eq!(workers.flush_priority("robot/odom"), FlushPriority::Normal);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_safety_written_before_video_under_backpressure() {
+    let mut config = RecorderConfig::default();
+    // Every sample is its own flush task, queued for a single worker
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.workers.flush_workers = 1;
+    config.recorder.workers.queue_capacity = 3;
+    let priorities = &mut config.recorder.workers.flush_priority;
+    priorities.insert(VIDEO.to_string(), FlushPriority::Low);
+    priorities.insert(SAFETY.to_string(), FlushPriority::High);
+    let backend = Arc::new(MockBackend::new(MockConfig {
+        latency_ms: 500,
+        ..Default::default()
+    }));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("prioritized".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&[VIDEO, SAFETY])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    // The worker is busy writing the first frame...
+    session.put(VIDEO, "frame-0").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // ...while three more fill its queue and two find it full
+    for i in 1..6 {
+        session.put(VIDEO, format!("frame-{}", i)).wait().unwrap();
+    }
+    // Safety events push out the newest frames
+    for i in 0..2 {
+        session.put(SAFETY, format!("event-{}", i)).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        manager.get_status("prioritized").await.flush_workers[0].dropped,
+        4
+    );
+
+    // Evicted frames count as dropped once the worker is done with the first
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let status = manager.get_status("prioritized").await;
+    let dropped = |topic: &str| {
+        status
+            .per_topic
+            .iter()
+            .find(|t| t.topic == topic)
+            .unwrap()
+            .dropped
+    };
+    assert_eq!(dropped(SAFETY), 0);
+    assert_eq!(dropped(VIDEO), 4);
+
+    assert!(manager.finish_recording("prioritized").await.success);
+    manager
+        .wait_for_completion("prioritized", Duration::from_secs(10))
+        .await;
+    let safety = backend.records(&topic_to_entry_name(SAFETY));
+    let video = backend.records(&topic_to_entry_name(VIDEO));
+    assert_eq!(safety.len(), 2);
+    assert_eq!(video.len(), 2);
+    // The queued frame was written after the events that came later
+    assert!(video[1].0 > safety[1].0);
+}
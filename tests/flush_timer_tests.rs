@@ -0,0 +1,92 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Time-based flushes of topics that stop publishing
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/flush_timer/gps";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_tail_flushed_after_publisher_stops() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.flush_policy.max_buffer_duration_seconds = 1;
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let manager = RecorderManager::new(session.clone(), storage, config).with_clock(clock.clone());
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![TOPIC.to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..2 {
+        session.put(TOPIC, format!("fix-{}", i)).wait().unwrap();
+    }
+
+    // The clock stands still: the samples stay buffered
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.buffer_size_bytes, 10);
+
+    // Nothing else is published; the timer flushes the two samples, and the
+    // flush worker writes them and accounts for them afterwards
+    clock.advance(Duration::from_secs(2));
+    let entry_dir = data_dir.path().join(topic_to_entry_name(TOPIC));
+    let records = || {
+        std::fs::read_dir(&entry_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+            .count()
+    };
+    let mut status = manager.get_status(&recording_id).await;
+    for _ in 0..30 {
+        if status.per_topic[0].bytes_flushed > 0 && records() > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        status = manager.get_status(&recording_id).await;
+    }
+    assert_eq!(status.buffer_size_bytes, 0);
+    assert_eq!(status.per_topic[0].samples_received, 2);
+    assert!(status.per_topic[0].bytes_flushed > 0);
+    assert_eq!(status.per_topic[0].high_watermark_bytes, 10);
+    assert_eq!(status.per_topic[0].flushes.time, 1);
+    assert_eq!(status.per_topic[0].flushes.size, 0);
+    assert_eq!(
+        status.per_topic[0].last_flush_reason,
+        Some(FlushReason::Time)
+    );
+    assert_eq!(records(), 1);
+    manager.cancel_recording(&recording_id).await;
+}
@@ -0,0 +1,101 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tracing spans of the flush pipeline
+///
+mod common;
+
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::RecorderManagerBuilder;
+
+const TOPIC: &str = "test/flush_tracing/imu";
+
+/// (span name, parent span name)
+type SpanEdge = (String, Option<String>);
+
+/// Layer recording every new span as (name, parent name)
+#[derive(Clone, Default)]
+struct SpanTree(Arc<Mutex<Vec<SpanEdge>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanTree {
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let parent = span.parent().map(|p| p.name().to_string());
+        self.0
+            .lock()
+            .unwrap()
+            .push((span.name().to_string(), parent));
+    }
+}
+
+impl SpanTree {
+    fn parents_of(&self, name: &str) -> Vec<Option<String>> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(span, _)| span == name)
+            .map(|(_, parent)| parent.clone())
+            .collect()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_flush_spans_are_nested() {
+    // Flush workers and the compression pool run on other threads
+    let spans = SpanTree::default();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(spans.clone()))
+        .unwrap();
+
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    let manager = RecorderManagerBuilder::new(config).build().await.unwrap();
+
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            device_id: "device".to_string(),
+            topics: vec![TOPIC.to_string()],
+            ..Default::default()
+        })
+        .await
+        .recording_id
+        .unwrap();
+    manager
+        .ingest_sample(TOPIC, "sample", 1_700_000_000_000_000_000)
+        .await
+        .unwrap();
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let flush = Some("flush".to_string());
+    assert!(!spans.parents_of("flush").is_empty());
+    for stage in ["serialize", "compress", "throttle", "upload"] {
+        let parents = spans.parents_of(stage);
+        assert!(!parents.is_empty(), "no {} span", stage);
+        assert!(
+            parents.iter().all(|p| *p == flush),
+            "{}: {:?}",
+            stage,
+            parents
+        );
+    }
+    manager.shutdown(None).await.unwrap();
+}
@@ -0,0 +1,122 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for payload format detection with `default_format = "auto"`
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::TopicSchemaInfo;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const CAMERA: &str = "test/format_sniff/camera";
+const ODOM: &str = "test/format_sniff/odom";
+const STATUS: &str = "test/format_sniff/status";
+const LOG: &str = "test/format_sniff/log";
+const IMU: &str = "test/format_sniff/imu";
+
+/// Schema formats of the messages stored for a topic
+fn stored_formats(data_dir: &Path, topic: &str) -> Vec<String> {
+    std::fs::read_dir(data_dir.join(topic_to_entry_name(topic)))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mcap"))
+        .flat_map(|path| parse_batch(&std::fs::read(path).unwrap()).unwrap().messages)
+        .map(|message| message.schema.unwrap().format)
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_auto_format_tags_topics() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.schema.default_format = "auto".to_string();
+    config.recorder.schema.include_metadata = true;
+    // Configured topics keep their format
+    config.recorder.schema.per_topic.insert(
+        IMU.to_string(),
+        TopicSchemaInfo {
+            format: "msgpack".to_string(),
+            schema_name: None,
+            schema_hash: None,
+        },
+    );
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage, config);
+
+    let topics = [CAMERA, ODOM, STATUS, LOG, IMU];
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: topics.iter().map(|t| t.to_string()).collect(),
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    for _ in 0..2 {
+        session
+            .put(CAMERA, vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10])
+            .wait()
+            .unwrap();
+        // Little-endian CDR: a float64 after the encapsulation header
+        session
+            .put(
+                ODOM,
+                vec![0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f],
+            )
+            .wait()
+            .unwrap();
+        session.put(STATUS, r#"{"state": "idle"}"#).wait().unwrap();
+        session
+            .put(IMU, vec![0x81, 0xa1, b'x', 0x01])
+            .wait()
+            .unwrap();
+    }
+    // Samples that disagree are left raw
+    session.put(LOG, r#"{"level": "info"}"#).wait().unwrap();
+    session.put(LOG, "plain text").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let expected = [
+        (CAMERA, "jpeg"),
+        (ODOM, "cdr"),
+        (STATUS, "json"),
+        (LOG, "raw"),
+        (IMU, "msgpack"),
+    ];
+    for (topic, format) in expected {
+        let formats = stored_formats(data_dir.path(), topic);
+        assert_eq!(formats, vec![format; 2], "{}", topic);
+    }
+
+    // Only detected formats are listed in the metadata
+    let metadata = common::read_metadata(data_dir.path());
+    assert_eq!(metadata.formats.len(), 4);
+    assert_eq!(metadata.formats[CAMERA], "jpeg");
+    assert_eq!(metadata.formats[ODOM], "cdr");
+    assert_eq!(metadata.formats[STATUS], "json");
+    assert_eq!(metadata.formats[LOG], "raw");
+}
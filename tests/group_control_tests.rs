@@ -0,0 +1,155 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for Start and Finish commands sent to a group of recorders
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use zenoh::query::ConsolidationMode;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+
+const GROUP_KEY: &str = "recorder/control/group/fleet";
+
+/// Recorder of `device_id` in the "fleet" group, serving its control queries
+fn start_member(session: Arc<Session>, device_id: &str, data_dir: &Path) -> Arc<RecorderManager> {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.device_id = device_id.to_string();
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let manager = Arc::new(RecorderManager::new(session.clone(), storage, config));
+    let control = ControlInterface::new(session, manager.clone(), device_id.to_string())
+        .with_groups(vec!["fleet".to_string()], Duration::from_secs(5));
+    tokio::spawn(async move { control.run().await });
+    manager
+}
+
+fn group_request(command: RecorderCommand, execute_at_ns: Option<u64>) -> RecorderRequest {
+    RecorderRequest {
+        command,
+        scene: Some("convoy".to_string()),
+        device_id: "controller".to_string(),
+        topics: vec!["test/group_control/**".to_string()],
+        compression_type: CompressionType::None,
+        execute_at_ns,
+        ..Default::default()
+    }
+}
+
+/// Every reply to a group command
+async fn query_group(session: &Session, request: &RecorderRequest) -> Vec<RecorderResponse> {
+    let replies = session
+        .get(GROUP_KEY)
+        .payload(serde_json::to_vec(request).unwrap())
+        .consolidation(ConsolidationMode::None)
+        .timeout(Duration::from_secs(5))
+        .await
+        .unwrap();
+    let mut responses = Vec::new();
+    while let Ok(reply) = replies.recv_async().await {
+        let sample = reply.into_result().unwrap();
+        responses.push(serde_json::from_slice(&sample.payload().to_bytes()).unwrap());
+    }
+    responses
+}
+
+fn now_ns(session: &Session) -> u64 {
+    session.new_timestamp().get_time().to_duration().as_nanos() as u64
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_group_start_and_finish() {
+    let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let robot_a = start_member(session.clone(), "robot-a", dir_a.path());
+    let robot_b = start_member(session.clone(), "robot-b", dir_b.path());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Both start once the scheduled time is reached
+    let started = Instant::now();
+    let execute_at_ns = now_ns(&session) + 500_000_000;
+    let responses = query_group(
+        &session,
+        &group_request(RecorderCommand::Start, Some(execute_at_ns)),
+    )
+    .await;
+    assert_eq!(responses.len(), 2);
+    assert!(responses.iter().all(|r| r.success), "{:?}", responses);
+    assert!(started.elapsed() >= Duration::from_millis(450));
+    let id_a = robot_a.recording_statuses().await[0].0.clone();
+    let id_b = robot_b.recording_statuses().await[0].0.clone();
+    assert_ne!(id_a, id_b);
+
+    // Finishing the group finishes the recording of each member
+    let responses = query_group(&session, &group_request(RecorderCommand::Finish, None)).await;
+    assert_eq!(responses.len(), 2);
+    assert!(responses.iter().all(|r| r.success), "{:?}", responses);
+    for (manager, recording_id) in [(&robot_a, &id_a), (&robot_b, &id_b)] {
+        let response = manager
+            .wait_for_completion(recording_id, Duration::from_secs(5))
+            .await;
+        assert!(response.completed, "{:?}", response);
+    }
+
+    for (dir, device_id) in [(&dir_a, "robot-a"), (&dir_b, "robot-b")] {
+        let metadata = common::read_metadata(dir.path());
+        assert_eq!(metadata.group_id.as_deref(), Some("fleet"));
+        assert_eq!(metadata.device_id, device_id);
+        assert_eq!(metadata.scene.as_deref(), Some("convoy"));
+    }
+
+    // Nothing left to finish
+    let responses = query_group(&session, &group_request(RecorderCommand::Finish, None)).await;
+    assert_eq!(responses.len(), 2);
+    assert!(responses
+        .iter()
+        .all(|r| r.error_code == Some(ErrorCode::NotFound)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_group_rejections() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = start_member(session.clone(), "robot-c", data_dir.path());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Too far ahead
+    let execute_at_ns = now_ns(&session) + 60_000_000_000;
+    let request = group_request(RecorderCommand::Start, Some(execute_at_ns));
+    let responses = query_group(&session, &request).await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].error_code, Some(ErrorCode::InvalidRequest));
+
+    // Only start and finish
+    let responses = query_group(&session, &group_request(RecorderCommand::Pause, None)).await;
+    assert_eq!(responses[0].error_code, Some(ErrorCode::InvalidRequest));
+
+    // Scheduling is for group keys only
+    let replies = session
+        .get("recorder/control/robot-c")
+        .payload(serde_json::to_vec(&request).unwrap())
+        .timeout(Duration::from_secs(5))
+        .await
+        .unwrap();
+    let sample = replies.recv_async().await.unwrap().into_result().unwrap();
+    let response: RecorderResponse = serde_json::from_slice(&sample.payload().to_bytes()).unwrap();
+    assert_eq!(response.error_code, Some(ErrorCode::InvalidRequest));
+    assert!(manager.recording_statuses().await.is_empty());
+}
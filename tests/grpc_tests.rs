@@ -0,0 +1,156 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "grpc")]
+
+/// gRPC control interface tests (run with `--features grpc`)
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tonic::Request;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::grpc::proto::recorder_control_client::RecorderControlClient;
+use zenoh_recorder::grpc::proto::recorder_control_server::RecorderControl;
+use zenoh_recorder::grpc::proto::{RecordingRef, StartRequest};
+use zenoh_recorder::grpc::GrpcControl;
+
+const TOPIC: &str = "test/grpc/camera";
+
+fn start_request() -> StartRequest {
+    StartRequest {
+        device_id: "device".to_string(),
+        topics: vec![TOPIC.to_string()],
+        ..Default::default()
+    }
+}
+
+fn recording(recording_id: &str) -> Request<RecordingRef> {
+    Request::new(RecordingRef {
+        recording_id: recording_id.to_string(),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_start_reports_all_problems() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let control = GrpcControl::new(Arc::new(common::create_test_manager(
+        session,
+        common::filesystem_config(data_dir.path()),
+    )));
+
+    let mut request = start_request();
+    request.compression_type = Some("brotli".to_string());
+    request.compression_level = Some(9);
+    request.if_exists = Some("overwrite".to_string());
+    request.buffer_strategy = Some("fastest".to_string());
+    request.device_id = String::new();
+    let response = control
+        .start(Request::new(request))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!response.success);
+    let fields: Vec<&str> = response.errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "compression_type",
+            "compression_level",
+            "if_exists",
+            "buffer_strategy",
+            "device_id"
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_recording_lifecycle() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = Arc::new(common::create_test_manager(
+        session.clone(),
+        common::filesystem_config(data_dir.path()),
+    ));
+
+    // Serve on a free port and drive it with the generated client
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    tokio::spawn(GrpcControl::new(manager.clone()).serve(addr));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let mut client = RecorderControlClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+
+    let mut request = start_request();
+    request.recording_id = Some("grpc-run".to_string());
+    let started = client.start(request).await.unwrap().into_inner();
+    assert!(started.success, "{}", started.message);
+    assert_eq!(started.recording_id.as_deref(), Some("grpc-run"));
+    assert_eq!(started.status.as_deref(), Some("recording"));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, vec![0u8; 16]).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(
+        client
+            .pause(recording("grpc-run"))
+            .await
+            .unwrap()
+            .into_inner()
+            .success
+    );
+    let status = client
+        .status(recording("grpc-run"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.status, "paused");
+    assert_eq!(status.per_topic[0].samples_received, 1);
+    assert_eq!(status.topic_paused.get(TOPIC), Some(&false));
+
+    // Both interfaces share the manager
+    assert!(manager.resume_recording("grpc-run").await.success);
+    assert!(
+        client
+            .finish(recording("grpc-run"))
+            .await
+            .unwrap()
+            .into_inner()
+            .success
+    );
+    // Finish returns while the upload runs in the background
+    assert!(
+        manager
+            .wait_for_completion("grpc-run", Duration::from_secs(5))
+            .await
+            .completed
+    );
+    let status = client
+        .status(recording("grpc-run"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.status, "finished");
+
+    let missing = client.cancel(recording("nope")).await.unwrap().into_inner();
+    assert!(!missing.success);
+}
@@ -0,0 +1,185 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Handoff tests between two recorder managers sharing a Zenoh session
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::control::ControlInterface;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+const DEVICE_ID: &str = "handoff-device";
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    mode: HandoffMode,
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.device_id = DEVICE_ID.to_string();
+    config.recorder.handoff.mode = mode;
+
+    common::create_test_manager(session, config)
+}
+
+fn start_request(recording_id: Option<&str>, topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: recording_id.map(String::from),
+        scene: Some("handoff".to_string()),
+        device_id: DEVICE_ID.to_string(),
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_take_over_without_predecessor() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, data_dir.path(), HandoffMode::Split);
+
+    assert!(manager.take_over().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_handoff_between_recorders() {
+    let old_dir = TempDir::new().unwrap();
+    let new_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+
+    // Old recorder with an active recording, serving the handoff queryable
+    let old_manager = Arc::new(create_test_manager(
+        session.clone(),
+        old_dir.path(),
+        HandoffMode::Split,
+    ));
+    let control =
+        ControlInterface::new(session.clone(), old_manager.clone(), DEVICE_ID.to_string());
+    let control_task = tokio::spawn(async move { control.run().await });
+
+    let old_id = old_manager
+        .start_recording(start_request(None, "test/handoff/data"))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // New recorder takes over
+    let new_manager = create_test_manager(session.clone(), new_dir.path(), HandoffMode::Split);
+    let taken_over = new_manager.take_over().await.unwrap();
+    assert_eq!(taken_over.len(), 1);
+    let new_id = taken_over[0].clone();
+
+    // Old recording finished, new one recording with the same parameters
+    let old_status = old_manager.get_status(&old_id).await;
+    assert_eq!(old_status.status, RecordingStatus::Finished);
+    let new_status = new_manager.get_status(&new_id).await;
+    assert_eq!(new_status.status, RecordingStatus::Recording);
+    assert_eq!(new_status.scene.as_deref(), Some("handoff"));
+    assert_eq!(
+        new_status.active_topics,
+        vec!["test/handoff/data".to_string()]
+    );
+
+    // The old recorder is told it may exit
+    tokio::time::timeout(Duration::from_secs(1), old_manager.handed_off())
+        .await
+        .expect("handoff signalled");
+
+    // Both recordings carry the overlap
+    let old_metadata = common::read_metadata(old_dir.path());
+    let successor = old_metadata.handoff.unwrap().successor.unwrap();
+    assert_eq!(successor.recording_id, new_id);
+    assert!(successor.overlap_end.is_some());
+
+    new_manager.finish_recording(&new_id).await;
+    let new_metadata = common::read_metadata(new_dir.path());
+    let predecessor = new_metadata.handoff.unwrap().predecessor.unwrap();
+    assert_eq!(predecessor.recording_id, old_id);
+    assert_eq!(predecessor.overlap_start, successor.overlap_start);
+    assert_eq!(predecessor.overlap_end, successor.overlap_end);
+
+    control_task.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_continue_handoff_keeps_recording() {
+    const TOPIC: &str = "test/handoff/continue";
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+
+    // Both recorders write to the same storage, as on one device
+    let old_manager = Arc::new(create_test_manager(
+        session.clone(),
+        data_dir.path(),
+        HandoffMode::Split,
+    ));
+    let control =
+        ControlInterface::new(session.clone(), old_manager.clone(), DEVICE_ID.to_string());
+    let control_task = tokio::spawn(async move { control.run().await });
+
+    assert!(
+        old_manager
+            .start_recording(start_request(Some("long-run"), TOPIC))
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, "before").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // The new recorder continues the recording under its id
+    let new_manager = create_test_manager(session.clone(), data_dir.path(), HandoffMode::Continue);
+    assert_eq!(new_manager.take_over().await.unwrap(), vec!["long-run"]);
+    tokio::time::timeout(Duration::from_secs(1), old_manager.handed_off())
+        .await
+        .expect("handoff signalled");
+    assert!(!old_manager.get_status("long-run").await.success);
+    assert_eq!(
+        new_manager.get_status("long-run").await.status,
+        RecordingStatus::Recording
+    );
+
+    session.put(TOPIC, "after").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(new_manager.finish_recording("long-run").await.success);
+
+    // One metadata record, listing the records of both recorders
+    let metadata = common::read_metadata(data_dir.path());
+    assert_eq!(metadata.recording_id, "long-run");
+    assert!(!metadata.interrupted);
+    assert_eq!(metadata.records.len(), 2);
+    assert_eq!(
+        metadata.total_bytes,
+        metadata.records.iter().map(|r| r.bytes as i64).sum::<i64>()
+    );
+    let predecessor = metadata.handoff.unwrap().predecessor.unwrap();
+    assert_eq!(predecessor.recording_id, "long-run");
+    assert!(predecessor.overlap_end.is_some());
+    let metadata_records = std::fs::read_dir(data_dir.path().join("recordings_metadata"))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+        .count();
+    assert_eq!(metadata_records, 1);
+
+    control_task.abort();
+}
@@ -0,0 +1,114 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the recorder liveliness token and heartbeats
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::sample::SampleKind;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some("healthy".to_string()),
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_heartbeat_reports_backend_health() {
+    let mut config = RecorderConfig::default();
+    config.recorder.device_id = "robot-7".to_string();
+    config.recorder.health.enabled = true;
+    config.recorder.health.key_prefix = "test/health/heartbeat".to_string();
+    config.recorder.health.heartbeat_interval_ms = 100;
+    let backend = Arc::new(MockBackend::new(MockConfig {
+        capacity_bytes: 1_000_000,
+        ..Default::default()
+    }));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let heartbeats = session
+        .declare_subscriber("test/health/heartbeat/health/robot-7")
+        .wait()
+        .unwrap();
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    let next_heartbeat = || async {
+        let sample = tokio::time::timeout(Duration::from_secs(2), heartbeats.recv_async())
+            .await
+            .unwrap()
+            .unwrap();
+        serde_json::from_slice::<Heartbeat>(&sample.payload().to_bytes()).unwrap()
+    };
+    let heartbeat = next_heartbeat().await;
+    assert_eq!(heartbeat.device_id, "robot-7");
+    assert_eq!(heartbeat.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(heartbeat.backend_type, "mock");
+    assert!(heartbeat.backend_healthy);
+    assert_eq!(heartbeat.free_disk_bytes, Some(1_000_000));
+    assert_eq!(heartbeat.active_recordings, 0);
+
+    assert!(
+        manager
+            .start_recording(start_request("test/health/heartbeat/data"))
+            .await
+            .success
+    );
+    backend.set_offline(true);
+    // Skip the heartbeat that may have been on its way
+    next_heartbeat().await;
+    let heartbeat = next_heartbeat().await;
+    assert!(!heartbeat.backend_healthy);
+    assert_eq!(heartbeat.active_recordings, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_liveliness_token_until_shutdown() {
+    const ALIVE: &str = "test/health/token/alive/robot-8";
+    let mut config = RecorderConfig::default();
+    config.recorder.device_id = "robot-8".to_string();
+    config.recorder.health.enabled = true;
+    config.recorder.health.key_prefix = "test/health/token".to_string();
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend, config);
+
+    let tokens = session
+        .liveliness()
+        .declare_subscriber("test/health/token/alive/*")
+        .history(true)
+        .wait()
+        .unwrap();
+    let next_change = || async {
+        tokio::time::timeout(Duration::from_secs(2), tokens.recv_async())
+            .await
+            .unwrap()
+            .unwrap()
+    };
+    let sample = next_change().await;
+    assert_eq!(sample.key_expr().as_str(), ALIVE);
+    assert_eq!(sample.kind(), SampleKind::Put);
+
+    manager.shutdown(None).await.unwrap();
+    let sample = next_change().await;
+    assert_eq!(sample.key_expr().as_str(), ALIVE);
+    assert_eq!(sample.kind(), SampleKind::Delete);
+}
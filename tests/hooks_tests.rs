@@ -0,0 +1,218 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the recording event hooks
+///
+mod common;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{HookAction, HookConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+fn hooks_manager(data_dir: &TempDir, hooks: Vec<HookConfig>) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir.path().join("data"));
+    config.recorder.device_id = "hooks-device".to_string();
+    config.recorder.hooks = hooks;
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    common::create_test_manager(session, config)
+}
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        scene: Some("hooks".to_string()),
+        device_id: "hooks-device".to_string(),
+        ..common::start_request(&[topic])
+    }
+}
+
+fn exec_hook(events: Vec<RecordingEventKind>, log: &Path) -> HookConfig {
+    HookConfig {
+        events,
+        action: HookAction::Exec {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!(
+                    "echo \"$RECORDER_EVENT $RECORDER_RECORDING_ID\" >> {0}.env; cat >> {0}; echo >> {0}",
+                    log.display()
+                ),
+            ],
+        },
+        timeout_ms: 5000,
+    }
+}
+
+/// Lines of `path` once it has `count` of them
+async fn wait_for_lines(path: &Path, count: usize) -> Vec<String> {
+    for _ in 0..100 {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let lines: Vec<String> = content.lines().map(String::from).collect();
+            if lines.len() >= count {
+                return lines;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{} never had {} lines", path.display(), count);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_exec_hook_receives_events() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("events.jsonl");
+    let manager = hooks_manager(&dir, vec![exec_hook(vec![], &log)]);
+
+    let recording_id = manager
+        .start_recording(start_request("test/hooks/exec"))
+        .await
+        .recording_id
+        .unwrap();
+    wait_for_lines(&log, 1).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let events: Vec<RecordingEvent> = wait_for_lines(&log, 2)
+        .await
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events[0].event, RecordingEventKind::Start);
+    assert_eq!(events[0].status.status, RecordingStatus::Recording);
+    assert_eq!(events[1].event, RecordingEventKind::Finish);
+    assert_eq!(events[1].status.status, RecordingStatus::Finished);
+    for event in &events {
+        assert_eq!(event.recording_id, recording_id);
+        assert_eq!(event.device_id, "hooks-device");
+        assert_eq!(event.status.scene.as_deref(), Some("hooks"));
+        assert!(event.error.is_none());
+    }
+
+    let env = wait_for_lines(&dir.path().join("events.jsonl.env"), 2).await;
+    assert_eq!(env[0], format!("start {}", recording_id));
+    assert_eq!(env[1], format!("finish {}", recording_id));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_hook_fires_only_on_its_events() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("cancels.jsonl");
+    let manager = hooks_manager(
+        &dir,
+        vec![exec_hook(vec![RecordingEventKind::Cancel], &log)],
+    );
+
+    let finished = manager
+        .start_recording(start_request("test/hooks/finished"))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.finish_recording(&finished).await.success);
+    let cancelled = manager
+        .start_recording(start_request("test/hooks/cancelled"))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.cancel_recording(&cancelled).await.success);
+
+    let lines = wait_for_lines(&log, 1).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+    let event: RecordingEvent = serde_json::from_str(&lines[0]).unwrap();
+    assert_eq!(event.event, RecordingEventKind::Cancel);
+    assert_eq!(event.recording_id, cancelled);
+    assert_eq!(event.status.status, RecordingStatus::Cancelled);
+}
+
+/// Accept one HTTP request, answer 200 and return its head and body
+async fn serve_once(listener: TcpListener) -> (String, Vec<u8>) {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the request was read");
+        request.extend_from_slice(&buf[..n]);
+        if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..head_end]).to_string();
+    let length: usize = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().unwrap())
+        })
+        .unwrap();
+    while request.len() < head_end + length {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+    }
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    (head, request[head_end..head_end + length].to_vec())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webhook_posts_event() {
+    let dir = TempDir::new().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/recordings", listener.local_addr().unwrap());
+    let server = tokio::spawn(serve_once(listener));
+
+    let manager = hooks_manager(
+        &dir,
+        vec![HookConfig {
+            events: vec![RecordingEventKind::Start],
+            action: HookAction::Webhook {
+                url,
+                headers: HashMap::from([("x-api-key".to_string(), "secret".to_string())]),
+            },
+            timeout_ms: 5000,
+        }],
+    );
+    let recording_id = manager
+        .start_recording(start_request("test/hooks/webhook"))
+        .await
+        .recording_id
+        .unwrap();
+
+    let (head, body) = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(head.starts_with("POST /recordings "));
+    let head = head.to_ascii_lowercase();
+    assert!(head.contains("x-api-key: secret"));
+    assert!(head.contains("content-type: application/json"));
+    let event: RecordingEvent = serde_json::from_slice(&body).unwrap();
+    assert_eq!(event.event, RecordingEventKind::Start);
+    assert_eq!(event.recording_id, recording_id);
+    assert_eq!(
+        event.status.active_topics,
+        vec!["test/hooks/webhook".to_string()]
+    );
+
+    assert!(manager.cancel_recording(&recording_id).await.success);
+}
@@ -0,0 +1,172 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for importing MCAP files and rosbag2 bags as recordings
+///
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tempfile::TempDir;
+use zenoh_recorder::backfill::{import, ImportOptions};
+use zenoh_recorder::config::MockConfig;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::replay::find_recording;
+use zenoh_recorder::storage::{MockBackend, CHECKSUM_LABEL};
+use zenoh_recorder::topic_to_entry_name;
+
+/// Write an MCAP file with `count` messages on each of `topics`, 1 ms apart
+/// from `start_ns`
+fn write_mcap(path: &Path, topics: &[&str], count: u64, start_ns: u64) {
+    let file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+    let mut writer = mcap::Writer::new(file).unwrap();
+    let schema_id = writer
+        .add_schema("std_msgs/msg/String", "ros2msg", b"string data")
+        .unwrap();
+    let channels: Vec<u16> = topics
+        .iter()
+        .map(|topic| {
+            writer
+                .add_channel(schema_id, topic, "cdr", &BTreeMap::new())
+                .unwrap()
+        })
+        .collect();
+    for i in 0..count {
+        for (channel_id, topic) in channels.iter().zip(topics) {
+            let log_time = start_ns + i * 1_000_000;
+            writer
+                .write_to_known_channel(
+                    &mcap::records::MessageHeader {
+                        channel_id: *channel_id,
+                        sequence: i as u32,
+                        log_time,
+                        publish_time: log_time - 500,
+                    },
+                    format!("{} #{}", topic, i).as_bytes(),
+                )
+                .unwrap();
+        }
+    }
+    writer.finish().unwrap();
+}
+
+fn options() -> ImportOptions {
+    ImportOptions {
+        recording_id: Some("imported-01".to_string()),
+        device_id: "robot-01".to_string(),
+        labels: HashMap::from([("site".to_string(), "lab".to_string())]),
+        batch_bytes: 256,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_import_mcap_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("drive.mcap");
+    let start_ns = 1_700_000_000_000_000_000;
+    write_mcap(&path, &["/camera/info", "/odom"], 40, start_ns);
+
+    let backend = MockBackend::new(MockConfig::default());
+    let summary = import(&backend, &[path], &options()).await.unwrap();
+    assert_eq!(summary.recording_id, "imported-01");
+    assert_eq!(summary.files, 1);
+    assert_eq!(summary.messages, 80);
+    // Batches of 256 payload bytes, so several records per topic
+    assert!(summary.records > 2, "{:?}", summary);
+
+    let metadata = find_recording(&backend, "imported-01").await.unwrap();
+    assert_eq!(metadata.device_id, "robot-01");
+    assert_eq!(metadata.topics, vec!["camera/info", "odom"]);
+    assert_eq!(metadata.total_samples, 80);
+    assert_eq!(metadata.records.len(), summary.records);
+    assert_eq!(
+        metadata.start_time,
+        chrono::DateTime::from_timestamp_nanos(start_ns as i64).to_rfc3339()
+    );
+
+    // Every message made it, in order, relabeled with the new recording
+    let records = backend.records(&topic_to_entry_name("odom"));
+    assert_eq!(records[0].0, start_ns / 1000);
+    let mut payloads = Vec::new();
+    for (_, record) in &records {
+        assert_eq!(record.labels["recording_id"], "imported-01");
+        assert_eq!(record.labels["topic"], "odom");
+        assert_eq!(record.labels["imported_from"], "drive.mcap");
+        assert_eq!(record.labels["site"], "lab");
+        assert!(record.labels.contains_key(CHECKSUM_LABEL));
+        let batch = parse_batch(&record.data).unwrap();
+        assert_eq!(batch.recording_id, "imported-01");
+        for message in batch.messages {
+            let sample = message.sample.unwrap();
+            assert_eq!(sample.source_timestamp_ns, message.timestamp_ns - 500);
+            assert_eq!(message.schema.unwrap().schema_name, "std_msgs/msg/String");
+            payloads.push(String::from_utf8(message.payload).unwrap());
+        }
+    }
+    let expected: Vec<String> = (0..40).map(|i| format!("/odom #{}", i)).collect();
+    assert_eq!(payloads, expected);
+}
+
+#[tokio::test]
+async fn test_import_rosbag2_directory() {
+    let dir = TempDir::new().unwrap();
+    let bag = dir.path().join("bag");
+    std::fs::create_dir(&bag).unwrap();
+    let start_ns = 1_700_000_000_000_000_000;
+    write_mcap(&bag.join("bag_0.mcap"), &["/scan"], 10, start_ns);
+    write_mcap(
+        &bag.join("bag_1.mcap"),
+        &["/scan"],
+        10,
+        start_ns + 10_000_000,
+    );
+    std::fs::write(
+        bag.join("metadata.yaml"),
+        "rosbag2_bagfile_information:\n  version: 8\n  storage_identifier: mcap\n  relative_file_paths:\n    - bag_0.mcap\n    - bag_1.mcap\n",
+    )
+    .unwrap();
+
+    let backend = MockBackend::new(MockConfig::default());
+    let summary = import(&backend, std::slice::from_ref(&bag), &options())
+        .await
+        .unwrap();
+    assert_eq!(summary.files, 2);
+    assert_eq!(summary.messages, 20);
+    let sources: Vec<String> = backend
+        .records(&topic_to_entry_name("scan"))
+        .into_iter()
+        .map(|(_, record)| record.labels["imported_from"].clone())
+        .collect();
+    assert_eq!(sources.first().unwrap(), "bag_0.mcap");
+    assert_eq!(sources.last().unwrap(), "bag_1.mcap");
+
+    // sqlite3 bags have to be converted first
+    std::fs::write(
+        bag.join("metadata.yaml"),
+        "rosbag2_bagfile_information:\n  storage_identifier: sqlite3\n  relative_file_paths:\n    - bag_0.db3\n",
+    )
+    .unwrap();
+    let error = import(&backend, &[bag], &options()).await.unwrap_err();
+    assert!(error.to_string().contains("sqlite3"), "{:#}", error);
+}
+
+#[tokio::test]
+async fn test_import_without_messages_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("empty.mcap");
+    write_mcap(&path, &["/odom"], 0, 0);
+
+    let backend = MockBackend::new(MockConfig::default());
+    assert!(import(&backend, &[path], &options()).await.is_err());
+    assert!(backend.records("recordings_metadata").is_empty());
+}
@@ -0,0 +1,320 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the upload of finished recordings to an ingestion API
+///
+mod common;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::IngestionConfig;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+/// State of the fake ingestion API
+#[derive(Default)]
+struct Api {
+    /// Number of the last token issued; only the second and later are valid
+    tokens_issued: u32,
+    /// Record bytes by path
+    records: HashMap<String, Vec<u8>>,
+    patches: usize,
+    /// Keep the bytes of the first PATCH but answer it with a 500
+    fail_first_patch: bool,
+    /// Status answered to the manifest
+    manifest_status: u16,
+    manifests: Vec<Vec<u8>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Request {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the request was read");
+        data.extend_from_slice(&buf[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap().split(' ');
+    let method = request_line.next().unwrap().to_string();
+    let path = request_line.next().unwrap().to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let length: usize = headers
+        .get("content-length")
+        .map_or(0, |length| length.parse().unwrap());
+    while data.len() < head_end + length {
+        let n = stream.read(&mut buf).await.unwrap();
+        data.extend_from_slice(&buf[..n]);
+    }
+    Request {
+        method,
+        path,
+        headers,
+        body: data[head_end..head_end + length].to_vec(),
+    }
+}
+
+/// Status, extra headers and body answered to `request`
+fn answer(api: &Mutex<Api>, request: Request) -> (u16, Vec<(String, String)>, Vec<u8>) {
+    let mut api = api.lock().unwrap();
+    if request.path == "/token" {
+        assert_eq!(request.method, "POST");
+        let form = String::from_utf8(request.body).unwrap();
+        assert!(form.contains("grant_type=client_credentials"));
+        assert!(form.contains("client_id=robot-01"));
+        assert!(form.contains("client_secret=s3cret"));
+        api.tokens_issued += 1;
+        let token = format!(
+            r#"{{"access_token": "token-{}", "expires_in": 3600}}"#,
+            api.tokens_issued
+        );
+        return (200, vec![], token.into_bytes());
+    }
+
+    // The first token was revoked
+    let authorization = request.headers.get("authorization").cloned();
+    let valid =
+        (2..=api.tokens_issued).any(|n| authorization == Some(format!("Bearer token-{}", n)));
+    if !valid {
+        return (401, vec![], vec![]);
+    }
+
+    let Some(path) = request.path.strip_prefix("/api/recordings/") else {
+        return (404, vec![], vec![]);
+    };
+    let held = api.records.get(path).map_or(0, |bytes| bytes.len());
+    let offset_header = |offset: usize| vec![("upload-offset".to_string(), offset.to_string())];
+    match request.method.as_str() {
+        "HEAD" if api.records.contains_key(path) => (200, offset_header(held), vec![]),
+        "HEAD" => (404, vec![], vec![]),
+        "PATCH" => {
+            let offset: usize = request.headers["upload-offset"].parse().unwrap();
+            if offset != held {
+                return (409, offset_header(held), vec![]);
+            }
+            let length: usize = request.headers["upload-length"].parse().unwrap();
+            let record = api.records.entry(path.to_string()).or_default();
+            record.extend_from_slice(&request.body);
+            assert!(record.len() <= length);
+            let held = record.len();
+            api.patches += 1;
+            if api.fail_first_patch && api.patches == 1 {
+                return (500, vec![], vec![]);
+            }
+            (204, offset_header(held), vec![])
+        }
+        "PUT" => {
+            api.manifests.push(request.body);
+            (api.manifest_status, vec![], vec![])
+        }
+        method => panic!("unexpected {} {}", method, request.path),
+    }
+}
+
+/// Serve the fake ingestion API; returns its base URL
+async fn serve(api: Arc<Mutex<Api>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let api = api.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut stream).await;
+                let (status, headers, body) = answer(&api, request);
+                let mut response = format!(
+                    "HTTP/1.1 {} X\r\ncontent-length: {}\r\nconnection: close\r\n",
+                    status,
+                    body.len()
+                );
+                for (name, value) in headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str("\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            });
+        }
+    });
+    url
+}
+
+fn create_test_manager(
+    data_dir: &Path,
+    ingestion: IngestionConfig,
+) -> (Arc<Session>, RecorderManager) {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.device_id = "robot-01".to_string();
+    config.recorder.ingestion = ingestion;
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session.clone(), config);
+    (session, manager)
+}
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        scene: Some("ingestion".to_string()),
+        device_id: "robot-01".to_string(),
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+/// Record `count` samples on `topic` and finish the recording
+async fn record(session: &Session, manager: &RecorderManager, topic: &str, count: usize) -> String {
+    let recording_id = manager
+        .start_recording(start_request(topic))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..count {
+        session
+            .put(topic, format!("sample {} of the ingestion test", i))
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+    recording_id
+}
+
+/// Status of a recording once it's no longer `finished`, or after 5 s
+async fn settled_status(manager: &RecorderManager, recording_id: &str) -> RecordingStatus {
+    for _ in 0..100 {
+        let status = manager.get_status(recording_id).await.status;
+        if status != RecordingStatus::Finished {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    RecordingStatus::Finished
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_is_uploaded_and_archived() {
+    let data_dir = TempDir::new().unwrap();
+    let api = Arc::new(Mutex::new(Api {
+        fail_first_patch: true,
+        manifest_status: 201,
+        ..Default::default()
+    }));
+    let url = serve(api.clone()).await;
+    let (session, manager) = create_test_manager(
+        data_dir.path(),
+        IngestionConfig {
+            enabled: true,
+            endpoint: format!("{}/api/", url),
+            include_data: true,
+            token_url: Some(format!("{}/token", url)),
+            client_id: Some("robot-01".to_string()),
+            client_secret: Some("s3cret".to_string()),
+            chunk_size_bytes: 256,
+            retry_delay_ms: 10,
+            ..Default::default()
+        },
+    );
+
+    let recording_id = record(&session, &manager, "test/ingestion/archived", 50).await;
+    assert_eq!(
+        settled_status(&manager, &recording_id).await,
+        RecordingStatus::Archived
+    );
+
+    let api = api.lock().unwrap();
+    // The revoked token was replaced once, then reused
+    assert_eq!(api.tokens_issued, 2);
+    assert_eq!(api.manifests.len(), 1);
+    let manifest: RecordingMetadata = serde_json::from_slice(&api.manifests[0]).unwrap();
+    assert_eq!(manifest.recording_id, recording_id);
+    assert_eq!(manifest.device_id, "robot-01");
+    assert!(!manifest.records.is_empty());
+
+    // Every record arrived whole, in several chunks, despite the failed one
+    assert_eq!(api.records.len(), manifest.records.len());
+    for record in &manifest.records {
+        let path = format!(
+            "{}/records/{}/{}",
+            recording_id, record.entry, record.timestamp_us
+        );
+        let stored = std::fs::read(
+            data_dir
+                .path()
+                .join(&record.entry)
+                .join(format!("{}.mcap", record.timestamp_us)),
+        )
+        .unwrap();
+        assert_eq!(api.records[&path], stored);
+    }
+    assert!(api.patches > manifest.records.len());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rejected_manifest_keeps_recording_finished() {
+    let data_dir = TempDir::new().unwrap();
+    let api = Arc::new(Mutex::new(Api {
+        tokens_issued: 2,
+        manifest_status: 400,
+        ..Default::default()
+    }));
+    let url = serve(api.clone()).await;
+    let (session, manager) = create_test_manager(
+        data_dir.path(),
+        IngestionConfig {
+            enabled: true,
+            endpoint: format!("{}/api", url),
+            token: Some("token-2".to_string()),
+            max_retries: 1,
+            retry_delay_ms: 10,
+            ..Default::default()
+        },
+    );
+
+    let recording_id = record(&session, &manager, "test/ingestion/rejected", 5).await;
+    for _ in 0..100 {
+        if api.lock().unwrap().manifests.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        manager.get_status(&recording_id).await.status,
+        RecordingStatus::Finished
+    );
+    let api = api.lock().unwrap();
+    // Sent and retried once, with no records without include_data
+    assert_eq!(api.manifests.len(), 2);
+    assert!(api.records.is_empty());
+}
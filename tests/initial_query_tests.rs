@@ -0,0 +1,82 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the queries of the latest topic values at Start
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+
+const MAP: &str = "test/initial_query/map";
+const PARAMS: &str = "test/initial_query/params";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_latest_values_queried_at_start() {
+    let mut config = RecorderConfig::default();
+    config.recorder.initial_query.topics = vec![MAP.to_string()];
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+
+    // Stand-in for a storage holding the last published values
+    let _storage = session
+        .declare_queryable("test/initial_query/*")
+        .callback(|query| {
+            let key = query.key_expr().clone();
+            query
+                .reply(key.clone(), format!("latest {}", key))
+                .wait()
+                .unwrap();
+        })
+        .wait()
+        .unwrap();
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("initial".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&[MAP, PARAMS])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    session.put(MAP, "live").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording("initial").await.success);
+    manager
+        .wait_for_completion("initial", Duration::from_secs(5))
+        .await;
+
+    // The map starts with the queried value, the parameters weren't queried
+    let payloads: Vec<Vec<u8>> = backend
+        .records("test_initial_query_map")
+        .iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| message.payload)
+        .collect();
+    assert_eq!(
+        payloads,
+        vec![format!("latest {}", MAP).into_bytes(), b"live".to_vec()]
+    );
+    assert!(backend.records("test_initial_query_params").is_empty());
+}
@@ -0,0 +1,262 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the stored batch viewer
+///
+use tempfile::TempDir;
+use zenoh::key_expr::KeyExpr;
+use zenoh::sample::{Sample, SampleBuilder};
+use zenoh_recorder::error::RecorderError;
+use zenoh_recorder::inspect::{
+    inspect_path, parse_batch, parse_batch_range, DumpFormat, InspectOptions,
+};
+use zenoh_recorder::mcap_writer::McapSerializer;
+use zenoh_recorder::protocol::{CompressionLevel, CompressionType};
+use zenoh_recorder::transform::DeltaTransform;
+
+fn serialize(compression: CompressionType, payloads: &[&[u8]]) -> Vec<u8> {
+    let key: KeyExpr<'static> = "test/inspect".try_into().unwrap();
+    let samples: Vec<Sample> = payloads
+        .iter()
+        .map(|p| SampleBuilder::put(key.clone(), p.to_vec()).into())
+        .collect();
+    let timestamps: Vec<u64> = (0..payloads.len() as u64)
+        .map(|i| 1_700_000_000_000_000_000 + i * 500_000_000)
+        .collect();
+    McapSerializer::new(compression, CompressionLevel::Default)
+        .serialize_timestamped_batch("/test/inspect", samples, &timestamps, "rec-42")
+        .unwrap()
+}
+
+fn options(dump: Vec<usize>, format: DumpFormat) -> InspectOptions {
+    InspectOptions {
+        dump,
+        dump_all: false,
+        format,
+        time_range: None,
+    }
+}
+
+#[test]
+fn test_parse_every_codec() {
+    for codec in [
+        CompressionType::None,
+        CompressionType::Lz4,
+        CompressionType::Zstd,
+    ] {
+        let data = serialize(codec, &[b"one", b"two", b"three"]);
+        let batch = parse_batch(&data).unwrap();
+
+        assert_eq!(batch.codec, codec);
+        assert_eq!(batch.topic, "/test/inspect");
+        assert_eq!(batch.recording_id, "rec-42");
+        assert_eq!(batch.header_count, 3);
+        assert_eq!(batch.messages.len(), 3);
+        assert_eq!(batch.messages[2].payload, b"three");
+        assert_eq!(batch.stored_bytes, data.len());
+        assert_eq!(batch.payload_bytes(), 11);
+        assert_eq!(
+            batch.time_range(),
+            Some((1_700_000_000_000_000_000, 1_700_000_001_000_000_000))
+        );
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(matches!(
+        parse_batch(b"not a batch\n"),
+        Err(RecorderError::Serialization(_))
+    ));
+
+    let data = serialize(CompressionType::None, &[b"payload"]);
+    let error = parse_batch(&data[..data.len() - 2]).unwrap_err();
+    assert!(error.to_string().contains("truncated"), "{}", error);
+}
+
+#[test]
+fn test_inspect_entry_directory() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("2000.mcap"),
+        serialize(CompressionType::Zstd, &[b"b"]),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("1000.mcap"),
+        serialize(CompressionType::Lz4, &[br#"{"speed": 1.5}"#, b"\xff\x01"]),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("1000.meta.json"), b"{}").unwrap();
+
+    let mut out = Vec::new();
+    inspect_path(dir.path(), &options(vec![0, 1], DumpFormat::Json), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    // Records in timestamp order, label sidecars skipped
+    let first = out.find("1000.mcap").unwrap();
+    let second = out.find("2000.mcap").unwrap();
+    assert!(first < second);
+    assert!(!out.contains("meta.json"));
+
+    assert!(out.contains("codec:        Lz4"));
+    assert!(out.contains("time range:   2023-11-14T22:13:20.000000000Z"));
+    assert!(out.contains(r#""payload":{"speed":1.5}"#));
+    assert!(out.contains(r#""payload":"ff01""#));
+    assert!(out.contains("2 records, 3 messages"));
+}
+
+#[test]
+fn test_inspect_hex_dump() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("1.mcap");
+    std::fs::write(&file, serialize(CompressionType::None, &[b"hello zenoh"])).unwrap();
+
+    let mut out = Vec::new();
+    inspect_path(&file, &options(vec![0], DumpFormat::Hex), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.contains("messages:     1"));
+    assert!(out.contains("#0 2023-11-14T22:13:20.000000000Z (11 bytes)"));
+    assert!(out.contains("00000000  68 65 6c 6c 6f 20 7a 65 6e 6f 68"));
+    assert!(out.contains("|hello zenoh|"));
+}
+
+#[test]
+fn test_sample_metadata() {
+    let key: KeyExpr<'static> = "test/inspect".try_into().unwrap();
+    let samples: Vec<Sample> = vec![
+        SampleBuilder::put(key.clone(), b"frame".to_vec()).into(),
+        SampleBuilder::delete(key).into(),
+    ];
+    let timestamps = [1_700_000_000_000_000_000, 1_700_000_000_500_000_000];
+    let received = [1_700_000_000_100_000_000, 1_700_000_000_600_000_000];
+    let data = McapSerializer::new(CompressionType::None, CompressionLevel::Default)
+        .serialize_received_batch("/test/inspect", samples, &timestamps, &received, "rec-42")
+        .unwrap();
+    let batch = parse_batch(&data).unwrap();
+
+    let put = batch.messages[0].sample.as_ref().unwrap();
+    assert_eq!(put.kind, "put");
+    assert_eq!(put.encoding, "zenoh/bytes");
+    assert_eq!(put.received_ns, 1_700_000_000_100_000_000);
+    // No publisher timestamp: the record timestamp came from the recorder
+    assert_eq!(put.source_timestamp_ns, 0);
+    assert!(put.source_id.is_empty());
+    assert_eq!(put.congestion_control, "drop");
+    assert_eq!(batch.messages[1].sample.as_ref().unwrap().kind, "delete");
+
+    let mut out = Vec::new();
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("1.mcap");
+    std::fs::write(&file, &data).unwrap();
+    inspect_path(&file, &options(vec![0], DumpFormat::Json), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(r#""kind":"put""#));
+    assert!(out.contains(r#""received_ns":1700000000100000000"#));
+}
+
+#[test]
+fn test_seekable_range_read() {
+    let key: KeyExpr<'static> = "test/inspect".try_into().unwrap();
+    let samples: Vec<Sample> = (0..20u8)
+        .map(|i| SampleBuilder::put(key.clone(), vec![i; 200]).into())
+        .collect();
+    let timestamps: Vec<u64> = (0..20).map(|i| 1_000_000 + i * 1_000).collect();
+    let data = McapSerializer::new(CompressionType::Zstd, CompressionLevel::Default)
+        .with_seekable_frame_bytes(1000)
+        .serialize_timestamped_batch("/test/inspect", samples, &timestamps, "rec-42")
+        .unwrap();
+
+    // Plain decoding reads every frame
+    let batch = parse_batch(&data).unwrap();
+    assert_eq!(batch.codec, CompressionType::Zstd);
+    assert_eq!(batch.messages.len(), 20);
+    let (_, total) = batch.frames.unwrap();
+    assert!(total > 3, "{} frames", total);
+
+    // A range only decompresses the header and the frames overlapping it
+    let batch = parse_batch_range(&data, 1_005_000, 1_006_000).unwrap();
+    assert_eq!(batch.recording_id, "rec-42");
+    assert_eq!(batch.header_count, 20);
+    let payloads: Vec<u8> = batch.messages.iter().map(|m| m.payload[0]).collect();
+    assert_eq!(payloads, vec![5, 6]);
+    let (decompressed, _) = batch.frames.unwrap();
+    assert!(decompressed < total, "{} of {} frames", decompressed, total);
+
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("1.mcap");
+    std::fs::write(&file, &data).unwrap();
+    let mut out = Vec::new();
+    let options = InspectOptions {
+        time_range: Some((1_005_000, 1_006_000)),
+        ..options(vec![], DumpFormat::Hex)
+    };
+    inspect_path(&file, &options, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("messages:     2 in range (of 20)"), "{}", out);
+    assert!(
+        out.contains(&format!("of {} decompressed", total)),
+        "{}",
+        out
+    );
+}
+
+#[test]
+fn test_delta_transform_round_trip() {
+    let key: KeyExpr<'static> = "test/inspect".try_into().unwrap();
+    // Consecutive "scans" with every 8th byte drifting
+    let mut scan: Vec<u8> = (0..4096u32).map(|j| (j * 7919 % 251) as u8).collect();
+    let payloads: Vec<Vec<u8>> = (0..30)
+        .map(|i| {
+            for byte in scan.iter_mut().skip(i % 8).step_by(8) {
+                *byte = byte.wrapping_add(1);
+            }
+            scan.clone()
+        })
+        .collect();
+    let timestamps: Vec<u64> = (0..30).map(|i| 1_000_000 + i * 1_000).collect();
+    let serialize = |transform: Option<std::sync::Arc<DeltaTransform>>| {
+        let samples: Vec<Sample> = payloads
+            .iter()
+            .map(|p| SampleBuilder::put(key.clone(), p.clone()).into())
+            .collect();
+        McapSerializer::new(CompressionType::Zstd, CompressionLevel::Default)
+            .with_seekable_frame_bytes(16 * 1024)
+            .with_transform(transform.map(|t| t as _))
+            .serialize_timestamped_batch("/test/inspect", samples, &timestamps, "rec-42")
+            .unwrap()
+    };
+
+    let plain = serialize(None);
+    let delta = serialize(Some(std::sync::Arc::new(DeltaTransform::new(8))));
+    assert!(
+        delta.len() * 2 < plain.len(),
+        "{} vs {} bytes",
+        delta.len(),
+        plain.len()
+    );
+
+    // Readers get the published payloads back
+    let batch = parse_batch(&delta).unwrap();
+    let decoded: Vec<Vec<u8>> = batch.messages.iter().map(|m| m.payload.clone()).collect();
+    assert_eq!(decoded, payloads);
+    assert!(batch.messages.iter().all(|m| m.transform.is_empty()));
+
+    // Ranges starting between keyframes fall back to the whole batch
+    let batch = parse_batch_range(&delta, 1_020_000, 1_021_000).unwrap();
+    assert_eq!(batch.messages.len(), 2);
+    assert_eq!(batch.messages[0].payload, payloads[20]);
+    assert_eq!(batch.messages[1].payload, payloads[21]);
+}
@@ -43,6 +43,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -92,8 +96,6 @@ async fn test_start_recording() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("test_scene".to_string()),
         skills: vec!["skill1".to_string()],
         organization: Some("test_org".to_string()),
@@ -101,8 +103,7 @@ async fn test_start_recording() {
         device_id: "device-01".to_string(),
         data_collector_id: Some("collector-01".to_string()),
         topics: vec!["test/topic1".to_string()],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -129,6 +130,7 @@ async fn test_pause_nonexistent_recording() {
 
     assert!(!response.success);
     assert!(response.message.contains("not found"));
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -149,6 +151,7 @@ async fn test_resume_nonexistent_recording() {
 
     assert!(!response.success);
     assert!(response.message.contains("not found"));
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -224,7 +227,7 @@ async fn test_manager_shutdown() {
     );
 
     // Shutdown should succeed even with no active recordings
-    let result = manager.shutdown().await;
+    let result = manager.shutdown(None).await;
     assert!(result.is_ok());
 }
 
@@ -244,17 +247,12 @@ async fn test_recording_lifecycle() {
 
     // Start recording
     let start_request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("test".to_string()),
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device-test".to_string(),
-        data_collector_id: None,
         topics: vec!["test/integration".to_string()],
         compression_level: CompressionLevel::Fast,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let start_response = manager.start_recording(start_request).await;
@@ -0,0 +1,123 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for record checksums and read-back verification
+///
+mod common;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::RecorderConfig;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::{checksum, CHECKSUM_LABEL};
+
+const TOPIC: &str = "test/integrity/lidar";
+
+fn create_test_manager(session: Arc<zenoh::Session>, data_dir: &Path) -> RecorderManager {
+    create_manager_with(session, data_dir, |config| {
+        config.recorder.integrity.verify_every = 1;
+    })
+}
+
+fn create_manager_with(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    configure: impl FnOnce(&mut RecorderConfig),
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    // Flush every sample into its own record
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    configure(&mut config);
+
+    common::create_test_manager(session, config)
+}
+
+/// Record `count` samples, each in its own record, and finish
+async fn record_samples(manager: &RecorderManager, session: &zenoh::Session, count: u8) {
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            compression_type: CompressionType::Lz4,
+            ..common::start_request(&[TOPIC])
+        })
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    for i in 0..count {
+        publisher.put(vec![i; 64]).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    manager.finish_recording(&recording_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_records_carry_checksums() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), data_dir.path());
+    record_samples(&manager, &session, 5).await;
+
+    let metadata = common::read_metadata(data_dir.path());
+    assert!(metadata.records.len() >= 5);
+    assert_eq!(metadata.verify_failures, 0);
+    assert_eq!(metadata.records_verified, metadata.records.len() as u64);
+
+    for record in &metadata.records {
+        assert_eq!(record.topic, TOPIC);
+        let entry_dir = data_dir.path().join(&record.entry);
+
+        // The manifest checksum matches the stored bytes
+        let data = std::fs::read(entry_dir.join(format!("{}.mcap", record.timestamp_us))).unwrap();
+        assert_eq!(data.len(), record.bytes);
+        assert_eq!(checksum(&data), record.crc32c);
+
+        // And so does the record label
+        let labels: HashMap<String, String> = serde_json::from_slice(
+            &std::fs::read(entry_dir.join(format!("{}.meta.json", record.timestamp_us))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(labels[CHECKSUM_LABEL], record.crc32c);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_verify_percent_reads_back_a_share() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_manager_with(session.clone(), data_dir.path(), |config| {
+        config.recorder.integrity.verify_percent = 50.0;
+    });
+    record_samples(&manager, &session, 6).await;
+
+    let metadata = common::read_metadata(data_dir.path());
+    assert!(metadata.records.len() >= 6);
+    assert_eq!(metadata.verify_failures, 0);
+    assert_eq!(metadata.records_verified, metadata.records.len() as u64 / 2);
+}
+
+#[test]
+fn test_checksum_format() {
+    // CRC32C check value
+    assert_eq!(checksum(b"123456789"), "e3069283");
+    assert_eq!(checksum(b""), "00000000");
+}
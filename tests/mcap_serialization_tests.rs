@@ -14,8 +14,11 @@
 
 use zenoh::key_expr::KeyExpr;
 use zenoh::sample::Sample;
+use zenoh_recorder::config::{SchemaConfig, TopicSchemaInfo};
+use zenoh_recorder::inspect::parse_batch;
 use zenoh_recorder::mcap_writer::McapSerializer;
 use zenoh_recorder::protocol::{CompressionLevel, CompressionType};
+use zenoh_recorder::sample_transform::{DropSamples, RedactJson, SampleTransform};
 
 // Helper function to create samples
 fn create_sample(topic: &'static str, data: Vec<u8>) -> Sample {
@@ -257,3 +260,153 @@ fn test_recording_id_in_output() {
     let result_str = String::from_utf8_lossy(&result);
     assert!(result_str.contains("unique-rec-id-456"));
 }
+
+#[test]
+fn test_clock_offset() {
+    let serializer = McapSerializer::new(CompressionType::Zstd, CompressionLevel::Default)
+        .with_clock_offset_ns(-250);
+    let samples = vec![
+        create_sample("test/topic", b"a".to_vec()),
+        create_sample("test/topic", b"b".to_vec()),
+    ];
+
+    let result = serializer
+        .serialize_received_batch(
+            "/test/topic",
+            samples,
+            &[1_000, 2_000],
+            &[1_100, 2_100],
+            "rec-123",
+        )
+        .unwrap();
+
+    // Record timestamps are corrected, the receive times kept as observed
+    let messages = parse_batch(&result).unwrap().messages;
+    let timestamps: Vec<i64> = messages.iter().map(|m| m.timestamp_ns).collect();
+    assert_eq!(timestamps, vec![750, 1_750]);
+    assert_eq!(messages[0].sample.as_ref().unwrap().received_ns, 1_100);
+}
+
+#[test]
+fn test_sample_transforms() {
+    let transforms: Vec<std::sync::Arc<dyn SampleTransform>> = vec![
+        std::sync::Arc::new(DropSamples::new(
+            None,
+            Some("debug"),
+            Some(serde_json::json!(true)),
+        )),
+        std::sync::Arc::new(RedactJson::new(
+            &["lat".to_string()],
+            serde_json::Value::Null,
+        )),
+    ];
+    let serializer = McapSerializer::new(CompressionType::Zstd, CompressionLevel::Default)
+        .with_sample_transforms(transforms);
+    let samples = vec![
+        create_sample("test/gps", br#"{"lat":48.1,"debug":false}"#.to_vec()),
+        create_sample("test/gps", br#"{"lat":48.2,"debug":true}"#.to_vec()),
+        create_sample("test/gps", b"binary".to_vec()),
+    ];
+
+    let result = serializer
+        .serialize_timestamped_batch("/test/gps", samples, &[1_000, 2_000, 3_000], "rec-123")
+        .unwrap();
+
+    // The dropped sample takes its timestamp with it
+    let messages = parse_batch(&result).unwrap().messages;
+    let timestamps: Vec<i64> = messages.iter().map(|m| m.timestamp_ns).collect();
+    assert_eq!(timestamps, vec![1_000, 3_000]);
+    assert_eq!(
+        messages[0].payload,
+        br#"{"debug":false,"lat":null}"#.to_vec()
+    );
+    assert_eq!(messages[1].payload, b"binary".to_vec());
+
+    // Nothing left to store
+    let samples = vec![create_sample("test/gps", br#"{"debug":true}"#.to_vec())];
+    let result = serializer
+        .serialize_batch("/test/gps", samples, "rec-123")
+        .unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_schema_written_once_per_batch() {
+    let mut schema_config = SchemaConfig {
+        include_metadata: true,
+        ..Default::default()
+    };
+    schema_config.per_topic.insert(
+        "/test/typed".to_string(),
+        TopicSchemaInfo {
+            format: "json".to_string(),
+            schema_name: Some("test/TypedSchemaName".to_string()),
+            schema_hash: None,
+        },
+    );
+    let serializer = McapSerializer::with_schema_config(
+        CompressionType::None,
+        CompressionLevel::Default,
+        schema_config,
+    );
+    let samples = (0..10)
+        .map(|i| create_sample("test/typed", vec![i]))
+        .collect();
+    let batch = serializer
+        .serialize_batch("/test/typed", samples, "rec-123")
+        .unwrap();
+
+    let name = b"test/TypedSchemaName";
+    assert_eq!(batch.windows(name.len()).filter(|w| w == name).count(), 1);
+    // Every message reads the schema of the batch
+    let messages = parse_batch(&batch).unwrap().messages;
+    assert_eq!(messages.len(), 10);
+    for message in messages {
+        let schema = message.schema.unwrap();
+        assert_eq!(schema.format, "json");
+        assert_eq!(schema.schema_name, "test/TypedSchemaName");
+    }
+}
+
+#[test]
+fn test_index_locates_messages() {
+    use prost::Message;
+    use zenoh_recorder::proto::RecordedMessage;
+
+    let samples = || {
+        (0..20)
+            .map(|i| create_sample("test/indexed", vec![i as u8; 100 + i]))
+            .collect::<Vec<_>>()
+    };
+    let timestamps: Vec<u64> = (0..20).map(|i| 5_000 - i * 10).collect();
+
+    // Uncompressed batches are read at the offsets directly
+    let serializer = McapSerializer::new(CompressionType::None, CompressionLevel::Default);
+    let (batch, index) = serializer
+        .serialize_indexed_batch("/test/indexed", samples(), &timestamps, &[], "rec-123")
+        .unwrap();
+    assert_eq!(index.samples, 20);
+    assert_eq!((index.first_ns, index.last_ns), (4_810, 5_000));
+    assert!(index.frames.is_empty());
+    for (i, &offset) in index.offsets.iter().enumerate() {
+        let offset = offset as usize;
+        let len = u32::from_le_bytes(batch[offset..offset + 4].try_into().unwrap()) as usize;
+        let message = RecordedMessage::decode(&batch[offset + 4..offset + 4 + len]).unwrap();
+        assert_eq!(message.payload, vec![i as u8; 100 + i]);
+    }
+
+    // Seekable batches list their frames, the header frame first
+    let serializer = McapSerializer::new(CompressionType::Zstd, CompressionLevel::Default)
+        .with_seekable_frame_bytes(512);
+    let (batch, seekable_index) = serializer
+        .serialize_indexed_batch("/test/indexed", samples(), &timestamps, &[], "rec-123")
+        .unwrap();
+    assert_eq!(seekable_index.offsets, index.offsets);
+    assert!(seekable_index.frames.len() > 2);
+    assert_eq!(seekable_index.frames[0].time_range, None);
+    assert!(seekable_index.frames[1..]
+        .iter()
+        .all(|frame| frame.time_range.is_some()));
+    let last = seekable_index.frames.last().unwrap();
+    assert!(last.offset + last.compressed_bytes <= batch.len() as u64);
+}
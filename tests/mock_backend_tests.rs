@@ -0,0 +1,112 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for recording against the mock backend with injected faults
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/mock_backend/counter";
+
+fn create_test_manager(config: MockConfig) -> (Arc<Session>, Arc<MockBackend>, RecorderManager) {
+    let mut recorder_config = RecorderConfig::default();
+    recorder_config
+        .recorder
+        .flush_policy
+        .max_buffer_duration_seconds = 1;
+    let backend = Arc::new(MockBackend::new(config));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), recorder_config);
+    (session, backend, manager)
+}
+
+fn start_request() -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+/// Counter values stored by the mock backend
+fn stored_counters(backend: &MockBackend) -> Vec<u32> {
+    let mut counters: Vec<u32> = backend
+        .records(&topic_to_entry_name(TOPIC))
+        .into_iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| String::from_utf8(message.payload).unwrap().parse().unwrap())
+        .collect();
+    counters.sort_unstable();
+    counters
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_failed_writes_are_retried() {
+    // Every other write fails, each slowed down a bit
+    let (session, backend, manager) = create_test_manager(MockConfig {
+        latency_ms: 10,
+        error_rate: 0.5,
+        ..Default::default()
+    });
+    let recording_id = manager
+        .start_recording(start_request())
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    for counter in 0..30u32 {
+        session.put(TOPIC, counter.to_string()).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    assert_eq!(stored_counters(&backend), (0..30).collect::<Vec<_>>());
+    assert!(backend.failed_writes() > 0);
+    assert_eq!(backend.failed_writes(), backend.write_attempts() / 2);
+    // The metadata record made it through the faults too
+    assert_eq!(backend.records("recordings_metadata").len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_full_backend_refuses_recordings() {
+    let (session, backend, manager) = create_test_manager(MockConfig::default());
+    let recording_id = manager
+        .start_recording(start_request())
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, "1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    // No room left for the next recording
+    backend.set_capacity_bytes(backend.stored_bytes());
+    let response = manager.start_recording(start_request()).await;
+    assert!(!response.success);
+    assert_eq!(response.error_code, Some(ErrorCode::QuotaExceeded));
+
+    backend.set_capacity_bytes(0);
+    assert!(manager.start_recording(start_request()).await.success);
+}
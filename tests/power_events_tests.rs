@@ -0,0 +1,97 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Emergency flush triggered by shutdown or low-battery signals
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recovery::SessionStateStore;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/power_events/imu";
+
+fn start_request(priority: i32) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        priority: Some(priority),
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_emergency_flush_checkpoints_recordings() {
+    let data_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    // Nothing would be flushed for an hour
+    config.recorder.flush_policy.max_buffer_duration_seconds = 3600;
+    config.recorder.recovery.enabled = true;
+    config.recorder.recovery.state_dir = state_dir.path().to_string_lossy().to_string();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session.clone(), config);
+
+    let low = manager.start_recording(start_request(0)).await;
+    let high = manager.start_recording(start_request(5)).await;
+    let recording_ids = [low.recording_id.unwrap(), high.recording_id.unwrap()];
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session.put(TOPIC, format!("sample-{}", i)).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for recording_id in &recording_ids {
+        assert!(manager.get_status(recording_id).await.buffer_size_bytes > 0);
+    }
+
+    let flushed = manager
+        .emergency_flush("battery at 5%", Duration::from_secs(5))
+        .await;
+    assert_eq!(flushed, 2);
+
+    // Written out and checkpointed, still recording
+    let entry_dir = data_dir.path().join(topic_to_entry_name(TOPIC));
+    let records = std::fs::read_dir(entry_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+        .count();
+    assert_eq!(records, 2);
+    let states = SessionStateStore::new(state_dir.path())
+        .load_all()
+        .await
+        .unwrap();
+    assert_eq!(states.len(), 2);
+    for state in &states {
+        assert_eq!(state.flushed_batches, 1);
+        assert!(state.last_flush_us.is_some());
+    }
+    for recording_id in &recording_ids {
+        let status = manager.get_status(recording_id).await;
+        assert_eq!(status.status, RecordingStatus::Recording);
+        assert_eq!(status.buffer_size_bytes, 0);
+    }
+
+    // Ended recordings are left alone
+    manager.cancel_recording(&recording_ids[0]).await;
+    let flushed = manager
+        .emergency_flush("system shutdown", Duration::from_secs(5))
+        .await;
+    assert_eq!(flushed, 1);
+    manager.cancel_recording(&recording_ids[1]).await;
+}
@@ -0,0 +1,72 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Preview channel tests using the filesystem backend
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::PreviewPolicy;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::BackendFactory;
+
+const CAMERA: &str = "test/preview/camera";
+const IMU: &str = "test/preview/imu";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_preview_keeps_one_sample_per_interval() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.preview.per_topic.insert(
+        "test/preview/camera".to_string(),
+        PreviewPolicy { interval_ms: 1000 },
+    );
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![CAMERA.to_string(), IMU.to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // A burst within one interval, then one more sample after it
+    for i in 0..10 {
+        session.put(CAMERA, format!("frame-{}", i)).wait().unwrap();
+        session.put(IMU, format!("imu-{}", i)).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    session.put(CAMERA, "frame-10").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    let preview = "test/preview/camera/preview";
+    assert_eq!(metadata.previews.len(), 1);
+    assert_eq!(metadata.previews[CAMERA], preview);
+    assert_eq!(metadata.per_topic_stats[CAMERA]["samples"], 11);
+    assert_eq!(metadata.per_topic_stats[preview]["samples"], 2);
+    assert!(metadata.records.iter().any(|r| r.topic == preview));
+}
@@ -47,7 +47,6 @@ fn test_compression_type_default() {
 #[test]
 fn test_recorder_request_serialization() {
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
         recording_id: Some("test-123".to_string()),
         scene: Some("test_scene".to_string()),
         skills: vec!["skill1".to_string(), "skill2".to_string()],
@@ -56,8 +55,7 @@ fn test_recorder_request_serialization() {
         device_id: "device-01".to_string(),
         data_collector_id: Some("collector-01".to_string()),
         topics: vec!["/test/topic1".to_string()],
-        compression_level: CompressionLevel::Default,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -67,6 +65,35 @@ fn test_recorder_request_serialization() {
     assert_eq!(deserialized.topics.len(), 1);
 }
 
+#[test]
+fn test_recorder_request_priority() {
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"], "priority": -2}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.priority, Some(-2));
+
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"]}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.priority, None);
+    assert!(!serde_json::to_string(&request)
+        .unwrap()
+        .contains("priority"));
+}
+
+#[test]
+fn test_recorder_request_buffer_strategy() {
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"],
+                   "buffer_strategy": "high_throughput"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        request.buffer_strategy,
+        Some(BufferStrategy::HighThroughput)
+    );
+
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["/a"],
+                   "buffer_strategy": "fastest"}"#;
+    assert!(serde_json::from_str::<RecorderRequest>(json).is_err());
+}
+
 #[test]
 fn test_recorder_response_success() {
     let response =
@@ -79,14 +106,34 @@ fn test_recorder_response_success() {
 
 #[test]
 fn test_recorder_response_error() {
-    let response = RecorderResponse::error("Test error".to_string());
+    let response = RecorderResponse::error(ErrorCode::NotFound, "Test error".to_string());
 
     assert!(!response.success);
     assert_eq!(response.message, "Test error");
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
     assert!(response.recording_id.is_none());
     assert!(response.bucket_name.is_none());
 }
 
+#[test]
+fn test_error_code_serialization() {
+    let response =
+        RecorderResponse::error(ErrorCode::BackendUnavailable, "Storage is down".to_string());
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["error_code"], "backend_unavailable");
+
+    // Omitted on success
+    let json = serde_json::to_value(RecorderResponse::success(None, None)).unwrap();
+    assert!(json.get("error_code").is_none());
+
+    let invalid = RecorderResponse::invalid(vec![ValidationError::new("device_id", "empty")]);
+    assert_eq!(invalid.error_code, Some(ErrorCode::InvalidRequest));
+
+    let status = StatusResponse::error(ErrorCode::InvalidState, "Not active".to_string());
+    let json = serde_json::to_string(&status).unwrap();
+    assert!(json.contains("\"error_code\":\"invalid_state\""));
+}
+
 #[test]
 fn test_recording_status_serialization() {
     let status = RecordingStatus::Recording;
@@ -112,9 +159,45 @@ fn test_status_response() {
         active_topics: vec!["/topic1".to_string()],
         buffer_size_bytes: 1024,
         total_recorded_bytes: 4096,
+        total_payload_bytes: 0,
+        compression_ratio: None,
+        samples_missing_timestamp: 0,
+        topic_paused: Default::default(),
+        flush_workers: vec![],
+        compression: None,
+        record_timestamps: None,
+        throughput: None,
+        per_topic: vec![],
+        uploads: vec![],
+        upload_percent: None,
+        environment: Default::default(),
+        error_code: None,
     };
 
     assert!(response.success);
     assert_eq!(response.buffer_size_bytes, 1024);
     assert_eq!(response.total_recorded_bytes, 4096);
 }
+
+#[test]
+fn test_set_upload_limit_request_parsing() {
+    let json = r#"{
+        "command": "set_upload_limit",
+        "device_id": "robot-01",
+        "upload_limit": {"max_bytes_per_sec": 262144, "max_concurrent_uploads": 1}
+    }"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::SetUploadLimit));
+    assert_eq!(
+        request.upload_limit,
+        Some(UploadLimit {
+            max_bytes_per_sec: 262144,
+            max_concurrent_uploads: 1,
+        })
+    );
+
+    // Omitted limits are unlimited
+    let json = r#"{"command": "set_upload_limit", "device_id": "d", "upload_limit": {}}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.upload_limit, Some(UploadLimit::default()));
+}
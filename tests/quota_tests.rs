@@ -0,0 +1,167 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the recording quotas per organization and task
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh_recorder::config::{QuotaLimits, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::RecorderManagerBuilder;
+
+async fn create_test_manager(
+    data_dir: &Path,
+    configure: impl FnOnce(&mut RecorderConfig),
+) -> Arc<RecorderManager> {
+    let mut config = common::filesystem_config(data_dir);
+    configure(&mut config);
+    Arc::new(RecorderManagerBuilder::new(config).build().await.unwrap())
+}
+
+fn start_request(
+    topic: &str,
+    organization: Option<&str>,
+    task_id: Option<&str>,
+) -> RecorderRequest {
+    RecorderRequest {
+        organization: organization.map(String::from),
+        task_id: task_id.map(String::from),
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_concurrent_recordings_limited_per_organization() {
+    let data_dir = TempDir::new().unwrap();
+    let manager = create_test_manager(data_dir.path(), |config| {
+        config.recorder.quotas.organizations.insert(
+            "acme".to_string(),
+            QuotaLimits {
+                max_concurrent_recordings: 1,
+                max_bytes_per_day: 0,
+            },
+        );
+    })
+    .await;
+
+    let first = manager
+        .start_recording(start_request("test/quota/a", Some("acme"), None))
+        .await;
+    assert!(first.success, "{}", first.message);
+    let first_id = first.recording_id.unwrap();
+
+    let refused = manager
+        .start_recording(start_request("test/quota/b", Some("acme"), None))
+        .await;
+    assert!(!refused.success);
+    assert_eq!(refused.error_code, Some(ErrorCode::QuotaExceeded));
+    assert!(refused.message.contains("organization 'acme'"));
+
+    // Other organizations have no limit
+    let other = manager
+        .start_recording(start_request("test/quota/c", Some("other"), None))
+        .await;
+    assert!(other.success, "{}", other.message);
+
+    // A finished recording frees its slot
+    assert!(manager.finish_recording(&first_id).await.success);
+    let second = manager
+        .start_recording(start_request("test/quota/b", Some("acme"), None))
+        .await;
+    assert!(second.success, "{}", second.message);
+
+    let usage = manager.quota_usage().await;
+    assert!(usage.success);
+    let acme = usage
+        .quotas
+        .iter()
+        .find(|q| q.scope == QuotaScope::Organization && q.name == "acme")
+        .unwrap();
+    assert_eq!(acme.active_recordings, 1);
+    assert_eq!(acme.max_concurrent_recordings, 1);
+
+    manager.shutdown(None).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recording_finished_when_daily_bytes_used_up() {
+    const TOPIC: &str = "test/quota/bytes";
+    let data_dir = TempDir::new().unwrap();
+    let manager = create_test_manager(data_dir.path(), |config| {
+        config.recorder.flush_policy.max_buffer_size_bytes = 1;
+        config.recorder.quotas.tasks.insert(
+            "task-1".to_string(),
+            QuotaLimits {
+                max_concurrent_recordings: 0,
+                max_bytes_per_day: 1,
+            },
+        );
+    })
+    .await;
+    let enforce = {
+        let manager = manager.clone();
+        tokio::spawn(async move { manager.enforce_quotas().await })
+    };
+
+    let response = manager
+        .start_recording(start_request(TOPIC, None, Some("task-1")))
+        .await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    manager
+        .ingest_sample(TOPIC, "sample", 1_700_000_000_000_000_000)
+        .await
+        .unwrap();
+
+    let mut status = RecordingStatus::Recording;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        status = manager.get_status(&recording_id).await.status;
+        if status == RecordingStatus::Finished {
+            break;
+        }
+    }
+    assert_eq!(status, RecordingStatus::Finished);
+
+    // No new recordings of the task for the rest of the day
+    let refused = manager
+        .start_recording(start_request(TOPIC, None, Some("task-1")))
+        .await;
+    assert_eq!(refused.error_code, Some(ErrorCode::QuotaExceeded));
+
+    let usage = manager.quota_usage().await;
+    let task = usage
+        .quotas
+        .iter()
+        .find(|q| q.scope == QuotaScope::Task && q.name == "task-1")
+        .unwrap();
+    assert!(task.bytes_today >= 1);
+    assert_eq!(task.max_bytes_per_day, 1);
+
+    enforce.abort();
+    manager.shutdown(None).await.unwrap();
+}
+
+#[test]
+fn test_quotas_command_parsing() {
+    let json = r#"{"command": "quotas", "device_id": "d", "topics": []}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::Quotas));
+}
@@ -0,0 +1,116 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for splitting oversized flush batches into several records
+///
+mod common;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::topic_to_entry_name;
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    max_record_size_bytes: usize,
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    // Everything ends up in the final flush of Finish
+    config.recorder.flush_policy.max_buffer_size_bytes = 10 * 1024 * 1024;
+    config.recorder.flush_policy.max_buffer_duration_seconds = 3600;
+    config.recorder.flush_policy.max_record_size_bytes = max_record_size_bytes;
+
+    common::create_test_manager(session, config)
+}
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+fn record_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "mcap"))
+        .collect();
+    files.sort();
+    files
+}
+
+async fn record(max_record_size_bytes: usize, topic: &str) -> (TempDir, Vec<PathBuf>) {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), data_dir.path(), max_record_size_bytes);
+
+    let response = manager.start_recording(start_request(topic)).await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(topic).wait().unwrap();
+    for i in 0..10u8 {
+        publisher.put(vec![i; 64]).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    manager.finish_recording(&recording_id).await;
+
+    let files = record_files(&data_dir.path().join(topic_to_entry_name(topic)));
+    (data_dir, files)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_oversized_batch_split_into_records() {
+    // Room for two 64-byte samples per record
+    let (_data_dir, files) = record(150, "test/record_size/split").await;
+    assert_eq!(files.len(), 5);
+
+    let mut payloads = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        let batch = parse_batch(&std::fs::read(file).unwrap()).unwrap();
+        assert_eq!(batch.messages.len(), 2);
+        payloads.extend(batch.messages.into_iter().map(|m| m.payload[0]));
+
+        let labels: HashMap<String, String> =
+            serde_json::from_slice(&std::fs::read(file.with_extension("meta.json")).unwrap())
+                .unwrap();
+        assert_eq!(labels["part"], format!("{}/5", index + 1));
+    }
+    // Sample order survives the split
+    assert_eq!(payloads, (0..10).collect::<Vec<u8>>());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_no_split_without_limit() {
+    let (_data_dir, files) = record(0, "test/record_size/unlimited").await;
+    assert_eq!(files.len(), 1);
+
+    let batch = parse_batch(&std::fs::read(&files[0]).unwrap()).unwrap();
+    assert_eq!(batch.messages.len(), 10);
+    let labels: HashMap<String, String> =
+        serde_json::from_slice(&std::fs::read(files[0].with_extension("meta.json")).unwrap())
+            .unwrap();
+    assert!(!labels.contains_key("part"));
+}
@@ -0,0 +1,140 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Unique record timestamps for records flushed back to back
+///
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::config::{CollisionStrategy, MockConfig, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::record_timestamps::SEQUENCE_LABEL;
+use zenoh_recorder::storage::{topic_to_entry_name, MockBackend};
+use zenoh_recorder::RecorderManagerBuilder;
+
+const TOPIC: &str = "test/record_timestamps/split";
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_split_records_numbered_within_millisecond() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    // One batch at Finish, split into five records written back to back
+    config.recorder.flush_policy.max_buffer_duration_seconds = 3600;
+    config.recorder.flush_policy.max_record_size_bytes = 150;
+    config.recorder.timestamps.collision_strategy = CollisionStrategy::Sequence;
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session.clone(), config);
+
+    let recording_id = manager
+        .start_recording(start_request(TOPIC))
+        .await
+        .recording_id
+        .unwrap();
+    // Kept running for the device-wide status
+    let idle_id = manager
+        .start_recording(start_request("test/record_timestamps/idle"))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..10u8 {
+        session.put(TOPIC, vec![i; 64]).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let entry_dir = data_dir.path().join(topic_to_entry_name(TOPIC));
+    let mut records: Vec<(u64, HashMap<String, String>)> = std::fs::read_dir(&entry_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "mcap"))
+        .map(|path| {
+            let timestamp_us = path.file_stem().unwrap().to_str().unwrap().parse().unwrap();
+            let labels =
+                serde_json::from_slice(&std::fs::read(path.with_extension("meta.json")).unwrap())
+                    .unwrap();
+            (timestamp_us, labels)
+        })
+        .collect();
+    records.sort_by_key(|(timestamp_us, _)| *timestamp_us);
+
+    // None overwritten, in part order, each numbered within its millisecond
+    assert_eq!(records.len(), 5);
+    for (index, (timestamp_us, labels)) in records.iter().enumerate() {
+        assert_eq!(labels["part"], format!("{}/5", index + 1));
+        assert_eq!(labels[SEQUENCE_LABEL], (timestamp_us % 1000).to_string());
+    }
+
+    let stats = manager
+        .get_status(&idle_id)
+        .await
+        .record_timestamps
+        .unwrap();
+    assert_eq!(stats.strategy, "sequence");
+    // The five parts and the metadata record
+    assert_eq!(stats.allocated, 6);
+    manager.cancel_recording(&idle_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_metadata_of_simultaneous_recordings_kept() {
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let manager = RecorderManagerBuilder::new(RecorderConfig::default())
+        .storage_backend(backend.clone())
+        .clock(clock)
+        .build()
+        .await
+        .unwrap();
+
+    // Both start within the same microsecond of the stopped clock
+    let mut recording_ids = Vec::new();
+    for topic in [
+        "test/record_timestamps/left",
+        "test/record_timestamps/right",
+    ] {
+        let recording_id = manager
+            .start_recording(start_request(topic))
+            .await
+            .recording_id
+            .unwrap();
+        assert!(manager.finish_recording(&recording_id).await.success);
+        recording_ids.push(recording_id);
+    }
+
+    let records = backend.records("recordings_metadata");
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].0, 1_700_000_000_000_000);
+    assert_eq!(records[1].0, 1_700_000_000_000_001);
+    let stored: Vec<String> = records
+        .iter()
+        .map(|(_, record)| record.labels["recording_id"].clone())
+        .collect();
+    assert_eq!(stored, recording_ids);
+}
@@ -14,6 +14,7 @@
 
 /// Recorder state machine and session management tests
 ///
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use zenoh::Config;
@@ -45,6 +46,10 @@ fn create_test_recorder_manager(
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             },
         },
     };
@@ -72,17 +77,13 @@ async fn test_multiple_recordings() {
     // Start multiple recordings
     for i in 0..3 {
         let request = RecorderRequest {
-            command: RecorderCommand::Start,
-            recording_id: None,
             scene: Some(format!("scene_{}", i)),
-            skills: vec![],
-            organization: None,
             task_id: Some(format!("task-{}", i)),
             device_id: format!("device-{}", i),
-            data_collector_id: None,
             topics: vec![format!("test/topic{}", i)],
             compression_level: CompressionLevel::Fast,
             compression_type: CompressionType::None,
+            ..Default::default()
         };
 
         let _response = manager.start_recording(request).await;
@@ -102,17 +103,11 @@ async fn test_recording_state_transitions() {
 
     // Start
     let start_request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("test".to_string()),
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/state".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let start_response = manager.start_recording(start_request).await;
@@ -159,17 +154,10 @@ async fn test_cancel_recording() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/cancel".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -197,8 +185,6 @@ async fn test_recording_with_all_metadata() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
         scene: Some("highway_driving".to_string()),
         skills: vec!["lane_keeping".to_string(), "obstacle_avoidance".to_string()],
         organization: Some("test_org".to_string()),
@@ -207,7 +193,7 @@ async fn test_recording_with_all_metadata() {
         data_collector_id: Some("collector-001".to_string()),
         topics: vec!["/camera/front".to_string(), "/lidar/points".to_string()],
         compression_level: CompressionLevel::Slow,
-        compression_type: CompressionType::Zstd,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -237,17 +223,10 @@ async fn test_pause_resume_cycle() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/pause".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
@@ -307,17 +286,12 @@ async fn test_concurrent_recordings() {
         let manager_clone = manager.clone();
         let handle = tokio::spawn(async move {
             let request = RecorderRequest {
-                command: RecorderCommand::Start,
-                recording_id: None,
                 scene: Some(format!("concurrent_{}", i)),
-                skills: vec![],
-                organization: None,
                 task_id: Some(format!("task-{}", i)),
                 device_id: format!("device-{}", i),
-                data_collector_id: None,
                 topics: vec![format!("test/concurrent{}", i)],
-                compression_level: CompressionLevel::Default,
                 compression_type: CompressionType::None,
+                ..Default::default()
             };
 
             manager_clone.start_recording(request).await
@@ -349,10 +323,35 @@ async fn test_recording_metadata_fields() {
         end_time: Some("2024-10-17T10:15:00Z".to_string()),
         total_bytes: 1073741824,
         total_samples: 150000,
+        payload_bytes: 0,
+        compression_ratio: None,
         per_topic_stats: serde_json::json!({
             "/topic1": {"samples": 100000, "bytes": 943718400},
             "/topic2": {"samples": 50000, "bytes": 130023424}
         }),
+        interrupted: false,
+        schema_drift: vec![],
+        handoff: None,
+        previous_recording_id: None,
+        group_id: None,
+        topic_changes: vec![],
+        session_gaps: vec![],
+        records: vec![],
+        verify_failures: 0,
+        records_verified: 0,
+        bucket: None,
+        controller_liveliness: None,
+        priority: None,
+        topic_aliases: BTreeMap::new(),
+        previews: BTreeMap::new(),
+        exclude_topics: vec![],
+        buffer_strategy: None,
+        retention_seconds: None,
+        expires_at: None,
+        formats: BTreeMap::new(),
+        topology: None,
+        environment: Default::default(),
+        labels: Default::default(),
     };
 
     // Verify all fields
@@ -379,17 +378,10 @@ async fn test_manager_handles_errors_gracefully() {
     );
 
     let request = RecorderRequest {
-        command: RecorderCommand::Start,
-        recording_id: None,
-        scene: None,
-        skills: vec![],
-        organization: None,
-        task_id: None,
         device_id: "device".to_string(),
-        data_collector_id: None,
         topics: vec!["test/error".to_string()],
-        compression_level: CompressionLevel::Default,
         compression_type: CompressionType::None,
+        ..Default::default()
     };
 
     let response = manager.start_recording(request).await;
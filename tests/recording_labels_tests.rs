@@ -0,0 +1,103 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Recording tags propagated to the labels of every record
+///
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{BackendConfig, FilesystemConfig, RecorderConfig, StorageConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/recording_labels/camera";
+
+fn start_request(labels: HashMap<String, String>) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        labels,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_labels_on_every_record() {
+    let data_dir = TempDir::new().unwrap();
+    let config = RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: data_dir.path().to_string_lossy().to_string(),
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    };
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let labels = HashMap::from([
+        ("weather".to_string(), "rain".to_string()),
+        ("operator".to_string(), "alice".to_string()),
+    ]);
+    let response = manager.start_recording(start_request(labels.clone())).await;
+    assert!(response.success, "{}", response.message);
+    let recording_id = response.recording_id.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session.put(TOPIC, format!("frame-{}", i)).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let entry = topic_to_entry_name(TOPIC);
+    let records = storage
+        .find_records(&entry, "weather", "rain")
+        .await
+        .unwrap();
+    assert!(!records.is_empty());
+    let all = storage
+        .find_records(&entry, "recording_id", &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(records, all);
+
+    let tagged = storage
+        .find_records("recordings_metadata", "operator", "alice")
+        .await
+        .unwrap();
+    assert!(!tagged.is_empty());
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.labels, labels);
+
+    // Tags can't override the recorder's own labels
+    let labels = HashMap::from([("topic".to_string(), "other".to_string())]);
+    let response = manager.start_recording(start_request(labels)).await;
+    assert!(!response.success);
+}
@@ -0,0 +1,170 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Crash recovery tests using the filesystem backend
+///
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::recovery::{SessionState, SessionStateStore};
+use zenoh_recorder::storage::BackendFactory;
+
+fn create_test_manager(data_dir: &Path, state_dir: &Path, resume: bool) -> RecorderManager {
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.recovery.enabled = true;
+    config.recorder.recovery.state_dir = state_dir.to_string_lossy().to_string();
+    config.recorder.recovery.resume = resume;
+
+    common::create_test_manager(session, config)
+}
+
+fn interrupted_state(recording_id: &str) -> SessionState {
+    SessionState {
+        recording_id: recording_id.to_string(),
+        metadata: RecordingMetadata {
+            recording_id: recording_id.to_string(),
+            scene: Some("crash_test".to_string()),
+            skills: vec![],
+            organization: None,
+            task_id: None,
+            device_id: "device-01".to_string(),
+            data_collector_id: None,
+            topics: vec!["test/recovery/topic".to_string()],
+            compression_type: "Zstd".to_string(),
+            compression_level: 2,
+            start_time: "2025-01-01T00:00:00+00:00".to_string(),
+            end_time: None,
+            total_bytes: 0,
+            total_samples: 0,
+            payload_bytes: 0,
+            compression_ratio: None,
+            per_topic_stats: serde_json::json!({}),
+            interrupted: false,
+            schema_drift: vec![],
+            handoff: None,
+            previous_recording_id: None,
+            group_id: None,
+            topic_changes: vec![],
+            session_gaps: vec![],
+            records: vec![],
+            verify_failures: 0,
+            records_verified: 0,
+            bucket: None,
+            controller_liveliness: None,
+            priority: None,
+            topic_aliases: BTreeMap::new(),
+            previews: BTreeMap::new(),
+            exclude_topics: vec![],
+            buffer_strategy: None,
+            retention_seconds: None,
+            expires_at: None,
+            formats: BTreeMap::new(),
+            topology: None,
+            environment: Default::default(),
+            labels: Default::default(),
+        },
+        compression_type: CompressionType::Zstd,
+        compression_level: CompressionLevel::Default,
+        flushed_batches: 2,
+        flushed_bytes: 2048,
+        last_flush_us: Some(1_735_689_700_000_000),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recover_finalizes_interrupted_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+
+    SessionStateStore::new(state_dir.path())
+        .save(&interrupted_state("rec-crashed"))
+        .await
+        .unwrap();
+
+    let manager = create_test_manager(data_dir.path(), state_dir.path(), false);
+    let recovered = manager.recover_sessions().await.unwrap();
+    assert_eq!(recovered, vec!["rec-crashed".to_string()]);
+
+    // State file is gone once the metadata has been finalized
+    let store = SessionStateStore::new(state_dir.path());
+    assert!(store.load_all().await.unwrap().is_empty());
+
+    // Finalized metadata record carries the interrupted marker
+    let metadata_dir = data_dir.path().join("recordings_metadata");
+    let meta_file = std::fs::read_dir(&metadata_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().to_string_lossy().ends_with(".meta.json"))
+        .expect("metadata labels written");
+    let labels: HashMap<String, String> =
+        serde_json::from_slice(&std::fs::read(meta_file.path()).unwrap()).unwrap();
+    assert_eq!(labels.get("interrupted").map(String::as_str), Some("true"));
+
+    let data_file = std::fs::read_dir(&metadata_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .find(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+        .expect("metadata record written");
+    let metadata: RecordingMetadata =
+        serde_json::from_slice(&std::fs::read(data_file.path()).unwrap()).unwrap();
+    assert!(metadata.interrupted);
+    assert_eq!(metadata.total_bytes, 2048);
+    assert!(metadata.end_time.is_some());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recover_resumes_interrupted_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let state_dir = TempDir::new().unwrap();
+
+    SessionStateStore::new(state_dir.path())
+        .save(&interrupted_state("rec-resumed"))
+        .await
+        .unwrap();
+
+    let manager = create_test_manager(data_dir.path(), state_dir.path(), true);
+    let recovered = manager.recover_sessions().await.unwrap();
+    assert_eq!(recovered, vec!["rec-resumed".to_string()]);
+
+    let status = manager.get_status("rec-resumed").await;
+    assert!(status.success);
+    assert_eq!(status.status, RecordingStatus::Recording);
+    assert_eq!(status.total_recorded_bytes, 2048);
+
+    // Cancelling the resumed recording removes its state file
+    let response = manager.cancel_recording("rec-resumed").await;
+    assert!(response.success);
+    let store = SessionStateStore::new(state_dir.path());
+    assert!(store.load_all().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recover_disabled_is_noop() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let config = common::filesystem_config(data_dir.path());
+    let storage_backend = BackendFactory::create(&config.storage).unwrap();
+    let manager = RecorderManager::new(session, storage_backend, config);
+
+    assert!(manager.recover_sessions().await.unwrap().is_empty());
+}
@@ -0,0 +1,259 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the batched writes of the ReductStore backend, against a mock
+/// server
+///
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use zenoh_recorder::config::ReductStoreConfig;
+use zenoh_recorder::storage::{ReductStoreBackend, StorageBackend};
+
+/// Request received by the mock server
+#[derive(Debug, Clone)]
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// ReductStore stand-in answering `/api/v1/info` with `version` and every
+/// write after 100ms; records at a timestamp of `rejected` fail
+struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    async fn start(version: &'static str, rejected: Option<u64>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, version, rejected, log.clone()));
+            }
+        });
+        Self { url, requests }
+    }
+
+    fn writes(&self) -> Vec<Request> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == "POST")
+            .cloned()
+            .collect()
+    }
+}
+
+/// Answer the requests of a keep-alive connection
+async fn serve(
+    stream: TcpStream,
+    version: &str,
+    rejected: Option<u64>,
+    log: Arc<Mutex<Vec<Request>>>,
+) {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            if stream.read(&mut byte).await.unwrap_or(0) == 0 {
+                return;
+            }
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap().split(' ');
+        let method = request_line.next().unwrap().to_string();
+        let path = request_line.next().unwrap().to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let mut body = vec![
+            0u8;
+            headers
+                .get("content-length")
+                .map_or(0, |l| l.parse().unwrap())
+        ];
+        stream.read_exact(&mut body).await.unwrap();
+
+        let mut extra = String::new();
+        let response_body = if path == "/api/v1/info" {
+            format!("{{\"version\": \"{}\"}}", version)
+        } else {
+            // Slow enough for the next writes to queue up
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if let Some(timestamp) = rejected {
+                if headers.contains_key(&format!("x-reduct-time-{}", timestamp)) {
+                    extra = format!(
+                        "x-reduct-error-{}: 409,A record already exists\r\n",
+                        timestamp
+                    );
+                }
+            }
+            String::new()
+        };
+        log.lock().unwrap().push(Request {
+            method,
+            path,
+            headers,
+            body,
+        });
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n{}\r\n{}",
+            response_body.len(),
+            extra,
+            response_body
+        );
+        if stream.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn create_backend(url: &str, batch_max_records: usize) -> Arc<ReductStoreBackend> {
+    Arc::new(
+        ReductStoreBackend::new(ReductStoreConfig {
+            url: url.to_string(),
+            bucket_name: "bucket".to_string(),
+            max_retries: 0,
+            batch_max_records,
+            ..Default::default()
+        })
+        .unwrap(),
+    )
+}
+
+/// Write `count` records to `entry` at once; returns the results by timestamp
+async fn write_concurrently(
+    backend: &Arc<ReductStoreBackend>,
+    entry: &str,
+    count: u64,
+) -> Vec<(u64, bool)> {
+    let writes = (1..=count).map(|timestamp| {
+        let backend = backend.clone();
+        let entry = entry.to_string();
+        async move {
+            let labels = HashMap::from([
+                ("topic".to_string(), "/camera".to_string()),
+                ("skills".to_string(), "grasp,place".to_string()),
+            ]);
+            let data = format!("record-{}", timestamp).into_bytes();
+            let result = backend.write_record(&entry, timestamp, data, labels).await;
+            (timestamp, result.is_ok())
+        }
+    });
+    futures_util::future::join_all(writes).await
+}
+
+/// Records of a batch request, by timestamp: (header, body)
+fn split_batch(request: &Request) -> Vec<(u64, String, Vec<u8>)> {
+    let mut records: Vec<(u64, String)> = request
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let timestamp = name.strip_prefix("x-reduct-time-")?.parse().ok()?;
+            Some((timestamp, value.clone()))
+        })
+        .collect();
+    records.sort();
+    let mut offset = 0;
+    records
+        .into_iter()
+        .map(|(timestamp, header)| {
+            let len: usize = header.split(',').next().unwrap().parse().unwrap();
+            let body = request.body[offset..offset + len].to_vec();
+            offset += len;
+            (timestamp, header, body)
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_queued_records_share_batch_requests() {
+    let server = MockServer::start("1.9.3", None).await;
+    let backend = create_backend(&server.url, 32);
+
+    let results = write_concurrently(&backend, "camera", 10).await;
+    assert!(results.iter().all(|(_, ok)| *ok));
+
+    // The first record goes alone, the others queue up behind it
+    let writes = server.writes();
+    assert!(writes.len() < 10, "{} requests", writes.len());
+    let mut stored = Vec::new();
+    for request in &writes {
+        assert!(request.path.starts_with("/api/v1/b/bucket/camera/batch"));
+        for (timestamp, header, body) in split_batch(request) {
+            assert_eq!(
+                header,
+                format!(
+                    "{},application/mcap,skills=\"grasp,place\",topic=/camera",
+                    body.len()
+                )
+            );
+            assert_eq!(body, format!("record-{}", timestamp).into_bytes());
+            stored.push(timestamp);
+        }
+    }
+    stored.sort();
+    assert_eq!(stored, (1..=10).collect::<Vec<_>>());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rejected_record_fails_alone() {
+    let server = MockServer::start("1.9.3", Some(3)).await;
+    let backend = create_backend(&server.url, 32);
+
+    let results = write_concurrently(&backend, "imu", 5).await;
+    for (timestamp, ok) in results {
+        assert_eq!(ok, timestamp != 3, "record {}", timestamp);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_single_writes_without_batch_support() {
+    // Older server
+    let server = MockServer::start("1.6.2", None).await;
+    let backend = create_backend(&server.url, 32);
+    let results = write_concurrently(&backend, "lidar", 4).await;
+    assert!(results.iter().all(|(_, ok)| *ok));
+    let writes = server.writes();
+    assert_eq!(writes.len(), 4);
+    assert!(writes
+        .iter()
+        .all(|r| r.path.starts_with("/api/v1/b/bucket/lidar?ts=")));
+
+    // Batching turned off
+    let server = MockServer::start("1.9.3", None).await;
+    let backend = create_backend(&server.url, 1);
+    write_concurrently(&backend, "lidar", 4).await;
+    assert_eq!(server.writes().len(), 4);
+    assert!(server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| r.path != "/api/v1/info"));
+}
@@ -0,0 +1,208 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the TLS settings of the ReductStore backend, against a mock
+/// server with a certificate of a private CA
+///
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, DnType, IsCa, KeyPair};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_native_tls::native_tls;
+use zenoh_recorder::config::{ReductStoreConfig, ReductStoreTlsConfig};
+use zenoh_recorder::storage::{ReductStoreBackend, StorageBackend};
+
+/// PEM files of a private CA and of a certificate it issued for `localhost`
+struct Pki {
+    dir: TempDir,
+    /// Certificate and key of `localhost`
+    cert_pem: String,
+    key_pem: String,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "Test CA");
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+
+        // A subject of its own, or it passes for self-signed
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "localhost");
+        let cert = params.signed_by(&key, &ca).unwrap();
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("ca.pem"), ca.pem()).unwrap();
+        std::fs::write(dir.path().join("client.pem"), cert.pem()).unwrap();
+        std::fs::write(dir.path().join("client.key"), key.serialize_pem()).unwrap();
+        Self {
+            dir,
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.dir.path().join(name).to_string_lossy().to_string()
+    }
+}
+
+/// Serve `https://localhost:{port}`, answering every request with 200
+async fn start_server(pki: &Pki) -> String {
+    let identity =
+        native_tls::Identity::from_pkcs8(pki.cert_pem.as_bytes(), pki.key_pem.as_bytes()).unwrap();
+    let acceptor = tokio_native_tls::TlsAcceptor::from(
+        native_tls::TlsAcceptor::builder(identity).build().unwrap(),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "https://localhost:{}",
+        listener.local_addr().unwrap().port()
+    );
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                // Clients rejecting the certificate end the handshake
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let body = "{\"version\": \"1.9.3\"}";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    url
+}
+
+fn create_backend(
+    url: &str,
+    tls: ReductStoreTlsConfig,
+) -> zenoh_recorder::error::Result<ReductStoreBackend> {
+    ReductStoreBackend::new(ReductStoreConfig {
+        url: url.to_string(),
+        max_retries: 0,
+        tls,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_private_ca_is_trusted_with_ca_file() {
+    let pki = Pki::new();
+    let url = start_server(&pki).await;
+
+    // Only the system roots: the private CA is unknown
+    let backend = create_backend(&url, ReductStoreTlsConfig::default()).unwrap();
+    assert!(!backend.health_check().await.unwrap());
+
+    let backend = create_backend(
+        &url,
+        ReductStoreTlsConfig {
+            ca_file: Some(pki.path("ca.pem")),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(backend.health_check().await.unwrap());
+
+    let backend = create_backend(
+        &url,
+        ReductStoreTlsConfig {
+            insecure_skip_verify: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(backend.health_check().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_connects_with_client_certificate() {
+    let pki = Pki::new();
+    let url = start_server(&pki).await;
+
+    let backend = create_backend(
+        &url,
+        ReductStoreTlsConfig {
+            ca_file: Some(pki.path("ca.pem")),
+            client_cert_file: Some(pki.path("client.pem")),
+            client_key_file: Some(pki.path("client.key")),
+            insecure_skip_verify: false,
+        },
+    )
+    .unwrap();
+    assert!(backend.health_check().await.unwrap());
+}
+
+#[test]
+fn test_invalid_tls_files_are_rejected() {
+    let pki = Pki::new();
+    let err = create_backend(
+        "https://localhost:8383",
+        ReductStoreTlsConfig {
+            ca_file: Some(pki.path("missing.pem")),
+            ..Default::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert!(format!("{:#}", err).contains("missing.pem"));
+
+    // A key isn't a CA bundle
+    let err = create_backend(
+        "https://localhost:8383",
+        ReductStoreTlsConfig {
+            ca_file: Some(pki.path("client.key")),
+            ..Default::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert!(format!("{:#}", err).contains("client.key"));
+
+    // Certificate and key swapped
+    let err = create_backend(
+        "https://localhost:8383",
+        ReductStoreTlsConfig {
+            client_cert_file: Some(pki.path("client.key")),
+            client_key_file: Some(pki.path("client.pem")),
+            ..Default::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert!(format!("{:#}", err).contains("Invalid client certificate"));
+}
@@ -0,0 +1,139 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Record index and power-loss repair tests using the filesystem backend
+///
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::BackendConfig;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::record_index::{self, INDEX_FILE};
+use zenoh_recorder::storage::repair::repair;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/repair/camera";
+
+/// Record files of an entry, oldest first
+fn record_files(entry_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(entry_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "mcap"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_repair_after_power_loss() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![TOPIC.to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session.put(TOPIC, format!("frame-{}", i)).wait().unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    // The recorder dies here: no Finish, no metadata record
+    drop(manager);
+
+    let entry_dir = data_dir.path().join(topic_to_entry_name(TOPIC));
+    let files = record_files(&entry_dir);
+    assert_eq!(files.len(), 3);
+    assert_eq!(record_index::read(&entry_dir).unwrap().entries.len(), 3);
+
+    // The last record was cut short, its index frame torn, and the labels of
+    // the first one never made it to disk
+    let last = std::fs::read(&files[2]).unwrap();
+    std::fs::write(&files[2], &last[..last.len() / 2]).unwrap();
+    let index_path = entry_dir.join(INDEX_FILE);
+    let index = std::fs::read(&index_path).unwrap();
+    std::fs::write(&index_path, &index[..index.len() - 5]).unwrap();
+    std::fs::remove_file(files[0].with_extension("meta.json")).unwrap();
+    // A complete record whose index frame was lost
+    let copy = entry_dir.join("1.mcap");
+    std::fs::copy(&files[1], &copy).unwrap();
+
+    let report = repair(data_dir.path(), "mcap").await.unwrap();
+    assert_eq!(report.entries, 1);
+    assert_eq!(report.torn_records, 1);
+    assert_eq!(report.valid_records, 3);
+    assert_eq!(report.reindexed, 1);
+    assert_eq!(report.restored_labels, 2);
+    assert!(report.torn_index_bytes > 0);
+    assert_eq!(report.rebuilt_recordings, vec![recording_id.clone()]);
+
+    assert!(!files[2].exists());
+    assert!(files[2].with_extension("mcap.torn").exists());
+    let labels: std::collections::HashMap<String, String> =
+        serde_json::from_slice(&std::fs::read(files[0].with_extension("meta.json")).unwrap())
+            .unwrap();
+    assert_eq!(labels["recording_id"], recording_id);
+    assert_eq!(labels["topic"], TOPIC);
+
+    // The rebuilt metadata describes the intact records
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert!(metadata.interrupted);
+    assert_eq!(metadata.topics, vec![TOPIC.to_string()]);
+    assert_eq!(metadata.records.len(), 3);
+    assert_eq!(metadata.total_samples, 3);
+    assert_eq!(metadata.compression_type, "Zstd");
+
+    // A second pass finds nothing left to fix
+    let report = repair(data_dir.path(), "mcap").await.unwrap();
+    assert_eq!(report.torn_records, 0);
+    assert_eq!(report.reindexed, 0);
+    assert_eq!(report.restored_labels, 0);
+    assert_eq!(report.torn_index_bytes, 0);
+    assert!(report.rebuilt_recordings.is_empty());
+}
+
+#[tokio::test]
+async fn test_index_can_be_disabled() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::per_sample_config(data_dir.path());
+    if let BackendConfig::Filesystem { filesystem } = &mut config.storage.backend_config {
+        filesystem.record_index = false;
+    }
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage
+        .write_record("camera", 1000, b"data".to_vec(), Default::default())
+        .await
+        .unwrap();
+
+    assert!(data_dir.path().join("camera/1000.mcap").exists());
+    assert!(!data_dir.path().join("camera").join(INDEX_FILE).exists());
+}
@@ -0,0 +1,353 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Replay tests using the filesystem backend
+///
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::sample::SampleKind;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::batch_index::{index_entry, BatchIndex};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay::{self, ReplayOptions};
+use zenoh_recorder::storage::BackendFactory;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_replay_republishes_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec!["test/replay/**".to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    for i in 0..3 {
+        session
+            .put(format!("test/replay/robot/{}", i), format!("frame-{}", i))
+            .attachment(format!("seq-{}", i))
+            .wait()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    session.delete("test/replay/robot/gone").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    manager.finish_recording(&recording_id).await;
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.recording_id, recording_id);
+
+    // Collect what the replay publishes under the remapped prefix
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let _subscriber = session
+        .declare_subscriber("test/replayed/**")
+        .callback(move |sample| {
+            sink.lock().unwrap().push((
+                sample.key_expr().to_string(),
+                sample.kind(),
+                sample.payload().to_bytes().to_vec(),
+                sample.attachment().map(|a| a.to_bytes().to_vec()),
+            ));
+        })
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let options = ReplayOptions {
+        rate: 10.0,
+        remap: vec![("test/replay".to_string(), "test/replayed".to_string())],
+        ..Default::default()
+    };
+    let summary = replay::replay(&session, storage.as_ref(), &metadata, &options)
+        .await
+        .unwrap();
+    assert_eq!(summary.messages, 4);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 4);
+    for (i, (key, kind, payload, attachment)) in received.iter().take(3).enumerate() {
+        assert_eq!(key, &format!("test/replayed/robot/{}", i));
+        assert_eq!(*kind, SampleKind::Put);
+        assert_eq!(payload, format!("frame-{}", i).as_bytes());
+        assert_eq!(attachment.as_deref(), Some(format!("seq-{}", i).as_bytes()));
+    }
+    assert_eq!(received[3].0, "test/replayed/robot/gone");
+    assert_eq!(received[3].1, SampleKind::Delete);
+    assert_eq!(received[3].3, None);
+
+    // The topic filter leaves everything else out
+    let options = ReplayOptions {
+        topics: vec!["other/**".to_string()],
+        ..Default::default()
+    };
+    let summary = replay::replay(&session, storage.as_ref(), &metadata, &options)
+        .await
+        .unwrap();
+    assert_eq!(summary.messages, 0);
+}
+
+#[tokio::test]
+async fn test_unknown_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+
+    let err = replay::find_recording(storage.as_ref(), "missing")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not found"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_rate() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = zenoh::open(Config::default()).await.unwrap();
+
+    let metadata: RecordingMetadata = serde_json::from_str(
+        r#"{
+        "recording_id": "rec-1", "scene": null, "skills": [], "organization": null,
+        "task_id": null, "device_id": "d", "data_collector_id": null, "topics": [],
+        "compression_type": "None", "compression_level": 2,
+        "start_time": "2025-01-01T00:00:00+00:00", "end_time": null,
+        "total_bytes": 0, "total_samples": 0, "per_topic_stats": {}
+    }"#,
+    )
+    .unwrap();
+    let options = ReplayOptions {
+        rate: 0.0,
+        ..Default::default()
+    };
+    assert!(
+        replay::replay(&session, storage.as_ref(), &metadata, &options)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_replay_merges_topics_under_new_prefix() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let topics = ["test/merge/camera", "test/merge/lidar"];
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: topics.iter().map(|t| t.to_string()).collect(),
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Interleave the topics; each sample is a record of its own
+    let mut published = Vec::new();
+    for i in 0..3 {
+        for topic in topics {
+            session.put(topic, format!("{}", i)).wait().unwrap();
+            published.push(format!("sim/{}", topic));
+            tokio::time::sleep(Duration::from_millis(30)).await;
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    manager.finish_recording(&recording_id).await;
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let _subscriber = session
+        .declare_subscriber("sim/**")
+        .callback(move |sample| sink.lock().unwrap().push(sample.key_expr().to_string()))
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let options = ReplayOptions {
+        rate: 10.0,
+        remap: vec![replay::parse_remap("**=sim/**").unwrap()],
+        ..Default::default()
+    };
+    let summary = replay::replay(&session, storage.as_ref(), &metadata, &options)
+        .await
+        .unwrap();
+    assert_eq!(summary.records, 6);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Samples of both topics go out in their recorded interleaving
+    assert_eq!(*received.lock().unwrap(), published);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_replay_declares_recorded_liveliness_tokens() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::per_sample_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    // Alive before the recording starts, so recorded from the history
+    let planner = session
+        .liveliness()
+        .declare_token("test/live/nodes/planner")
+        .wait()
+        .unwrap();
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec!["@liveliness/test/live/nodes/**".to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let lidar = session
+        .liveliness()
+        .declare_token("test/live/nodes/lidar")
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(planner);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+    drop(lidar);
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let _subscriber = session
+        .liveliness()
+        .declare_subscriber("test/relive/**")
+        .callback(move |sample| {
+            sink.lock()
+                .unwrap()
+                .push((sample.key_expr().to_string(), sample.kind()));
+        })
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let options = ReplayOptions {
+        rate: 10.0,
+        remap: vec![("test/live".to_string(), "test/relive".to_string())],
+        ..Default::default()
+    };
+    let summary = replay::replay(&session, storage.as_ref(), &metadata, &options)
+        .await
+        .unwrap();
+    assert_eq!(summary.messages, 3);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // The lidar token still alive at the end goes with the replay
+    let received = received.lock().unwrap().clone();
+    assert_eq!(
+        received,
+        vec![
+            ("test/relive/nodes/planner".to_string(), SampleKind::Put),
+            ("test/relive/nodes/lidar".to_string(), SampleKind::Put),
+            ("test/relive/nodes/planner".to_string(), SampleKind::Delete),
+            ("test/relive/nodes/lidar".to_string(), SampleKind::Delete),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_replay_time_range_skips_indexed_batches() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::per_sample_config(data_dir.path());
+    config.recorder.index.enabled = true;
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec!["test/indexed/robot".to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for i in 0..3 {
+        session
+            .put("test/indexed/robot", format!("frame-{}", i))
+            .wait()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    manager.finish_recording(&recording_id).await;
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.records.len(), 3);
+
+    // Every record has an index record at its timestamp
+    let mut indexes = Vec::new();
+    for record in &metadata.records {
+        let data = storage
+            .read_record(&index_entry(&record.entry), record.timestamp_us)
+            .await
+            .unwrap();
+        let index: BatchIndex = serde_json::from_slice(&data).unwrap();
+        assert_eq!(index.samples, 1);
+        indexes.push(index);
+    }
+    indexes.sort_by_key(|index| index.first_ns);
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let _subscriber = session
+        .declare_subscriber("test/indexed/robot")
+        .callback(move |sample| {
+            sink.lock()
+                .unwrap()
+                .push(sample.payload().to_bytes().to_vec())
+        })
+        .wait()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Only the middle record is read
+    let options = ReplayOptions {
+        time_range: Some((indexes[1].first_ns, indexes[1].last_ns)),
+        ..Default::default()
+    };
+    let summary = replay::replay(&session, storage.as_ref(), &metadata, &options)
+        .await
+        .unwrap();
+    assert_eq!(summary.records, 1);
+    assert_eq!(summary.unread_records, 2);
+    assert_eq!(summary.messages, 1);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(*received.lock().unwrap(), vec![b"frame-1".to_vec()]);
+}
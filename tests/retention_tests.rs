@@ -0,0 +1,108 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Disk retention tests using the filesystem backend
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{
+    BackendConfig, FilesystemConfig, RecorderConfig, RetentionConfig, StorageConfig,
+};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::RecorderError;
+
+fn create_test_config(data_dir: &Path, retention: RetentionConfig) -> RecorderConfig {
+    RecorderConfig {
+        storage: StorageConfig {
+            backend: "filesystem".to_string(),
+            backend_config: BackendConfig::Filesystem {
+                filesystem: FilesystemConfig {
+                    base_path: data_dir.to_string_lossy().to_string(),
+                    file_format: "mcap".to_string(),
+                    retention,
+                    ..Default::default()
+                },
+            },
+        },
+        ..Default::default()
+    }
+}
+
+fn start_request(topic: &str) -> RecorderRequest {
+    RecorderRequest {
+        compression_type: CompressionType::None,
+        ..common::start_request(&[topic])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_start_refused_when_disk_is_full() {
+    let data_dir = TempDir::new().unwrap();
+    let config = create_test_config(
+        data_dir.path(),
+        RetentionConfig {
+            min_free_bytes: u64::MAX,
+            ..Default::default()
+        },
+    );
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    assert!(matches!(
+        storage.check_capacity().await,
+        Err(RecorderError::InsufficientSpace(_))
+    ));
+
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session, storage, config);
+    let response = manager
+        .start_recording(start_request("test/retention/full"))
+        .await;
+    assert!(!response.success);
+    assert!(response.recording_id.is_none());
+    let message = response.message;
+    assert!(message.contains("insufficient disk space"), "{}", message);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_background_pass_deletes_expired_records() {
+    let data_dir = TempDir::new().unwrap();
+    let entry_dir = data_dir.path().join("camera");
+    std::fs::create_dir_all(&entry_dir).unwrap();
+    // Records from 1970 are well past any age limit
+    std::fs::write(entry_dir.join("1000.mcap"), b"old").unwrap();
+    std::fs::write(entry_dir.join("1000.meta.json"), b"{}").unwrap();
+
+    let config = create_test_config(
+        data_dir.path(),
+        RetentionConfig {
+            max_age_hours: 1,
+            ..Default::default()
+        },
+    );
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    storage.initialize().await.unwrap();
+    // Nothing to refuse without a free space limit
+    storage.check_capacity().await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!entry_dir.join("1000.mcap").exists());
+    assert!(!entry_dir.join("1000.meta.json").exists());
+}
@@ -0,0 +1,201 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for chaining recordings with the rollover command
+///
+mod common;
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::QuotaLimits;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+const TOPIC: &str = "test/rollover/counter";
+
+fn create_test_manager(data_dir: &TempDir) -> (Arc<Session>, RecorderManager) {
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.flush_policy.max_buffer_duration_seconds = 1;
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session.clone(), config);
+    (session, manager)
+}
+
+fn start_request() -> RecorderRequest {
+    RecorderRequest {
+        scene: Some("shift".to_string()),
+        compression_type: CompressionType::None,
+        labels: HashMap::from([("line".to_string(), "3".to_string())]),
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+/// Counter values stored for a recording
+fn stored_counters(data_dir: &Path, recording_id: &str) -> BTreeSet<u32> {
+    let entry = data_dir.join(topic_to_entry_name(TOPIC));
+    let mut counters = BTreeSet::new();
+    for file in std::fs::read_dir(&entry).unwrap().filter_map(|e| e.ok()) {
+        let name = file.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = name.strip_suffix(".meta.json") else {
+            continue;
+        };
+        let labels: HashMap<String, String> =
+            serde_json::from_slice(&std::fs::read(file.path()).unwrap()).unwrap();
+        if labels["recording_id"] != recording_id {
+            continue;
+        }
+        let data = std::fs::read(entry.join(format!("{}.mcap", timestamp))).unwrap();
+        for message in parse_batch(&data).unwrap().messages {
+            counters.insert(String::from_utf8(message.payload).unwrap().parse().unwrap());
+        }
+    }
+    counters
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rollover_continues_without_gap() {
+    let data_dir = TempDir::new().unwrap();
+    let (session, manager) = create_test_manager(&data_dir);
+
+    let first = manager
+        .start_recording(start_request())
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Publish a counter until stopped
+    let running = Arc::new(AtomicBool::new(true));
+    let publisher = tokio::spawn({
+        let (session, running) = (session.clone(), running.clone());
+        async move {
+            let mut counter = 0u32;
+            while running.load(Ordering::SeqCst) {
+                session.put(TOPIC, counter.to_string()).wait().unwrap();
+                counter += 1;
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            counter
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let response = manager.rollover(&first).await;
+    assert!(response.success, "{}", response.message);
+    let second = response.recording_id.unwrap();
+    assert_ne!(second, first);
+    assert_eq!(response.status, Some(RecordingStatus::Recording));
+    assert!(manager.complete_finish(&first).await.success);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    running.store(false, Ordering::SeqCst);
+    let published = publisher.await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&second).await.success);
+
+    // Every counter is in one of the recordings, and each got some
+    let before = stored_counters(data_dir.path(), &first);
+    let after = stored_counters(data_dir.path(), &second);
+    assert!(!before.is_empty() && !after.is_empty());
+    let all: BTreeSet<u32> = before.union(&after).copied().collect();
+    assert_eq!(all, (0..published).collect());
+    assert!(before.last() < after.last());
+
+    // The next recording has the same settings and links back
+    let metadata = common::find_metadata(data_dir.path(), &second);
+    assert_eq!(
+        metadata.previous_recording_id.as_deref(),
+        Some(first.as_str())
+    );
+    assert_eq!(metadata.scene.as_deref(), Some("shift"));
+    assert_eq!(metadata.topics, vec![TOPIC.to_string()]);
+    assert_eq!(metadata.labels["line"], "3");
+    assert!(common::find_metadata(data_dir.path(), &first)
+        .previous_recording_id
+        .is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rollover_needs_active_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let (_session, manager) = create_test_manager(&data_dir);
+
+    let response = manager.rollover("missing").await;
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
+
+    let recording_id = manager
+        .start_recording(start_request())
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.pause_recording(&recording_id).await.success);
+    let response = manager.rollover(&recording_id).await;
+    assert_eq!(response.error_code, Some(ErrorCode::InvalidState));
+
+    // Nothing was started or finished
+    assert_eq!(manager.recording_statuses().await.len(), 1);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.status, RecordingStatus::Paused);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rollover_keeps_quota_slot_and_muted_topics() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.quotas.organizations.insert(
+        "acme".to_string(),
+        QuotaLimits {
+            max_concurrent_recordings: 1,
+            max_bytes_per_day: 0,
+        },
+    );
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, config);
+
+    let muted = "test/rollover/muted".to_string();
+    let first = manager
+        .start_recording(RecorderRequest {
+            organization: Some("acme".to_string()),
+            topics: vec![TOPIC.to_string(), muted.clone()],
+            ..start_request()
+        })
+        .await
+        .recording_id
+        .unwrap();
+    assert!(
+        manager
+            .pause_topics(&first, std::slice::from_ref(&muted))
+            .await
+            .success
+    );
+
+    // The recording it replaces doesn't count against the limit
+    let response = manager.rollover(&first).await;
+    assert!(response.success, "{}", response.message);
+    let second = response.recording_id.unwrap();
+    assert!(manager.complete_finish(&first).await.success);
+
+    let status = manager.get_status(&second).await;
+    assert!(status.topic_paused[&muted]);
+    assert!(!status.topic_paused[TOPIC]);
+    assert!(manager.resume_topics(&second, &[muted]).await.success);
+    assert!(manager.finish_recording(&second).await.success);
+}
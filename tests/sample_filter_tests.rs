@@ -0,0 +1,90 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for filter expressions on sample payloads
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig, SampleTransformConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+
+const ODOM: &str = "test/sample_filter/odom";
+const STATUS: &str = "test/sample_filter/status";
+
+fn recorded_payloads(backend: &MockBackend, entry: &str) -> Vec<String> {
+    backend
+        .records(entry)
+        .iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| String::from_utf8(message.payload).unwrap())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_only_matching_samples_recorded() {
+    let mut config = RecorderConfig::default();
+    config.recorder.transforms.per_topic.insert(
+        ODOM.to_string(),
+        vec![SampleTransformConfig::Filter {
+            expression: "payload.speed > 2.0 && payload.mode != 'manual'".to_string(),
+        }],
+    );
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("moving".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&[ODOM, STATUS])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let odometry = [
+        r#"{"speed": 0.5, "mode": "auto"}"#,
+        r#"{"speed": 2.5, "mode": "auto"}"#,
+        r#"{"speed": 3.0, "mode": "manual"}"#,
+        r#"{"speed": 4.0, "mode": "auto"}"#,
+        "not json",
+    ];
+    for payload in odometry {
+        session.put(ODOM, payload).wait().unwrap();
+    }
+    session.put(STATUS, "idle").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording("moving").await.success);
+    manager
+        .wait_for_completion("moving", Duration::from_secs(5))
+        .await;
+
+    assert_eq!(
+        recorded_payloads(&backend, "test_sample_filter_odom"),
+        vec![odometry[1], odometry[3]]
+    );
+    // Topics without a filter are recorded as they are
+    assert_eq!(
+        recorded_payloads(&backend, "test_sample_filter_status"),
+        vec!["idle"]
+    );
+}
@@ -0,0 +1,98 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for sample transforms added through the manager builder
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManagerBuilder;
+use zenoh_recorder::sample_transform::SampleTransform;
+use zenoh_recorder::storage::MockBackend;
+
+const CAMERA: &str = "test/sample_transform/camera";
+const ODOM: &str = "test/sample_transform/odom";
+
+/// Upper-cases payloads and drops empty ones
+#[derive(Debug)]
+struct Shout;
+
+impl SampleTransform for Shout {
+    fn name(&self) -> &'static str {
+        "shout"
+    }
+
+    fn apply(&self, _topic: &str, payload: &mut Vec<u8>) -> bool {
+        payload.make_ascii_uppercase();
+        !payload.is_empty()
+    }
+}
+
+fn recorded_payloads(backend: &MockBackend, entry: &str) -> Vec<String> {
+    backend
+        .records(entry)
+        .iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| String::from_utf8(message.payload).unwrap())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_builder_transform_applied_to_recorded_data() {
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManagerBuilder::new(RecorderConfig::default())
+        .session(session.clone())
+        .storage_backend(backend.clone())
+        .sample_transform(CAMERA, Arc::new(Shout))
+        .build()
+        .await
+        .unwrap();
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("shouted".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&[CAMERA, ODOM])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for payload in ["frame-1", "", "frame-2"] {
+        session.put(CAMERA, payload).wait().unwrap();
+    }
+    session.put(ODOM, "moving").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording("shouted").await.success);
+    manager
+        .wait_for_completion("shouted", Duration::from_secs(5))
+        .await;
+
+    assert_eq!(
+        recorded_payloads(&backend, "test_sample_transform_camera"),
+        vec!["FRAME-1", "FRAME-2"]
+    );
+    // Other topics are recorded as they are
+    assert_eq!(
+        recorded_payloads(&backend, "test_sample_transform_odom"),
+        vec!["moving"]
+    );
+}
@@ -0,0 +1,136 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Zenoh session supervision: connection tracking and session gaps
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::session_supervisor::{ConnectionTracker, Transition};
+
+const TOPIC: &str = "test/session_supervisor/imu";
+
+fn create_manager(data_dir: &Path, session: Arc<zenoh::Session>) -> RecorderManager {
+    let config = common::filesystem_config(data_dir);
+    common::create_test_manager(session, config)
+}
+
+#[test]
+fn test_tracker_ignores_sessions_that_never_connected() {
+    let mut tracker = ConnectionTracker::new(Some(Duration::from_secs(1)));
+    let now = Instant::now();
+    assert_eq!(tracker.observe(false, false, now), None);
+    assert_eq!(
+        tracker.observe(false, false, now + Duration::from_secs(5)),
+        None
+    );
+}
+
+#[test]
+fn test_tracker_reports_loss_and_restoration() {
+    let mut tracker = ConnectionTracker::new(None);
+    let now = Instant::now();
+    assert_eq!(tracker.observe(true, false, now), None);
+    assert_eq!(tracker.observe(false, false, now), Some(Transition::Lost));
+    // Without reopen_after, a disconnected session is waited for
+    assert_eq!(
+        tracker.observe(false, false, now + Duration::from_secs(3600)),
+        None
+    );
+    assert_eq!(
+        tracker.observe(true, false, now),
+        Some(Transition::Restored { reopened: false })
+    );
+    assert_eq!(tracker.observe(true, false, now), None);
+}
+
+#[test]
+fn test_tracker_reopens_after_timeout() {
+    let mut tracker = ConnectionTracker::new(Some(Duration::from_secs(30)));
+    let now = Instant::now();
+    tracker.observe(true, false, now);
+    assert_eq!(tracker.observe(false, false, now), Some(Transition::Lost));
+    assert_eq!(
+        tracker.observe(false, false, now + Duration::from_secs(10)),
+        None
+    );
+    assert_eq!(
+        tracker.observe(false, false, now + Duration::from_secs(30)),
+        Some(Transition::Reopen)
+    );
+
+    // The replacement gets another timeout to connect
+    let reopened_at = now + Duration::from_secs(30);
+    tracker.reopened(reopened_at);
+    assert_eq!(
+        tracker.observe(false, false, reopened_at + Duration::from_secs(10)),
+        None
+    );
+    assert_eq!(
+        tracker.observe(true, false, reopened_at + Duration::from_secs(11)),
+        Some(Transition::Restored { reopened: true })
+    );
+}
+
+#[test]
+fn test_tracker_reopens_closed_sessions() {
+    let mut tracker = ConnectionTracker::new(None);
+    let now = Instant::now();
+    assert_eq!(tracker.observe(false, true, now), Some(Transition::Lost));
+    assert_eq!(tracker.observe(false, true, now), Some(Transition::Reopen));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_session_gaps_are_stored_in_metadata() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_manager(data_dir.path(), session.clone());
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[TOPIC]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(manager.begin_session_gap().await, 1);
+    // A gap is only opened once
+    assert_eq!(manager.begin_session_gap().await, 0);
+
+    // Samples keep arriving on a replacement session
+    let replacement = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    assert_eq!(manager.redeclare(replacement.clone()).await, 1);
+    session.close().await.unwrap();
+    assert_eq!(manager.end_session_gap(true).await, 1);
+    assert_eq!(manager.end_session_gap(true).await, 0);
+
+    replacement.put(TOPIC, "after").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.per_topic[0].samples_received, 1);
+
+    manager.finish_recording(&recording_id).await;
+    let metadata = common::read_metadata(data_dir.path());
+    assert_eq!(metadata.session_gaps.len(), 1);
+    let gap = &metadata.session_gaps[0];
+    assert!(gap.ended_at.is_some());
+    assert!(gap.reopened);
+    assert!(gap.started_at <= *gap.ended_at.as_ref().unwrap());
+}
@@ -0,0 +1,84 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "shared-memory")]
+
+/// Tests for the ingestion of shared-memory payloads
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::shm::{PosixShmProviderBackend, ShmProviderBuilder};
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+
+const CAMERA: &str = "test/shm_ingestion/camera";
+const LOGS: &str = "test/shm_ingestion/logs";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shm_samples_counted_per_topic() {
+    let mut zenoh_config = Config::default();
+    zenoh_config
+        .insert_json5("transport/shared_memory/enabled", "true")
+        .unwrap();
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(zenoh_config).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), RecorderConfig::default());
+
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("zero-copy".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&[CAMERA, LOGS])
+            })
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let provider =
+        ShmProviderBuilder::backend(PosixShmProviderBackend::builder(4096).wait().unwrap()).wait();
+    let layout = provider.alloc_layout(256).unwrap();
+    for i in 0..3 {
+        let mut buffer = layout.alloc().wait().unwrap();
+        buffer.fill(i);
+        session.put(CAMERA, buffer).wait().unwrap();
+    }
+    session.put(LOGS, "not shared").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let status = manager.get_status("zero-copy").await;
+    let camera = status.per_topic.iter().find(|t| t.topic == CAMERA).unwrap();
+    assert_eq!(camera.shm_samples, 3);
+    let logs = status.per_topic.iter().find(|t| t.topic == LOGS).unwrap();
+    assert_eq!(logs.shm_samples, 0);
+
+    // Shared-memory payloads are recorded like any other
+    assert!(manager.finish_recording("zero-copy").await.success);
+    manager
+        .wait_for_completion("zero-copy", Duration::from_secs(5))
+        .await;
+    let payloads: Vec<Vec<u8>> = backend
+        .records("test_shm_ingestion_camera")
+        .iter()
+        .flat_map(|(_, record)| parse_batch(&record.data).unwrap().messages)
+        .map(|message| message.payload)
+        .collect();
+    assert_eq!(payloads, (0..3).map(|i| vec![i; 256]).collect::<Vec<_>>());
+}
@@ -0,0 +1,237 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Shutdown deadline: recordings spilled to the work directory and completed
+/// on the next start
+///
+mod common;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use zenoh_recorder::config::RecorderConfig;
+use zenoh_recorder::data_bridge::{self, DataQuery};
+use zenoh_recorder::error::Result;
+use zenoh_recorder::proto::RecordedMessage;
+use zenoh_recorder::storage::{BackendFactory, StorageBackend};
+use zenoh_recorder::RecorderManagerBuilder;
+
+const TOPIC: &str = "test/shutdown_spill/imu";
+
+/// Backend whose uplink hangs for everything but the metadata
+struct StalledBackend {
+    inner: Arc<dyn StorageBackend>,
+}
+
+#[async_trait]
+impl StorageBackend for StalledBackend {
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn write_record(
+        &self,
+        entry_name: &str,
+        timestamp_us: u64,
+        data: Vec<u8>,
+        labels: HashMap<String, String>,
+    ) -> Result<()> {
+        if entry_name != "recordings_metadata" {
+            std::future::pending::<()>().await;
+        }
+        self.inner
+            .write_record(entry_name, timestamp_us, data, labels)
+            .await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn backend_type(&self) -> &str {
+        "stalled"
+    }
+}
+
+fn create_test_config(data_dir: &Path, work_dir: &Path) -> RecorderConfig {
+    let mut config = common::filesystem_config(data_dir);
+    // Flush every sample as its own record
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.work_dir.enabled = true;
+    config.recorder.work_dir.path = work_dir.to_string_lossy().to_string();
+    config.recorder.workers.shutdown_timeout_ms = 200;
+    config
+}
+
+/// Number of records kept in `dir` and below
+fn count_records(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.unwrap().path())
+        .map(|path| match path.is_dir() {
+            true => count_records(&path),
+            false => usize::from(path.extension().is_some_and(|ext| ext == "mcap")),
+        })
+        .sum()
+}
+
+async fn read_messages(config: &RecorderConfig, recording_id: &str) -> Vec<RecordedMessage> {
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let query = DataQuery {
+        recording_id: recording_id.to_string(),
+        topic: TOPIC.to_string(),
+        from_ns: i64::MIN,
+        to_ns: i64::MAX,
+    };
+    let messages = Arc::new(Mutex::new(Vec::<RecordedMessage>::new()));
+    data_bridge::read_pages(storage.as_ref(), &query, 1 << 20, |_, _, page| {
+        let messages = messages.clone();
+        async move {
+            messages
+                .lock()
+                .unwrap()
+                .extend(data_bridge::decode_page(&page)?);
+            Ok(())
+        }
+    })
+    .await
+    .unwrap();
+
+    let mut messages = messages.lock().unwrap().clone();
+    messages.sort_by_key(|m| m.timestamp_ns);
+    messages
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shutdown_deadline_spills_recordings() {
+    let data_dir = TempDir::new().unwrap();
+    let work_dir = TempDir::new().unwrap();
+    let config = create_test_config(data_dir.path(), work_dir.path());
+    let backend = Arc::new(StalledBackend {
+        inner: BackendFactory::create(&config.storage).unwrap(),
+    });
+    let manager = RecorderManagerBuilder::new(config.clone())
+        .storage_backend(backend)
+        .build()
+        .await
+        .unwrap();
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[TOPIC]))
+        .await
+        .recording_id
+        .unwrap();
+    for i in 0..3u64 {
+        manager
+            .ingest_sample(
+                TOPIC,
+                format!("sample-{}", i),
+                1_700_000_000_000_000_000 + i,
+            )
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The first upload hangs, the others wait behind it
+    let started = Instant::now();
+    manager
+        .shutdown(Some(Duration::from_millis(200)))
+        .await
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let recording_dir = work_dir.path().join(&recording_id);
+    assert!(recording_dir.join("shutdown_manifest.json").is_file());
+    assert_eq!(count_records(&recording_dir.join("deferred")), 3);
+    assert!(common::read_all_metadata(data_dir.path()).is_empty());
+
+    // The next start uploads the spilled records and writes the metadata
+    let manager = RecorderManagerBuilder::new(config.clone())
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(
+        manager.recover_sessions().await.unwrap(),
+        vec![recording_id.clone()]
+    );
+    assert!(!recording_dir.exists());
+
+    let metadata = common::read_all_metadata(data_dir.path());
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata[0].recording_id, recording_id);
+    assert!(metadata[0].end_time.is_some());
+    assert!(!metadata[0].interrupted);
+    assert_eq!(metadata[0].records.len(), 3);
+    assert_eq!(
+        metadata[0].total_bytes,
+        metadata[0]
+            .records
+            .iter()
+            .map(|r| r.bytes as i64)
+            .sum::<i64>()
+    );
+
+    let messages = read_messages(&config, &recording_id).await;
+    assert_eq!(messages.len(), 3);
+    for (i, message) in messages.iter().enumerate() {
+        assert_eq!(message.payload, format!("sample-{}", i).into_bytes());
+    }
+
+    // Completed only once
+    assert!(manager.recover_sessions().await.unwrap().is_empty());
+    manager.shutdown(None).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shutdown_within_deadline_finishes_recordings() {
+    let data_dir = TempDir::new().unwrap();
+    let work_dir = TempDir::new().unwrap();
+    let config = create_test_config(data_dir.path(), work_dir.path());
+    let manager = RecorderManagerBuilder::new(config.clone())
+        .build()
+        .await
+        .unwrap();
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[TOPIC]))
+        .await
+        .recording_id
+        .unwrap();
+    for i in 0..2u64 {
+        manager
+            .ingest_sample(
+                TOPIC,
+                format!("sample-{}", i),
+                1_700_000_000_000_000_000 + i,
+            )
+            .await
+            .unwrap();
+    }
+
+    manager
+        .shutdown(Some(Duration::from_secs(10)))
+        .await
+        .unwrap();
+
+    assert!(!work_dir.path().join(&recording_id).exists());
+    let metadata = common::read_all_metadata(data_dir.path());
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata[0].records.len(), 2);
+    assert_eq!(read_messages(&config, &recording_id).await.len(), 2);
+}
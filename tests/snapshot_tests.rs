@@ -0,0 +1,175 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the snapshot command
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::RecorderConfig;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::snapshot::{SNAPSHOT_ENTRY, SNAPSHOT_METADATA_ENTRY};
+use zenoh_recorder::storage::{BackendFactory, StorageBackend};
+
+const CAMERA: &str = "test/snapshot/camera";
+const IMU: &str = "test/snapshot/imu";
+const SILENT: &str = "test/snapshot/silent";
+
+fn create_test_manager(
+    data_dir: &TempDir,
+    configure: impl FnOnce(&mut RecorderConfig),
+) -> (Arc<Session>, Arc<dyn StorageBackend>, RecorderManager) {
+    let mut config = common::filesystem_config(data_dir.path());
+    configure(&mut config);
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+    (session, storage, manager)
+}
+
+fn request(command: RecorderCommand, topics: &[&str]) -> RecorderRequest {
+    RecorderRequest {
+        command,
+        device_id: "snapshot-device".to_string(),
+        topics: topics.iter().map(|t| t.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_takes_cached_and_next_samples() {
+    let data_dir = TempDir::new().unwrap();
+    let (session, storage, manager) = create_test_manager(&data_dir, |_| {});
+
+    // The camera is recorded, so its last sample is cached
+    let recording_id = manager
+        .start_recording(request(RecorderCommand::Start, &[CAMERA]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(CAMERA, "frame-1").wait().unwrap();
+    session.put(CAMERA, "frame-2").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // The IMU publishes only after the snapshot subscribed
+    let publisher = session.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        publisher.put(IMU, "imu-1").wait().unwrap();
+    });
+
+    let mut snapshot = request(RecorderCommand::Snapshot, &[CAMERA, IMU, SILENT]);
+    snapshot.wait_timeout_ms = Some(1000);
+    snapshot
+        .labels
+        .insert("bug".to_string(), "1234".to_string());
+    let response = manager.snapshot(snapshot).await;
+    assert!(response.success, "{}", response.message);
+    let manifest = response.manifest.unwrap();
+    assert_eq!(manifest.snapshot_id, response.snapshot_id.unwrap());
+    assert_eq!(manifest.missing, vec![SILENT.to_string()]);
+    let topics: Vec<(&str, bool)> = manifest
+        .topics
+        .iter()
+        .map(|t| (t.topic.as_str(), t.cached))
+        .collect();
+    assert_eq!(topics, vec![(CAMERA, true), (IMU, false)]);
+
+    // One batch with a message per topic, and its manifest
+    let data = storage
+        .read_record(SNAPSHOT_ENTRY, manifest.timestamp_us)
+        .await
+        .unwrap();
+    assert_eq!(data.len() as u64, manifest.bytes);
+    let batch = parse_batch(&data).unwrap();
+    let payloads: Vec<(&str, &[u8])> = batch
+        .messages
+        .iter()
+        .map(|m| (m.topic.as_str(), m.payload.as_slice()))
+        .collect();
+    assert_eq!(
+        payloads,
+        vec![(CAMERA, &b"frame-2"[..]), (IMU, &b"imu-1"[..])]
+    );
+    let stored: SnapshotManifest = serde_json::from_slice(
+        &storage
+            .read_record(SNAPSHOT_METADATA_ENTRY, manifest.timestamp_us)
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stored, manifest);
+    assert_eq!(stored.labels.get("bug").map(String::as_str), Some("1234"));
+
+    // No recording was started and the snapshot left the subscribers
+    assert_eq!(manager.recording_statuses().await.len(), 1);
+    assert_eq!(manager.subscription_hub().subscriber_count(), 1);
+    assert!(manager.finish_recording(&recording_id).await.success);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_of_configured_topics() {
+    let data_dir = TempDir::new().unwrap();
+    let (session, storage, manager) = create_test_manager(&data_dir, |config| {
+        config.recorder.snapshot.topics = vec![IMU.to_string()];
+        config.recorder.snapshot.timeout_ms = 2000;
+    });
+
+    let publisher = session.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        publisher.put(IMU, "imu-1").wait().unwrap();
+    });
+    let response = manager
+        .snapshot(request(RecorderCommand::Snapshot, &[]))
+        .await;
+    assert!(response.success, "{}", response.message);
+    let manifest = response.manifest.unwrap();
+    assert_eq!(manifest.topics.len(), 1);
+    assert_eq!(manifest.topics[0].topic, IMU);
+    assert!(storage
+        .read_record(SNAPSHOT_ENTRY, manifest.timestamp_us)
+        .await
+        .is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_without_samples_fails() {
+    let data_dir = TempDir::new().unwrap();
+    let (_session, storage, manager) = create_test_manager(&data_dir, |_| {});
+
+    // No topics requested or configured
+    let response = manager
+        .snapshot(request(RecorderCommand::Snapshot, &[]))
+        .await;
+    assert!(!response.success);
+    assert_eq!(response.error_code, Some(ErrorCode::InvalidRequest));
+
+    let mut snapshot = request(RecorderCommand::Snapshot, &[SILENT]);
+    snapshot.wait_timeout_ms = Some(200);
+    let response = manager.snapshot(snapshot).await;
+    assert!(!response.success);
+    assert_eq!(response.error_code, Some(ErrorCode::NotFound));
+    assert!(storage
+        .find_records(SNAPSHOT_METADATA_ENTRY, "device_id", "snapshot-device")
+        .await
+        .unwrap()
+        .is_empty());
+}
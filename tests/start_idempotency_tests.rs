@@ -0,0 +1,125 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for Starts with a client-supplied recording_id
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+
+const TOPIC: &str = "test/idempotent/camera";
+
+fn start_request(recording_id: &str, if_exists: Option<IfExists>) -> RecorderRequest {
+    RecorderRequest {
+        recording_id: Some(recording_id.to_string()),
+        if_exists,
+        ..common::start_request(&[TOPIC])
+    }
+}
+
+#[test]
+fn test_if_exists_parsing() {
+    let json = r#"{"command": "start", "device_id": "d", "topics": ["t"],
+        "recording_id": "run-1", "if_exists": "return_existing"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(request.if_exists, Some(IfExists::ReturnExisting));
+    assert_eq!(IfExists::default(), IfExists::ReturnExisting);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retried_start_returns_existing() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, common::filesystem_config(data_dir.path()));
+
+    let first = manager.start_recording(start_request("run-1", None)).await;
+    assert!(first.success);
+    assert_eq!(first.recording_id.as_deref(), Some("run-1"));
+    assert_eq!(first.status, Some(RecordingStatus::Recording));
+
+    manager.pause_recording("run-1").await;
+    let paused_again = manager.pause_recording("run-1").await;
+    assert!(!paused_again.success);
+    assert_eq!(paused_again.error_code, Some(ErrorCode::InvalidState));
+
+    let retry = manager.start_recording(start_request("run-1", None)).await;
+    assert!(retry.success);
+    assert_eq!(retry.recording_id.as_deref(), Some("run-1"));
+    assert_eq!(retry.status, Some(RecordingStatus::Paused));
+    assert!(retry.message.contains("already exists"));
+
+    let conflict = manager
+        .start_recording(start_request("run-1", Some(IfExists::Error)))
+        .await;
+    assert!(!conflict.success);
+    assert!(conflict.message.contains("already exists"));
+    assert_eq!(conflict.error_code, Some(ErrorCode::AlreadyExists));
+    assert_eq!(
+        manager.get_status("run-1").await.status,
+        RecordingStatus::Paused
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_restart_replaces_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager =
+        common::create_test_manager(session.clone(), common::filesystem_config(data_dir.path()));
+
+    assert!(
+        manager
+            .start_recording(start_request("run-2", None))
+            .await
+            .success
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, vec![0u8; 10]).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        manager.get_status("run-2").await.per_topic[0].samples_received,
+        1
+    );
+
+    let restart = manager
+        .start_recording(start_request("run-2", Some(IfExists::Restart)))
+        .await;
+    assert!(restart.success);
+    assert_eq!(restart.recording_id.as_deref(), Some("run-2"));
+    assert_eq!(restart.status, Some(RecordingStatus::Recording));
+
+    // The new session starts from scratch
+    let status = manager.get_status("run-2").await;
+    assert_eq!(status.status, RecordingStatus::Recording);
+    assert_eq!(status.per_topic[0].samples_received, 0);
+    manager.cancel_recording("run-2").await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_recording_id_rejected() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, common::filesystem_config(data_dir.path()));
+
+    let response = manager
+        .start_recording(start_request("../escape", None))
+        .await;
+    assert!(!response.success);
+    assert_eq!(response.errors[0].field, "recording_id");
+}
@@ -0,0 +1,141 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for rate-limited status event publication
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+
+const TOPIC: &str = "test/status_events/data";
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    device_id: &str,
+    max_per_sec: f64,
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    config.recorder.device_id = device_id.to_string();
+    config.recorder.status_events.enabled = true;
+    config.recorder.status_events.max_per_sec = max_per_sec;
+
+    common::create_test_manager(session, config)
+}
+
+/// Start a recording, then toggle its topic `toggles` times in a burst
+///
+/// Returns the received status events.
+async fn record_burst(device_id: &str, max_per_sec: f64, toggles: usize) -> Vec<StatusResponse> {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let subscriber = session
+        .declare_subscriber(format!("recorder/events/{}/*", device_id))
+        .wait()
+        .unwrap();
+    let manager = create_test_manager(session.clone(), data_dir.path(), device_id, max_per_sec);
+
+    let request = RecorderRequest {
+        device_id: device_id.to_string(),
+        topics: vec![TOPIC.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+
+    let topics = [TOPIC.to_string()];
+    for i in 0..toggles {
+        let response = if i % 2 == 0 {
+            manager.pause_topics(&recording_id, &topics).await
+        } else {
+            manager.resume_topics(&recording_id, &topics).await
+        };
+        assert!(response.success);
+    }
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+
+    let mut events = Vec::new();
+    while let Ok(Some(sample)) = subscriber.try_recv() {
+        assert!(sample.key_expr().as_str().ends_with(&recording_id));
+        events.push(serde_json::from_slice(&sample.payload().to_bytes()).unwrap());
+    }
+    events
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_status_events_unlimited() {
+    let events = record_burst("events-unlimited", 0.0, 4).await;
+
+    // Start plus every toggle
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0].topic_paused.get(TOPIC), Some(&false));
+    assert_eq!(events[1].topic_paused.get(TOPIC), Some(&true));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_status_events_coalesced() {
+    let events = record_burst("events-coalesced", 2.0, 9).await;
+
+    // Start goes out immediately, the burst collapses into its latest state
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].status, RecordingStatus::Recording);
+    assert_eq!(events[1].topic_paused.get(TOPIC), Some(&true));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_finish_reports_uploading() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let subscriber = session
+        .declare_subscriber("recorder/events/events-finish/*")
+        .wait()
+        .unwrap();
+    let manager = create_test_manager(session.clone(), data_dir.path(), "events-finish", 0.0);
+
+    let request = RecorderRequest {
+        device_id: "events-finish".to_string(),
+        topics: vec![TOPIC.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, vec![0u8; 64]).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut statuses = Vec::new();
+    while let Ok(Some(sample)) = subscriber.try_recv() {
+        let event: StatusResponse = serde_json::from_slice(&sample.payload().to_bytes()).unwrap();
+        // Finished uploads are no longer listed
+        if event.status == RecordingStatus::Finished {
+            assert!(event.uploads.is_empty());
+        }
+        statuses.push(event.status);
+    }
+    let uploading = statuses
+        .iter()
+        .position(|s| *s == RecordingStatus::Uploading)
+        .unwrap();
+    assert_eq!(statuses.last(), Some(&RecordingStatus::Finished));
+    assert!(uploading < statuses.len() - 1);
+}
@@ -24,6 +24,10 @@ fn test_reductstore_client_creation() {
         api_token: None,
         timeout_seconds: 300,
         max_retries: 3,
+        max_label_bytes: 8192,
+        batch_max_records: 32,
+        tls: Default::default(),
+        bucket_settings: Default::default(),
     };
     let client = ReductStoreBackend::new(config);
     // Just verify it can be created
@@ -86,6 +90,10 @@ fn test_multiple_client_creation() {
                 api_token: None,
                 timeout_seconds: 300,
                 max_retries: 3,
+                max_label_bytes: 8192,
+                batch_max_records: 32,
+                tls: Default::default(),
+                bucket_settings: Default::default(),
             };
             ReductStoreBackend::new(config)
         })
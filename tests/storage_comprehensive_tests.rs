@@ -38,6 +38,10 @@ fn test_client_creation_various_urls() {
             api_token: None,
             timeout_seconds: 300,
             max_retries: 3,
+            max_label_bytes: 8192,
+            batch_max_records: 32,
+            tls: Default::default(),
+            bucket_settings: Default::default(),
         };
         let client = ReductStoreBackend::new(config);
         if let Ok(client) = client {
@@ -63,6 +67,10 @@ fn test_client_creation_various_buckets() {
             api_token: None,
             timeout_seconds: 300,
             max_retries: 3,
+            max_label_bytes: 8192,
+            batch_max_records: 32,
+            tls: Default::default(),
+            bucket_settings: Default::default(),
         };
         let client = ReductStoreBackend::new(config);
         if let Ok(client) = client {
@@ -213,6 +221,10 @@ fn test_reductstore_url_handling() {
             api_token: None,
             timeout_seconds: 300,
             max_retries: 3,
+            max_label_bytes: 8192,
+            batch_max_records: 32,
+            tls: Default::default(),
+            bucket_settings: Default::default(),
         };
         let _client = ReductStoreBackend::new(config);
         // Just verify creation doesn't panic
@@ -19,6 +19,7 @@ use std::collections::HashMap;
 use std::env;
 use std::time::{SystemTime, UNIX_EPOCH};
 use zenoh_recorder::config::ReductStoreConfig;
+use zenoh_recorder::error::RecorderError;
 use zenoh_recorder::storage::{ReductStoreBackend, StorageBackend};
 
 // Helper to get ReductStore URL from environment or use default
@@ -57,13 +58,17 @@ fn test_entry_name(test_name: &str) -> String {
 }
 
 // Helper to create ReductStoreBackend with config
-fn create_test_client() -> Result<ReductStoreBackend, anyhow::Error> {
+fn create_test_client() -> Result<ReductStoreBackend, RecorderError> {
     let config = ReductStoreConfig {
         url: get_reductstore_url(),
         bucket_name: get_test_bucket(),
         api_token: None,
         timeout_seconds: 300,
         max_retries: 3,
+        max_label_bytes: 8192,
+        batch_max_records: 32,
+        tls: Default::default(),
+        bucket_settings: Default::default(),
     };
     ReductStoreBackend::new(config)
 }
@@ -439,6 +444,10 @@ async fn test_storage_multiple_buckets() {
         api_token: None,
         timeout_seconds: 300,
         max_retries: 3,
+        max_label_bytes: 8192,
+        batch_max_records: 32,
+        tls: Default::default(),
+        bucket_settings: Default::default(),
     };
     let config2 = ReductStoreConfig {
         url: get_reductstore_url(),
@@ -446,6 +455,10 @@ async fn test_storage_multiple_buckets() {
         api_token: None,
         timeout_seconds: 300,
         max_retries: 3,
+        max_label_bytes: 8192,
+        batch_max_records: 32,
+        tls: Default::default(),
+        bucket_settings: Default::default(),
     };
 
     let client1 = ReductStoreBackend::new(config1).expect("Failed to create client1");
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use zenoh_recorder::storage::topic_to_entry_name;
+use zenoh_recorder::storage::{topic_to_entry_name, UploadProgress};
 
 #[test]
 fn test_topic_to_entry_name() {
@@ -32,3 +32,19 @@ fn test_topic_to_entry_name_special_chars() {
         "topic_with_underscore"
     );
 }
+
+#[test]
+fn test_upload_progress() {
+    let progress = UploadProgress::new(100);
+    assert_eq!(progress.uploaded(), 0);
+    progress.advance(40);
+    assert_eq!(progress.uploaded(), 40);
+
+    // Retries start over; overshoot is clamped to the total
+    progress.reset();
+    progress.advance(150);
+    assert_eq!(progress.uploaded(), 100);
+    progress.reset();
+    progress.complete();
+    assert_eq!((progress.uploaded(), progress.total()), (100, 100));
+}
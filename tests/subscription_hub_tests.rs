@@ -0,0 +1,183 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Zenoh subscribers shared by concurrent recordings
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::sample::SampleKind;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::subscription_hub::{SampleSink, SubscriptionHub};
+
+const CAMERA: &str = "test/shared_subscription/camera";
+const IMU: &str = "test/shared_subscription/imu";
+
+fn samples_received(status: &StatusResponse, topic: &str) -> u64 {
+    status
+        .per_topic
+        .iter()
+        .find(|t| t.topic == topic)
+        .map_or(0, |t| t.samples_received)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sinks_share_one_subscriber() {
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let hub = SubscriptionHub::new(session.clone());
+
+    let first = hub.subscribe(CAMERA).unwrap();
+    let second = hub.subscribe(CAMERA).unwrap();
+    assert_eq!(hub.subscriber_count(), 1);
+    assert_eq!(hub.sink_count(CAMERA), 2);
+
+    session.put(CAMERA, "frame").wait().unwrap();
+    for sink in [&first, &second] {
+        let sample = tokio::time::timeout(Duration::from_secs(2), sink.recv_async())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.payload().to_bytes().as_ref(), b"frame");
+    }
+
+    // The subscriber goes with the last sink
+    drop(first);
+    assert_eq!(hub.subscriber_count(), 1);
+    assert_eq!(hub.sink_count(CAMERA), 1);
+    drop(second);
+    assert_eq!(hub.subscriber_count(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recordings_share_topic_subscriptions() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::filesystem_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage, config);
+
+    let first = manager
+        .start_recording(common::start_request(&[CAMERA, IMU]))
+        .await
+        .recording_id
+        .unwrap();
+    let second = manager
+        .start_recording(common::start_request(&[CAMERA]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let hub = manager.subscription_hub();
+    assert_eq!(hub.subscriber_count(), 2);
+    assert_eq!(hub.sink_count(CAMERA), 2);
+
+    for i in 0..5 {
+        session.put(CAMERA, format!("frame-{}", i)).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    for recording_id in [&first, &second] {
+        let status = manager.get_status(recording_id).await;
+        assert_eq!(samples_received(&status, CAMERA), 5);
+    }
+
+    // The remaining recording keeps receiving the shared topic
+    assert!(manager.cancel_recording(&first).await.success);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(hub.subscriber_count(), 1);
+    assert_eq!(hub.sink_count(CAMERA), 1);
+    session.put(CAMERA, "frame-5").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let status = manager.get_status(&second).await;
+    assert_eq!(samples_received(&status, CAMERA), 6);
+
+    assert!(manager.finish_recording(&second).await.success);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(hub.subscriber_count(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_redeclared_subscribers_keep_feeding_sinks() {
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let hub = SubscriptionHub::new(session.clone());
+    let sink = hub.subscribe(IMU).unwrap();
+
+    // On a replacement session, the sink keeps receiving without resubscribing
+    let replacement = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    assert_eq!(hub.redeclare(replacement.clone()), 1);
+    session.close().await.unwrap();
+    assert_eq!(hub.subscriber_count(), 1);
+    assert_eq!(hub.sink_count(IMU), 1);
+
+    replacement.put(IMU, "sample").wait().unwrap();
+    let sample = tokio::time::timeout(Duration::from_secs(2), sink.recv_async())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sample.payload().to_bytes().as_ref(), b"sample");
+
+    // Declared once: no duplicates
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), sink.recv_async())
+            .await
+            .is_err()
+    );
+}
+
+async fn next_change(sink: &SampleSink) -> (String, SampleKind) {
+    let sample = tokio::time::timeout(Duration::from_secs(2), sink.recv_async())
+        .await
+        .unwrap()
+        .unwrap();
+    (sample.key_expr().to_string(), sample.kind())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_liveliness_sinks_get_alive_tokens() {
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let hub = SubscriptionHub::new(session.clone());
+    let topic = "@liveliness/test/shared_subscription/nodes/**";
+
+    let planner = session
+        .liveliness()
+        .declare_token("test/shared_subscription/nodes/planner")
+        .wait()
+        .unwrap();
+    let first = hub.subscribe(topic).unwrap();
+    let alive = (
+        "test/shared_subscription/nodes/planner".to_string(),
+        SampleKind::Put,
+    );
+    assert_eq!(next_change(&first).await, alive);
+
+    // A sink joining later gets the tokens alive, then the changes
+    let second = hub.subscribe(topic).unwrap();
+    assert_eq!(hub.subscriber_count(), 1);
+    assert_eq!(next_change(&second).await, alive);
+    drop(planner);
+    for sink in [&first, &second] {
+        assert_eq!(
+            next_change(sink).await,
+            (
+                "test/shared_subscription/nodes/planner".to_string(),
+                SampleKind::Delete
+            )
+        );
+    }
+}
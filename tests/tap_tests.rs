@@ -0,0 +1,112 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the live tap of recorded topics
+///
+mod common;
+
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::{Config, Session, Wait};
+use zenoh_recorder::config::{MockConfig, RecorderConfig, TapConfig, TapPolicy};
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::MockBackend;
+use zenoh_recorder::topic_to_entry_name;
+
+fn create_test_manager() -> (Arc<Session>, Arc<MockBackend>, RecorderManager) {
+    let mut config = RecorderConfig::default();
+    config.recorder.tap = TapConfig {
+        publish: true,
+        per_topic: HashMap::from([(
+            "test/tap/camera".to_string(),
+            TapPolicy { interval_ms: 200 },
+        )]),
+        ..Default::default()
+    };
+    let backend = Arc::new(MockBackend::new(MockConfig::default()));
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), backend.clone(), config);
+    (session, backend, manager)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_tap_is_down_sampled_and_republished() {
+    let (session, backend, manager) = create_test_manager();
+    assert!(
+        manager
+            .start_recording(RecorderRequest {
+                recording_id: Some("tapped".to_string()),
+                compression_type: CompressionType::None,
+                ..common::start_request(&["test/tap/camera", "test/tap/odom"])
+            })
+            .await
+            .success
+    );
+    let stream = manager.tap("tapped").unwrap();
+    let republished = session
+        .declare_subscriber("recorder/tap/tapped/**")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // 20 samples over a second; the tap keeps one per 200 ms
+    for i in 0..20 {
+        session
+            .put("test/tap/camera", format!("frame {}", i))
+            .await
+            .unwrap();
+        session
+            .put("test/tap/odom", format!("pose {}", i))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(manager.finish_recording("tapped").await.success);
+
+    // The stream ends with the recording
+    let tapped: Vec<_> = tokio::time::timeout(Duration::from_secs(5), stream.collect())
+        .await
+        .unwrap();
+    assert!((3..=8).contains(&tapped.len()), "{} tapped", tapped.len());
+    assert_eq!(
+        tapped[0].sample.payload().try_to_string().unwrap(),
+        "frame 0"
+    );
+    assert!(tapped
+        .iter()
+        .all(|t| t.recording_id == "tapped" && t.topic == "test/tap/camera"));
+    assert!(manager.tap("tapped").is_none());
+
+    let mut keys = Vec::new();
+    while let Ok(Some(sample)) = republished.try_recv() {
+        keys.push(sample.key_expr().to_string());
+    }
+    assert_eq!(keys.len(), tapped.len());
+    assert!(keys
+        .iter()
+        .all(|k| k == "recorder/tap/tapped/test/tap/camera"));
+
+    // Every sample was recorded all the same
+    let recorded: usize = backend
+        .records(&topic_to_entry_name("test/tap/camera"))
+        .into_iter()
+        .map(|(_, record)| parse_batch(&record.data).unwrap().messages.len())
+        .sum();
+    assert_eq!(recorded, 20);
+}
@@ -0,0 +1,206 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Background task registry: listing, panics, aborts and ordered shutdown
+///
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{BackendConfig, RetentionConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::task_registry::{TaskRegistry, PROCESS_SCOPE};
+
+/// Records its name in `log` when dropped (finished or aborted)
+struct DropLog(&'static str, Arc<Mutex<Vec<&'static str>>>);
+
+impl Drop for DropLog {
+    fn drop(&mut self) {
+        self.1.lock().unwrap().push(self.0);
+    }
+}
+
+#[tokio::test]
+async fn test_tasks_are_listed_while_running() {
+    let registry = TaskRegistry::new(PROCESS_SCOPE);
+    let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+    registry.spawn(TaskStage::Flush, "upload", async move {
+        let _ = receiver.await;
+    });
+    registry.spawn_service(TaskStage::Control, "listener", std::future::pending());
+
+    let tasks = registry.tasks();
+    assert_eq!(tasks.len(), 2);
+    // Listed in shutdown order
+    assert_eq!(tasks[0].name, "listener");
+    assert_eq!(tasks[0].kind, TaskKind::Service);
+    assert_eq!(tasks[1].name, "upload");
+    assert_eq!(tasks[1].stage, TaskStage::Flush);
+    assert_eq!(tasks[1].scope, PROCESS_SCOPE);
+
+    sender.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let tasks = registry.tasks();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].name, "listener");
+
+    assert_eq!(registry.abort_all(), 1);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(registry.tasks().is_empty());
+}
+
+#[tokio::test]
+async fn test_panics_are_kept_in_the_shared_log() {
+    let registry = TaskRegistry::new(PROCESS_SCOPE);
+    let recording = registry.child("rec-1");
+    recording.spawn(TaskStage::Ingest, "subscriber /imu", async {
+        panic!("buffer exploded");
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(recording.tasks().is_empty());
+    let panics = registry.panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].scope, "rec-1");
+    assert_eq!(panics[0].name, "subscriber /imu");
+    assert_eq!(panics[0].stage, TaskStage::Ingest);
+    assert_eq!(panics[0].message, "buffer exploded");
+    assert_eq!(recording.panics(), panics);
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_stages_in_order() {
+    let registry = TaskRegistry::new(PROCESS_SCOPE);
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let guard = DropLog("background", log.clone());
+    registry.spawn_service(TaskStage::Background, "gate", async move {
+        let _guard = guard;
+        std::future::pending::<()>().await;
+    });
+    let guard = DropLog("flush", log.clone());
+    registry.spawn(TaskStage::Flush, "finish", async move {
+        let _guard = guard;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    });
+    let guard = DropLog("control", log.clone());
+    registry.spawn_service(TaskStage::Control, "listener", async move {
+        let _guard = guard;
+        std::future::pending::<()>().await;
+    });
+
+    // The job ends on its own, the services are aborted
+    assert_eq!(registry.shutdown(Duration::from_secs(5)).await, 2);
+    assert_eq!(*log.lock().unwrap(), vec!["control", "flush", "background"]);
+    assert!(registry.tasks().is_empty());
+}
+
+#[tokio::test]
+async fn test_shutdown_aborts_overdue_jobs() {
+    let registry = TaskRegistry::new(PROCESS_SCOPE);
+    registry.spawn(TaskStage::Ingest, "stuck", async {
+        tokio::time::sleep(Duration::from_secs(3600)).await;
+    });
+
+    let started = std::time::Instant::now();
+    assert_eq!(registry.shutdown(Duration::from_millis(100)).await, 1);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(registry.tasks().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_manager_lists_process_and_recording_tasks() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::filesystem_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session, storage, config);
+
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            device_id: "device".to_string(),
+            topics: vec!["test/task_registry/imu".to_string()],
+            ..Default::default()
+        })
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let response = manager.list_tasks();
+    assert!(response.success);
+    assert!(response
+        .tasks
+        .iter()
+        .any(|t| t.scope == PROCESS_SCOPE && t.name.starts_with("flush worker")));
+    assert!(response
+        .tasks
+        .iter()
+        .any(|t| t.scope == recording_id && t.stage == TaskStage::Ingest));
+
+    manager.cancel_recording(&recording_id).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!manager
+        .list_tasks()
+        .tasks
+        .iter()
+        .any(|t| t.scope == recording_id));
+
+    manager.shutdown(None).await.unwrap();
+    assert!(manager.list_tasks().tasks.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_storage_and_controller_tasks_are_registered() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    if let BackendConfig::Filesystem { filesystem } = &mut config.storage.backend_config {
+        filesystem.retention = RetentionConfig {
+            max_age_hours: 24,
+            ..Default::default()
+        };
+    }
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session, storage, config);
+
+    let recording_id = manager
+        .start_recording(RecorderRequest {
+            device_id: "device".to_string(),
+            topics: vec!["test/task_registry/gps".to_string()],
+            controller_liveliness: Some("test/task_registry/controller".to_string()),
+            ..Default::default()
+        })
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let tasks = manager.list_tasks().tasks;
+    assert!(tasks
+        .iter()
+        .any(|t| t.scope == PROCESS_SCOPE && t.name == "retention"));
+    assert!(tasks
+        .iter()
+        .any(|t| t.scope == recording_id
+            && t.name == "controller watch test/task_registry/controller"));
+
+    manager.shutdown(None).await.unwrap();
+    assert!(manager.list_tasks().tasks.is_empty());
+}
@@ -0,0 +1,179 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for attaching/detaching topics of a live recording
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::clock::MockClock;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+const CAMERA: &str = "test/topic_changes/camera";
+const LIDAR: &str = "test/topic_changes/lidar";
+
+fn record_count(data_dir: &Path, entry: &str) -> usize {
+    std::fs::read_dir(data_dir.join(entry))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+async fn start(manager: &RecorderManager) -> String {
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![CAMERA.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    manager.start_recording(request).await.recording_id.unwrap()
+}
+
+#[test]
+fn test_topic_change_command_parsing() {
+    let json = r#"{
+        "command": "add_topics",
+        "recording_id": "rec-1",
+        "device_id": "d",
+        "topics": ["/lidar/points"]
+    }"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::AddTopics));
+    assert_eq!(request.topics, vec!["/lidar/points".to_string()]);
+
+    let json = r#"{"command": "remove_topics", "device_id": "d"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::RemoveTopics));
+
+    let change: TopicChange = serde_json::from_str(
+        r#"{"topic": "/lidar/points", "change": "removed", "at": "2025-01-01T00:00:00+00:00"}"#,
+    )
+    .unwrap();
+    assert_eq!(change.change, TopicChangeKind::Removed);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_topic_change_validation() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, common::per_sample_config(data_dir.path()));
+
+    let response = manager.add_topics("missing", &[LIDAR.to_string()]).await;
+    assert!(!response.success);
+
+    let recording_id = start(&manager).await;
+    assert!(!manager.add_topics(&recording_id, &[]).await.success);
+
+    let response = manager
+        .add_topics(&recording_id, &[CAMERA.to_string()])
+        .await;
+    assert!(!response.success);
+    assert!(response.message.contains(CAMERA));
+
+    let response = manager
+        .remove_topics(&recording_id, &[LIDAR.to_string()])
+        .await;
+    assert!(!response.success);
+    assert!(response.message.contains(LIDAR));
+
+    manager.finish_recording(&recording_id).await;
+    let response = manager
+        .add_topics(&recording_id, &[LIDAR.to_string()])
+        .await;
+    assert!(!response.success);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_add_and_remove_topics() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let clock = Arc::new(MockClock::new(
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    ));
+    let manager =
+        common::create_test_manager(session.clone(), common::per_sample_config(data_dir.path()))
+            .with_clock(clock.clone());
+
+    let recording_id = start(&manager).await;
+    let camera = session.declare_publisher(CAMERA).wait().unwrap();
+    let lidar = session.declare_publisher(LIDAR).wait().unwrap();
+
+    clock.advance(Duration::from_secs(10));
+    let response = manager
+        .add_topics(&recording_id, &[LIDAR.to_string()])
+        .await;
+    assert!(response.success);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(
+        status.active_topics,
+        vec![CAMERA.to_string(), LIDAR.to_string()]
+    );
+    assert_eq!(status.topic_paused.get(LIDAR), Some(&false));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    lidar.put("scan-1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        record_count(data_dir.path(), &topic_to_entry_name(LIDAR)),
+        1
+    );
+
+    clock.advance(Duration::from_secs(10));
+    let response = manager
+        .remove_topics(&recording_id, &[CAMERA.to_string()])
+        .await;
+    assert!(response.success);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.active_topics, vec![LIDAR.to_string()]);
+    assert!(!status.topic_paused.contains_key(CAMERA));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    camera.put("frame-1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(
+        record_count(data_dir.path(), &topic_to_entry_name(CAMERA)),
+        0
+    );
+
+    manager.finish_recording(&recording_id).await;
+    let metadata = common::read_metadata(data_dir.path());
+    assert_eq!(metadata.topics, vec![CAMERA.to_string(), LIDAR.to_string()]);
+    let changes: Vec<(&str, TopicChangeKind, &str)> = metadata
+        .topic_changes
+        .iter()
+        .map(|c| (c.topic.as_str(), c.change, c.at.as_str()))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (LIDAR, TopicChangeKind::Added, "2023-11-14T22:13:30+00:00"),
+            (
+                CAMERA,
+                TopicChangeKind::Removed,
+                "2023-11-14T22:13:40+00:00"
+            )
+        ]
+    );
+}
@@ -0,0 +1,106 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for the per-topic compression settings using the filesystem backend
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::{Config, Wait};
+use zenoh_recorder::config::TopicCompression;
+use zenoh_recorder::inspect::parse_batch;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::topic_to_entry_name;
+
+const CAMERA: &str = "test/topic_compression/camera/front";
+const LOGS: &str = "test/topic_compression/logs";
+const STATE: &str = "test/topic_compression/state";
+
+/// Codecs of the batches stored for a topic
+fn stored_codecs(data_dir: &Path, topic: &str) -> Vec<CompressionType> {
+    std::fs::read_dir(data_dir.join(topic_to_entry_name(topic)))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mcap"))
+        .map(|path| parse_batch(&std::fs::read(path).unwrap()).unwrap().codec)
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_per_topic_compression_within_recording() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    let per_topic = &mut config.recorder.compression.per_topic;
+    per_topic.insert(
+        "test/topic_compression/camera/**".to_string(),
+        TopicCompression {
+            r#type: "lz4".to_string(),
+            level: 0,
+            transform: None,
+        },
+    );
+    per_topic.insert(
+        LOGS.to_string(),
+        TopicCompression {
+            r#type: "zstd".to_string(),
+            level: 4,
+            transform: None,
+        },
+    );
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = RecorderManager::new(session.clone(), storage, config);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![
+            "test/topic_compression/camera/**".to_string(),
+            LOGS.to_string(),
+            STATE.to_string(),
+        ],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    session.put(CAMERA, vec![7u8; 4096]).wait().unwrap();
+    session
+        .put(LOGS, "INFO started ".repeat(100))
+        .wait()
+        .unwrap();
+    session.put(STATE, "idle").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    // The configured topics use their own settings, the others the request's
+    assert_eq!(
+        stored_codecs(data_dir.path(), "test/topic_compression/camera/**"),
+        vec![CompressionType::Lz4]
+    );
+    assert_eq!(
+        stored_codecs(data_dir.path(), LOGS),
+        vec![CompressionType::Zstd]
+    );
+    assert_eq!(
+        stored_codecs(data_dir.path(), STATE),
+        vec![CompressionType::None]
+    );
+}
@@ -0,0 +1,135 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Per-topic pause/resume tests using the filesystem backend
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+const CAMERA: &str = "test/pause_topics/camera";
+const IMU: &str = "test/pause_topics/imu";
+
+fn record_count(data_dir: &Path, topic: &str) -> usize {
+    std::fs::read_dir(data_dir.join(topic_to_entry_name(topic)))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "mcap"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+async fn start(manager: &RecorderManager) -> String {
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![CAMERA.to_string(), IMU.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    manager.start_recording(request).await.recording_id.unwrap()
+}
+
+#[test]
+fn test_pause_topics_command_parsing() {
+    let json = r#"{
+        "command": "pause_topics",
+        "recording_id": "rec-1",
+        "device_id": "d",
+        "topics": ["/camera/front"]
+    }"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::PauseTopics));
+    assert_eq!(request.topics, vec!["/camera/front".to_string()]);
+
+    let json = r#"{"command": "resume_topics", "device_id": "d"}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::ResumeTopics));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pause_topics_validation() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = common::create_test_manager(session, common::per_sample_config(data_dir.path()));
+
+    let response = manager.pause_topics("missing", &[CAMERA.to_string()]).await;
+    assert!(!response.success);
+
+    let recording_id = start(&manager).await;
+    let response = manager.pause_topics(&recording_id, &[]).await;
+    assert!(!response.success);
+    let response = manager
+        .pause_topics(&recording_id, &["not/recorded".to_string()])
+        .await;
+    assert!(!response.success);
+    assert!(response.message.contains("not/recorded"));
+
+    manager.cancel_recording(&recording_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_paused_topic_is_muted() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager =
+        common::create_test_manager(session.clone(), common::per_sample_config(data_dir.path()));
+
+    let recording_id = start(&manager).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let camera = session.declare_publisher(CAMERA).wait().unwrap();
+    let imu = session.declare_publisher(IMU).wait().unwrap();
+    camera.put("frame-1").wait().unwrap();
+    imu.put("imu-1").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let response = manager
+        .pause_topics(&recording_id, &[CAMERA.to_string()])
+        .await;
+    assert!(response.success);
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.status, RecordingStatus::Recording);
+    assert_eq!(status.topic_paused.get(CAMERA), Some(&true));
+    assert_eq!(status.topic_paused.get(IMU), Some(&false));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    camera.put("frame-2").wait().unwrap();
+    imu.put("imu-2").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(record_count(data_dir.path(), CAMERA), 1);
+    assert_eq!(record_count(data_dir.path(), IMU), 2);
+
+    let response = manager
+        .resume_topics(&recording_id, &[CAMERA.to_string()])
+        .await;
+    assert!(response.success);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    camera.put("frame-3").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(record_count(data_dir.path(), CAMERA), 2);
+
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.topic_paused.get(CAMERA), Some(&false));
+}
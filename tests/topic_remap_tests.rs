@@ -0,0 +1,110 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Topic remapping tests using the filesystem backend
+///
+mod common;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+const ORIGINAL: &str = "test/topic_remap/robot1/camera";
+const RECORDED: &str = "test/topic_remap/camera";
+const PLAIN: &str = "test/topic_remap/lidar";
+
+fn create_test_manager(session: Arc<zenoh::Session>, data_dir: &Path) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir);
+    // Flush every sample as its own record
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config
+        .recorder
+        .topic_remap
+        .insert(ORIGINAL.to_string(), RECORDED.to_string());
+
+    common::create_test_manager(session, config)
+}
+
+/// Label sidecars of the records of an entry
+fn record_labels(data_dir: &Path, entry: &str) -> Vec<HashMap<String, String>> {
+    std::fs::read_dir(data_dir.join(entry))
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".meta.json"))
+        .map(|e| serde_json::from_slice(&std::fs::read(e.path()).unwrap()).unwrap())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_remapped_topic_recorded_under_alias() {
+    let data_dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), data_dir.path());
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![ORIGINAL.to_string(), PLAIN.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    session.put(ORIGINAL, "frame").wait().unwrap();
+    session.put(PLAIN, "points").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    manager.finish_recording(&recording_id).await;
+
+    // Records are stored and labeled under the recorded name
+    assert!(!data_dir.path().join(topic_to_entry_name(ORIGINAL)).exists());
+    let labels = record_labels(data_dir.path(), &topic_to_entry_name(RECORDED));
+    assert!(!labels.is_empty());
+    for labels in &labels {
+        assert_eq!(labels["topic"], RECORDED);
+        assert_eq!(labels["original_topic"], ORIGINAL);
+    }
+    let labels = record_labels(data_dir.path(), &topic_to_entry_name(PLAIN));
+    assert!(!labels.is_empty());
+    assert!(labels.iter().all(|l| !l.contains_key("original_topic")));
+
+    // The metadata resolves both names
+    let metadata = common::read_metadata(data_dir.path());
+    assert_eq!(metadata.topic_aliases.len(), 1);
+    assert_eq!(metadata.topic_aliases[ORIGINAL], RECORDED);
+    assert!(metadata.topics.contains(&ORIGINAL.to_string()));
+    assert!(metadata.per_topic_stats.get(RECORDED).is_some());
+    assert!(metadata.per_topic_stats.get(ORIGINAL).is_none());
+}
+
+#[test]
+fn test_metadata_without_aliases() {
+    let json = r#"{
+        "recording_id": "rec-1", "scene": null, "skills": [], "organization": null,
+        "task_id": null, "device_id": "d", "data_collector_id": null, "topics": [],
+        "compression_type": "None", "compression_level": 2,
+        "start_time": "2025-01-01T00:00:00+00:00", "end_time": null,
+        "total_bytes": 0, "total_samples": 0, "per_topic_stats": {}
+    }"#;
+    let metadata: RecordingMetadata = serde_json::from_str(json).unwrap();
+    assert!(metadata.topic_aliases.is_empty());
+    let json = serde_json::to_string(&metadata).unwrap();
+    assert!(!json.contains("topic_aliases"));
+}
@@ -0,0 +1,147 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Tests for per-topic statistics in status responses and across recordings
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+
+const TOPIC: &str = "test/topic_stats/imu";
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &Path,
+    stats_enabled: bool,
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir.join("data"));
+    config.recorder.topic_stats.enabled = stats_enabled;
+    config.recorder.topic_stats.path = data_dir
+        .join("topic_stats.json")
+        .to_string_lossy()
+        .to_string();
+
+    common::create_test_manager(session, config)
+}
+
+#[test]
+fn test_estimate_command_parsing() {
+    let json = r#"{"command": "estimate", "device_id": "d", "topics": ["/imu"]}"#;
+    let request: RecorderRequest = serde_json::from_str(json).unwrap();
+    assert!(matches!(request.command, RecorderCommand::Estimate));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_estimate_disabled() {
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, dir.path(), false);
+
+    let response = manager.estimate(&[TOPIC.to_string()]);
+    assert!(!response.success);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_statistics_learned_from_recording() {
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), dir.path(), true);
+
+    let response = manager.estimate(&[TOPIC.to_string()]);
+    assert!(response.success);
+    assert_eq!(response.unknown_topics, vec![TOPIC.to_string()]);
+
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![TOPIC.to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    for _ in 0..20 {
+        publisher.put(vec![0u8; 100]).wait().unwrap();
+    }
+    // Rates are only learned from recordings of a second or more
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    manager.finish_recording(&recording_id).await;
+
+    // Learned profile is persisted and reloaded by the next recorder
+    let manager = create_test_manager(session, dir.path(), true);
+    let response = manager.estimate(&[]);
+    assert!(response.success);
+    assert!(response.unknown_topics.is_empty());
+    assert_eq!(response.topics.len(), 1);
+
+    let estimate = &response.topics[0];
+    assert_eq!(estimate.topic, TOPIC);
+    assert_eq!(estimate.recordings, 1);
+    assert_eq!(estimate.avg_sample_bytes, 100.0);
+    assert!(estimate.samples_per_sec > 0.0);
+    assert!(estimate.compression_ratio.is_some());
+    assert!(response.upload_feasible);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_status_reports_per_topic_counters() {
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let mut config = common::filesystem_config(dir.path());
+    // Flush after the third 100-byte sample
+    config.recorder.flush_policy.max_buffer_size_bytes = 250;
+    let storage_backend = BackendFactory::create(&config.storage).unwrap();
+    let manager = RecorderManager::new(session.clone(), storage_backend, config);
+
+    let stalled = "test/topic_stats/stalled";
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![TOPIC.to_string(), stalled.to_string()],
+        ..Default::default()
+    };
+    let recording_id = manager.start_recording(request).await.recording_id.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    for _ in 0..5 {
+        publisher.put(vec![0u8; 100]).wait().unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let status = manager.get_status(&recording_id).await;
+    assert_eq!(status.per_topic.len(), 2);
+    let imu = &status.per_topic[0];
+    assert_eq!(imu.topic, TOPIC);
+    assert_eq!(imu.samples_received, 5);
+    assert_eq!(imu.bytes_flushed, 300);
+    assert_eq!(imu.bytes_buffered, 200);
+    assert_eq!(imu.dropped, 0);
+    assert!(imu.last_sample_at.is_some());
+
+    let stalled_stats = &status.per_topic[1];
+    assert_eq!(stalled_stats.topic, stalled);
+    assert_eq!(stalled_stats.samples_received, 0);
+    assert_eq!(stalled_stats.bytes_flushed, 0);
+    assert!(stalled_stats.last_sample_at.is_none());
+
+    manager.finish_recording(&recording_id).await;
+}
@@ -0,0 +1,137 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Topology snapshots taken from the Zenoh admin space
+///
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::BackendFactory;
+
+const CAMERA: &str = "test/topology/camera";
+
+fn admin_session() -> Arc<zenoh::Session> {
+    admin_session_with(Config::default())
+}
+
+fn admin_session_with(mut config: Config) -> Arc<zenoh::Session> {
+    config.insert_json5("adminspace/enabled", "true").unwrap();
+    Arc::new(zenoh::open(config).wait().unwrap())
+}
+
+/// Endpoint on a free local port
+fn free_endpoint() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!(
+        "\"tcp/127.0.0.1:{}\"",
+        listener.local_addr().unwrap().port()
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_stored_in_metadata() {
+    let data_dir = TempDir::new().unwrap();
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.topology.snapshot = true;
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = admin_session();
+    let manager = RecorderManager::new(session.clone(), storage.clone(), config);
+
+    let _camera = session.declare_publisher(CAMERA).wait().unwrap();
+    let _other = session
+        .declare_publisher("test/other/lidar")
+        .wait()
+        .unwrap();
+    let recording_id = manager
+        .start_recording(common::start_request(&[CAMERA]))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    let topology = metadata.topology.unwrap();
+    assert_eq!(topology.zid, session.zid().to_string());
+    assert!(!topology.taken_at.is_empty());
+
+    // Only the publisher on the recorded topic is listed
+    assert_eq!(topology.publishers.len(), 1);
+    assert_eq!(topology.publishers[0].key_expr, CAMERA);
+    assert_eq!(topology.publishers[0].zid, topology.zid);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_lists_connected_nodes() {
+    let endpoint = free_endpoint();
+    let mut config = Config::default();
+    config
+        .insert_json5("listen/endpoints", &format!("[{}]", endpoint))
+        .unwrap();
+    let session = admin_session_with(config);
+
+    let mut config = Config::default();
+    config
+        .insert_json5("scouting/multicast/enabled", "false")
+        .unwrap();
+    config
+        .insert_json5("connect/endpoints", &format!("[{}]", endpoint))
+        .unwrap();
+    let other = zenoh::open(config).wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let snapshot = zenoh_recorder::topology::snapshot(
+        &session,
+        &[CAMERA.to_string()],
+        Duration::from_millis(500),
+    )
+    .await
+    .unwrap();
+    let node = snapshot
+        .sessions
+        .iter()
+        .find(|s| s.zid == other.zid().to_string())
+        .unwrap();
+    assert_eq!(node.whatami, "peer");
+    assert!(!node.locators.is_empty());
+    assert!(!snapshot.locators.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_snapshot_off_by_default() {
+    let data_dir = TempDir::new().unwrap();
+    let config = common::filesystem_config(data_dir.path());
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = admin_session();
+    let manager = RecorderManager::new(session, storage.clone(), config);
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[CAMERA]))
+        .await
+        .recording_id
+        .unwrap();
+    assert!(manager.finish_recording(&recording_id).await.success);
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert!(metadata.topology.is_none());
+}
@@ -0,0 +1,126 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Uploads held back until the deferral conditions are met
+///
+mod common;
+
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{ConnectivityProbe, UploadDeferralConfig, WorkDirConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::replay;
+use zenoh_recorder::storage::{BackendFactory, StorageBackend};
+use zenoh_recorder::work_dir::WorkDirs;
+
+const TOPIC: &str = "test/deferral/camera";
+
+fn create_manager(
+    session: Arc<zenoh::Session>,
+    data_dir: &TempDir,
+    work_dir: &TempDir,
+    probe_address: String,
+) -> (RecorderManager, Arc<dyn StorageBackend>) {
+    let mut config = common::filesystem_config(data_dir.path());
+    config.recorder.work_dir = WorkDirConfig {
+        enabled: true,
+        path: work_dir.path().to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    config.recorder.upload_deferral = UploadDeferralConfig {
+        enabled: true,
+        probes: vec![ConnectivityProbe::Tcp {
+            address: probe_address,
+            timeout_ms: 200,
+        }],
+        check_interval_seconds: 1,
+        ..Default::default()
+    };
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    (
+        RecorderManager::new(session, storage.clone(), config),
+        storage,
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_records_deferred_until_probe_passes() {
+    let data_dir = TempDir::new().unwrap();
+    let work_dir = TempDir::new().unwrap();
+    // Nothing listens on the probed port until the link "comes up"
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let (manager, storage) =
+        create_manager(session.clone(), &data_dir, &work_dir, address.to_string());
+    let manager = Arc::new(manager);
+
+    let recording_id = manager
+        .start_recording(common::start_request(&[TOPIC]))
+        .await
+        .recording_id
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    session.put(TOPIC, "frame").wait().unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(manager.begin_finish(&recording_id).await.success);
+    let finish = {
+        let manager = manager.clone();
+        let recording_id = recording_id.clone();
+        tokio::spawn(async move { manager.complete_finish(&recording_id).await })
+    };
+
+    // The record is kept in the work directory instead of being uploaded
+    let work_dirs = WorkDirs::new(work_dir.path(), Duration::from_secs(3600));
+    let mut deferred = Vec::new();
+    for _ in 0..50 {
+        deferred = work_dirs.deferred(&recording_id).await;
+        if !deferred.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(deferred.len(), 1);
+    let completion = manager
+        .wait_for_completion(&recording_id, Duration::from_millis(1500))
+        .await;
+    assert!(!completion.completed);
+    assert_eq!(completion.status, RecordingStatus::Uploading);
+    assert!(replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .is_err());
+
+    // Uploaded once the probe passes
+    let _listener = TcpListener::bind(address).unwrap();
+    let completion = manager
+        .wait_for_completion(&recording_id, Duration::from_secs(10))
+        .await;
+    assert!(completion.completed, "{}", completion.message);
+    assert!(finish.await.unwrap().success);
+    assert!(work_dirs.deferred(&recording_id).await.is_empty());
+
+    let metadata = replay::find_recording(storage.as_ref(), &recording_id)
+        .await
+        .unwrap();
+    assert_eq!(metadata.records.len(), 1);
+    assert_eq!(metadata.total_bytes as usize, metadata.records[0].bytes);
+}
@@ -0,0 +1,238 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Start request validation tests
+///
+mod common;
+
+use std::sync::Arc;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::config::{CompressionConfig, CompressionLimitAction, RecorderConfig};
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::storage::BackendFactory;
+use zenoh_recorder::validation::{limit_compression, validate_start, MAX_TEXT_LEN};
+
+fn fields(errors: &[ValidationError]) -> Vec<&str> {
+    errors.iter().map(|e| e.field.as_str()).collect()
+}
+
+#[test]
+fn test_valid_request() {
+    let request = common::start_request(&["robot/camera/**", "robot/lidar"]);
+    assert!(validate_start(&request, CompressionLevel::Default).is_empty());
+}
+
+#[test]
+fn test_all_problems_reported() {
+    let mut request = common::start_request(&["robot/camera", "/robot/lidar", "robot/camera", ""]);
+    request.device_id = "  ".to_string();
+    request.scene = Some("s".repeat(MAX_TEXT_LEN + 1));
+    request.skills = vec!["grasp".to_string(), String::new()];
+    request.compression_type = CompressionType::None;
+    request.compression_level = CompressionLevel::Slowest;
+    request.bucket = Some("../escape".to_string());
+    request.controller_liveliness = Some("app//alive".to_string());
+    request.priority = Some(1_000_000);
+
+    let errors = validate_start(&request, CompressionLevel::Default);
+    assert_eq!(
+        fields(&errors),
+        vec![
+            "device_id",
+            "scene",
+            "skills[1]",
+            "topics[1]",
+            "topics[2]",
+            "topics[3]",
+            "compression_level",
+            "bucket",
+            "controller_liveliness",
+            "priority",
+        ]
+    );
+    assert!(errors[3].message.contains("must not start with '/'"));
+    assert!(errors[4].message.contains("duplicates topics[0]"));
+}
+
+#[test]
+fn test_topics_required() {
+    let errors = validate_start(&common::start_request(&[]), CompressionLevel::Default);
+    assert_eq!(fields(&errors), vec!["topics"]);
+}
+
+#[test]
+fn test_exclude_topics() {
+    let mut request = common::start_request(&["robot/**"]);
+    request.exclude_topics = vec!["robot/**/debug/**".to_string()];
+    assert!(validate_start(&request, CompressionLevel::Default).is_empty());
+
+    request.exclude_topics.push("/robot/log".to_string());
+    assert_eq!(
+        fields(&validate_start(&request, CompressionLevel::Default)),
+        vec!["exclude_topics[1]"]
+    );
+}
+
+#[test]
+fn test_uncompressed_with_configured_level() {
+    // The configured level is filled in for requests that leave it out
+    let mut request = common::start_request(&["robot/camera"]);
+    request.compression_type = CompressionType::None;
+    request.compression_level = CompressionLevel::Fast;
+    assert!(validate_start(&request, CompressionLevel::Fast).is_empty());
+    assert_eq!(
+        fields(&validate_start(&request, CompressionLevel::Default)),
+        vec!["compression_level"]
+    );
+}
+
+#[test]
+fn test_environment_keys() {
+    let mut request = common::start_request(&["robot/camera"]);
+    request
+        .environment
+        .insert("sim_seed".to_string(), "42".to_string());
+    assert!(validate_start(&request, CompressionLevel::Default).is_empty());
+
+    // Keys become label names
+    request
+        .environment
+        .insert("map version".to_string(), "v3".to_string());
+    request
+        .environment
+        .insert("stack".to_string(), "x".repeat(MAX_TEXT_LEN + 1));
+    assert_eq!(
+        fields(&validate_start(&request, CompressionLevel::Default)),
+        vec!["environment.map version", "environment.stack"]
+    );
+}
+
+#[test]
+fn test_label_keys() {
+    let mut request = common::start_request(&["robot/camera"]);
+    request
+        .labels
+        .insert("weather".to_string(), "rain".to_string());
+    assert!(validate_start(&request, CompressionLevel::Default).is_empty());
+
+    // System labels can't be overridden
+    for key in ["topic", "env_sim_seed", "bad-key"] {
+        request.labels.insert(key.to_string(), "x".to_string());
+    }
+    assert_eq!(
+        fields(&validate_start(&request, CompressionLevel::Default)),
+        vec!["labels.bad-key", "labels.env_sim_seed", "labels.topic"]
+    );
+}
+
+#[test]
+fn test_compression_limits() {
+    let mut config = CompressionConfig {
+        max_zstd_level: Some(10),
+        max_lz4_level: Some(9),
+        ..Default::default()
+    };
+
+    // Within the limits, or not compressed at all
+    let mut request = common::start_request(&["robot/imu"]);
+    request.compression_level = CompressionLevel::Slow;
+    assert_eq!(limit_compression(&mut request, &config), Ok(None));
+    request.compression_type = CompressionType::None;
+    request.compression_level = CompressionLevel::Slowest;
+    assert_eq!(limit_compression(&mut request, &config), Ok(None));
+
+    // Zstd 19 is rejected by default
+    request.compression_type = CompressionType::Zstd;
+    let error = limit_compression(&mut request, &config).unwrap_err();
+    assert_eq!(error.field, "compression_level");
+    assert!(error.message.contains("max_zstd_level"));
+    assert_eq!(request.compression_level, CompressionLevel::Slowest);
+
+    // Or lowered to the slowest level within the limit
+    config.over_limit = CompressionLimitAction::Clamp;
+    assert_eq!(
+        limit_compression(&mut request, &config),
+        Ok(Some(CompressionLevel::Slowest))
+    );
+    assert_eq!(request.compression_level, CompressionLevel::Slow);
+
+    request.compression_type = CompressionType::Lz4;
+    request.compression_level = CompressionLevel::Slowest;
+    assert_eq!(
+        limit_compression(&mut request, &config),
+        Ok(Some(CompressionLevel::Slowest))
+    );
+    assert_eq!(request.compression_level.to_lz4_level(), 9);
+}
+
+fn create_manager(
+    data_dir: &TempDir,
+    configure: impl FnOnce(&mut RecorderConfig),
+) -> RecorderManager {
+    let mut config = common::filesystem_config(data_dir.path());
+    configure(&mut config);
+    let storage = BackendFactory::create(&config.storage).unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    RecorderManager::new(session, storage, config)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_start_rejected_with_errors() {
+    let data_dir = TempDir::new().unwrap();
+    let manager = create_manager(&data_dir, |_| {});
+
+    let mut request = common::start_request(&["test/validation", "test/validation"]);
+    request.device_id = String::new();
+    let response = manager.start_recording(request).await;
+    assert!(!response.success);
+    assert!(response.recording_id.is_none());
+    assert_eq!(fields(&response.errors), vec!["device_id", "topics[1]"]);
+    assert!(response.message.starts_with("Invalid request: device_id:"));
+
+    // Clients get the list as `errors`
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["errors"][1]["field"], "topics[1]");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_start_over_compression_limit() {
+    let data_dir = TempDir::new().unwrap();
+    let manager = create_manager(&data_dir, |config| {
+        config.recorder.compression.max_zstd_level = Some(10);
+    });
+
+    let mut request = common::start_request(&["test/validation/limit"]);
+    request.compression_level = CompressionLevel::Slowest;
+    let response = manager.start_recording(request.clone()).await;
+    assert!(!response.success);
+    assert!(response.recording_id.is_none());
+    assert_eq!(fields(&response.errors), vec!["compression_level"]);
+
+    let manager = create_manager(&data_dir, |config| {
+        config.recorder.compression.max_zstd_level = Some(10);
+        config.recorder.compression.over_limit = CompressionLimitAction::Clamp;
+    });
+    let response = manager.start_recording(request).await;
+    assert!(response.success, "{}", response.message);
+    assert!(response.message.contains("Slow instead of Slowest"));
+    let recording_id = response.recording_id.unwrap();
+    assert_eq!(
+        manager.get_status(&recording_id).await.status,
+        RecordingStatus::Recording
+    );
+    manager.cancel_recording(&recording_id).await;
+}
@@ -0,0 +1,124 @@
+// Copyright 2025 coScene
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Per-recording work directory tests using the filesystem backend
+///
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zenoh::Config;
+use zenoh::Wait;
+use zenoh_recorder::protocol::*;
+use zenoh_recorder::recorder::RecorderManager;
+use zenoh_recorder::topic_to_entry_name;
+
+fn create_test_manager(
+    session: Arc<zenoh::Session>,
+    dir: &Path,
+    retain_failed_hours: u64,
+) -> RecorderManager {
+    let mut config = common::filesystem_config(dir.join("data"));
+    // Flush every sample as its own record
+    config.recorder.flush_policy.max_buffer_size_bytes = 1;
+    config.recorder.work_dir.enabled = true;
+    config.recorder.work_dir.path = dir.join("work").to_string_lossy().to_string();
+    config.recorder.work_dir.retain_failed_hours = retain_failed_hours;
+
+    common::create_test_manager(session, config)
+}
+
+async fn start(manager: &RecorderManager, topic: &str) -> String {
+    let request = RecorderRequest {
+        device_id: "device".to_string(),
+        topics: vec![topic.to_string()],
+        compression_type: CompressionType::None,
+        ..Default::default()
+    };
+    manager.start_recording(request).await.recording_id.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_work_dir_removed_on_finish_and_cancel() {
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session, dir.path(), 24);
+
+    let finished = start(&manager, "test/work_dir/finish").await;
+    let cancelled = start(&manager, "test/work_dir/cancel").await;
+    assert!(dir.path().join("work").join(&finished).is_dir());
+    assert!(dir.path().join("work").join(&cancelled).is_dir());
+
+    manager.cancel_recording(&cancelled).await;
+    assert!(!dir.path().join("work").join(&cancelled).exists());
+    assert!(dir.path().join("work").join(&finished).is_dir());
+
+    manager.finish_recording(&finished).await;
+    assert!(!dir.path().join("work").join(&finished).exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_failed_uploads_are_spilled_and_retained() {
+    const TOPIC: &str = "test/work_dir/spill";
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    let manager = create_test_manager(session.clone(), dir.path(), 24);
+
+    // A file in place of the entry directory makes every upload fail
+    let entry_name = topic_to_entry_name(TOPIC);
+    std::fs::create_dir_all(dir.path().join("data")).unwrap();
+    std::fs::write(dir.path().join("data").join(&entry_name), b"").unwrap();
+
+    let recording_id = start(&manager, TOPIC).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let publisher = session.declare_publisher(TOPIC).wait().unwrap();
+    publisher.put("frame-1").wait().unwrap();
+    // Upload retries back off for 100 + 200 + 400 ms
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    let spill_dir = dir
+        .path()
+        .join("work")
+        .join(&recording_id)
+        .join("spill")
+        .join(&entry_name);
+    assert_eq!(std::fs::read_dir(&spill_dir).unwrap().count(), 1);
+
+    manager.finish_recording(&recording_id).await;
+    assert!(spill_dir.is_dir(), "spilled records outlive the recording");
+    assert_eq!(manager.sweep_work_dirs().await, 0);
+
+    // Without retention a restart sweeps them
+    let manager = create_test_manager(session, dir.path(), 0);
+    assert_eq!(manager.sweep_work_dirs().await, 1);
+    assert!(!dir.path().join("work").join(&recording_id).exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_crash_leftovers_are_swept() {
+    let dir = TempDir::new().unwrap();
+    let session = Arc::new(zenoh::open(Config::default()).wait().unwrap());
+    std::fs::create_dir_all(dir.path().join("work/crashed-recording")).unwrap();
+
+    let manager = create_test_manager(session, dir.path(), 24);
+    let active = start(&manager, "test/work_dir/active").await;
+
+    assert_eq!(manager.sweep_work_dirs().await, 1);
+    assert!(!dir.path().join("work/crashed-recording").exists());
+    assert!(dir.path().join("work").join(&active).is_dir());
+
+    manager.cancel_recording(&active).await;
+}